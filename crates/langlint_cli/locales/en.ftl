@@ -0,0 +1,35 @@
+# Langlint's own messages, in English. Every other catalog falls back to
+# these for messages it does not have.
+
+summary = Summary:
+dry-run-mode = DRY RUN MODE
+files-processed = Files processed: { $count }
+files-translated = Files translated: { $count }
+files-scanned = Files scanned: { $count }
+units-translated = Total units translated: { $count }
+translatable-units = Total translatable units: { $count }
+average-confidence = Average confidence: { $value }
+failed-units = Failed units: { $count } ({ $percent }%)
+skipped-units = Skipped units (already in target language): { $count }
+error-count = Errors: { $count }
+limited-count = Skipped by file limits: { $count }
+quarantined-count = Quarantined: { $count }
+mapping-run = Mapping run: { $run } (undo with `langlint revert --run { $run }`)
+manifest = Manifest: { $path }
+review-units = Low-confidence units kept for review: { $count }
+translation-complete = Translation complete!
+files-written-to = Files written to: { $path }
+files-overwritten = Files overwritten (backups created with .backup extension)
+backups-created = Backups created with .backup extension
+no-backups = (No backups created)
+limited-title = Skipped by file limits
+errors-title = Errors
+errors-skipped = ({ $count } { $count ->
+        [one] file
+       *[other] files
+    } skipped, rerun with --strict to stop at the first):
+quarantined-title = Quarantined
+quarantined-unchanged = ({ $count } { $count ->
+        [one] file
+       *[other] files
+    } left unchanged, rerun with --force <file> to write them anyway):
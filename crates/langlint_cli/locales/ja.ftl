@@ -0,0 +1,28 @@
+# Langlint 自身のメッセージ（日本語）
+
+summary = 概要:
+dry-run-mode = ドライランモード
+files-processed = 処理したファイル: { $count }
+files-translated = 翻訳したファイル: { $count }
+files-scanned = スキャンしたファイル: { $count }
+units-translated = 翻訳したユニットの合計: { $count }
+translatable-units = 翻訳可能なユニットの合計: { $count }
+average-confidence = 平均信頼度: { $value }
+failed-units = 失敗したユニット: { $count }（{ $percent }%）
+skipped-units = スキップしたユニット（すでに翻訳先の言語）: { $count }
+error-count = エラー: { $count }
+limited-count = ファイル制限によりスキップ: { $count }
+quarantined-count = 隔離: { $count }
+mapping-run = マッピング実行: { $run }（`langlint revert --run { $run }` で元に戻せます）
+manifest = マニフェスト: { $path }
+review-units = レビュー待ちの低信頼度ユニット: { $count }
+translation-complete = 翻訳が完了しました！
+files-written-to = 書き込み先: { $path }
+files-overwritten = ファイルを上書きしました（.backup 拡張子でバックアップを作成しました）
+backups-created = .backup 拡張子でバックアップを作成しました
+no-backups = （バックアップは作成していません）
+limited-title = ファイル制限によりスキップ
+errors-title = エラー
+errors-skipped = （{ $count } 件のファイルをスキップしました。最初のエラーで止めるには --strict を付けて再実行してください）:
+quarantined-title = 隔離
+quarantined-unchanged = （{ $count } 件のファイルは変更していません。強制的に書き込むには --force <ファイル> を付けて再実行してください）:
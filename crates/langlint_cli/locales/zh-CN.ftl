@@ -0,0 +1,28 @@
+# Langlint 自身的消息（简体中文）
+
+summary = 汇总：
+dry-run-mode = 试运行模式
+files-processed = 已处理文件：{ $count }
+files-translated = 已翻译文件：{ $count }
+files-scanned = 已扫描文件：{ $count }
+units-translated = 已翻译单元总数：{ $count }
+translatable-units = 可翻译单元总数：{ $count }
+average-confidence = 平均置信度：{ $value }
+failed-units = 失败单元：{ $count }（{ $percent }%）
+skipped-units = 跳过的单元（已是目标语言）：{ $count }
+error-count = 错误：{ $count }
+limited-count = 因文件限制跳过：{ $count }
+quarantined-count = 已隔离：{ $count }
+mapping-run = 映射运行：{ $run }（可用 `langlint revert --run { $run }` 撤销）
+manifest = 清单：{ $path }
+review-units = 待审阅的低置信度单元：{ $count }
+translation-complete = 翻译完成！
+files-written-to = 文件已写入：{ $path }
+files-overwritten = 文件已覆盖（已创建 .backup 备份）
+backups-created = 已创建 .backup 备份
+no-backups = （未创建备份）
+limited-title = 因文件限制跳过
+errors-title = 错误
+errors-skipped = （跳过了 { $count } 个文件，使用 --strict 重新运行可在首个错误处停止）：
+quarantined-title = 已隔离
+quarantined-unchanged = （{ $count } 个文件未改动，使用 --force <文件> 重新运行可强制写入）：
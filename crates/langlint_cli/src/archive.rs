@@ -0,0 +1,236 @@
+//! In-memory reading and writing of `.zip` and `.tar.gz` inputs
+
+use anyhow::{bail, Context, Result};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::{self, File};
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+
+/// Largest total uncompressed size of the entries read from an archive
+pub const MAX_UNPACKED_SIZE: u64 = 512 * 1024 * 1024;
+
+/// Supported archive formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    TarGz,
+}
+
+impl ArchiveKind {
+    /// Detect the archive format from a file name
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else {
+            None
+        }
+    }
+}
+
+/// A regular file stored in an archive
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveEntry {
+    /// Path inside the archive
+    pub name: String,
+    pub data: Vec<u8>,
+    /// Unix permission bits, if the archive records them
+    pub mode: Option<u32>,
+}
+
+impl ArchiveEntry {
+    /// Entry contents as text, if they are valid UTF-8
+    pub fn text(&self) -> Option<&str> {
+        std::str::from_utf8(&self.data).ok()
+    }
+}
+
+/// Read all regular file entries of an archive into memory
+///
+/// Fails once the entries add up to more than [`MAX_UNPACKED_SIZE`]; sizes
+/// declared in the archive are not trusted.
+pub fn read_archive(path: &Path) -> Result<Vec<ArchiveEntry>> {
+    let kind = ArchiveKind::from_path(path)
+        .with_context(|| format!("Unsupported archive format: {}", path.display()))?;
+    let bytes =
+        fs::read(path).with_context(|| format!("Failed to read archive: {}", path.display()))?;
+
+    match kind {
+        ArchiveKind::Zip => read_zip(&bytes, MAX_UNPACKED_SIZE),
+        ArchiveKind::TarGz => read_tar_gz(&bytes, MAX_UNPACKED_SIZE),
+    }
+    .with_context(|| format!("Failed to read archive: {}", path.display()))
+}
+
+/// Write entries to a new archive, in the format implied by the file name
+pub fn write_archive(path: &Path, entries: &[ArchiveEntry]) -> Result<()> {
+    let kind = ArchiveKind::from_path(path)
+        .with_context(|| format!("Unsupported archive format: {}", path.display()))?;
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create archive: {}", path.display()))?;
+
+    match kind {
+        ArchiveKind::Zip => write_zip(file, entries),
+        ArchiveKind::TarGz => write_tar_gz(file, entries),
+    }
+    .with_context(|| format!("Failed to write archive: {}", path.display()))
+}
+
+/// Read an entry, taking its size off the bytes `remaining` of `limit`
+fn read_limited(reader: impl Read, remaining: &mut u64, limit: u64) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    reader.take(*remaining + 1).read_to_end(&mut data)?;
+    if data.len() as u64 > *remaining {
+        bail!("Archive unpacks to more than {} bytes", limit);
+    }
+    *remaining -= data.len() as u64;
+    Ok(data)
+}
+
+fn read_zip(bytes: &[u8], limit: u64) -> Result<Vec<ArchiveEntry>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+    let mut entries = Vec::new();
+    let mut remaining = limit;
+
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        if !file.is_file() {
+            continue;
+        }
+        let name = file.name().to_string();
+        let mode = file.unix_mode();
+        let data = read_limited(&mut file, &mut remaining, limit)?;
+        entries.push(ArchiveEntry { name, data, mode });
+    }
+
+    Ok(entries)
+}
+
+fn write_zip(file: File, entries: &[ArchiveEntry]) -> Result<()> {
+    let mut writer = zip::ZipWriter::new(file);
+
+    for entry in entries {
+        let mut options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        if let Some(mode) = entry.mode {
+            options = options.unix_permissions(mode);
+        }
+        writer.start_file(entry.name.as_str(), options)?;
+        writer.write_all(&entry.data)?;
+    }
+
+    writer.finish()?;
+    Ok(())
+}
+
+fn read_tar_gz(bytes: &[u8], limit: u64) -> Result<Vec<ArchiveEntry>> {
+    let mut archive = tar::Archive::new(GzDecoder::new(bytes));
+    let mut entries = Vec::new();
+    let mut remaining = limit;
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry.path()?.to_string_lossy().into_owned();
+        let mode = entry.header().mode().ok();
+        let data = read_limited(&mut entry, &mut remaining, limit)?;
+        entries.push(ArchiveEntry { name, data, mode });
+    }
+
+    Ok(entries)
+}
+
+fn write_tar_gz(file: File, entries: &[ArchiveEntry]) -> Result<()> {
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+    for entry in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(entry.data.len() as u64);
+        header.set_mode(entry.mode.unwrap_or(0o644));
+        header.set_cksum();
+        builder.append_data(&mut header, &entry.name, entry.data.as_slice())?;
+    }
+
+    builder.into_inner()?.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn sample_entries() -> Vec<ArchiveEntry> {
+        vec![
+            ArchiveEntry {
+                name: "src/app.py".to_string(),
+                data: "# 你好\n".as_bytes().to_vec(),
+                mode: Some(0o644),
+            },
+            ArchiveEntry {
+                name: "logo.png".to_string(),
+                data: vec![0x89, 0x50, 0xff, 0x00],
+                mode: Some(0o600),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_archive_kind() {
+        assert_eq!(
+            ArchiveKind::from_path(Path::new("a/hw.ZIP")),
+            Some(ArchiveKind::Zip)
+        );
+        assert_eq!(
+            ArchiveKind::from_path(Path::new("drop.tar.gz")),
+            Some(ArchiveKind::TarGz)
+        );
+        assert_eq!(
+            ArchiveKind::from_path(Path::new("drop.tgz")),
+            Some(ArchiveKind::TarGz)
+        );
+        assert_eq!(ArchiveKind::from_path(Path::new("app.py")), None);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let dir = TempDir::new().unwrap();
+        for name in ["out.zip", "out.tar.gz"] {
+            let path = dir.path().join(name);
+            write_archive(&path, &sample_entries()).unwrap();
+
+            let entries = read_archive(&path).unwrap();
+            assert_eq!(entries.len(), 2, "{}", name);
+            assert_eq!(entries[0].name, "src/app.py");
+            assert_eq!(entries[0].text(), Some("# 你好\n"));
+            assert_eq!(entries[1].data, vec![0x89, 0x50, 0xff, 0x00]);
+            assert_eq!(entries[1].text(), None);
+            assert_eq!(entries[1].mode.map(|m| m & 0o777), Some(0o600));
+        }
+    }
+
+    #[test]
+    fn test_unpacked_size_limit() {
+        let dir = TempDir::new().unwrap();
+        for name in ["big.zip", "big.tar.gz"] {
+            let path = dir.path().join(name);
+            write_archive(&path, &sample_entries()).unwrap();
+            let bytes = fs::read(&path).unwrap();
+            let read = match ArchiveKind::from_path(&path).unwrap() {
+                ArchiveKind::Zip => read_zip,
+                ArchiveKind::TarGz => read_tar_gz,
+            };
+
+            // "# 你好\n" is 9 bytes, the image 4
+            assert_eq!(read(&bytes, 13).unwrap().len(), 2, "{}", name);
+            let error = read(&bytes, 12).unwrap_err();
+            assert!(error.to_string().contains("more than"), "{}", name);
+        }
+    }
+}
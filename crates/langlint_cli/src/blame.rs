@@ -0,0 +1,248 @@
+//! Line history from `git blame`
+//!
+//! Used to select units by when (and by whom) their lines were last
+//! modified. Lines that are not committed yet, including every line of an
+//! untracked file, count as modified now.
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Last modification of a single line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineBlame {
+    pub commit: String,
+    pub author: String,
+    /// Author email without angle brackets
+    pub author_mail: String,
+    /// Author time in Unix seconds
+    pub time: i64,
+}
+
+/// Blame every line of a file (index 0 is line 1)
+///
+/// Untracked files inside a work tree return no lines; files outside a git
+/// repository are an error.
+pub fn blame_file(path: &Path) -> Result<Vec<LineBlame>> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let name = path.file_name().context("Cannot blame a directory")?;
+
+    let output = Command::new("git")
+        .args(["blame", "--porcelain", "--"])
+        .arg(name)
+        .current_dir(dir)
+        .output()
+        .context("Failed to run git (is it installed?)")?;
+
+    if output.status.success() {
+        return Ok(parse_porcelain(
+            &String::from_utf8_lossy(&output.stdout),
+            now(),
+        ));
+    }
+
+    let in_work_tree = Command::new("git")
+        .args(["rev-parse", "--is-inside-work-tree"])
+        .current_dir(dir)
+        .output()
+        .is_ok_and(|o| o.status.success());
+    if in_work_tree {
+        return Ok(Vec::new());
+    }
+
+    anyhow::bail!(
+        "Failed to blame {}: {}",
+        path.display(),
+        String::from_utf8_lossy(&output.stderr).trim()
+    )
+}
+
+/// Parse `git blame --porcelain` output
+///
+/// Commit details are only printed for the first line of each commit, so
+/// they are remembered by hash. Uncommitted lines (all-zero hash) get the
+/// time `now`.
+fn parse_porcelain(output: &str, now: i64) -> Vec<LineBlame> {
+    let mut commits: HashMap<String, LineBlame> = HashMap::new();
+    let mut lines = Vec::new();
+    let mut current: Option<LineBlame> = None;
+
+    for line in output.lines() {
+        if line.starts_with('\t') {
+            // Line content ends the entry
+            if let Some(entry) = current.take() {
+                let entry = commits.entry(entry.commit.clone()).or_insert(entry).clone();
+                lines.push(entry);
+            }
+        } else if let Some(current) = current.as_mut() {
+            if let Some(author) = line.strip_prefix("author ") {
+                current.author = author.to_string();
+            } else if let Some(mail) = line.strip_prefix("author-mail ") {
+                current.author_mail = mail.trim_matches(['<', '>']).to_string();
+            } else if let Some(time) = line.strip_prefix("author-time ") {
+                current.time = time.parse().unwrap_or(now);
+            }
+        } else {
+            let commit = line.split(' ').next().unwrap_or_default().to_string();
+            current = Some(commits.get(&commit).cloned().unwrap_or(LineBlame {
+                commit,
+                author: String::new(),
+                author_mail: String::new(),
+                time: now,
+            }));
+        }
+    }
+
+    // Uncommitted lines report the file mtime
+    for line in &mut lines {
+        if line.commit.bytes().all(|b| b == b'0') {
+            line.time = now;
+        }
+    }
+
+    lines
+}
+
+/// Check if a line was written by an author matching a pattern
+///
+/// The pattern is matched case-insensitively against `Name <email>`: as a
+/// glob if it contains `*`, `?` or `[` (e.g. `*@platform.example.com>`),
+/// otherwise as a substring.
+pub fn author_matches(line: &LineBlame, pattern: &str) -> bool {
+    let author = format!("{} <{}>", line.author, line.author_mail);
+    if pattern.contains(['*', '?', '[']) {
+        globset::GlobBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .is_ok_and(|glob| glob.compile_matcher().is_match(&author))
+    } else {
+        author.to_lowercase().contains(&pattern.to_lowercase())
+    }
+}
+
+/// Parse a `YYYY-MM-DD` date as Unix seconds at midnight UTC
+pub fn parse_date(text: &str) -> Result<i64> {
+    let invalid = || format!("Invalid date: {}. Use YYYY-MM-DD", text);
+
+    let mut parts = text.trim().splitn(3, '-');
+    let mut next = |range: std::ops::RangeInclusive<i64>| -> Result<i64> {
+        parts
+            .next()
+            .and_then(|part| part.parse::<i64>().ok())
+            .filter(|value| range.contains(value))
+            .with_context(invalid)
+    };
+    let year = next(1970..=9999)?;
+    let month = next(1..=12)?;
+    let day = next(1..=31)?;
+
+    Ok(days_from_civil(year, month, day) * 86_400)
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Format Unix seconds as a `YYYY-MM-DD` date (UTC)
+pub fn format_date(time: i64) -> String {
+    let days = time.div_euclid(86_400) + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PORCELAIN: &str = "\
+1111111111111111111111111111111111111111 1 1 2
+author Alice
+author-mail <alice@example.com>
+author-time 1600000000
+author-tz +0000
+summary init
+filename app.py
+\t# 你好
+1111111111111111111111111111111111111111 2 2
+\tx = 1
+0000000000000000000000000000000000000000 3 3 1
+author Not Committed Yet
+author-mail <not.committed.yet>
+author-time 1700000000
+filename app.py
+\t# 新的
+";
+
+    #[test]
+    fn test_parse_porcelain() {
+        let lines = parse_porcelain(PORCELAIN, 1_800_000_000);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].author, "Alice");
+        assert_eq!(lines[0].author_mail, "alice@example.com");
+        assert_eq!(lines[0].time, 1_600_000_000);
+        assert_eq!(lines[1], lines[0]);
+        assert_eq!(lines[2].time, 1_800_000_000);
+    }
+
+    #[test]
+    fn test_author_matches() {
+        let line = LineBlame {
+            commit: "1".repeat(40),
+            author: "Alice Wang".to_string(),
+            author_mail: "alice@platform.example.com".to_string(),
+            time: 0,
+        };
+        assert!(author_matches(&line, "alice"));
+        assert!(author_matches(&line, "ALICE@platform"));
+        assert!(author_matches(&line, "*@platform.example.com>"));
+        assert!(!author_matches(&line, "bob"));
+        assert!(!author_matches(&line, "*@web.example.com>"));
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("1970-01-01").unwrap(), 0);
+        assert_eq!(parse_date("2000-03-01").unwrap(), 951_868_800);
+        assert_eq!(parse_date("2024-02-29").unwrap(), 1_709_164_800);
+        assert!(parse_date("2024-13-01").is_err());
+        assert!(parse_date("last week").is_err());
+    }
+
+    #[test]
+    fn test_format_date() {
+        for date in ["1970-01-01", "2000-02-29", "2024-12-31", "2026-03-01"] {
+            assert_eq!(format_date(parse_date(date).unwrap() + 3600), date);
+        }
+    }
+}
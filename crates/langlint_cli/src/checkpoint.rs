@@ -0,0 +1,203 @@
+//! Checkpoints of runs stopped before all files were translated
+//!
+//! When a run stops early (its time limit or budget is used up) the files
+//! it did not finish are written to a [`CHECKPOINT_FILE`] next to the
+//! input, and `--resume` translates only those on the next run. Files
+//! translated before the stop are already written and are not touched
+//! again.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Checkpoint file name, in the input directory (or a file input's directory)
+pub const CHECKPOINT_FILE: &str = ".langlint-checkpoint.json";
+
+/// Files printed when reporting what remains
+const LISTED_FILES: usize = 10;
+
+/// Files left by a stopped run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Command that stopped (`translate`, `fix`)
+    pub command: String,
+    pub source: String,
+    pub target: String,
+    /// Why the run stopped
+    pub reason: String,
+    /// Number of files finished before the stop
+    pub completed: usize,
+    /// Files not translated, relative to the checkpoint's directory
+    pub remaining: Vec<PathBuf>,
+}
+
+impl Checkpoint {
+    /// Checkpoint of a run over `input` that stopped before `files[stopped_at]`
+    pub fn new(
+        command: &str,
+        source: &str,
+        target: &str,
+        reason: &anyhow::Error,
+        input: &Path,
+        files: &[PathBuf],
+        stopped_at: usize,
+    ) -> Self {
+        let root = root(input);
+        Self {
+            command: command.to_string(),
+            source: source.to_string(),
+            target: target.to_string(),
+            reason: format!("{:#}", reason),
+            completed: stopped_at,
+            remaining: files[stopped_at..]
+                .iter()
+                .map(|file| relative(&root, file))
+                .collect(),
+        }
+    }
+
+    /// Path of the checkpoint for a run over `input`
+    pub fn path_for(input: &Path) -> PathBuf {
+        root(input).join(CHECKPOINT_FILE)
+    }
+
+    /// Load the checkpoint for a run over `input`, if there is one
+    pub fn load(input: &Path) -> Result<Option<Self>> {
+        let path = Self::path_for(input);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read checkpoint: {}", path.display()))?;
+        let checkpoint = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid checkpoint: {}", path.display()))?;
+        Ok(Some(checkpoint))
+    }
+
+    /// Write the checkpoint for a run over `input`
+    pub fn save(&self, input: &Path) -> Result<PathBuf> {
+        let path = Self::path_for(input);
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(&path, content + "\n")
+            .with_context(|| format!("Failed to write checkpoint: {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// Delete the checkpoint for a run over `input` after it completed
+    pub fn remove(input: &Path) -> Result<()> {
+        let path = Self::path_for(input);
+        if path.exists() {
+            fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove checkpoint: {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Keep the files of `files` this checkpoint left untranslated
+    pub fn resume(
+        &self,
+        input: &Path,
+        command: &str,
+        target: &str,
+        files: Vec<PathBuf>,
+    ) -> Result<Vec<PathBuf>> {
+        if self.command != command || self.target != target {
+            anyhow::bail!(
+                "Checkpoint {} is for `{}` to '{}', not `{}` to '{}'",
+                Self::path_for(input).display(),
+                self.command,
+                self.target,
+                command,
+                target
+            );
+        }
+        let root = root(input);
+        let remaining: HashSet<&PathBuf> = self.remaining.iter().collect();
+        Ok(files
+            .into_iter()
+            .filter(|file| remaining.contains(&relative(&root, file)))
+            .collect())
+    }
+
+    /// Print the files left and how to resume
+    pub fn print(&self, input: &Path, saved: &Path) {
+        println!(
+            "\n{} Stopped after {} files, {} left untranslated:",
+            "⏱".yellow(),
+            self.completed,
+            self.remaining.len()
+        );
+        for file in self.remaining.iter().take(LISTED_FILES) {
+            println!("  {}", file.display());
+        }
+        if self.remaining.len() > LISTED_FILES {
+            println!("  ... and {} more", self.remaining.len() - LISTED_FILES);
+        }
+        println!(
+            "  Checkpoint: {} (continue with `langlint {} {} --resume`)",
+            saved.display(),
+            self.command,
+            input.display()
+        );
+    }
+}
+
+/// Directory the checkpoint of a run over `input` lives in
+fn root(input: &Path) -> PathBuf {
+    if input.is_dir() {
+        input.to_path_buf()
+    } else {
+        input
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .map_or_else(|| PathBuf::from("."), Path::to_path_buf)
+    }
+}
+
+/// `file` relative to `root` when it is inside it
+fn relative(root: &Path, file: &Path) -> PathBuf {
+    file.strip_prefix(root).unwrap_or(file).to_path_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_checkpoint_resume() {
+        let dir = TempDir::new().unwrap();
+        let files: Vec<PathBuf> = ["a.py", "b.py", "sub/c.py"]
+            .iter()
+            .map(|name| dir.path().join(name))
+            .collect();
+        let reason = anyhow::anyhow!("the time limit is up");
+
+        assert_eq!(Checkpoint::load(dir.path()).unwrap(), None);
+        let checkpoint = Checkpoint::new("fix", "zh", "en", &reason, dir.path(), &files, 1);
+        assert_eq!(
+            checkpoint.remaining,
+            vec![PathBuf::from("b.py"), PathBuf::from("sub/c.py")]
+        );
+        checkpoint.save(dir.path()).unwrap();
+
+        let loaded = Checkpoint::load(dir.path()).unwrap().unwrap();
+        assert_eq!(loaded, checkpoint);
+        assert_eq!(
+            loaded
+                .resume(dir.path(), "fix", "en", files.clone())
+                .unwrap(),
+            files[1..].to_vec()
+        );
+        assert!(loaded
+            .resume(dir.path(), "translate", "en", files.clone())
+            .is_err());
+        assert!(loaded.resume(dir.path(), "fix", "ja", files).is_err());
+
+        Checkpoint::remove(dir.path()).unwrap();
+        assert_eq!(Checkpoint::load(dir.path()).unwrap(), None);
+    }
+}
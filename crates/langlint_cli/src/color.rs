@@ -0,0 +1,115 @@
+//! Colors and progress bars of the terminal output
+//!
+//! With `--color auto`, the default, output is colored only when stdout is
+//! a terminal, `NO_COLOR` is unset and `TERM` is not `dumb`, so reports
+//! piped to files and CI logs stay plain text (`CLICOLOR_FORCE` colors
+//! them anyway). `--color always` and `--color never` override all of
+//! that. Progress bars are drawn on a terminal only, and without colors
+//! when colors are off.
+
+use anyhow::Result;
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// When output is colored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Parse a `--color` value
+    pub fn parse(choice: &str) -> Result<Self> {
+        match choice.trim().to_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            other => anyhow::bail!(
+                "Unknown color choice '{}' (expected auto, always or never)",
+                other
+            ),
+        }
+    }
+
+    /// Whether to color, given the environment and whether stdout is a
+    /// terminal
+    fn enabled(self, env: impl Fn(&str) -> Option<String>, terminal: bool) -> bool {
+        let set = |name: &str| env(name).is_some_and(|value| !value.is_empty() && value != "0");
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            Self::Auto if set("NO_COLOR") => false,
+            Self::Auto if set("CLICOLOR_FORCE") => true,
+            Self::Auto => terminal && env("TERM").as_deref() != Some("dumb"),
+        }
+    }
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Turn colors on or off for the rest of the run
+pub fn configure(choice: ColorChoice) {
+    let enabled = choice.enabled(
+        |name| std::env::var(name).ok(),
+        std::io::stdout().is_terminal(),
+    );
+    #[cfg(windows)]
+    let enabled = enabled && colored::control::set_virtual_terminal(true).is_ok();
+    colored::control::set_override(enabled);
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Progress bar over `len` files, hidden unless stderr is a terminal
+pub fn progress_bar(len: u64) -> ProgressBar {
+    let pb = ProgressBar::with_draw_target(
+        Some(len),
+        if std::io::stderr().is_terminal() {
+            ProgressDrawTarget::stderr()
+        } else {
+            ProgressDrawTarget::hidden()
+        },
+    );
+    let template = if ENABLED.load(Ordering::Relaxed) {
+        "{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} {msg}"
+    } else {
+        "{spinner} [{bar:40}] {pos}/{len} {msg}"
+    };
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template(template)
+            .unwrap()
+            .progress_chars("=>-"),
+    );
+    pb
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_choice() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        assert_eq!(ColorChoice::parse("Never").unwrap(), ColorChoice::Never);
+        assert!(ColorChoice::parse("sometimes").is_err());
+
+        assert!(ColorChoice::Auto.enabled(env(&[]), true));
+        assert!(!ColorChoice::Auto.enabled(env(&[]), false));
+        assert!(!ColorChoice::Auto.enabled(env(&[("NO_COLOR", "1")]), true));
+        assert!(ColorChoice::Auto.enabled(env(&[("NO_COLOR", "")]), true));
+        assert!(!ColorChoice::Auto.enabled(env(&[("TERM", "dumb")]), true));
+        assert!(ColorChoice::Auto.enabled(env(&[("CLICOLOR_FORCE", "1")]), false));
+        assert!(ColorChoice::Always.enabled(env(&[("NO_COLOR", "1")]), false));
+        assert!(!ColorChoice::Never.enabled(env(&[]), true));
+    }
+}
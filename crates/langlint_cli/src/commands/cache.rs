@@ -0,0 +1,28 @@
+//! Cache command implementation - manage the persistent translation cache
+
+use anyhow::Result;
+use colored::Colorize;
+use langlint_core::{default_cache_dir, TranslationMemory};
+
+/// Execute `cache clear`
+pub async fn clear(verbose: bool) -> Result<()> {
+    let Some(dir) = default_cache_dir() else {
+        anyhow::bail!("No cache directory available (set LANGLINT_CACHE_DIR)");
+    };
+
+    if !dir.exists() {
+        println!("{} Translation cache is already empty", "✓".green());
+        return Ok(());
+    }
+
+    let memory = TranslationMemory::open(&dir)?;
+    let count = memory.len();
+    memory.clear()?;
+
+    println!("{} Cleared {} cached translation(s)", "✓".green(), count);
+    if verbose {
+        println!("  Cache: {}", dir.display());
+    }
+
+    Ok(())
+}
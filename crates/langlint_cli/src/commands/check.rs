@@ -0,0 +1,131 @@
+//! Check command implementation - evaluate the config's language policy
+
+use anyhow::Result;
+use colored::Colorize;
+use langlint_core::{PolicyRule, TranslatableUnit};
+use langlint_parsers::{generated, jsx, license, sinks};
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::scan;
+use crate::pipeline::FileErrors;
+use crate::policy::Policy;
+use crate::report::{self, Finding, Report, ReportContent};
+
+/// Execute the check command
+///
+/// Lists every unit and document breaking a `[[policy]]` rule, and fails
+/// when there is any.
+pub async fn execute(
+    path: &str,
+    include: Option<Vec<String>>,
+    exclude: Option<Vec<String>>,
+    rules: &[PolicyRule],
+    format: &str,
+    verbose: bool,
+) -> Result<()> {
+    if rules.is_empty() {
+        anyhow::bail!("No language policy configured (add [[policy]] tables to langlint.toml)");
+    }
+    let policy = Policy::new(rules)?;
+
+    let root = Path::new(path);
+    let mut errors = FileErrors::new(false);
+    let files = scan::collect_files(root, include.as_ref(), exclude.as_ref(), &mut errors)?;
+    if verbose {
+        println!("{} {} files found", "Total:".bold(), files.len());
+    }
+
+    let mut violations = Vec::new();
+    let mut languages = Vec::new();
+    for file_path in &files {
+        let results = match scan::scan_input(file_path).await {
+            Ok(results) => results,
+            Err(e) => {
+                errors.record(file_path, e)?;
+                continue;
+            }
+        };
+        for (result_path, mut result) in results {
+            if generated::is_generated(&result) {
+                continue;
+            }
+            result.units.retain(|unit| {
+                !license::is_license_header(unit)
+                    && !sinks::is_sink_string(unit)
+                    && !jsx::is_jsx_text(unit)
+            });
+            let relative = result_path
+                .strip_prefix(root)
+                .ok()
+                .filter(|relative| !relative.as_os_str().is_empty())
+                .unwrap_or(&result_path);
+            let relative = langlint_core::paths::to_slash(relative);
+            violations.extend(policy.check_units(&relative, &result.units));
+            if policy.checks_translations() {
+                languages.push((relative, dominant_language(&result.units)));
+            }
+        }
+    }
+    violations.extend(policy.check_translations(&languages));
+
+    let findings: Vec<Finding> = violations.iter().cloned().map(Finding::from).collect();
+    print!(
+        "{}",
+        report::render(
+            &Report {
+                content: ReportContent::Findings {
+                    key: "violations",
+                    findings: &findings,
+                    files: files.len(),
+                    success: "follow the language policy",
+                },
+                errors: errors.files(),
+                verbose,
+            },
+            format,
+        )?
+    );
+    errors.print();
+
+    if !violations.is_empty() {
+        anyhow::bail!("{} language policy violation(s)", violations.len());
+    }
+    Ok(())
+}
+
+/// Language most of a file's text is written in
+fn dominant_language(units: &[TranslatableUnit]) -> Option<String> {
+    let mut characters: HashMap<&str, usize> = HashMap::new();
+    for unit in units {
+        if let Some(language) = &unit.detected_language {
+            *characters.entry(language.as_str()).or_default() += unit.content.chars().count();
+        }
+    }
+    characters
+        .into_iter()
+        .max_by_key(|&(language, count)| (count, std::cmp::Reverse(language)))
+        .map(|(language, _)| language.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use langlint_core::UnitType;
+
+    #[test]
+    fn test_dominant_language() {
+        let unit = |content: &str, language: Option<&str>| {
+            let mut unit = TranslatableUnit::new(content.to_string(), UnitType::TextNode, 1, 1);
+            unit.detected_language = language.map(str::to_string);
+            unit
+        };
+        let units = [
+            unit("A long English paragraph about the project", Some("en")),
+            unit("中文标题", Some("zh-CN")),
+            unit("1.2.3", None),
+        ];
+        assert_eq!(dominant_language(&units).as_deref(), Some("en"));
+        assert_eq!(dominant_language(&units[2..]), None);
+    }
+}
@@ -0,0 +1,200 @@
+//! Commit-message command implementation - enforce the target language in
+//! commit messages (used as a git `commit-msg` hook)
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use langlint_core::types::detect_language;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fs;
+use std::sync::OnceLock;
+
+use crate::pipeline::{self, same_language};
+
+/// Line below which git puts the diff in `commit --verbose`
+const SCISSORS: &str = "------------------------ >8 ------------------------";
+
+/// Execute the commit-msg command
+///
+/// Blocks the commit when the message is in another language than
+/// `target`, or rewrites the message file in place when `rewrite` is set.
+#[allow(clippy::too_many_arguments)]
+pub async fn execute(
+    file: &str,
+    source: &str,
+    target: &str,
+    translator_name: &str,
+    routes: &BTreeMap<String, String>,
+    rewrite: bool,
+    _format: &str,
+    verbose: bool,
+) -> Result<()> {
+    let content = fs::read_to_string(file)
+        .with_context(|| format!("Failed to read commit message: {}", file))?;
+
+    let message = message_text(&content);
+    let Some(detected) = detect_language(&message) else {
+        if verbose {
+            println!("{} Commit message language not detected", "→".dimmed());
+        }
+        return Ok(());
+    };
+
+    if same_language(&detected, target) {
+        if verbose {
+            println!(
+                "{} Commit message is in the target language ({})",
+                "✓".green(),
+                target
+            );
+        }
+        return Ok(());
+    }
+
+    if !rewrite {
+        anyhow::bail!(
+            "Commit message appears to be in '{}' but the target language is '{}' \
+             (rewrite it, or use `langlint commit-msg --rewrite`)",
+            detected,
+            target
+        );
+    }
+
+    let translator = pipeline::build_translator(translator_name, routes)?;
+
+    // With `auto`, lines are translated from their own language
+    let lines = translatable_lines(&content);
+    let texts: Vec<String> = lines.iter().map(|&(_, text)| text.to_string()).collect();
+    let results =
+        pipeline::translate_by_language(&texts, source, target, translator.as_ref()).await?;
+    let source = if source == "auto" {
+        detected.as_str()
+    } else {
+        source
+    };
+
+    let mut rewritten: Vec<String> = content.lines().map(str::to_string).collect();
+    for ((index, _), result) in lines.into_iter().zip(results) {
+        rewritten[index] = result.translated_text;
+    }
+
+    let mut output = rewritten.join("\n");
+    if content.ends_with('\n') {
+        output.push('\n');
+    }
+    fs::write(file, output).with_context(|| format!("Failed to write commit message: {}", file))?;
+
+    println!(
+        "{} Commit message translated from {} to {}",
+        "✓".green(),
+        source,
+        target
+    );
+
+    Ok(())
+}
+
+/// Message lines git keeps: everything before the scissors line that is
+/// not a `#` comment
+fn message_lines(content: &str) -> impl Iterator<Item = (usize, &str)> {
+    content
+        .lines()
+        .enumerate()
+        .take_while(|(_, line)| !line.contains(SCISSORS))
+        .filter(|(_, line)| !line.starts_with('#'))
+}
+
+/// The commit message as git will record it, without trailers
+fn message_text(content: &str) -> String {
+    message_lines(content)
+        .filter(|(_, line)| !is_trailer(line))
+        .map(|(_, line)| line)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Lines to translate, with their line index
+fn translatable_lines(content: &str) -> Vec<(usize, &str)> {
+    message_lines(content)
+        .filter(|(_, line)| !line.trim().is_empty() && !is_trailer(line))
+        .collect()
+}
+
+/// Check if a line is a trailer such as `Signed-off-by: Name <email>`
+fn is_trailer(line: &str) -> bool {
+    static TRAILER_RE: OnceLock<Regex> = OnceLock::new();
+    let re = TRAILER_RE.get_or_init(|| {
+        Regex::new(r"^[A-Za-z][A-Za-z-]*-by: |^[A-Z][A-Za-z-]*: .*<[^>]+@").unwrap()
+    });
+    re.is_match(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::NamedTempFile;
+
+    const CHINESE_MESSAGE: &str = "修复登录页面在移动设备上的显示问题\n\n调整了按钮的布局和字体大小。\n\nSigned-off-by: Dev <dev@example.com>\n# Please enter the commit message\n";
+
+    async fn run(content: &str, rewrite: bool) -> (Result<()>, String) {
+        let file = NamedTempFile::new().unwrap();
+        fs::write(file.path(), content).unwrap();
+        let result = execute(
+            file.path().to_str().unwrap(),
+            "auto",
+            "en",
+            "mock",
+            &BTreeMap::new(),
+            rewrite,
+            "text",
+            false,
+        )
+        .await;
+        (result, fs::read_to_string(file.path()).unwrap())
+    }
+
+    #[test]
+    fn test_message_text_skips_comments_and_trailers() {
+        let content = format!("{}# {}\ndiff --git a/x b/x\n", CHINESE_MESSAGE, SCISSORS);
+        assert_eq!(
+            message_text(&content),
+            "修复登录页面在移动设备上的显示问题\n\n调整了按钮的布局和字体大小。\n"
+        );
+    }
+
+    #[test]
+    fn test_same_language() {
+        assert!(same_language("zh-CN", "zh"));
+        assert!(same_language("en", "EN"));
+        assert!(!same_language("zh-CN", "en"));
+    }
+
+    #[tokio::test]
+    async fn test_blocks_other_language() {
+        let (result, content) = run(CHINESE_MESSAGE, false).await;
+        assert!(result.is_err());
+        assert_eq!(content, CHINESE_MESSAGE);
+    }
+
+    #[tokio::test]
+    async fn test_accepts_target_language() {
+        let message = "Fix the layout of the login page on mobile devices\n";
+        let (result, content) = run(message, false).await;
+        assert!(result.is_ok());
+        assert_eq!(content, message);
+    }
+
+    #[tokio::test]
+    async fn test_rewrites_message() {
+        let (result, content) = run(CHINESE_MESSAGE, true).await;
+        assert!(result.is_ok());
+
+        let lines: Vec<&str> = content.lines().collect();
+        assert!(lines[0].starts_with("[EN]"));
+        assert_eq!(lines[1], "");
+        assert!(lines[2].starts_with("[EN]"));
+        assert_eq!(lines[4], "Signed-off-by: Dev <dev@example.com>");
+        assert_eq!(lines[5], "# Please enter the commit message");
+        assert!(content.ends_with('\n'));
+    }
+}
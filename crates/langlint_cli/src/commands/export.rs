@@ -0,0 +1,174 @@
+//! Export command implementation - write translatable units as XLIFF
+//!
+//! Each unit becomes a segment of the `<file>` of its source file, with an
+//! id made of its line and column (`12-5`, then `12-5.2` for a second unit
+//! at the same place) and a note naming the line and unit type. After
+//! translating in a CAT tool, `langlint import` writes the targets back.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use langlint_core::{encoding, paths, TranslatableUnit};
+use langlint_parsers::xliff::{write_xliff, XliffSegment, XliffVersion};
+use langlint_parsers::{generated, jsx, license, sinks};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use super::{fix, scan};
+use crate::archive::ArchiveKind;
+use crate::pipeline::{self, FileErrors};
+
+/// Execute the export command
+#[allow(clippy::too_many_arguments)]
+pub async fn execute(
+    path: &str,
+    include: Option<Vec<String>>,
+    exclude: Option<Vec<String>>,
+    source: &str,
+    target: &str,
+    output: Option<&str>,
+    format: &str,
+    verbose: bool,
+) -> Result<()> {
+    let version = match format {
+        "text" | "xliff" => XliffVersion::V1_2,
+        "xliff2" => XliffVersion::V2_0,
+        _ => anyhow::bail!(
+            "Unsupported export format '{}' (expected xliff or xliff2)",
+            format
+        ),
+    };
+
+    let root = Path::new(path);
+    let mut errors = FileErrors::new(false);
+    let files = scan::collect_files(root, include.as_ref(), exclude.as_ref(), &mut errors)?;
+
+    let mut segments = Vec::new();
+    let mut languages: HashMap<String, usize> = HashMap::new();
+    let mut exported_files = 0;
+    for file_path in &files {
+        // Entries cannot be imported back into an archive
+        if ArchiveKind::from_path(file_path).is_some() {
+            continue;
+        }
+        let path_str = paths::to_slash(file_path);
+        let Some(parser) = fix::get_parser_for_file(&path_str) else {
+            continue;
+        };
+        let result = encoding::read_file(file_path).and_then(|(content, _)| {
+            parser
+                .extract_units(&content, &path_str)
+                .with_context(|| format!("Failed to parse file: {}", path_str))
+        });
+        let result = match result {
+            Ok(result) => result,
+            Err(e) => {
+                errors.record(file_path, e)?;
+                continue;
+            }
+        };
+        if generated::is_generated(&result) {
+            continue;
+        }
+
+        let before = segments.len();
+        for (unit, id) in result.units.iter().zip(unit_ids(&result.units)) {
+            if license::is_license_header(unit)
+                || sinks::is_sink_string(unit)
+                || jsx::is_jsx_text(unit)
+            {
+                continue;
+            }
+            if let Some(language) = &unit.detected_language {
+                *languages.entry(language.clone()).or_default() += 1;
+            }
+            segments.push(XliffSegment {
+                file: path_str.clone(),
+                id,
+                source: unit.content.clone(),
+                target: None,
+                note: Some(format!(
+                    "line {}, {}",
+                    unit.line_number,
+                    format!("{:?}", unit.unit_type).to_lowercase()
+                )),
+            });
+        }
+        if segments.len() > before {
+            exported_files += 1;
+            if verbose {
+                println!(
+                    "{} {} ({} units)",
+                    "✓".green(),
+                    path_str,
+                    segments.len() - before
+                );
+            }
+        }
+    }
+
+    // XLIFF needs a source language; use the one most units are written in
+    let source = if pipeline::is_auto_source(source) {
+        languages
+            .into_iter()
+            .max_by_key(|(language, count)| (*count, std::cmp::Reverse(language.clone())))
+            .map_or_else(|| "und".to_string(), |(language, _)| language)
+    } else {
+        source.to_string()
+    };
+    let document = write_xliff(&segments, &source, target, version);
+
+    match output {
+        Some(output) => {
+            fs::write(output, &document)
+                .with_context(|| format!("Failed to write XLIFF: {}", output))?;
+            println!(
+                "{} Exported {} units of {} files to {}",
+                "✓".green(),
+                segments.len(),
+                exported_files,
+                output
+            );
+        }
+        None => print!("{}", document),
+    }
+    errors.print();
+    Ok(())
+}
+
+/// Segment ids of a file's units: line and column, numbered from the
+/// second unit at the same place
+pub(crate) fn unit_ids(units: &[TranslatableUnit]) -> Vec<String> {
+    let mut seen: HashMap<(u32, u32), usize> = HashMap::new();
+    units
+        .iter()
+        .map(|unit| {
+            let count = seen
+                .entry((unit.line_number, unit.column_number))
+                .or_default();
+            *count += 1;
+            match *count {
+                1 => format!("{}-{}", unit.line_number, unit.column_number),
+                n => format!("{}-{}.{}", unit.line_number, unit.column_number, n),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use langlint_core::UnitType;
+
+    #[test]
+    fn test_unit_ids() {
+        let unit = |line: u32, column: u32| {
+            TranslatableUnit::new("注释".to_string(), UnitType::Comment, line, column)
+        };
+        let units = [unit(1, 1), unit(3, 5), unit(3, 5), unit(4, 1), unit(3, 5)];
+        assert_eq!(
+            unit_ids(&units),
+            vec!["1-1", "3-5", "3-5.2", "4-1", "3-5.3"]
+        );
+    }
+}
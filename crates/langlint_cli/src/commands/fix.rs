@@ -2,13 +2,26 @@
 
 use anyhow::{Context, Result};
 use colored::Colorize;
-use indicatif::{ProgressBar, ProgressStyle};
-use langlint_core::Config;
-use langlint_parsers::{GenericCodeParser, NotebookParser, Parser, PythonParser};
-use langlint_translators::{GoogleTranslator, MockTranslator, Translator};
+use langlint_core::{encoding, paths, TranslationMap};
+use langlint_parsers::Parser;
+use langlint_translators::Translator;
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
+
+use crate::checkpoint::Checkpoint;
+use crate::color;
+use crate::directives;
+use crate::interrupt;
+use crate::limits;
+use crate::manifest::{self, RunManifest};
+use crate::overrides;
+use crate::pipeline::{
+    self, FileErrors, FileOptions, HistoryFilter, NotebookCells, QualityGate, QuarantineReport,
+    Quarantined, RunStats,
+};
+use crate::ui::tr;
+use crate::walk;
 
 /// Execute the fix command - translate files in-place with backup
 #[allow(clippy::too_many_arguments)]
@@ -17,21 +30,26 @@ pub async fn execute(
     source: &str,
     target: &str,
     translator_name: &str,
+    routes: &BTreeMap<String, String>,
     yes: bool,
-    no_backup: bool,
+    should_backup: bool,
+    gate: QualityGate,
+    history: HistoryFilter,
+    write_map: bool,
+    source_map: bool,
+    include_todos: bool,
+    include_strings: bool,
+    include_license_headers: bool,
+    include_generated: bool,
+    notebook_cells: NotebookCells,
+    force: Vec<String>,
+    output_encoding: Option<&str>,
+    strict: bool,
+    resume: bool,
+    mut manifest: Option<RunManifest>,
     _format: &str,
     verbose: bool,
 ) -> Result<()> {
-    // Load config to get backup preference (if not overridden by CLI)
-    let config = Config::find_and_load().unwrap_or_default();
-
-    // Command line --no-backup flag overrides config file
-    let should_backup = if no_backup {
-        false // Explicit --no-backup flag
-    } else {
-        config.backup // Use config file setting (default: true)
-    };
-
     if verbose {
         println!("{} {}", "Fixing (in-place translate):".bold().cyan(), path);
         println!("  Source language: {}", source);
@@ -44,20 +62,29 @@ pub async fn execute(
     }
 
     // Create translator
-    let translator: Box<dyn Translator> = match translator_name {
-        "mock" => Box::new(MockTranslator::new()),
-        "google" => Box::new(GoogleTranslator::new()?),
-        _ => anyhow::bail!("Unknown translator: {}", translator_name),
-    };
+    let translator = pipeline::build_translator(translator_name, routes)?;
 
     if verbose {
         println!("{} Translator created", "✓".green());
     }
+    if let Some(manifest) = &mut manifest {
+        manifest.start(path, source, target, translator.as_ref());
+    }
 
     let path_obj = Path::new(path);
 
     // Collect files to translate
-    let files = collect_files(path_obj)?;
+    let mut errors = FileErrors::new(strict);
+    let mut files = collect_files(path_obj, &mut errors)?;
+    if resume {
+        let checkpoint = Checkpoint::load(path_obj)?.with_context(|| {
+            format!(
+                "No checkpoint to resume: {}",
+                Checkpoint::path_for(path_obj).display()
+            )
+        })?;
+        files = checkpoint.resume(path_obj, "fix", target, files)?;
+    }
 
     if files.is_empty() {
         println!("{} No translatable files found", "!".yellow());
@@ -89,54 +116,90 @@ pub async fn execute(
     }
 
     // Setup progress bar
-    let pb = ProgressBar::new(files.len() as u64);
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template("{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} {msg}")
-            .unwrap()
-            .progress_chars("=>-"),
-    );
+    let pb = color::progress_bar(files.len() as u64);
 
     let mut translated_count = 0;
-    let mut error_count = 0;
-    let mut total_units = 0;
+    let mut stats = RunStats::default();
+    let options = FileOptions {
+        map_run: write_map.then(pipeline::run_timestamp),
+        source_map,
+        include_todos,
+        include_strings,
+        include_license_headers,
+        include_generated,
+        notebook_cells,
+        history,
+        output_encoding: pipeline::output_encoding(output_encoding)?,
+        force,
+    };
+    let mut quarantine = QuarantineReport::default();
+    let mut budget_stop = None;
 
-    for file_path in &files {
+    // Ctrl+C stops after the file being translated is dropped, not mid-write
+    interrupt::install();
+    for (index, file_path) in files.iter().enumerate() {
         let filename = file_path.file_name().unwrap().to_string_lossy();
         pb.set_message(format!("Translating {}", filename));
 
-        match translate_file(
+        let bytes_before = manifest::file_size(file_path);
+        match interrupt::or_interrupted(translate_file(
             file_path,
             source,
             target,
             translator.as_ref(),
             should_backup,
+            &options,
             verbose,
-        )
+        ))
         .await
         {
-            Ok(units) => {
-                if units > 0 {
+            Ok(file_stats) => {
+                let file_stats = file_stats.in_file(&file_path.display().to_string());
+                if let Some(manifest) = &mut manifest {
+                    manifest.record(file_path, file_path, bytes_before, &file_stats);
+                }
+                stats.merge(&file_stats);
+                if file_stats.units > 0 {
                     translated_count += 1;
-                    total_units += units;
                     if verbose {
                         pb.println(format!(
                             "{} {} ({} units)",
                             "✓".green(),
                             file_path.display(),
-                            units
+                            file_stats.units
                         ));
                     }
                 }
             }
+            Err(e) if pipeline::is_budget_exceeded(&e) || interrupt::is_interrupt(&e) => {
+                pb.println(format!(
+                    "{} Stopping at {}: {:#}",
+                    "✗".red(),
+                    file_path.display(),
+                    e
+                ));
+                budget_stop = Some((index, e));
+                break;
+            }
+            Err(e) if quarantine.record(file_path, &e) => {
+                pb.println(format!(
+                    "{} Quarantined {}: {}",
+                    "⚠".yellow(),
+                    file_path.display(),
+                    e
+                ));
+            }
             Err(e) => {
-                error_count += 1;
                 pb.println(format!(
-                    "{} Failed to translate {}: {}",
+                    "{} Failed to translate {}: {:#}",
                     "✗".red(),
                     file_path.display(),
                     e
                 ));
+                if let Err(e) = errors.record(file_path, e) {
+                    pb.abandon();
+                    return Err(e);
+                }
             }
         }
 
@@ -146,27 +209,108 @@ pub async fn execute(
     pb.finish_with_message("Done!");
 
     // Summary
-    println!("\n{}", "Summary:".bold().green());
-    println!("  Files processed: {}", files.len());
-    println!("  Files translated: {}", translated_count);
-    println!("  Total units translated: {}", total_units);
-    if error_count > 0 {
-        println!("  {} Errors: {}", "⚠".yellow(), error_count);
+    println!("\n{}", tr!("summary").bold().green());
+    // A stopped run reports the files it got through
+    let processed = budget_stop
+        .as_ref()
+        .map_or(files.len(), |(stopped_at, _)| *stopped_at);
+    println!("  {}", tr!("files-processed", count = processed));
+    println!("  {}", tr!("files-translated", count = translated_count));
+    println!("  {}", tr!("units-translated", count = stats.units));
+    if stats.units > 0 {
+        println!(
+            "  {}",
+            tr!(
+                "average-confidence",
+                value = format!("{:.2}", stats.average_confidence())
+            )
+        );
+        println!(
+            "  {}",
+            tr!(
+                "failed-units",
+                count = stats.failed,
+                percent = format!("{:.1}", stats.failure_rate() * 100.0)
+            )
+        );
+    }
+    if stats.skipped > 0 {
+        println!("  {}", tr!("skipped-units", count = stats.skipped));
+    }
+    if !errors.is_empty() {
+        println!(
+            "  {} {}",
+            "⚠".yellow(),
+            tr!("error-count", count = errors.len())
+        );
+    }
+    if !errors.limited().is_empty() {
+        println!("  {}", tr!("limited-count", count = errors.limited().len()));
+    }
+    if !quarantine.is_empty() {
+        println!(
+            "  {} {}",
+            "⚠".yellow(),
+            tr!("quarantined-count", count = quarantine.len())
+        );
+    }
+    if let Some(run) = options.map_run {
+        println!("  {}", tr!("mapping-run", run = run));
+    }
+    if let Some(manifest) = &manifest {
+        println!("  {}", tr!("manifest", path = manifest.path().display()));
+    }
+    if !stats.review.is_empty() {
+        println!(
+            "  {} {}",
+            "⚠".yellow(),
+            tr!("review-units", count = stats.review.len())
+        );
+    }
+    pipeline::print_cost_report();
+
+    stats.print_review();
+    quarantine.print();
+    errors.print();
+    if let Some(manifest) = manifest {
+        manifest.finish(budget_stop.is_none(), errors.len(), quarantine.len())?;
+    }
+    if let Some((stopped_at, e)) = budget_stop {
+        let checkpoint = Checkpoint::new("fix", source, target, &e, path_obj, &files, stopped_at);
+        let saved = checkpoint.save(path_obj)?;
+        checkpoint.print(path_obj, &saved);
+        let stop = if interrupt::is_interrupt(&e) {
+            "Interrupted"
+        } else {
+            "Budget exhausted"
+        };
+        return Err(e.context(format!(
+            "{}, {} files left untranslated",
+            stop,
+            files.len() - stopped_at
+        )));
+    }
+    if resume {
+        Checkpoint::remove(path_obj)?;
     }
 
     if should_backup {
         println!(
-            "\n{} Translation complete! Backups created with .backup extension",
-            "✓".green().bold()
+            "\n{} {} {}",
+            "✓".green().bold(),
+            tr!("translation-complete"),
+            tr!("backups-created")
         );
     } else {
         println!(
-            "\n{} Translation complete! (No backups created)",
-            "✓".green().bold()
+            "\n{} {} {}",
+            "✓".green().bold(),
+            tr!("translation-complete"),
+            tr!("no-backups")
         );
     }
 
-    Ok(())
+    gate.check(&stats)
 }
 
 /// Translate a single file in-place with backup
@@ -176,165 +320,162 @@ async fn translate_file(
     target: &str,
     translator: &dyn Translator,
     should_backup: bool,
+    options: &FileOptions,
     verbose: bool,
-) -> Result<usize> {
+) -> Result<RunStats> {
     // Read file
-    let content = fs::read_to_string(path)
-        .with_context(|| format!("Failed to read file: {}", path.display()))?;
+    let (content, input_encoding) = encoding::read_file(path)?;
 
     let path_str = path.to_string_lossy();
 
     // Parse file to extract translatable units
-    let parse_result = match get_parser_for_file(&path_str) {
-        Some(parser) => parser.extract_units(&content, &path_str)?,
-        None => return Ok(0), // Skip files without parser
+    let mut parse_result = match get_parser_for_file(&path_str) {
+        Some(parser) => parser
+            .extract_units(&content, &path_str)
+            .with_context(|| format!("Failed to parse file: {}", path_str))?,
+        None => return Ok(RunStats::default()), // Skip files without parser
     };
 
+    options.select_units(&mut parse_result);
+    options.history.select_units(path, &mut parse_result)?;
     let unit_count = parse_result.units.len();
+    limits::check_units(path, unit_count)?;
 
     if unit_count == 0 {
         if verbose {
             println!("  {} No translatable units", "→".dimmed());
         }
-        return Ok(0);
+        return Ok(RunStats::default());
     }
 
-    // Translate all units
-    let texts: Vec<String> = parse_result
+    // Translate all units, honouring `langlint:` directives
+    let pins = directives::pins(&parse_result.units, &directives::parse_file(&content)?);
+    let translated = pipeline::translate_pinned_units(
+        &parse_result,
+        Some(&path_str),
+        &pins,
+        source,
+        target,
+        translator,
+    )
+    .await?;
+    // Bilingual comments cut to their target half change without translating
+    let unchanged = translated
         .units
         .iter()
-        .map(|u| u.content.clone())
-        .collect();
-
-    let translations = translator.translate_batch(&texts, source, target).await?;
+        .zip(&parse_result.units)
+        .all(|(translated, original)| translated.content == original.content);
+    if translated.stats.units == 0 && unchanged {
+        if verbose {
+            println!("  {} Already in the target language", "→".dimmed());
+        }
+        return Ok(translated.stats);
+    }
 
-    // Create new units with translations
-    let mut translated_units = parse_result.units.clone();
-    for (i, trans) in translations.iter().enumerate() {
-        if i < translated_units.len() {
-            translated_units[i].content = trans.translated_text.clone();
+    // Reconstruct file with translations
+    let parser =
+        get_parser_for_file(&path_str).ok_or_else(|| anyhow::anyhow!("No parser available"))?;
+    let reconstructed = parser.reconstruct(&content, &translated.units, &path_str)?;
+
+    // Leave the file alone if the translations damaged it
+    let reasons = pipeline::verify_reconstruction(
+        &content,
+        &reconstructed,
+        &parse_result.units,
+        &translated.units,
+    );
+    if !reasons.is_empty() {
+        if !options.is_forced(&path_str) {
+            return Err(Quarantined { reasons }.into());
+        }
+        if verbose {
+            println!("  {} Forced despite: {}", "⚠".yellow(), reasons.join("; "));
         }
     }
 
     // Create backup (if enabled)
     if should_backup {
         let backup_path = format!("{}.backup", path.display());
-        fs::copy(path, &backup_path)
-            .with_context(|| format!("Failed to create backup: {}", backup_path))?;
+        fs::copy(
+            paths::long_path(path),
+            paths::long_path(Path::new(&backup_path)),
+        )
+        .with_context(|| format!("Failed to create backup: {}", backup_path))?;
 
         if verbose {
             println!("  {} Backup created: {}", "✓".green(), backup_path);
         }
     }
 
-    // Reconstruct file with translations
-    let parser =
-        get_parser_for_file(&path_str).ok_or_else(|| anyhow::anyhow!("No parser available"))?;
-    let reconstructed = parser.reconstruct(&content, &translated_units, &path_str)?;
-
     // Write back to original file
-    fs::write(path, reconstructed)
-        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    let output_encoding = options.output_encoding.unwrap_or(input_encoding);
+    encoding::write_file(path, &reconstructed, output_encoding)?;
+
+    if let Some(run) = options.map_run {
+        let map =
+            TranslationMap::from_units(&parse_result.units, &translated.units, source, target)
+                .with_run(run);
+        let map_path = TranslationMap::path_for(path);
+        map.save(&map_path)?;
+
+        if verbose {
+            println!("  {} Mapping written: {}", "✓".green(), map_path.display());
+        }
+    }
+    if options.source_map {
+        pipeline::write_source_map(path, &content, &reconstructed, verbose)?;
+    }
 
-    Ok(unit_count)
+    Ok(translated.stats)
 }
 
 /// Collect files to translate
-fn collect_files(path: &Path) -> Result<Vec<PathBuf>> {
+///
+/// Unreadable directory entries are recorded in `errors`.
+fn collect_files(path: &Path, errors: &mut FileErrors) -> Result<Vec<PathBuf>> {
     let mut files = Vec::new();
 
     if path.is_file() {
         if should_translate(path) {
-            files.push(path.to_path_buf());
+            match limits::check_size(path) {
+                Ok(()) => files.push(path.to_path_buf()),
+                Err(e) => errors.record(path, e)?,
+            }
         }
         return Ok(files);
     }
 
     // Walk directory
-    for entry in WalkDir::new(path)
-        .follow_links(true)
+    for entry in walk::walker(path)
         .into_iter()
-        .filter_entry(|e| {
-            let name = e.file_name().to_string_lossy();
-            !name.starts_with('.')
-                && name != "node_modules"
-                && name != "target"
-                && name != "__pycache__"
-                && name != "venv"
-                && name != ".venv"
-                && name != "build"
-                && name != "dist"
-        })
+        .filter_entry(walk::keep_entry)
     {
-        let entry = entry?;
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                errors.record_walk(e)?;
+                continue;
+            }
+        };
         if entry.file_type().is_file() && should_translate(entry.path()) {
-            files.push(entry.path().to_path_buf());
+            match limits::check_size(entry.path()) {
+                Ok(()) => files.push(entry.path().to_path_buf()),
+                Err(e) => errors.record(entry.path(), e)?,
+            }
         }
     }
 
     Ok(files)
 }
 
-/// Check if a file should be translated
+/// Check if a file should be translated: some parser handles it
 fn should_translate(path: &Path) -> bool {
-    if let Some(ext) = path.extension() {
-        let ext_str = ext.to_string_lossy();
-        matches!(
-            ext_str.as_ref(),
-            "py" | "js"
-                | "ts"
-                | "jsx"
-                | "tsx"
-                | "rs"
-                | "go"
-                | "java"
-                | "c"
-                | "cpp"
-                | "h"
-                | "hpp"
-                | "cs"
-                | "php"
-                | "rb"
-                | "sh"
-                | "bash"
-                | "sql"
-                | "r"
-                | "R"
-                | "m"
-                | "scala"
-                | "kt"
-                | "swift"
-                | "dart"
-                | "lua"
-                | "vim"
-                | "ipynb"
-        )
-    } else {
-        false
-    }
+    overrides::parser_for(path, None).is_some()
 }
 
 /// Get appropriate parser for a file
-fn get_parser_for_file(path: &str) -> Option<Box<dyn Parser>> {
-    // Try Python parser
-    let python_parser = PythonParser::new();
-    if python_parser.can_parse(path, None) {
-        return Some(Box::new(python_parser));
-    }
-
-    // Try Notebook parser
-    let notebook_parser = NotebookParser::new();
-    if notebook_parser.can_parse(path, None) {
-        return Some(Box::new(notebook_parser));
-    }
-
-    // Try generic code parser
-    let generic_parser = GenericCodeParser::new();
-    if generic_parser.can_parse(path, None) {
-        return Some(Box::new(generic_parser));
-    }
-
-    None
+pub(crate) fn get_parser_for_file(path: &str) -> Option<Box<dyn Parser>> {
+    overrides::parser_for(Path::new(path), None)
 }
 
 #[cfg(test)]
@@ -348,7 +489,20 @@ mod tests {
         assert!(should_translate(Path::new("test.rs")));
         assert!(should_translate(Path::new("test.ipynb")));
         assert!(!should_translate(Path::new("test.txt")));
-        assert!(!should_translate(Path::new("README.md")));
+        assert!(should_translate(Path::new("README.md")));
+        assert!(should_translate(Path::new("docs/index.rst")));
+        assert!(should_translate(Path::new("templates/base.html")));
+        assert!(should_translate(Path::new("paper/main.tex")));
+        assert!(should_translate(Path::new("locale/fr/LC_MESSAGES/app.po")));
+        assert!(should_translate(Path::new("src/locales/zh-CN.json")));
+        assert!(should_translate(Path::new(
+            "app/src/main/res/values/strings.xml"
+        )));
+        assert!(should_translate(Path::new(
+            "Base.lproj/Localizable.strings"
+        )));
+        assert!(should_translate(Path::new("l10n/app.zh.xlf")));
+        assert!(!should_translate(Path::new("package.json")));
     }
 
     #[test]
@@ -0,0 +1,133 @@
+//! Install-hooks command implementation - set up langlint git hooks
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Marker identifying hook scripts written by langlint
+const HOOK_MARKER: &str = "# Installed by langlint";
+
+/// Execute the install-hooks command
+pub async fn execute(repo: &str, rewrite: bool, force: bool, verbose: bool) -> Result<()> {
+    let hooks_dir = hooks_dir(Path::new(repo))?;
+    let hook_path = hooks_dir.join("commit-msg");
+
+    if hook_path.exists() && !force {
+        let existing = fs::read_to_string(&hook_path).unwrap_or_default();
+        if !existing.contains(HOOK_MARKER) {
+            anyhow::bail!(
+                "A commit-msg hook already exists at {} (use --force to replace it)",
+                hook_path.display()
+            );
+        }
+    }
+
+    fs::create_dir_all(&hooks_dir)
+        .with_context(|| format!("Failed to create directory: {}", hooks_dir.display()))?;
+    fs::write(&hook_path, commit_msg_hook(rewrite))
+        .with_context(|| format!("Failed to write hook: {}", hook_path.display()))?;
+    make_executable(&hook_path)?;
+
+    println!(
+        "{} Installed commit-msg hook: {}",
+        "✓".green(),
+        hook_path.display()
+    );
+    if verbose {
+        println!(
+            "  Mode: {}",
+            if rewrite {
+                "rewrite messages"
+            } else {
+                "block messages"
+            }
+        );
+    }
+
+    Ok(())
+}
+
+/// Script for the commit-msg hook
+fn commit_msg_hook(rewrite: bool) -> String {
+    format!(
+        "#!/bin/sh\n{}\nexec langlint commit-msg{} \"$1\"\n",
+        HOOK_MARKER,
+        if rewrite { " --rewrite" } else { "" }
+    )
+}
+
+/// Locate the hooks directory of a git repository
+fn hooks_dir(repo: &Path) -> Result<PathBuf> {
+    let git = repo.join(".git");
+    if git.is_dir() {
+        return Ok(git.join("hooks"));
+    }
+
+    // Worktrees and submodules use a `.git` file pointing at the git dir
+    if git.is_file() {
+        let content = fs::read_to_string(&git)
+            .with_context(|| format!("Failed to read: {}", git.display()))?;
+        if let Some(dir) = content.trim().strip_prefix("gitdir:") {
+            return Ok(repo.join(dir.trim()).join("hooks"));
+        }
+    }
+
+    anyhow::bail!("Not a git repository: {}", repo.display())
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+        .with_context(|| format!("Failed to make executable: {}", path.display()))
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_install_commit_msg_hook() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join(".git")).unwrap();
+        let repo = dir.path().to_str().unwrap();
+
+        execute(repo, true, false, false).await.unwrap();
+        let hook = fs::read_to_string(dir.path().join(".git/hooks/commit-msg")).unwrap();
+        assert!(hook.contains("langlint commit-msg --rewrite \"$1\""));
+
+        // Reinstalling over our own hook is allowed
+        execute(repo, false, false, false).await.unwrap();
+        let hook = fs::read_to_string(dir.path().join(".git/hooks/commit-msg")).unwrap();
+        assert!(hook.contains("langlint commit-msg \"$1\""));
+    }
+
+    #[tokio::test]
+    async fn test_keeps_foreign_hook_without_force() {
+        let dir = TempDir::new().unwrap();
+        let hooks = dir.path().join(".git/hooks");
+        fs::create_dir_all(&hooks).unwrap();
+        fs::write(hooks.join("commit-msg"), "#!/bin/sh\nexit 0\n").unwrap();
+        let repo = dir.path().to_str().unwrap();
+
+        assert!(execute(repo, false, false, false).await.is_err());
+        execute(repo, false, true, false).await.unwrap();
+        let hook = fs::read_to_string(hooks.join("commit-msg")).unwrap();
+        assert!(hook.contains(HOOK_MARKER));
+    }
+
+    #[tokio::test]
+    async fn test_requires_git_repository() {
+        let dir = TempDir::new().unwrap();
+        assert!(execute(dir.path().to_str().unwrap(), false, false, false)
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,306 @@
+//! i18n command implementation - replace user-facing literals with lookup calls
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use langlint_parsers::i18n::KEY_PLACEHOLDER;
+use langlint_parsers::{I18nExtractor, SourceKind};
+use langlint_translators::Translator;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::pipeline;
+use crate::ui::tr;
+use crate::walk;
+
+/// Key → message resource for one locale
+type Messages = BTreeMap<String, String>;
+
+/// Execute the i18n command
+#[allow(clippy::too_many_arguments)]
+pub async fn execute(
+    path: &str,
+    template: Option<&str>,
+    source: &str,
+    targets: &[String],
+    translator_name: &str,
+    routes: &BTreeMap<String, String>,
+    locales_dir: &str,
+    dry_run: bool,
+    should_backup: bool,
+    _format: &str,
+    verbose: bool,
+) -> Result<()> {
+    if source == "auto" {
+        anyhow::bail!("The i18n command needs an explicit source language (--source)");
+    }
+    if let Some(template) = template {
+        if !template.contains(KEY_PLACEHOLDER) {
+            anyhow::bail!(
+                "Template must contain {} (e.g. _(\"{}\"))",
+                KEY_PLACEHOLDER,
+                KEY_PLACEHOLDER
+            );
+        }
+    }
+
+    if verbose {
+        println!("{} {}", "Extracting i18n keys:".bold().cyan(), path);
+        println!("  Source locale: {}", source);
+        println!("  Target locales: {}", targets.join(", "));
+        println!("  Locales directory: {}", locales_dir);
+        if dry_run {
+            println!("  {}", tr!("dry-run-mode").yellow().bold());
+        }
+    }
+
+    let locales = Path::new(locales_dir);
+    let source_resource = locales.join(format!("{}.json", source));
+
+    let mut extractor = I18nExtractor::new().with_messages(load_messages(&source_resource)?);
+    if let Some(template) = template {
+        extractor = extractor.with_template(template);
+    }
+
+    let files = collect_files(Path::new(path))?;
+    if files.is_empty() {
+        println!("{} No source files found", "!".yellow());
+        return Ok(());
+    }
+
+    let mut changed_files = 0;
+    let mut replaced = 0;
+
+    for file_path in &files {
+        let Some(kind) = SourceKind::from_path(&file_path.to_string_lossy()) else {
+            continue;
+        };
+        let content = fs::read_to_string(file_path)
+            .with_context(|| format!("Failed to read file: {}", file_path.display()))?;
+
+        let extraction = extractor.extract(&content, kind);
+        if extraction.replaced == 0 {
+            continue;
+        }
+
+        changed_files += 1;
+        replaced += extraction.replaced;
+        if verbose {
+            println!(
+                "{} {} ({} strings)",
+                "✓".green(),
+                file_path.display(),
+                extraction.replaced
+            );
+        }
+
+        if !dry_run {
+            if should_backup {
+                let backup_path = format!("{}.backup", file_path.display());
+                fs::copy(file_path, &backup_path)
+                    .with_context(|| format!("Failed to create backup: {}", backup_path))?;
+            }
+            fs::write(file_path, extraction.content)
+                .with_context(|| format!("Failed to write file: {}", file_path.display()))?;
+        }
+    }
+
+    let messages = extractor.messages().clone();
+
+    println!("\n{}", tr!("summary").bold().green());
+    println!("  Files rewritten: {}", changed_files);
+    println!("  Strings replaced: {}", replaced);
+    println!("  Messages: {}", messages.len());
+
+    if dry_run {
+        println!("\n{} Dry run: no files written", "!".yellow());
+        return Ok(());
+    }
+
+    fs::create_dir_all(locales)
+        .with_context(|| format!("Failed to create directory: {}", locales.display()))?;
+    save_messages(&source_resource, &messages)?;
+    println!("  {} {}", "Wrote".green(), source_resource.display());
+
+    let translator = pipeline::build_translator(translator_name, routes)?;
+    for target in targets.iter().filter(|t| t.as_str() != source) {
+        let resource = locales.join(format!("{}.json", target));
+        let mut translated = load_messages(&resource)?;
+        let added = translate_missing(
+            &messages,
+            &mut translated,
+            source,
+            target,
+            translator.as_ref(),
+        )
+        .await?;
+        save_messages(&resource, &translated)?;
+        println!(
+            "  {} {} ({} new messages)",
+            "Wrote".green(),
+            resource.display(),
+            added
+        );
+    }
+
+    Ok(())
+}
+
+/// Translate messages missing from a target resource, returning how many
+/// were added
+async fn translate_missing(
+    messages: &Messages,
+    translated: &mut Messages,
+    source: &str,
+    target: &str,
+    translator: &dyn Translator,
+) -> Result<usize> {
+    let (keys, texts): (Vec<String>, Vec<String>) = messages
+        .iter()
+        .filter(|(key, _)| !translated.contains_key(*key))
+        .map(|(key, text)| (key.clone(), text.clone()))
+        .unzip();
+
+    if texts.is_empty() {
+        return Ok(0);
+    }
+
+    let results = translator.translate_batch(&texts, source, target).await?;
+    for (key, result) in keys.into_iter().zip(results) {
+        translated.insert(key, result.translated_text);
+    }
+
+    Ok(texts.len())
+}
+
+/// Load a locale resource, or an empty one if it does not exist yet
+fn load_messages(path: &Path) -> Result<Messages> {
+    if !path.exists() {
+        return Ok(Messages::new());
+    }
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read resource: {}", path.display()))?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("Invalid resource file: {}", path.display()))
+}
+
+/// Write a locale resource as pretty-printed JSON
+fn save_messages(path: &Path, messages: &Messages) -> Result<()> {
+    let content = serde_json::to_string_pretty(messages)? + "\n";
+    fs::write(path, content)
+        .with_context(|| format!("Failed to write resource: {}", path.display()))
+}
+
+/// Collect Python and JavaScript/TypeScript source files
+fn collect_files(path: &Path) -> Result<Vec<PathBuf>> {
+    let is_source = |p: &Path| SourceKind::from_path(&p.to_string_lossy()).is_some();
+
+    if path.is_file() {
+        return Ok(if is_source(path) {
+            vec![path.to_path_buf()]
+        } else {
+            Vec::new()
+        });
+    }
+
+    let mut files = Vec::new();
+    for entry in walk::walker(path)
+        .into_iter()
+        .filter_entry(walk::keep_entry)
+    {
+        let entry = entry?;
+        if entry.file_type().is_file() && is_source(entry.path()) {
+            files.push(entry.path().to_path_buf());
+        }
+    }
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_execute_writes_resources() {
+        let dir = TempDir::new().unwrap();
+        let src = dir.path().join("src");
+        fs::create_dir(&src).unwrap();
+        fs::write(src.join("app.py"), "print(\"Hello world\")\n").unwrap();
+        fs::write(src.join("ui.js"), "alert('Hello world');\n").unwrap();
+        let locales = dir.path().join("locales");
+
+        execute(
+            src.to_str().unwrap(),
+            None,
+            "en",
+            &["en".to_string(), "zh".to_string()],
+            "mock",
+            &BTreeMap::new(),
+            locales.to_str().unwrap(),
+            false,
+            false,
+            "text",
+            false,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(src.join("app.py")).unwrap(),
+            "print(_(\"hello_world\"))\n"
+        );
+        assert_eq!(
+            fs::read_to_string(src.join("ui.js")).unwrap(),
+            "alert(t('hello_world'));\n"
+        );
+
+        let en = load_messages(&locales.join("en.json")).unwrap();
+        assert_eq!(en["hello_world"], "Hello world");
+        let zh = load_messages(&locales.join("zh.json")).unwrap();
+        assert_eq!(zh.len(), 1);
+        assert!(zh.contains_key("hello_world"));
+    }
+
+    #[tokio::test]
+    async fn test_translate_missing_keeps_existing() {
+        let mut messages = Messages::new();
+        messages.insert("a".to_string(), "Alpha text".to_string());
+        messages.insert("b".to_string(), "Beta text".to_string());
+        let mut translated = Messages::new();
+        translated.insert("a".to_string(), "reviewed".to_string());
+
+        let translator = pipeline::create_translator("mock").unwrap();
+        let added = translate_missing(&messages, &mut translated, "en", "zh", translator.as_ref())
+            .await
+            .unwrap();
+
+        assert_eq!(added, 1);
+        assert_eq!(translated["a"], "reviewed");
+        assert!(translated.contains_key("b"));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_auto_source_and_bad_template() {
+        let run = |source: &'static str, template: Option<&'static str>| async move {
+            execute(
+                "missing",
+                template,
+                source,
+                &[],
+                "mock",
+                &BTreeMap::new(),
+                "locales",
+                true,
+                false,
+                "text",
+                false,
+            )
+            .await
+        };
+
+        assert!(run("auto", None).await.is_err());
+        assert!(run("en", Some("t(key)")).await.is_err());
+    }
+}
@@ -0,0 +1,217 @@
+//! Import command implementation - write XLIFF targets back into files
+//!
+//! Segments are matched to the units of their `<file>` by the ids `langlint
+//! export` gave them. A segment is applied only when its source still
+//! equals the unit's text; otherwise the file changed since the export and
+//! the segment is reported as stale.
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use langlint_core::{encoding, paths};
+use langlint_parsers::xliff::{read_xliff, XliffSegment};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use super::export::unit_ids;
+use super::fix;
+use crate::pipeline::{self, FileErrors, QuarantineReport, Quarantined};
+use crate::ui::tr;
+
+/// Segments of one file applied and left out
+#[derive(Debug, Default, PartialEq)]
+struct Imported {
+    applied: usize,
+    stale: Vec<String>,
+}
+
+/// Execute the import command
+pub async fn execute(
+    file: &str,
+    dry_run: bool,
+    should_backup: bool,
+    _format: &str,
+    verbose: bool,
+) -> Result<()> {
+    let content =
+        fs::read_to_string(file).with_context(|| format!("Failed to read XLIFF: {}", file))?;
+    let segments = read_xliff(&content);
+    if segments.is_empty() {
+        anyhow::bail!("No XLIFF segments found in {}", file);
+    }
+
+    let mut by_file: BTreeMap<&str, Vec<&XliffSegment>> = BTreeMap::new();
+    let mut untranslated = 0;
+    for segment in &segments {
+        if segment.target.is_some() {
+            by_file.entry(&segment.file).or_default().push(segment);
+        } else {
+            untranslated += 1;
+        }
+    }
+
+    let mut errors = FileErrors::new(false);
+    let mut quarantine = QuarantineReport::default();
+    let mut updated_files = 0;
+    let mut applied = 0;
+    let mut stale = Vec::new();
+    for (path, segments) in &by_file {
+        let path = Path::new(path);
+        match import_file(path, segments, dry_run, should_backup) {
+            Ok(imported) => {
+                if imported.applied > 0 {
+                    updated_files += 1;
+                    if verbose {
+                        println!(
+                            "{} {} ({} segments)",
+                            "✓".green(),
+                            path.display(),
+                            imported.applied
+                        );
+                    }
+                }
+                applied += imported.applied;
+                stale.extend(
+                    imported
+                        .stale
+                        .into_iter()
+                        .map(|id| format!("{}#{}", paths::to_slash(path), id)),
+                );
+            }
+            Err(e) if quarantine.record(path, &e) => {}
+            Err(e) => errors.record(path, e)?,
+        }
+    }
+
+    println!("\n{}", tr!("summary").bold().green());
+    if dry_run {
+        println!("  {}", tr!("dry-run-mode").yellow().bold());
+    }
+    println!("  Files updated: {}", updated_files);
+    println!("  Segments applied: {}", applied);
+    if untranslated > 0 {
+        println!("  Segments without a target: {}", untranslated);
+    }
+    if !stale.is_empty() {
+        println!(
+            "  {} Stale segments (source changed since the export): {}",
+            "⚠".yellow(),
+            stale.len()
+        );
+        for segment in &stale {
+            println!("    {}", segment);
+        }
+    }
+    quarantine.print();
+    errors.print();
+    Ok(())
+}
+
+/// Apply the targets of `segments` to a file
+fn import_file(
+    path: &Path,
+    segments: &[&XliffSegment],
+    dry_run: bool,
+    should_backup: bool,
+) -> Result<Imported> {
+    let path_str = paths::to_slash(path);
+    let parser = fix::get_parser_for_file(&path_str)
+        .with_context(|| format!("No parser available for {}", path_str))?;
+    let (content, file_encoding) = encoding::read_file(path)?;
+    let parse_result = parser
+        .extract_units(&content, &path_str)
+        .with_context(|| format!("Failed to parse file: {}", path_str))?;
+
+    let ids = unit_ids(&parse_result.units);
+    let mut units = parse_result.units.clone();
+    let mut imported = Imported::default();
+    for segment in segments {
+        let unit = ids
+            .iter()
+            .position(|id| *id == segment.id)
+            .map(|index| &mut units[index])
+            .filter(|unit| unit.content == segment.source);
+        match (unit, &segment.target) {
+            (Some(unit), Some(target)) => {
+                unit.content = target.clone();
+                imported.applied += 1;
+            }
+            _ => imported.stale.push(segment.id.clone()),
+        }
+    }
+    if imported.applied == 0 {
+        return Ok(imported);
+    }
+
+    let reconstructed = parser.reconstruct(&content, &units, &path_str)?;
+    let reasons =
+        pipeline::verify_reconstruction(&content, &reconstructed, &parse_result.units, &units);
+    if !reasons.is_empty() {
+        return Err(Quarantined { reasons }.into());
+    }
+    if dry_run {
+        return Ok(imported);
+    }
+
+    if should_backup {
+        let backup_path = format!("{}.backup", path.display());
+        fs::copy(
+            paths::long_path(path),
+            paths::long_path(Path::new(&backup_path)),
+        )
+        .with_context(|| format!("Failed to create backup: {}", backup_path))?;
+    }
+    encoding::write_file(path, &reconstructed, file_encoding)?;
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use langlint_parsers::xliff::{write_xliff, XliffVersion};
+
+    #[test]
+    fn test_import_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("app.py");
+        fs::write(&file, "# 初始化\nx = 1\n\n# 计数器\ny = 2\n\n# 旧的注释").unwrap();
+        let file_str = paths::to_slash(&file);
+
+        // Exported as `langlint export` does, then edited in a CAT tool
+        let units = fix::get_parser_for_file(&file_str)
+            .unwrap()
+            .extract_units(&fs::read_to_string(&file).unwrap(), &file_str)
+            .unwrap()
+            .units;
+        assert_eq!(units.len(), 3);
+        let targets = ["Initialize", "Counter", "Changed comment"];
+        let mut exported: Vec<XliffSegment> = units
+            .iter()
+            .zip(unit_ids(&units))
+            .zip(targets)
+            .map(|((unit, id), target)| XliffSegment {
+                file: file_str.clone(),
+                id,
+                source: unit.content.clone(),
+                target: Some(target.to_string()),
+                note: None,
+            })
+            .collect();
+        exported[2].source = "已改动的注释".to_string();
+        let document = write_xliff(&exported, "zh", "en", XliffVersion::V1_2);
+        let segments = read_xliff(&document);
+        let segments: Vec<&XliffSegment> = segments.iter().collect();
+
+        let imported = import_file(&file, &segments, true, false).unwrap();
+        assert_eq!(imported.applied, 2);
+        assert_eq!(imported.stale, vec![exported[2].id.clone()]);
+        assert!(fs::read_to_string(&file).unwrap().contains("初始化"));
+
+        import_file(&file, &segments, false, true).unwrap();
+        assert_eq!(
+            fs::read_to_string(&file).unwrap(),
+            "# Initialize\nx = 1\n\n# Counter\ny = 2\n\n# 旧的注释"
+        );
+        assert!(dir.path().join("app.py.backup").exists());
+    }
+}
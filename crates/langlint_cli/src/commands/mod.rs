@@ -1,5 +1,16 @@
 //! CLI command implementations
 
+pub mod cache;
+pub mod check;
+pub mod commit_msg;
+pub mod export;
 pub mod fix;
+pub mod hooks;
+pub mod i18n;
+pub mod import;
+pub mod rename;
+pub mod revert;
 pub mod scan;
+pub mod stats;
+pub mod sync;
 pub mod translate;
@@ -0,0 +1,300 @@
+//! Rename command implementation - translate or transliterate file and
+//! directory names that are not in the target language
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use langlint_core::types::detect_language;
+use langlint_core::{transliterate, ParseResult, TranslatableUnit, UnitType};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::pipeline::{self, same_language};
+use crate::walk;
+
+/// A proposed rename
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Rename {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+/// Execute the rename command
+///
+/// Proposes renames for names containing non-ASCII characters that are
+/// not in `target`; with `apply` the renames are made, through `git mv`
+/// when `git` is set. Deeper entries are renamed first, so every path in
+/// the plan stays valid.
+#[allow(clippy::too_many_arguments)]
+pub async fn execute(
+    path: &str,
+    source: &str,
+    target: &str,
+    translator_name: &str,
+    routes: &BTreeMap<String, String>,
+    transliterate: bool,
+    apply: bool,
+    git: bool,
+    format: &str,
+    verbose: bool,
+) -> Result<()> {
+    let root = Path::new(path);
+    let candidates = collect_candidates(root, target)?;
+    if verbose {
+        println!(
+            "{} {} names not in the target language",
+            "→".dimmed(),
+            candidates.len()
+        );
+    }
+
+    let names: Vec<String> = candidates.iter().map(|path| stem(path)).collect();
+    let new_names = if transliterate {
+        names
+            .iter()
+            .map(|name| transliterate::to_ascii(name))
+            .collect()
+    } else {
+        let translator = pipeline::build_translator(translator_name, routes)?;
+        translate_names(&names, source, target, translator.as_ref()).await?
+    };
+
+    let plan = plan_renames(&candidates, &new_names);
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&plan)?);
+    } else {
+        for rename in &plan {
+            println!(
+                "{} {} {}",
+                rename.from.display(),
+                "→".dimmed(),
+                rename.to.display()
+            );
+        }
+    }
+
+    if !apply {
+        if format != "json" {
+            println!(
+                "\n{} {} renames proposed; run with --apply to rename",
+                "→".cyan(),
+                plan.len()
+            );
+        }
+        return Ok(());
+    }
+
+    for rename in &plan {
+        rename_path(rename, git)?;
+    }
+    if format != "json" {
+        println!("\n{} Renamed {} entries", "✓".green(), plan.len());
+    }
+    Ok(())
+}
+
+/// Files and directories below `root` whose names need renaming, deepest
+/// first
+fn collect_candidates(root: &Path, target: &str) -> Result<Vec<PathBuf>> {
+    let mut candidates = Vec::new();
+    for entry in walk::walker(root)
+        .min_depth(1)
+        .contents_first(true)
+        .into_iter()
+        .filter_entry(walk::keep_entry)
+    {
+        let entry = entry.with_context(|| format!("Failed to walk {}", root.display()))?;
+        if needs_rename(&stem(entry.path()), target) {
+            candidates.push(entry.into_path());
+        }
+    }
+    Ok(candidates)
+}
+
+/// Check if a name is not in the target language
+///
+/// Only names with non-ASCII characters are considered; names too short to
+/// detect count as foreign.
+fn needs_rename(name: &str, target: &str) -> bool {
+    !name.is_ascii()
+        && detect_language(name).is_none_or(|language| !same_language(&language, target))
+}
+
+/// The part of a name to rename: a file's name without its extension, or
+/// a directory's whole name
+fn stem(path: &Path) -> String {
+    let name = if path.is_dir() {
+        path.file_name()
+    } else {
+        path.file_stem()
+    };
+    name.map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Translate names, returning `None` for names that failed
+async fn translate_names(
+    names: &[String],
+    source: &str,
+    target: &str,
+    translator: &dyn langlint_translators::Translator,
+) -> Result<Vec<Option<String>>> {
+    let units: Vec<TranslatableUnit> = names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| TranslatableUnit::new(name.clone(), UnitType::Metadata, i as u32 + 1, 1))
+        .collect();
+    let parsed = ParseResult::new("file_names", "utf-8", names.len() as u32).with_units(units);
+    let translated = pipeline::translate_units(&parsed, source, target, translator).await?;
+
+    Ok(translated
+        .units
+        .into_iter()
+        .zip(names)
+        .map(|(unit, name)| Some(unit.content).filter(|content| content != name))
+        .collect())
+}
+
+/// Pair candidates with their new names
+///
+/// Names that could not be converted, come out empty or unchanged, or
+/// would collide with an existing entry or another rename are skipped.
+fn plan_renames(candidates: &[PathBuf], new_names: &[Option<String>]) -> Vec<Rename> {
+    let mut taken = HashSet::new();
+    let mut plan = Vec::new();
+
+    for (from, new_name) in candidates.iter().zip(new_names) {
+        let original = stem(from);
+        let separator = if original.contains('-') { '-' } else { '_' };
+        let Some(slug) = new_name
+            .as_deref()
+            .map(|name| slugify(name, separator))
+            .filter(|slug| !slug.is_empty() && *slug != original)
+        else {
+            continue;
+        };
+
+        let file_name = match from.extension().filter(|_| !from.is_dir()) {
+            Some(extension) => format!("{}.{}", slug, extension.to_string_lossy()),
+            None => slug,
+        };
+        let to = from.with_file_name(file_name);
+        if to.exists() || !taken.insert(to.clone()) {
+            eprintln!(
+                "{} Skipping {}: {} already exists",
+                "⚠".yellow(),
+                from.display(),
+                to.display()
+            );
+            continue;
+        }
+        plan.push(Rename {
+            from: from.clone(),
+            to,
+        });
+    }
+    plan
+}
+
+/// Turn a translated name into a file name: lowercase words joined by
+/// `separator`
+fn slugify(name: &str, separator: char) -> String {
+    let mut slug = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with(separator) {
+            slug.push(separator);
+        }
+    }
+    slug.trim_end_matches(separator).to_string()
+}
+
+/// Rename one entry, with `git mv` or directly
+fn rename_path(rename: &Rename, git: bool) -> Result<()> {
+    if git {
+        let output = Command::new("git")
+            .arg("mv")
+            .arg(&rename.from)
+            .arg(&rename.to)
+            .output()
+            .context("Failed to run git mv")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "git mv {} failed: {}",
+                rename.from.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        return Ok(());
+    }
+    std::fs::rename(&rename.from, &rename.to).with_context(|| {
+        format!(
+            "Failed to rename {} to {}",
+            rename.from.display(),
+            rename.to.display()
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Data processing", '_'), "data_processing");
+        assert_eq!(slugify("  Error log (old)!", '-'), "error-log-old");
+        assert_eq!(slugify("[EN] 数据", '_'), "en_数据");
+        assert_eq!(slugify("...", '_'), "");
+    }
+
+    #[test]
+    fn test_needs_rename() {
+        assert!(needs_rename("数据处理", "en"));
+        assert!(needs_rename("Журнал", "en"));
+        assert!(!needs_rename("data_processing", "en"));
+        assert!(!needs_rename("这是一个用于测试的中文文件名称", "zh"));
+    }
+
+    #[tokio::test]
+    async fn test_transliterated_renames() {
+        let dir = TempDir::new().unwrap();
+        let folder = dir.path().join("Отчёты");
+        fs::create_dir(&folder).unwrap();
+        fs::write(folder.join("журнал-ошибок.txt"), "").unwrap();
+        fs::write(folder.join("数据.py"), "").unwrap();
+        fs::write(dir.path().join("notes.md"), "").unwrap();
+
+        let candidates = collect_candidates(dir.path(), "en").unwrap();
+        let names: Vec<Option<String>> = candidates
+            .iter()
+            .map(|path| transliterate::to_ascii(&stem(path)))
+            .collect();
+        let plan = plan_renames(&candidates, &names);
+
+        // Contents first, and the untransliterable name is left alone
+        assert_eq!(
+            plan,
+            vec![
+                Rename {
+                    from: folder.join("журнал-ошибок.txt"),
+                    to: folder.join("zhurnal-oshibok.txt"),
+                },
+                Rename {
+                    from: folder.clone(),
+                    to: dir.path().join("otchyoty"),
+                },
+            ]
+        );
+
+        for rename in &plan {
+            rename_path(rename, false).unwrap();
+        }
+        assert!(dir.path().join("otchyoty/zhurnal-oshibok.txt").exists());
+        assert!(dir.path().join("otchyoty/数据.py").exists());
+    }
+}
@@ -0,0 +1,251 @@
+//! Revert command implementation - undo translations using sidecar maps
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use langlint_core::mapfile::MAP_SUFFIX;
+use langlint_core::{encoding, SourceMap, TranslationMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::ui::tr;
+
+/// Execute the revert command
+pub async fn execute(
+    path: &str,
+    run: Option<u64>,
+    lines: &[u32],
+    _format: &str,
+    verbose: bool,
+) -> Result<()> {
+    if verbose {
+        println!("{} {}", "Reverting:".bold().cyan(), path);
+        if let Some(run) = run {
+            println!("  Run: {}", run);
+        }
+    }
+
+    let targets = collect_targets(Path::new(path));
+
+    if targets.is_empty() {
+        println!("{} No mapping files found", "!".yellow());
+        return Ok(());
+    }
+
+    let mut reverted_files = 0;
+    let mut reverted_units = 0;
+    let mut remaining_units = 0;
+    let mut error_count = 0;
+
+    for file_path in &targets {
+        match revert_file(file_path, run, lines) {
+            Ok(None) => {
+                if verbose {
+                    println!("  {} {} (different run)", "→".dimmed(), file_path.display());
+                }
+            }
+            Ok(Some((reverted, remaining))) => {
+                if reverted > 0 {
+                    reverted_files += 1;
+                }
+                reverted_units += reverted;
+                remaining_units += remaining;
+                if verbose {
+                    println!(
+                        "{} {} ({} units reverted)",
+                        "✓".green(),
+                        file_path.display(),
+                        reverted
+                    );
+                }
+            }
+            Err(e) => {
+                error_count += 1;
+                eprintln!(
+                    "{} Failed to revert {}: {}",
+                    "✗".red(),
+                    file_path.display(),
+                    e
+                );
+            }
+        }
+    }
+
+    println!("\n{}", tr!("summary").bold().green());
+    println!("  Files reverted: {}", reverted_files);
+    println!("  Units reverted: {}", reverted_units);
+    if remaining_units > 0 {
+        println!(
+            "  {} Units not found (edited since translation?): {}",
+            "⚠".yellow(),
+            remaining_units
+        );
+    }
+    if error_count > 0 {
+        println!("  {} Errors: {}", "⚠".yellow(), error_count);
+    }
+
+    Ok(())
+}
+
+/// Revert one file from its mapping file
+///
+/// Returns `None` if the map belongs to a different run, otherwise the
+/// number of reverted units and the number of entries that could not be
+/// located. The mapping file is removed once every entry is reverted.
+fn revert_file(path: &Path, run: Option<u64>, lines: &[u32]) -> Result<Option<(usize, usize)>> {
+    let map_path = TranslationMap::path_for(path);
+    let mut map = TranslationMap::load(&map_path)?;
+
+    if run.is_some_and(|run| run != map.run) {
+        return Ok(None);
+    }
+
+    // Translated files may have been written with --output-encoding
+    let (content, file_encoding) = encoding::read_file(path)?;
+
+    let is_notebook = path.extension().is_some_and(|ext| ext == "ipynb");
+    let (reverted_content, reverted) = map.revert_text(&content, lines, is_notebook);
+    if reverted > 0 {
+        encoding::write_file(path, &reverted_content, file_encoding)?;
+        // Its line numbers no longer match the file
+        let source_map = SourceMap::path_for(path);
+        if source_map.is_file() {
+            fs::remove_file(&source_map).with_context(|| {
+                format!("Failed to remove source map: {}", source_map.display())
+            })?;
+        }
+    }
+
+    if map.is_empty() {
+        fs::remove_file(&map_path)
+            .with_context(|| format!("Failed to remove mapping file: {}", map_path.display()))?;
+    } else if reverted > 0 {
+        map.save(&map_path)?;
+    }
+
+    // Entries filtered out by `lines` are not reported as missing
+    let remaining = map
+        .entries
+        .iter()
+        .filter(|entry| lines.is_empty() || lines.contains(&entry.line))
+        .count();
+
+    Ok(Some((reverted, remaining)))
+}
+
+/// Collect translated files that have a mapping file
+///
+/// `path` may be a translated file, its mapping file, or a directory.
+fn collect_targets(path: &Path) -> Vec<PathBuf> {
+    if path.is_file() {
+        let file = target_of(path).unwrap_or_else(|| path.to_path_buf());
+        return if TranslationMap::path_for(&file).is_file() {
+            vec![file]
+        } else {
+            Vec::new()
+        };
+    }
+
+    let mut targets: Vec<PathBuf> = WalkDir::new(path)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| target_of(e.path()))
+        .filter(|file| file.is_file())
+        .collect();
+    targets.sort();
+    targets
+}
+
+/// Translated file that a mapping file belongs to
+fn target_of(map_path: &Path) -> Option<PathBuf> {
+    map_path
+        .to_str()
+        .and_then(|s| s.strip_suffix(MAP_SUFFIX))
+        .map(PathBuf::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use langlint_core::{TranslatableUnit, UnitType};
+    use tempfile::TempDir;
+
+    fn write_translated(dir: &Path) -> PathBuf {
+        let file = dir.join("app.py");
+        fs::write(&file, "# hello\nx = 1\n# world\n").unwrap();
+
+        let original = vec![
+            TranslatableUnit::new("你好".to_string(), UnitType::Comment, 1, 1),
+            TranslatableUnit::new("世界".to_string(), UnitType::Comment, 3, 1),
+        ];
+        let translated = vec![
+            TranslatableUnit::new("hello".to_string(), UnitType::Comment, 1, 1),
+            TranslatableUnit::new("world".to_string(), UnitType::Comment, 3, 1),
+        ];
+        TranslationMap::from_units(&original, &translated, "zh", "en")
+            .with_run(42)
+            .save(TranslationMap::path_for(&file))
+            .unwrap();
+
+        file
+    }
+
+    #[test]
+    fn test_revert_file() {
+        let dir = TempDir::new().unwrap();
+        let file = write_translated(dir.path());
+
+        assert_eq!(revert_file(&file, None, &[]).unwrap(), Some((2, 0)));
+        assert_eq!(
+            fs::read_to_string(&file).unwrap(),
+            "# 你好\nx = 1\n# 世界\n"
+        );
+        assert!(!TranslationMap::path_for(&file).exists());
+    }
+
+    #[test]
+    fn test_revert_selected_lines() {
+        let dir = TempDir::new().unwrap();
+        let file = write_translated(dir.path());
+
+        assert_eq!(revert_file(&file, Some(42), &[3]).unwrap(), Some((1, 0)));
+        assert_eq!(
+            fs::read_to_string(&file).unwrap(),
+            "# hello\nx = 1\n# 世界\n"
+        );
+
+        let map = TranslationMap::load(TranslationMap::path_for(&file)).unwrap();
+        assert_eq!(map.entries.len(), 1);
+        assert_eq!(map.entries[0].original, "你好");
+    }
+
+    #[test]
+    fn test_revert_other_run_is_skipped() {
+        let dir = TempDir::new().unwrap();
+        let file = write_translated(dir.path());
+
+        assert_eq!(revert_file(&file, Some(7), &[]).unwrap(), None);
+        assert_eq!(
+            fs::read_to_string(&file).unwrap(),
+            "# hello\nx = 1\n# world\n"
+        );
+    }
+
+    #[test]
+    fn test_collect_targets() {
+        let dir = TempDir::new().unwrap();
+        let file = write_translated(dir.path());
+        fs::write(dir.path().join("other.py"), "# other\n").unwrap();
+
+        assert_eq!(collect_targets(dir.path()), vec![file.clone()]);
+        assert_eq!(collect_targets(&file), vec![file.clone()]);
+        assert_eq!(
+            collect_targets(&TranslationMap::path_for(&file)),
+            vec![file]
+        );
+        assert!(collect_targets(&dir.path().join("other.py")).is_empty());
+    }
+}
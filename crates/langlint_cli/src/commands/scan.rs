@@ -2,11 +2,23 @@
 
 use anyhow::{Context, Result};
 use colored::Colorize;
-use langlint_core::ParseResult;
-use langlint_parsers::{GenericCodeParser, Parser, PythonParser};
+use langlint_core::{encoding, paths, ParseResult};
+use langlint_parsers::{generated, jsx, license, sinks, todo};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
+
+use crate::archive::{self, ArchiveKind};
+use crate::heatmap;
+use crate::limits;
+use crate::overrides;
+use crate::packages::{self, Package};
+use crate::pipeline::FileErrors;
+use crate::remote;
+use crate::report::{self, Report, ReportContent};
+use crate::ui::tr;
+use crate::walk;
 
 /// Execute the scan command
 #[allow(clippy::too_many_arguments)]
@@ -16,18 +28,56 @@ pub async fn execute(
     exclude: Option<Vec<String>>,
     unit_types: Option<Vec<String>>,
     priority: Option<String>,
+    include_todos: bool,
+    include_strings: bool,
+    include_license_headers: bool,
+    include_generated: bool,
+    package_names: &[String],
+    by_package: bool,
+    group_by: &[String],
+    sort: Option<&str>,
+    strict: bool,
     output: Option<&str>,
+    heatmap: Option<&str>,
     format: &str,
     verbose: bool,
 ) -> Result<()> {
+    let group_by = group_by
+        .iter()
+        .map(|key| GroupBy::parse(key))
+        .collect::<Result<Vec<_>>>()?;
+    let sort = sort.map(SortBy::parse).transpose()?.unwrap_or_default();
+
     if verbose {
         println!("{} {}", "Scanning:".bold().cyan(), path);
     }
 
-    let path_obj = Path::new(path);
+    // Remote repositories are shallow-cloned and removed after the scan
+    let checkout = if remote::is_git_url(path) {
+        if verbose {
+            println!("{} {}", "Cloning:".dimmed(), path);
+        }
+        Some(remote::shallow_clone(path, verbose)?)
+    } else {
+        None
+    };
+    let path_obj = checkout
+        .as_ref()
+        .map_or_else(|| Path::new(path), |dir| dir.path());
 
     // Collect files to scan
-    let files = collect_files(path_obj, include.as_ref(), exclude.as_ref())?;
+    let mut errors = FileErrors::new(strict);
+    let mut files = collect_files(path_obj, include.as_ref(), exclude.as_ref(), &mut errors)?;
+
+    // Detect monorepo packages only when they are needed
+    let package_list = if by_package || !package_names.is_empty() {
+        packages::detect_packages(path_obj)?
+    } else {
+        Vec::new()
+    };
+    if !package_names.is_empty() {
+        select_packages(&mut files, &package_list, package_names)?;
+    }
 
     if verbose {
         println!("{} {} files found", "Total:".bold(), files.len());
@@ -42,24 +92,68 @@ pub async fn execute(
             println!("{} {}", "Processing:".dimmed(), file_path.display());
         }
 
-        match scan_file(file_path).await {
-            Ok(result) => {
-                let units_count = result.units.len();
-                total_units += units_count;
-
-                if verbose && units_count > 0 {
-                    println!("  {} {} translatable units", "Found:".green(), units_count);
+        match scan_input(file_path).await {
+            Ok(results) => {
+                for (result_path, mut result) in results {
+                    if !include_generated && generated::is_generated(&result) {
+                        if verbose {
+                            println!("  {} generated file", "Skipped:".dimmed());
+                        }
+                        continue;
+                    }
+                    if !include_todos {
+                        result.units.retain(|unit| !todo::is_todo(unit));
+                    }
+                    if !include_strings {
+                        result
+                            .units
+                            .retain(|unit| !sinks::is_sink_string(unit) && !jsx::is_jsx_text(unit));
+                    }
+                    if !include_license_headers {
+                        result
+                            .units
+                            .retain(|unit| !license::is_license_header(unit));
+                    }
+                    let units_count = result.units.len();
+                    total_units += units_count;
+
+                    if verbose && units_count > 0 {
+                        println!("  {} {} translatable units", "Found:".green(), units_count);
+                    }
+
+                    all_results.push((result_path, result));
                 }
-
-                all_results.push((file_path.clone(), result));
             }
             Err(e) => {
                 eprintln!(
-                    "{} Failed to scan {}: {}",
+                    "{} Failed to scan {}: {:#}",
                     "Warning:".yellow(),
                     file_path.display(),
                     e
                 );
+                errors.record(file_path, e)?;
+            }
+        }
+    }
+
+    // Aggregate per package before paths are rewritten
+    let package_summaries = if by_package {
+        packages::summarize(
+            &package_list,
+            path_obj,
+            all_results
+                .iter()
+                .map(|(path, result)| (path.as_path(), result.units.len())),
+        )
+    } else {
+        Vec::new()
+    };
+
+    // Report paths of a remote checkout relative to the repository root
+    if let Some(dir) = &checkout {
+        for (result_path, _) in &mut all_results {
+            if let Ok(relative) = result_path.strip_prefix(dir.path()) {
+                *result_path = relative.to_path_buf();
             }
         }
     }
@@ -72,8 +166,30 @@ pub async fn execute(
         all_results = filter_by_priority(all_results, prio)?;
     }
 
+    sort_results(&mut all_results, sort);
+
+    // Extra report views over the same results
+    let views: Vec<GroupedView> = group_by
+        .iter()
+        .map(|by| GroupedView {
+            by: *by,
+            groups: group_results(&all_results, path_obj, *by),
+        })
+        .collect();
+
     // Output results
-    let output_content = format_results(&all_results, format, verbose)?;
+    let output_content = report::render(
+        &Report {
+            content: ReportContent::Scan {
+                results: &all_results,
+                packages: &package_summaries,
+                groups: &views,
+            },
+            errors: errors.files(),
+            verbose,
+        },
+        format,
+    )?;
 
     // Write to file or stdout
     if let Some(output_path) = output {
@@ -86,50 +202,101 @@ pub async fn execute(
         print!("{}", output_content);
     }
 
+    if let Some(heatmap_path) = heatmap {
+        heatmap::write(
+            Path::new(heatmap_path),
+            &heatmap::rows(&all_results, path_obj),
+        )?;
+        if verbose {
+            println!("{} Heat map written to: {}", "✓".green(), heatmap_path);
+        }
+    }
+
     // Summary
     if verbose || output.is_none() {
-        println!("\n{}", "Summary:".bold().green());
-        println!("  Files scanned: {}", files.len());
-        println!("  Total translatable units: {}", total_units);
+        println!("\n{}", tr!("summary").bold().green());
+        println!("  {}", tr!("files-scanned", count = files.len()));
+        println!("  {}", tr!("translatable-units", count = total_units));
+        if !errors.is_empty() {
+            println!(
+                "  {} {}",
+                "⚠".yellow(),
+                tr!("error-count", count = errors.len())
+            );
+        }
+        if !errors.limited().is_empty() {
+            println!("  {}", tr!("limited-count", count = errors.limited().len()));
+        }
     }
+    errors.print();
 
     Ok(())
 }
 
+/// Keep only files belonging to the selected packages
+fn select_packages(files: &mut Vec<PathBuf>, found: &[Package], names: &[String]) -> Result<()> {
+    for name in names {
+        if !found.iter().any(|package| &package.name == name) {
+            let available: Vec<&str> = found.iter().map(|p| p.name.as_str()).collect();
+            anyhow::bail!(
+                "Unknown package '{}' (found: {})",
+                name,
+                if available.is_empty() {
+                    "none".to_string()
+                } else {
+                    available.join(", ")
+                }
+            );
+        }
+    }
+
+    files.retain(|file| {
+        packages::package_for(found, file).is_some_and(|package| names.contains(&package.name))
+    });
+    Ok(())
+}
+
 /// Collect files to scan based on include/exclude patterns
-fn collect_files(
+///
+/// Unreadable directory entries are recorded in `errors`.
+pub(crate) fn collect_files(
     path: &Path,
     include: Option<&Vec<String>>,
     exclude: Option<&Vec<String>>,
+    errors: &mut FileErrors,
 ) -> Result<Vec<PathBuf>> {
     let mut files = Vec::new();
 
     if path.is_file() {
-        files.push(path.to_path_buf());
+        match limits::check_size(path) {
+            Ok(()) => files.push(path.to_path_buf()),
+            Err(e) => errors.record(path, e)?,
+        }
         return Ok(files);
     }
 
     // Walk directory
-    for entry in WalkDir::new(path)
-        .follow_links(true)
+    for entry in walk::walker(path)
         .into_iter()
-        .filter_entry(|e| {
-            // Skip hidden directories and common exclusions
-            let name = e.file_name().to_string_lossy();
-            !name.starts_with('.')
-                && name != "node_modules"
-                && name != "target"
-                && name != "__pycache__"
-                && name != "venv"
-        })
+        .filter_entry(walk::keep_entry)
     {
-        let entry = entry?;
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                errors.record_walk(e)?;
+                continue;
+            }
+        };
         if entry.file_type().is_file() {
             let file_path = entry.path();
 
             // Apply include/exclude patterns
-            if should_include_file(file_path, include, exclude) {
-                files.push(file_path.to_path_buf());
+            if !should_include_file(file_path, include, exclude) {
+                continue;
+            }
+            match limits::check_size(file_path) {
+                Ok(()) => files.push(file_path.to_path_buf()),
+                Err(e) => errors.record(file_path, e)?,
             }
         }
     }
@@ -148,7 +315,7 @@ fn should_include_file(
     // Check exclude patterns first
     if let Some(excludes) = exclude {
         for pattern in excludes {
-            if path_str.contains(pattern) {
+            if matches_pattern(&path_str, pattern) {
                 return false;
             }
         }
@@ -157,7 +324,7 @@ fn should_include_file(
     // Check include patterns
     if let Some(includes) = include {
         for pattern in includes {
-            if path_str.contains(pattern) {
+            if matches_pattern(&path_str, pattern) {
                 return true;
             }
         }
@@ -167,26 +334,75 @@ fn should_include_file(
     true // Include by default
 }
 
+/// Match a path against an include/exclude pattern
+///
+/// Patterns containing glob metacharacters (as used in config files, e.g.
+/// `**/node_modules/**`) are matched as globs; plain strings keep the
+/// original substring semantics of the `--include`/`--exclude` flags.
+/// Separators are normalized to `/`, and matching is case-insensitive on
+/// Windows.
+fn matches_pattern(path: &str, pattern: &str) -> bool {
+    matches_pattern_with(path, pattern, paths::CASE_INSENSITIVE)
+}
+
+fn matches_pattern_with(path: &str, pattern: &str, case_insensitive: bool) -> bool {
+    let path = paths::normalize_separators(path);
+    let pattern = paths::normalize_separators(pattern);
+    if pattern.contains(['*', '?', '[']) {
+        globset::GlobBuilder::new(&pattern)
+            .case_insensitive(case_insensitive)
+            .build()
+            .map(|glob| {
+                let matcher = glob.compile_matcher();
+                matcher.is_match(&path) || matcher.is_match(path.trim_start_matches("./"))
+            })
+            .unwrap_or(false)
+    } else if case_insensitive {
+        path.to_lowercase().contains(&pattern.to_lowercase())
+    } else {
+        path.contains(&pattern)
+    }
+}
+
+/// Scan a file, or every text entry of an archive
+///
+/// Archive entries are reported as `<archive>/<entry path>`.
+pub(crate) async fn scan_input(path: &Path) -> Result<Vec<(PathBuf, ParseResult)>> {
+    if ArchiveKind::from_path(path).is_none() {
+        return Ok(vec![(path.to_path_buf(), scan_file(path).await?)]);
+    }
+
+    let mut results = Vec::new();
+    for entry in archive::read_archive(path)? {
+        let Some(content) = entry.text() else {
+            continue;
+        };
+        let entry_path = path.join(&entry.name);
+        let result = scan_content(&entry_path, content)?;
+        limits::check_units(Path::new(&entry.name), result.units.len())
+            .map_err(|e| limits::in_entry(e, &entry.name))?;
+        results.push((entry_path, result));
+    }
+    Ok(results)
+}
+
 /// Scan a single file and extract translatable units
 async fn scan_file(path: &Path) -> Result<ParseResult> {
-    let content = fs::read_to_string(path)
-        .with_context(|| format!("Failed to read file: {}", path.display()))?;
+    let (content, file_encoding) = encoding::read_file(path)?;
 
-    let path_str = path.to_string_lossy();
+    let mut result = scan_content(path, &content)?;
+    limits::check_units(path, result.units.len())?;
+    result.encoding = file_encoding.name().to_lowercase();
+    Ok(result)
+}
 
-    // Try Python parser first
-    let python_parser = PythonParser::new();
-    if python_parser.can_parse(&path_str, Some(&content)) {
-        return python_parser
-            .extract_units(&content, &path_str)
-            .with_context(|| format!("Failed to parse Python file: {}", path.display()));
-    }
+/// Extract translatable units from file content
+fn scan_content(path: &Path, content: &str) -> Result<ParseResult> {
+    let path_str = path.to_string_lossy();
 
-    // Try generic code parser
-    let generic_parser = GenericCodeParser::new();
-    if generic_parser.can_parse(&path_str, Some(&content)) {
-        return generic_parser
-            .extract_units(&content, &path_str)
+    if let Some(parser) = overrides::parser_for(path, Some(content)) {
+        return parser
+            .extract_units(content, &path_str)
             .with_context(|| format!("Failed to parse file: {}", path.display()));
     }
 
@@ -212,7 +428,7 @@ fn filter_by_unit_types(
                 let unit_type_str = format!("{:?}", unit.unit_type).to_lowercase();
                 types
                     .iter()
-                    .any(|t| unit_type_str.contains(&t.to_lowercase()))
+                    .any(|t| unit_type_str.contains(&t.to_lowercase().replace(['_', '-'], "")))
             });
             (path, result)
         })
@@ -249,106 +465,556 @@ fn filter_by_priority(
         .collect())
 }
 
-/// Format scan results in the specified format (returns string instead of printing)
-fn format_results(
-    results: &[(PathBuf, ParseResult)],
-    format: &str,
-    verbose: bool,
-) -> Result<String> {
-    match format {
-        "json" => format_json(results, false),
-        "pretty-json" => format_json(results, true),
-        _ => format_text(results, verbose),
-    }
+/// Report dimension for `--group-by`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum GroupBy {
+    /// Directory of the file, relative to the scanned path
+    Dir,
+    /// File extension
+    Ext,
+    /// Detected language of each unit
+    Language,
 }
 
-/// Format results as JSON
-fn format_json(results: &[(PathBuf, ParseResult)], pretty: bool) -> Result<String> {
-    use serde::Serialize;
+impl GroupBy {
+    fn parse(key: &str) -> Result<Self> {
+        match key.trim().to_lowercase().as_str() {
+            "dir" | "directory" => Ok(Self::Dir),
+            "ext" | "extension" => Ok(Self::Ext),
+            "language" | "lang" => Ok(Self::Language),
+            _ => anyhow::bail!("Invalid group-by key: {}. Use dir, ext, or language", key),
+        }
+    }
 
-    #[derive(Serialize)]
-    struct JsonOutput {
-        files: Vec<FileOutput>,
+    pub(crate) fn title(&self) -> &'static str {
+        match self {
+            Self::Dir => "By directory",
+            Self::Ext => "By extension",
+            Self::Language => "By language",
+        }
     }
+}
 
-    #[derive(Serialize)]
-    struct FileOutput {
-        path: String,
-        units: Vec<UnitOutput>,
+/// File order of a scan report for `--sort`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum SortBy {
+    /// Path order
+    #[default]
+    Path,
+    /// Longest files first
+    Size,
+    /// Files with the most units first
+    Units,
+    /// By the most common detected language of each file's units
+    Language,
+}
+
+impl SortBy {
+    fn parse(key: &str) -> Result<Self> {
+        match key.trim().to_lowercase().as_str() {
+            "path" => Ok(Self::Path),
+            "size" => Ok(Self::Size),
+            "units" => Ok(Self::Units),
+            "language" | "lang" => Ok(Self::Language),
+            _ => anyhow::bail!(
+                "Invalid sort key: {}. Use path, size, units, or language",
+                key
+            ),
+        }
     }
+}
 
-    #[derive(Serialize)]
-    struct UnitOutput {
-        content: String,
-        unit_type: String,
-        priority: String,
-        line_start: usize,
-        line_end: usize,
-        #[serde(skip_serializing_if = "Option::is_none")]
-        detected_language: Option<String>,
+/// Put results into a deterministic order
+///
+/// Units are ordered by position within each file; files by `sort`, with
+/// ties broken by path so the report only changes when the scan does.
+fn sort_results(results: &mut [(PathBuf, ParseResult)], sort: SortBy) {
+    for (_, result) in results.iter_mut() {
+        result
+            .units
+            .sort_by_key(|unit| (unit.line_number, unit.column_number));
     }
 
-    let output = JsonOutput {
-        files: results
-            .iter()
-            .map(|(path, result)| FileOutput {
-                path: path.display().to_string(),
-                units: result
-                    .units
-                    .iter()
-                    .map(|unit| UnitOutput {
-                        content: unit.content.clone(),
-                        unit_type: format!("{:?}", unit.unit_type),
-                        priority: format!("{:?}", unit.priority),
-                        line_start: unit.line_number as usize,
-                        line_end: unit.line_number as usize,
-                        detected_language: unit.detected_language.clone(),
-                    })
-                    .collect(),
-            })
-            .collect(),
-    };
+    results.sort_by(|(a_path, a), (b_path, b)| {
+        let order = match sort {
+            SortBy::Path => std::cmp::Ordering::Equal,
+            SortBy::Size => b.line_count.cmp(&a.line_count),
+            SortBy::Units => b.units.len().cmp(&a.units.len()),
+            SortBy::Language => dominant_language(a).cmp(&dominant_language(b)),
+        };
+        order.then_with(|| a_path.cmp(b_path))
+    });
+}
 
-    if pretty {
-        Ok(serde_json::to_string_pretty(&output)?)
-    } else {
-        Ok(serde_json::to_string(&output)?)
+/// Most common detected language of a file's units (ties: alphabetical)
+///
+/// Files without detected languages sort last.
+fn dominant_language(result: &ParseResult) -> (bool, String) {
+    match result.dominant_language() {
+        Some(language) => (false, language),
+        None => (true, String::new()),
     }
 }
 
-/// Format results as human-readable text
-fn format_text(results: &[(PathBuf, ParseResult)], verbose: bool) -> Result<String> {
-    let mut output = String::new();
-    output.push_str(&format!("\n{}\n", "=== Scan Results ==="));
+/// Totals of one group in a grouped view
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct GroupSummary {
+    pub key: String,
+    /// Files with at least one unit in the group
+    pub files: usize,
+    pub units: usize,
+}
+
+/// Scan results aggregated along one dimension
+#[derive(Debug, Serialize)]
+pub(crate) struct GroupedView {
+    pub by: GroupBy,
+    pub groups: Vec<GroupSummary>,
+}
+
+/// Aggregate scan results into groups, largest first
+///
+/// Directories are reported relative to `base`. Files without units are
+/// not counted.
+fn group_results(
+    results: &[(PathBuf, ParseResult)],
+    base: &Path,
+    by: GroupBy,
+) -> Vec<GroupSummary> {
+    let mut groups: BTreeMap<String, (HashSet<&Path>, usize)> = BTreeMap::new();
 
     for (path, result) in results {
-        if result.units.is_empty() {
-            continue;
+        for unit in &result.units {
+            let key = match by {
+                GroupBy::Dir => {
+                    let relative = path.strip_prefix(base).unwrap_or(path);
+                    match relative.parent() {
+                        Some(dir) if !dir.as_os_str().is_empty() => dir.display().to_string(),
+                        _ => ".".to_string(),
+                    }
+                }
+                GroupBy::Ext => path
+                    .extension()
+                    .map(|ext| format!(".{}", ext.to_string_lossy()))
+                    .unwrap_or_else(|| "(none)".to_string()),
+                GroupBy::Language => unit
+                    .detected_language
+                    .clone()
+                    .unwrap_or_else(|| "unknown".to_string()),
+            };
+            let group = groups.entry(key).or_default();
+            group.0.insert(path.as_path());
+            group.1 += 1;
         }
+    }
 
-        output.push_str(&format!("\n{} {}\n", "File:", path.display()));
-        output.push_str(&format!("{} {} units\n", "Units:", result.units.len()));
+    let mut summaries: Vec<GroupSummary> = groups
+        .into_iter()
+        .map(|(key, (files, units))| GroupSummary {
+            key,
+            files: files.len(),
+            units,
+        })
+        .collect();
+    summaries.sort_by(|a, b| b.units.cmp(&a.units).then_with(|| a.key.cmp(&b.key)));
+    summaries
+}
 
-        if verbose {
-            for (i, unit) in result.units.iter().enumerate() {
-                output.push_str(&format!("\n  {} {}:\n", "Unit", i + 1));
-                output.push_str(&format!("    Type: {:?}\n", unit.unit_type));
-                output.push_str(&format!("    Priority: {:?}\n", unit.priority));
-                output.push_str(&format!(
-                    "    Location: line {}, column {}\n",
-                    unit.line_number, unit.column_number
-                ));
-
-                // Truncate long content
-                let content = if unit.content.len() > 100 {
-                    format!("{}...", &unit.content[..100])
-                } else {
-                    unit.content.clone()
-                };
-                output.push_str(&format!("    Content: {}\n", content));
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_include_file_substring() {
+        let exclude = vec!["vendor".to_string()];
+        assert!(!should_include_file(
+            Path::new("src/vendor/lib.py"),
+            None,
+            Some(&exclude)
+        ));
+        assert!(should_include_file(
+            Path::new("src/lib.py"),
+            None,
+            Some(&exclude)
+        ));
+    }
+
+    #[test]
+    fn test_should_include_file_glob() {
+        let include = vec!["**/*.py".to_string()];
+        let exclude = vec!["**/node_modules/**".to_string()];
+
+        assert!(should_include_file(
+            Path::new("src/app/main.py"),
+            Some(&include),
+            Some(&exclude)
+        ));
+        assert!(!should_include_file(
+            Path::new("src/app/main.js"),
+            Some(&include),
+            Some(&exclude)
+        ));
+        assert!(!should_include_file(
+            Path::new("web/node_modules/pkg/index.py"),
+            Some(&include),
+            Some(&exclude)
+        ));
+    }
+
+    #[test]
+    fn test_matches_pattern_windows_paths() {
+        // Backslash paths and patterns match either separator
+        assert!(matches_pattern_with(
+            r"web\node_modules\pkg\index.py",
+            "**/node_modules/**",
+            false
+        ));
+        assert!(matches_pattern_with(
+            "src/vendor/lib.py",
+            r"src\vendor",
+            false
+        ));
+
+        // Case-insensitive matching, as on Windows
+        assert!(matches_pattern_with(
+            r"Web\Node_Modules\pkg\index.py",
+            "**/node_modules/**",
+            true
+        ));
+        assert!(matches_pattern_with(
+            "SRC/Vendor/lib.py",
+            "src/vendor",
+            true
+        ));
+        assert!(!matches_pattern_with(
+            "SRC/Vendor/lib.py",
+            "src/vendor",
+            false
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_scan_git_url() {
+        use std::process::Command;
+
+        let repo = tempfile::TempDir::new().unwrap();
+        fs::write(repo.path().join("app.py"), "# 你好世界\nx = 1\n").unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+                .args(args)
+                .current_dir(repo.path())
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {:?}", args);
+        };
+        git(&["init", "-q"]);
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "init"]);
+
+        let report = repo.path().join("report.json");
+        let url = format!("file://{}", repo.path().display());
+        execute(
+            &url,
+            None,
+            None,
+            None,
+            None,
+            false,
+            false,
+            false,
+            false,
+            &[],
+            false,
+            &[],
+            None,
+            false,
+            Some(report.to_str().unwrap()),
+            None,
+            "json",
+            false,
+        )
+        .await
+        .unwrap();
+
+        let report = fs::read_to_string(report).unwrap();
+        assert!(report.contains("\"app.py\""));
+        assert!(report.contains("你好世界"));
+    }
+
+    #[tokio::test]
+    async fn test_scan_by_package() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("web")).unwrap();
+        fs::write(root.join("web/package.json"), r#"{"name": "web"}"#).unwrap();
+        fs::write(root.join("web/index.js"), "// 你好世界\n").unwrap();
+        fs::create_dir_all(root.join("api")).unwrap();
+        fs::write(root.join("api/setup.py"), "").unwrap();
+        fs::write(
+            root.join("api/app.py"),
+            "# 第一条注释\nx = 1\n# 第二条注释\n",
+        )
+        .unwrap();
+        fs::write(root.join("build.py"), "# 构建脚本\n").unwrap();
+
+        let scan = |names: Vec<String>| {
+            let report = root.join("report.json");
+            let path = root.to_str().unwrap().to_string();
+            async move {
+                execute(
+                    &path,
+                    None,
+                    None,
+                    None,
+                    None,
+                    false,
+                    false,
+                    false,
+                    false,
+                    &names,
+                    true,
+                    &[],
+                    None,
+                    false,
+                    Some(report.to_str().unwrap()),
+                    None,
+                    "json",
+                    false,
+                )
+                .await
+                .map(|_| {
+                    serde_json::from_str::<serde_json::Value>(&fs::read_to_string(&report).unwrap())
+                        .unwrap()
+                })
             }
-        }
+        };
+
+        let report = scan(Vec::new()).await.unwrap();
+        let packages = report["packages"].as_array().unwrap();
+        assert_eq!(packages.len(), 3);
+        assert_eq!(packages[0]["name"], "api");
+        assert_eq!(packages[0]["units"], 2);
+        assert_eq!(packages[1]["name"], "web");
+        assert_eq!(packages[1]["units"], 1);
+        assert_eq!(packages[2]["name"], packages::ROOT_PACKAGE);
+        assert_eq!(packages[2]["units"], 1);
+
+        let report = scan(vec!["web".to_string()]).await.unwrap();
+        let files = report["files"].as_array().unwrap();
+        assert!(files
+            .iter()
+            .all(|file| file["path"].as_str().unwrap().contains("web")));
+        assert!(files
+            .iter()
+            .any(|file| file["path"].as_str().unwrap().ends_with("index.js")));
+
+        assert!(scan(vec!["missing".to_string()]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_scan_group_by() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src/util")).unwrap();
+        fs::write(
+            root.join("src/app.py"),
+            "# 第一条注释\nx = 1\n# 第二条注释\n",
+        )
+        .unwrap();
+        fs::write(root.join("src/util/io.py"), "# 读取文件\n").unwrap();
+        fs::write(root.join("main.js"), "// 你好世界\n").unwrap();
+
+        let report = root.join("report.json");
+        execute(
+            root.to_str().unwrap(),
+            None,
+            None,
+            None,
+            None,
+            false,
+            false,
+            false,
+            false,
+            &[],
+            false,
+            &["dir".to_string(), "ext".to_string()],
+            None,
+            false,
+            Some(report.to_str().unwrap()),
+            None,
+            "json",
+            false,
+        )
+        .await
+        .unwrap();
+
+        let report: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&report).unwrap()).unwrap();
+        let views = report["groups"].as_array().unwrap();
+        assert_eq!(views.len(), 2);
+
+        assert_eq!(views[0]["by"], "dir");
+        let dirs = views[0]["groups"].as_array().unwrap();
+        assert_eq!(dirs[0]["key"], "src");
+        assert_eq!(dirs[0]["units"], 2);
+        assert!(dirs.iter().any(|g| g["key"] == "." && g["units"] == 1));
+        assert!(dirs.iter().any(|g| g["key"] == "src/util"));
+
+        assert_eq!(views[1]["by"], "ext");
+        let exts = views[1]["groups"].as_array().unwrap();
+        assert_eq!(exts[0]["key"], ".py");
+        assert_eq!(
+            (exts[0]["files"].as_u64(), exts[0]["units"].as_u64()),
+            (Some(2), Some(3))
+        );
+        assert_eq!(exts[1]["key"], ".js");
     }
 
-    Ok(output)
+    #[test]
+    fn test_scan_content_parsers() {
+        let notebook = r##"{"cells": [{"cell_type": "markdown", "source": ["# 数据分析"]}]}"##;
+        let result = scan_content(Path::new("analysis.ipynb"), notebook).unwrap();
+        assert_eq!(result.units.len(), 1);
+        assert_eq!(result.units[0].content, "# 数据分析");
+
+        let result = scan_content(Path::new("notes.txt"), "普通文本").unwrap();
+        assert_eq!(result.file_type, "unknown");
+        assert!(result.units.is_empty());
+    }
+
+    #[test]
+    fn test_group_by_language() {
+        use langlint_core::{TranslatableUnit, UnitType};
+
+        let unit = |text: &str, lang: Option<&str>| {
+            let mut unit = TranslatableUnit::new(text.to_string(), UnitType::Comment, 1, 1);
+            unit.detected_language = lang.map(str::to_string);
+            unit
+        };
+        let results = vec![
+            (
+                PathBuf::from("a.py"),
+                ParseResult::new("python", "utf-8", 2).with_units(vec![
+                    unit("你好", Some("zh")),
+                    unit("こんにちは", Some("ja")),
+                ]),
+            ),
+            (
+                PathBuf::from("b.py"),
+                ParseResult::new("python", "utf-8", 2)
+                    .with_units(vec![unit("世界", Some("zh")), unit("???", None)]),
+            ),
+        ];
+
+        let groups = group_results(&results, Path::new(""), GroupBy::Language);
+        let summary: Vec<(&str, usize, usize)> = groups
+            .iter()
+            .map(|g| (g.key.as_str(), g.files, g.units))
+            .collect();
+        assert_eq!(summary, vec![("zh", 2, 2), ("ja", 1, 1), ("unknown", 1, 1)]);
+
+        assert!(GroupBy::parse("owner").is_err());
+    }
+
+    #[test]
+    fn test_sort_results() {
+        use langlint_core::{TranslatableUnit, UnitType};
+
+        let unit = |line: u32, lang: &str| {
+            let mut unit = TranslatableUnit::new("注释".to_string(), UnitType::Comment, line, 1);
+            unit.detected_language = Some(lang.to_string());
+            unit
+        };
+        let results = vec![
+            (
+                PathBuf::from("b.py"),
+                ParseResult::new("python", "utf-8", 50)
+                    .with_units(vec![unit(9, "zh"), unit(2, "zh")]),
+            ),
+            (
+                PathBuf::from("c.py"),
+                ParseResult::new("python", "utf-8", 10).with_units(vec![
+                    unit(1, "ja"),
+                    unit(2, "ja"),
+                    unit(3, "zh"),
+                ]),
+            ),
+            (
+                PathBuf::from("a.py"),
+                ParseResult::new("python", "utf-8", 10).with_units(vec![unit(1, "zh")]),
+            ),
+        ];
+        let order = |sort: &str| {
+            let mut results = results.clone();
+            sort_results(&mut results, SortBy::parse(sort).unwrap());
+            results
+                .iter()
+                .map(|(path, _)| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        assert_eq!(order("path"), "a.py b.py c.py");
+        assert_eq!(order("size"), "b.py a.py c.py");
+        assert_eq!(order("units"), "c.py b.py a.py");
+        assert_eq!(order("language"), "c.py a.py b.py");
+        assert!(SortBy::parse("mtime").is_err());
+
+        let mut sorted = results.clone();
+        sort_results(&mut sorted, SortBy::Path);
+        let lines: Vec<u32> = sorted[1].1.units.iter().map(|u| u.line_number).collect();
+        assert_eq!(lines, vec![2, 9]);
+    }
+
+    #[tokio::test]
+    async fn test_scan_skips_generated_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path();
+        fs::write(root.join("app.py"), "# 你好世界\n").unwrap();
+        fs::write(
+            root.join("api_pb2.py"),
+            "# Generated by the protocol buffer compiler.  DO NOT EDIT!\n# 生成的注释\n",
+        )
+        .unwrap();
+
+        let scan = |include_generated: bool| {
+            let report = root.join("report.json");
+            let path = root.to_str().unwrap().to_string();
+            async move {
+                execute(
+                    &path,
+                    None,
+                    None,
+                    None,
+                    None,
+                    false,
+                    false,
+                    false,
+                    include_generated,
+                    &[],
+                    false,
+                    &[],
+                    None,
+                    false,
+                    Some(report.to_str().unwrap()),
+                    None,
+                    "json",
+                    false,
+                )
+                .await
+                .unwrap();
+                fs::read_to_string(&report).unwrap()
+            }
+        };
+
+        let report = scan(false).await;
+        assert!(report.contains("你好世界"));
+        assert!(!report.contains("api_pb2.py"));
+
+        let report = scan(true).await;
+        assert!(report.contains("生成的注释"));
+    }
 }
@@ -0,0 +1,202 @@
+//! Stats command implementation - detected languages of a tree
+//!
+//! Each file's dominant language is recorded in the state file (see
+//! [`crate::state`]), which later runs use as their default source language.
+//! Files unchanged since the last run are not parsed again.
+
+use anyhow::Result;
+use colored::Colorize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::scan;
+use crate::pipeline::FileErrors;
+use crate::sandbox;
+use crate::state::{self, State};
+
+/// Files and units of a detected language
+#[derive(Debug, Default, Serialize)]
+struct LanguageStats {
+    language: String,
+    files: usize,
+    units: usize,
+}
+
+/// Execute the stats command
+///
+/// Prints the files and units of each detected language, or with
+/// `dominant` the language of each file and the one dominant overall.
+pub async fn execute(
+    path: &str,
+    include: Option<Vec<String>>,
+    exclude: Option<Vec<String>>,
+    dominant: bool,
+    format: &str,
+    verbose: bool,
+) -> Result<()> {
+    let input = Path::new(path);
+    let root = state::root(input);
+    let previous = State::load(&root)?;
+    let mut errors = FileErrors::new(false);
+    let files = scan::collect_files(input, include.as_ref(), exclude.as_ref(), &mut errors)?;
+
+    // Files outside this run stay recorded until they are deleted
+    let mut state = previous.clone();
+    state.files.retain(|file, _| root.join(file).is_file());
+    let mut parsed = 0;
+    for file_path in &files {
+        if let Some(known) = previous.current(&root, file_path) {
+            state.record(&root, file_path, known.language.clone(), known.units);
+            continue;
+        }
+        let results = match scan::scan_input(file_path).await {
+            Ok(results) => results,
+            Err(e) => {
+                errors.record(file_path, e)?;
+                continue;
+            }
+        };
+        parsed += 1;
+        let mut language = None;
+        let mut units = 0;
+        for (_, result) in results {
+            if let Some(dominant) = result.dominant_language() {
+                let count = result
+                    .units
+                    .iter()
+                    .filter(|unit| unit.detected_language.as_ref() == Some(&dominant))
+                    .count();
+                if count > units {
+                    (language, units) = (Some(dominant), count);
+                }
+            }
+        }
+        state.record(&root, file_path, language, units);
+    }
+    if verbose {
+        println!(
+            "{} {} files, {} parsed again",
+            "→".dimmed(),
+            files.len(),
+            parsed
+        );
+    }
+    // A sandboxed run writes nothing
+    if !sandbox::is_active() {
+        state.save(&root)?;
+    }
+
+    let overall = state.dominant("");
+    if dominant {
+        let languages: BTreeMap<&str, Option<&str>> = state
+            .files
+            .iter()
+            .map(|(file, known)| (file.as_str(), known.language.as_deref()))
+            .collect();
+        if format == "json" {
+            let report = serde_json::json!({ "dominant": overall, "files": languages });
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            for (file, language) in &languages {
+                println!("{}  {}", language.unwrap_or("-"), file);
+            }
+            println!(
+                "\n{} {}",
+                "Dominant language:".bold(),
+                overall.as_deref().unwrap_or("none detected")
+            );
+        }
+    } else {
+        let languages = language_stats(&state);
+        if format == "json" {
+            println!("{}", serde_json::to_string_pretty(&languages)?);
+        } else {
+            for stats in &languages {
+                println!(
+                    "{:<8} {:>6} files {:>8} units",
+                    stats.language, stats.files, stats.units
+                );
+            }
+        }
+    }
+    errors.print();
+    Ok(())
+}
+
+/// Files and units per language, most units first
+fn language_stats(state: &State) -> Vec<LanguageStats> {
+    let mut languages: BTreeMap<&str, LanguageStats> = BTreeMap::new();
+    for known in state.files.values() {
+        let Some(language) = &known.language else {
+            continue;
+        };
+        let stats = languages.entry(language).or_insert_with(|| LanguageStats {
+            language: language.clone(),
+            ..LanguageStats::default()
+        });
+        stats.files += 1;
+        stats.units += known.units;
+    }
+    let mut languages: Vec<LanguageStats> = languages.into_values().collect();
+    languages.sort_by_key(|stats| std::cmp::Reverse(stats.units));
+    languages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_stats_records_dominant_language() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(
+            root.join("main.py"),
+            "# 检查用户是否为管理员\nx = 1\n# 返回用户的名字\nname = \"\"\n",
+        )
+        .unwrap();
+        fs::write(root.join("util.py"), "# 计算总数\ny = 2\n").unwrap();
+
+        let path = root.to_string_lossy();
+        execute(&path, None, None, true, "json", false)
+            .await
+            .unwrap();
+
+        let recorded = state::recorded_source(&path).expect("recorded language");
+        assert!(recorded.starts_with("zh"), "{}", recorded);
+
+        let stats = language_stats(&State::load(root).unwrap());
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].language, recorded);
+        assert_eq!((stats[0].files, stats[0].units), (2, 3));
+    }
+
+    #[test]
+    fn test_language_stats() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let mut state = State::default();
+        for (name, language, units) in [
+            ("a.py", Some("ja"), 2),
+            ("b.py", Some("zh-CN"), 4),
+            ("c.py", Some("ja"), 3),
+            ("d.py", None, 0),
+        ] {
+            let file = root.join(name);
+            fs::write(&file, "x = 1\n").unwrap();
+            state.record(root, &file, language.map(str::to_string), units);
+        }
+
+        let stats: Vec<_> = language_stats(&state)
+            .into_iter()
+            .map(|stats| (stats.language, stats.files, stats.units))
+            .collect();
+        assert_eq!(
+            stats,
+            [("ja".to_string(), 2, 5), ("zh-CN".to_string(), 1, 4)]
+        );
+    }
+}
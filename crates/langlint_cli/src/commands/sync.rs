@@ -0,0 +1,458 @@
+//! Sync command implementation - compare parallel translated trees
+//!
+//! Files of the two trees are paired by relative path, ignoring language
+//! tags (`docs/zh/guide.md` and `docs/en/guide.md`, `intro.zh.md` and
+//! `intro.en.md`). Each file is cut into sections, each starting at a
+//! high-priority unit (a heading or a docstring), and the sections of a
+//! pair are aligned on what translation leaves alone: code spans, URLs,
+//! numbers and Latin words, plus the number of units. Sections left without
+//! a partner are missing from the other tree. A translated section is stale
+//! when `git blame` shows its source section changed after it.
+
+use anyhow::Result;
+use colored::Colorize;
+use langlint_core::{paths, Priority, TranslatableUnit};
+use langlint_parsers::{generated, jsx, license, sinks};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use super::scan;
+use crate::blame;
+use crate::pipeline::FileErrors;
+use crate::policy::split_language;
+use crate::report::{self, Finding, Level, Report, ReportContent};
+
+/// Score below which two sections are not considered the same section
+const MATCH_THRESHOLD: f64 = 0.3;
+
+/// A difference between the trees
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SyncIssue {
+    /// `missing_file`, `missing_section` or `stale_section`
+    pub kind: &'static str,
+    /// File lacking something, or holding the stale section
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+impl From<SyncIssue> for Finding {
+    fn from(issue: SyncIssue) -> Self {
+        Self {
+            level: match issue.kind {
+                "stale_section" => Level::Warning,
+                _ => Level::Error,
+            },
+            rule: issue.kind.to_string(),
+            path: issue.path,
+            line: issue.line,
+            message: issue.message,
+        }
+    }
+}
+
+/// A heading or docstring with the units up to the next one
+#[derive(Debug, Clone, PartialEq)]
+struct Section {
+    heading: String,
+    start_line: u32,
+    /// Last line, or `u32::MAX` for the end of the file
+    end_line: u32,
+    units: usize,
+    tokens: HashSet<String>,
+}
+
+/// Execute the sync command
+///
+/// `source` is the reference tree, `translated` the tree kept in sync with
+/// it. Fails when the trees differ.
+pub async fn execute(
+    source: &str,
+    translated: &str,
+    check_stale: bool,
+    format: &str,
+    verbose: bool,
+) -> Result<()> {
+    let mut errors = FileErrors::new(false);
+    let source_files = documents(Path::new(source), &mut errors)?;
+    let translated_files = documents(Path::new(translated), &mut errors)?;
+    if verbose {
+        println!(
+            "{} {} source and {} translated files",
+            "Total:".bold(),
+            source_files.len(),
+            translated_files.len()
+        );
+    }
+
+    let mut issues = Vec::new();
+    for (document, source_path) in &source_files {
+        if !translated_files.contains_key(document) {
+            issues.push(SyncIssue {
+                kind: "missing_file",
+                path: display(&Path::new(translated).join(document)),
+                line: None,
+                message: format!("No translation of {}", display(source_path)),
+            });
+        }
+    }
+    for (document, translated_path) in &translated_files {
+        if !source_files.contains_key(document) {
+            issues.push(SyncIssue {
+                kind: "missing_file",
+                path: display(&Path::new(source).join(document)),
+                line: None,
+                message: format!("No source of {}", display(translated_path)),
+            });
+        }
+    }
+
+    let mut blame_available = check_stale;
+    for (document, source_path) in &source_files {
+        let Some(translated_path) = translated_files.get(document) else {
+            continue;
+        };
+        let (Some(source_sections), Some(translated_sections)) = (
+            file_sections(source_path, &mut errors).await?,
+            file_sections(translated_path, &mut errors).await?,
+        ) else {
+            continue;
+        };
+
+        let pairs = align(&source_sections, &translated_sections);
+        for pair in &pairs {
+            match *pair {
+                (Some(s), None) => issues.push(SyncIssue {
+                    kind: "missing_section",
+                    path: display(translated_path),
+                    line: None,
+                    message: format!(
+                        "Missing section \"{}\" ({}:{})",
+                        source_sections[s].heading,
+                        display(source_path),
+                        source_sections[s].start_line
+                    ),
+                }),
+                (None, Some(t)) => issues.push(SyncIssue {
+                    kind: "missing_section",
+                    path: display(source_path),
+                    line: None,
+                    message: format!(
+                        "Missing section \"{}\" ({}:{})",
+                        translated_sections[t].heading,
+                        display(translated_path),
+                        translated_sections[t].start_line
+                    ),
+                }),
+                _ => {}
+            }
+        }
+
+        if !blame_available {
+            continue;
+        }
+        let (source_blame, translated_blame) = match (
+            blame::blame_file(source_path),
+            blame::blame_file(translated_path),
+        ) {
+            (Ok(source), Ok(translated)) => (source, translated),
+            (Err(e), _) | (_, Err(e)) => {
+                eprintln!(
+                    "{} Stale sections not checked: {:#}",
+                    "Warning:".yellow(),
+                    e
+                );
+                blame_available = false;
+                continue;
+            }
+        };
+        for pair in &pairs {
+            let (Some(s), Some(t)) = *pair else {
+                continue;
+            };
+            let changed = last_change(&source_blame, &source_sections[s]);
+            let translated_changed = last_change(&translated_blame, &translated_sections[t]);
+            if changed > translated_changed {
+                issues.push(SyncIssue {
+                    kind: "stale_section",
+                    path: display(translated_path),
+                    line: Some(translated_sections[t].start_line),
+                    message: format!(
+                        "Section \"{}\" is older than its source ({}:{}, changed {}; translation changed {})",
+                        translated_sections[t].heading,
+                        display(source_path),
+                        source_sections[s].start_line,
+                        date(changed),
+                        date(translated_changed)
+                    ),
+                });
+            }
+        }
+    }
+
+    let findings: Vec<Finding> = issues.iter().cloned().map(Finding::from).collect();
+    print!(
+        "{}",
+        report::render(
+            &Report {
+                content: ReportContent::Findings {
+                    key: "issues",
+                    findings: &findings,
+                    files: source_files.len(),
+                    success: "are in sync",
+                },
+                errors: &[],
+                verbose,
+            },
+            format,
+        )?
+    );
+    errors.print();
+
+    if !issues.is_empty() {
+        anyhow::bail!("{} translation sync issue(s)", issues.len());
+    }
+    Ok(())
+}
+
+/// Files of a tree by their path relative to it, without language tags
+fn documents(root: &Path, errors: &mut FileErrors) -> Result<BTreeMap<String, PathBuf>> {
+    let files = scan::collect_files(root, None, None, errors)?;
+    Ok(files
+        .into_iter()
+        .map(|file| {
+            let relative = file
+                .strip_prefix(root)
+                .ok()
+                .filter(|relative| !relative.as_os_str().is_empty())
+                .map_or_else(|| file.clone(), Path::to_path_buf);
+            (split_language(&paths::to_slash(&relative)).0, file)
+        })
+        .collect())
+}
+
+fn display(path: &Path) -> String {
+    paths::to_slash(path)
+}
+
+/// Sections of a file, or `None` when it cannot be parsed
+async fn file_sections(path: &Path, errors: &mut FileErrors) -> Result<Option<Vec<Section>>> {
+    let mut result = match scan::scan_input(path).await {
+        Ok(mut results) if !results.is_empty() => results.swap_remove(0).1,
+        Ok(_) => return Ok(None),
+        Err(e) => {
+            errors.record(path, e)?;
+            return Ok(None);
+        }
+    };
+    if generated::is_generated(&result) {
+        return Ok(None);
+    }
+    result.units.retain(|unit| {
+        !license::is_license_header(unit) && !sinks::is_sink_string(unit) && !jsx::is_jsx_text(unit)
+    });
+    Ok(Some(sections(&result.units)))
+}
+
+/// Cut units into sections at each high-priority unit
+fn sections(units: &[TranslatableUnit]) -> Vec<Section> {
+    let mut sections: Vec<Section> = Vec::new();
+    for unit in units {
+        let starts_section = unit.priority == Priority::High || sections.is_empty();
+        if starts_section {
+            if let Some(previous) = sections.last_mut() {
+                previous.end_line = unit.line_number.saturating_sub(1).max(previous.start_line);
+            }
+            sections.push(Section {
+                heading: heading(&unit.content),
+                start_line: unit.line_number,
+                end_line: u32::MAX,
+                units: 0,
+                tokens: HashSet::new(),
+            });
+        }
+        let section = sections.last_mut().expect("a section was just pushed");
+        section.units += 1;
+        section.tokens.extend(neutral_tokens(&unit.content));
+    }
+    sections
+}
+
+/// The first line of a unit, shortened
+fn heading(content: &str) -> String {
+    let line = content.trim().lines().next().unwrap_or("");
+    match line.char_indices().nth(50) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line.to_string(),
+    }
+}
+
+/// Parts of a text that translation leaves alone: code spans, URLs,
+/// numbers and Latin-script words, lowercased
+fn neutral_tokens(text: &str) -> impl Iterator<Item = String> + '_ {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| {
+        Regex::new(r"`[^`\n]+`|[A-Za-z][A-Za-z0-9+.\-]*://\S+|\d+(?:\.\d+)*|[A-Za-z_][A-Za-z0-9_]+")
+            .unwrap()
+    });
+    re.find_iter(text)
+        .map(|token| token.as_str().to_lowercase())
+}
+
+/// How likely two sections are the same section in two languages (0 to 1)
+fn similarity(a: &Section, b: &Section) -> f64 {
+    let units = a.units.min(b.units) as f64 / a.units.max(b.units).max(1) as f64;
+    let tokens = match (a.tokens.is_empty(), b.tokens.is_empty()) {
+        // Nothing to compare, e.g. Chinese and Japanese prose
+        (true, true) => 0.5,
+        (true, false) | (false, true) => 0.25,
+        (false, false) => {
+            let shared = a.tokens.intersection(&b.tokens).count() as f64;
+            shared / a.tokens.len().min(b.tokens.len()) as f64
+        }
+    };
+    (units + tokens) / 2.0
+}
+
+/// Pair sections in order, leaving out those without a counterpart
+fn align(source: &[Section], translated: &[Section]) -> Vec<(Option<usize>, Option<usize>)> {
+    let (n, m) = (source.len(), translated.len());
+    // Best total score of aligning the first i and j sections
+    let mut best = vec![vec![0.0f64; m + 1]; n + 1];
+    for i in 1..=n {
+        for j in 1..=m {
+            let matched = best[i - 1][j - 1] + similarity(&source[i - 1], &translated[j - 1])
+                - MATCH_THRESHOLD;
+            best[i][j] = matched.max(best[i - 1][j]).max(best[i][j - 1]);
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (n, m);
+    while i > 0 || j > 0 {
+        if i > 0 && j > 0 {
+            let matched = best[i - 1][j - 1] + similarity(&source[i - 1], &translated[j - 1])
+                - MATCH_THRESHOLD;
+            if (best[i][j] - matched).abs() < f64::EPSILON {
+                pairs.push((Some(i - 1), Some(j - 1)));
+                i -= 1;
+                j -= 1;
+                continue;
+            }
+        }
+        if i > 0 && (j == 0 || best[i][j] == best[i - 1][j]) {
+            pairs.push((Some(i - 1), None));
+            i -= 1;
+        } else {
+            pairs.push((None, Some(j - 1)));
+            j -= 1;
+        }
+    }
+    pairs.reverse();
+    pairs
+}
+
+/// Latest author time of a section's lines; uncommitted lines count as now
+fn last_change(lines: &[blame::LineBlame], section: &Section) -> i64 {
+    if lines.is_empty() {
+        // Untracked file
+        return i64::MAX;
+    }
+    let start = section.start_line.saturating_sub(1) as usize;
+    let end = (section.end_line as usize).min(lines.len());
+    lines
+        .get(start..end.max(start))
+        .unwrap_or_default()
+        .iter()
+        .map(|line| line.time)
+        .max()
+        .unwrap_or(i64::MIN)
+}
+
+fn date(time: i64) -> String {
+    match time {
+        i64::MAX => "uncommitted".to_string(),
+        i64::MIN => "never".to_string(),
+        _ => blame::format_date(time),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use langlint_core::UnitType;
+
+    fn units(items: &[(u32, &str, bool)]) -> Vec<TranslatableUnit> {
+        items
+            .iter()
+            .map(|&(line, content, heading)| {
+                let unit = TranslatableUnit::new(content.to_string(), UnitType::TextNode, line, 1);
+                if heading {
+                    unit.with_priority(Priority::High)
+                } else {
+                    unit
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sections_and_alignment() {
+        let zh = sections(&units(&[
+            (1, "项目简介", true),
+            (3, "Langlint 用于翻译代码注释。", false),
+            (5, "安装", true),
+            (7, "运行 `pip install langlint` 安装。", false),
+            (9, "配置", true),
+            (11, "编辑 `langlint.toml` 文件，设置 `target_lang`。", false),
+            (12, "参见 https://example.com/docs 了解详情。", false),
+        ]));
+        assert_eq!(zh.len(), 3);
+        assert_eq!((zh[1].start_line, zh[1].end_line), (5, 8));
+        assert_eq!(zh[2].end_line, u32::MAX);
+
+        // The translation lacks the installation section
+        let en = sections(&units(&[
+            (1, "About", true),
+            (3, "Langlint translates code comments.", false),
+            (5, "Configuration", true),
+            (
+                7,
+                "Edit the `langlint.toml` file and set `target_lang`.",
+                false,
+            ),
+            (8, "See https://example.com/docs for details.", false),
+        ]));
+        assert_eq!(
+            align(&zh, &en),
+            vec![(Some(0), Some(0)), (Some(1), None), (Some(2), Some(1)),]
+        );
+        assert_eq!(align(&en, &en).len(), 2);
+        assert_eq!(align(&[], &en), vec![(None, Some(0)), (None, Some(1))]);
+    }
+
+    #[test]
+    fn test_last_change() {
+        let line = |time: i64| blame::LineBlame {
+            commit: String::new(),
+            author: String::new(),
+            author_mail: String::new(),
+            time,
+        };
+        let lines = [line(10), line(30), line(20), line(40)];
+        let section = |start_line: u32, end_line: u32| Section {
+            heading: String::new(),
+            start_line,
+            end_line,
+            units: 1,
+            tokens: HashSet::new(),
+        };
+        assert_eq!(last_change(&lines, &section(1, 3)), 30);
+        assert_eq!(last_change(&lines, &section(3, u32::MAX)), 40);
+        assert_eq!(last_change(&[], &section(1, 2)), i64::MAX);
+    }
+}
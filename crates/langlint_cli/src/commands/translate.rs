@@ -2,13 +2,28 @@
 
 use anyhow::{Context, Result};
 use colored::Colorize;
-use indicatif::{ProgressBar, ProgressStyle};
-use langlint_core::ParseResult;
-use langlint_parsers::{GenericCodeParser, NotebookParser, Parser, PythonParser};
-use langlint_translators::{GoogleTranslator, MockTranslator, Translator};
+use langlint_core::{encoding, paths, ParseResult, TranslatableUnit, TranslationMap};
+use langlint_parsers::Parser;
+use langlint_translators::{TextMetrics, Translator};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
+use std::time::Duration;
+
+use crate::archive::{self, ArchiveKind};
+use crate::checkpoint::Checkpoint;
+use crate::color;
+use crate::directives;
+use crate::interrupt;
+use crate::limits::{self, FileLimits};
+use crate::manifest::{self, RunManifest};
+use crate::overrides;
+use crate::pipeline::{
+    self, FileErrors, FileOptions, HistoryFilter, NotebookCells, QualityGate, QuarantineReport,
+    Quarantined, RunStats, TranslatedUnits,
+};
+use crate::ui::tr;
+use crate::walk;
 
 /// Execute the translate command
 #[allow(clippy::too_many_arguments)]
@@ -17,8 +32,23 @@ pub async fn execute(
     source: &str,
     target: &str,
     translator_name: &str,
+    routes: &BTreeMap<String, String>,
     output: Option<&str>,
     dry_run: bool,
+    gate: QualityGate,
+    history: HistoryFilter,
+    write_map: bool,
+    source_map: bool,
+    include_todos: bool,
+    include_strings: bool,
+    include_license_headers: bool,
+    include_generated: bool,
+    notebook_cells: NotebookCells,
+    force: Vec<String>,
+    output_encoding: Option<&str>,
+    strict: bool,
+    resume: bool,
+    mut manifest: Option<RunManifest>,
     _format: &str,
     verbose: bool,
 ) -> Result<()> {
@@ -28,25 +58,34 @@ pub async fn execute(
         println!("  Target language: {}", target);
         println!("  Translator: {}", translator_name);
         if dry_run {
-            println!("  {}", "DRY RUN MODE".yellow().bold());
+            println!("  {}", tr!("dry-run-mode").yellow().bold());
         }
     }
 
     // Create translator
-    let translator: Box<dyn Translator> = match translator_name {
-        "mock" => Box::new(MockTranslator::new()),
-        "google" => Box::new(GoogleTranslator::new()?),
-        _ => anyhow::bail!("Unknown translator: {}", translator_name),
-    };
+    let translator = pipeline::build_translator(translator_name, routes)?;
 
     if verbose {
         println!("{} Translator created", "✓".green());
     }
+    if let Some(manifest) = &mut manifest {
+        manifest.start(path, source, target, translator.as_ref());
+    }
 
     let path_obj = Path::new(path);
 
     // Collect files to translate
-    let files = collect_files(path_obj)?;
+    let mut errors = FileErrors::new(strict);
+    let mut files = collect_files(path_obj, &mut errors)?;
+    if resume {
+        let checkpoint = Checkpoint::load(path_obj)?.with_context(|| {
+            format!(
+                "No checkpoint to resume: {}",
+                Checkpoint::path_for(path_obj).display()
+            )
+        })?;
+        files = checkpoint.resume(path_obj, "translate", target, files)?;
+    }
 
     if files.is_empty() {
         println!("{} No translatable files found", "!".yellow());
@@ -57,20 +96,47 @@ pub async fn execute(
         println!("{} {} files found", "Total:".bold(), files.len());
     }
 
+    if history.is_active() && files.iter().any(|f| ArchiveKind::from_path(f).is_some()) {
+        anyhow::bail!("--modified-before/--modified-after/--author cannot be used with archives");
+    }
+
     // Setup progress bar
-    let pb = ProgressBar::new(files.len() as u64);
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template("{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} {msg}")
-            .unwrap()
-            .progress_chars("=>-"),
-    );
+    let pb = color::progress_bar(files.len() as u64);
+
+    let options = FileOptions {
+        map_run: write_map.then(pipeline::run_timestamp),
+        source_map,
+        include_todos,
+        include_strings,
+        include_license_headers,
+        include_generated,
+        notebook_cells,
+        history,
+        output_encoding: pipeline::output_encoding(output_encoding)?,
+        force,
+    };
+
+    if dry_run {
+        pb.finish_and_clear();
+        return report_dry_run(
+            &files,
+            source,
+            target,
+            translator.as_ref(),
+            &options,
+            limits::current(),
+            &mut errors,
+        );
+    }
 
     let mut translated_count = 0;
-    let mut error_count = 0;
-    let mut total_units = 0;
+    let mut stats = RunStats::default();
+    let mut quarantine = QuarantineReport::default();
+    let mut budget_stop = None;
 
-    for file_path in &files {
+    // Ctrl+C stops after the file being translated is dropped, not mid-write
+    interrupt::install();
+    for (index, file_path) in files.iter().enumerate() {
         let filename = file_path.file_name().unwrap().to_string_lossy();
         pb.set_message(format!("Translating {}", filename));
 
@@ -87,40 +153,66 @@ pub async fn execute(
             file_path.clone()
         };
 
-        match translate_single_file(
+        let bytes_before = manifest::file_size(file_path);
+        match interrupt::or_interrupted(translate_single_file(
             file_path,
             &output_file_path,
             source,
             target,
             translator.as_ref(),
-            dry_run,
+            &options,
             verbose,
-        )
+        ))
         .await
         {
-            Ok(units) => {
-                if units > 0 {
+            Ok(file_stats) => {
+                let file_stats = file_stats.in_file(&file_path.display().to_string());
+                if let Some(manifest) = &mut manifest {
+                    manifest.record(file_path, &output_file_path, bytes_before, &file_stats);
+                }
+                stats.merge(&file_stats);
+                if file_stats.units > 0 {
                     translated_count += 1;
-                    total_units += units;
                     if verbose {
                         pb.println(format!(
                             "{} {} → {} ({} units)",
                             "✓".green(),
                             file_path.display(),
                             output_file_path.display(),
-                            units
+                            file_stats.units
                         ));
                     }
                 }
             }
+            Err(e) if pipeline::is_budget_exceeded(&e) || interrupt::is_interrupt(&e) => {
+                pb.println(format!(
+                    "{} Stopping at {}: {:#}",
+                    "✗".red(),
+                    file_path.display(),
+                    e
+                ));
+                budget_stop = Some((index, e));
+                break;
+            }
+            Err(e) if quarantine.record(file_path, &e) => {
+                pb.println(format!(
+                    "{} Quarantined {}: {}",
+                    "⚠".yellow(),
+                    file_path.display(),
+                    e
+                ));
+            }
             Err(e) => {
-                error_count += 1;
                 pb.println(format!(
-                    "{} Failed to translate {}: {}",
+                    "{} Failed to translate {}: {:#}",
                     "✗".red(),
                     file_path.display(),
                     e
                 ));
+                if let Err(e) = errors.record(file_path, e) {
+                    pb.abandon();
+                    return Err(e);
+                }
             }
         }
 
@@ -130,192 +222,562 @@ pub async fn execute(
     pb.finish_with_message("Translation complete");
 
     // Summary
-    println!("\n{}", "Summary:".bold().green());
-    println!("  Files processed: {}", files.len());
-    println!("  Files translated: {}", translated_count);
-    println!("  Total units translated: {}", total_units);
-    if error_count > 0 {
-        println!("  {} Errors: {}", "⚠".yellow(), error_count);
+    println!("\n{}", tr!("summary").bold().green());
+    // A stopped run reports the files it got through
+    let processed = budget_stop
+        .as_ref()
+        .map_or(files.len(), |(stopped_at, _)| *stopped_at);
+    println!("  {}", tr!("files-processed", count = processed));
+    println!("  {}", tr!("files-translated", count = translated_count));
+    println!("  {}", tr!("units-translated", count = stats.units));
+    if stats.units > 0 {
+        println!(
+            "  {}",
+            tr!(
+                "average-confidence",
+                value = format!("{:.2}", stats.average_confidence())
+            )
+        );
+        println!(
+            "  {}",
+            tr!(
+                "failed-units",
+                count = stats.failed,
+                percent = format!("{:.1}", stats.failure_rate() * 100.0)
+            )
+        );
+    }
+    if stats.skipped > 0 {
+        println!("  {}", tr!("skipped-units", count = stats.skipped));
+    }
+    if !errors.is_empty() {
+        println!(
+            "  {} {}",
+            "⚠".yellow(),
+            tr!("error-count", count = errors.len())
+        );
+    }
+    if !errors.limited().is_empty() {
+        println!("  {}", tr!("limited-count", count = errors.limited().len()));
+    }
+    if !quarantine.is_empty() {
+        println!(
+            "  {} {}",
+            "⚠".yellow(),
+            tr!("quarantined-count", count = quarantine.len())
+        );
+    }
+    if let Some(run) = options.map_run {
+        println!("  {}", tr!("mapping-run", run = run));
+    }
+    if let Some(manifest) = &manifest {
+        println!("  {}", tr!("manifest", path = manifest.path().display()));
     }
+    if !stats.review.is_empty() {
+        println!(
+            "  {} {}",
+            "⚠".yellow(),
+            tr!("review-units", count = stats.review.len())
+        );
+    }
+    pipeline::print_cost_report();
 
-    if dry_run {
-        println!("\n{} Dry run completed (no changes made)", "✓".green());
-    } else if let Some(output_dir) = output {
+    stats.print_review();
+    quarantine.print();
+    errors.print();
+    if let Some(manifest) = manifest {
+        manifest.finish(budget_stop.is_none(), errors.len(), quarantine.len())?;
+    }
+    if let Some((stopped_at, e)) = budget_stop {
+        let checkpoint = Checkpoint::new(
+            "translate",
+            source,
+            target,
+            &e,
+            path_obj,
+            &files,
+            stopped_at,
+        );
+        let saved = checkpoint.save(path_obj)?;
+        checkpoint.print(path_obj, &saved);
+        let stop = if interrupt::is_interrupt(&e) {
+            "Interrupted"
+        } else {
+            "Budget exhausted"
+        };
+        return Err(e.context(format!(
+            "{}, {} files left untranslated",
+            stop,
+            files.len() - stopped_at
+        )));
+    }
+    if resume {
+        Checkpoint::remove(path_obj)?;
+    }
+
+    if let Some(output_dir) = output {
         println!(
-            "\n{} Translation complete! Files written to: {}",
+            "\n{} {} {}",
             "✓".green().bold(),
-            output_dir
+            tr!("translation-complete"),
+            tr!("files-written-to", path = output_dir)
         );
     } else {
         println!(
-            "\n{} Translation complete! Files overwritten (backups created with .backup extension)",
-            "✓".green().bold()
+            "\n{} {} {}",
+            "✓".green().bold(),
+            tr!("translation-complete"),
+            tr!("files-overwritten")
         );
     }
 
-    Ok(())
+    gate.check(&stats)
 }
 
-/// Translate a single file
+/// Translate a single file (or archive)
 async fn translate_single_file(
     input_path: &Path,
     output_path: &Path,
     source: &str,
     target: &str,
     translator: &dyn Translator,
-    dry_run: bool,
+    options: &FileOptions,
     verbose: bool,
-) -> Result<usize> {
+) -> Result<RunStats> {
+    if ArchiveKind::from_path(input_path).is_some() {
+        return translate_archive(
+            input_path,
+            output_path,
+            source,
+            target,
+            translator,
+            options,
+            verbose,
+        )
+        .await;
+    }
+
     // Read file
-    let content = fs::read_to_string(input_path)
-        .with_context(|| format!("Failed to read file: {}", input_path.display()))?;
+    let (content, input_encoding) = encoding::read_file(input_path)?;
 
     let path_str = input_path.to_string_lossy();
 
+    let Some(translated) = translate_content(
+        &path_str, &content, source, target, translator, options, verbose,
+    )
+    .await?
+    else {
+        return Ok(RunStats::default());
+    };
+
+    // Create output directory if needed
+    create_parent_dir(output_path)?;
+
+    // If output path is the same as input path, create backup
+    if output_path == input_path {
+        create_backup(input_path, verbose)?;
+    }
+
+    // Write output
+    let output_encoding = options.output_encoding.unwrap_or(input_encoding);
+    encoding::write_file(output_path, &translated.content, output_encoding)?;
+
+    if let Some(run) = options.map_run {
+        let map = TranslationMap::from_units(
+            &translated.original_units,
+            &translated.units.units,
+            source,
+            target,
+        )
+        .with_run(run);
+        let map_path = TranslationMap::path_for(output_path);
+        map.save(&map_path)?;
+
+        if verbose {
+            println!("  {} Mapping written: {}", "✓".green(), map_path.display());
+        }
+    }
+    if options.source_map {
+        pipeline::write_source_map(output_path, &content, &translated.content, verbose)?;
+    }
+
+    Ok(translated.units.stats)
+}
+
+/// Translate every supported entry of an archive in memory and write the
+/// translated archive
+///
+/// Other entries are copied unchanged. Sidecar mapping files are not
+/// written for archive entries.
+async fn translate_archive(
+    input_path: &Path,
+    output_path: &Path,
+    source: &str,
+    target: &str,
+    translator: &dyn Translator,
+    options: &FileOptions,
+    verbose: bool,
+) -> Result<RunStats> {
+    let mut entries = archive::read_archive(input_path)?;
+    let mut stats = RunStats::default();
+
+    for entry in &mut entries {
+        if get_parser_for_file(&entry.name).is_err() {
+            continue;
+        }
+        let Some(content) = entry.text() else {
+            continue;
+        };
+
+        if verbose {
+            println!("  {} {}", "Entry:".dimmed(), entry.name);
+        }
+        let name = entry.name.clone();
+        let translated =
+            translate_content(&name, content, source, target, translator, options, verbose)
+                .await
+                .map_err(|e| match e.downcast::<Quarantined>() {
+                    // Name the entry, the report only lists the archive
+                    Ok(quarantined) => Quarantined {
+                        reasons: quarantined
+                            .reasons
+                            .into_iter()
+                            .map(|reason| format!("{}: {}", name, reason))
+                            .collect(),
+                    }
+                    .into(),
+                    Err(e) => limits::in_entry(e, &name),
+                })?;
+        if let Some(translated) = translated {
+            entry.data = translated.content.into_bytes();
+            stats.merge(&translated.units.stats.in_file(&name));
+        }
+    }
+
+    if stats.units == 0 {
+        return Ok(stats);
+    }
+
+    create_parent_dir(output_path)?;
+    if output_path == input_path {
+        create_backup(input_path, verbose)?;
+    }
+    archive::write_archive(output_path, &entries)?;
+
+    Ok(stats)
+}
+
+/// Translated file content plus the units before and after translation
+struct TranslatedContent {
+    content: String,
+    original_units: Vec<TranslatableUnit>,
+    units: TranslatedUnits,
+}
+
+/// Parse, translate and reconstruct file content
+///
+/// Returns `None` if the content has no translatable units, and a
+/// [`Quarantined`] error if the result fails verification and the file is
+/// not forced.
+async fn translate_content(
+    path_str: &str,
+    content: &str,
+    source: &str,
+    target: &str,
+    translator: &dyn Translator,
+    options: &FileOptions,
+    verbose: bool,
+) -> Result<Option<TranslatedContent>> {
     // Parse file to extract translatable units
-    let parse_result = parse_file(&path_str, &content)?;
+    let mut parse_result = parse_file(path_str, content)?;
+    options.select_units(&mut parse_result);
+    options
+        .history
+        .select_units(Path::new(path_str), &mut parse_result)?;
     let unit_count = parse_result.units.len();
+    limits::check_units(Path::new(path_str), unit_count)?;
 
     if unit_count == 0 {
         if verbose {
             println!("  {} No translatable units", "→".dimmed());
         }
-        return Ok(0);
+        return Ok(None);
     }
 
     if verbose {
         println!("  Found {} translatable units", unit_count);
     }
 
-    if dry_run {
-        return Ok(unit_count);
+    // Translate all units, honouring `langlint:` directives
+    let pins = directives::pins(&parse_result.units, &directives::parse_file(content)?);
+    let translated = pipeline::translate_pinned_units(
+        &parse_result,
+        Some(path_str),
+        &pins,
+        source,
+        target,
+        translator,
+    )
+    .await?;
+
+    // Reconstruct file with translations
+    let parser = get_parser_for_file(path_str)?;
+    let reconstructed = parser.reconstruct(content, &translated.units, path_str)?;
+
+    let reasons = pipeline::verify_reconstruction(
+        content,
+        &reconstructed,
+        &parse_result.units,
+        &translated.units,
+    );
+    if !reasons.is_empty() {
+        if !options.is_forced(path_str) {
+            return Err(Quarantined { reasons }.into());
+        }
+        if verbose {
+            println!("  {} Forced despite: {}", "⚠".yellow(), reasons.join("; "));
+        }
     }
 
-    // Translate all units
-    let texts: Vec<String> = parse_result
-        .units
-        .iter()
-        .map(|u| u.content.clone())
-        .collect();
+    Ok(Some(TranslatedContent {
+        content: reconstructed,
+        original_units: parse_result.units,
+        units: translated,
+    }))
+}
 
-    let translations = translator.translate_batch(&texts, source, target).await?;
+/// Create the parent directory of an output path if needed
+fn create_parent_dir(path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(paths::long_path(parent))
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+    Ok(())
+}
 
-    // Create new units with translations
-    let mut translated_units = parse_result.units.clone();
-    for (i, trans) in translations.iter().enumerate() {
-        if i < translated_units.len() {
-            translated_units[i].content = trans.translated_text.clone();
-        }
+/// Copy a file to `<file>.backup` before overwriting it
+fn create_backup(path: &Path, verbose: bool) -> Result<()> {
+    let backup_path = format!("{}.backup", path.display());
+    fs::copy(
+        paths::long_path(path),
+        paths::long_path(Path::new(&backup_path)),
+    )
+    .with_context(|| format!("Failed to create backup: {}", backup_path))?;
+
+    if verbose {
+        println!("  {} Backup created: {}", "✓".green(), backup_path);
     }
+    Ok(())
+}
 
-    // Reconstruct file with translations
-    let parser = get_parser_for_file(&path_str)?;
-    let reconstructed = parser.reconstruct(&content, &translated_units, &path_str)?;
+/// Projected workload for a single file in dry-run mode
+#[derive(Debug, Default, Clone, PartialEq)]
+struct FileEstimate {
+    units: usize,
+    text: TextMetrics,
+    requests: usize,
+    cost: f64,
+    duration: Duration,
+}
 
-    // Create output directory if needed
-    if let Some(parent) = output_path.parent() {
-        fs::create_dir_all(parent)
-            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+impl FileEstimate {
+    /// Estimate the translation workload for parsed units
+    fn from_units(
+        parse_result: &ParseResult,
+        source: &str,
+        target: &str,
+        translator: &dyn Translator,
+    ) -> Self {
+        let units = parse_result.units.len();
+        let mut text = TextMetrics::default();
+        for unit in &parse_result.units {
+            text.add(&TextMetrics::measure(&unit.content));
+        }
+        Self {
+            units,
+            text,
+            requests: translator.estimate_requests(units),
+            cost: parse_result
+                .units
+                .iter()
+                .map(|u| translator.estimate_cost(&u.content, source, target))
+                .sum(),
+            duration: translator.estimate_duration(units),
+        }
     }
 
-    // If output path is the same as input path, create backup
-    if output_path == input_path {
-        let backup_path = format!("{}.backup", input_path.display());
-        fs::copy(input_path, &backup_path)
-            .with_context(|| format!("Failed to create backup: {}", backup_path))?;
+    fn add(&mut self, other: &FileEstimate) {
+        self.units += other.units;
+        self.text.add(&other.text);
+        self.requests += other.requests;
+        self.cost += other.cost;
+        self.duration += other.duration;
+    }
+}
 
-        if verbose {
-            println!("  {} Backup created: {}", "✓".green(), backup_path);
+/// Parse every file and print projected units, characters, requests, cost and time
+fn report_dry_run(
+    files: &[PathBuf],
+    source: &str,
+    target: &str,
+    translator: &dyn Translator,
+    options: &FileOptions,
+    file_limits: &FileLimits,
+    errors: &mut FileErrors,
+) -> Result<()> {
+    println!("\n{}", "Dry run estimate:".bold().cyan());
+
+    let mut total = FileEstimate::default();
+    let mut error_count = 0;
+
+    for file_path in files {
+        let estimate = parse_input(file_path).and_then(|results| {
+            let mut estimate = FileEstimate::default();
+            for (entry, mut result) in results {
+                options.select_units(&mut result);
+                options.history.select_units(file_path, &mut result)?;
+                match &entry {
+                    Some(name) => file_limits
+                        .check_units(Path::new(name), result.units.len())
+                        .map_err(|e| limits::in_entry(e, name))?,
+                    None => file_limits.check_units(file_path, result.units.len())?,
+                }
+                estimate.add(&FileEstimate::from_units(
+                    &result, source, target, translator,
+                ));
+            }
+            Ok(estimate)
+        });
+
+        match estimate {
+            Ok(estimate) => {
+                if estimate.units > 0 {
+                    println!(
+                        "  {} {} units, {} chars, {} requests",
+                        file_path.display(),
+                        estimate.units,
+                        estimate.text.chars,
+                        estimate.requests
+                    );
+                }
+                total.add(&estimate);
+            }
+            Err(e) if e.is::<limits::OverLimit>() => errors.record(file_path, e)?,
+            Err(e) => {
+                error_count += 1;
+                println!("  {} {}: {}", "✗".red(), file_path.display(), e);
+            }
         }
     }
 
-    // Write output
-    fs::write(output_path, reconstructed)
-        .with_context(|| format!("Failed to write to: {}", output_path.display()))?;
+    println!("\n{}", tr!("summary").bold().green());
+    println!("  {}", tr!("files-processed", count = files.len()));
+    println!("  Total units: {}", total.units);
+    println!(
+        "  Total characters: {} ({} CJK, {} UTF-8 bytes)",
+        total.text.chars, total.text.cjk_chars, total.text.bytes
+    );
+    if let Some(pricing) = translator.pricing() {
+        println!(
+            "  Billable {}: {}",
+            pricing.unit,
+            total.text.billable(pricing.unit)
+        );
+    }
+    println!("  Estimated requests: {}", total.requests);
+    println!(
+        "  Projected cost ({}): ${:.4}",
+        translator.name(),
+        total.cost
+    );
+    println!("  Estimated time: {}", format_duration(total.duration));
+    if error_count > 0 {
+        println!("  {} Errors: {}", "⚠".yellow(), error_count);
+    }
+    if !errors.limited().is_empty() {
+        println!("  {}", tr!("limited-count", count = errors.limited().len()));
+    }
+    errors.print();
+
+    println!("\n{} Dry run completed (no changes made)", "✓".green());
 
-    Ok(unit_count)
+    Ok(())
+}
+
+/// Parse a file, or every supported text entry of an archive, named by the
+/// entry
+fn parse_input(path: &Path) -> Result<Vec<(Option<String>, ParseResult)>> {
+    if ArchiveKind::from_path(path).is_some() {
+        return Ok(archive::read_archive(path)?
+            .iter()
+            .filter(|entry| get_parser_for_file(&entry.name).is_ok())
+            .filter_map(|entry| Some((entry.name.as_str(), entry.text()?)))
+            .filter_map(|(name, content)| {
+                Some((Some(name.to_string()), parse_file(name, content).ok()?))
+            })
+            .collect());
+    }
+
+    let (content, _) = encoding::read_file(path)?;
+    Ok(vec![(None, parse_file(&path.to_string_lossy(), &content)?)])
+}
+
+/// Format a duration as a short human-readable ETA (e.g. "1h 02m", "3m 05s", "12s")
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}h {:02}m", secs / 3600, (secs % 3600) / 60)
+    } else if secs >= 60 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else if secs > 0 {
+        format!("{}s", secs)
+    } else {
+        format!("{}ms", duration.as_millis())
+    }
 }
 
 /// Collect files to translate
-fn collect_files(path: &Path) -> Result<Vec<PathBuf>> {
+///
+/// Unreadable directory entries are recorded in `errors`.
+fn collect_files(path: &Path, errors: &mut FileErrors) -> Result<Vec<PathBuf>> {
     let mut files = Vec::new();
 
     if path.is_file() {
-        if should_translate(path) {
-            files.push(path.to_path_buf());
+        if should_translate(path) || ArchiveKind::from_path(path).is_some() {
+            match limits::check_size(path) {
+                Ok(()) => files.push(path.to_path_buf()),
+                Err(e) => errors.record(path, e)?,
+            }
         }
         return Ok(files);
     }
 
-    let root_path = path.to_path_buf();
-
     // Walk directory
-    for entry in WalkDir::new(path)
-        .follow_links(true)
+    for entry in walk::walker(path)
         .into_iter()
-        .filter_entry(|e| {
-            // Don't filter the root directory itself
-            if e.path() == root_path {
-                return true;
-            }
-
-            let name = e.file_name().to_string_lossy();
-            !name.starts_with('.')
-                && name != "node_modules"
-                && name != "target"
-                && name != "__pycache__"
-                && name != "venv"
-                && name != ".venv"
-                && name != "build"
-                && name != "dist"
-        })
+        .filter_entry(walk::keep_entry)
     {
-        let entry = entry?;
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                errors.record_walk(e)?;
+                continue;
+            }
+        };
         if entry.file_type().is_file() && should_translate(entry.path()) {
-            files.push(entry.path().to_path_buf());
+            match limits::check_size(entry.path()) {
+                Ok(()) => files.push(entry.path().to_path_buf()),
+                Err(e) => errors.record(entry.path(), e)?,
+            }
         }
     }
 
     Ok(files)
 }
 
-/// Check if a file should be translated
+/// Check if a file should be translated: some parser handles it
 fn should_translate(path: &Path) -> bool {
-    if let Some(ext) = path.extension() {
-        let ext_str = ext.to_string_lossy();
-        matches!(
-            ext_str.as_ref(),
-            "py" | "js"
-                | "ts"
-                | "jsx"
-                | "tsx"
-                | "rs"
-                | "go"
-                | "java"
-                | "c"
-                | "cpp"
-                | "h"
-                | "hpp"
-                | "cs"
-                | "php"
-                | "rb"
-                | "sh"
-                | "bash"
-                | "sql"
-                | "r"
-                | "R"
-                | "m"
-                | "scala"
-                | "kt"
-                | "swift"
-                | "dart"
-                | "lua"
-                | "vim"
-                | "ipynb"
-        )
-    } else {
-        false
-    }
+    overrides::parser_for(path, None).is_some()
 }
 
 /// Parse a file and extract translatable units
@@ -328,25 +790,8 @@ fn parse_file(path: &str, content: &str) -> Result<ParseResult> {
 
 /// Get appropriate parser for a file
 fn get_parser_for_file(path: &str) -> Result<Box<dyn Parser>> {
-    // Try Python parser first
-    let python_parser = PythonParser::new();
-    if python_parser.can_parse(path, None) {
-        return Ok(Box::new(python_parser));
-    }
-
-    // Try Notebook parser
-    let notebook_parser = NotebookParser::new();
-    if notebook_parser.can_parse(path, None) {
-        return Ok(Box::new(notebook_parser));
-    }
-
-    // Try generic code parser
-    let generic_parser = GenericCodeParser::new();
-    if generic_parser.can_parse(path, None) {
-        return Ok(Box::new(generic_parser));
-    }
-
-    anyhow::bail!("No suitable parser found for file: {}", path)
+    overrides::parser_for(Path::new(path), None)
+        .with_context(|| format!("No suitable parser found for file: {}", path))
 }
 
 #[cfg(test)]
@@ -362,7 +807,20 @@ mod tests {
         assert!(should_translate(Path::new("test.rs")));
         assert!(should_translate(Path::new("test.ipynb")));
         assert!(!should_translate(Path::new("test.txt")));
-        assert!(!should_translate(Path::new("README.md")));
+        assert!(should_translate(Path::new("README.md")));
+        assert!(should_translate(Path::new("docs/index.rst")));
+        assert!(should_translate(Path::new("templates/base.html")));
+        assert!(should_translate(Path::new("paper/main.tex")));
+        assert!(should_translate(Path::new("locale/fr/LC_MESSAGES/app.po")));
+        assert!(should_translate(Path::new("src/locales/zh-CN.json")));
+        assert!(should_translate(Path::new(
+            "app/src/main/res/values/strings.xml"
+        )));
+        assert!(should_translate(Path::new(
+            "Base.lproj/Localizable.strings"
+        )));
+        assert!(should_translate(Path::new("l10n/app.zh.xlf")));
+        assert!(!should_translate(Path::new("package.json")));
     }
 
     #[test]
@@ -373,13 +831,41 @@ mod tests {
         assert!(get_parser_for_file("test.txt").is_err());
     }
 
+    #[test]
+    fn test_file_estimate() {
+        let translator = langlint_translators::MockTranslator::new();
+        let result = parse_file("test.py", "# 你好世界\nx = 1\n# 第二条注释\n").unwrap();
+
+        let estimate = FileEstimate::from_units(&result, "zh", "en", &translator);
+        assert_eq!(estimate.units, 2);
+        assert_eq!(estimate.text.chars, 4 + 5);
+        assert_eq!(estimate.text.cjk_chars, 4 + 5);
+        assert_eq!(estimate.text.bytes, 3 * (4 + 5));
+        assert_eq!(estimate.requests, 1);
+        assert_eq!(estimate.cost, 0.0);
+
+        let mut total = FileEstimate::default();
+        total.add(&estimate);
+        total.add(&estimate);
+        assert_eq!(total.units, 4);
+        assert_eq!(total.requests, 2);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(250)), "250ms");
+        assert_eq!(format_duration(Duration::from_secs(12)), "12s");
+        assert_eq!(format_duration(Duration::from_secs(185)), "3m 05s");
+        assert_eq!(format_duration(Duration::from_secs(3720)), "1h 02m");
+    }
+
     #[test]
     fn test_collect_files_single_file() {
         let temp_dir = TempDir::new().unwrap();
         let file_path = temp_dir.path().join("test.py");
         fs::write(&file_path, "# test").unwrap();
 
-        let files = collect_files(&file_path).unwrap();
+        let files = collect_files(&file_path, &mut FileErrors::default()).unwrap();
         assert_eq!(files.len(), 1);
         assert_eq!(files[0], file_path);
     }
@@ -393,7 +879,7 @@ mod tests {
         fs::write(temp_dir.path().join("test2.js"), "// test2").unwrap();
         fs::write(temp_dir.path().join("readme.txt"), "readme").unwrap(); // Should be ignored
 
-        let files = collect_files(temp_dir.path()).unwrap();
+        let files = collect_files(temp_dir.path(), &mut FileErrors::default()).unwrap();
         assert_eq!(files.len(), 2); // Only .py and .js files
     }
 
@@ -409,7 +895,7 @@ mod tests {
         // Create a normal file
         fs::write(temp_dir.path().join("test.py"), "# visible").unwrap();
 
-        let files = collect_files(temp_dir.path()).unwrap();
+        let files = collect_files(temp_dir.path(), &mut FileErrors::default()).unwrap();
         assert_eq!(files.len(), 1); // Only visible file
     }
 
@@ -427,7 +913,7 @@ mod tests {
         // Create a normal file
         fs::write(temp_dir.path().join("test.py"), "# visible").unwrap();
 
-        let files = collect_files(temp_dir.path()).unwrap();
+        let files = collect_files(temp_dir.path(), &mut FileErrors::default()).unwrap();
         assert_eq!(files.len(), 1); // Only visible file
     }
 
@@ -444,7 +930,173 @@ mod tests {
         fs::write(src_dir.join("lib.py"), "# lib").unwrap();
         fs::write(utils_dir.join("helper.py"), "# helper").unwrap();
 
-        let files = collect_files(temp_dir.path()).unwrap();
+        let files = collect_files(temp_dir.path(), &mut FileErrors::default()).unwrap();
         assert_eq!(files.len(), 3); // All .py files in all directories
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_collect_files_reports_unreadable_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("main.py"), "# main").unwrap();
+        std::os::unix::fs::symlink(
+            temp_dir.path().join("missing.py"),
+            temp_dir.path().join("broken.py"),
+        )
+        .unwrap();
+
+        let mut errors = FileErrors::new(false);
+        let files = collect_files(temp_dir.path(), &mut errors).unwrap();
+        assert_eq!(files, vec![temp_dir.path().join("main.py")]);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors.files()[0].kind, "not found");
+
+        assert!(collect_files(temp_dir.path(), &mut FileErrors::new(true)).is_err());
+    }
+
+    #[test]
+    fn test_dry_run_unit_limit() {
+        let file_limits = FileLimits::new(&[langlint_core::FileLimit {
+            files: vec!["dry_run_limited.py".to_string()],
+            max_size: None,
+            max_units: Some(1),
+        }])
+        .unwrap();
+        let temp_dir = TempDir::new().unwrap();
+        let limited = temp_dir.path().join("dry_run_limited.py");
+        let other = temp_dir.path().join("other.py");
+        fs::write(
+            &limited,
+            "# 第一行注释
+x = 1
+# 第二行注释
+",
+        )
+        .unwrap();
+        fs::write(
+            &other,
+            "# 第一行注释
+x = 1
+# 第二行注释
+",
+        )
+        .unwrap();
+
+        let translator = langlint_translators::MockTranslator::new();
+        let mut errors = FileErrors::default();
+        report_dry_run(
+            &[limited.clone(), other],
+            "zh",
+            "en",
+            &translator,
+            &FileOptions::default(),
+            &file_limits,
+            &mut errors,
+        )
+        .unwrap();
+        assert_eq!(
+            errors.limited(),
+            [(
+                limited.display().to_string(),
+                "2 units exceed the limit of 1".to_string()
+            )]
+        );
+        assert!(errors.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_translate_archive() {
+        use crate::archive::ArchiveEntry;
+
+        let temp_dir = TempDir::new().unwrap();
+        let input = temp_dir.path().join("src.zip");
+        let output = temp_dir.path().join("out/src.zip");
+        archive::write_archive(
+            &input,
+            &[
+                ArchiveEntry {
+                    name: "pkg/app.py".to_string(),
+                    data: "# 你好世界\nx = 1\n".as_bytes().to_vec(),
+                    mode: None,
+                },
+                ArchiveEntry {
+                    name: "data.bin".to_string(),
+                    data: vec![0xff, 0x00],
+                    mode: None,
+                },
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            collect_files(&input, &mut FileErrors::default()).unwrap(),
+            vec![input.clone()]
+        );
+
+        let translator = langlint_translators::MockTranslator::with_config(
+            langlint_translators::mock::MockConfig {
+                delay_range: (0, 0),
+                ..Default::default()
+            },
+        );
+        let stats = translate_single_file(
+            &input,
+            &output,
+            "zh",
+            "en",
+            &translator,
+            &FileOptions::default(),
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(stats.units, 1);
+
+        let entries = archive::read_archive(&output).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].text().unwrap().starts_with("# [EN] 你好世界"));
+        assert_eq!(entries[1].data, vec![0xff, 0x00]);
+    }
+
+    #[tokio::test]
+    async fn test_output_encoding() {
+        use langlint_core::FileEncoding;
+
+        let temp_dir = TempDir::new().unwrap();
+        let input = temp_dir.path().join("legacy.py");
+        let gbk = FileEncoding::for_label("gbk").unwrap();
+        let source = "# 这是一个旧系统里的中文注释，文件使用国标编码保存。\nx = 1\n";
+        fs::write(&input, gbk.encode(source).unwrap()).unwrap();
+
+        let translator = langlint_translators::MockTranslator::with_config(
+            langlint_translators::mock::MockConfig {
+                delay_range: (0, 0),
+                ..Default::default()
+            },
+        );
+        let translate = |output: PathBuf, output_encoding| {
+            let input = input.clone();
+            let translator = &translator;
+            async move {
+                let options = FileOptions {
+                    output_encoding,
+                    ..FileOptions::default()
+                };
+                translate_single_file(&input, &output, "zh", "en", translator, &options, false)
+                    .await
+                    .unwrap();
+                fs::read(output).unwrap()
+            }
+        };
+
+        // By default the file keeps its own encoding
+        let kept = translate(temp_dir.path().join("kept.py"), None).await;
+        assert!(std::str::from_utf8(&kept).is_err());
+        let (text, detected) = encoding::decode(&kept);
+        assert_eq!(detected.encoding, gbk.encoding);
+        assert!(text.starts_with("# [EN] "));
+
+        let utf8 = translate(temp_dir.path().join("utf8.py"), Some(FileEncoding::UTF8)).await;
+        assert!(String::from_utf8(utf8).unwrap().starts_with("# [EN] "));
+    }
 }
@@ -0,0 +1,186 @@
+//! `langlint:` directives pinning units to a translator or target language
+//!
+//! A comment such as `# langlint: translator=google target=ja` (or
+//! `// langlint: ...`, `<!-- langlint: ... -->`) applies to the unit on
+//! its own line, as a trailing comment, or else to the next unit and the
+//! units continuing it on the following lines, e.g. a comment block.
+//! Directives applying to the same unit combine.
+
+use anyhow::Result;
+use langlint_core::TranslatableUnit;
+use langlint_parsers::paragraph;
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// Translator and target language overrides of a unit
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Directive {
+    pub translator: Option<String>,
+    pub target: Option<String>,
+}
+
+impl Directive {
+    /// Parse the `key=value` pairs after `langlint:`
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut directive = Self::default();
+        for pair in text.split([' ', ',', '\t']).filter(|pair| !pair.is_empty()) {
+            let Some((key, value)) = pair.split_once('=').filter(|(_, value)| !value.is_empty())
+            else {
+                anyhow::bail!("Invalid langlint directive '{}' (expected key=value)", pair);
+            };
+            match key {
+                "translator" => directive.translator = Some(value.to_string()),
+                "target" => directive.target = Some(value.to_string()),
+                _ => anyhow::bail!(
+                    "Unknown langlint directive '{}' (expected translator or target)",
+                    key
+                ),
+            }
+        }
+        Ok(directive)
+    }
+
+    /// Override fields with those set in `other`
+    fn merge(&mut self, other: &Directive) {
+        if other.translator.is_some() {
+            self.translator.clone_from(&other.translator);
+        }
+        if other.target.is_some() {
+            self.target.clone_from(&other.target);
+        }
+    }
+}
+
+fn directive_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r"(?:#|//|/\*|<!--|--|;|%)\s*langlint:([^\n]*?)\s*(?:\*/|-->)?\s*$").unwrap()
+    })
+}
+
+/// Directives of a file with their 1-based line numbers
+///
+/// Fails on a malformed directive, naming its line.
+pub fn parse_file(content: &str) -> Result<Vec<(u32, Directive)>> {
+    let mut directives = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let Some(captures) = directive_regex().captures(line) else {
+            continue;
+        };
+        let directive = Directive::parse(&captures[1])
+            .map_err(|e| e.context(format!("Invalid directive on line {}", index + 1)))?;
+        directives.push((index as u32 + 1, directive));
+    }
+    Ok(directives)
+}
+
+/// Directive of each unit, empty for units no directive applies to
+pub fn pins(units: &[TranslatableUnit], directives: &[(u32, Directive)]) -> Vec<Directive> {
+    let mut pins = vec![Directive::default(); units.len()];
+
+    for (line, directive) in directives {
+        let trailing: Vec<usize> = (0..units.len())
+            .filter(|&i| units[i].line_number <= *line && *line <= paragraph::end_line(&units[i]))
+            .collect();
+        if !trailing.is_empty() {
+            for i in trailing {
+                pins[i].merge(directive);
+            }
+            continue;
+        }
+
+        let Some(first) = units.iter().position(|unit| unit.line_number > *line) else {
+            continue;
+        };
+        let mut next_line = units[first].line_number;
+        for (unit, pin) in units.iter().zip(pins.iter_mut()).skip(first) {
+            if unit.unit_type != units[first].unit_type || unit.line_number != next_line {
+                break;
+            }
+            pin.merge(directive);
+            next_line = paragraph::end_line(unit) + 1;
+        }
+    }
+    pins
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use langlint_core::UnitType;
+
+    fn unit(content: &str, unit_type: UnitType, line: u32) -> TranslatableUnit {
+        TranslatableUnit::new(content.to_string(), unit_type, line, 1)
+    }
+
+    #[test]
+    fn test_parse_file() {
+        let content = "# langlint: translator=google target=ja\n\
+                       x = 1  // langlint: target=fr\n\
+                       <!-- langlint: translator=mock -->\n\
+                       print('langlint: not a directive')\n";
+        let directives = parse_file(content).unwrap();
+        assert_eq!(
+            directives,
+            vec![
+                (
+                    1,
+                    Directive {
+                        translator: Some("google".to_string()),
+                        target: Some("ja".to_string()),
+                    }
+                ),
+                (
+                    2,
+                    Directive {
+                        translator: None,
+                        target: Some("fr".to_string()),
+                    }
+                ),
+                (
+                    3,
+                    Directive {
+                        translator: Some("mock".to_string()),
+                        target: None,
+                    }
+                ),
+            ]
+        );
+
+        let error = parse_file("x = 1\n# langlint: engine=deepl\n").unwrap_err();
+        assert!(format!("{:#}", error).contains("line 2"));
+        assert!(parse_file("# langlint: target\n").is_err());
+    }
+
+    #[test]
+    fn test_pins() {
+        let units = vec![
+            unit("第一行", UnitType::Comment, 3),
+            unit("第二行", UnitType::Comment, 4),
+            unit("文档", UnitType::Docstring, 5),
+            unit("你好", UnitType::StringLiteral, 8),
+            unit("其他", UnitType::Comment, 10),
+        ];
+        let content = "\n# langlint: translator=google\n# 第一行\n# 第二行\n\"\"\"文档\"\"\"\n\
+                       \n# langlint: target=ja\nx = '你好'  # langlint: translator=mock\n";
+        let pins = pins(&units, &parse_file(content).unwrap());
+
+        // The comment block, but not the docstring after it
+        let google = Directive {
+            translator: Some("google".to_string()),
+            target: None,
+        };
+        assert_eq!(pins[0], google);
+        assert_eq!(pins[1], google);
+        assert_eq!(pins[2], Directive::default());
+        // Directives applying to the same unit combine
+        assert_eq!(
+            pins[3],
+            Directive {
+                translator: Some("mock".to_string()),
+                target: Some("ja".to_string()),
+            }
+        );
+        assert_eq!(pins[4], Directive::default());
+    }
+}
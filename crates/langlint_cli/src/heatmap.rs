@@ -0,0 +1,195 @@
+//! Comment density heat map export
+//!
+//! One row per scanned file with its unit counts, broken down by unit
+//! type, and the units per thousand lines, written as JSON or CSV for
+//! rendering heat maps in dashboards.
+
+use anyhow::{Context, Result};
+use langlint_core::{paths, ParseResult, UnitType};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Unit types in column order
+const UNIT_TYPES: [UnitType; 5] = [
+    UnitType::Comment,
+    UnitType::Docstring,
+    UnitType::StringLiteral,
+    UnitType::TextNode,
+    UnitType::Metadata,
+];
+
+/// Heat map entry of one file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HeatmapRow {
+    /// Path relative to the scanned root, with `/` separators
+    pub path: String,
+    pub lines: u32,
+    pub units: usize,
+    /// Units per thousand lines
+    pub density: f64,
+    pub comment: usize,
+    pub docstring: usize,
+    pub string_literal: usize,
+    pub text_node: usize,
+    pub metadata: usize,
+    /// Most common detected language, empty when none was detected
+    pub language: String,
+}
+
+impl HeatmapRow {
+    fn new(path: &Path, root: &Path, result: &ParseResult) -> Self {
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        let count = |unit_type: UnitType| {
+            result
+                .units
+                .iter()
+                .filter(|unit| unit.unit_type == unit_type)
+                .count()
+        };
+        let units = result.units.len();
+        Self {
+            path: paths::to_slash(relative),
+            lines: result.line_count,
+            units,
+            density: if result.line_count == 0 {
+                0.0
+            } else {
+                // Rounded to keep the export stable and readable
+                (units as f64 * 1000.0 / f64::from(result.line_count) * 100.0).round() / 100.0
+            },
+            comment: count(UnitType::Comment),
+            docstring: count(UnitType::Docstring),
+            string_literal: count(UnitType::StringLiteral),
+            text_node: count(UnitType::TextNode),
+            metadata: count(UnitType::Metadata),
+            language: result.dominant_language().unwrap_or_default(),
+        }
+    }
+
+    fn csv_fields(&self) -> Vec<String> {
+        let mut fields = vec![
+            csv_field(&self.path),
+            self.lines.to_string(),
+            self.units.to_string(),
+            self.density.to_string(),
+        ];
+        fields.extend(
+            [
+                self.comment,
+                self.docstring,
+                self.string_literal,
+                self.text_node,
+                self.metadata,
+            ]
+            .iter()
+            .map(usize::to_string),
+        );
+        fields.push(csv_field(&self.language));
+        fields
+    }
+}
+
+/// Heat map rows for scan results
+pub fn rows(results: &[(PathBuf, ParseResult)], root: &Path) -> Vec<HeatmapRow> {
+    results
+        .iter()
+        .map(|(path, result)| HeatmapRow::new(path, root, result))
+        .collect()
+}
+
+/// Render rows as CSV with a header line
+pub fn to_csv(rows: &[HeatmapRow]) -> String {
+    let mut header = vec!["path", "lines", "units", "density"];
+    header.extend(UNIT_TYPES.iter().map(unit_type_name));
+    header.push("language");
+
+    let mut csv = header.join(",");
+    csv.push('\n');
+    for row in rows {
+        csv.push_str(&row.csv_fields().join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Write a heat map, as CSV for `.csv` paths and JSON otherwise
+pub fn write(path: &Path, rows: &[HeatmapRow]) -> Result<()> {
+    let is_csv = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+    let content = if is_csv {
+        to_csv(rows)
+    } else {
+        serde_json::to_string_pretty(rows)? + "\n"
+    };
+    fs::write(path, content)
+        .with_context(|| format!("Failed to write heat map: {}", path.display()))
+}
+
+fn unit_type_name(unit_type: &UnitType) -> &'static str {
+    match unit_type {
+        UnitType::Comment => "comment",
+        UnitType::Docstring => "docstring",
+        UnitType::StringLiteral => "string_literal",
+        UnitType::TextNode => "text_node",
+        UnitType::Metadata => "metadata",
+    }
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use langlint_core::TranslatableUnit;
+
+    fn result(types: &[UnitType], lines: u32) -> ParseResult {
+        let units = types
+            .iter()
+            .enumerate()
+            .map(|(i, unit_type)| {
+                TranslatableUnit::new("注释".to_string(), *unit_type, i as u32 + 1, 1)
+                    .with_detected_language("zh-CN".to_string())
+            })
+            .collect();
+        ParseResult::new("python", "utf-8", lines).with_units(units)
+    }
+
+    #[test]
+    fn test_rows_and_csv() {
+        let root = Path::new("/repo");
+        let results = vec![
+            (
+                PathBuf::from("/repo/src/app.py"),
+                result(
+                    &[UnitType::Comment, UnitType::Comment, UnitType::Docstring],
+                    200,
+                ),
+            ),
+            (PathBuf::from("/repo/src/a,b.py"), result(&[], 0)),
+        ];
+
+        let rows = rows(&results, root);
+        assert_eq!(rows[0].path, "src/app.py");
+        assert_eq!(rows[0].units, 3);
+        assert_eq!(rows[0].density, 15.0);
+        assert_eq!((rows[0].comment, rows[0].docstring), (2, 1));
+        assert_eq!(rows[0].language, "zh-CN");
+        assert_eq!(rows[1].density, 0.0);
+
+        assert_eq!(
+            to_csv(&rows),
+            "path,lines,units,density,comment,docstring,string_literal,text_node,metadata,language\n\
+             src/app.py,200,3,15,2,1,0,0,0,zh-CN\n\
+             \"src/a,b.py\",0,0,0,0,0,0,0,0,\n"
+        );
+    }
+}
@@ -0,0 +1,130 @@
+//! Ctrl+C and SIGTERM during translation runs
+//!
+//! Once [`install`]ed, a signal no longer aborts the process: the file
+//! being translated is dropped together with its in-flight translator
+//! requests (nothing of it has been written yet), files already written
+//! stay, and the run ends with its summary and a checkpoint of the files
+//! left. A second signal exits at once.
+
+use anyhow::Result;
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Once, OnceLock};
+use tokio::sync::Notify;
+
+/// Exit code of a run stopped by a signal, as shells report SIGINT
+pub const EXIT_CODE: i32 = 130;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+fn notify() -> &'static Notify {
+    static NOTIFY: OnceLock<Notify> = OnceLock::new();
+    NOTIFY.get_or_init(Notify::new)
+}
+
+/// Error of a file cut short by a signal
+#[derive(Debug)]
+pub struct Interrupted;
+
+impl fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "interrupted by a signal")
+    }
+}
+
+impl std::error::Error for Interrupted {}
+
+/// Stop gracefully on Ctrl+C or SIGTERM from now on
+pub fn install() {
+    static INSTALLED: Once = Once::new();
+    INSTALLED.call_once(|| {
+        tokio::spawn(async {
+            loop {
+                wait_for_signal().await;
+                if INTERRUPTED.swap(true, Ordering::SeqCst) {
+                    eprintln!("\nInterrupted again, quitting");
+                    std::process::exit(EXIT_CODE);
+                }
+                eprintln!("\nInterrupted, finishing up (press Ctrl+C again to quit at once)");
+                notify().notify_waiters();
+            }
+        });
+    });
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+    match signal(SignalKind::terminate()) {
+        Ok(mut terminate) => {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+        }
+        Err(_) => {
+            let _ = tokio::signal::ctrl_c().await;
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Check if a signal arrived
+pub fn is_interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Wait for a signal
+async fn interrupted() {
+    loop {
+        let notified = notify().notified();
+        if is_interrupted() {
+            return;
+        }
+        notified.await;
+    }
+}
+
+/// Run `future` unless a signal arrives first, which drops it
+pub async fn or_interrupted<T>(future: impl Future<Output = Result<T>>) -> Result<T> {
+    unless(interrupted(), future).await
+}
+
+/// Run `future` unless `stop` completes first
+async fn unless<T>(
+    stop: impl Future<Output = ()>,
+    future: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    tokio::select! {
+        biased;
+        _ = stop => Err(Interrupted.into()),
+        result = future => result,
+    }
+}
+
+/// Check if a file failed because the run was interrupted
+pub fn is_interrupt(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| cause.is::<Interrupted>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_interrupt_drops_the_file() {
+        let pending = std::future::pending::<()>;
+        assert_eq!(unless(pending(), async { Ok(1) }).await.unwrap(), 1);
+
+        let error = unless(async {}, std::future::pending::<Result<()>>())
+            .await
+            .unwrap_err();
+        assert!(is_interrupt(&error));
+        assert!(!is_interrupt(&anyhow::anyhow!("Failed to parse")));
+    }
+}
@@ -0,0 +1,227 @@
+//! File size and unit count limits from the config's `file_limits`
+//!
+//! Keeps runs on messy repositories predictable: files over a size limit
+//! are skipped as they are discovered, and files with more units than a
+//! limit allows once they are parsed, before anything is translated.
+//! Skipped files are listed with the run's summary rather than as errors.
+//! Globs without a `/` match the file name; others match the path from
+//! any directory down, so `vendor/**` also covers `app/vendor/x.js`. Set
+//! once at startup, like the walk filters.
+
+use anyhow::{anyhow, Context, Result};
+use globset::{GlobBuilder, GlobMatcher};
+use langlint_core::{paths, FileLimit};
+use std::fmt;
+use std::path::Path;
+use std::sync::OnceLock;
+
+static LIMITS: OnceLock<FileLimits> = OnceLock::new();
+
+/// Limits used before (or without) [`configure`]
+static NO_LIMITS: FileLimits = FileLimits { rules: Vec::new() };
+
+/// Use the config's file limits for this process
+///
+/// Fails on an invalid glob, or if limits were already configured.
+pub fn configure(limits: &[FileLimit]) -> Result<()> {
+    LIMITS
+        .set(FileLimits::new(limits)?)
+        .map_err(|_| anyhow!("File limits are already configured"))
+}
+
+/// Limits configured for this process
+pub fn current() -> &'static FileLimits {
+    LIMITS.get().unwrap_or(&NO_LIMITS)
+}
+
+/// Fail with [`OverLimit`] if a file is larger than a limit allows
+pub fn check_size(path: &Path) -> Result<()> {
+    let limits = current();
+    if limits.is_empty() {
+        return Ok(());
+    }
+    match std::fs::metadata(path) {
+        Ok(metadata) => limits.check_size(path, metadata.len()),
+        Err(_) => Ok(()),
+    }
+}
+
+/// Fail with [`OverLimit`] if a file has more units than a limit allows
+pub fn check_units(path: &Path, units: usize) -> Result<()> {
+    current().check_units(path, units)
+}
+
+/// Name an archive entry in its [`OverLimit`] error, as the archive is
+/// what gets skipped and listed
+pub fn in_entry(error: anyhow::Error, entry: &str) -> anyhow::Error {
+    match error.downcast::<OverLimit>() {
+        Ok(over) => OverLimit {
+            reason: format!("{}: {}", entry, over.reason),
+        }
+        .into(),
+        Err(error) => error,
+    }
+}
+
+/// Error for a file skipped by a limit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverLimit {
+    pub reason: String,
+}
+
+impl fmt::Display for OverLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "skipped: {}", self.reason)
+    }
+}
+
+impl std::error::Error for OverLimit {}
+
+/// Compiled limits
+#[derive(Debug, Default)]
+pub struct FileLimits {
+    /// Matchers (none for every file), whether they match file names only,
+    /// and the limit
+    rules: Vec<(Vec<(GlobMatcher, bool)>, FileLimit)>,
+}
+
+impl FileLimits {
+    pub fn new(limits: &[FileLimit]) -> Result<Self> {
+        let mut rules = Vec::new();
+        for limit in limits {
+            let mut matchers = Vec::new();
+            for pattern in &limit.files {
+                let pattern = paths::normalize_separators(pattern);
+                let matcher = GlobBuilder::new(&pattern)
+                    .literal_separator(true)
+                    .case_insensitive(paths::CASE_INSENSITIVE)
+                    .build()
+                    .with_context(|| format!("Invalid file_limits glob: {}", pattern))?
+                    .compile_matcher();
+                matchers.push((matcher, !pattern.contains('/')));
+            }
+            rules.push((matchers, limit.clone()));
+        }
+        Ok(Self { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Limits that apply to a file
+    fn applying(&self, path: &Path) -> impl Iterator<Item = &FileLimit> {
+        let path = paths::to_slash(path);
+        let path = path.trim_start_matches("./").to_string();
+        self.rules.iter().filter_map(move |(matchers, limit)| {
+            let name = path.rsplit('/').next().unwrap_or(&path);
+            let matches = matchers.is_empty()
+                || matchers.iter().any(|(matcher, name_only)| {
+                    if *name_only {
+                        return matcher.is_match(name);
+                    }
+                    // The path, or its part below any directory
+                    std::iter::once(path.as_str())
+                        .chain(path.match_indices('/').map(|(i, _)| &path[i + 1..]))
+                        .any(|tail| matcher.is_match(tail))
+                });
+            matches.then_some(limit)
+        })
+    }
+
+    fn check_size(&self, path: &Path, size: u64) -> Result<()> {
+        for limit in self.applying(path) {
+            if let Some(max) = limit.max_size.filter(|&max| size > max) {
+                return Err(OverLimit {
+                    reason: format!("{} exceeds the {} limit", bytes(size), bytes(max)),
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Fail with [`OverLimit`] if a file has more units than a limit allows
+    pub fn check_units(&self, path: &Path, units: usize) -> Result<()> {
+        for limit in self.applying(path) {
+            if let Some(max) = limit.max_units.filter(|&max| units > max) {
+                return Err(OverLimit {
+                    reason: format!("{} units exceed the limit of {}", units, max),
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A size for people: `512 B`, `1.4 MB`
+fn bytes(size: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = size as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", size)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_limits() {
+        let limits = FileLimits::new(&[
+            FileLimit {
+                max_units: Some(5000),
+                ..FileLimit::default()
+            },
+            FileLimit {
+                files: vec!["vendor/**".to_string(), "*.min.js".to_string()],
+                max_size: Some(1 << 20),
+                max_units: None,
+            },
+        ])
+        .unwrap();
+
+        let vendored = Path::new("./app/vendor/lib/big.js");
+        let reason = limits.check_size(vendored, 3 << 19).unwrap_err();
+        assert_eq!(
+            reason.downcast_ref::<OverLimit>().unwrap().reason,
+            "1.5 MB exceeds the 1.0 MB limit"
+        );
+        assert!(limits
+            .check_size(Path::new("dist/app.min.js"), 2 << 20)
+            .is_err());
+        assert!(limits.check_size(Path::new("src/big.js"), 2 << 20).is_ok());
+        assert!(limits.check_size(vendored, 1 << 20).is_ok());
+
+        assert!(limits.check_units(Path::new("src/main.py"), 5000).is_ok());
+        let error = limits
+            .check_units(Path::new("src/main.py"), 5001)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "skipped: 5001 units exceed the limit of 5000"
+        );
+        assert!(FileLimits::new(&[FileLimit {
+            files: vec!["[".to_string()],
+            ..FileLimit::default()
+        }])
+        .is_err());
+    }
+
+    #[test]
+    fn test_configure_once() {
+        // No limits, so the other tests in this process are unaffected
+        assert!(configure(&[]).is_ok());
+        assert!(current().is_empty());
+        assert!(configure(&[]).is_err());
+    }
+}
@@ -3,10 +3,41 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use colored::Colorize;
+use langlint_core::Config;
+use langlint_translators::Glossary;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
+mod archive;
+mod blame;
+mod checkpoint;
+mod color;
 mod commands;
+mod directives;
+mod heatmap;
+mod interrupt;
+mod limits;
+mod manifest;
+mod metrics_server;
+mod overrides;
+mod packages;
+mod pipeline;
+mod policy;
+mod remote;
+mod report;
+mod sandbox;
+mod shared_memory;
+mod state;
+mod ui;
+mod walk;
 
-use commands::{fix, scan, translate};
+use commands::{
+    cache, check, commit_msg, export, fix, hooks, i18n, import, rename, revert, scan, stats, sync,
+    translate,
+};
+use manifest::RunManifest;
+use pipeline::{parse_confidence, parse_duration, HistoryFilter, NotebookCells, QualityGate};
 
 /// Langlint - Intelligent translation management for code and documentation
 #[derive(Parser)]
@@ -20,16 +51,101 @@ struct Cli {
     #[arg(short, long, global = true)]
     verbose: bool,
 
-    /// Output format (text, json, or pretty-json)
+    /// Output format (text, json, pretty-json, sarif, github, or html)
     #[arg(short, long, default_value = "text", global = true)]
     format: String,
+
+    /// Color output: auto (terminals without NO_COLOR), always or never
+    #[arg(long, value_name = "WHEN", default_value = "auto", global = true)]
+    color: String,
+
+    /// Language of langlint's own messages, e.g. zh-CN (default: the system locale)
+    #[arg(long, value_name = "LANG", global = true)]
+    ui_lang: Option<String>,
+
+    /// Write translation metrics (OpenMetrics text format) to this file when done
+    #[arg(long, value_name = "PATH", global = true)]
+    metrics_file: Option<String>,
+
+    /// Serve translation metrics on http://ADDR/metrics while the command runs, e.g. 127.0.0.1:9898
+    #[arg(long, value_name = "ADDR", global = true)]
+    metrics_listen: Option<String>,
+
+    /// Dump every translator API request and response, API keys redacted, as JSON files in this directory
+    #[arg(long, value_name = "DIR", global = true)]
+    trace_api: Option<PathBuf>,
+
+    /// Do not read or write the persistent or shared translation cache
+    #[arg(long, global = true)]
+    no_cache: bool,
+
+    /// Translate units detected in the target language too, instead of skipping them
+    #[arg(long, global = true)]
+    no_skip_target_language: bool,
+
+    /// Stop translating before the estimated cost exceeds this many US dollars
+    #[arg(long, value_name = "USD", global = true)]
+    max_cost: Option<f64>,
+
+    /// Stop translating before more than this many characters are sent
+    #[arg(long, value_name = "N", global = true)]
+    max_characters: Option<usize>,
+
+    /// Stop sending translations after this long (e.g. 10m, 1h30m), keeping finished files and a checkpoint
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, global = true)]
+    max_duration: Option<Duration>,
+
+    /// Keep units translated below this confidence (0.0-1.0) in the original and list them for review
+    #[arg(long, value_name = "SCORE", global = true)]
+    min_confidence: Option<f64>,
+
+    /// Translator retrying units below --min-confidence before they are kept for review
+    #[arg(long, value_name = "NAME", global = true)]
+    secondary_translator: Option<String>,
+
+    /// Translate high-priority units (docstrings, headings) with all of these translators, keeping the consensus
+    #[arg(long, value_name = "NAMES", value_delimiter = ',', global = true)]
+    ensemble: Vec<String>,
+
+    /// Translate pairs a translator lacks through LANG, e.g. zh->en->th (default LANG: en)
+    #[arg(long = "pivot", value_name = "LANG", num_args = 0..=1, default_missing_value = "en", global = true)]
+    pivot_language: Option<String>,
+
+    /// Comments in two languages (`初始化 / initialize`): translate, keep-target or translate-foreign
+    #[arg(long, value_name = "MODE", global = true)]
+    bilingual: Option<String>,
+
+    /// Control, bidi and invisible characters translations introduce: allow, strip or reject
+    #[arg(long, value_name = "MODE", global = true)]
+    unsafe_characters: Option<String>,
+
+    /// Write nothing (translate and i18n become dry runs) and block network translators
+    #[arg(long, global = true)]
+    sandbox: bool,
+
+    /// Network translator allowed in --sandbox mode (repeatable, comma-separated)
+    #[arg(
+        long = "allow-translator",
+        value_name = "NAME",
+        value_delimiter = ',',
+        global = true
+    )]
+    allow_translators: Vec<String>,
+
+    /// API key of a translator, e.g. openai=sk-... (repeatable; prefer env vars or the keyring)
+    #[arg(long = "api-key", value_name = "PROVIDER=KEY", global = true)]
+    api_keys: Vec<String>,
+
+    /// Glossary (.toml or .csv) of required term translations [env: LANGLINT_GLOSSARY]
+    #[arg(long, value_name = "PATH", global = true)]
+    glossary: Option<String>,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Scan files and extract translatable units
     Scan {
-        /// Input file or directory to scan
+        /// Input file, directory, archive (.zip, .tar.gz) or git URL to scan
         #[arg(value_name = "PATH")]
         path: String,
 
@@ -49,36 +165,243 @@ enum Commands {
         #[arg(short, long)]
         priority: Option<String>,
 
+        /// Also list the bodies of TODO/FIXME comments
+        #[arg(long)]
+        include_todos: bool,
+
+        /// Also list JSX text and strings passed to print, logging, exceptions, alert, t() fallbacks, ... (also `unit_types = ["string_literal"]` in config)
+        #[arg(long)]
+        include_strings: bool,
+
+        /// Also include license/copyright headers (also `include_license_headers` in config)
+        #[arg(long)]
+        include_license_headers: bool,
+
+        /// Also include files marked as generated (also `include_generated` in config)
+        #[arg(long)]
+        include_generated: bool,
+
+        /// Only scan the named monorepo package (repeatable)
+        #[arg(long = "package", value_name = "NAME")]
+        packages: Vec<String>,
+
+        /// Aggregate results per monorepo package
+        #[arg(long)]
+        by_package: bool,
+
+        /// Add report views grouped by dir, ext or language (repeatable, comma-separated)
+        #[arg(long, value_name = "KEY", value_delimiter = ',')]
+        group_by: Vec<String>,
+
+        /// Order files by path (default), size, units or language; units stay in line order
+        #[arg(long, value_name = "KEY")]
+        sort: Option<String>,
+
+        /// Stop at the first unreadable or unparsable file instead of reporting it
+        #[arg(long)]
+        strict: bool,
+
         /// Output file path (optional, defaults to stdout)
         #[arg(short, long)]
         output: Option<String>,
+
+        /// Also write per-file unit counts for heat maps (.csv, otherwise JSON)
+        #[arg(long, value_name = "FILE")]
+        heatmap: Option<String>,
+    },
+
+    /// Check files against the language policy (`[[policy]]` in the config)
+    Check {
+        /// File or directory to check
+        #[arg(value_name = "PATH", default_value = ".")]
+        path: String,
+
+        /// File patterns to include (glob)
+        #[arg(short, long)]
+        include: Option<Vec<String>>,
+
+        /// File patterns to exclude (glob)
+        #[arg(short, long)]
+        exclude: Option<Vec<String>>,
+    },
+
+    /// Show the detected languages of a tree and remember each file's dominant one (.langlint-state.json), the default source language of later runs
+    Stats {
+        /// File or directory to examine
+        #[arg(value_name = "PATH", default_value = ".")]
+        path: String,
+
+        /// File patterns to include (glob)
+        #[arg(short, long)]
+        include: Option<Vec<String>>,
+
+        /// File patterns to exclude (glob)
+        #[arg(short, long)]
+        exclude: Option<Vec<String>>,
+
+        /// List the dominant language of each file and of the whole tree
+        #[arg(long)]
+        dominant: bool,
+    },
+
+    /// Compare parallel translated trees (e.g. docs/zh and docs/en) and report missing or stale sections
+    Sync {
+        /// Reference tree
+        #[arg(value_name = "SOURCE")]
+        source: String,
+
+        /// Translated tree kept in sync with it
+        #[arg(value_name = "TRANSLATED")]
+        translated: String,
+
+        /// Only report missing files and sections, not stale ones (skips git blame)
+        #[arg(long)]
+        no_stale: bool,
+    },
+
+    /// Export translatable units as XLIFF for CAT tools (-f xliff for 1.2, -f xliff2 for 2.0)
+    Export {
+        /// File or directory to export
+        #[arg(value_name = "PATH", default_value = ".")]
+        path: String,
+
+        /// File patterns to include (glob)
+        #[arg(short, long)]
+        include: Option<Vec<String>>,
+
+        /// File patterns to exclude (glob)
+        #[arg(short, long)]
+        exclude: Option<Vec<String>>,
+
+        /// Source language code, or auto to use the most common detected one [env: LANGLINT_SOURCE_LANG]
+        #[arg(short, long)]
+        source: Option<String>,
+
+        /// Target language code [env: LANGLINT_TARGET_LANG]
+        #[arg(short, long)]
+        target: Option<String>,
+
+        /// XLIFF file to write (default: stdout)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Write the targets of an XLIFF file (from `langlint export`) back into the source files
+    Import {
+        /// Translated XLIFF file
+        #[arg(value_name = "FILE")]
+        file: String,
+
+        /// Report what would change without writing files [env: LANGLINT_DRY_RUN]
+        #[arg(long, overrides_with = "no_dry_run")]
+        dry_run: bool,
+
+        /// Write files even if the config or environment asks for a dry run
+        #[arg(long, overrides_with = "dry_run")]
+        no_dry_run: bool,
+
+        /// Disable automatic backup creation
+        #[arg(long)]
+        no_backup: bool,
     },
 
     /// Translate text from one language to another
     Translate {
-        /// Input file or directory to translate
+        /// Input file, directory or archive (.zip, .tar.gz) to translate
         #[arg(value_name = "PATH")]
         path: String,
 
-        /// Source language code (e.g., en, zh, ja)
+        /// Source language code (e.g., en, zh, ja) [env: LANGLINT_SOURCE_LANG]
         #[arg(short, long)]
-        source: String,
+        source: Option<String>,
 
-        /// Target language code (e.g., en, zh, ja)
+        /// Target language code (e.g., en, zh, ja) [env: LANGLINT_TARGET_LANG]
         #[arg(short, long)]
-        target: String,
+        target: Option<String>,
 
-        /// Translator to use (mock, google, openai, deepl)
-        #[arg(long, default_value = "google")]
-        translator: String,
+        /// Translator to use (mock, google, openai, ollama, libretranslate, mymemory, papago), or a fallback chain like google,mock [env: LANGLINT_TRANSLATOR]
+        #[arg(long)]
+        translator: Option<String>,
 
         /// Output file (default: overwrite input)
         #[arg(short, long)]
         output: Option<String>,
 
-        /// Dry run (don't write changes)
-        #[arg(long)]
+        /// Dry run (don't write changes) [env: LANGLINT_DRY_RUN]
+        #[arg(long, overrides_with = "no_dry_run")]
         dry_run: bool,
+
+        /// Write changes even if the config or environment asks for a dry run
+        #[arg(long, overrides_with = "dry_run")]
+        no_dry_run: bool,
+
+        /// Fail if the average translation confidence is below this value (0.0-1.0)
+        #[arg(long, value_name = "SCORE", value_parser = parse_confidence)]
+        min_avg_confidence: Option<f64>,
+
+        /// Fail if more than this many units failed to translate
+        #[arg(long, value_name = "COUNT")]
+        max_failed_units: Option<usize>,
+
+        /// Only translate units whose lines were last modified before this date (YYYY-MM-DD, via git blame)
+        #[arg(long, value_name = "DATE")]
+        modified_before: Option<String>,
+
+        /// Only translate units whose lines were last modified on or after this date (YYYY-MM-DD, via git blame)
+        #[arg(long, value_name = "DATE")]
+        modified_after: Option<String>,
+
+        /// Only translate units with a line last written by a matching author, name or email (repeatable, via git blame)
+        #[arg(long = "author", value_name = "PATTERN")]
+        authors: Vec<String>,
+
+        /// Write a <file>.langlint-map.json sidecar recording original and translated text
+        #[arg(long)]
+        write_map: bool,
+
+        /// Write a <file>.langlint-sourcemap.json mapping original to translated line numbers
+        #[arg(long)]
+        source_map: bool,
+
+        /// Also translate the bodies of TODO/FIXME comments, keeping the marker
+        #[arg(long)]
+        include_todos: bool,
+
+        /// Also translate JSX text and strings passed to print, logging, exceptions, alert, t() fallbacks, ... (also `unit_types = ["string_literal"]` in config)
+        #[arg(long)]
+        include_strings: bool,
+
+        /// Also include license/copyright headers (also `include_license_headers` in config)
+        #[arg(long)]
+        include_license_headers: bool,
+
+        /// Also include files marked as generated (also `include_generated` in config)
+        #[arg(long)]
+        include_generated: bool,
+
+        /// Notebook cells to translate: markdown, code or both (also `notebook_cells` in config)
+        #[arg(long, value_name = "KIND", value_delimiter = ',')]
+        notebook_cells: Vec<String>,
+
+        /// Write files even if verification flags them as unsafe; optionally only files matching PATTERN (repeatable)
+        #[arg(long, value_name = "PATTERN", num_args = 0..=1, default_missing_value = "*")]
+        force: Vec<String>,
+
+        /// Encoding for written files, e.g. `gbk` or `utf-8-sig` (default: keep each file's encoding)
+        #[arg(long, value_name = "ENCODING")]
+        output_encoding: Option<String>,
+
+        /// Stop at the first unreadable or unparsable file instead of reporting it
+        #[arg(long)]
+        strict: bool,
+
+        /// Only translate the files a run stopped by --max-duration or the budget left
+        #[arg(long)]
+        resume: bool,
+
+        /// Write a JSON manifest of the run (files, bytes and units changed, translator, config hash)
+        #[arg(long, value_name = "FILE")]
+        manifest: Option<String>,
     },
 
     /// Fix (in-place translate) files with automatic backup
@@ -87,17 +410,17 @@ enum Commands {
         #[arg(value_name = "PATH")]
         path: String,
 
-        /// Source language code (e.g., en, zh, ja, auto)
-        #[arg(short, long, default_value = "auto")]
-        source: String,
+        /// Source language code (e.g., en, zh, ja, auto) [env: LANGLINT_SOURCE_LANG]
+        #[arg(short, long)]
+        source: Option<String>,
 
-        /// Target language code (e.g., en, zh, ja)
-        #[arg(short = 't', long, default_value = "en")]
-        target: String,
+        /// Target language code (e.g., en, zh, ja) [env: LANGLINT_TARGET_LANG]
+        #[arg(short = 't', long)]
+        target: Option<String>,
 
-        /// Translator to use (mock, google, openai, deepl)
-        #[arg(long, default_value = "google")]
-        translator: String,
+        /// Translator to use (mock, google, openai, ollama, libretranslate, mymemory, papago), or a fallback chain like google,mock [env: LANGLINT_TRANSLATOR]
+        #[arg(long)]
+        translator: Option<String>,
 
         /// Skip confirmation prompt
         #[arg(short, long)]
@@ -106,34 +429,417 @@ enum Commands {
         /// Disable automatic backup creation
         #[arg(long)]
         no_backup: bool,
+
+        /// Fail if the average translation confidence is below this value (0.0-1.0)
+        #[arg(long, value_name = "SCORE", value_parser = parse_confidence)]
+        min_avg_confidence: Option<f64>,
+
+        /// Fail if more than this many units failed to translate
+        #[arg(long, value_name = "COUNT")]
+        max_failed_units: Option<usize>,
+
+        /// Only translate units whose lines were last modified before this date (YYYY-MM-DD, via git blame)
+        #[arg(long, value_name = "DATE")]
+        modified_before: Option<String>,
+
+        /// Only translate units whose lines were last modified on or after this date (YYYY-MM-DD, via git blame)
+        #[arg(long, value_name = "DATE")]
+        modified_after: Option<String>,
+
+        /// Only translate units with a line last written by a matching author, name or email (repeatable, via git blame)
+        #[arg(long = "author", value_name = "PATTERN")]
+        authors: Vec<String>,
+
+        /// Write a <file>.langlint-map.json sidecar recording original and translated text
+        #[arg(long)]
+        write_map: bool,
+
+        /// Write a <file>.langlint-sourcemap.json mapping original to translated line numbers
+        #[arg(long)]
+        source_map: bool,
+
+        /// Also translate the bodies of TODO/FIXME comments, keeping the marker
+        #[arg(long)]
+        include_todos: bool,
+
+        /// Also translate JSX text and strings passed to print, logging, exceptions, alert, t() fallbacks, ... (also `unit_types = ["string_literal"]` in config)
+        #[arg(long)]
+        include_strings: bool,
+
+        /// Also include license/copyright headers (also `include_license_headers` in config)
+        #[arg(long)]
+        include_license_headers: bool,
+
+        /// Also include files marked as generated (also `include_generated` in config)
+        #[arg(long)]
+        include_generated: bool,
+
+        /// Notebook cells to translate: markdown, code or both (also `notebook_cells` in config)
+        #[arg(long, value_name = "KIND", value_delimiter = ',')]
+        notebook_cells: Vec<String>,
+
+        /// Write files even if verification flags them as unsafe; optionally only files matching PATTERN (repeatable)
+        #[arg(long, value_name = "PATTERN", num_args = 0..=1, default_missing_value = "*")]
+        force: Vec<String>,
+
+        /// Encoding for written files, e.g. `gbk` or `utf-8-sig` (default: keep each file's encoding)
+        #[arg(long, value_name = "ENCODING")]
+        output_encoding: Option<String>,
+
+        /// Stop at the first unreadable or unparsable file instead of reporting it
+        #[arg(long)]
+        strict: bool,
+
+        /// Only translate the files a run stopped by --max-duration or the budget left
+        #[arg(long)]
+        resume: bool,
+
+        /// Write a JSON manifest of the run (files, bytes and units changed, translator, config hash)
+        #[arg(long, value_name = "FILE")]
+        manifest: Option<String>,
+    },
+
+    /// Replace user-facing string literals with i18n lookup calls and build locale files
+    I18n {
+        /// Input file or directory (Python, JavaScript, TypeScript)
+        #[arg(value_name = "PATH")]
+        path: String,
+
+        /// Lookup call template with a {key} placeholder (default: _("{key}") for Python, t('{key}') for JS/TS)
+        #[arg(long)]
+        template: Option<String>,
+
+        /// Language of the existing strings (e.g., en, zh) [env: LANGLINT_SOURCE_LANG]
+        #[arg(short, long)]
+        source: Option<String>,
+
+        /// Target locales to generate, comma separated [env: LANGLINT_TARGET_LANG]
+        #[arg(short, long, value_delimiter = ',')]
+        target: Vec<String>,
+
+        /// Translator to use (mock, google, openai, ollama, libretranslate, mymemory, papago), or a fallback chain like google,mock [env: LANGLINT_TRANSLATOR]
+        #[arg(long)]
+        translator: Option<String>,
+
+        /// Directory for the <locale>.json resource files
+        #[arg(long, default_value = "locales")]
+        locales_dir: String,
+
+        /// Report what would change without writing files [env: LANGLINT_DRY_RUN]
+        #[arg(long, overrides_with = "no_dry_run")]
+        dry_run: bool,
+
+        /// Write files even if the config or environment asks for a dry run
+        #[arg(long, overrides_with = "dry_run")]
+        no_dry_run: bool,
+
+        /// Disable automatic backup creation
+        #[arg(long)]
+        no_backup: bool,
+    },
+
+    /// Check a commit message's language (git commit-msg hook)
+    CommitMsg {
+        /// Commit message file passed by git
+        #[arg(value_name = "FILE")]
+        file: String,
+
+        /// Source language code, or auto to detect it [env: LANGLINT_SOURCE_LANG]
+        #[arg(short, long)]
+        source: Option<String>,
+
+        /// Required commit message language [env: LANGLINT_TARGET_LANG]
+        #[arg(short, long)]
+        target: Option<String>,
+
+        /// Translator to use (mock, google, openai, ollama, libretranslate, mymemory, papago), or a fallback chain like google,mock [env: LANGLINT_TRANSLATOR]
+        #[arg(long)]
+        translator: Option<String>,
+
+        /// Translate the message instead of blocking the commit
+        #[arg(long)]
+        rewrite: bool,
+    },
+
+    /// Install langlint git hooks (commit-msg) into a repository
+    InstallHooks {
+        /// Repository root
+        #[arg(value_name = "PATH", default_value = ".")]
+        path: String,
+
+        /// Make the commit-msg hook translate messages instead of blocking
+        #[arg(long)]
+        rewrite: bool,
+
+        /// Replace an existing hook that was not installed by langlint
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Translate or transliterate file and directory names not in the target language
+    Rename {
+        /// Directory whose entries to rename
+        #[arg(value_name = "PATH", default_value = ".")]
+        path: String,
+
+        /// Source language code, or auto to detect it per name [env: LANGLINT_SOURCE_LANG]
+        #[arg(short, long)]
+        source: Option<String>,
+
+        /// Target language code [env: LANGLINT_TARGET_LANG]
+        #[arg(short, long)]
+        target: Option<String>,
+
+        /// Translator to use (mock, google, openai, ollama, libretranslate, mymemory, papago), or a fallback chain like google,mock [env: LANGLINT_TRANSLATOR]
+        #[arg(long)]
+        translator: Option<String>,
+
+        /// Transliterate names (Latin diacritics, Cyrillic, Greek) instead of translating
+        #[arg(long)]
+        transliterate: bool,
+
+        /// Rename the entries instead of only proposing renames
+        #[arg(long)]
+        apply: bool,
+
+        /// Rename with `git mv` so git tracks the moves
+        #[arg(long)]
+        git: bool,
+    },
+
+    /// Undo translations recorded in .langlint-map.json sidecar files
+    Revert {
+        /// Translated file, mapping file or directory to revert
+        #[arg(value_name = "PATH")]
+        path: String,
+
+        /// Only revert files translated in this run (as printed by translate/fix)
+        #[arg(long)]
+        run: Option<u64>,
+
+        /// Only revert units recorded at these original line numbers
+        #[arg(short, long)]
+        line: Vec<u32>,
+    },
+
+    /// Manage the persistent translation cache
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
     },
 }
 
+#[derive(Subcommand)]
+enum CacheAction {
+    /// Delete all cached translations
+    Clear,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
+    color::configure(color::ColorChoice::parse(&cli.color)?);
+    ui::configure(cli.ui_lang.as_deref())?;
 
     // Setup logging/verbosity
     if cli.verbose {
         println!("{}", "Verbose mode enabled".dimmed());
     }
 
-    match cli.command {
+    // Config file < LANGLINT_* environment < CLI flags
+    let config = Config::load()?;
+    if cli.sandbox {
+        sandbox::configure(cli.allow_translators.clone());
+        if cli.metrics_file.is_some() {
+            sandbox::ensure_writable("--metrics-file")?;
+        }
+        if cli.trace_api.is_some() {
+            sandbox::ensure_writable("--trace-api")?;
+        }
+    }
+    if let Some(addr) = &cli.metrics_listen {
+        let addr = metrics_server::serve(addr).await?;
+        if cli.verbose {
+            println!("{} http://{}/metrics", "Serving metrics on".dimmed(), addr);
+        }
+    }
+    walk::configure(&config);
+    overrides::configure(&config.parsers)?;
+    limits::configure(&config.file_limits)?;
+    pipeline::configure_protection(&config.protect)?;
+    pipeline::configure_segmentation(&config.segmentation)?;
+    pipeline::configure_target_language_skip(
+        config.skip_target_language && !cli.no_skip_target_language,
+    );
+    pipeline::configure_rate_limits(&config.rate_limits);
+    if let Some(retry) = &config.retry {
+        pipeline::configure_retry(retry);
+    }
+    pipeline::configure_http(&config);
+    if let Some(dir) = &cli.trace_api {
+        pipeline::configure_trace(dir)?;
+    }
+    pipeline::configure_credentials(&cli.api_keys, &config)?;
+    pipeline::configure_confidence(
+        cli.min_confidence.or(config.min_confidence),
+        cli.secondary_translator
+            .clone()
+            .or_else(|| config.secondary_translator.clone()),
+    )?;
+    pipeline::configure_budget(
+        config.budget.as_ref(),
+        cli.max_cost,
+        cli.max_characters,
+        cli.max_duration,
+    );
+    pipeline::configure_ensemble(if cli.ensemble.is_empty() {
+        &config.ensemble
+    } else {
+        &cli.ensemble
+    })?;
+    if let Some(pivot) = cli
+        .pivot_language
+        .as_ref()
+        .or(config.pivot_language.as_ref())
+    {
+        pipeline::configure_pivot(pivot);
+    }
+    if let Some(mode) = cli.bilingual.as_ref().or(config.bilingual.as_ref()) {
+        pipeline::configure_bilingual(pipeline::BilingualMode::parse(mode)?);
+    }
+    if let Some(transliterate) = &config.transliterate {
+        pipeline::configure_transliteration(transliterate)?;
+    }
+    if let Some(mode) = cli
+        .unsafe_characters
+        .as_ref()
+        .or(config.unsafe_characters.as_ref())
+    {
+        pipeline::configure_unsafe_characters(pipeline::UnsafeCharacters::parse(mode)?);
+    }
+    if !cli.no_cache && !cli.sandbox {
+        pipeline::configure_cache(langlint_core::default_cache_dir());
+        if let Some(shared) = &config.shared_memory {
+            shared_memory::configure(shared)?;
+        }
+    }
+    if let Some(path) = cli.glossary.as_ref().or(config.glossary.as_ref()) {
+        pipeline::configure_glossary(Glossary::load(Path::new(path))?);
+    }
+
+    let command = if cli.sandbox {
+        sandboxed(cli.command)?
+    } else {
+        cli.command
+    };
+
+    let result = match command {
         Commands::Scan {
             path,
             include,
             exclude,
             unit_types,
             priority,
+            include_todos,
+            include_strings,
+            include_license_headers,
+            include_generated,
+            packages,
+            by_package,
+            group_by,
+            sort,
+            strict,
             output,
+            heatmap,
         } => {
             scan::execute(
                 &path,
-                include,
-                exclude,
+                include.or_else(|| non_empty(&config.include)),
+                exclude.or_else(|| non_empty(&config.exclude)),
                 unit_types,
                 priority,
+                include_todos,
+                include_strings || config.includes_string_literals(),
+                include_license_headers || config.include_license_headers,
+                include_generated || config.include_generated,
+                &packages,
+                by_package,
+                &group_by,
+                sort.as_deref(),
+                strict,
                 output.as_deref(),
+                heatmap.as_deref(),
+                &cli.format,
+                cli.verbose,
+            )
+            .await
+        }
+        Commands::Check {
+            path,
+            include,
+            exclude,
+        } => {
+            check::execute(
+                &path,
+                include.or_else(|| non_empty(&config.include)),
+                exclude.or_else(|| non_empty(&config.exclude)),
+                &config.policy,
+                &cli.format,
+                cli.verbose,
+            )
+            .await
+        }
+        Commands::Stats {
+            path,
+            include,
+            exclude,
+            dominant,
+        } => {
+            stats::execute(
+                &path,
+                include.or_else(|| non_empty(&config.include)),
+                exclude.or_else(|| non_empty(&config.exclude)),
+                dominant,
+                &cli.format,
+                cli.verbose,
+            )
+            .await
+        }
+        Commands::Sync {
+            source,
+            translated,
+            no_stale,
+        } => sync::execute(&source, &translated, !no_stale, &cli.format, cli.verbose).await,
+        Commands::Export {
+            path,
+            include,
+            exclude,
+            source,
+            target,
+            output,
+        } => {
+            export::execute(
+                &path,
+                include.or_else(|| non_empty(&config.include)),
+                exclude.or_else(|| non_empty(&config.exclude)),
+                &source.unwrap_or_else(|| default_source(&config, &path)),
+                &target.unwrap_or_else(|| config.target_lang.clone()),
+                output.as_deref(),
+                &cli.format,
+                cli.verbose,
+            )
+            .await
+        }
+        Commands::Import {
+            file,
+            dry_run,
+            no_dry_run,
+            no_backup,
+        } => {
+            import::execute(
+                &file,
+                dry_run_setting(dry_run, no_dry_run, &config),
+                !no_backup && config.backup,
                 &cli.format,
                 cli.verbose,
             )
@@ -146,14 +852,57 @@ async fn main() -> Result<()> {
             translator,
             output,
             dry_run,
+            no_dry_run,
+            min_avg_confidence,
+            max_failed_units,
+            modified_before,
+            modified_after,
+            authors,
+            write_map,
+            source_map,
+            include_todos,
+            include_strings,
+            include_license_headers,
+            include_generated,
+            notebook_cells,
+            force,
+            output_encoding,
+            strict,
+            resume,
+            manifest,
         } => {
             translate::execute(
                 &path,
-                &source,
-                &target,
-                &translator,
+                &source.unwrap_or_else(|| default_source(&config, &path)),
+                &target.unwrap_or_else(|| config.target_lang.clone()),
+                &translator
+                    .clone()
+                    .unwrap_or_else(|| config.translator.clone()),
+                &routes_for(&config, translator.as_deref()),
                 output.as_deref(),
-                dry_run,
+                dry_run_setting(dry_run, no_dry_run, &config),
+                QualityGate {
+                    min_avg_confidence,
+                    max_failed_units,
+                },
+                HistoryFilter::from_dates(modified_before.as_deref(), modified_after.as_deref())?
+                    .with_authors(authors),
+                write_map,
+                source_map,
+                include_todos,
+                include_strings || config.includes_string_literals(),
+                include_license_headers || config.include_license_headers,
+                include_generated || config.include_generated,
+                NotebookCells::parse(if notebook_cells.is_empty() {
+                    &config.notebook_cells
+                } else {
+                    &notebook_cells
+                })?,
+                force,
+                output_encoding.as_deref(),
+                strict,
+                resume,
+                manifest.map(|path| RunManifest::new(&path, "translate", &config)),
                 &cli.format,
                 cli.verbose,
             )
@@ -166,18 +915,258 @@ async fn main() -> Result<()> {
             translator,
             yes,
             no_backup,
+            min_avg_confidence,
+            max_failed_units,
+            modified_before,
+            modified_after,
+            authors,
+            write_map,
+            source_map,
+            include_todos,
+            include_strings,
+            include_license_headers,
+            include_generated,
+            notebook_cells,
+            force,
+            output_encoding,
+            strict,
+            resume,
+            manifest,
         } => {
             fix::execute(
                 &path,
-                &source,
-                &target,
-                &translator,
+                &source.unwrap_or_else(|| default_source(&config, &path)),
+                &target.unwrap_or_else(|| config.target_lang.clone()),
+                &translator
+                    .clone()
+                    .unwrap_or_else(|| config.translator.clone()),
+                &routes_for(&config, translator.as_deref()),
                 yes,
-                no_backup,
+                !no_backup && config.backup,
+                QualityGate {
+                    min_avg_confidence,
+                    max_failed_units,
+                },
+                HistoryFilter::from_dates(modified_before.as_deref(), modified_after.as_deref())?
+                    .with_authors(authors),
+                write_map,
+                source_map,
+                include_todos,
+                include_strings || config.includes_string_literals(),
+                include_license_headers || config.include_license_headers,
+                include_generated || config.include_generated,
+                NotebookCells::parse(if notebook_cells.is_empty() {
+                    &config.notebook_cells
+                } else {
+                    &notebook_cells
+                })?,
+                force,
+                output_encoding.as_deref(),
+                strict,
+                resume,
+                manifest.map(|path| RunManifest::new(&path, "fix", &config)),
+                &cli.format,
+                cli.verbose,
+            )
+            .await
+        }
+        Commands::I18n {
+            path,
+            template,
+            source,
+            target,
+            translator,
+            locales_dir,
+            dry_run,
+            no_dry_run,
+            no_backup,
+        } => {
+            let targets = if target.is_empty() {
+                vec![config.target_lang.clone()]
+            } else {
+                target
+            };
+            i18n::execute(
+                &path,
+                template.as_deref(),
+                &source.unwrap_or_else(|| default_source(&config, &path)),
+                &targets,
+                &translator
+                    .clone()
+                    .unwrap_or_else(|| config.translator.clone()),
+                &routes_for(&config, translator.as_deref()),
+                &locales_dir,
+                dry_run_setting(dry_run, no_dry_run, &config),
+                !no_backup && config.backup,
+                &cli.format,
+                cli.verbose,
+            )
+            .await
+        }
+        Commands::CommitMsg {
+            file,
+            source,
+            target,
+            translator,
+            rewrite,
+        } => {
+            commit_msg::execute(
+                &file,
+                &source.unwrap_or_else(|| default_source(&config, ".")),
+                &target.unwrap_or_else(|| config.target_lang.clone()),
+                &translator
+                    .clone()
+                    .unwrap_or_else(|| config.translator.clone()),
+                &routes_for(&config, translator.as_deref()),
+                rewrite,
+                &cli.format,
+                cli.verbose,
+            )
+            .await
+        }
+        Commands::Rename {
+            path,
+            source,
+            target,
+            translator,
+            transliterate,
+            apply,
+            git,
+        } => {
+            rename::execute(
+                &path,
+                &source.unwrap_or_else(|| default_source(&config, &path)),
+                &target.unwrap_or_else(|| config.target_lang.clone()),
+                &translator
+                    .clone()
+                    .unwrap_or_else(|| config.translator.clone()),
+                &routes_for(&config, translator.as_deref()),
+                transliterate,
+                apply,
+                git,
                 &cli.format,
                 cli.verbose,
             )
             .await
         }
+        Commands::InstallHooks {
+            path,
+            rewrite,
+            force,
+        } => hooks::execute(&path, rewrite, force, cli.verbose).await,
+        Commands::Revert { path, run, line } => {
+            revert::execute(&path, run, &line, &cli.format, cli.verbose).await
+        }
+        Commands::Cache { action } => match action {
+            CacheAction::Clear => cache::clear(cli.verbose).await,
+        },
+    };
+    let result = result.and(pipeline::flush_memory());
+
+    // Written even when the command failed, so errors show up in monitoring
+    if let Some(path) = &cli.metrics_file {
+        pipeline::write_metrics(path)?;
+    }
+
+    if let Err(e) = &result {
+        if interrupt::is_interrupt(e) {
+            eprintln!("Error: {:?}", e);
+            std::process::exit(interrupt::EXIT_CODE);
+        }
+    }
+    result
+}
+
+/// Turn writing commands into dry runs, or refuse them, for `--sandbox`
+fn sandboxed(mut command: Commands) -> Result<Commands> {
+    match &mut command {
+        Commands::Scan {
+            output, heatmap, ..
+        } => {
+            if output.is_some() {
+                sandbox::ensure_writable("--output")?;
+            }
+            if heatmap.is_some() {
+                sandbox::ensure_writable("--heatmap")?;
+            }
+        }
+        Commands::Export {
+            output: Some(_), ..
+        } => sandbox::ensure_writable("export --output")?,
+        Commands::Translate {
+            dry_run,
+            no_dry_run,
+            ..
+        }
+        | Commands::I18n {
+            dry_run,
+            no_dry_run,
+            ..
+        }
+        | Commands::Import {
+            dry_run,
+            no_dry_run,
+            ..
+        } => {
+            *dry_run = true;
+            *no_dry_run = false;
+        }
+        Commands::Fix { .. } => sandbox::ensure_writable("fix (use translate --dry-run)")?,
+        Commands::CommitMsg { rewrite: true, .. } => {
+            sandbox::ensure_writable("commit-msg --rewrite")?
+        }
+        Commands::Rename { apply: true, .. } => sandbox::ensure_writable("rename --apply")?,
+        Commands::InstallHooks { .. } => sandbox::ensure_writable("install-hooks")?,
+        Commands::Revert { .. } => sandbox::ensure_writable("revert")?,
+        Commands::Cache { .. } => sandbox::ensure_writable("cache")?,
+        Commands::Check { .. }
+        | Commands::Stats { .. }
+        | Commands::Sync { .. }
+        | Commands::Export { .. }
+        | Commands::CommitMsg { .. }
+        | Commands::Rename { .. } => {}
+    }
+    Ok(command)
+}
+
+/// Whether to dry run: `--dry-run`/`--no-dry-run` win over the config and
+/// `LANGLINT_DRY_RUN`
+fn dry_run_setting(dry_run: bool, no_dry_run: bool, config: &Config) -> bool {
+    dry_run || (!no_dry_run && config.dry_run)
+}
+
+/// Source language to use for `input` when none is given on the command line
+///
+/// A single configured source language is used directly. Otherwise (the
+/// default `["auto"]` or a list of candidates) the language `langlint stats`
+/// recorded for the input is used if it is one of them, else auto-detection.
+fn default_source(config: &Config, input: &str) -> String {
+    match config.source_lang.as_slice() {
+        [single] if single != "auto" => single.clone(),
+        candidates => state::recorded_source(input)
+            .filter(|recorded| {
+                candidates
+                    .iter()
+                    .any(|candidate| candidate == "auto" || candidate == recorded)
+            })
+            .unwrap_or_else(|| "auto".to_string()),
+    }
+}
+
+/// Language-pair routes to use; an explicit `--translator` disables routing
+fn routes_for(config: &Config, cli_translator: Option<&str>) -> BTreeMap<String, String> {
+    if cli_translator.is_some() {
+        BTreeMap::new()
+    } else {
+        config.routes.clone()
+    }
+}
+
+/// Turn an empty config list into `None` so CLI defaults still apply
+fn non_empty(patterns: &[String]) -> Option<Vec<String>> {
+    if patterns.is_empty() {
+        None
+    } else {
+        Some(patterns.to_vec())
     }
 }
@@ -0,0 +1,249 @@
+//! Run manifests - what a translate or fix run changed
+//!
+//! With `--manifest FILE` a run writes the files it touched with their sizes
+//! before and after, units per status, the translator and a hash of the
+//! configuration. The manifest can be attached to a pull request for
+//! review, and tells how to repeat the run later.
+
+use anyhow::{Context, Result};
+use langlint_core::{paths, Config};
+use langlint_translators::Translator;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::pipeline::{self, RunStats};
+
+/// Record of one run, written as JSON
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunManifest {
+    #[serde(skip)]
+    path: PathBuf,
+    pub langlint_version: &'static str,
+    /// Command that ran (`translate`, `fix`)
+    pub command: &'static str,
+    /// Start of the run in Unix seconds
+    pub run: u64,
+    pub input: String,
+    pub source: String,
+    pub target: String,
+    pub translator: TranslatorInfo,
+    /// FNV-1a hash of the effective configuration
+    pub config_hash: String,
+    /// False when the run stopped early (budget, time limit, Ctrl+C)
+    pub complete: bool,
+    pub files: Vec<FileEntry>,
+    pub totals: UnitCounts,
+    pub bytes_changed: usize,
+    /// Files that could not be read or parsed
+    pub errors: usize,
+    /// Files not written because verification flagged them
+    pub quarantined: usize,
+}
+
+/// Translator of a run, with its model when it has one
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TranslatorInfo {
+    pub name: String,
+    /// Name including wrappers that change the output (glossary, pivot)
+    pub cache_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Translators of a fallback chain
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain: Option<String>,
+}
+
+/// A file written by the run
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileEntry {
+    pub path: String,
+    /// Where the translation was written, when not over the input
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+    /// Bytes of original text replaced by translations
+    pub bytes_changed: usize,
+    pub units: UnitCounts,
+}
+
+/// Units by translation status
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct UnitCounts {
+    pub translated: usize,
+    pub failed: usize,
+    /// Already in the target language
+    pub skipped: usize,
+    /// Kept in the original for being below the minimum confidence
+    pub review: usize,
+}
+
+impl UnitCounts {
+    pub fn from_stats(stats: &RunStats) -> Self {
+        Self {
+            translated: stats
+                .units
+                .saturating_sub(stats.failed + stats.review.len()),
+            failed: stats.failed,
+            skipped: stats.skipped,
+            review: stats.review.len(),
+        }
+    }
+}
+
+impl RunManifest {
+    /// Manifest of a `command` run, to be written to `path`
+    pub fn new(path: &str, command: &'static str, config: &Config) -> Self {
+        Self {
+            path: PathBuf::from(path),
+            langlint_version: env!("CARGO_PKG_VERSION"),
+            command,
+            run: pipeline::run_timestamp(),
+            input: String::new(),
+            source: String::new(),
+            target: String::new(),
+            translator: TranslatorInfo::default(),
+            config_hash: config_hash(config),
+            complete: true,
+            files: Vec::new(),
+            totals: UnitCounts::default(),
+            bytes_changed: 0,
+            errors: 0,
+            quarantined: 0,
+        }
+    }
+
+    /// File the manifest is written to
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Record the input, languages and translator of the run
+    pub fn start(&mut self, input: &str, source: &str, target: &str, translator: &dyn Translator) {
+        let info = translator.get_usage_info();
+        self.input = input.to_string();
+        self.source = source.to_string();
+        self.target = target.to_string();
+        self.translator = TranslatorInfo {
+            name: translator.name().to_string(),
+            cache_name: translator.cache_name(),
+            model: info.get("model").cloned(),
+            chain: info.get("chain").cloned(),
+        };
+    }
+
+    /// Record a file translated from `input` into `output`, `bytes_before`
+    /// long before the run
+    ///
+    /// Files without translated or skipped units were not written and are
+    /// left out.
+    pub fn record(&mut self, input: &Path, output: &Path, bytes_before: u64, stats: &RunStats) {
+        if stats.units == 0 && stats.skipped == 0 {
+            return;
+        }
+        let units = UnitCounts::from_stats(stats);
+        self.totals.translated += units.translated;
+        self.totals.failed += units.failed;
+        self.totals.skipped += units.skipped;
+        self.totals.review += units.review;
+        self.bytes_changed += stats.bytes_changed;
+        self.files.push(FileEntry {
+            path: paths::to_slash(input),
+            output: (output != input).then(|| paths::to_slash(output)),
+            bytes_before,
+            bytes_after: file_size(output),
+            bytes_changed: stats.bytes_changed,
+            units,
+        });
+    }
+
+    /// Write the manifest of a run that ended, completely or not
+    pub fn finish(mut self, complete: bool, errors: usize, quarantined: usize) -> Result<()> {
+        self.complete = complete;
+        self.errors = errors;
+        self.quarantined = quarantined;
+        let content = serde_json::to_string_pretty(&self)?;
+        fs::write(&self.path, content + "\n")
+            .with_context(|| format!("Failed to write manifest: {}", self.path.display()))
+    }
+}
+
+/// Size of a file, 0 if it does not exist
+pub fn file_size(path: &Path) -> u64 {
+    fs::metadata(path).map_or(0, |metadata| metadata.len())
+}
+
+/// Hash of a configuration, the same for equal configurations
+fn config_hash(config: &Config) -> String {
+    // Through a `Value` so map fields are serialized in sorted order
+    let text = serde_json::to_value(config)
+        .map(|value| value.to_string())
+        .unwrap_or_default();
+    let hash = text.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::ReviewItem;
+
+    #[test]
+    fn test_manifest_records_written_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.py");
+        fs::write(&file, "# Hello\n").unwrap();
+        let path = dir.path().join("manifest.json");
+
+        let config = Config::default();
+        let mut manifest = RunManifest::new(path.to_str().unwrap(), "fix", &config);
+        assert_eq!(manifest.config_hash, config_hash(&Config::default()));
+        assert_ne!(
+            manifest.config_hash,
+            config_hash(&Config {
+                target_lang: "ja".to_string(),
+                ..Config::default()
+            })
+        );
+
+        let stats = RunStats {
+            units: 4,
+            failed: 1,
+            skipped: 2,
+            bytes_changed: 12,
+            review: vec![ReviewItem {
+                file: String::new(),
+                line: 3,
+                original: "注释".to_string(),
+                translation: "Note".to_string(),
+                confidence: 0.2,
+            }],
+            ..RunStats::default()
+        };
+        manifest.record(&file, &file, 10, &stats);
+        manifest.record(&dir.path().join("b.py"), &file, 10, &RunStats::default());
+        assert_eq!(manifest.files.len(), 1);
+        assert_eq!(manifest.files[0].bytes_after, 8);
+        assert_eq!(manifest.files[0].output, None);
+        assert_eq!(
+            manifest.totals,
+            UnitCounts {
+                translated: 2,
+                failed: 1,
+                skipped: 2,
+                review: 1
+            }
+        );
+
+        manifest.finish(false, 1, 0).unwrap();
+        let written: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["command"], "fix");
+        assert_eq!(written["complete"], false);
+        assert_eq!(written["bytes_changed"], 12);
+        assert_eq!(written["files"][0]["units"]["translated"], 2);
+    }
+}
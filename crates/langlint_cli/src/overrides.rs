@@ -0,0 +1,30 @@
+//! Parser overrides for this process
+//!
+//! Wraps the config's [`ParserOverrides`], set once at startup like the walk
+//! filters.
+
+use anyhow::Result;
+use langlint_parsers::overrides::ParserOverrides;
+use langlint_parsers::Parser;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+static OVERRIDES: OnceLock<ParserOverrides> = OnceLock::new();
+
+/// Use the config's parser overrides for this process
+///
+/// Fails on an invalid glob or an unknown parser name.
+pub fn configure(parsers: &BTreeMap<String, String>) -> Result<()> {
+    let _ = OVERRIDES.set(ParserOverrides::new(parsers)?);
+    Ok(())
+}
+
+/// Parser for a file: the configured one, else the first parser that can
+/// handle it
+pub fn parser_for(path: &Path, content: Option<&str>) -> Option<Box<dyn Parser>> {
+    match OVERRIDES.get() {
+        Some(overrides) => overrides.resolve(path, content),
+        None => langlint_parsers::parser_for(&path.to_string_lossy(), content),
+    }
+}
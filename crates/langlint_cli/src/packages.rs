@@ -0,0 +1,281 @@
+//! Monorepo package detection
+//!
+//! Package boundaries come from manifests: `Cargo.toml` with a `[package]`
+//! table, `package.json` with a `name`, and `pyproject.toml` (PEP 621 or
+//! Poetry) or `setup.py` for Python packages. Workspace members are found
+//! through their own manifests, so virtual workspace roots are not packages.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::walk;
+
+/// Name used for files outside every detected package
+pub const ROOT_PACKAGE: &str = "(root)";
+
+/// Manifest kind a package was detected from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageKind {
+    Cargo,
+    Npm,
+    Python,
+}
+
+/// A package inside the scanned tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Package {
+    pub name: String,
+    pub kind: PackageKind,
+    /// Directory containing the manifest
+    pub root: PathBuf,
+}
+
+/// Per-package totals of a scan
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PackageSummary {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<PackageKind>,
+    pub root: String,
+    pub files: usize,
+    pub units: usize,
+}
+
+/// Detect all packages below `root`, sorted by root path
+pub fn detect_packages(root: &Path) -> Result<Vec<Package>> {
+    let mut packages = Vec::new();
+
+    for entry in WalkDir::new(root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(walk::keep_entry)
+    {
+        let entry = entry?;
+        if entry.file_type().is_dir() {
+            if let Some(package) = package_at(entry.path()) {
+                packages.push(package);
+            }
+        }
+    }
+
+    packages.sort_by(|a, b| a.root.cmp(&b.root));
+    Ok(packages)
+}
+
+/// Read the package defined in a directory, if any
+fn package_at(dir: &Path) -> Option<Package> {
+    let read = |file: &str| fs::read_to_string(dir.join(file)).ok();
+    let package = |name: &str, kind| Package {
+        name: name.to_string(),
+        kind,
+        root: dir.to_path_buf(),
+    };
+
+    if let Some(manifest) = read("Cargo.toml") {
+        let value: toml::Value = toml::from_str(&manifest).ok()?;
+        if let Some(name) = value
+            .get("package")
+            .and_then(|p| p.get("name"))
+            .and_then(|n| n.as_str())
+        {
+            return Some(package(name, PackageKind::Cargo));
+        }
+    }
+
+    if let Some(manifest) = read("package.json") {
+        let value: serde_json::Value = serde_json::from_str(&manifest).ok()?;
+        if let Some(name) = value.get("name").and_then(|n| n.as_str()) {
+            return Some(package(name, PackageKind::Npm));
+        }
+    }
+
+    if let Some(manifest) = read("pyproject.toml") {
+        let value: toml::Value = toml::from_str(&manifest).ok()?;
+        let name = value
+            .get("project")
+            .and_then(|p| p.get("name"))
+            .or_else(|| {
+                value
+                    .get("tool")
+                    .and_then(|t| t.get("poetry"))
+                    .and_then(|p| p.get("name"))
+            })
+            .and_then(|n| n.as_str());
+        if let Some(name) = name {
+            return Some(package(name, PackageKind::Python));
+        }
+    }
+
+    if dir.join("setup.py").is_file() {
+        let name = dir.file_name()?.to_string_lossy();
+        return Some(package(&name, PackageKind::Python));
+    }
+
+    None
+}
+
+/// Innermost package containing a path
+pub fn package_for<'a>(packages: &'a [Package], path: &Path) -> Option<&'a Package> {
+    package_index(packages, path).map(|index| &packages[index])
+}
+
+fn package_index(packages: &[Package], path: &Path) -> Option<usize> {
+    packages
+        .iter()
+        .enumerate()
+        .filter(|(_, package)| path.starts_with(&package.root))
+        .max_by_key(|(_, package)| package.root.components().count())
+        .map(|(index, _)| index)
+}
+
+/// Aggregate per-file unit counts by package
+///
+/// Package roots are reported relative to `base`. Files outside every
+/// package are grouped under [`ROOT_PACKAGE`].
+pub fn summarize<'a>(
+    packages: &[Package],
+    base: &Path,
+    files: impl IntoIterator<Item = (&'a Path, usize)>,
+) -> Vec<PackageSummary> {
+    let mut summaries: Vec<PackageSummary> = packages
+        .iter()
+        .map(|package| PackageSummary {
+            name: package.name.clone(),
+            kind: Some(package.kind),
+            root: display_root(&package.root, base),
+            files: 0,
+            units: 0,
+        })
+        .collect();
+    let mut root = PackageSummary {
+        name: ROOT_PACKAGE.to_string(),
+        kind: None,
+        root: ".".to_string(),
+        files: 0,
+        units: 0,
+    };
+
+    for (path, units) in files {
+        let summary = match package_index(packages, path) {
+            Some(index) => &mut summaries[index],
+            None => &mut root,
+        };
+        summary.files += 1;
+        summary.units += units;
+    }
+
+    if root.files > 0 {
+        summaries.push(root);
+    }
+    summaries
+}
+
+fn display_root(root: &Path, base: &Path) -> String {
+    match root.strip_prefix(base) {
+        Ok(relative) if relative.as_os_str().is_empty() => ".".to_string(),
+        Ok(relative) => relative.display().to_string(),
+        Err(_) => root.display().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn monorepo() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\n",
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("crates/core/src")).unwrap();
+        fs::write(
+            root.join("crates/core/Cargo.toml"),
+            "[package]\nname = \"core\"\n",
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("web/node_modules/dep")).unwrap();
+        fs::write(root.join("web/package.json"), r#"{"name": "@acme/web"}"#).unwrap();
+        fs::write(
+            root.join("web/node_modules/dep/package.json"),
+            r#"{"name": "dep"}"#,
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("tools/cli")).unwrap();
+        fs::write(
+            root.join("tools/pyproject.toml"),
+            "[project]\nname = \"acme-tools\"\n",
+        )
+        .unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_detect_packages() {
+        let dir = monorepo();
+        let packages = detect_packages(dir.path()).unwrap();
+        let names: Vec<(&str, PackageKind)> =
+            packages.iter().map(|p| (p.name.as_str(), p.kind)).collect();
+
+        assert_eq!(names.len(), 3);
+        assert!(names.contains(&("core", PackageKind::Cargo)));
+        assert!(names.contains(&("@acme/web", PackageKind::Npm)));
+        assert!(names.contains(&("acme-tools", PackageKind::Python)));
+    }
+
+    #[test]
+    fn test_package_for_picks_innermost() {
+        let packages = vec![
+            Package {
+                name: "outer".to_string(),
+                kind: PackageKind::Npm,
+                root: PathBuf::from("repo"),
+            },
+            Package {
+                name: "inner".to_string(),
+                kind: PackageKind::Npm,
+                root: PathBuf::from("repo/packages/inner"),
+            },
+        ];
+
+        let found = |path: &str| package_for(&packages, Path::new(path)).map(|p| p.name.as_str());
+        assert_eq!(found("repo/packages/inner/src/a.js"), Some("inner"));
+        assert_eq!(found("repo/src/b.js"), Some("outer"));
+        assert_eq!(found("repo/packages/inner2/c.js"), Some("outer"));
+        assert_eq!(found("elsewhere/d.js"), None);
+    }
+
+    #[test]
+    fn test_summarize() {
+        let dir = monorepo();
+        let packages = detect_packages(dir.path()).unwrap();
+        let core = dir.path().join("crates/core/src/lib.rs");
+        let web = dir.path().join("web/index.js");
+        let script = dir.path().join("build.py");
+
+        let summaries = summarize(
+            &packages,
+            dir.path(),
+            [
+                (core.as_path(), 3),
+                (web.as_path(), 2),
+                (script.as_path(), 1),
+            ],
+        );
+        let core = summaries.iter().find(|s| s.name == "core").unwrap();
+        assert_eq!((core.files, core.units), (1, 3));
+        assert_eq!(core.root, "crates/core");
+        let tools = summaries.iter().find(|s| s.name == "acme-tools").unwrap();
+        assert_eq!((tools.files, tools.units), (0, 0));
+        let root = summaries.last().unwrap();
+        assert_eq!(root.name, ROOT_PACKAGE);
+        assert_eq!((root.files, root.units), (1, 1));
+    }
+}
@@ -0,0 +1,2781 @@
+//! Translation pipeline shared by the translate and fix commands
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use langlint_core::format_string::{self, Numbering};
+use langlint_core::invisible;
+use langlint_core::segmentation::Segmented;
+use langlint_core::transliterate::Transliteration;
+use langlint_core::types::detect_language;
+use langlint_core::{
+    Bilingual, BudgetConfig, Config, FileEncoding, MarkdownBlocks, Masked, ParseResult, Priority,
+    ProtectConfig, Protection, RateLimitConfig, RetryConfig, SegmentationRules, SourceMap,
+    TranslatableUnit, TranslationMemory, TransliterateConfig, UnitType,
+};
+use langlint_parsers::{generated, jsx, license, paragraph, python, sinks, todo};
+use langlint_translators::{
+    credentials, trace, Budget, BudgetTranslator, CachedTranslator, CostTracker,
+    EnsembleTranslator, FallbackTranslator, Glossary, GlossaryTranslator, HttpSettings,
+    MetricsTranslator, PivotTranslator, RateLimit, RetryPolicy, RoutingTranslator, SharedStore,
+    TranslationError, TranslationMetrics, TranslationRequest, TranslationResult, TranslationStatus,
+    Translator, TranslatorRegistry,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::blame;
+use crate::directives::Directive;
+use crate::limits::OverLimit;
+use crate::sandbox;
+use crate::shared_memory;
+use crate::ui::tr;
+
+/// Maximum number of notebook cells translated concurrently
+const CELL_CONCURRENCY: usize = 4;
+
+/// Create a translator by name from the global [`TranslatorRegistry`]
+///
+/// A comma-separated list (`deepl,google,mock`) builds a
+/// [`FallbackTranslator`] trying each in turn. Every translator records its
+/// requests into the process-wide [`metrics`] and charges them to the
+/// [`cost_tracker`].
+pub fn create_translator(name: &str) -> Result<Box<dyn Translator>> {
+    if name.contains(',') {
+        let chain = name
+            .split(',')
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .map(create_translator)
+            .collect::<Result<Vec<_>>>()?;
+        return Ok(Box::new(FallbackTranslator::new(chain)?));
+    }
+
+    sandbox::check_translator(name)?;
+    let translator = TranslatorRegistry::global().create(name)?;
+    let translator = MetricsTranslator::new(translator, Arc::clone(metrics()));
+    Ok(Box::new(BudgetTranslator::new(
+        Box::new(translator),
+        Arc::clone(cost_tracker()),
+    )))
+}
+
+static BUDGET: OnceLock<Budget> = OnceLock::new();
+
+/// Enforce a per-run budget on all translators of this process
+///
+/// `--max-cost` and `--max-characters` override the config's limits;
+/// `--max-duration` stops sending requests that long after the start. Must
+/// run before translators are created.
+pub fn configure_budget(
+    config: Option<&BudgetConfig>,
+    max_cost: Option<f64>,
+    max_characters: Option<usize>,
+    max_duration: Option<Duration>,
+) {
+    let config = config.copied().unwrap_or_default();
+    let _ = BUDGET.set(Budget {
+        max_cost: max_cost.or(config.max_cost),
+        max_characters: max_characters.or(config.max_characters),
+        deadline: max_duration.and_then(|duration| Instant::now().checked_add(duration)),
+    });
+}
+
+/// Parse a duration such as `90s`, `10m` or `1h30m` (bare numbers are seconds)
+///
+/// Durations too long to count from now are rejected.
+pub fn parse_duration(text: &str) -> Result<Duration> {
+    let text = text.trim();
+    let invalid = || {
+        anyhow::anyhow!(
+            "Invalid duration '{}' (expected e.g. 90s, 10m, 1h30m)",
+            text
+        )
+    };
+
+    let total = match text.parse::<u64>() {
+        Ok(seconds) => seconds,
+        Err(_) => {
+            let mut total: u64 = 0;
+            let mut number = String::new();
+            for c in text.chars() {
+                if c.is_ascii_digit() {
+                    number.push(c);
+                    continue;
+                }
+                let unit = match c {
+                    'h' => 3600,
+                    'm' => 60,
+                    's' => 1,
+                    _ => return Err(invalid()),
+                };
+                let value: u64 = number.parse().map_err(|_| invalid())?;
+                total = value
+                    .checked_mul(unit)
+                    .and_then(|seconds| total.checked_add(seconds))
+                    .ok_or_else(invalid)?;
+                number.clear();
+            }
+            if !number.is_empty() || total == 0 {
+                return Err(invalid());
+            }
+            total
+        }
+    };
+
+    let duration = Duration::from_secs(total);
+    Instant::now().checked_add(duration).ok_or_else(invalid)?;
+    Ok(duration)
+}
+
+/// Characters and estimated cost sent to translators by this process
+pub fn cost_tracker() -> &'static Arc<CostTracker> {
+    static TRACKER: OnceLock<Arc<CostTracker>> = OnceLock::new();
+    TRACKER.get_or_init(|| Arc::new(CostTracker::new(BUDGET.get().copied().unwrap_or_default())))
+}
+
+/// Check if a file failed because the run's budget is used up
+pub fn is_budget_exceeded(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<TranslationError>(),
+            Some(TranslationError::BudgetExceeded(_))
+        )
+    })
+}
+
+/// Print what was sent to translators against the budget, for run summaries
+pub fn print_cost_report() {
+    let tracker = cost_tracker();
+    let total = tracker.total();
+    let budget = tracker.budget();
+    if total.requests == 0 && !budget.is_limited() {
+        return;
+    }
+
+    println!("  Sent to translators: {}", total);
+    let by_translator = tracker.by_translator();
+    if by_translator.len() > 1 {
+        for (name, spending) in &by_translator {
+            println!("    {}: {}", name, spending);
+        }
+    }
+    if let Some(max) = budget.max_cost {
+        println!("  Cost budget: ${:.4} of ${:.2}", total.cost, max);
+    }
+    if let Some(max) = budget.max_characters {
+        println!("  Character budget: {} of {}", total.characters, max);
+    }
+    if let Some(deadline) = budget.deadline {
+        match deadline.checked_duration_since(Instant::now()) {
+            Some(left) => println!("  Time limit: {}s left", left.as_secs()),
+            None => println!("  Time limit: reached"),
+        }
+    }
+}
+
+/// Apply a configured retry policy to all translators of this process
+///
+/// Unset fields keep the general defaults. Must run before translators
+/// are created.
+pub fn configure_retry(config: &RetryConfig) {
+    let default = RetryPolicy::default();
+    langlint_translators::retry::set_retry_policy(RetryPolicy {
+        max_attempts: config.max_attempts.unwrap_or(default.max_attempts),
+        base_delay: config
+            .base_delay_ms
+            .map_or(default.base_delay, Duration::from_millis),
+        max_delay: config
+            .max_delay_ms
+            .map_or(default.max_delay, Duration::from_millis),
+        jitter: config.jitter.unwrap_or(default.jitter),
+        honor_retry_after: config
+            .honor_retry_after
+            .unwrap_or(default.honor_retry_after),
+    });
+}
+
+/// Apply the config's proxy and CA bundle to all translators of this process
+///
+/// Must run before translators are created.
+pub fn configure_http(config: &Config) {
+    langlint_translators::http::set_http_settings(HttpSettings {
+        proxy: config.proxy.clone(),
+        no_proxy: config.no_proxy.clone(),
+        ca_bundle: config.ca_bundle.as_ref().map(PathBuf::from),
+    });
+}
+
+/// Dump translator API calls into `dir` (`--trace-api`)
+pub fn configure_trace(dir: &Path) -> Result<()> {
+    trace::set_trace_dir(dir.to_path_buf())
+        .with_context(|| format!("Failed to create trace directory {}", dir.display()))
+}
+
+/// Make `--api-key PROVIDER=KEY` flags, then the config's keys, and the
+/// keyring setting available to all translators of this process
+pub fn configure_credentials(flags: &[String], config: &Config) -> Result<()> {
+    for flag in flags {
+        // The value is not echoed, as it may be a bare key
+        let Some((provider, key)) = flag.split_once('=') else {
+            anyhow::bail!("Invalid --api-key value (expected PROVIDER=KEY)");
+        };
+        credentials::set_api_key(provider.trim(), key);
+    }
+    for (provider, key) in &config.api_keys {
+        credentials::set_api_key(provider, key);
+    }
+    credentials::use_keyring(config.keyring);
+    Ok(())
+}
+
+/// Apply configured per-translator rate limits to this process
+///
+/// Must run before translators are created.
+pub fn configure_rate_limits(limits: &BTreeMap<String, RateLimitConfig>) {
+    for (name, limit) in limits {
+        langlint_translators::ratelimit::set_rate_limit(
+            name,
+            RateLimit {
+                requests_per_minute: limit.requests_per_minute,
+                characters_per_minute: limit.characters_per_minute,
+            },
+        );
+    }
+}
+
+static MEMORY: OnceLock<Arc<TranslationMemory>> = OnceLock::new();
+static CACHE_DIR: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+/// Keep the translation memory in `dir` across runs; `None` keeps it in memory
+///
+/// Must run before the first translation. The cache is opened lazily, so
+/// commands that never translate do not touch it.
+pub fn configure_cache(dir: Option<PathBuf>) {
+    let _ = CACHE_DIR.set(dir);
+}
+
+/// Translation memory for this process
+///
+/// Identical (after normalization) texts are translated once per run, and
+/// with a [`configure_cache`] directory once across runs.
+pub fn memory() -> &'static Arc<TranslationMemory> {
+    MEMORY.get_or_init(|| {
+        Arc::new(match CACHE_DIR.get().cloned().flatten() {
+            Some(dir) => TranslationMemory::open(&dir).unwrap_or_else(|e| {
+                eprintln!(
+                    "{} {:#}; continuing without the translation cache",
+                    "Warning:".yellow(),
+                    e
+                );
+                TranslationMemory::new()
+            }),
+            None => TranslationMemory::new(),
+        })
+    })
+}
+
+/// Answer repeated texts from the process-wide [`memory`], and from the
+/// shared memory if one is configured
+fn cached(translator: Box<dyn Translator>) -> Box<dyn Translator> {
+    let translator = CachedTranslator::with_memory(translator, Arc::clone(memory()))
+        .with_metrics(Arc::clone(metrics()));
+    match shared_memory::get() {
+        Some(shared) => {
+            Box::new(translator.with_shared(Arc::clone(shared) as Arc<dyn SharedStore>))
+        }
+        None => Box::new(translator),
+    }
+}
+
+/// Write cached translations to disk, if the memory was used
+pub fn flush_memory() -> Result<()> {
+    match MEMORY.get() {
+        Some(memory) => memory.flush(),
+        None => Ok(()),
+    }
+}
+
+/// Translation metrics for this process
+pub fn metrics() -> &'static Arc<TranslationMetrics> {
+    static METRICS: OnceLock<Arc<TranslationMetrics>> = OnceLock::new();
+    METRICS.get_or_init(|| Arc::new(TranslationMetrics::new()))
+}
+
+/// Write the process metrics in OpenMetrics text format (for a textfile collector)
+pub fn write_metrics(path: &str) -> Result<()> {
+    std::fs::write(path, metrics().render())
+        .with_context(|| format!("Failed to write metrics to: {}", path))
+}
+
+static SEGMENTATION: OnceLock<SegmentationRules> = OnceLock::new();
+
+/// Split units of the configured types into sentences or paragraphs when
+/// translating
+pub fn configure_segmentation(rules: &BTreeMap<String, String>) -> Result<()> {
+    let _ = SEGMENTATION.set(SegmentationRules::from_config(rules)?);
+    Ok(())
+}
+
+fn segmentation() -> &'static SegmentationRules {
+    SEGMENTATION.get_or_init(SegmentationRules::default)
+}
+
+static PROTECTION: OnceLock<Protection> = OnceLock::new();
+
+/// Use the config's protected regions for this process
+///
+/// Until called, every built-in heuristic applies.
+pub fn configure_protection(config: &ProtectConfig) -> Result<()> {
+    let _ = PROTECTION.set(Protection::new(config)?);
+    Ok(())
+}
+
+fn protection() -> &'static Protection {
+    PROTECTION.get_or_init(Protection::default)
+}
+
+/// Protection of markdown text nodes, which also keeps math, images and HTML
+fn markdown_protection() -> &'static Protection {
+    static MARKDOWN: OnceLock<Protection> = OnceLock::new();
+    MARKDOWN.get_or_init(|| protection().with_markup())
+}
+
+static TRANSLITERATION: OnceLock<Transliteration> = OnceLock::new();
+
+/// Transliterate the configured units and regions instead of translating
+/// them
+pub fn configure_transliteration(config: &TransliterateConfig) -> Result<()> {
+    let _ = TRANSLITERATION.set(Transliteration::from_config(config)?);
+    Ok(())
+}
+
+static GLOSSARY: OnceLock<Glossary> = OnceLock::new();
+static ENSEMBLE: OnceLock<Vec<String>> = OnceLock::new();
+static PIVOT: OnceLock<String> = OnceLock::new();
+
+/// Enforce a glossary on every translator built with [`build_translator`]
+pub fn configure_glossary(glossary: Glossary) {
+    let _ = GLOSSARY.set(glossary);
+}
+
+/// What a run does with comments written in two languages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BilingualMode {
+    /// Translate the whole comment
+    #[default]
+    Translate,
+    /// Keep only the half in the target language
+    KeepTarget,
+    /// Translate only the other half, keeping the target half as written
+    TranslateForeign,
+}
+
+impl BilingualMode {
+    /// Parse a `--bilingual` mode
+    pub fn parse(mode: &str) -> Result<Self> {
+        match mode.trim().to_lowercase().as_str() {
+            "translate" => Ok(Self::Translate),
+            "keep-target" => Ok(Self::KeepTarget),
+            "translate-foreign" => Ok(Self::TranslateForeign),
+            other => anyhow::bail!(
+                "Unknown bilingual mode '{}' (expected translate, keep-target or translate-foreign)",
+                other
+            ),
+        }
+    }
+}
+
+static BILINGUAL: OnceLock<BilingualMode> = OnceLock::new();
+
+/// Choose how comments written in two languages are translated
+pub fn configure_bilingual(mode: BilingualMode) {
+    let _ = BILINGUAL.set(mode);
+}
+
+fn bilingual_mode() -> BilingualMode {
+    BILINGUAL.get().copied().unwrap_or_default()
+}
+
+/// What a run does with control, bidi and invisible characters that
+/// translations introduce
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnsafeCharacters {
+    /// Write them as translated
+    #[default]
+    Allow,
+    /// Remove them from the translations
+    Strip,
+    /// Quarantine files they would be written to
+    Reject,
+}
+
+impl UnsafeCharacters {
+    /// Parse an `unsafe_characters` mode
+    pub fn parse(mode: &str) -> Result<Self> {
+        match mode.trim().to_lowercase().as_str() {
+            "allow" => Ok(Self::Allow),
+            "strip" => Ok(Self::Strip),
+            "reject" => Ok(Self::Reject),
+            other => anyhow::bail!(
+                "Unknown unsafe_characters mode '{}' (expected allow, strip or reject)",
+                other
+            ),
+        }
+    }
+}
+
+static UNSAFE_CHARACTERS: OnceLock<UnsafeCharacters> = OnceLock::new();
+
+/// Choose how characters translations must not introduce are handled
+pub fn configure_unsafe_characters(mode: UnsafeCharacters) {
+    let _ = UNSAFE_CHARACTERS.set(mode);
+}
+
+fn unsafe_characters() -> UnsafeCharacters {
+    UNSAFE_CHARACTERS.get().copied().unwrap_or_default()
+}
+
+/// Bridge language pairs translators lack through `pivot` (e.g. `en`)
+pub fn configure_pivot(pivot: &str) {
+    let _ = PIVOT.set(pivot.to_string());
+}
+
+/// Translate high-priority units with an ensemble of the named translators
+pub fn configure_ensemble(names: &[String]) -> Result<()> {
+    let names: Vec<String> = names
+        .iter()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect();
+    match names.len() {
+        0 => Ok(()),
+        1 => anyhow::bail!("--ensemble needs at least two translators"),
+        _ => {
+            let _ = ENSEMBLE.set(names);
+            Ok(())
+        }
+    }
+}
+
+/// Translator pins use for the configured ensemble
+const ENSEMBLE_PIN: &str = "ensemble";
+
+/// Build the configured ensemble, if any
+fn build_ensemble() -> Result<Option<Box<dyn Translator>>> {
+    let Some(names) = ENSEMBLE.get() else {
+        return Ok(None);
+    };
+    let members = names
+        .iter()
+        .map(|name| build_translator(name, &BTreeMap::new()))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Some(Box::new(EnsembleTranslator::new(members)?)))
+}
+
+/// Create the translator for a run, honouring per-language-pair routes
+///
+/// With no routes this is just `create_translator(name)`. Otherwise a
+/// `RoutingTranslator` is built from the routes, and `name` serves as the
+/// `*` route unless the routes define one themselves. A configured pivot
+/// language, then a configured glossary wrap the result, and the
+/// translation memory wraps them all.
+pub fn build_translator(
+    name: &str,
+    routes: &BTreeMap<String, String>,
+) -> Result<Box<dyn Translator>> {
+    let mut translator = build_routed_translator(name, routes)?;
+    if let Some(pivot) = PIVOT.get() {
+        translator = Box::new(PivotTranslator::new(translator, pivot));
+    }
+    if let Some(glossary) = GLOSSARY.get() {
+        translator = Box::new(GlossaryTranslator::new(translator, glossary.clone()));
+    }
+    Ok(cached(translator))
+}
+
+fn build_routed_translator(
+    name: &str,
+    routes: &BTreeMap<String, String>,
+) -> Result<Box<dyn Translator>> {
+    if routes.is_empty() {
+        return create_translator(name);
+    }
+
+    let mut router = RoutingTranslator::new();
+    for (pattern, route_name) in routes {
+        router = router.with_route(pattern, create_translator(route_name)?)?;
+    }
+    if !routes.contains_key("*") {
+        router = router.with_route("*", create_translator(name)?)?;
+    }
+
+    Ok(Box::new(router))
+}
+
+/// Write the line number source map of a translated file next to it
+pub fn write_source_map(
+    path: &Path,
+    original: &str,
+    translated: &str,
+    verbose: bool,
+) -> Result<()> {
+    let map_path = SourceMap::path_for(path);
+    SourceMap::from_contents(original, translated).save(&map_path)?;
+    if verbose {
+        println!(
+            "  {} Source map written: {}",
+            "✓".green(),
+            map_path.display()
+        );
+    }
+    Ok(())
+}
+
+/// Per-file options shared by the translate and fix commands
+#[derive(Debug, Clone, Default)]
+pub struct FileOptions {
+    /// Run id to tag sidecar mapping files with; `None` disables them
+    pub map_run: Option<u64>,
+    /// Write a line number source map next to each translated file
+    pub source_map: bool,
+    /// Translate the bodies of TODO/FIXME comments
+    pub include_todos: bool,
+    /// Translate JSX text and JS/TS string literals passed to user-facing sinks
+    pub include_strings: bool,
+    /// Translate license/copyright headers
+    pub include_license_headers: bool,
+    /// Translate files marked as generated
+    pub include_generated: bool,
+    /// Kinds of notebook cells to translate
+    pub notebook_cells: NotebookCells,
+    /// Only translate units last modified in this time window
+    pub history: HistoryFilter,
+    /// Encoding for written files; `None` keeps each file's own encoding
+    pub output_encoding: Option<FileEncoding>,
+    /// `--force` patterns: files written even if verification flags them
+    pub force: Vec<String>,
+}
+
+impl FileOptions {
+    /// Drop units this run should leave untouched
+    pub fn select_units(&self, parse_result: &mut ParseResult) {
+        if !self.include_generated && generated::is_generated(parse_result) {
+            parse_result.units.clear();
+            return;
+        }
+        if !self.include_todos {
+            parse_result.units.retain(|unit| !todo::is_todo(unit));
+        }
+        if !self.include_strings {
+            parse_result
+                .units
+                .retain(|unit| !sinks::is_sink_string(unit) && !jsx::is_jsx_text(unit));
+        }
+        if !self.include_license_headers {
+            parse_result
+                .units
+                .retain(|unit| !license::is_license_header(unit));
+        }
+        if parse_result.file_type == "jupyter_notebook" {
+            parse_result
+                .units
+                .retain(|unit| self.notebook_cells.keeps(unit));
+        }
+    }
+
+    /// Check if a file matches a `--force` pattern
+    ///
+    /// Patterns with `*`, `?` or `[` are globs; anything else must match the
+    /// path or a trailing part of it.
+    pub fn is_forced(&self, path: &str) -> bool {
+        let path = path.replace('\\', "/");
+        self.force.iter().any(|pattern| {
+            if pattern.contains(['*', '?', '[']) {
+                globset::Glob::new(pattern).is_ok_and(|glob| glob.compile_matcher().is_match(&path))
+            } else {
+                let pattern = pattern.trim_start_matches("./");
+                path == pattern || path.ends_with(&format!("/{}", pattern))
+            }
+        })
+    }
+}
+
+/// Kinds of notebook cells a run translates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotebookCells {
+    /// Narrative in markdown cells
+    pub markdown: bool,
+    /// Comments in code cells
+    pub code: bool,
+}
+
+impl Default for NotebookCells {
+    fn default() -> Self {
+        Self {
+            markdown: true,
+            code: true,
+        }
+    }
+}
+
+impl NotebookCells {
+    /// Parse `--notebook-cells` kinds; none selects every kind
+    pub fn parse(kinds: &[String]) -> Result<Self> {
+        if kinds.is_empty() {
+            return Ok(Self::default());
+        }
+        let mut cells = Self {
+            markdown: false,
+            code: false,
+        };
+        for kind in kinds {
+            match kind.trim().to_lowercase().as_str() {
+                "markdown" => cells.markdown = true,
+                "code" => cells.code = true,
+                other => anyhow::bail!(
+                    "Unknown notebook cell kind '{}' (expected markdown or code)",
+                    other
+                ),
+            }
+        }
+        Ok(cells)
+    }
+
+    /// Check if a notebook unit comes from a selected kind of cell
+    fn keeps(&self, unit: &TranslatableUnit) -> bool {
+        match unit.unit_type {
+            UnitType::TextNode => self.markdown,
+            _ => self.code,
+        }
+    }
+}
+
+/// Selection of units by the `git blame` history of their lines
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistoryFilter {
+    /// Keep units last modified before this Unix time
+    pub modified_before: Option<i64>,
+    /// Keep units last modified at or after this Unix time
+    pub modified_after: Option<i64>,
+    /// Keep units with a line last written by a matching author
+    /// (see [`blame::author_matches`])
+    pub authors: Vec<String>,
+}
+
+impl HistoryFilter {
+    /// Build a filter from `--modified-before`/`--modified-after` dates
+    pub fn from_dates(before: Option<&str>, after: Option<&str>) -> Result<Self> {
+        Ok(Self {
+            modified_before: before.map(blame::parse_date).transpose()?,
+            modified_after: after.map(blame::parse_date).transpose()?,
+            authors: Vec::new(),
+        })
+    }
+
+    /// Also require a line by one of these `--author` patterns
+    pub fn with_authors(mut self, authors: Vec<String>) -> Self {
+        self.authors = authors;
+        self
+    }
+
+    /// Check if the filter needs blame information
+    pub fn is_active(&self) -> bool {
+        self.modified_before.is_some() || self.modified_after.is_some() || !self.authors.is_empty()
+    }
+
+    fn keeps(&self, time: i64) -> bool {
+        self.modified_before.is_none_or(|before| time < before)
+            && self.modified_after.is_none_or(|after| time >= after)
+    }
+
+    /// Drop units outside the time window or not written by the authors
+    ///
+    /// A unit's age is the most recent modification of any of its lines.
+    pub fn select_units(&self, path: &Path, parse_result: &mut ParseResult) -> Result<()> {
+        if !self.is_active() || parse_result.units.is_empty() {
+            return Ok(());
+        }
+
+        let lines = blame::blame_file(path)?;
+        let now = run_timestamp() as i64;
+        parse_result.units.retain(|unit| {
+            let first = unit.line_number.max(1) as usize;
+            let last = (paragraph::end_line(unit) as usize).max(first);
+            let history: Vec<_> = (first..=last)
+                .filter_map(|line| lines.get(line - 1))
+                .collect();
+
+            // Lines without blame (untracked) are new and have no author
+            let modified = if history.len() == last - first + 1 {
+                history.iter().map(|blame| blame.time).max().unwrap_or(now)
+            } else {
+                now
+            };
+            let by_author = self.authors.is_empty()
+                || history.iter().any(|blame| {
+                    self.authors
+                        .iter()
+                        .any(|pattern| blame::author_matches(blame, pattern))
+                });
+            self.keeps(modified) && by_author
+        });
+        Ok(())
+    }
+}
+
+/// Delimiters whose count must not change when translations are spliced in
+const DELIMITERS: [&str; 6] = ["\"\"\"", "'''", "/*", "*/", "<!--", "-->"];
+
+/// Check a reconstructed file for damage done by the translations
+///
+/// Returns the reasons the file is unsafe to write, empty if it looks
+/// fine. Flags single-line comments whose translation spans several lines
+/// (the extra lines would become code), string literals whose format
+/// placeholders bind different arguments, docstring and block comment
+/// delimiters that appeared or disappeared, JSON files (notebooks) that no
+/// longer parse, and unless [`UnsafeCharacters::Allow`]ed, control, bidi
+/// and invisible characters the file did not have.
+pub fn verify_reconstruction(
+    original: &str,
+    reconstructed: &str,
+    original_units: &[TranslatableUnit],
+    translated_units: &[TranslatableUnit],
+) -> Vec<String> {
+    let mut reasons = Vec::new();
+
+    for (before, after) in original_units.iter().zip(translated_units) {
+        // Paragraphs are re-wrapped, so line breaks in them are harmless
+        if after.unit_type == UnitType::Comment
+            && paragraph::paragraph_lines(before) == 1
+            && !before.content.contains('\n')
+            && after.content.trim_end().contains('\n')
+        {
+            reasons.push(format!(
+                "line {}: translated comment spans several lines",
+                before.line_number
+            ));
+        }
+        if after.unit_type == UnitType::StringLiteral
+            && format_string::bindings(&before.content) != format_string::bindings(&after.content)
+        {
+            reasons.push(format!(
+                "line {}: translated string binds format arguments differently",
+                before.line_number
+            ));
+        }
+    }
+
+    for delimiter in DELIMITERS {
+        let expected = original.matches(delimiter).count();
+        let found = reconstructed.matches(delimiter).count();
+        if expected != found {
+            reasons.push(format!(
+                "`{}` appears {} times, expected {}",
+                delimiter, found, expected
+            ));
+        }
+    }
+
+    if serde_json::from_str::<serde_json::Value>(original).is_ok() {
+        if let Err(e) = serde_json::from_str::<serde_json::Value>(reconstructed) {
+            reasons.push(format!("no longer valid JSON: {}", e));
+        }
+    }
+
+    if unsafe_characters() != UnsafeCharacters::Allow {
+        reasons.extend(unsafe_character_reasons(original, reconstructed));
+    }
+
+    reasons
+}
+
+/// Control, bidi and invisible characters `reconstructed` has more of than
+/// `original`, reported at the first line holding one
+fn unsafe_character_reasons(original: &str, reconstructed: &str) -> Vec<String> {
+    let introduced = invisible::introduced(original, reconstructed);
+    if introduced.is_empty() {
+        return Vec::new();
+    }
+    let line = reconstructed
+        .lines()
+        .position(|line| line.contains(introduced.as_slice()))
+        .map_or(0, |line| line + 1);
+    let characters: Vec<String> = introduced.into_iter().map(invisible::code_point).collect();
+    vec![format!(
+        "line {}: translation introduces control or invisible characters ({})",
+        line,
+        characters.join(", ")
+    )]
+}
+
+/// Error for a file whose translation failed verification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quarantined {
+    pub reasons: Vec<String>,
+}
+
+impl std::fmt::Display for Quarantined {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unsafe to modify: {}", self.reasons.join("; "))
+    }
+}
+
+impl std::error::Error for Quarantined {}
+
+/// Files left untouched because verification flagged them
+#[derive(Debug, Default)]
+pub struct QuarantineReport {
+    files: Vec<(String, Vec<String>)>,
+}
+
+impl QuarantineReport {
+    /// Record a file if the error is a [`Quarantined`] one
+    ///
+    /// Returns `false` for any other error.
+    pub fn record(&mut self, path: &Path, error: &anyhow::Error) -> bool {
+        match error.downcast_ref::<Quarantined>() {
+            Some(quarantined) => {
+                self.files
+                    .push((path.display().to_string(), quarantined.reasons.clone()));
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Print the quarantined files with their reasons
+    pub fn print(&self) {
+        if self.is_empty() {
+            return;
+        }
+        println!(
+            "\n{} {}",
+            tr!("quarantined-title").bold().yellow(),
+            tr!("quarantined-unchanged", count = self.len())
+        );
+        for (path, reasons) in &self.files {
+            println!("  {}", path);
+            for reason in reasons {
+                println!("    - {}", reason);
+            }
+        }
+    }
+}
+
+/// Files that could not be processed, collected instead of aborting the run
+///
+/// In strict mode (`--strict`) the first error is returned instead. Files
+/// skipped by the config's `file_limits` are kept apart: they are not
+/// errors, even in strict mode.
+#[derive(Debug, Default)]
+pub struct FileErrors {
+    strict: bool,
+    files: Vec<FileError>,
+    /// Files over a limit, with the reason
+    limited: Vec<(String, String)>,
+}
+
+/// A file that could not be processed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileError {
+    pub path: String,
+    /// Category such as `permission denied` or `parse failure`
+    pub kind: &'static str,
+    pub message: String,
+}
+
+impl FileErrors {
+    pub fn new(strict: bool) -> Self {
+        Self {
+            strict,
+            ..Self::default()
+        }
+    }
+
+    /// Record the error of a file, or return it in strict mode
+    pub fn record(&mut self, path: &Path, error: anyhow::Error) -> Result<()> {
+        if let Some(over) = error.downcast_ref::<OverLimit>() {
+            self.limited
+                .push((path.display().to_string(), over.reason.clone()));
+            return Ok(());
+        }
+        if self.strict {
+            return Err(error.context(format!("Failed to process {}", path.display())));
+        }
+        self.files.push(FileError {
+            path: path.display().to_string(),
+            kind: error_kind(&error),
+            message: format!("{:#}", error),
+        });
+        Ok(())
+    }
+
+    /// Record an entry a directory walk could not read
+    pub fn record_walk(&mut self, error: walkdir::Error) -> Result<()> {
+        let path = error.path().map(Path::to_path_buf).unwrap_or_default();
+        self.record(&path, error.into())
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    pub fn files(&self) -> &[FileError] {
+        &self.files
+    }
+
+    /// Files skipped by a limit, with the reason
+    pub fn limited(&self) -> &[(String, String)] {
+        &self.limited
+    }
+
+    /// Print the files skipped by limits, then the failed files with their
+    /// errors (to stderr)
+    pub fn print(&self) {
+        if !self.limited.is_empty() {
+            println!("\n{}", tr!("limited-title").bold().yellow());
+            for (path, reason) in &self.limited {
+                println!("  {}: {}", path, reason);
+            }
+        }
+        if self.is_empty() {
+            return;
+        }
+        eprintln!(
+            "\n{} {}",
+            tr!("errors-title").bold().red(),
+            tr!("errors-skipped", count = self.len())
+        );
+        for error in &self.files {
+            eprintln!("  {} [{}]", error.path, error.kind);
+            eprintln!("    {}", error.message);
+        }
+    }
+}
+
+/// Short category of a per-file error for the error report
+pub fn error_kind(error: &anyhow::Error) -> &'static str {
+    for cause in error.chain() {
+        let io = cause.downcast_ref::<std::io::Error>().or_else(|| {
+            cause
+                .downcast_ref::<walkdir::Error>()
+                .and_then(walkdir::Error::io_error)
+        });
+        if let Some(io) = io {
+            return match io.kind() {
+                std::io::ErrorKind::PermissionDenied => "permission denied",
+                std::io::ErrorKind::NotFound => "not found",
+                std::io::ErrorKind::InvalidData => "bad encoding",
+                _ => "I/O error",
+            };
+        }
+        let message = cause.to_string();
+        if message.starts_with("Failed to parse") {
+            return "parse failure";
+        }
+        if message.contains("encoding") || message.starts_with("Failed to encode") {
+            return "bad encoding";
+        }
+        if cause.is::<Quarantined>() {
+            return "quarantined";
+        }
+    }
+    "error"
+}
+
+/// Resolve an `--output-encoding` label
+pub fn output_encoding(label: Option<&str>) -> Result<Option<FileEncoding>> {
+    label.map(FileEncoding::for_label).transpose()
+}
+
+/// Identifier for a translation run: its start time in Unix seconds
+pub fn run_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Aggregate translation statistics for a file or a whole run
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RunStats {
+    /// Number of translated units
+    pub units: usize,
+    /// Units whose translation failed (original text kept)
+    pub failed: usize,
+    /// Units skipped as already in the target language
+    pub skipped: usize,
+    /// Sum of per-unit confidence scores
+    pub confidence_sum: f64,
+    /// Bytes of original text replaced by translations
+    pub bytes_changed: usize,
+    /// Units kept in the original for being below the minimum confidence
+    pub review: Vec<ReviewItem>,
+}
+
+/// A unit left untranslated for manual review
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReviewItem {
+    /// File of the unit; empty until set with [`RunStats::in_file`]
+    pub file: String,
+    pub line: u32,
+    pub original: String,
+    /// The rejected translation
+    pub translation: String,
+    pub confidence: f64,
+}
+
+impl RunStats {
+    /// Record one unit from the results of its segments
+    ///
+    /// A unit split into several segments fails if any segment failed and
+    /// scores the lowest confidence among them. Skipped units are only
+    /// counted as skipped.
+    fn record(&mut self, results: &[TranslationResult]) {
+        if !results.is_empty()
+            && results
+                .iter()
+                .all(|r| r.status == TranslationStatus::Skipped)
+        {
+            self.skipped += 1;
+            return;
+        }
+        self.units += 1;
+        if results.is_empty()
+            || results
+                .iter()
+                .any(|r| r.status == TranslationStatus::Failed)
+        {
+            self.failed += 1;
+        }
+        self.confidence_sum += results
+            .iter()
+            .map(|r| r.confidence)
+            .reduce(f64::min)
+            .unwrap_or(0.0);
+    }
+
+    /// Add another set of statistics to this one
+    pub fn merge(&mut self, other: &RunStats) {
+        self.units += other.units;
+        self.failed += other.failed;
+        self.skipped += other.skipped;
+        self.confidence_sum += other.confidence_sum;
+        self.bytes_changed += other.bytes_changed;
+        self.review.extend(other.review.iter().cloned());
+    }
+
+    /// Attribute units kept for review to a file
+    pub fn in_file(mut self, file: &str) -> Self {
+        for item in self.review.iter_mut().filter(|item| item.file.is_empty()) {
+            item.file = file.to_string();
+        }
+        self
+    }
+
+    /// Print the units kept for review, for run summaries
+    pub fn print_review(&self) {
+        if self.review.is_empty() {
+            return;
+        }
+        println!(
+            "\n{} Kept in the original for review (confidence below {:.2}):",
+            "⚠".yellow(),
+            confidence_gate().map_or(0.0, |gate| gate.min_confidence)
+        );
+        for item in &self.review {
+            let original: String = item.original.chars().take(60).collect();
+            println!(
+                "  {}:{} ({:.2}) {}",
+                item.file,
+                item.line,
+                item.confidence,
+                original.replace('\n', " ")
+            );
+        }
+    }
+
+    /// Average confidence over all units (0.0 when nothing was translated)
+    pub fn average_confidence(&self) -> f64 {
+        if self.units == 0 {
+            0.0
+        } else {
+            self.confidence_sum / self.units as f64
+        }
+    }
+
+    /// Fraction of units that failed
+    pub fn failure_rate(&self) -> f64 {
+        if self.units == 0 {
+            0.0
+        } else {
+            self.failed as f64 / self.units as f64
+        }
+    }
+}
+
+/// Parse a confidence score between 0.0 and 1.0
+pub fn parse_confidence(text: &str) -> Result<f64> {
+    text.trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|score| (0.0..=1.0).contains(score))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Invalid confidence '{}' (expected a score from 0.0 to 1.0, e.g. 0.8)",
+                text
+            )
+        })
+}
+
+/// Run-level thresholds that fail the command when not met
+#[derive(Debug, Default, Clone, Copy)]
+pub struct QualityGate {
+    pub min_avg_confidence: Option<f64>,
+    pub max_failed_units: Option<usize>,
+}
+
+impl QualityGate {
+    /// Check run statistics against the thresholds
+    pub fn check(&self, stats: &RunStats) -> Result<()> {
+        if let Some(min) = self.min_avg_confidence {
+            if stats.units > 0 && stats.average_confidence() < min {
+                anyhow::bail!(
+                    "Quality gate failed: average confidence {:.2} is below --min-avg-confidence {:.2}",
+                    stats.average_confidence(),
+                    min
+                );
+            }
+        }
+        if let Some(max) = self.max_failed_units {
+            if stats.failed > max {
+                anyhow::bail!(
+                    "Quality gate failed: {} failed units exceed --max-failed-units {}",
+                    stats.failed,
+                    max
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Per-unit confidence threshold, with a translator to retry units below it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfidenceGate {
+    pub min_confidence: f64,
+    /// Translator low-confidence units are translated again with
+    pub secondary: Option<String>,
+}
+
+static CONFIDENCE_GATE: OnceLock<ConfidenceGate> = OnceLock::new();
+
+/// Keep units translated below `min_confidence` in the original, after
+/// retrying them with `secondary`
+pub fn configure_confidence(min_confidence: Option<f64>, secondary: Option<String>) -> Result<()> {
+    let Some(min_confidence) = min_confidence else {
+        if secondary.is_some() {
+            anyhow::bail!("--secondary-translator needs --min-confidence");
+        }
+        return Ok(());
+    };
+    if !(0.0..=1.0).contains(&min_confidence) {
+        anyhow::bail!(
+            "--min-confidence must be between 0.0 and 1.0, got {}",
+            min_confidence
+        );
+    }
+    let _ = CONFIDENCE_GATE.set(ConfidenceGate {
+        min_confidence,
+        secondary,
+    });
+    Ok(())
+}
+
+fn confidence_gate() -> Option<&'static ConfidenceGate> {
+    CONFIDENCE_GATE.get()
+}
+
+/// Lowest confidence among the results of a unit's segments
+fn lowest_confidence(results: &[TranslationResult]) -> f64 {
+    results
+        .iter()
+        .map(|r| r.confidence)
+        .reduce(f64::min)
+        .unwrap_or(0.0)
+}
+
+impl ConfidenceGate {
+    /// Check if a unit was translated, but below the minimum confidence
+    ///
+    /// Failed units already keep their original text.
+    fn is_low(&self, results: &[TranslationResult]) -> bool {
+        !results.is_empty()
+            && results.iter().all(|r| {
+                matches!(
+                    r.status,
+                    TranslationStatus::Success | TranslationStatus::Partial
+                )
+            })
+            && lowest_confidence(results) < self.min_confidence
+    }
+
+    /// Retry low-confidence units with the secondary translator, then keep
+    /// those still below the threshold in the original and mark them partial
+    async fn review(
+        &self,
+        units: &[TranslatableUnit],
+        path: Option<&str>,
+        batches: &mut [TranslatedBatch],
+    ) -> Result<Vec<ReviewItem>> {
+        let mut secondary: Option<Box<dyn Translator>> = None;
+        let mut review = Vec::new();
+
+        for (source, target, indices, translated) in batches.iter_mut() {
+            let low: Vec<usize> = (0..translated.len())
+                .filter(|&k| self.is_low(&translated[k].1))
+                .collect();
+            if low.is_empty() {
+                continue;
+            }
+
+            if let Some(name) = &self.secondary {
+                if secondary.is_none() {
+                    secondary = Some(build_translator(name, &BTreeMap::new())?);
+                }
+                let retry_units: Vec<&TranslatableUnit> =
+                    low.iter().map(|&k| &units[indices[k]]).collect();
+                let retried = translate_group(
+                    &retry_units,
+                    path,
+                    source,
+                    target,
+                    secondary.as_deref().unwrap(),
+                )
+                .await?;
+                for (&k, retry) in low.iter().zip(retried) {
+                    let better = !retry.1.is_empty()
+                        && retry
+                            .1
+                            .iter()
+                            .all(|r| r.status == TranslationStatus::Success)
+                        && lowest_confidence(&retry.1) > lowest_confidence(&translated[k].1);
+                    if better {
+                        translated[k] = retry;
+                    }
+                }
+            }
+
+            for k in low {
+                let (text, results) = &mut translated[k];
+                if !self.is_low(results) {
+                    continue;
+                }
+                let unit = &units[indices[k]];
+                review.push(ReviewItem {
+                    file: String::new(),
+                    line: unit.line_number,
+                    original: unit.content.clone(),
+                    translation: std::mem::replace(text, unit.content.clone()),
+                    confidence: lowest_confidence(results),
+                });
+                for result in results.iter_mut() {
+                    result.status = TranslationStatus::Partial;
+                }
+            }
+        }
+        Ok(review)
+    }
+}
+
+/// Source language, target language and pinned translator of a batch
+type BatchKey = (String, String, Option<String>);
+
+/// Source and target language of a translated batch, its unit indices and
+/// their translations
+type TranslatedBatch = (String, String, Vec<usize>, Vec<TranslatedUnit>);
+
+/// Translated content of one unit with the results of its segments
+type TranslatedUnit = (String, Vec<TranslationResult>);
+
+/// Units of a file with translated content, plus translation statistics
+pub struct TranslatedUnits {
+    pub units: Vec<TranslatableUnit>,
+    pub stats: RunStats,
+}
+
+/// Check if a source language asks for per-unit detection
+pub fn is_auto_source(source: &str) -> bool {
+    source.eq_ignore_ascii_case("auto")
+}
+
+/// Check if two language codes name the same language (`zh-CN` and `zh`)
+pub fn same_language(a: &str, b: &str) -> bool {
+    let primary = |code: &str| code.split(['-', '_']).next().unwrap_or(code).to_lowercase();
+    primary(a) == primary(b)
+}
+
+static SKIP_TARGET_LANGUAGE: OnceLock<bool> = OnceLock::new();
+
+/// Choose whether units detected in the target language are skipped
+///
+/// On by default. With an `auto` source they are always skipped.
+pub fn configure_target_language_skip(enabled: bool) {
+    let _ = SKIP_TARGET_LANGUAGE.set(enabled);
+}
+
+fn skip_target_language() -> bool {
+    *SKIP_TARGET_LANGUAGE.get_or_init(|| true)
+}
+
+/// How a unit is translated
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum UnitSource {
+    /// Translate from this language
+    Language(String),
+    /// Already in the target language; skipped
+    Target,
+    /// No language to translate from; left alone
+    Unknown,
+}
+
+/// Source language of each unit
+///
+/// With an explicit `source`, units detected in the target language are
+/// skipped (unless configured otherwise) and everything else is translated
+/// from `source`. With `auto`, units use their detected language, and
+/// non-ASCII units too short to detect fall back to the file's most common
+/// language; units with no language to go on (such as short ASCII text,
+/// which is most likely English) are left alone.
+fn unit_sources(units: &[TranslatableUnit], source: &str, target: &str) -> Vec<UnitSource> {
+    let detected: Vec<Option<String>> = units
+        .iter()
+        .map(|unit| {
+            unit.detected_language
+                .clone()
+                .or_else(|| detect_language(&unit.content))
+        })
+        .collect();
+    let contents: Vec<&str> = units.iter().map(|unit| unit.content.as_str()).collect();
+    text_sources(&contents, detected, source, target)
+}
+
+/// Source language of each text given its detected language, as for
+/// [`unit_sources`]
+fn text_sources(
+    texts: &[&str],
+    detected: Vec<Option<String>>,
+    source: &str,
+    target: &str,
+) -> Vec<UnitSource> {
+    if !is_auto_source(source) {
+        let skip = skip_target_language() && !same_language(source, target);
+        return detected
+            .into_iter()
+            .map(|language| match language {
+                Some(language) if skip && same_language(&language, target) => UnitSource::Target,
+                _ => UnitSource::Language(source.to_string()),
+            })
+            .collect();
+    }
+
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for language in detected.iter().flatten() {
+        *counts.entry(language).or_default() += 1;
+    }
+    // Most common, ties broken by code for stable results
+    let dominant = counts
+        .into_iter()
+        .max_by(|(a, x), (b, y)| x.cmp(y).then_with(|| b.cmp(a)))
+        .map(|(language, _)| language.to_string());
+
+    detected
+        .iter()
+        .zip(texts)
+        .map(|(language, text)| {
+            match language
+                .clone()
+                .or_else(|| dominant.clone().filter(|_| !text.is_ascii()))
+            {
+                Some(language) if same_language(&language, target) => UnitSource::Target,
+                Some(language) => UnitSource::Language(language),
+                None => UnitSource::Unknown,
+            }
+        })
+        .collect()
+}
+
+/// Translate bare texts with one batch per source language
+///
+/// With an `auto` source, each text is sent with its own detected language
+/// rather than the whole batch with one code, which garbles texts in the
+/// minority language. Texts already in the target language, or with no
+/// language to go on, come back skipped.
+pub async fn translate_by_language(
+    texts: &[String],
+    source: &str,
+    target: &str,
+    translator: &dyn Translator,
+) -> Result<Vec<TranslationResult>> {
+    let contents: Vec<&str> = texts.iter().map(String::as_str).collect();
+    let detected = contents.iter().map(|text| detect_language(text)).collect();
+    let sources = text_sources(&contents, detected, source, target);
+
+    let mut batches: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    let mut results: Vec<Option<TranslationResult>> = vec![None; texts.len()];
+    for (i, text_source) in sources.iter().enumerate() {
+        let reason = match text_source {
+            UnitSource::Language(language) => {
+                batches.entry(language).or_default().push(i);
+                continue;
+            }
+            UnitSource::Target => "already in the target language",
+            UnitSource::Unknown => "source language not detected",
+        };
+        results[i] = Some(TranslationResult::skipped(
+            texts[i].clone(),
+            source.to_string(),
+            target.to_string(),
+            reason.to_string(),
+        ));
+    }
+
+    for (language, indices) in batches {
+        let batch: Vec<String> = indices.iter().map(|&i| texts[i].clone()).collect();
+        let translated = translator.translate_batch(&batch, language, target).await?;
+        for (i, result) in indices.into_iter().zip(translated) {
+            results[i] = Some(result);
+        }
+    }
+    // A short reply from the translator fails the remaining texts
+    Ok(results
+        .into_iter()
+        .zip(texts)
+        .map(|(result, text)| {
+            result.unwrap_or_else(|| {
+                TranslationResult::missing(text.clone(), source.to_string(), target.to_string())
+            })
+        })
+        .collect())
+}
+
+/// Source language of each unit for its own target language
+fn pinned_unit_sources(
+    units: &[TranslatableUnit],
+    source: &str,
+    targets: &[String],
+) -> Vec<UnitSource> {
+    let mut sources = vec![UnitSource::Unknown; units.len()];
+    let distinct: std::collections::BTreeSet<&String> = targets.iter().collect();
+    for target in distinct {
+        for (i, unit_source) in unit_sources(units, source, target).into_iter().enumerate() {
+            if targets[i] == *target {
+                sources[i] = unit_source;
+            }
+        }
+    }
+    sources
+}
+
+/// Translate all units of a parsed file, returning units with translated content
+///
+/// Notebook units are translated one batch per cell, with up to
+/// `CELL_CONCURRENCY` cells in flight, and reassembled in original order.
+/// Everything else is sent as a single batch. With an `auto` source, each
+/// batch is split by detected language. Units already in the target
+/// language are marked skipped without reaching the translator.
+pub async fn translate_units(
+    parse_result: &ParseResult,
+    source: &str,
+    target: &str,
+    translator: &dyn Translator,
+) -> Result<TranslatedUnits> {
+    translate_pinned_units(parse_result, None, &[], source, target, translator).await
+}
+
+/// Translate all units like [`translate_units`], honouring the `langlint:`
+/// directive of each unit
+///
+/// Units pinned to another translator or target language are batched
+/// separately; pinned translators are built once per file. The file's
+/// `path` is passed to the translator along with each unit.
+pub async fn translate_pinned_units(
+    parse_result: &ParseResult,
+    path: Option<&str>,
+    pins: &[Directive],
+    source: &str,
+    target: &str,
+    translator: &dyn Translator,
+) -> Result<TranslatedUnits> {
+    let groups = if parse_result.file_type == "jupyter_notebook" {
+        group_by_cell(&parse_result.units)
+    } else {
+        vec![(0..parse_result.units.len()).collect()]
+    };
+
+    let pin = |i: usize| pins.get(i).cloned().unwrap_or_default();
+    let targets: Vec<String> = (0..parse_result.units.len())
+        .map(|i| pin(i).target.unwrap_or_else(|| target.to_string()))
+        .collect();
+    let mode = bilingual_mode();
+    let (units, splits) = split_bilingual(&parse_result.units, &targets, mode);
+    let mut sources = pinned_unit_sources(&units, source, &targets);
+    if mode == BilingualMode::KeepTarget {
+        for (unit_source, split) in sources.iter_mut().zip(&splits) {
+            if split.is_some() {
+                *unit_source = UnitSource::Target;
+            }
+        }
+    }
+
+    let mut pinned_translators: BTreeMap<String, Box<dyn Translator>> = BTreeMap::new();
+    let ensemble = build_ensemble()?;
+    let use_ensemble = ensemble.is_some();
+    if let Some(ensemble) = ensemble {
+        pinned_translators.insert(ENSEMBLE_PIN.to_string(), ensemble);
+    }
+    // High-priority units go to the ensemble unless pinned elsewhere
+    let pin_translator = |i: usize| {
+        pin(i).translator.or_else(|| {
+            (use_ensemble && parse_result.units[i].priority == Priority::High)
+                .then(|| ENSEMBLE_PIN.to_string())
+        })
+    };
+    for name in pins.iter().filter_map(|pin| pin.translator.as_ref()) {
+        if !pinned_translators.contains_key(name) {
+            pinned_translators.insert(name.clone(), build_translator(name, &BTreeMap::new())?);
+        }
+    }
+
+    // Units of the configured types are transliterated, not translated
+    let transliterated: Vec<Option<String>> = units
+        .iter()
+        .zip(&sources)
+        .map(|(unit, unit_source)| match unit_source {
+            UnitSource::Language(_) => TRANSLITERATION.get()?.unit(unit),
+            _ => None,
+        })
+        .collect();
+
+    let groups: Vec<(BatchKey, Vec<usize>)> = groups
+        .into_iter()
+        .flat_map(|indices| {
+            let mut by_key: BTreeMap<BatchKey, Vec<usize>> = BTreeMap::new();
+            for i in indices {
+                if transliterated[i].is_some() {
+                    continue;
+                }
+                if let UnitSource::Language(language) = &sources[i] {
+                    let key = (language.clone(), targets[i].clone(), pin_translator(i));
+                    by_key.entry(key).or_default().push(i);
+                }
+            }
+            by_key
+        })
+        .collect();
+
+    let pinned_translators = &pinned_translators;
+    let units = &units;
+    let mut batches: Vec<TranslatedBatch> = stream::iter(groups)
+        .map(|((source, target, name), indices)| async move {
+            let units: Vec<&TranslatableUnit> = indices.iter().map(|&i| &units[i]).collect();
+            let translator = match &name {
+                Some(name) => pinned_translators[name].as_ref(),
+                None => translator,
+            };
+            let translated = translate_group(&units, path, &source, &target, translator).await?;
+            Ok::<_, anyhow::Error>((source, target, indices, translated))
+        })
+        .buffered(CELL_CONCURRENCY)
+        .try_collect()
+        .await?;
+
+    let review = match confidence_gate() {
+        Some(gate) => gate.review(units, path, &mut batches).await?,
+        None => Vec::new(),
+    };
+    for (i, text) in transliterated.into_iter().enumerate() {
+        let (Some(text), UnitSource::Language(language)) = (text, &sources[i]) else {
+            continue;
+        };
+        let result = TranslationResult::success(
+            units[i].content.clone(),
+            text.clone(),
+            language.clone(),
+            targets[i].clone(),
+            1.0,
+        );
+        batches.push((
+            language.clone(),
+            targets[i].clone(),
+            vec![i],
+            vec![(text, vec![result])],
+        ));
+    }
+
+    let mut translated_units = units.clone();
+    let mut stats = RunStats {
+        review,
+        ..RunStats::default()
+    };
+    for ((unit, unit_source), target) in units.iter().zip(&sources).zip(&targets) {
+        if *unit_source == UnitSource::Target {
+            stats.record(&[TranslationResult::skipped(
+                unit.content.clone(),
+                source.to_string(),
+                target.clone(),
+                "already in the target language".to_string(),
+            )]);
+        }
+    }
+    for (_, _, indices, translated) in batches {
+        for (i, (text, results)) in indices.into_iter().zip(translated) {
+            translated_units[i].content = match &splits[i] {
+                Some(split) => split.replace_other(&targets[i], &text).unwrap_or(text),
+                None => text,
+            };
+            if translated_units[i].content != units[i].content {
+                stats.bytes_changed += units[i].content.len();
+            }
+            stats.record(&results);
+        }
+    }
+
+    Ok(TranslatedUnits {
+        units: translated_units,
+        stats,
+    })
+}
+
+/// Split the bilingual comments of `units` as `mode` asks
+///
+/// Returns the units to translate, each split comment holding only the
+/// half it keeps or translates, and the splits to rejoin them with. Only
+/// comments with a half in their target language are split.
+fn split_bilingual(
+    units: &[TranslatableUnit],
+    targets: &[String],
+    mode: BilingualMode,
+) -> (Vec<TranslatableUnit>, Vec<Option<Bilingual>>) {
+    let mut units = units.to_vec();
+    let mut splits = vec![None; units.len()];
+    if mode == BilingualMode::Translate {
+        return (units, splits);
+    }
+
+    for ((unit, split), target) in units.iter_mut().zip(&mut splits).zip(targets) {
+        if unit.unit_type != UnitType::Comment {
+            continue;
+        }
+        let Some(bilingual) = Bilingual::split(&unit.content) else {
+            continue;
+        };
+        let Some((kept, foreign, _)) = bilingual.halves_for(target) else {
+            continue;
+        };
+        let (kept, foreign) = (kept.trim().to_string(), foreign.trim().to_string());
+        if mode == BilingualMode::KeepTarget {
+            unit.content = kept;
+            unit.detected_language = Some(target.clone());
+        } else {
+            unit.detected_language = bilingual.other_language(target).map(str::to_string);
+            unit.content = foreign;
+        }
+        *split = Some(bilingual);
+    }
+    (units, splits)
+}
+
+/// Translate a group of units in one batch, returning each unit's translated
+/// content together with the results of its segments
+///
+/// Docstrings and text nodes containing lists or tables are split into
+/// blocks so bullets, numbering and table pipes survive translation, as
+/// are the segments of multi-line Python docstrings, one per line;
+/// comments drawing diagrams or box tables are translated label by label.
+/// Each segment is then split into sentences or paragraphs if the
+/// configured [`SegmentationRules`] ask for it. Format placeholders, and
+/// the math, images and HTML of text nodes, are masked while translating;
+/// a segment whose translation loses one keeps its original text. Bare
+/// placeholders a string literal's translation moves are numbered so they
+/// still bind their arguments.
+///
+/// The translator receives each segment with its unit's type, priority and
+/// context and the file's `path`.
+async fn translate_group(
+    units: &[&TranslatableUnit],
+    path: Option<&str>,
+    source: &str,
+    target: &str,
+    translator: &dyn Translator,
+) -> Result<Vec<TranslatedUnit>> {
+    let mut texts = Vec::new();
+    let mut masked = Vec::new();
+    let mut numberings = Vec::new();
+    let mut requests = Vec::new();
+    let mut layouts = Vec::with_capacity(units.len());
+
+    for unit in units {
+        let blocks = match unit.unit_type {
+            UnitType::Docstring | UnitType::TextNode => Some(MarkdownBlocks::parse(&unit.content)),
+            UnitType::Comment => Some(MarkdownBlocks::parse_diagram(&unit.content)),
+            _ => None,
+        }
+        .filter(|blocks| blocks.is_structured() || python::is_segmented_docstring(unit));
+
+        let segmentation = segmentation().for_unit(unit.unit_type);
+        let segments: Vec<Segmented> = match &blocks {
+            Some(blocks) => blocks.texts(),
+            None => vec![unit.content.clone()],
+        }
+        .iter()
+        .map(|text| segmentation.split(text))
+        .collect();
+        let pieces: Vec<String> = segments
+            .iter()
+            .flat_map(|segments| segments.texts().iter().cloned())
+            .collect();
+        let protection = match unit.unit_type {
+            UnitType::TextNode => markdown_protection(),
+            _ => protection(),
+        };
+        // Arguments are numbered across the whole string, so a split one
+        // is left to verification
+        let numbering = (unit.unit_type == UnitType::StringLiteral && pieces.len() == 1)
+            .then(|| Numbering::for_path(path.unwrap_or_default()));
+        for piece in &pieces {
+            let mut piece = Masked::with_protection(piece, protection);
+            if let Some(transliteration) = TRANSLITERATION.get() {
+                transliteration.mask(&mut piece);
+            }
+            requests.push(TranslationRequest::for_unit(&piece.text, unit).with_path(path));
+            masked.push(piece);
+            numberings.push(numbering);
+        }
+        layouts.push((texts.len()..texts.len() + pieces.len(), blocks, segments));
+        texts.extend(pieces);
+    }
+
+    let results: Vec<TranslationResult> = translator
+        .translate_requests(&requests, source, target)
+        .await?
+        .into_iter()
+        .zip(texts.iter().zip(&masked).zip(numberings))
+        .map(|(result, ((text, masked), numbering))| {
+            let mut result = unmask(result, text, masked, numbering);
+            if unsafe_characters() == UnsafeCharacters::Strip {
+                result.translated_text = invisible::strip_introduced(text, &result.translated_text);
+            }
+            result
+        })
+        .collect();
+
+    Ok(layouts
+        .into_iter()
+        .zip(units)
+        .map(|((range, blocks, segmented), unit)| {
+            let segment_results = results.get(range).unwrap_or_default();
+            let mut translated = segment_results.iter().map(|r| r.translated_text.clone());
+            let segments: Vec<String> = segmented
+                .iter()
+                .map(|segments| {
+                    let pieces: Vec<String> =
+                        translated.by_ref().take(segments.texts().len()).collect();
+                    segments.rebuild(&pieces)
+                })
+                .collect();
+            let content = match blocks {
+                Some(blocks) => blocks.rebuild(&segments),
+                None => segments
+                    .first()
+                    .cloned()
+                    .unwrap_or_else(|| unit.content.clone()),
+            };
+            (content, segment_results.to_vec())
+        })
+        .collect())
+}
+
+/// Restore the placeholders of a result translated from masked text
+///
+/// With a `numbering`, the text is a format string whose moved bare
+/// placeholders are numbered; if they cannot be, the result fails.
+fn unmask(
+    result: TranslationResult,
+    original: &str,
+    masked: &Masked,
+    numbering: Option<Numbering>,
+) -> TranslationResult {
+    if !masked.has_placeholders() {
+        return result;
+    }
+    let rebound;
+    let masked = match numbering.map(|n| format_string::rebind(masked, &result.translated_text, n))
+    {
+        Some(Ok(placeholders)) => {
+            rebound = Masked {
+                text: masked.text.clone(),
+                placeholders,
+            };
+            &rebound
+        }
+        Some(Err(moved)) => {
+            return TranslationResult::failed(
+                original.to_string(),
+                result.source_language,
+                result.target_language,
+                format!(
+                    "translation moved format placeholders that cannot be numbered: {}",
+                    moved.join(", ")
+                ),
+            )
+        }
+        None => masked,
+    };
+    match masked.restore(&result.translated_text) {
+        Ok(translated_text) => TranslationResult {
+            original_text: original.to_string(),
+            translated_text,
+            ..result
+        },
+        Err(e) => TranslationResult::failed(
+            original.to_string(),
+            result.source_language,
+            result.target_language,
+            e.to_string(),
+        ),
+    }
+}
+
+/// Group unit indices by their notebook cell, in cell order
+fn group_by_cell(units: &[TranslatableUnit]) -> Vec<Vec<usize>> {
+    let mut cells: BTreeMap<u64, Vec<usize>> = BTreeMap::new();
+    for (i, unit) in units.iter().enumerate() {
+        let cell = unit
+            .metadata
+            .as_ref()
+            .and_then(|m| m.get("cell_index"))
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0);
+        cells.entry(cell).or_default().push(i);
+    }
+    cells.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use langlint_translators::mock::{MockConfig, MockFault, MockTranslator};
+
+    fn cell_unit(content: &str, cell: usize) -> TranslatableUnit {
+        TranslatableUnit::new(content.to_string(), UnitType::TextNode, cell as u32, 0)
+            .with_metadata(serde_json::json!({ "cell_index": cell }))
+    }
+
+    #[test]
+    fn test_build_translator_with_routes() {
+        let routes: BTreeMap<String, String> = [("zh->en".to_string(), "mock".to_string())]
+            .into_iter()
+            .collect();
+
+        let translator = build_translator("mock", &routes).unwrap();
+        assert_eq!(translator.name(), "Router");
+        assert!(translator.validate_languages("ja", "en").is_ok()); // '*' fallback
+
+        assert_eq!(
+            build_translator("mock", &BTreeMap::new()).unwrap().name(),
+            "Mock"
+        );
+        assert!(build_translator("nope", &BTreeMap::new()).is_err());
+        assert_eq!(
+            build_translator("mock, mock", &BTreeMap::new())
+                .unwrap()
+                .name(),
+            "Fallback"
+        );
+        assert!(build_translator("mock,nope", &BTreeMap::new()).is_err());
+
+        let bad_routes: BTreeMap<String, String> = [("zh->en".to_string(), "nope".to_string())]
+            .into_iter()
+            .collect();
+        assert!(build_translator("mock", &bad_routes).is_err());
+    }
+
+    #[test]
+    fn test_group_by_cell() {
+        let units = vec![cell_unit("a", 2), cell_unit("b", 0), cell_unit("c", 2)];
+        assert_eq!(group_by_cell(&units), vec![vec![1], vec![0, 2]]);
+    }
+
+    #[tokio::test]
+    async fn test_translate_structured_docstring_by_block() {
+        let translator = MockTranslator::with_config(MockConfig {
+            delay_range: (0, 0),
+            ..MockConfig::default()
+        });
+        let units = vec![
+            TranslatableUnit::new("注释".to_string(), UnitType::Comment, 1, 1),
+            TranslatableUnit::new("参数：\n- 甲\n- 乙".to_string(), UnitType::Docstring, 2, 1),
+        ];
+        let result = ParseResult::new("python", "utf-8", 5).with_units(units);
+
+        let translated = translate_units(&result, "zh", "en", &translator)
+            .await
+            .unwrap()
+            .units;
+
+        assert_eq!(translated[0].content, "[EN] 注释");
+        assert_eq!(translated[1].content, "[EN] 参数：\n- [EN] 甲\n- [EN] 乙");
+    }
+
+    #[tokio::test]
+    async fn test_translate_notebook_units_in_order() {
+        let translator = MockTranslator::with_config(MockConfig {
+            delay_range: (0, 20),
+            ..MockConfig::default()
+        });
+        let units: Vec<_> = (0..10)
+            .map(|i| cell_unit(&format!("cell {}", i), i))
+            .collect();
+        let result = ParseResult::new("jupyter_notebook", "utf-8", 10).with_units(units);
+
+        let translated = translate_units(&result, "zh", "en", &translator)
+            .await
+            .unwrap();
+
+        for (i, unit) in translated.units.iter().enumerate() {
+            assert_eq!(unit.content, format!("[EN] cell {}", i));
+        }
+        assert_eq!(translated.stats.units, 10);
+        assert_eq!(translated.stats.failed, 0);
+        assert!(translated.stats.average_confidence() >= 0.8);
+    }
+
+    #[tokio::test]
+    async fn test_partial_failures_keep_original_text() {
+        let translator = MockTranslator::with_config(MockConfig {
+            delay_range: (0, 0),
+            faults: vec![MockFault::FailMatching(
+                regex::Regex::new("故障注入 乙").unwrap(),
+            )],
+            ..MockConfig::default()
+        });
+        let units: Vec<_> = ["故障注入 甲", "故障注入 乙", "故障注入 丙"]
+            .iter()
+            .enumerate()
+            .map(|(i, text)| {
+                TranslatableUnit::new(text.to_string(), UnitType::Comment, i as u32 + 1, 1)
+            })
+            .collect();
+        let result = ParseResult::new("python", "utf-8", 3).with_units(units);
+
+        let translated = translate_units(&result, "zh", "en", &translator)
+            .await
+            .unwrap();
+
+        let contents: Vec<_> = translated
+            .units
+            .iter()
+            .map(|u| u.content.as_str())
+            .collect();
+        assert_eq!(
+            contents,
+            vec!["[EN] 故障注入 甲", "故障注入 乙", "[EN] 故障注入 丙"]
+        );
+        assert_eq!(translated.stats.units, 3);
+        assert_eq!(translated.stats.failed, 1);
+        // The failed unit is unchanged
+        assert_eq!(translated.stats.bytes_changed, 2 * "故障注入 甲".len());
+    }
+
+    #[test]
+    fn test_unit_sources() {
+        let units: Vec<TranslatableUnit> = [
+            "これはテスト用の日本語の文章です",
+            "これは二番目の日本語のコメントです",
+            "这是一个用于测试自动检测的中文句子",
+            "This function reads the configuration file and returns a dictionary",
+            "√ ok",
+            "Short note",
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, text)| TranslatableUnit::new(text.to_string(), UnitType::Comment, i as u32, 1))
+        .collect();
+
+        let language = |code: &str| UnitSource::Language(code.to_string());
+        assert_eq!(
+            unit_sources(&units, "auto", "en"),
+            vec![
+                language("ja"),
+                language("ja"),
+                language("zh-CN"),
+                UnitSource::Target,
+                // Too short to detect: the file's most common language
+                language("ja"),
+                UnitSource::Unknown,
+            ]
+        );
+
+        // An explicit source only skips units in the target language
+        assert_eq!(
+            unit_sources(&units, "zh", "en"),
+            vec![
+                language("zh"),
+                language("zh"),
+                language("zh"),
+                UnitSource::Target,
+                language("zh"),
+                language("zh"),
+            ]
+        );
+        assert!(same_language("zh-CN", "zh"));
+        assert!(!same_language("ja", "en"));
+    }
+
+    #[tokio::test]
+    async fn test_translate_by_language_batches_per_source() {
+        let translator = MockTranslator::with_config(MockConfig {
+            delay_range: (0, 0),
+            ..MockConfig::default()
+        });
+        let texts: Vec<String> = [
+            "这是一个用于测试的中文句子",
+            "これはテスト用の日本語の文章です",
+            "This sentence is already written in English",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+        let results = translate_by_language(&texts, "auto", "en", &translator)
+            .await
+            .unwrap();
+        let sources: Vec<&str> = results.iter().map(|r| r.source_language.as_str()).collect();
+        assert_eq!(sources, ["zh", "ja", "auto"]);
+        assert_eq!(
+            results[1].translated_text,
+            "[EN] これはテスト用の日本語の文章です"
+        );
+        assert_eq!(results[2].status, TranslationStatus::Skipped);
+
+        // A short reply fails the texts it left out, keeping the rest
+        let translator = MockTranslator::with_config(MockConfig {
+            delay_range: (0, 0),
+            faults: vec![MockFault::ShortReply(1)],
+            ..MockConfig::default()
+        });
+        let texts = [
+            texts[0].clone(),
+            "这是另一个用于测试的中文句子".to_string(),
+            texts[2].clone(),
+        ];
+        let results = translate_by_language(&texts, "auto", "en", &translator)
+            .await
+            .unwrap();
+        let statuses: Vec<TranslationStatus> = results.iter().map(|r| r.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                TranslationStatus::Success,
+                TranslationStatus::Failed,
+                TranslationStatus::Skipped
+            ]
+        );
+        assert_eq!(results[1].translated_text, texts[1]);
+    }
+
+    #[tokio::test]
+    async fn test_auto_source_skips_target_language() {
+        let translator = MockTranslator::with_config(MockConfig {
+            delay_range: (0, 0),
+            ..MockConfig::default()
+        });
+        let units = vec![
+            TranslatableUnit::new(
+                "自动检测测试：这是一个中文注释".to_string(),
+                UnitType::Comment,
+                1,
+                1,
+            ),
+            TranslatableUnit::new(
+                "This function reads the configuration file and returns a dictionary".to_string(),
+                UnitType::Comment,
+                2,
+                1,
+            ),
+        ];
+        let result = ParseResult::new("python", "utf-8", 2).with_units(units);
+
+        let translated = translate_units(&result, "auto", "en", &translator)
+            .await
+            .unwrap();
+        assert_eq!(
+            translated.units[0].content,
+            "[EN] 自动检测测试：这是一个中文注释"
+        );
+        assert_eq!(
+            translated.units[1].content,
+            "This function reads the configuration file and returns a dictionary"
+        );
+        assert_eq!(translated.stats.units, 1);
+        assert_eq!(translated.stats.skipped, 1);
+
+        // The same holds with an explicit source language
+        let translated = translate_units(&result, "zh", "en", &translator)
+            .await
+            .unwrap();
+        assert_eq!(
+            translated.units[0].content,
+            "[EN] 自动检测测试：这是一个中文注释"
+        );
+        assert_eq!(
+            translated.units[1].content,
+            "This function reads the configuration file and returns a dictionary"
+        );
+        assert_eq!((translated.stats.units, translated.stats.skipped), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_placeholders_survive_translation() {
+        let translator = MockTranslator::with_config(MockConfig {
+            delay_range: (0, 0),
+            ..MockConfig::default()
+        });
+        let unit = TranslatableUnit::new(
+            "占位符测试 %s 写入 {path}，见 `cfg.load()` 和 https://example.com/a".to_string(),
+            UnitType::Comment,
+            1,
+            1,
+        );
+        let result = ParseResult::new("python", "utf-8", 1).with_units(vec![unit]);
+
+        let translated = translate_units(&result, "zh", "en", &translator)
+            .await
+            .unwrap();
+        assert_eq!(
+            translated.units[0].content,
+            "[EN] 占位符测试 %s 写入 {path}，见 `cfg.load()` 和 https://example.com/a"
+        );
+
+        // A translation that drops a token keeps the original text
+        let masked = Masked::new("写入 {path}");
+        let lost = TranslationResult::success(
+            masked.text.clone(),
+            "Write it".to_string(),
+            "zh".to_string(),
+            "en".to_string(),
+            0.9,
+        );
+        let unmasked = unmask(lost, "写入 {path}", &masked, None);
+        assert_eq!(unmasked.status, TranslationStatus::Failed);
+        assert_eq!(unmasked.translated_text, "写入 {path}");
+
+        // A format string whose translation swaps bare placeholders binds
+        // them by number
+        let masked = Masked::new("已将 %s 复制到 %s");
+        let swapped = TranslationResult::success(
+            masked.text.clone(),
+            "Copied to ⟪1⟫ from ⟪0⟫".to_string(),
+            "zh".to_string(),
+            "en".to_string(),
+            0.9,
+        );
+        let unmasked = unmask(
+            swapped.clone(),
+            "已将 %s 复制到 %s",
+            &masked,
+            Some(Numbering::Go),
+        );
+        assert_eq!(unmasked.translated_text, "Copied to %[2]s from %[1]s");
+        let unmasked = unmask(
+            swapped,
+            "已将 %s 复制到 %s",
+            &masked,
+            Some(Numbering::BraceOnly),
+        );
+        assert_eq!(unmasked.status, TranslationStatus::Failed);
+    }
+
+    fn result(status: TranslationStatus, confidence: f64) -> TranslationResult {
+        let mut result = TranslationResult::success(
+            "a".to_string(),
+            "b".to_string(),
+            "zh".to_string(),
+            "en".to_string(),
+            confidence,
+        );
+        result.status = status;
+        result
+    }
+
+    #[tokio::test]
+    async fn test_translation_memory_normalizes_text() {
+        let requests = Arc::new(TranslationMetrics::new());
+        let translator = cached(Box::new(MetricsTranslator::new(
+            Box::new(MockTranslator::with_config(MockConfig {
+                delay_range: (0, 0),
+                ..MockConfig::default()
+            })),
+            Arc::clone(&requests),
+        )));
+        let units = vec![
+            TranslatableUnit::new("记忆测试  一。".to_string(), UnitType::Comment, 1, 1),
+            TranslatableUnit::new("记忆测试 一".to_string(), UnitType::Comment, 2, 1),
+            TranslatableUnit::new("记忆测试 二".to_string(), UnitType::Comment, 3, 1),
+        ];
+        let result = ParseResult::new("python", "utf-8", 3).with_units(units);
+
+        let translated = translate_units(&result, "zh", "en", &translator)
+            .await
+            .unwrap();
+        assert_eq!(requests.total_requests(), 1);
+        assert_eq!(translated.units[0].content, "[EN] 记忆测试  一。");
+        assert_eq!(translated.units[1].content, "[EN] 记忆测试  一");
+        assert_eq!(translated.units[2].content, "[EN] 记忆测试 二");
+        assert_eq!(translated.stats.units, 3);
+
+        // Another file with the same sentences only hits the memory
+        let again = ParseResult::new("python", "utf-8", 1).with_units(vec![TranslatableUnit::new(
+            "记忆测试 二。".to_string(),
+            UnitType::Comment,
+            1,
+            1,
+        )]);
+        let translated = translate_units(&again, "zh", "en", &translator)
+            .await
+            .unwrap();
+        assert_eq!(requests.total_requests(), 1);
+        assert_eq!(translated.units[0].content, "[EN] 记忆测试 二。");
+    }
+
+    #[tokio::test]
+    async fn test_budget_exceeded_fails_the_file() {
+        let tracker = Arc::new(CostTracker::new(Budget {
+            max_characters: Some(4),
+            ..Budget::default()
+        }));
+        let translator = BudgetTranslator::new(
+            Box::new(MockTranslator::with_config(MockConfig {
+                delay_range: (0, 0),
+                ..MockConfig::default()
+            })),
+            Arc::clone(&tracker),
+        );
+        let result =
+            ParseResult::new("python", "utf-8", 1).with_units(vec![TranslatableUnit::new(
+                "预算测试超出限制".to_string(),
+                UnitType::Comment,
+                1,
+                1,
+            )]);
+
+        let error = translate_units(&result, "zh", "en", &translator)
+            .await
+            .err()
+            .unwrap();
+        assert!(is_budget_exceeded(&error));
+        assert!(!is_budget_exceeded(&anyhow::anyhow!("Failed to parse")));
+        assert_eq!(tracker.total().requests, 0);
+    }
+
+    #[test]
+    fn test_parse_confidence() {
+        assert_eq!(parse_confidence("0.8").unwrap(), 0.8);
+        assert_eq!(parse_confidence("1").unwrap(), 1.0);
+        assert_eq!(parse_confidence("0").unwrap(), 0.0);
+        for invalid in ["80", "-0.1", "1.01", "NaN", "high", ""] {
+            assert!(parse_confidence(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("10m").unwrap(), Duration::from_secs(600));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration(" 45s ").unwrap(), Duration::from_secs(45));
+        assert!(parse_duration("10").is_ok());
+        assert!(parse_duration("9999999999999999h").is_err());
+        assert!(parse_duration("18446744073709551615").is_err());
+        assert!(parse_duration("5124095576030431h1s").is_err());
+        for invalid in ["", "m", "10x", "1h30", "0m"] {
+            assert!(parse_duration(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_split_bilingual() {
+        let units: Vec<TranslatableUnit> = [
+            (
+                UnitType::Comment,
+                "初始化配置文件 / initialize the config file",
+            ),
+            (UnitType::Comment, "这是一个用于测试的中文注释"),
+            (
+                UnitType::Docstring,
+                "初始化配置文件 / initialize the config file",
+            ),
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, (unit_type, text))| {
+            TranslatableUnit::new(text.to_string(), *unit_type, i as u32, 1)
+        })
+        .collect();
+        let targets = vec!["en".to_string(); units.len()];
+        let contents = |units: &[TranslatableUnit]| -> Vec<String> {
+            units.iter().map(|unit| unit.content.clone()).collect()
+        };
+
+        let (same, splits) = split_bilingual(&units, &targets, BilingualMode::Translate);
+        assert_eq!(contents(&same), contents(&units));
+        assert!(splits.iter().all(Option::is_none));
+
+        let (kept, splits) = split_bilingual(&units, &targets, BilingualMode::KeepTarget);
+        assert_eq!(kept[0].content, "initialize the config file");
+        assert_eq!(kept[0].detected_language.as_deref(), Some("en"));
+        assert_eq!(contents(&kept[1..]), contents(&units[1..]));
+        assert!(splits[0].is_some() && splits[2].is_none());
+
+        let (foreign, splits) = split_bilingual(&units, &targets, BilingualMode::TranslateForeign);
+        assert_eq!(foreign[0].content, "初始化配置文件");
+        assert_eq!(foreign[0].detected_language.as_deref(), Some("zh-CN"));
+        assert_eq!(
+            splits[0]
+                .as_ref()
+                .and_then(|split| split.replace_other("en", "Initialize the configuration file")),
+            Some("Initialize the configuration file / initialize the config file".to_string())
+        );
+        assert!(BilingualMode::parse("keep-target").is_ok());
+        assert!(BilingualMode::parse("both").is_err());
+    }
+
+    #[test]
+    fn test_select_units_skips_todos() {
+        use langlint_parsers::Parser;
+
+        let mut result = langlint_parsers::PythonParser::new()
+            .extract_units("# TODO: 修复\n# 普通注释\n", "test.py")
+            .unwrap();
+        let mut with_todos = result.clone();
+
+        FileOptions::default().select_units(&mut result);
+        assert_eq!(result.units.len(), 1);
+        assert_eq!(result.units[0].content, "普通注释");
+
+        FileOptions {
+            include_todos: true,
+            ..FileOptions::default()
+        }
+        .select_units(&mut with_todos);
+        assert_eq!(with_todos.units.len(), 2);
+    }
+
+    #[test]
+    fn test_select_units_skips_license_headers() {
+        use langlint_parsers::Parser;
+
+        let mut result = langlint_parsers::PythonParser::new()
+            .extract_units(
+                "# 版权所有 (c) 2024 某公司\n# 保留所有权利\n\nimport os\n# 普通注释\n",
+                "test.py",
+            )
+            .unwrap();
+        let mut with_headers = result.clone();
+
+        FileOptions::default().select_units(&mut result);
+        assert_eq!(result.units.len(), 1);
+        assert_eq!(result.units[0].content, "普通注释");
+
+        FileOptions {
+            include_license_headers: true,
+            ..FileOptions::default()
+        }
+        .select_units(&mut with_headers);
+        // The two header lines form one paragraph
+        assert_eq!(with_headers.units.len(), 2);
+    }
+
+    #[test]
+    fn test_select_notebook_cells() {
+        use langlint_parsers::Parser;
+
+        let notebook = r##"{"cells":[{"cell_type":"markdown","source":["本节介绍数据清洗"]},{"cell_type":"code","source":["# 读取原始数据\n","df = load()"]}]}"##;
+        let contents = |kinds: &[&str]| {
+            let kinds: Vec<String> = kinds.iter().map(|k| k.to_string()).collect();
+            let mut result = langlint_parsers::NotebookParser::new()
+                .extract_units(notebook, "lesson.ipynb")
+                .unwrap();
+            FileOptions {
+                notebook_cells: NotebookCells::parse(&kinds).unwrap(),
+                ..FileOptions::default()
+            }
+            .select_units(&mut result);
+            result
+                .units
+                .into_iter()
+                .map(|u| u.content)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(contents(&["markdown"]), vec!["本节介绍数据清洗"]);
+        assert_eq!(contents(&["Code"]), vec!["读取原始数据"]);
+        assert_eq!(contents(&[]).len(), 2);
+        assert_eq!(contents(&["markdown", "code"]).len(), 2);
+        assert!(NotebookCells::parse(&["raw".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_history_filter() {
+        use langlint_parsers::Parser;
+        use std::process::Command;
+
+        let repo = tempfile::TempDir::new().unwrap();
+        let file = repo.path().join("app.py");
+        std::fs::write(&file, "# 旧的注释\nx = 1\n").unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+                .args(args)
+                .env("GIT_AUTHOR_DATE", "2020-06-01T00:00:00Z")
+                .current_dir(repo.path())
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {:?}", args);
+        };
+        git(&["init", "-q"]);
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "init"]);
+        std::fs::write(&file, "# 旧的注释\nx = 1\n# 新的注释\n").unwrap();
+
+        let parse = || {
+            langlint_parsers::PythonParser::new()
+                .extract_units(&std::fs::read_to_string(&file).unwrap(), "app.py")
+                .unwrap()
+        };
+        let contents = |filter: HistoryFilter| {
+            let mut result = parse();
+            filter.select_units(&file, &mut result).unwrap();
+            result
+                .units
+                .into_iter()
+                .map(|u| u.content)
+                .collect::<Vec<_>>()
+        };
+
+        let legacy = HistoryFilter::from_dates(Some("2021-01-01"), None).unwrap();
+        assert_eq!(contents(legacy), vec!["旧的注释"]);
+        let recent = HistoryFilter::from_dates(None, Some("2021-01-01")).unwrap();
+        assert_eq!(contents(recent), vec!["新的注释"]);
+        assert_eq!(contents(HistoryFilter::default()).len(), 2);
+
+        let by_author = HistoryFilter::default().with_authors(vec!["t@example".to_string()]);
+        assert_eq!(contents(by_author), vec!["旧的注释"]);
+        let by_other = HistoryFilter::default().with_authors(vec!["bob".to_string()]);
+        assert!(contents(by_other).is_empty());
+    }
+
+    #[test]
+    fn test_select_units_skips_generated_files() {
+        use langlint_parsers::Parser;
+
+        let mut result = langlint_parsers::GenericCodeParser::new()
+            .extract_units(
+                "// Code generated by protoc-gen-go. DO NOT EDIT.\n// 消息定义\n",
+                "api.pb.go",
+            )
+            .unwrap();
+        let mut included = result.clone();
+
+        FileOptions::default().select_units(&mut result);
+        assert!(result.units.is_empty());
+
+        FileOptions {
+            include_generated: true,
+            ..FileOptions::default()
+        }
+        .select_units(&mut included);
+        assert_eq!(included.units.len(), 1);
+    }
+
+    #[test]
+    fn test_verify_reconstruction() {
+        use langlint_parsers::Parser;
+
+        let parser = langlint_parsers::PythonParser::new();
+        let original =
+            "# 计算总和\ndef add(a, b):\n    \"\"\"返回两数之和\"\"\"\n    return a + b\n";
+        let units = parser.extract_units(original, "math.py").unwrap().units;
+
+        let mut translated = units.clone();
+        translated[0].content = "Compute the sum".to_string();
+        translated[1].content = "Return the sum".to_string();
+        let reconstructed = parser
+            .reconstruct(original, &translated, "math.py")
+            .unwrap();
+        assert!(verify_reconstruction(original, &reconstructed, &units, &translated).is_empty());
+
+        // A newline would turn the rest of the comment into code, and a stray
+        // delimiter would end the docstring early
+        translated[0].content = "Compute\nthe sum".to_string();
+        translated[1].content = "Return \"\"\"the sum".to_string();
+        let reconstructed = parser
+            .reconstruct(original, &translated, "math.py")
+            .unwrap();
+        let reasons = verify_reconstruction(original, &reconstructed, &units, &translated);
+        assert_eq!(reasons.len(), 2);
+        assert!(reasons[0].contains("line 1"));
+        assert!(reasons[1].contains("appears 3 times, expected 2"));
+    }
+
+    #[test]
+    fn test_unsafe_character_reasons() {
+        let original = "# 检查权限\nif admin:\n    pass\n";
+        assert!(
+            unsafe_character_reasons(original, "# Check access\nif admin:\n    pass\n").is_empty()
+        );
+        let reasons = unsafe_character_reasons(
+            original,
+            "# Check\u{202e} access\u{2066}\nif admin:\n    pass\n",
+        );
+        assert_eq!(
+            reasons,
+            vec![
+                "line 1: translation introduces control or invisible characters (U+202E, U+2066)"
+                    .to_string()
+            ]
+        );
+        assert_eq!(
+            UnsafeCharacters::parse(" Reject").unwrap(),
+            UnsafeCharacters::Reject
+        );
+        assert!(UnsafeCharacters::parse("drop").is_err());
+    }
+
+    #[test]
+    fn test_verify_reconstruction_json() {
+        let original = r##"{"cells": [{"source": ["# 标题"]}]}"##;
+        let broken = r##"{"cells": [{"source": ["# "Title""]}]}"##;
+        let reasons = verify_reconstruction(original, broken, &[], &[]);
+        assert_eq!(reasons.len(), 1);
+        assert!(reasons[0].starts_with("no longer valid JSON"));
+    }
+
+    #[test]
+    fn test_verify_reconstruction_format_arguments() {
+        let before = TranslatableUnit::new(
+            "%s 中有 %d 个错误".to_string(),
+            UnitType::StringLiteral,
+            3,
+            17,
+        );
+        let mut after = before.clone();
+        after.content = "%2$d errors in %1$s".to_string();
+        assert!(verify_reconstruction(
+            "",
+            "",
+            std::slice::from_ref(&before),
+            std::slice::from_ref(&after)
+        )
+        .is_empty());
+
+        after.content = "%d errors in %s".to_string();
+        let reasons = verify_reconstruction("", "", &[before], &[after]);
+        assert_eq!(
+            reasons,
+            vec!["line 3: translated string binds format arguments differently"]
+        );
+    }
+
+    #[test]
+    fn test_is_forced() {
+        assert!(!FileOptions::default().is_forced("src/app.py"));
+
+        let options = FileOptions {
+            force: vec!["app.py".to_string(), "docs/*.md".to_string()],
+            ..FileOptions::default()
+        };
+        assert!(options.is_forced("src/app.py"));
+        assert!(options.is_forced("app.py"));
+        assert!(!options.is_forced("src/webapp.py"));
+        assert!(options.is_forced("docs/intro.md"));
+
+        let all = FileOptions {
+            force: vec!["*".to_string()],
+            ..FileOptions::default()
+        };
+        assert!(all.is_forced("any/where/file.rs"));
+    }
+
+    #[test]
+    fn test_quarantine_report() {
+        let mut report = QuarantineReport::default();
+        let quarantined = anyhow::Error::new(Quarantined {
+            reasons: vec!["line 3: translated comment spans several lines".to_string()],
+        });
+        assert!(report.record(Path::new("app.py"), &quarantined));
+        assert!(!report.record(Path::new("lib.py"), &anyhow::anyhow!("network down")));
+        assert_eq!(report.len(), 1);
+    }
+
+    #[test]
+    fn test_file_errors() {
+        let denied = || {
+            anyhow::Error::new(std::io::Error::from(std::io::ErrorKind::PermissionDenied))
+                .context("Failed to read file: secret.py")
+        };
+
+        let mut errors = FileErrors::new(false);
+        errors.record(Path::new("secret.py"), denied()).unwrap();
+        errors
+            .record(
+                Path::new("bad.py"),
+                anyhow::anyhow!("unexpected token").context("Failed to parse file: bad.py"),
+            )
+            .unwrap();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors.files()[0].kind, "permission denied");
+        assert_eq!(errors.files()[1].kind, "parse failure");
+        assert_eq!(error_kind(&anyhow::anyhow!("network down")), "error");
+
+        let mut strict = FileErrors::new(true);
+        let error = strict.record(Path::new("secret.py"), denied()).unwrap_err();
+        assert!(error.to_string().contains("secret.py"));
+        assert!(strict.is_empty());
+    }
+
+    #[test]
+    fn test_run_stats() {
+        let mut stats = RunStats::default();
+        stats.record(&[result(TranslationStatus::Success, 0.9)]);
+        stats.record(&[
+            result(TranslationStatus::Success, 0.8),
+            result(TranslationStatus::Failed, 0.0),
+        ]);
+
+        let mut total = RunStats::default();
+        total.merge(&stats);
+        total.merge(&stats);
+
+        assert_eq!(total.units, 4);
+        assert_eq!(total.failed, 2);
+        assert!((total.average_confidence() - 0.45).abs() < 1e-9);
+        assert_eq!(total.failure_rate(), 0.5);
+        assert_eq!(RunStats::default().average_confidence(), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_confidence_gate_keeps_low_confidence_units() {
+        let units = vec![
+            TranslatableUnit::new("置信度高的注释".to_string(), UnitType::Comment, 1, 1),
+            TranslatableUnit::new("置信度低的注释".to_string(), UnitType::Comment, 2, 1),
+        ];
+        let batch = |confidences: [f64; 2]| -> Vec<TranslatedBatch> {
+            let translated = units
+                .iter()
+                .zip(confidences)
+                .map(|(unit, confidence)| {
+                    let result = TranslationResult::success(
+                        unit.content.clone(),
+                        "Comment".to_string(),
+                        "zh".to_string(),
+                        "en".to_string(),
+                        confidence,
+                    );
+                    ("Comment".to_string(), vec![result])
+                })
+                .collect();
+            vec![("zh".to_string(), "en".to_string(), vec![0, 1], translated)]
+        };
+
+        let gate = ConfidenceGate {
+            min_confidence: 0.7,
+            secondary: None,
+        };
+        let mut batches = batch([0.9, 0.4]);
+        let review = gate.review(&units, None, &mut batches).await.unwrap();
+        let translated = &batches[0].3;
+        assert_eq!(translated[0].0, "Comment");
+        assert_eq!(translated[1].0, "置信度低的注释");
+        assert_eq!(translated[1].1[0].status, TranslationStatus::Partial);
+        assert_eq!(
+            review,
+            vec![ReviewItem {
+                file: String::new(),
+                line: 2,
+                original: "置信度低的注释".to_string(),
+                translation: "Comment".to_string(),
+                confidence: 0.4,
+            }]
+        );
+        let stats = RunStats {
+            review,
+            ..RunStats::default()
+        };
+        assert_eq!(stats.in_file("a.py").review[0].file, "a.py");
+
+        // The mock answers with 0.8 or more, so the retry is kept
+        let gate = ConfidenceGate {
+            secondary: Some("mock".to_string()),
+            ..gate
+        };
+        let mut batches = batch([0.9, 0.4]);
+        assert!(gate
+            .review(&units, None, &mut batches)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(batches[0].3[1].0, "[EN] 置信度低的注释");
+
+        assert!(configure_confidence(Some(1.5), None).is_err());
+        assert!(configure_confidence(None, Some("mock".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_quality_gate() {
+        let stats = RunStats {
+            units: 4,
+            failed: 1,
+            confidence_sum: 3.0,
+            ..RunStats::default()
+        };
+
+        assert!(QualityGate::default().check(&stats).is_ok());
+        assert!(QualityGate {
+            min_avg_confidence: Some(0.7),
+            max_failed_units: Some(1),
+        }
+        .check(&stats)
+        .is_ok());
+        assert!(QualityGate {
+            min_avg_confidence: Some(0.8),
+            ..QualityGate::default()
+        }
+        .check(&stats)
+        .is_err());
+        assert!(QualityGate {
+            max_failed_units: Some(0),
+            ..QualityGate::default()
+        }
+        .check(&stats)
+        .is_err());
+    }
+}
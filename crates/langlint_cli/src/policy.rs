@@ -0,0 +1,358 @@
+//! Language policy from the config's `[[policy]]` tables
+//!
+//! A rule either limits the languages units may be written in ("docstrings
+//! must be English", "comments may be English or Japanese"), or requires
+//! files to exist in several languages ("docs must exist in en and zh").
+//! A file's language comes from its name (`README.zh.md`, `docs/zh/x.md`)
+//! or, failing that, from its content; files differing only in that tag are
+//! versions of the same document. File globs follow the `parsers` table:
+//! without a `/` they match file names anywhere in the tree.
+
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobMatcher};
+use langlint_core::types::detect_language;
+use langlint_core::{paths, PolicyRule, TranslatableUnit, UnitType};
+use langlint_parsers::locale::is_language_tag;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::pipeline::same_language;
+use crate::report::{Finding, Level};
+
+/// A unit or file breaking a rule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Violation {
+    pub rule: String,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+impl From<Violation> for Finding {
+    fn from(violation: Violation) -> Self {
+        Self {
+            rule: violation.rule,
+            path: violation.path,
+            line: violation.line,
+            message: violation.message,
+            level: Level::Error,
+        }
+    }
+}
+
+/// Compiled policy rules
+#[derive(Debug, Default)]
+pub struct Policy {
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug)]
+struct Rule {
+    name: String,
+    units: Vec<UnitType>,
+    /// Matchers, and whether they match file names only
+    files: Vec<(GlobMatcher, bool)>,
+    languages: Vec<String>,
+    require_translations: Vec<String>,
+}
+
+impl Rule {
+    fn matches_file(&self, path: &str) -> bool {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        self.files.is_empty()
+            || self
+                .files
+                .iter()
+                .any(|(matcher, name_only)| matcher.is_match(if *name_only { name } else { path }))
+    }
+}
+
+impl Policy {
+    /// Compile rules, checking unit type names and globs
+    pub fn new(rules: &[PolicyRule]) -> Result<Self> {
+        let mut compiled = Vec::new();
+        for (index, rule) in rules.iter().enumerate() {
+            let name = rule
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("policy[{}]", index));
+            if rule.languages.is_empty() && rule.require_translations.is_empty() {
+                anyhow::bail!(
+                    "Policy '{}' needs `languages` or `require_translations`",
+                    name
+                );
+            }
+            let units = rule
+                .units
+                .iter()
+                .map(|unit| {
+                    serde_json::from_value(serde_json::Value::from(unit.trim().to_lowercase()))
+                        .map_err(|_| {
+                            anyhow::anyhow!(
+                                "Unknown unit type '{}' in policy '{}' (expected comment, \
+                                 docstring, string_literal, text_node or metadata)",
+                                unit,
+                                name
+                            )
+                        })
+                })
+                .collect::<Result<_>>()?;
+            let files = rule
+                .files
+                .iter()
+                .map(|pattern| {
+                    let pattern = paths::normalize_separators(pattern);
+                    let matcher = GlobBuilder::new(&pattern)
+                        .literal_separator(true)
+                        .case_insensitive(paths::CASE_INSENSITIVE)
+                        .build()
+                        .with_context(|| format!("Invalid glob in policy '{}': {}", name, pattern))?
+                        .compile_matcher();
+                    Ok((matcher, !pattern.contains('/')))
+                })
+                .collect::<Result<_>>()?;
+            compiled.push(Rule {
+                name,
+                units,
+                files,
+                languages: rule.languages.clone(),
+                require_translations: rule.require_translations.clone(),
+            });
+        }
+        Ok(Self { rules: compiled })
+    }
+
+    /// Check if some rule requires files to exist in several languages
+    pub fn checks_translations(&self) -> bool {
+        self.rules
+            .iter()
+            .any(|rule| !rule.require_translations.is_empty())
+    }
+
+    /// Units of a file written in a language their rules do not allow
+    ///
+    /// `path` is relative to the checked root. Units whose language is not
+    /// detected pass.
+    pub fn check_units(&self, path: &str, units: &[TranslatableUnit]) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        for rule in &self.rules {
+            if rule.languages.is_empty() || !rule.matches_file(path) {
+                continue;
+            }
+            for unit in units {
+                if !rule.units.is_empty() && !rule.units.contains(&unit.unit_type) {
+                    continue;
+                }
+                let Some(language) = unit
+                    .detected_language
+                    .clone()
+                    .or_else(|| detect_language(&unit.content))
+                else {
+                    continue;
+                };
+                if rule
+                    .languages
+                    .iter()
+                    .any(|allowed| same_language(&language, allowed))
+                {
+                    continue;
+                }
+                violations.push(Violation {
+                    rule: rule.name.clone(),
+                    path: path.to_string(),
+                    line: Some(unit.line_number),
+                    message: format!(
+                        "{} in {} (allowed: {}): {}",
+                        unit_type_name(unit.unit_type),
+                        language,
+                        rule.languages.join(", "),
+                        excerpt(&unit.content)
+                    ),
+                });
+            }
+        }
+        violations
+    }
+
+    /// Documents missing a required language
+    ///
+    /// `files` are paths relative to the checked root with the language of
+    /// their content, used when the name carries no language tag.
+    pub fn check_translations(&self, files: &[(String, Option<String>)]) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        for rule in &self.rules {
+            if rule.require_translations.is_empty() {
+                continue;
+            }
+            // Versions of each document, by path without the language tag
+            let mut documents: BTreeMap<String, Vec<(&str, Option<String>)>> = BTreeMap::new();
+            for (path, content_language) in files {
+                if !rule.matches_file(path) {
+                    continue;
+                }
+                let (document, tagged) = split_language(path);
+                let language = tagged.or_else(|| content_language.clone());
+                documents
+                    .entry(document)
+                    .or_default()
+                    .push((path.as_str(), language));
+            }
+
+            for versions in documents.values() {
+                for required in &rule.require_translations {
+                    let present = versions.iter().any(|(_, language)| {
+                        language
+                            .as_deref()
+                            .is_some_and(|language| same_language(language, required))
+                    });
+                    if present {
+                        continue;
+                    }
+                    let found: Vec<&str> = versions.iter().map(|(path, _)| *path).collect();
+                    violations.push(Violation {
+                        rule: rule.name.clone(),
+                        path: versions[0].0.to_string(),
+                        line: None,
+                        message: format!("No {} version (found: {})", required, found.join(", ")),
+                    });
+                }
+            }
+        }
+        violations
+    }
+}
+
+/// A path with its language tag replaced, and that language: `README.zh.md`
+/// gives `README.md` and `zh`, `docs/en/x.md` gives `docs/*/x.md` and `en`
+pub(crate) fn split_language(path: &str) -> (String, Option<String>) {
+    let (dir, file) = path.rsplit_once('/').unwrap_or(("", path));
+    let join = |dir: &str, file: &str| match dir {
+        "" => file.to_string(),
+        _ => format!("{}/{}", dir, file),
+    };
+    let mut pieces: Vec<&str> = file.split('.').collect();
+    if pieces.len() >= 3 && is_language_tag(pieces[pieces.len() - 2]) {
+        let language = pieces.remove(pieces.len() - 2).to_string();
+        return (join(dir, &pieces.join(".")), Some(language));
+    }
+    let mut parts: Vec<&str> = dir.split('/').collect();
+    if let Some(index) = parts.iter().rposition(|part| is_language_tag(part)) {
+        let language = parts[index].to_string();
+        parts[index] = "*";
+        return (join(&parts.join("/"), file), Some(language));
+    }
+    (path.to_string(), None)
+}
+
+fn unit_type_name(unit_type: UnitType) -> String {
+    serde_json::to_value(unit_type)
+        .ok()
+        .and_then(|value| value.as_str().map(|name| name.replace('_', " ")))
+        .unwrap_or_default()
+}
+
+/// The start of a unit's first line
+fn excerpt(text: &str) -> String {
+    let line = text.trim().lines().next().unwrap_or("");
+    match line.char_indices().nth(60) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(name: &str, units: &[&str], files: &[&str], languages: &[&str]) -> PolicyRule {
+        let strings = |items: &[&str]| items.iter().map(|s| s.to_string()).collect();
+        PolicyRule {
+            name: Some(name.to_string()),
+            units: strings(units),
+            files: strings(files),
+            languages: strings(languages),
+            require_translations: Vec::new(),
+        }
+    }
+
+    fn unit(content: &str, unit_type: UnitType, line: u32) -> TranslatableUnit {
+        let mut unit = TranslatableUnit::new(content.to_string(), unit_type, line, 1);
+        unit.detect_language();
+        unit
+    }
+
+    #[test]
+    fn test_unit_languages() {
+        let policy = Policy::new(&[
+            rule("docstrings-en", &["docstring"], &["*.py"], &["en"]),
+            rule("comments", &["comment"], &[], &["en", "ja"]),
+        ])
+        .unwrap();
+        let units = [
+            unit("计算两个数的和并返回结果", UnitType::Docstring, 2),
+            unit("Compute the sum of two numbers", UnitType::Docstring, 9),
+            unit("ここで結果をキャッシュします", UnitType::Comment, 4),
+            unit("这里缓存计算结果以提高性能", UnitType::Comment, 5),
+        ];
+
+        let violations = policy.check_units("src/math.py", &units);
+        let found: Vec<(&str, Option<u32>)> = violations
+            .iter()
+            .map(|v| (v.rule.as_str(), v.line))
+            .collect();
+        assert_eq!(
+            found,
+            vec![("docstrings-en", Some(2)), ("comments", Some(5))]
+        );
+        assert!(violations[0].message.starts_with("docstring in zh"));
+
+        // The docstring rule only covers Python files
+        assert_eq!(policy.check_units("web/app.js", &units).len(), 1);
+    }
+
+    #[test]
+    fn test_required_translations() {
+        let policy = Policy::new(&[PolicyRule {
+            name: Some("bilingual-docs".to_string()),
+            files: vec!["*.md".to_string()],
+            require_translations: vec!["en".to_string(), "zh".to_string()],
+            ..PolicyRule::default()
+        }])
+        .unwrap();
+        let files = |paths: &[(&str, Option<&str>)]| -> Vec<(String, Option<String>)> {
+            paths
+                .iter()
+                .map(|(path, language)| (path.to_string(), language.map(str::to_string)))
+                .collect()
+        };
+
+        let violations = policy.check_translations(&files(&[
+            ("README.md", Some("en")),
+            ("README.zh-CN.md", None),
+            ("docs/en/guide.md", None),
+            ("docs/zh/guide.md", None),
+            ("docs/en/faq.md", None),
+            ("CHANGELOG.md", Some("en")),
+            ("src/main.rs", Some("en")),
+        ]));
+        let found: Vec<(&str, &str)> = violations
+            .iter()
+            .map(|v| (v.path.as_str(), v.message.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("CHANGELOG.md", "No zh version (found: CHANGELOG.md)"),
+                ("docs/en/faq.md", "No zh version (found: docs/en/faq.md)"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        assert!(Policy::new(&[rule("r", &["docs"], &[], &["en"])]).is_err());
+        assert!(Policy::new(&[rule("r", &[], &["["], &["en"])]).is_err());
+        assert!(Policy::new(&[rule("r", &[], &[], &[])]).is_err());
+    }
+}
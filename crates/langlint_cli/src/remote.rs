@@ -0,0 +1,83 @@
+//! Git URL inputs - shallow-clone a remote repository for auditing
+
+use anyhow::{Context, Result};
+use std::process::Command;
+use tempfile::TempDir;
+
+use crate::sandbox;
+
+/// Check if an input looks like a git URL rather than a local path
+pub fn is_git_url(input: &str) -> bool {
+    const SCHEMES: [&str; 5] = ["https://", "http://", "git://", "ssh://", "file://"];
+    if SCHEMES.iter().any(|scheme| input.starts_with(scheme)) {
+        return true;
+    }
+
+    // scp-like syntax: `git@github.com:owner/repo.git`
+    match input.split_once(':') {
+        Some((host, path)) => {
+            host.contains('@') && !host.contains('/') && !path.is_empty() && !path.starts_with('/')
+        }
+        None => false,
+    }
+}
+
+/// Shallow-clone a repository into a temporary directory
+///
+/// The checkout is removed when the returned [`TempDir`] is dropped.
+pub fn shallow_clone(url: &str, verbose: bool) -> Result<TempDir> {
+    sandbox::ensure_writable("Cloning a remote repository")?;
+    let dir = tempfile::Builder::new()
+        .prefix("langlint-remote-")
+        .tempdir()
+        .context("Failed to create temporary directory")?;
+
+    let mut command = Command::new("git");
+    command.args(["clone", "--depth", "1", "--single-branch"]);
+    if !verbose {
+        command.arg("--quiet");
+    }
+    let output = command
+        .arg("--")
+        .arg(url)
+        .arg(dir.path())
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()
+        .context("Failed to run git (is it installed?)")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to clone {}: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_git_url() {
+        assert!(is_git_url("https://github.com/HzaCode/Langlint"));
+        assert!(is_git_url("ssh://git@example.com/repo.git"));
+        assert!(is_git_url("git@github.com:HzaCode/Langlint.git"));
+        assert!(is_git_url("file:///srv/repos/app.git"));
+
+        assert!(!is_git_url("src/"));
+        assert!(!is_git_url("."));
+        assert!(!is_git_url("C:\\projects\\app"));
+        assert!(!is_git_url("notes@home/file.py"));
+    }
+
+    #[test]
+    fn test_clone_failure_is_reported() {
+        let dir = TempDir::new().unwrap();
+        let url = format!("file://{}/missing.git", dir.path().display());
+        let error = shallow_clone(&url, false).unwrap_err();
+        assert!(error.to_string().contains("Failed to clone"));
+    }
+}
@@ -0,0 +1,720 @@
+//! Output formats of `scan`, `check` and `sync`
+//!
+//! A command describes what it found in a [`Report`]; a [`Reporter`] turns
+//! the report into one output format. Reporters are looked up by the
+//! `--format` name in [`ReporterRegistry::global`], which holds the
+//! built-in formats (`text`, `json`, `pretty-json`, `sarif`, `github`,
+//! `html`), so a new format is a new reporter rather than another match arm
+//! in every command.
+
+use anyhow::Result;
+use colored::Colorize;
+use langlint_core::ParseResult;
+use langlint_parsers::paragraph;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use crate::commands::scan::GroupedView;
+use crate::packages::PackageSummary;
+use crate::pipeline::FileError;
+
+/// Severity of a finding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Error,
+    Warning,
+    Note,
+}
+
+/// A problem found in a file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    /// Rule or kind of problem
+    pub rule: String,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+    pub message: String,
+    pub level: Level,
+}
+
+impl Finding {
+    /// `path:line`, or the path alone
+    pub fn location(&self) -> String {
+        match self.line {
+            Some(line) => format!("{}:{}", self.path, line),
+            None => self.path.clone(),
+        }
+    }
+}
+
+/// What a command found
+pub enum ReportContent<'a> {
+    /// Translatable units of scanned files
+    Scan {
+        results: &'a [(PathBuf, ParseResult)],
+        packages: &'a [PackageSummary],
+        groups: &'a [GroupedView],
+    },
+    /// Problems found by a check
+    Findings {
+        /// JSON key of the findings (`violations`, `issues`)
+        key: &'static str,
+        findings: &'a [Finding],
+        /// Number of files checked
+        files: usize,
+        /// Said of the files when nothing was found ("are in sync")
+        success: &'static str,
+    },
+}
+
+/// Output of one command
+pub struct Report<'a> {
+    pub content: ReportContent<'a>,
+    /// Files that could not be processed
+    pub errors: &'a [FileError],
+    pub verbose: bool,
+}
+
+/// Renders reports in one output format
+pub trait Reporter: Send + Sync {
+    /// Format name selecting this reporter (`--format`)
+    fn name(&self) -> &'static str;
+
+    /// Render a report
+    fn render(&self, report: &Report) -> Result<String>;
+}
+
+/// Reporters by format name
+pub struct ReporterRegistry {
+    reporters: RwLock<BTreeMap<String, Arc<dyn Reporter>>>,
+}
+
+impl ReporterRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self {
+            reporters: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// Create a registry with the built-in formats
+    pub fn with_builtins() -> Self {
+        let registry = Self::new();
+        registry.register(TextReporter);
+        registry.register(JsonReporter { pretty: false });
+        registry.register(JsonReporter { pretty: true });
+        registry.register(SarifReporter);
+        registry.register(GithubReporter);
+        registry.register(HtmlReporter);
+        registry
+    }
+
+    /// The process-wide registry, initialised with the built-in formats
+    pub fn global() -> &'static ReporterRegistry {
+        static GLOBAL: OnceLock<ReporterRegistry> = OnceLock::new();
+        GLOBAL.get_or_init(Self::with_builtins)
+    }
+
+    /// Register a reporter under its name, replacing any previous one
+    pub fn register(&self, reporter: impl Reporter + 'static) {
+        self.reporters
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(reporter.name().to_lowercase(), Arc::new(reporter));
+    }
+
+    /// Registered format names, sorted
+    pub fn names(&self) -> Vec<String> {
+        self.reporters
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    /// The reporter of a format
+    pub fn get(&self, format: &str) -> Result<Arc<dyn Reporter>> {
+        let reporter = self
+            .reporters
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&format.to_lowercase())
+            .cloned();
+        reporter.ok_or_else(|| {
+            anyhow::anyhow!(
+                "Unknown output format: {} (available: {})",
+                format,
+                self.names().join(", ")
+            )
+        })
+    }
+}
+
+impl Default for ReporterRegistry {
+    fn default() -> Self {
+        Self::with_builtins()
+    }
+}
+
+/// Render a report in a format of the global registry
+pub fn render(report: &Report, format: &str) -> Result<String> {
+    ReporterRegistry::global().get(format)?.render(report)
+}
+
+/// Findings of a report; scanned units are notes
+fn findings(report: &Report) -> Vec<Finding> {
+    match &report.content {
+        ReportContent::Findings { findings, .. } => findings.to_vec(),
+        ReportContent::Scan { results, .. } => results
+            .iter()
+            .flat_map(|(path, result)| {
+                result.units.iter().map(move |unit| {
+                    let language = unit
+                        .detected_language
+                        .as_deref()
+                        .map(|language| format!(" ({})", language))
+                        .unwrap_or_default();
+                    Finding {
+                        rule: format!("{:?}", unit.unit_type).to_lowercase(),
+                        path: path.display().to_string(),
+                        line: Some(unit.line_number),
+                        message: format!(
+                            "Translatable {:?}{}: {}",
+                            unit.unit_type,
+                            language,
+                            truncate(&unit.content, 100)
+                        ),
+                        level: Level::Note,
+                    }
+                })
+            })
+            .collect(),
+    }
+}
+
+/// The first `max` characters of a text, marked when cut
+fn truncate(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// Human-readable text (`text`)
+pub struct TextReporter;
+
+impl Reporter for TextReporter {
+    fn name(&self) -> &'static str {
+        "text"
+    }
+
+    fn render(&self, report: &Report) -> Result<String> {
+        let mut output = String::new();
+        match &report.content {
+            ReportContent::Scan {
+                results,
+                packages,
+                groups,
+            } => {
+                output.push_str(&format!("\n{}\n", "=== Scan Results ==="));
+
+                for (path, result) in results.iter() {
+                    if result.units.is_empty() {
+                        continue;
+                    }
+
+                    output.push_str(&format!("\n{} {}\n", "File:", path.display()));
+                    output.push_str(&format!("{} {} units\n", "Units:", result.units.len()));
+
+                    if report.verbose {
+                        for (i, unit) in result.units.iter().enumerate() {
+                            output.push_str(&format!("\n  {} {}:\n", "Unit", i + 1));
+                            output.push_str(&format!("    Type: {:?}\n", unit.unit_type));
+                            output.push_str(&format!("    Priority: {:?}\n", unit.priority));
+                            output.push_str(&format!(
+                                "    Location: line {}, column {}\n",
+                                unit.line_number, unit.column_number
+                            ));
+                            output.push_str(&format!(
+                                "    Content: {}\n",
+                                truncate(&unit.content, 100)
+                            ));
+                        }
+                    }
+                }
+
+                if !packages.is_empty() {
+                    output.push_str(&format!("\n{}\n", "=== Packages ==="));
+                    for package in packages.iter() {
+                        output.push_str(&format!(
+                            "  {} ({}): {} files, {} units\n",
+                            package.name, package.root, package.files, package.units
+                        ));
+                    }
+                }
+
+                for view in groups.iter() {
+                    output.push_str(&format!("\n=== {} ===\n", view.by.title()));
+                    for group in &view.groups {
+                        output.push_str(&format!(
+                            "  {}: {} files, {} units\n",
+                            group.key, group.files, group.units
+                        ));
+                    }
+                }
+            }
+            ReportContent::Findings {
+                findings,
+                files,
+                success,
+                ..
+            } => {
+                for finding in findings.iter() {
+                    let mark = match finding.level {
+                        Level::Error => "✗".red(),
+                        Level::Warning => "⚠".yellow(),
+                        Level::Note => "•".dimmed(),
+                    };
+                    output.push_str(&format!(
+                        "{} {} {} {}\n",
+                        mark,
+                        finding.location().bold(),
+                        format!("[{}]", finding.rule).yellow(),
+                        finding.message
+                    ));
+                }
+                if findings.is_empty() {
+                    output.push_str(&format!("{} {} files {}\n", "✓".green(), files, success));
+                }
+            }
+        }
+        Ok(output)
+    }
+}
+
+/// JSON (`json`, and `pretty-json` indented)
+pub struct JsonReporter {
+    pub pretty: bool,
+}
+
+impl Reporter for JsonReporter {
+    fn name(&self) -> &'static str {
+        if self.pretty {
+            "pretty-json"
+        } else {
+            "json"
+        }
+    }
+
+    fn render(&self, report: &Report) -> Result<String> {
+        let value = match &report.content {
+            ReportContent::Scan {
+                results,
+                packages,
+                groups,
+            } => {
+                #[derive(Serialize)]
+                struct JsonOutput<'a> {
+                    files: Vec<FileOutput>,
+                    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+                    packages: &'a [PackageSummary],
+                    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+                    groups: &'a [GroupedView],
+                    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+                    errors: &'a [FileError],
+                }
+
+                #[derive(Serialize)]
+                struct FileOutput {
+                    path: String,
+                    units: Vec<UnitOutput>,
+                }
+
+                #[derive(Serialize)]
+                struct UnitOutput {
+                    content: String,
+                    unit_type: String,
+                    priority: String,
+                    line_start: usize,
+                    line_end: usize,
+                    #[serde(skip_serializing_if = "Option::is_none")]
+                    detected_language: Option<String>,
+                }
+
+                serde_json::to_value(JsonOutput {
+                    files: results
+                        .iter()
+                        .map(|(path, result)| FileOutput {
+                            path: path.display().to_string(),
+                            units: result
+                                .units
+                                .iter()
+                                .map(|unit| UnitOutput {
+                                    content: unit.content.clone(),
+                                    unit_type: format!("{:?}", unit.unit_type),
+                                    priority: format!("{:?}", unit.priority),
+                                    line_start: unit.line_number as usize,
+                                    line_end: paragraph::end_line(unit) as usize,
+                                    detected_language: unit.detected_language.clone(),
+                                })
+                                .collect(),
+                        })
+                        .collect(),
+                    packages,
+                    groups,
+                    errors: report.errors,
+                })?
+            }
+            ReportContent::Findings { key, findings, .. } => {
+                let mut output = serde_json::Map::new();
+                output.insert(key.to_string(), serde_json::to_value(findings)?);
+                if !report.errors.is_empty() {
+                    output.insert("errors".to_string(), serde_json::to_value(report.errors)?);
+                }
+                serde_json::Value::Object(output)
+            }
+        };
+
+        if self.pretty {
+            Ok(serde_json::to_string_pretty(&value)? + "\n")
+        } else {
+            Ok(serde_json::to_string(&value)? + "\n")
+        }
+    }
+}
+
+/// SARIF 2.1.0 for code scanning dashboards (`sarif`)
+pub struct SarifReporter;
+
+impl Reporter for SarifReporter {
+    fn name(&self) -> &'static str {
+        "sarif"
+    }
+
+    fn render(&self, report: &Report) -> Result<String> {
+        let findings = findings(report);
+        let rules: std::collections::BTreeSet<&str> = findings
+            .iter()
+            .map(|finding| finding.rule.as_str())
+            .collect();
+        let results: Vec<serde_json::Value> = findings
+            .iter()
+            .map(|finding| {
+                let mut location = serde_json::json!({
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": finding.path.replace('\\', "/") }
+                    }
+                });
+                if let Some(line) = finding.line {
+                    location["physicalLocation"]["region"] =
+                        serde_json::json!({ "startLine": line });
+                }
+                serde_json::json!({
+                    "ruleId": finding.rule,
+                    "level": finding.level,
+                    "message": { "text": finding.message },
+                    "locations": [location],
+                })
+            })
+            .collect();
+
+        let sarif = serde_json::json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "langlint",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "informationUri": "https://github.com/HzaCode/Langlint",
+                        "rules": rules
+                            .iter()
+                            .map(|rule| serde_json::json!({ "id": rule }))
+                            .collect::<Vec<_>>(),
+                    }
+                },
+                "results": results,
+            }]
+        });
+        Ok(serde_json::to_string_pretty(&sarif)? + "\n")
+    }
+}
+
+/// GitHub Actions workflow commands, shown as annotations (`github`)
+pub struct GithubReporter;
+
+impl GithubReporter {
+    fn escape_data(text: &str) -> String {
+        text.replace('%', "%25")
+            .replace('\r', "%0D")
+            .replace('\n', "%0A")
+    }
+
+    fn escape_property(text: &str) -> String {
+        Self::escape_data(text)
+            .replace(':', "%3A")
+            .replace(',', "%2C")
+    }
+}
+
+impl Reporter for GithubReporter {
+    fn name(&self) -> &'static str {
+        "github"
+    }
+
+    fn render(&self, report: &Report) -> Result<String> {
+        let mut output = String::new();
+        for finding in findings(report) {
+            let command = match finding.level {
+                Level::Error => "error",
+                Level::Warning => "warning",
+                Level::Note => "notice",
+            };
+            let mut properties = format!("file={}", Self::escape_property(&finding.path));
+            if let Some(line) = finding.line {
+                properties.push_str(&format!(",line={}", line));
+            }
+            properties.push_str(&format!(",title={}", Self::escape_property(&finding.rule)));
+            output.push_str(&format!(
+                "::{} {}::{}\n",
+                command,
+                properties,
+                Self::escape_data(&finding.message)
+            ));
+        }
+        for error in report.errors {
+            output.push_str(&format!(
+                "::warning file={},title={}::{}\n",
+                Self::escape_property(&error.path),
+                Self::escape_property(error.kind),
+                Self::escape_data(&error.message)
+            ));
+        }
+        Ok(output)
+    }
+}
+
+/// A standalone HTML page (`html`)
+pub struct HtmlReporter;
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl Reporter for HtmlReporter {
+    fn name(&self) -> &'static str {
+        "html"
+    }
+
+    fn render(&self, report: &Report) -> Result<String> {
+        let (title, header, rows) = match &report.content {
+            ReportContent::Scan { results, .. } => {
+                let rows: Vec<[String; 5]> = results
+                    .iter()
+                    .flat_map(|(path, result)| {
+                        result.units.iter().map(move |unit| {
+                            [
+                                format!("{}:{}", path.display(), unit.line_number),
+                                format!("{:?}", unit.unit_type),
+                                format!("{:?}", unit.priority),
+                                unit.detected_language.clone().unwrap_or_default(),
+                                unit.content.clone(),
+                            ]
+                        })
+                    })
+                    .collect();
+                (
+                    "Scan results",
+                    ["Location", "Type", "Priority", "Language", "Content"],
+                    rows,
+                )
+            }
+            ReportContent::Findings { findings, .. } => (
+                "Findings",
+                ["Location", "Rule", "Level", "", "Message"],
+                findings
+                    .iter()
+                    .map(|finding| {
+                        [
+                            finding.location(),
+                            finding.rule.clone(),
+                            format!("{:?}", finding.level).to_lowercase(),
+                            String::new(),
+                            finding.message.clone(),
+                        ]
+                    })
+                    .collect(),
+            ),
+        };
+
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>langlint: {title}</title>\n\
+             <style>body{{font-family:sans-serif}}table{{border-collapse:collapse}}\
+             td,th{{border:1px solid #ccc;padding:4px 8px;text-align:left;vertical-align:top}}\
+             td:last-child{{white-space:pre-wrap}}</style>\n</head>\n<body>\n<h1>{title}</h1>\n<p>{} entries</p>\n<table>\n<tr>",
+            rows.len()
+        );
+        for cell in header.iter().filter(|cell| !cell.is_empty()) {
+            html.push_str(&format!("<th>{}</th>", cell));
+        }
+        html.push_str("</tr>\n");
+        for row in &rows {
+            html.push_str("<tr>");
+            for (cell, name) in row.iter().zip(header) {
+                if !name.is_empty() {
+                    html.push_str(&format!("<td>{}</td>", escape_html(cell)));
+                }
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</table>\n");
+        if !report.errors.is_empty() {
+            html.push_str("<h2>Errors</h2>\n<ul>\n");
+            for error in report.errors {
+                html.push_str(&format!(
+                    "<li>{} ({}): {}</li>\n",
+                    escape_html(&error.path),
+                    error.kind,
+                    escape_html(&error.message)
+                ));
+            }
+            html.push_str("</ul>\n");
+        }
+        html.push_str("</body>\n</html>\n");
+        Ok(html)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use langlint_core::{TranslatableUnit, UnitType};
+
+    fn finding(level: Level, line: Option<u32>) -> Finding {
+        Finding {
+            rule: "docstrings-english".to_string(),
+            path: "src/a,b.py".to_string(),
+            line,
+            message: "Docstring in zh-CN\n(expected en)".to_string(),
+            level,
+        }
+    }
+
+    #[test]
+    fn test_registry() {
+        let registry = ReporterRegistry::with_builtins();
+        assert_eq!(
+            registry.names(),
+            vec!["github", "html", "json", "pretty-json", "sarif", "text"]
+        );
+        assert!(registry.get("SARIF").is_ok());
+        let error = registry.get("xml").err().unwrap().to_string();
+        assert!(error.contains("Unknown output format: xml"));
+
+        struct CountReporter;
+        impl Reporter for CountReporter {
+            fn name(&self) -> &'static str {
+                "count"
+            }
+            fn render(&self, report: &Report) -> Result<String> {
+                Ok(findings(report).len().to_string())
+            }
+        }
+        registry.register(CountReporter);
+        let findings = [finding(Level::Error, Some(3))];
+        let report = Report {
+            content: ReportContent::Findings {
+                key: "violations",
+                findings: &findings,
+                files: 1,
+                success: "are fine",
+            },
+            errors: &[],
+            verbose: false,
+        };
+        assert_eq!(registry.get("count").unwrap().render(&report).unwrap(), "1");
+    }
+
+    #[test]
+    fn test_finding_formats() {
+        let findings = [
+            finding(Level::Error, Some(3)),
+            finding(Level::Warning, None),
+        ];
+        let report = Report {
+            content: ReportContent::Findings {
+                key: "violations",
+                findings: &findings,
+                files: 2,
+                success: "are fine",
+            },
+            errors: &[],
+            verbose: false,
+        };
+
+        let json: serde_json::Value =
+            serde_json::from_str(&render(&report, "json").unwrap()).unwrap();
+        assert_eq!(json["violations"][0]["line"], 3);
+        assert_eq!(json["violations"][1]["level"], "warning");
+
+        assert_eq!(
+            render(&report, "github").unwrap(),
+            "::error file=src/a%2Cb.py,line=3,title=docstrings-english::Docstring in zh-CN%0A(expected en)\n\
+             ::warning file=src/a%2Cb.py,title=docstrings-english::Docstring in zh-CN%0A(expected en)\n"
+        );
+
+        let sarif: serde_json::Value =
+            serde_json::from_str(&render(&report, "sarif").unwrap()).unwrap();
+        let result = &sarif["runs"][0]["results"][0];
+        assert_eq!(result["ruleId"], "docstrings-english");
+        assert_eq!(result["level"], "error");
+        assert_eq!(
+            result["locations"][0]["physicalLocation"]["region"]["startLine"],
+            3
+        );
+        assert!(
+            sarif["runs"][0]["results"][1]["locations"][0]["physicalLocation"]
+                .get("region")
+                .is_none()
+        );
+
+        let html = render(&report, "html").unwrap();
+        assert!(html.contains("<td>src/a,b.py:3</td><td>docstrings-english</td><td>error</td>"));
+    }
+
+    #[test]
+    fn test_scan_formats() {
+        let mut unit = TranslatableUnit::new("<b>注释</b>".to_string(), UnitType::Comment, 4, 1);
+        unit.detected_language = Some("zh-CN".to_string());
+        let results = vec![(
+            PathBuf::from("a.py"),
+            ParseResult::new("python", "utf-8", 5).with_units(vec![unit]),
+        )];
+        let report = Report {
+            content: ReportContent::Scan {
+                results: &results,
+                packages: &[],
+                groups: &[],
+            },
+            errors: &[],
+            verbose: false,
+        };
+
+        assert_eq!(
+            render(&report, "github").unwrap(),
+            "::notice file=a.py,line=4,title=comment::Translatable Comment (zh-CN): <b>注释</b>\n"
+        );
+        assert!(render(&report, "html")
+            .unwrap()
+            .contains("<td>&lt;b&gt;注释&lt;/b&gt;</td>"));
+        assert!(render(&report, "text").unwrap().contains("Units: 1 units"));
+    }
+}
@@ -0,0 +1,92 @@
+//! Read-only sandbox for auditing untrusted repositories
+//!
+//! With `--sandbox` a run writes nothing: translate and i18n become dry
+//! runs, commands and options that write files are refused, remote
+//! repositories are not cloned and no translation cache is read or
+//! written. Only the offline mock translator runs, plus translators
+//! allowed with `--allow-translator`.
+
+use anyhow::Result;
+use std::sync::OnceLock;
+
+/// Translators that never reach the network
+const OFFLINE_TRANSLATORS: [&str; 1] = ["mock"];
+
+static SANDBOX: OnceLock<Sandbox> = OnceLock::new();
+
+/// Restrictions of a sandboxed run
+#[derive(Debug, Clone, Default)]
+pub struct Sandbox {
+    allowed_translators: Vec<String>,
+}
+
+impl Sandbox {
+    /// Sandbox allowing the named network translators
+    pub fn new(allowed_translators: Vec<String>) -> Self {
+        Self {
+            allowed_translators: allowed_translators
+                .into_iter()
+                .map(|name| name.trim().to_lowercase())
+                .collect(),
+        }
+    }
+
+    /// Fail unless a translator is offline or allowed
+    pub fn check_translator(&self, name: &str) -> Result<()> {
+        let name = name.trim().to_lowercase();
+        if OFFLINE_TRANSLATORS.contains(&name.as_str()) || self.allowed_translators.contains(&name)
+        {
+            return Ok(());
+        }
+        anyhow::bail!(
+            "Translator '{}' is blocked in sandbox mode (allow it with --allow-translator {})",
+            name,
+            name
+        )
+    }
+}
+
+/// Run this process in the sandbox
+pub fn configure(allowed_translators: Vec<String>) {
+    let _ = SANDBOX.set(Sandbox::new(allowed_translators));
+}
+
+/// Check if this process runs in the sandbox
+pub fn is_active() -> bool {
+    SANDBOX.get().is_some()
+}
+
+/// Fail if a translator may not be used in this process
+pub fn check_translator(name: &str) -> Result<()> {
+    match SANDBOX.get() {
+        Some(sandbox) => sandbox.check_translator(name),
+        None => Ok(()),
+    }
+}
+
+/// Fail in the sandbox, naming what would have written to disk
+pub fn ensure_writable(what: &str) -> Result<()> {
+    if is_active() {
+        anyhow::bail!(
+            "{} is not allowed in sandbox mode, which writes nothing",
+            what
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translator_whitelist() {
+        let sandbox = Sandbox::new(vec![" LibreTranslate ".to_string()]);
+        assert!(sandbox.check_translator("mock").is_ok());
+        assert!(sandbox.check_translator("libretranslate").is_ok());
+
+        let error = sandbox.check_translator("Google").unwrap_err();
+        assert!(error.to_string().contains("--allow-translator google"));
+        assert!(Sandbox::default().check_translator("ollama").is_err());
+    }
+}
@@ -0,0 +1,275 @@
+//! Translation memory shared through a remote backend
+//!
+//! Lets CI runs and developers reuse each other's translations: texts the
+//! local memory misses are looked up in Redis (`redis://`) or an HTTP
+//! service (`https://`) before translating, and new translations are
+//! stored there under the local memory's keys.
+//!
+//! The HTTP service answers `POST {url}/lookup` with `{"keys": [...]}` by
+//! `{"entries": {key: {"translation", "confidence"}}}`, and accepts
+//! `POST {url}/store` with `{"entries": {...}}`; a bearer token is sent
+//! from `LANGLINT_SHARED_MEMORY_TOKEN`.
+//!
+//! The backend only saves work, so it never fails a run: after its first
+//! error it is turned off for the rest of the run with a warning.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use colored::Colorize;
+use langlint_core::{MemoryEntry, SharedMemoryConfig};
+use langlint_translators::SharedStore;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+/// Prefix of the Redis keys, so the database can be shared
+const REDIS_PREFIX: &str = "langlint:tm:";
+
+/// Timeout of one request to the backend
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+static SHARED: OnceLock<Arc<SharedMemory>> = OnceLock::new();
+
+/// Use a shared memory for this process
+///
+/// Fails on an unsupported URL.
+pub fn configure(config: &SharedMemoryConfig) -> Result<()> {
+    let token = std::env::var("LANGLINT_SHARED_MEMORY_TOKEN")
+        .ok()
+        .filter(|token| !token.is_empty());
+    let _ = SHARED.set(Arc::new(SharedMemory::new(config, token)?));
+    Ok(())
+}
+
+/// Shared memory of this process, if one is configured
+pub fn get() -> Option<&'static Arc<SharedMemory>> {
+    SHARED.get()
+}
+
+enum Backend {
+    Redis {
+        client: redis::Client,
+        connection: tokio::sync::OnceCell<redis::aio::MultiplexedConnection>,
+    },
+    Http {
+        client: reqwest::Client,
+        url: String,
+        token: Option<String>,
+    },
+}
+
+/// Remote translation memory
+pub struct SharedMemory {
+    backend: Backend,
+    read_only: bool,
+    disabled: AtomicBool,
+}
+
+#[derive(Serialize)]
+struct LookupRequest<'a> {
+    keys: &'a [String],
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entries {
+    #[serde(default)]
+    entries: HashMap<String, MemoryEntry>,
+}
+
+impl SharedMemory {
+    /// Create a client for a `redis://`, `rediss://`, `http://` or `https://` URL
+    pub fn new(config: &SharedMemoryConfig, token: Option<String>) -> Result<Self> {
+        let url = config.url.trim();
+        let backend = if url.starts_with("redis://") || url.starts_with("rediss://") {
+            Backend::Redis {
+                client: redis::Client::open(url)
+                    .with_context(|| format!("Invalid shared memory URL: {}", url))?,
+                connection: tokio::sync::OnceCell::new(),
+            }
+        } else if url.starts_with("http://") || url.starts_with("https://") {
+            Backend::Http {
+                client: reqwest::Client::builder().timeout(TIMEOUT).build()?,
+                url: url.trim_end_matches('/').to_string(),
+                token,
+            }
+        } else {
+            anyhow::bail!(
+                "Unsupported shared memory URL: {} (expected redis:// or https://)",
+                url
+            );
+        };
+        Ok(Self {
+            backend,
+            read_only: config.read_only,
+            disabled: AtomicBool::new(false),
+        })
+    }
+
+    /// Turn the backend off after an error, warning once
+    fn or_disable<T>(&self, result: Result<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                if !self.disabled.swap(true, Ordering::Relaxed) {
+                    eprintln!(
+                        "{} {:#}; continuing without the shared translation memory",
+                        "Warning:".yellow(),
+                        e
+                    );
+                }
+                None
+            }
+        }
+    }
+
+    async fn redis_connection(&self) -> Result<redis::aio::MultiplexedConnection> {
+        let Backend::Redis { client, connection } = &self.backend else {
+            unreachable!("not a Redis backend");
+        };
+        let connection = connection
+            .get_or_try_init(|| async {
+                tokio::time::timeout(TIMEOUT, client.get_multiplexed_tokio_connection())
+                    .await
+                    .context("Timed out connecting to the shared memory")?
+                    .context("Failed to connect to the shared memory")
+            })
+            .await?;
+        Ok(connection.clone())
+    }
+
+    async fn redis_get(&self, keys: &[String]) -> Result<HashMap<String, MemoryEntry>> {
+        let mut connection = self.redis_connection().await?;
+        let redis_keys: Vec<String> = keys.iter().map(|key| redis_key(key)).collect();
+        let values: Vec<Option<String>> = tokio::time::timeout(
+            TIMEOUT,
+            redis::cmd("MGET")
+                .arg(&redis_keys)
+                .query_async(&mut connection),
+        )
+        .await
+        .context("Timed out reading the shared memory")?
+        .context("Failed to read the shared memory")?;
+
+        // Malformed entries count as missing
+        Ok(keys
+            .iter()
+            .zip(values)
+            .filter_map(|(key, value)| {
+                let entry = serde_json::from_str(&value?).ok()?;
+                Some((key.clone(), entry))
+            })
+            .collect())
+    }
+
+    async fn redis_set(&self, entries: HashMap<String, MemoryEntry>) -> Result<()> {
+        let mut connection = self.redis_connection().await?;
+        let mut command = redis::cmd("MSET");
+        for (key, entry) in &entries {
+            command
+                .arg(redis_key(key))
+                .arg(serde_json::to_string(entry)?);
+        }
+        tokio::time::timeout(TIMEOUT, command.query_async::<_, ()>(&mut connection))
+            .await
+            .context("Timed out writing the shared memory")?
+            .context("Failed to write the shared memory")
+    }
+
+    async fn http_post<B, R>(&self, endpoint: &str, body: &B) -> Result<R>
+    where
+        B: Serialize,
+        R: for<'de> Deserialize<'de>,
+    {
+        let Backend::Http { client, url, token } = &self.backend else {
+            unreachable!("not an HTTP backend");
+        };
+        let mut request = client.post(format!("{}/{}", url, endpoint)).json(body);
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach the shared memory at {}", url))?
+            .error_for_status()
+            .context("Shared memory request failed")?;
+        response
+            .json()
+            .await
+            .context("Invalid response from the shared memory")
+    }
+}
+
+#[async_trait]
+impl SharedStore for SharedMemory {
+    /// Look up entries by memory key; missing keys are left out
+    async fn get_many(&self, keys: &[String]) -> HashMap<String, MemoryEntry> {
+        if keys.is_empty() || self.disabled.load(Ordering::Relaxed) {
+            return HashMap::new();
+        }
+        let result = match &self.backend {
+            Backend::Redis { .. } => self.redis_get(keys).await,
+            Backend::Http { .. } => self
+                .http_post::<_, Entries>("lookup", &LookupRequest { keys })
+                .await
+                .map(|response| response.entries),
+        };
+        self.or_disable(result).unwrap_or_default()
+    }
+
+    /// Store entries by memory key, unless the memory is read-only
+    async fn insert_many(&self, entries: HashMap<String, MemoryEntry>) {
+        if entries.is_empty() || self.read_only || self.disabled.load(Ordering::Relaxed) {
+            return;
+        }
+        let result = match &self.backend {
+            Backend::Redis { .. } => self.redis_set(entries).await,
+            Backend::Http { .. } => self
+                .http_post::<_, serde::de::IgnoredAny>("store", &Entries { entries })
+                .await
+                .map(|_| ()),
+        };
+        self.or_disable(result);
+    }
+}
+
+fn redis_key(key: &str) -> String {
+    format!("{}{}", REDIS_PREFIX, key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(url: &str) -> SharedMemoryConfig {
+        SharedMemoryConfig {
+            url: url.to_string(),
+            read_only: false,
+        }
+    }
+
+    #[test]
+    fn test_backend_urls() {
+        assert!(SharedMemory::new(&config("redis://127.0.0.1:6379/0"), None).is_ok());
+        assert!(SharedMemory::new(&config("https://tm.example.com/v1/"), None).is_ok());
+        assert!(SharedMemory::new(&config("ftp://tm.example.com"), None).is_err());
+        assert_eq!(
+            redis_key("google:zh:en:数据"),
+            "langlint:tm:google:zh:en:数据"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_backend_is_disabled() {
+        // Nothing listens on port 9 (discard) locally
+        let shared = SharedMemory::new(&config("http://127.0.0.1:9"), None).unwrap();
+        let keys = vec!["google:zh:en:数据".to_string()];
+
+        assert!(shared.get_many(&keys).await.is_empty());
+        assert!(shared.disabled.load(Ordering::Relaxed));
+        // Later calls skip the backend
+        assert!(shared.get_many(&keys).await.is_empty());
+    }
+}
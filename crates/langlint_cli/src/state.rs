@@ -0,0 +1,187 @@
+//! Per-repository state remembered between runs
+//!
+//! `langlint stats` records the dominant detected language of each file it
+//! scans in a [`STATE_FILE`] next to its input, along with the file's size
+//! and modification time so unchanged files are not parsed again. When a
+//! command gets no `-s` and the config names no single source language,
+//! the language recorded for its input (the file's own, or the one
+//! dominant across the files below a directory) is used instead of
+//! auto-detection.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// State file name, in the directory `langlint stats` was run on
+pub const STATE_FILE: &str = ".langlint-state.json";
+
+/// What is known about the files below a directory
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct State {
+    /// Files by path relative to the state file's directory, with `/`
+    #[serde(default)]
+    pub files: BTreeMap<String, FileState>,
+}
+
+/// Detected language of a file, as of its recorded size and time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileState {
+    /// Most common detected language of its units, if any was detected
+    pub language: Option<String>,
+    /// Units detected in that language
+    pub units: usize,
+    pub size: u64,
+    /// Modification time, in seconds since the Unix epoch
+    pub modified: u64,
+}
+
+impl State {
+    /// State of the directory `root`, empty if it has none
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = root.join(STATE_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read state file: {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Invalid state file: {}", path.display()))
+    }
+
+    /// Write the state into the directory `root`
+    pub fn save(&self, root: &Path) -> Result<()> {
+        let path = root.join(STATE_FILE);
+        fs::write(&path, serde_json::to_string_pretty(self)? + "\n")
+            .with_context(|| format!("Failed to write state file: {}", path.display()))
+    }
+
+    /// Recorded state of `file` if the file has not changed since
+    pub fn current(&self, root: &Path, file: &Path) -> Option<&FileState> {
+        let (size, modified) = stamp(file)?;
+        self.files
+            .get(&key(root, file))
+            .filter(|state| state.size == size && state.modified == modified)
+    }
+
+    /// Record the dominant language of `file`'s units
+    pub fn record(&mut self, root: &Path, file: &Path, language: Option<String>, units: usize) {
+        let (size, modified) = stamp(file).unwrap_or_default();
+        self.files.insert(
+            key(root, file),
+            FileState {
+                language,
+                units,
+                size,
+                modified,
+            },
+        );
+    }
+
+    /// Language of the most units across the files below `prefix` (a path
+    /// relative to the state's directory, empty for all), ties broken
+    /// alphabetically
+    pub fn dominant(&self, prefix: &str) -> Option<String> {
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for (path, state) in &self.files {
+            let below = prefix.is_empty()
+                || path == prefix
+                || path.starts_with(&format!("{}/", prefix.trim_end_matches('/')));
+            if let Some(language) = state.language.as_deref().filter(|_| below) {
+                *counts.entry(language).or_default() += state.units.max(1);
+            }
+        }
+        let mut best: Option<(&str, usize)> = None;
+        for (language, count) in counts {
+            if best.is_none_or(|(_, best_count)| count > best_count) {
+                best = Some((language, count));
+            }
+        }
+        best.map(|(language, _)| language.to_string())
+    }
+}
+
+/// Directory the state of `input` is kept in: the input itself, or a file's
+/// directory
+pub fn root(input: &Path) -> PathBuf {
+    if input.is_dir() {
+        input.to_path_buf()
+    } else {
+        input
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .map_or_else(|| PathBuf::from("."), Path::to_path_buf)
+    }
+}
+
+/// Source language recorded for `input` in the nearest state file at or
+/// above it
+pub fn recorded_source(input: &str) -> Option<String> {
+    let input = std::path::absolute(input).ok()?;
+    let dir = root(&input)
+        .ancestors()
+        .find(|dir| dir.join(STATE_FILE).is_file())?
+        .to_path_buf();
+    let state = State::load(&dir).ok()?;
+    let prefix = langlint_core::paths::to_slash(input.strip_prefix(&dir).ok()?);
+    state.dominant(&prefix)
+}
+
+/// `file` relative to `root`, with `/`
+fn key(root: &Path, file: &Path) -> String {
+    langlint_core::paths::to_slash(file.strip_prefix(root).unwrap_or(file))
+}
+
+/// Size and modification time of a file
+fn stamp(file: &Path) -> Option<(u64, u64)> {
+    let metadata = fs::metadata(file).ok()?;
+    let modified = metadata
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_secs();
+    Some((metadata.len(), modified))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_state_records_dominant_language() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join("docs")).unwrap();
+        let files = [
+            (root.join("main.py"), "zh-CN", 5),
+            (root.join("docs/a.md"), "ja", 2),
+            (root.join("docs/b.md"), "ja", 2),
+        ];
+        let mut state = State::default();
+        for (file, language, units) in &files {
+            fs::write(file, "# 注释\n").unwrap();
+            state.record(root, file, Some(language.to_string()), *units);
+        }
+        state.save(root).unwrap();
+
+        let state = State::load(root).unwrap();
+        assert!(state.current(root, &files[0].0).is_some());
+        assert_eq!(state.dominant("").as_deref(), Some("zh-CN"));
+        assert_eq!(state.dominant("docs").as_deref(), Some("ja"));
+        assert_eq!(state.dominant("docs/b.md").as_deref(), Some("ja"));
+        assert_eq!(state.dominant("src"), None);
+
+        let input = root.join("docs");
+        assert_eq!(
+            recorded_source(&input.to_string_lossy()).as_deref(),
+            Some("ja")
+        );
+
+        fs::write(&files[0].0, "# 改过的注释\n").unwrap();
+        assert!(state.current(root, &files[0].0).is_none());
+    }
+}
@@ -0,0 +1,202 @@
+//! Langlint's own messages in the user's language
+//!
+//! Summaries and status lines are looked up by id in the Fluent (`.ftl`)
+//! catalogs under `locales/`, built into the binary. The language is the
+//! `--ui-lang` value, else the system locale (`LC_ALL`, `LC_MESSAGES`,
+//! `LANG`), else English; messages a catalog lacks fall back to English.
+//! Catalogs are formatted with `fluent-bundle`, so selectors, terms and
+//! attributes work; numeric variables are passed as Fluent numbers, e.g. to
+//! pick a plural form with `{ $count -> [one] ... *[other] ... }`.
+
+use anyhow::Result;
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue};
+use std::sync::OnceLock;
+
+/// Built-in catalogs, English first
+const CATALOGS: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.ftl")),
+    ("zh-CN", include_str!("../locales/zh-CN.ftl")),
+    ("ja", include_str!("../locales/ja.ftl")),
+];
+
+/// Look up a message, filling in its variables
+///
+/// `tr!("files-processed", count = 3)` formats the `files-processed`
+/// message with `{ $count }` set to 3.
+macro_rules! tr {
+    ($id:literal) => {
+        $crate::ui::message($id, &[])
+    };
+    ($id:literal, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::ui::message($id, &[$((stringify!($name), $value.to_string())),+])
+    };
+}
+pub(crate) use tr;
+
+/// Messages of one catalog
+struct Catalog(FluentBundle<FluentResource>);
+
+impl Catalog {
+    /// Parse a catalog for a language, failing on Fluent syntax errors
+    fn parse(language: &str, source: &str) -> Result<Self> {
+        let resource = FluentResource::try_new(source.to_string())
+            .map_err(|(_, errors)| anyhow::anyhow!("Invalid {} catalog: {:?}", language, errors))?;
+        let locale = language
+            .parse()
+            .map_err(|e| anyhow::anyhow!("Invalid catalog language '{}': {}", language, e))?;
+        let mut bundle = FluentBundle::new_concurrent(vec![locale]);
+        // Plain terminal output, without Unicode isolation marks
+        bundle.set_use_isolating(false);
+        bundle
+            .add_resource(resource)
+            .map_err(|errors| anyhow::anyhow!("Invalid {} catalog: {:?}", language, errors))?;
+        Ok(Self(bundle))
+    }
+
+    fn format(&self, id: &str, args: &[(&str, String)]) -> Option<String> {
+        let pattern = self.0.get_message(id)?.value()?;
+        let mut fluent_args = FluentArgs::new();
+        for (name, value) in args {
+            fluent_args.set(*name, FluentValue::try_number(value));
+        }
+        let mut errors = Vec::new();
+        Some(
+            self.0
+                .format_pattern(pattern, Some(&fluent_args), &mut errors)
+                .into_owned(),
+        )
+    }
+}
+
+fn catalogs() -> &'static [Catalog] {
+    static PARSED: OnceLock<Vec<Catalog>> = OnceLock::new();
+    PARSED.get_or_init(|| {
+        CATALOGS
+            .iter()
+            .map(|(language, source)| Catalog::parse(language, source).unwrap())
+            .collect()
+    })
+}
+
+/// Index of the catalog in use
+static LANGUAGE: OnceLock<usize> = OnceLock::new();
+
+/// Catalog for a language tag or POSIX locale such as `zh_CN.UTF-8`,
+/// matched exactly and then by primary language
+fn resolve(locale: &str) -> Option<usize> {
+    let tag = locale
+        .split(['.', '@'])
+        .next()
+        .unwrap_or_default()
+        .replace('_', "-");
+    if tag.is_empty() || tag == "C" || tag == "POSIX" {
+        return None;
+    }
+    let primary = |tag: &str| tag.split('-').next().unwrap_or_default().to_lowercase();
+    CATALOGS
+        .iter()
+        .position(|(name, _)| name.eq_ignore_ascii_case(&tag))
+        .or_else(|| {
+            CATALOGS
+                .iter()
+                .position(|(name, _)| primary(name) == primary(&tag))
+        })
+}
+
+/// Language of the system locale, from the POSIX locale variables
+fn system_locale(env: impl Fn(&str) -> Option<String>) -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| env(name))
+        .find(|value| !value.is_empty())
+}
+
+/// Pick the language of langlint's messages for the rest of the run
+pub fn configure(ui_lang: Option<&str>) -> Result<()> {
+    let language = match ui_lang {
+        Some(requested) => resolve(requested).ok_or_else(|| {
+            let available: Vec<&str> = CATALOGS.iter().map(|(name, _)| *name).collect();
+            anyhow::anyhow!(
+                "Unknown UI language '{}' (available: {})",
+                requested,
+                available.join(", ")
+            )
+        })?,
+        None => system_locale(|name| std::env::var(name).ok())
+            .and_then(|locale| resolve(&locale))
+            .unwrap_or(0),
+    };
+    let _ = LANGUAGE.set(language);
+    Ok(())
+}
+
+/// Message `id` in the configured language, with `args` filled in
+pub fn message(id: &str, args: &[(&str, String)]) -> String {
+    let catalogs = catalogs();
+    let language = LANGUAGE.get().copied().unwrap_or(0);
+    catalogs[language]
+        .format(id, args)
+        .or_else(|| catalogs[0].format(id, args))
+        .unwrap_or_else(|| id.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_locale() {
+        assert_eq!(resolve("zh_CN.UTF-8"), Some(1));
+        assert_eq!(resolve("zh-TW"), Some(1));
+        assert_eq!(resolve("ja_JP"), Some(2));
+        assert_eq!(resolve("en-GB"), Some(0));
+        assert_eq!(resolve("C.UTF-8"), None);
+        assert_eq!(resolve("fr_FR"), None);
+
+        let env = |name: &str| match name {
+            "LC_ALL" => Some(String::new()),
+            "LANG" => Some("ja_JP.UTF-8".to_string()),
+            _ => None,
+        };
+        assert_eq!(system_locale(env).as_deref(), Some("ja_JP.UTF-8"));
+    }
+
+    use fluent_syntax::ast::Entry;
+
+    #[test]
+    fn test_catalogs_format_every_message() {
+        let english = Catalog::parse("en", CATALOGS[0].1).unwrap();
+        assert_eq!(
+            english
+                .format(
+                    "failed-units",
+                    &[("count", "2".into()), ("percent", "5.0".into())]
+                )
+                .unwrap(),
+            "Failed units: 2 (5.0%)"
+        );
+        assert_eq!(
+            english
+                .format("errors-skipped", &[("count", "1".into())])
+                .unwrap(),
+            "(1 file skipped, rerun with --strict to stop at the first):"
+        );
+        let resource = FluentResource::try_new(CATALOGS[0].1.to_string()).unwrap();
+        for (name, source) in &CATALOGS[1..] {
+            let catalog = Catalog::parse(name, source).unwrap();
+            for entry in resource.entries() {
+                if let Entry::Message(message) = entry {
+                    let id = message.id.name;
+                    assert!(catalog.0.has_message(id), "{} lacks {}", name, id);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_catalog_is_rejected() {
+        assert!(Catalog::parse("en", "summary = { $count ->\n").is_err());
+        assert!(Catalog::parse("en", "summary = Summary:\nsummary = Again\n").is_err());
+    }
+}
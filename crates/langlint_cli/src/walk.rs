@@ -0,0 +1,63 @@
+//! Directory filtering shared by every command that walks a tree
+//!
+//! The skipped directory names come from the config (`override_excludes`,
+//! `extend_default_excludes`) and are set once at startup; until then the
+//! defaults in [`langlint_core::DEFAULT_EXCLUDED_DIRS`] apply.
+
+use langlint_core::{paths, Config};
+use std::path::Path;
+use std::sync::OnceLock;
+use walkdir::{DirEntry, WalkDir};
+
+static EXCLUDED_DIRS: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Use the config's excluded directories for this process
+pub fn configure(config: &Config) {
+    let _ = EXCLUDED_DIRS.set(config.excluded_dirs());
+}
+
+/// Directory names skipped while walking
+pub fn excluded_dirs() -> &'static [String] {
+    EXCLUDED_DIRS.get_or_init(|| Config::default().excluded_dirs())
+}
+
+/// Walk a directory tree following links, in file name order
+///
+/// Sorting makes the file order, and so every report, stable across runs
+/// and platforms.
+pub fn walker(root: &Path) -> WalkDir {
+    WalkDir::new(root).follow_links(true).sort_by_file_name()
+}
+
+/// Check if a walk should descend into (or yield) an entry
+///
+/// The root is always kept; below it hidden entries and excluded
+/// directory names are skipped.
+pub fn keep_entry(entry: &DirEntry) -> bool {
+    if entry.depth() == 0 {
+        return true;
+    }
+    let name = entry.file_name().to_string_lossy();
+    !is_skipped_name(&name, excluded_dirs())
+}
+
+/// Check if a file or directory name is hidden or excluded
+///
+/// Names compare case-insensitively on Windows.
+pub fn is_skipped_name(name: &str, excluded: &[String]) -> bool {
+    name.starts_with('.') || excluded.iter().any(|dir| paths::names_equal(dir, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_skipped_name() {
+        let excluded = vec!["node_modules".to_string()];
+        assert!(is_skipped_name(".git", &excluded));
+        assert!(is_skipped_name("node_modules", &excluded));
+        assert!(!is_skipped_name("examples", &excluded));
+        assert!(!is_skipped_name("src", &[]));
+    }
+}
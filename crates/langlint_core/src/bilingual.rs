@@ -0,0 +1,168 @@
+//! Comments written in two languages
+//!
+//! Comments such as `初始化 config / initialize config` or
+//! `初始化配置（initialize config）` say the same thing twice.
+//! [`Bilingual::split`] finds the two halves, split at a slash, bar or
+//! dash, or a trailing parenthesis, when each is detected in a different
+//! language.
+
+use crate::types::detect_language;
+
+/// Separators between the halves, tried in order
+const SEPARATORS: [&str; 6] = [" / ", " ／ ", "／", " | ", " — ", " - "];
+
+/// Parentheses around a trailing half
+const PARENTHESES: [(&str, &str); 2] = [("（", "）"), ("(", ")")];
+
+/// A text split into halves in two languages
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bilingual {
+    pub first: String,
+    pub first_language: String,
+    /// Text between the halves, spaces included
+    pub separator: String,
+    pub second: String,
+    pub second_language: String,
+    /// Closing parenthesis after the second half, if any
+    pub closing: String,
+}
+
+impl Bilingual {
+    /// Split a single-line text into halves in two different languages
+    pub fn split(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if text.contains('\n') {
+            return None;
+        }
+
+        for (open, close) in PARENTHESES {
+            let Some(inner) = text.strip_suffix(close) else {
+                continue;
+            };
+            let Some(start) = inner.rfind(open) else {
+                continue;
+            };
+            let first = inner[..start].trim_end();
+            let separator = &inner[first.len()..start + open.len()];
+            if let Some(split) =
+                Self::from_halves(first, separator, &inner[start + open.len()..], close)
+            {
+                return Some(split);
+            }
+        }
+
+        SEPARATORS.iter().find_map(|separator| {
+            let (first, second) = text.split_once(separator)?;
+            if second.contains(separator) {
+                return None;
+            }
+            Self::from_halves(first, separator, second, "")
+        })
+    }
+
+    fn from_halves(first: &str, separator: &str, second: &str, closing: &str) -> Option<Self> {
+        if first.trim().is_empty() || second.trim().is_empty() {
+            return None;
+        }
+        let first_language = half_language(first)?;
+        let second_language = half_language(second)?;
+        if primary(&first_language) == primary(&second_language) {
+            return None;
+        }
+        Some(Self {
+            first: first.to_string(),
+            first_language,
+            separator: separator.to_string(),
+            second: second.to_string(),
+            second_language,
+            closing: closing.to_string(),
+        })
+    }
+
+    /// The half in `language` and the other one, and whether the half in
+    /// `language` comes first
+    pub fn halves_for(&self, language: &str) -> Option<(&str, &str, bool)> {
+        if primary(&self.first_language) == primary(language) {
+            Some((&self.first, &self.second, true))
+        } else if primary(&self.second_language) == primary(language) {
+            Some((&self.second, &self.first, false))
+        } else {
+            None
+        }
+    }
+
+    /// Language of the half not in `language`
+    pub fn other_language(&self, language: &str) -> Option<&str> {
+        match self.halves_for(language)? {
+            (_, _, true) => Some(&self.second_language),
+            (_, _, false) => Some(&self.first_language),
+        }
+    }
+
+    /// The text with the half not in `language` replaced
+    pub fn replace_other(&self, language: &str, replacement: &str) -> Option<String> {
+        let (_, _, kept_first) = self.halves_for(language)?;
+        let (first, second) = if kept_first {
+            (self.first.as_str(), replacement)
+        } else {
+            (replacement, self.second.as_str())
+        };
+        Some(format!(
+            "{}{}{}{}",
+            first, self.separator, second, self.closing
+        ))
+    }
+}
+
+/// Language of a half
+///
+/// Short English halves such as `load config` rarely reach the confidence
+/// [`detect_language`] asks for, so an English guess is taken as is; the
+/// other half still has to be told apart with confidence.
+fn half_language(text: &str) -> Option<String> {
+    detect_language(text).or_else(|| {
+        let info = whatlang::detect(text)?;
+        (info.lang() == whatlang::Lang::Eng && text.is_ascii()).then(|| "en".to_string())
+    })
+}
+
+/// Primary subtag of a language code (`zh` of `zh-CN`)
+fn primary(code: &str) -> String {
+    code.split(['-', '_']).next().unwrap_or(code).to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_bilingual() {
+        let split = Bilingual::split("初始化配置文件 / initialize the config file").unwrap();
+        assert_eq!(split.first, "初始化配置文件");
+        assert_eq!(split.second, "initialize the config file");
+        assert_eq!(
+            split.halves_for("en").unwrap().0,
+            "initialize the config file"
+        );
+        assert_eq!(split.other_language("en"), Some("zh-CN"));
+        assert_eq!(
+            split
+                .replace_other("en", "Initialize the configuration file")
+                .as_deref(),
+            Some("Initialize the configuration file / initialize the config file")
+        );
+
+        let split = Bilingual::split("初始化配置文件（initialize the config file）").unwrap();
+        assert_eq!(split.separator, "（");
+        assert_eq!(split.closing, "）");
+        assert_eq!(
+            split.replace_other("zh", "初始化配置").as_deref(),
+            Some("初始化配置文件（初始化配置）")
+        );
+
+        // One language, or halves too short to tell
+        assert_eq!(Bilingual::split("读取配置文件 / 写入配置文件"), None);
+        assert_eq!(Bilingual::split("read the file / write the file"), None);
+        assert_eq!(Bilingual::split("a / b"), None);
+    }
+}
@@ -1,5 +1,8 @@
 use crate::types::ParseResult;
+use anyhow::{Context, Result};
 use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 /// Thread-safe cache for parse results
@@ -71,6 +74,216 @@ impl Default for Cache {
     }
 }
 
+/// Trailing characters ignored by translation memory lookups
+const TRAILING_PERIODS: [char; 3] = ['.', '。', '．'];
+
+/// Normalize text for translation memory lookups
+///
+/// Trims the text, collapses runs of whitespace within each line and drops
+/// trailing periods, so `"Compute  the sum."` and `"Compute the sum"` share
+/// an entry. Line breaks and case are kept, as a translation reused across
+/// them would carry them over.
+pub fn normalize_text(text: &str) -> String {
+    let collapsed = text
+        .trim()
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect::<Vec<_>>()
+        .join("\n");
+    collapsed
+        .trim_end_matches(TRAILING_PERIODS)
+        .trim_end()
+        .to_string()
+}
+
+/// Directory of the persistent translation cache
+///
+/// `LANGLINT_CACHE_DIR` if set, otherwise `langlint/translations` in the
+/// platform cache directory (e.g. `~/.cache` on Linux).
+pub fn default_cache_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("LANGLINT_CACHE_DIR").filter(|d| !d.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+    dirs::cache_dir().map(|dir| dir.join("langlint").join("translations"))
+}
+
+/// A remembered translation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemoryEntry {
+    pub translation: String,
+    pub confidence: f64,
+}
+
+/// Thread-safe translation memory keyed by normalized text
+///
+/// Entries are specific to a translator and language pair. A hit for text
+/// that only differs in spacing within its lines or a trailing period
+/// returns the remembered translation with the trailing period of the text
+/// looked up.
+///
+/// A memory created with [`TranslationMemory::open`] is also kept on disk,
+/// so later runs reuse earlier translations.
+pub struct TranslationMemory {
+    inner: Arc<DashMap<String, MemoryEntry>>,
+    store: Option<sled::Db>,
+}
+
+impl TranslationMemory {
+    /// Create a new empty in-memory memory
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(DashMap::new()),
+            store: None,
+        }
+    }
+
+    /// Open a memory persisted in a directory, creating it if needed
+    ///
+    /// Fails if the directory is in use by another langlint process.
+    pub fn open(dir: &Path) -> Result<Self> {
+        Self::open_with(dir, sled::Config::new())
+    }
+
+    fn open_with(dir: &Path, config: sled::Config) -> Result<Self> {
+        let store = config
+            .path(dir)
+            .open()
+            .with_context(|| format!("Failed to open translation cache: {}", dir.display()))?;
+        Ok(Self {
+            inner: Arc::new(DashMap::new()),
+            store: Some(store),
+        })
+    }
+
+    /// Check whether entries are kept on disk
+    pub fn is_persistent(&self) -> bool {
+        self.store.is_some()
+    }
+
+    /// Generate a memory key from text, language pair and translator
+    pub fn generate_key(text: &str, source: &str, target: &str, translator: &str) -> String {
+        format!(
+            "{}:{}:{}:{}",
+            translator.to_lowercase(),
+            source.to_lowercase(),
+            target.to_lowercase(),
+            normalize_text(text)
+        )
+    }
+
+    /// Look up the translation of a text
+    pub fn get(
+        &self,
+        text: &str,
+        source: &str,
+        target: &str,
+        translator: &str,
+    ) -> Option<MemoryEntry> {
+        let key = Self::generate_key(text, source, target, translator);
+        let entry = match self.inner.get(&key) {
+            Some(entry) => entry.clone(),
+            None => {
+                let entry = self.load(&key)?;
+                self.inner.insert(key, entry.clone());
+                entry
+            }
+        };
+        if line_count(&entry.translation) != line_count(text) {
+            return None;
+        }
+        Some(MemoryEntry {
+            translation: match_trailing_period(text, &entry.translation),
+            ..entry
+        })
+    }
+
+    /// Remember the translation of a text
+    pub fn insert(
+        &self,
+        text: &str,
+        source: &str,
+        target: &str,
+        translator: &str,
+        entry: MemoryEntry,
+    ) {
+        let key = Self::generate_key(text, source, target, translator);
+        if let Some(store) = &self.store {
+            // The cache is an optimization: a failed write only costs a
+            // translation in a later run
+            if let Ok(value) = serde_json::to_vec(&entry) {
+                let _ = store.insert(key.as_bytes(), value);
+            }
+        }
+        self.inner.insert(key, entry);
+    }
+
+    /// Read an entry from disk; unreadable entries count as missing
+    fn load(&self, key: &str) -> Option<MemoryEntry> {
+        let value = self.store.as_ref()?.get(key.as_bytes()).ok()??;
+        serde_json::from_slice(&value).ok()
+    }
+
+    /// Write pending entries to disk
+    pub fn flush(&self) -> Result<()> {
+        if let Some(store) = &self.store {
+            store
+                .flush()
+                .context("Failed to write the translation cache")?;
+        }
+        Ok(())
+    }
+
+    /// Clear all entries, on disk too
+    pub fn clear(&self) -> Result<()> {
+        self.inner.clear();
+        if let Some(store) = &self.store {
+            store
+                .clear()
+                .context("Failed to clear the translation cache")?;
+        }
+        self.flush()
+    }
+
+    /// Get the number of entries
+    pub fn len(&self) -> usize {
+        match &self.store {
+            Some(store) => store.len(),
+            None => self.inner.len(),
+        }
+    }
+
+    /// Check if the memory is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for TranslationMemory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Number of lines of a text, ignoring surrounding whitespace
+fn line_count(text: &str) -> usize {
+    text.trim().lines().count()
+}
+
+/// Give a translation a trailing period exactly when the text has one
+///
+/// Keeps the translation's own period (e.g. `。`) if it has one.
+fn match_trailing_period(text: &str, translation: &str) -> String {
+    let base = translation.trim_end_matches(TRAILING_PERIODS);
+    if !text.trim_end().ends_with(TRAILING_PERIODS) {
+        return base.to_string();
+    }
+    if base.len() < translation.len() {
+        return translation.to_string();
+    }
+    let period = text.trim_end().chars().last().unwrap_or('.');
+    format!("{}{}", translation, period)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -190,6 +403,89 @@ mod tests {
         assert_eq!(cache.len(), 1); // Still only 1 entry
     }
 
+    #[test]
+    fn test_normalize_text() {
+        assert_eq!(normalize_text("  Compute\tthe   sum. "), "Compute the sum");
+        assert_eq!(
+            normalize_text("One  line\n  and   another"),
+            "One line\nand another"
+        );
+        assert_eq!(normalize_text("计算总和。"), "计算总和");
+        assert_eq!(normalize_text("Loading..."), "Loading");
+        assert_eq!(normalize_text("Why?"), "Why?");
+    }
+
+    #[test]
+    fn test_translation_memory() {
+        let memory = TranslationMemory::new();
+        let entry = MemoryEntry {
+            translation: "Compute the sum.".to_string(),
+            confidence: 0.9,
+        };
+        memory.insert("计算总和。", "zh", "en", "Google", entry);
+
+        let hit = memory.get("计算总和", "zh", "en", "google").unwrap();
+        assert_eq!(hit.translation, "Compute the sum");
+        assert_eq!(hit.confidence, 0.9);
+        let hit = memory.get(" 计算总和 。", "zh", "en", "google").unwrap();
+        assert_eq!(hit.translation, "Compute the sum.");
+
+        assert!(memory.get("计算\n总和", "zh", "en", "google").is_none());
+
+        assert!(memory.get("计算总和", "zh", "ja", "google").is_none());
+        assert!(memory.get("计算总和", "zh", "en", "openai").is_none());
+        assert_eq!(memory.len(), 1);
+    }
+
+    #[test]
+    fn test_translation_memory_keeps_line_count() {
+        let memory = TranslationMemory::new();
+        let entry = MemoryEntry {
+            translation: "First line\nSecond line\nThird line".to_string(),
+            confidence: 0.9,
+        };
+        memory.insert("第一行\n第二行", "zh", "en", "google", entry);
+
+        // The translator added a line, so the entry is not reused
+        assert!(memory.get("第一行\n第二行", "zh", "en", "google").is_none());
+    }
+
+    #[test]
+    fn test_persistent_translation_memory() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry = MemoryEntry {
+            translation: "Compute the sum".to_string(),
+            confidence: 0.9,
+        };
+
+        // Without sled's background flusher, whose thread keeps the
+        // directory locked for a while after the memory is dropped
+        let open =
+            || TranslationMemory::open_with(dir.path(), sled::Config::new().flush_every_ms(None));
+        {
+            let memory = open().unwrap();
+            assert!(memory.is_persistent());
+            memory.insert("计算总和", "zh", "en", "google", entry.clone());
+            memory.flush().unwrap();
+        }
+
+        let memory = open().unwrap();
+        assert_eq!(memory.len(), 1);
+        assert_eq!(memory.get("计算总和", "zh", "en", "google"), Some(entry));
+        assert!(memory.get("计算总和", "zh", "en", "openai").is_none());
+
+        memory.clear().unwrap();
+        assert!(memory.is_empty());
+        assert!(memory.get("计算总和", "zh", "en", "google").is_none());
+    }
+
+    #[test]
+    fn test_match_trailing_period() {
+        assert_eq!(match_trailing_period("Sum.", "合计"), "合计.");
+        assert_eq!(match_trailing_period("Sum.", "合计。"), "合计。");
+        assert_eq!(match_trailing_period("Sum", "合计。"), "合计");
+    }
+
     #[test]
     fn test_cache_default() {
         let cache = Cache::default();
@@ -1,8 +1,174 @@
 use anyhow::{Context, Result};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+/// Directory names skipped when walking a tree, unless overridden
+///
+/// Hidden directories (`.git`, `.venv`, ...) are always skipped as well.
+pub const DEFAULT_EXCLUDED_DIRS: &[&str] = &[
+    "node_modules",
+    "target",
+    "__pycache__",
+    "venv",
+    "build",
+    "dist",
+    "htmlcov",
+    "demo_files",
+    "examples",
+    "figures",
+    "submission_patterns",
+];
+
+/// Rate limit for one translator backend
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests_per_minute: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub characters_per_minute: Option<u32>,
+}
+
+/// Retries of failed translation requests; unset fields keep the defaults
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetryConfig {
+    /// Attempts including the first
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_attempts: Option<u32>,
+    /// Delay before the first retry, doubled for each further one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_delay_ms: Option<u64>,
+    /// Longest delay between attempts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_delay_ms: Option<u64>,
+    /// Randomize delays so concurrent retries spread out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jitter: Option<bool>,
+    /// Wait as long as a rate-limited reply's `Retry-After` asks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub honor_retry_after: Option<bool>,
+}
+
+/// Limits on what one run may send to paid translators
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct BudgetConfig {
+    /// Estimated cost in US dollars
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_cost: Option<f64>,
+    /// Characters sent to translators
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_characters: Option<usize>,
+}
+
+/// Units and regions of them spelled out in Latin letters instead of
+/// translated, e.g. names and identifiers written in prose
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransliterateConfig {
+    /// `pinyin`, `romaji`, `iso9` or `ascii`
+    pub scheme: String,
+    /// Regexes of regions to transliterate
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub patterns: Vec<String>,
+    /// Unit types transliterated whole (`string_literal`, `metadata`, ...)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unit_types: Vec<String>,
+}
+
+/// Regions of a text kept verbatim when translating
+///
+/// Format placeholders are always protected; these switch the heuristics
+/// for other regions, and `patterns` adds regexes of their own.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtectConfig {
+    /// Backtick code spans: `` `x` ``, ``` ``x`` ```
+    #[serde(default = "default_true")]
+    pub code_spans: bool,
+    /// URLs with a scheme: `https://example.com/a?b=1`
+    #[serde(default = "default_true")]
+    pub urls: bool,
+    /// File paths: `/etc/hosts`, `./build`, `src/io.py`, `C:\temp`
+    #[serde(default = "default_true")]
+    pub paths: bool,
+    /// Identifiers: `snake_case`, `camelCase`, `os.path.join`, `run()`
+    #[serde(default = "default_true")]
+    pub identifiers: bool,
+    /// Additional regexes of regions to protect
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub patterns: Vec<String>,
+}
+
+impl Default for ProtectConfig {
+    fn default() -> Self {
+        Self {
+            code_spans: true,
+            urls: true,
+            paths: true,
+            identifiers: true,
+            patterns: Vec::new(),
+        }
+    }
+}
+
+/// Translation memory shared by a team through a remote backend
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SharedMemoryConfig {
+    /// `redis://host:6379/0` or the base URL of an HTTP memory service
+    pub url: String,
+    /// Only look translations up, e.g. on developer machines
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+/// Files skipped when discovered, for being too large or holding too many
+/// units, e.g. `{ max_units = 5000 }` or
+/// `{ files = ["vendor/**"], max_size = "1MB" }`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileLimit {
+    /// File globs the limit applies to; all files when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<String>,
+    /// Largest size in bytes, also written `500KB` or `1MB` (1024-based)
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_size"
+    )]
+    pub max_size: Option<u64>,
+    /// Most translatable units
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_units: Option<usize>,
+}
+
+/// A language rule checked by `langlint check`
+///
+/// Either limits the languages units may be written in, or requires every
+/// matching file to exist in several languages (`README.md` and
+/// `README.zh.md`, `docs/en/x.md` and `docs/zh/x.md`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyRule {
+    /// Name shown with violations (default: `policy[<index>]`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Unit types checked (`comment`, `docstring`, `text_node`, ...); all
+    /// when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub units: Vec<String>,
+    /// File globs checked; all files when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<String>,
+    /// Languages units may be written in
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub languages: Vec<String>,
+    /// Languages every matching file must exist in
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub require_translations: Vec<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
 /// Langlint configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -18,7 +184,12 @@ pub struct Config {
     #[serde(default = "default_target_lang")]
     pub target_lang: String,
 
-    #[serde(default = "default_translator")]
+    /// Translator name, or a comma-separated fallback chain such as
+    /// `deepl,google,mock` (also accepted as a list in config files)
+    #[serde(
+        default = "default_translator",
+        deserialize_with = "deserialize_translator"
+    )]
     pub translator: String,
 
     #[serde(default)]
@@ -26,6 +197,139 @@ pub struct Config {
 
     #[serde(default = "default_backup")]
     pub backup: bool,
+
+    /// Translator per language pair, e.g. `"zh->en" = "google"`, `"*" = "mock"`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub routes: BTreeMap<String, String>,
+
+    /// Translate license/copyright headers at the top of files
+    #[serde(default)]
+    pub include_license_headers: bool,
+
+    /// Translate files marked as generated (`@generated`, `DO NOT EDIT`)
+    #[serde(default)]
+    pub include_generated: bool,
+
+    /// Notebook cells to translate: `markdown`, `code` or both (the default)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notebook_cells: Vec<String>,
+
+    /// Unit types extracted besides comments and docstrings, e.g.
+    /// `["string_literal"]` for strings passed to print, logging and
+    /// exceptions (like `--include-strings`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unit_types: Vec<String>,
+
+    /// Directory names skipped in addition to [`DEFAULT_EXCLUDED_DIRS`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extend_default_excludes: Vec<String>,
+
+    /// Directory names skipped instead of [`DEFAULT_EXCLUDED_DIRS`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub override_excludes: Option<Vec<String>>,
+
+    /// Rate limits per translator, e.g. `google = { requests_per_minute = 60 }`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub rate_limits: BTreeMap<String, RateLimitConfig>,
+
+    /// Retry policy of all translators, e.g. `retry = { max_attempts = 5 }`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryConfig>,
+
+    /// API keys per translator, e.g. `openai = "sk-..."`; prefer environment
+    /// variables or the OS keyring for keys in shared files
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub api_keys: BTreeMap<String, String>,
+
+    /// Look up API keys found nowhere else in the OS keyring
+    #[serde(default)]
+    pub keyring: bool,
+
+    /// Proxy for translator requests, e.g. `http://proxy.corp.example:3128`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+
+    /// Comma-separated hosts reached without the proxy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_proxy: Option<String>,
+
+    /// PEM file of extra root certificates trusted by translator requests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_bundle: Option<String>,
+
+    /// Units translated below this confidence keep their original text and
+    /// are listed for review
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_confidence: Option<f64>,
+
+    /// Translator retrying units below `min_confidence`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secondary_translator: Option<String>,
+
+    /// Translators queried together for high-priority units, keeping the
+    /// translation they agree on
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ensemble: Vec<String>,
+
+    /// Language bridging pairs a translator does not support directly
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pivot_language: Option<String>,
+
+    /// Handling of comments written in two languages: `translate`,
+    /// `keep-target` or `translate-foreign`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bilingual: Option<String>,
+
+    /// Handling of control, bidi and invisible characters translations
+    /// introduce: `allow`, `strip` or `reject`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unsafe_characters: Option<String>,
+
+    /// Names and units transliterated instead of translated, e.g.
+    /// `transliterate = { scheme = "pinyin", patterns = ["张伟"] }`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transliterate: Option<TransliterateConfig>,
+
+    /// Per-run budget, e.g. `budget = { max_cost = 5.0 }`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<BudgetConfig>,
+
+    /// Glossary file (`.toml` or `.csv`) of required term translations
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub glossary: Option<String>,
+
+    /// Parser per file glob, e.g. `"*.tpl" = "html"`, `"Jenkinsfile" = "generic:groovy"`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub parsers: BTreeMap<String, String>,
+
+    /// Skip units already detected in the target language
+    #[serde(default = "default_true")]
+    pub skip_target_language: bool,
+
+    /// Remote translation memory shared across runs and machines
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shared_memory: Option<SharedMemoryConfig>,
+
+    /// Code spans, URLs, paths and identifiers kept verbatim when translating
+    #[serde(default, skip_serializing_if = "is_default_protect")]
+    pub protect: ProtectConfig,
+
+    /// Pieces units are translated in per unit type: `whole`, `paragraph` or
+    /// `sentence`, e.g. `docstring = "sentence"`, `"*" = "paragraph"`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub segmentation: BTreeMap<String, String>,
+
+    /// Files skipped for their size or unit count (`[[file_limits]]` tables)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub file_limits: Vec<FileLimit>,
+
+    /// Language rules checked by `langlint check` (`[[policy]]` tables)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub policy: Vec<PolicyRule>,
+}
+
+fn is_default_protect(protect: &ProtectConfig) -> bool {
+    *protect == ProtectConfig::default()
 }
 
 fn default_source_lang() -> Vec<String> {
@@ -40,10 +344,89 @@ fn default_translator() -> String {
     "google".to_string()
 }
 
+/// Accept a translator name or a list of names forming a fallback chain
+fn deserialize_translator<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NameOrChain {
+        Name(String),
+        Chain(Vec<String>),
+    }
+
+    Ok(match NameOrChain::deserialize(deserializer)? {
+        NameOrChain::Name(name) => name,
+        NameOrChain::Chain(chain) => chain.join(","),
+    })
+}
+
+/// Accept a byte count or a size with a unit: `1048576`, `500KB`, `1 MB`
+fn deserialize_size<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum BytesOrText {
+        Bytes(u64),
+        Text(String),
+    }
+
+    match BytesOrText::deserialize(deserializer)? {
+        BytesOrText::Bytes(bytes) => Ok(Some(bytes)),
+        BytesOrText::Text(text) => parse_size(&text)
+            .map(Some)
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid size '{}'", text))),
+    }
+}
+
+/// Parse a size such as `1MB`, `1.5 GiB` or `2048`
+pub fn parse_size(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let factor: u64 = match unit.trim().to_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1 << 10,
+        "m" | "mb" | "mib" => 1 << 20,
+        "g" | "gb" | "gib" => 1 << 30,
+        _ => return None,
+    };
+    Some((number * factor as f64) as u64)
+}
+
 fn default_backup() -> bool {
     true
 }
 
+/// Split a comma-separated environment value into trimmed, non-empty items
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Parse the boolean value of environment variable `key`
+fn parse_bool(key: &str, value: &str) -> Result<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => anyhow::bail!(
+            "Invalid value '{}' for {}: expected 1/0, true/false, yes/no or on/off",
+            value.trim(),
+            key
+        ),
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
@@ -54,6 +437,36 @@ impl Default for Config {
             translator: default_translator(),
             dry_run: false,
             backup: default_backup(),
+            routes: BTreeMap::new(),
+            include_license_headers: false,
+            include_generated: false,
+            notebook_cells: Vec::new(),
+            unit_types: Vec::new(),
+            extend_default_excludes: Vec::new(),
+            override_excludes: None,
+            rate_limits: BTreeMap::new(),
+            retry: None,
+            api_keys: BTreeMap::new(),
+            keyring: false,
+            proxy: None,
+            no_proxy: None,
+            ca_bundle: None,
+            min_confidence: None,
+            secondary_translator: None,
+            ensemble: Vec::new(),
+            pivot_language: None,
+            bilingual: None,
+            unsafe_characters: None,
+            transliterate: None,
+            budget: None,
+            glossary: None,
+            parsers: BTreeMap::new(),
+            skip_target_language: true,
+            shared_memory: None,
+            protect: ProtectConfig::default(),
+            segmentation: BTreeMap::new(),
+            file_limits: Vec::new(),
+            policy: Vec::new(),
         }
     }
 }
@@ -90,11 +503,12 @@ impl Config {
         for config_file in &config_files {
             let path = PathBuf::from(config_file);
             if path.exists() {
-                if config_file == &"pyproject.toml" {
-                    return Self::load_from_pyproject(&path);
+                let config = if config_file == &"pyproject.toml" {
+                    Self::load_from_pyproject(&path)
                 } else {
-                    return Self::load_from_file(&path);
-                }
+                    Self::load_from_file(&path)
+                };
+                return config.with_context(|| format!("Invalid config file: {}", path.display()));
             }
         }
 
@@ -102,6 +516,81 @@ impl Config {
         Ok(Self::default())
     }
 
+    /// Find the project configuration and layer `LANGLINT_*` environment
+    /// variables on top of it
+    ///
+    /// CLI flags are expected to be applied by the caller afterwards, giving
+    /// the precedence order: defaults < config file < environment < CLI.
+    pub fn load() -> Result<Self> {
+        Self::find_and_load()?.apply_env()
+    }
+
+    /// Apply overrides from `LANGLINT_*` environment variables
+    pub fn apply_env(self) -> Result<Self> {
+        self.apply_env_from(|key| std::env::var(key).ok())
+    }
+
+    /// Apply overrides using a custom variable lookup
+    ///
+    /// Supported variables:
+    /// * `LANGLINT_TRANSLATOR` - translator name or comma-separated fallback chain
+    /// * `LANGLINT_TARGET_LANG` - target language code
+    /// * `LANGLINT_SOURCE_LANG` - comma-separated source language codes
+    /// * `LANGLINT_INCLUDE` / `LANGLINT_EXCLUDE` - comma-separated patterns
+    /// * `LANGLINT_GLOSSARY` - glossary file path
+    /// * `LANGLINT_SHARED_MEMORY` - shared translation memory URL
+    /// * `LANGLINT_DRY_RUN` / `LANGLINT_BACKUP` / `LANGLINT_INCLUDE_LICENSE_HEADERS` /
+    ///   `LANGLINT_INCLUDE_GENERATED` - booleans (`1`/`0`, `true`/`false`, `yes`/`no`,
+    ///   `on`/`off`)
+    ///
+    /// Empty values are ignored. Fails on a boolean that cannot be parsed.
+    pub fn apply_env_from<F>(mut self, lookup: F) -> Result<Self>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let get = |key: &str| lookup(key).filter(|v| !v.trim().is_empty());
+        let get_bool = |key: &str| get(key).map(|v| parse_bool(key, &v)).transpose();
+
+        if let Some(translator) = get("LANGLINT_TRANSLATOR") {
+            self.translator = translator.trim().to_string();
+        }
+        if let Some(target) = get("LANGLINT_TARGET_LANG") {
+            self.target_lang = target.trim().to_string();
+        }
+        if let Some(source) = get("LANGLINT_SOURCE_LANG") {
+            self.source_lang = split_list(&source);
+        }
+        if let Some(include) = get("LANGLINT_INCLUDE") {
+            self.include = split_list(&include);
+        }
+        if let Some(exclude) = get("LANGLINT_EXCLUDE") {
+            self.exclude = split_list(&exclude);
+        }
+        if let Some(glossary) = get("LANGLINT_GLOSSARY") {
+            self.glossary = Some(glossary.trim().to_string());
+        }
+        if let Some(url) = get("LANGLINT_SHARED_MEMORY") {
+            let read_only = self.shared_memory.as_ref().is_some_and(|m| m.read_only);
+            self.shared_memory = Some(SharedMemoryConfig {
+                url: url.trim().to_string(),
+                read_only,
+            });
+        }
+        if let Some(dry_run) = get_bool("LANGLINT_DRY_RUN")? {
+            self.dry_run = dry_run;
+        }
+        if let Some(backup) = get_bool("LANGLINT_BACKUP")? {
+            self.backup = backup;
+        }
+        if let Some(include) = get_bool("LANGLINT_INCLUDE_LICENSE_HEADERS")? {
+            self.include_license_headers = include;
+        }
+        if let Some(include) = get_bool("LANGLINT_INCLUDE_GENERATED")? {
+            self.include_generated = include;
+        }
+        Ok(self)
+    }
+
     /// Load configuration from pyproject.toml [tool.langlint] section
     fn load_from_pyproject(path: &Path) -> Result<Self> {
         let content = fs::read_to_string(path)?;
@@ -141,8 +630,120 @@ impl Config {
         if other.backup != default_backup() {
             self.backup = other.backup;
         }
+        if !other.routes.is_empty() {
+            self.routes = other.routes;
+        }
+        if other.include_license_headers {
+            self.include_license_headers = true;
+        }
+        if other.include_generated {
+            self.include_generated = true;
+        }
+        if !other.notebook_cells.is_empty() {
+            self.notebook_cells = other.notebook_cells;
+        }
+        if !other.unit_types.is_empty() {
+            self.unit_types = other.unit_types;
+        }
+        if !other.extend_default_excludes.is_empty() {
+            self.extend_default_excludes = other.extend_default_excludes;
+        }
+        if other.override_excludes.is_some() {
+            self.override_excludes = other.override_excludes;
+        }
+        self.rate_limits.extend(other.rate_limits);
+        if other.retry.is_some() {
+            self.retry = other.retry;
+        }
+        self.api_keys.extend(other.api_keys);
+        if other.keyring {
+            self.keyring = true;
+        }
+        if other.proxy.is_some() {
+            self.proxy = other.proxy;
+        }
+        if other.no_proxy.is_some() {
+            self.no_proxy = other.no_proxy;
+        }
+        if other.ca_bundle.is_some() {
+            self.ca_bundle = other.ca_bundle;
+        }
+        if other.min_confidence.is_some() {
+            self.min_confidence = other.min_confidence;
+        }
+        if other.secondary_translator.is_some() {
+            self.secondary_translator = other.secondary_translator;
+        }
+        if !other.ensemble.is_empty() {
+            self.ensemble = other.ensemble;
+        }
+        if other.pivot_language.is_some() {
+            self.pivot_language = other.pivot_language;
+        }
+        if other.bilingual.is_some() {
+            self.bilingual = other.bilingual;
+        }
+        if other.unsafe_characters.is_some() {
+            self.unsafe_characters = other.unsafe_characters;
+        }
+        if other.transliterate.is_some() {
+            self.transliterate = other.transliterate;
+        }
+        if other.budget.is_some() {
+            self.budget = other.budget;
+        }
+        if other.glossary.is_some() {
+            self.glossary = other.glossary;
+        }
+        self.parsers.extend(other.parsers);
+        if !other.skip_target_language {
+            self.skip_target_language = false;
+        }
+        if other.shared_memory.is_some() {
+            self.shared_memory = other.shared_memory;
+        }
+        if !is_default_protect(&other.protect) {
+            self.protect = other.protect;
+        }
+        self.segmentation.extend(other.segmentation);
+        if !other.file_limits.is_empty() {
+            self.file_limits = other.file_limits;
+        }
+        if !other.policy.is_empty() {
+            self.policy = other.policy;
+        }
         self
     }
+
+    /// Directory names to skip when walking a tree
+    ///
+    /// `override_excludes` replaces the defaults (an empty list skips only
+    /// hidden directories); `extend_default_excludes` adds to either.
+    pub fn excluded_dirs(&self) -> Vec<String> {
+        let mut dirs: Vec<String> = match &self.override_excludes {
+            Some(dirs) => dirs.clone(),
+            None => DEFAULT_EXCLUDED_DIRS
+                .iter()
+                .map(|d| d.to_string())
+                .collect(),
+        };
+        for dir in &self.extend_default_excludes {
+            if !dirs.contains(dir) {
+                dirs.push(dir.clone());
+            }
+        }
+        dirs
+    }
+
+    /// Whether `unit_types` asks for user-facing string literals
+    pub fn includes_string_literals(&self) -> bool {
+        self.unit_types.iter().any(|unit_type| {
+            matches!(
+                unit_type.to_lowercase().replace(['_', '-'], "").as_str(),
+                "string" | "strings" | "stringliteral"
+            )
+        })
+    }
 }
 
 #[cfg(test)]
@@ -233,6 +834,7 @@ mod tests {
             backup: false,
             include: vec!["*.py".to_string()],
             exclude: vec!["test_*.py".to_string()],
+            ..Config::default()
         };
 
         let toml_str = toml::to_string(&config).unwrap();
@@ -291,6 +893,192 @@ backup = true
         assert!(config.backup);
     }
 
+    #[test]
+    fn test_load_translator_chain() {
+        let temp_dir = TempDir::new().unwrap();
+        let toml_path = temp_dir.path().join("test.toml");
+        fs::write(&toml_path, r#"translator = ["deepl", "google", "mock"]"#).unwrap();
+        assert_eq!(
+            Config::load_from_file(&toml_path).unwrap().translator,
+            "deepl,google,mock"
+        );
+
+        let yaml_path = temp_dir.path().join("test.yml");
+        fs::write(&yaml_path, "translator: [google, mock]\n").unwrap();
+        assert_eq!(
+            Config::load_from_file(&yaml_path).unwrap().translator,
+            "google,mock"
+        );
+    }
+
+    #[test]
+    fn test_excluded_dirs() {
+        let config = Config::default();
+        assert!(config.excluded_dirs().contains(&"examples".to_string()));
+
+        let yaml = "extend_default_excludes: [fixtures]\n";
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        let dirs = config.excluded_dirs();
+        assert!(dirs.contains(&"node_modules".to_string()));
+        assert!(dirs.contains(&"fixtures".to_string()));
+
+        let toml = r#"override_excludes = ["node_modules", "target"]"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(config.excluded_dirs(), vec!["node_modules", "target"]);
+
+        let merged = Config::default().merge(config);
+        assert!(!merged.excluded_dirs().contains(&"examples".to_string()));
+    }
+
+    #[test]
+    fn test_load_rate_limits_and_retry() {
+        let toml = r#"
+retry = { max_attempts = 5, honor_retry_after = false }
+proxy = "http://proxy.corp.example:3128"
+keyring = true
+ca_bundle = "/etc/ssl/corp-ca.pem"
+min_confidence = 0.6
+secondary_translator = "openai"
+ensemble = ["google", "openai", "papago"]
+pivot_language = "en"
+bilingual = "keep-target"
+unsafe_characters = "reject"
+transliterate = { scheme = "romaji", unit_types = ["metadata"] }
+budget = { max_characters = 500000 }
+
+[rate_limits]
+google = { requests_per_minute = 60 }
+openai = { requests_per_minute = 500, characters_per_minute = 200000 }
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(
+            config.rate_limits["google"],
+            RateLimitConfig {
+                requests_per_minute: Some(60),
+                characters_per_minute: None,
+            }
+        );
+        assert_eq!(
+            config.rate_limits["openai"].characters_per_minute,
+            Some(200_000)
+        );
+        assert_eq!(
+            config.retry,
+            Some(RetryConfig {
+                max_attempts: Some(5),
+                honor_retry_after: Some(false),
+                ..RetryConfig::default()
+            })
+        );
+        assert_eq!(
+            config.proxy.as_deref(),
+            Some("http://proxy.corp.example:3128")
+        );
+        assert_eq!(config.ca_bundle.as_deref(), Some("/etc/ssl/corp-ca.pem"));
+        assert_eq!(config.no_proxy, None);
+        assert!(config.keyring);
+        assert_eq!(config.min_confidence, Some(0.6));
+        assert_eq!(config.secondary_translator.as_deref(), Some("openai"));
+        assert_eq!(config.ensemble, vec!["google", "openai", "papago"]);
+        assert_eq!(config.pivot_language.as_deref(), Some("en"));
+        assert_eq!(config.bilingual.as_deref(), Some("keep-target"));
+        assert_eq!(config.unsafe_characters.as_deref(), Some("reject"));
+        assert_eq!(
+            config.transliterate,
+            Some(TransliterateConfig {
+                scheme: "romaji".to_string(),
+                patterns: Vec::new(),
+                unit_types: vec!["metadata".to_string()],
+            })
+        );
+        assert!(config.api_keys.is_empty());
+        assert_eq!(
+            config.budget,
+            Some(BudgetConfig {
+                max_cost: None,
+                max_characters: Some(500_000),
+            })
+        );
+    }
+
+    #[test]
+    fn test_load_parser_overrides() {
+        let toml = r#"
+notebook_cells = ["markdown"]
+unit_types = ["string_literal"]
+
+[parsers]
+"*.tpl" = "html"
+"Jenkinsfile" = "generic:groovy"
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(config.parsers["*.tpl"], "html");
+        assert_eq!(config.notebook_cells, vec!["markdown"]);
+        assert!(config.includes_string_literals());
+        assert!(!Config::default().includes_string_literals());
+
+        let mut other = Config::default();
+        other
+            .parsers
+            .insert("*.tpl".to_string(), "generic".to_string());
+        let merged = config.merge(other);
+        assert_eq!(merged.parsers["*.tpl"], "generic");
+        assert_eq!(merged.parsers["Jenkinsfile"], "generic:groovy");
+        assert_eq!(merged.notebook_cells, vec!["markdown"]);
+    }
+
+    #[test]
+    fn test_load_file_limits() {
+        let toml = r#"
+[[file_limits]]
+max_units = 5000
+
+[[file_limits]]
+files = ["vendor/**"]
+max_size = "1MB"
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(
+            config.file_limits,
+            vec![
+                FileLimit {
+                    max_units: Some(5000),
+                    ..FileLimit::default()
+                },
+                FileLimit {
+                    files: vec!["vendor/**".to_string()],
+                    max_size: Some(1 << 20),
+                    max_units: None,
+                },
+            ]
+        );
+        assert_eq!(parse_size("2048"), Some(2048));
+        assert_eq!(parse_size("1.5 kB"), Some(1536));
+        assert_eq!(parse_size("3 parsecs"), None);
+        assert!(toml::from_str::<Config>("[[file_limits]]\nmax_size = \"big\"").is_err());
+    }
+
+    #[test]
+    fn test_load_routes_from_yaml() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("test.yml");
+
+        let yaml_content = r#"
+translator: google
+routes:
+  "zh->en": google
+  "ja->en": mock
+  "*": mock
+"#;
+
+        fs::write(&config_path, yaml_content).unwrap();
+
+        let config = Config::load_from_file(&config_path).unwrap();
+        assert_eq!(config.routes.len(), 3);
+        assert_eq!(config.routes.get("zh->en").unwrap(), "google");
+        assert_eq!(config.routes.get("*").unwrap(), "mock");
+    }
+
     #[test]
     fn test_load_unsupported_format() {
         let temp_dir = TempDir::new().unwrap();
@@ -332,6 +1120,71 @@ backup = true
         assert!(default_backup());
     }
 
+    #[test]
+    fn test_apply_env_overrides() {
+        let env: std::collections::HashMap<&str, &str> = [
+            ("LANGLINT_TRANSLATOR", "mock"),
+            ("LANGLINT_TARGET_LANG", "ja"),
+            ("LANGLINT_SOURCE_LANG", "zh, ko"),
+            ("LANGLINT_EXCLUDE", "vendor,,generated "),
+            ("LANGLINT_GLOSSARY", "terms.csv"),
+            ("LANGLINT_SHARED_MEMORY", "redis://cache:6379/0"),
+            ("LANGLINT_DRY_RUN", "yes"),
+            ("LANGLINT_BACKUP", "0"),
+            ("LANGLINT_INCLUDE_GENERATED", "true"),
+        ]
+        .into_iter()
+        .collect();
+
+        let config = Config::default()
+            .apply_env_from(|key| env.get(key).map(|v| v.to_string()))
+            .unwrap();
+
+        assert_eq!(config.translator, "mock");
+        assert_eq!(config.target_lang, "ja");
+        assert_eq!(config.source_lang, vec!["zh", "ko"]);
+        assert_eq!(config.exclude, vec!["vendor", "generated"]);
+        assert_eq!(config.glossary.as_deref(), Some("terms.csv"));
+        assert_eq!(
+            config.shared_memory.map(|m| m.url).as_deref(),
+            Some("redis://cache:6379/0")
+        );
+        assert!(config.include.is_empty());
+        assert!(config.dry_run);
+        assert!(!config.backup);
+        assert!(config.include_generated);
+    }
+
+    #[test]
+    fn test_apply_env_ignores_empty() {
+        let base = Config {
+            translator: "google".to_string(),
+            backup: true,
+            ..Config::default()
+        };
+
+        let config = base
+            .apply_env_from(|key| match key {
+                "LANGLINT_TRANSLATOR" => Some("   ".to_string()),
+                "LANGLINT_BACKUP" => Some(String::new()),
+                _ => None,
+            })
+            .unwrap();
+
+        assert_eq!(config.translator, "google");
+        assert!(config.backup);
+    }
+
+    #[test]
+    fn test_apply_env_rejects_invalid_bool() {
+        let error = Config::default()
+            .apply_env_from(|key| (key == "LANGLINT_DRY_RUN").then(|| "ture".to_string()))
+            .unwrap_err();
+        let message = error.to_string();
+        assert!(message.contains("LANGLINT_DRY_RUN"), "{}", message);
+        assert!(message.contains("'ture'"), "{}", message);
+    }
+
     #[test]
     fn test_config_clone() {
         let config = Config::default();
@@ -0,0 +1,242 @@
+//! Source file encoding detection and conversion
+//!
+//! Files are decoded by BOM, then as UTF-8 if valid, and otherwise by a
+//! statistical guess (GBK, Shift_JIS, EUC-KR, windows-1252...). Translated
+//! content is written back in the detected encoding unless an output
+//! encoding is requested.
+
+use crate::paths;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+pub use encoding_rs::Encoding;
+
+/// Text encoding of a file, including whether it starts with a BOM
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileEncoding {
+    pub encoding: &'static Encoding,
+    pub bom: bool,
+}
+
+impl FileEncoding {
+    /// Plain UTF-8 without BOM
+    pub const UTF8: FileEncoding = FileEncoding {
+        encoding: encoding_rs::UTF_8,
+        bom: false,
+    };
+
+    /// Look up an encoding by WHATWG label (`utf-8`, `gbk`, `shift_jis`...)
+    ///
+    /// `utf-8-sig` selects UTF-8 with a BOM.
+    pub fn for_label(label: &str) -> Result<Self> {
+        let label = label.trim();
+        if label.eq_ignore_ascii_case("utf-8-sig") || label.eq_ignore_ascii_case("utf8-sig") {
+            return Ok(Self {
+                encoding: encoding_rs::UTF_8,
+                bom: true,
+            });
+        }
+
+        let encoding = Encoding::for_label(label.as_bytes())
+            .with_context(|| format!("Unknown encoding: {}", label))?;
+        Ok(Self {
+            encoding,
+            bom: false,
+        })
+    }
+
+    /// Canonical encoding name, with `-sig` for UTF-8 with BOM
+    pub fn name(&self) -> String {
+        if self.bom && self.encoding == encoding_rs::UTF_8 {
+            "UTF-8-sig".to_string()
+        } else {
+            self.encoding.name().to_string()
+        }
+    }
+
+    /// Encode text, failing if a character cannot be represented
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>> {
+        let utf16 = |big_endian: bool| -> Vec<u8> {
+            let bom: &[u8] = match (self.bom, big_endian) {
+                (false, _) => &[],
+                (true, false) => &[0xFF, 0xFE],
+                (true, true) => &[0xFE, 0xFF],
+            };
+            let mut bytes = bom.to_vec();
+            for unit in text.encode_utf16() {
+                let pair = if big_endian {
+                    unit.to_be_bytes()
+                } else {
+                    unit.to_le_bytes()
+                };
+                bytes.extend_from_slice(&pair);
+            }
+            bytes
+        };
+
+        // encoding_rs only decodes UTF-16, so encode it by hand
+        if self.encoding == encoding_rs::UTF_16LE {
+            return Ok(utf16(false));
+        }
+        if self.encoding == encoding_rs::UTF_16BE {
+            return Ok(utf16(true));
+        }
+
+        let (bytes, _, unmappable) = self.encoding.encode(text);
+        if unmappable {
+            let offending = text
+                .chars()
+                .find(|c| {
+                    let mut buf = [0u8; 4];
+                    self.encoding.encode(c.encode_utf8(&mut buf)).2
+                })
+                .unwrap_or('?');
+            anyhow::bail!(
+                "Text cannot be written as {}: no mapping for {:?}",
+                self.name(),
+                offending
+            );
+        }
+
+        let mut output = Vec::with_capacity(bytes.len() + 3);
+        if self.bom && self.encoding == encoding_rs::UTF_8 {
+            output.extend_from_slice(&[0xEF, 0xBB, 0xBF]);
+        }
+        output.extend_from_slice(&bytes);
+        Ok(output)
+    }
+}
+
+impl Default for FileEncoding {
+    fn default() -> Self {
+        Self::UTF8
+    }
+}
+
+/// Detect the encoding of raw file contents
+pub fn detect(bytes: &[u8]) -> FileEncoding {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return FileEncoding {
+            encoding,
+            bom: true,
+        };
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        return FileEncoding::UTF8;
+    }
+
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(bytes, true);
+    FileEncoding {
+        encoding: detector.guess(None, true),
+        bom: false,
+    }
+}
+
+/// Decode raw file contents, detecting their encoding (BOM is stripped)
+pub fn decode(bytes: &[u8]) -> (String, FileEncoding) {
+    let detected = detect(bytes);
+    let (text, _) = detected.encoding.decode_with_bom_removal(bytes);
+    (text.into_owned(), detected)
+}
+
+/// Read a text file in any supported encoding
+pub fn read_file(path: &Path) -> Result<(String, FileEncoding)> {
+    let bytes = fs::read(paths::long_path(path))
+        .with_context(|| format!("Failed to read file: {}", path.display()))?;
+    Ok(decode(&bytes))
+}
+
+/// Write a text file in the given encoding
+pub fn write_file(path: &Path, text: &str, encoding: FileEncoding) -> Result<()> {
+    if cfg!(windows) {
+        if let Some(name) = paths::reserved_component(path) {
+            anyhow::bail!(
+                "Cannot write {}: '{}' is a reserved file name on Windows",
+                path.display(),
+                name
+            );
+        }
+    }
+    let bytes = encoding
+        .encode(text)
+        .with_context(|| format!("Failed to encode: {}", path.display()))?;
+
+    // Written next to the file and renamed over it, so the file is never
+    // left half-written; a symlink is written through to its target
+    let target = match fs::symlink_metadata(paths::long_path(path)) {
+        Ok(meta) if meta.file_type().is_symlink() => fs::canonicalize(path)?,
+        _ => path.to_path_buf(),
+    };
+    let mut temp_name = target.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".langlint-tmp");
+    let temp = target.with_file_name(temp_name);
+    let written = fs::write(paths::long_path(&temp), bytes).and_then(|()| {
+        if let Ok(meta) = fs::metadata(paths::long_path(&target)) {
+            fs::set_permissions(paths::long_path(&temp), meta.permissions())?;
+        }
+        fs::rename(paths::long_path(&temp), paths::long_path(&target))
+    });
+    if written.is_err() {
+        let _ = fs::remove_file(paths::long_path(&temp));
+    }
+    written.with_context(|| format!("Failed to write to: {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_utf8_and_bom() {
+        assert_eq!(detect("# 你好".as_bytes()), FileEncoding::UTF8);
+
+        let with_bom = [&[0xEF, 0xBB, 0xBF][..], "x".as_bytes()].concat();
+        let (text, encoding) = decode(&with_bom);
+        assert_eq!(text, "x");
+        assert!(encoding.bom);
+        assert_eq!(encoding.name(), "UTF-8-sig");
+        assert_eq!(encoding.encode("x").unwrap(), with_bom);
+    }
+
+    #[test]
+    fn test_gbk_round_trip() {
+        let gbk = FileEncoding::for_label("gbk").unwrap();
+        let text = "# 这是一个用于检测编码的中文注释，包含足够多的汉字。\nx = 1\n";
+        let bytes = gbk.encode(text).unwrap();
+        assert!(std::str::from_utf8(&bytes).is_err());
+
+        let (decoded, detected) = decode(&bytes);
+        assert_eq!(decoded, text);
+        assert_eq!(detected.encoding, encoding_rs::GBK);
+    }
+
+    #[test]
+    fn test_unmappable_character_is_an_error() {
+        let latin1 = FileEncoding::for_label("latin1").unwrap();
+        let error = latin1.encode("café 中").unwrap_err();
+        assert!(error.to_string().contains("'中'"));
+        assert!(latin1.encode("café").is_ok());
+    }
+
+    #[test]
+    fn test_utf16_output() {
+        let utf16 = FileEncoding {
+            encoding: encoding_rs::UTF_16LE,
+            bom: true,
+        };
+        let bytes = utf16.encode("a中").unwrap();
+        assert_eq!(bytes, vec![0xFF, 0xFE, 0x61, 0x00, 0x2D, 0x4E]);
+        assert_eq!(decode(&bytes).0, "a中");
+    }
+
+    #[test]
+    fn test_unknown_label() {
+        assert!(FileEncoding::for_label("klingon").is_err());
+        assert_eq!(
+            FileEncoding::for_label("utf-8-sig").unwrap().name(),
+            "UTF-8-sig"
+        );
+    }
+}
@@ -0,0 +1,270 @@
+//! Argument binding of translated format strings
+//!
+//! Translators move placeholders to suit the word order of the target
+//! language. Numbered (`%1$s`, `%[1]d`, `{0}`) and named (`%(name)s`,
+//! `{name}`) placeholders bind the same argument wherever they go, but bare
+//! ones (`%s`, `{}`, `{:>8}`) bind arguments in order, so a moved `%s`
+//! silently swaps two arguments. [`rebind`] numbers the bare placeholders
+//! of a translation that moves them, in the form the file's language
+//! understands, and [`bindings`] lists the arguments a text binds, for
+//! checking translations made elsewhere.
+
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::sync::OnceLock;
+
+use crate::placeholders::{token_regex, Masked};
+
+/// Numbered placeholder forms of a programming language
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Numbering {
+    /// `%1$s` and `{0}`: C, C++, Objective-C, Java, Kotlin, Swift, PHP,
+    /// Rust, C#, Android and Apple strings
+    Positional,
+    /// `%[1]s`: Go
+    Go,
+    /// `{0}` only: Python, whose `%` operator has no numbered form, and
+    /// languages not known to have one
+    BraceOnly,
+}
+
+impl Numbering {
+    /// Numbering of the language of a file
+    pub fn for_path(path: &str) -> Self {
+        let extension = path.rsplit('.').next().unwrap_or_default().to_lowercase();
+        match extension.as_str() {
+            "go" => Self::Go,
+            "c" | "h" | "cc" | "cpp" | "hpp" | "m" | "mm" | "java" | "kt" | "swift" | "php"
+            | "rs" | "cs" | "xml" | "strings" | "stringsdict" => Self::Positional,
+            _ => Self::BraceOnly,
+        }
+    }
+}
+
+/// Argument a placeholder binds
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Binding {
+    /// printf argument by 1-based position, with its conversion
+    Printf(usize, char),
+    /// `{}` argument by 0-based position, with its format spec
+    Brace(usize, String),
+    /// Named argument, as written
+    Named(String),
+}
+
+/// A placeholder taken apart
+#[derive(Debug, PartialEq)]
+enum Arg<'a> {
+    /// `%s`, `%1$5.2f`, `%[2]d`: position and everything after it
+    Printf {
+        index: Option<usize>,
+        spec: &'a str,
+    },
+    /// `{}`, `{0:>8}`: position and everything after it
+    Brace {
+        index: Option<usize>,
+        spec: &'a str,
+    },
+    /// `%%`
+    Percent,
+    Named,
+}
+
+impl<'a> Arg<'a> {
+    fn parse(placeholder: &'a str) -> Self {
+        static PRINTF: OnceLock<Regex> = OnceLock::new();
+        static BRACE: OnceLock<Regex> = OnceLock::new();
+        let printf = PRINTF.get_or_init(|| {
+            Regex::new(r"^%(?:(\d+)\$|\[(\d+)\])?([-+#0]*(?:\d+|\*)?(?:\.(?:\d+|\*))?[A-Za-z@]+)$")
+                .unwrap()
+        });
+        let brace =
+            BRACE.get_or_init(|| Regex::new(r"^\{(\d*)((?:![rsa])?(?::[^{}]*)?)\}$").unwrap());
+
+        if placeholder == "%%" {
+            return Self::Percent;
+        }
+        if let Some(caps) = printf.captures(placeholder) {
+            let index = caps
+                .get(1)
+                .or(caps.get(2))
+                .and_then(|i| i.as_str().parse().ok());
+            let spec = caps.get(3).map_or("", |spec| spec.as_str());
+            return Self::Printf { index, spec };
+        }
+        if let Some(caps) = brace.captures(placeholder) {
+            let index = caps[1].parse().ok();
+            let spec = caps.get(2).map_or("", |spec| spec.as_str());
+            return Self::Brace { index, spec };
+        }
+        Self::Named
+    }
+
+    /// Whether this is a bare printf (or else brace) placeholder
+    fn is_bare_like(&self, printf: bool) -> bool {
+        match self {
+            Self::Printf { index: None, .. } => printf,
+            Self::Brace { index: None, .. } => !printf,
+            _ => false,
+        }
+    }
+
+    /// Whether this is a numbered printf (or else brace) placeholder
+    fn is_numbered_like(&self, printf: bool) -> bool {
+        match self {
+            Self::Printf { index: Some(_), .. } => printf,
+            Self::Brace { index: Some(_), .. } => !printf,
+            _ => false,
+        }
+    }
+
+    /// This bare placeholder bound to argument `position` (0-based)
+    fn numbered(&self, position: usize, numbering: Numbering) -> Option<String> {
+        match (self, numbering) {
+            (Self::Printf { spec, .. }, _) if spec.contains('*') => None,
+            (Self::Printf { .. }, Numbering::BraceOnly) => None,
+            (Self::Printf { spec, .. }, Numbering::Go) => {
+                Some(format!("%[{}]{}", position + 1, spec))
+            }
+            (Self::Printf { spec, .. }, _) => Some(format!("%{}${}", position + 1, spec)),
+            (Self::Brace { spec, .. }, _) => Some(format!("{{{}{}}}", position, spec)),
+            _ => None,
+        }
+    }
+}
+
+/// Arguments the placeholders of a text bind
+pub fn bindings(text: &str) -> BTreeSet<Binding> {
+    let mut printf = 0;
+    let mut brace = 0;
+    let mut bindings = BTreeSet::new();
+    for placeholder in Masked::new(text).placeholders {
+        let binding = match Arg::parse(&placeholder) {
+            Arg::Printf { index, spec } => {
+                let position = index.unwrap_or_else(|| {
+                    printf += 1;
+                    printf
+                });
+                Binding::Printf(position, spec.chars().last().unwrap_or('s'))
+            }
+            Arg::Brace { index, spec } => {
+                let position = index.unwrap_or_else(|| {
+                    brace += 1;
+                    brace - 1
+                });
+                Binding::Brace(position, spec.to_string())
+            }
+            Arg::Percent => continue,
+            Arg::Named => Binding::Named(placeholder.clone()),
+        };
+        bindings.insert(binding);
+    }
+    bindings
+}
+
+/// Placeholders to restore a translation of `masked` with
+///
+/// They are returned as they are unless the translation moves or repeats
+/// bare placeholders; those are then numbered by the argument they bind in
+/// the source. Fails with the moved placeholders if they cannot be
+/// numbered: `numbering` has no form for them, they take a `*` width, or
+/// the source mixes them with numbered ones.
+pub fn rebind(
+    masked: &Masked,
+    translation: &str,
+    numbering: Numbering,
+) -> Result<Vec<String>, Vec<String>> {
+    let args: Vec<Arg> = masked.placeholders.iter().map(|p| Arg::parse(p)).collect();
+    let order: Vec<usize> = token_regex()
+        .captures_iter(translation)
+        .filter_map(|caps| caps[1].parse().ok())
+        .filter(|&i| i < args.len())
+        .collect();
+
+    let mut placeholders = masked.placeholders.clone();
+    let mut moved = Vec::new();
+    for printf in [true, false] {
+        let bare: Vec<usize> = (0..args.len())
+            .filter(|&i| args[i].is_bare_like(printf))
+            .collect();
+        let found: Vec<usize> = order.iter().copied().filter(|i| bare.contains(i)).collect();
+        if found.windows(2).all(|pair| pair[0] < pair[1]) {
+            continue;
+        }
+        let numbered: Option<Vec<String>> = if args.iter().any(|arg| arg.is_numbered_like(printf)) {
+            None
+        } else {
+            bare.iter()
+                .enumerate()
+                .map(|(position, &i)| args[i].numbered(position, numbering))
+                .collect()
+        };
+        match numbered {
+            Some(numbered) => {
+                for (&i, placeholder) in bare.iter().zip(numbered) {
+                    placeholders[i] = placeholder;
+                }
+            }
+            None => moved.extend(bare.iter().map(|&i| masked.placeholders[i].clone())),
+        }
+    }
+
+    if moved.is_empty() {
+        Ok(placeholders)
+    } else {
+        Err(moved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rebind_moved_placeholders() {
+        let masked = Masked::new("Copied %s to %s in {}s");
+        // Kept in order: nothing to number
+        assert_eq!(
+            rebind(
+                &masked,
+                "⟪0⟫ 已复制到 ⟪1⟫，用时 ⟪2⟫ 秒",
+                Numbering::BraceOnly
+            )
+            .unwrap(),
+            vec!["%s", "%s", "{}"]
+        );
+        // Moved: bound by their source position
+        let translation = "已将 ⟪1⟫ 的 ⟪0⟫ 复制完，用时 ⟪2⟫ 秒";
+        assert_eq!(
+            rebind(&masked, translation, Numbering::Positional).unwrap(),
+            vec!["%1$s", "%2$s", "{}"]
+        );
+        assert_eq!(
+            rebind(&masked, translation, Numbering::Go).unwrap(),
+            vec!["%[1]s", "%[2]s", "{}"]
+        );
+        assert_eq!(
+            rebind(&masked, translation, Numbering::BraceOnly),
+            Err(vec!["%s".to_string(), "%s".to_string()])
+        );
+
+        let masked = Masked::new("{} of {:>3} done, {name} left");
+        assert_eq!(
+            rebind(&masked, "⟪2⟫：⟪1⟫ 中的 ⟪0⟫", Numbering::BraceOnly).unwrap(),
+            vec!["{0}", "{1:>3}", "{name}"]
+        );
+        assert_eq!(Numbering::for_path("src/main.go"), Numbering::Go);
+        assert_eq!(Numbering::for_path("app.py"), Numbering::BraceOnly);
+    }
+
+    #[test]
+    fn test_bindings() {
+        assert_eq!(bindings("%s 复制到 %d"), bindings("%2$d 中的 %1$s"));
+        assert_eq!(bindings("{} of {}"), bindings("{1} 中的 {0}"));
+        assert_ne!(bindings("%s 复制到 %d"), bindings("%d 中的 %s"));
+        assert_eq!(
+            bindings("%(name)s: 100%%"),
+            BTreeSet::from([Binding::Named("%(name)s".to_string())])
+        );
+    }
+}
@@ -0,0 +1,167 @@
+//! Control, bidi and invisible characters in translations
+//!
+//! Machine translation output can carry characters a reviewer never sees:
+//! bidi overrides that make code read differently from how it compiles
+//! (CVE-2021-42574), zero-width spaces and joiners that split identifiers,
+//! and stray control characters. [`introduced`] lists the ones a
+//! translation adds to its source, and [`strip_introduced`] removes them.
+//! Zero-width joiners and non-joiners between letters of scripts that
+//! shape with them (Persian, Arabic, the Indic scripts), and joiners
+//! inside emoji sequences, are part of the text and left alone.
+
+use std::collections::BTreeMap;
+
+/// Whether a character is a control, bidi or invisible formatting
+/// character; tab, line feed and carriage return are not
+pub fn is_unsafe(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0}'..='\u{8}'
+            | '\u{b}'
+            | '\u{c}'
+            | '\u{e}'..='\u{1f}'
+            | '\u{7f}'..='\u{9f}'
+            | '\u{ad}'
+            | '\u{61c}'
+            | '\u{180e}'
+            | '\u{200b}'..='\u{200f}'
+            | '\u{202a}'..='\u{202e}'
+            | '\u{2060}'..='\u{2064}'
+            | '\u{2066}'..='\u{2069}'
+            | '\u{feff}'
+    )
+}
+
+/// Whether a letter belongs to a script whose shaping uses ZWNJ and ZWJ
+fn uses_joiners(c: char) -> bool {
+    matches!(
+        c,
+        '\u{600}'..='\u{8ff}'
+            | '\u{900}'..='\u{dff}'
+            | '\u{1000}'..='\u{109f}'
+            | '\u{1780}'..='\u{18af}'
+            | '\u{a8e0}'..='\u{a8ff}'
+            | '\u{fb50}'..='\u{fdff}'
+            | '\u{fe70}'..='\u{fefc}'
+    )
+}
+
+/// Whether a character can be part of an emoji ZWJ sequence
+fn is_emoji(c: char) -> bool {
+    matches!(c, '\u{2600}'..='\u{27bf}' | '\u{fe0f}' | '\u{1f000}'..='\u{1faff}')
+}
+
+/// Whether a ZWNJ or ZWJ between `prev` and `next` is there on purpose
+fn is_joining(prev: Option<char>, c: char, next: Option<char>) -> bool {
+    let (Some(prev), Some(next)) = (prev, next) else {
+        return false;
+    };
+    match c {
+        '\u{200c}' => uses_joiners(prev) && uses_joiners(next),
+        '\u{200d}' => {
+            (uses_joiners(prev) && uses_joiners(next)) || (is_emoji(prev) && is_emoji(next))
+        }
+        _ => false,
+    }
+}
+
+/// The characters of `text`, each with whether it is unsafe where it stands
+fn classify(text: &str) -> Vec<(char, bool)> {
+    let chars: Vec<char> = text.chars().collect();
+    chars
+        .iter()
+        .enumerate()
+        .map(|(i, &c)| {
+            let prev = i.checked_sub(1).map(|i| chars[i]);
+            let next = chars.get(i + 1).copied();
+            (c, is_unsafe(c) && !is_joining(prev, c, next))
+        })
+        .collect()
+}
+
+fn counts(text: &str) -> BTreeMap<char, usize> {
+    let mut counts = BTreeMap::new();
+    for (c, _) in classify(text).into_iter().filter(|(_, flagged)| *flagged) {
+        *counts.entry(c).or_default() += 1;
+    }
+    counts
+}
+
+/// Unsafe characters `translated` has more of than `original`, in code
+/// point order
+pub fn introduced(original: &str, translated: &str) -> Vec<char> {
+    let before = counts(original);
+    counts(translated)
+        .into_iter()
+        .filter(|(c, count)| count > before.get(c).unwrap_or(&0))
+        .map(|(c, _)| c)
+        .collect()
+}
+
+/// `translated` without the unsafe characters `original` lacks
+///
+/// Characters the original has too are kept, as their copies cannot be
+/// told apart; [`introduced`] still reports any extra ones.
+pub fn strip_introduced(original: &str, translated: &str) -> String {
+    let before = counts(original);
+    classify(translated)
+        .into_iter()
+        .filter(|(c, flagged)| !flagged || before.contains_key(c))
+        .map(|(c, _)| c)
+        .collect()
+}
+
+/// A character as `U+202E`
+pub fn code_point(c: char) -> String {
+    format!("U+{:04X}", c as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_introduced_characters() {
+        let original = "# 检查用户是否为管理员\n";
+        let translated = "# Check\u{202e} whether the user\u{200b} is an admin\t\n";
+        assert_eq!(
+            introduced(original, translated),
+            vec!['\u{200b}', '\u{202e}']
+        );
+        assert_eq!(
+            strip_introduced(original, translated),
+            "# Check whether the user is an admin\t\n"
+        );
+        assert_eq!(code_point('\u{202e}'), "U+202E");
+
+        // Already in the source: kept, extra copies still reported
+        let original = "名\u{200d}字";
+        assert!(introduced(original, "na\u{200d}me").is_empty());
+        assert_eq!(strip_introduced(original, "na\u{200d}me"), "na\u{200d}me");
+        assert_eq!(
+            introduced(original, "n\u{200d}a\u{200d}me"),
+            vec!['\u{200d}']
+        );
+    }
+
+    #[test]
+    fn test_joiners_in_context() {
+        // en -> fa: the ZWNJ in "می‌کند" is part of the spelling
+        let original = "# Checks whether the user is an admin\n";
+        let translated = "# بررسی می\u{200c}کند که کاربر مدیر است\n";
+        assert!(introduced(original, translated).is_empty());
+        assert_eq!(strip_introduced(original, translated), translated);
+
+        // Hindi half forms and emoji sequences use ZWJ
+        assert!(introduced("", "क्\u{200d}ष").is_empty());
+        assert!(introduced("", "👩\u{200d}💻 ❤\u{fe0f}\u{200d}🔥").is_empty());
+
+        // Between Latin letters, or at the edges, they are still flagged
+        assert_eq!(introduced("", "user\u{200c}name"), vec!['\u{200c}']);
+        assert_eq!(introduced("", "\u{200d}می"), vec!['\u{200d}']);
+        assert_eq!(
+            strip_introduced("", "a\u{200d}b می\u{200c}کند"),
+            "ab می\u{200c}کند"
+        );
+    }
+}
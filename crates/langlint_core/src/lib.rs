@@ -1,8 +1,29 @@
+pub mod bilingual;
 pub mod cache;
 pub mod config;
+pub mod encoding;
+pub mod format_string;
+pub mod invisible;
+pub mod mapfile;
+pub mod markdown;
+pub mod paths;
+pub mod placeholders;
+pub mod segmentation;
+pub mod sourcemap;
+pub mod transliterate;
 pub mod types;
 
 // Re-export commonly used types
-pub use cache::Cache;
-pub use config::Config;
+pub use bilingual::Bilingual;
+pub use cache::{default_cache_dir, Cache, MemoryEntry, TranslationMemory};
+pub use config::{
+    BudgetConfig, Config, FileLimit, PolicyRule, ProtectConfig, RateLimitConfig, RetryConfig,
+    SharedMemoryConfig, TransliterateConfig, DEFAULT_EXCLUDED_DIRS,
+};
+pub use encoding::FileEncoding;
+pub use mapfile::TranslationMap;
+pub use markdown::MarkdownBlocks;
+pub use placeholders::{Masked, Protection};
+pub use segmentation::{Segmentation, SegmentationRules};
+pub use sourcemap::SourceMap;
 pub use types::{ParseResult, Position, Priority, TranslatableUnit, UnitType};
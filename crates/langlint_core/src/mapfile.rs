@@ -0,0 +1,280 @@
+//! Sidecar mapping files recording what was translated in a file
+//!
+//! A `<file>.langlint-map.json` next to a translated file keeps every
+//! original/translated pair together with its position in the original
+//! source, so the translation can be undone without a backup.
+
+use crate::types::{TranslatableUnit, UnitType};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 1-based line number of a byte offset
+fn line_at(content: &str, offset: usize) -> u32 {
+    content[..offset].matches('\n').count() as u32 + 1
+}
+
+/// Suffix appended to a file name to form its mapping file name
+pub const MAP_SUFFIX: &str = ".langlint-map.json";
+
+/// Current mapping file format version
+const MAP_VERSION: u32 = 1;
+
+/// One translated span
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MapEntry {
+    /// Line of the unit in the original file
+    pub line: u32,
+    /// Column of the unit in the original file
+    pub column: u32,
+    pub unit_type: UnitType,
+    pub original: String,
+    pub translated: String,
+}
+
+/// All translated spans of a single file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TranslationMap {
+    pub version: u32,
+    /// Start time of the run that wrote the map (Unix seconds)
+    #[serde(default)]
+    pub run: u64,
+    pub source_lang: String,
+    pub target_lang: String,
+    pub entries: Vec<MapEntry>,
+}
+
+impl TranslationMap {
+    /// Build a map from units before and after translation
+    ///
+    /// Units are paired by position; units whose text did not change are
+    /// left out.
+    pub fn from_units(
+        original: &[TranslatableUnit],
+        translated: &[TranslatableUnit],
+        source_lang: &str,
+        target_lang: &str,
+    ) -> Self {
+        let entries = original
+            .iter()
+            .zip(translated)
+            .filter(|(before, after)| before.content != after.content)
+            .map(|(before, after)| MapEntry {
+                line: before.line_number,
+                column: before.column_number,
+                unit_type: before.unit_type,
+                original: before.content.clone(),
+                translated: after.content.clone(),
+            })
+            .collect();
+
+        Self {
+            version: MAP_VERSION,
+            run: 0,
+            source_lang: source_lang.to_string(),
+            target_lang: target_lang.to_string(),
+            entries,
+        }
+    }
+
+    /// Tag the map with the run that produced it
+    pub fn with_run(mut self, run: u64) -> Self {
+        self.run = run;
+        self
+    }
+
+    /// Restore original text in the content of a translated file
+    ///
+    /// Each entry's translated text is located in `content` at the
+    /// occurrence closest to the entry's original line and replaced by the
+    /// original text. Parsers cannot be used here because they skip text
+    /// already in the target language. With `json_escaped` both texts are
+    /// matched in their JSON string form (notebooks). When `lines` is not
+    /// empty only entries recorded at those lines are considered.
+    ///
+    /// Applied entries are removed from the map; returns the new content and
+    /// the number of reverted entries.
+    pub fn revert_text(
+        &mut self,
+        content: &str,
+        lines: &[u32],
+        json_escaped: bool,
+    ) -> (String, usize) {
+        let encode = |text: &str| {
+            if json_escaped {
+                let quoted = serde_json::to_string(text).unwrap_or_default();
+                quoted[1..quoted.len() - 1].to_string()
+            } else {
+                text.to_string()
+            }
+        };
+
+        let mut replacements: Vec<(usize, usize, String)> = Vec::new();
+        self.entries.retain(|entry| {
+            if !lines.is_empty() && !lines.contains(&entry.line) {
+                return true;
+            }
+
+            let needle = encode(&entry.translated);
+            if needle.is_empty() {
+                return true;
+            }
+
+            let found = content
+                .match_indices(needle.as_str())
+                .map(|(start, _)| (start, start + needle.len()))
+                .filter(|&(start, end)| {
+                    replacements.iter().all(|&(s, e, _)| end <= s || start >= e)
+                })
+                .min_by_key(|&(start, _)| line_at(content, start).abs_diff(entry.line));
+
+            match found {
+                Some((start, end)) => {
+                    replacements.push((start, end, encode(&entry.original)));
+                    false
+                }
+                None => true,
+            }
+        });
+
+        replacements.sort_by_key(|&(start, _, _)| std::cmp::Reverse(start));
+        let mut result = content.to_string();
+        for (start, end, original) in &replacements {
+            result.replace_range(*start..*end, original);
+        }
+
+        (result, replacements.len())
+    }
+
+    /// Mapping file path for a translated file
+    pub fn path_for(file: &Path) -> PathBuf {
+        let mut name = file.as_os_str().to_os_string();
+        name.push(MAP_SUFFIX);
+        PathBuf::from(name)
+    }
+
+    /// Load a mapping file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read mapping file: {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Invalid mapping file: {}", path.display()))
+    }
+
+    /// Write the mapping file
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content)
+            .with_context(|| format!("Failed to write mapping file: {}", path.display()))
+    }
+
+    /// Check if the map records no translations
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn unit(content: &str, line: u32) -> TranslatableUnit {
+        TranslatableUnit::new(content.to_string(), UnitType::Comment, line, 4)
+    }
+
+    #[test]
+    fn test_from_units_skips_unchanged() {
+        let original = vec![unit("注释", 1), unit("same", 3)];
+        let translated = vec![unit("comment", 1), unit("same", 3)];
+
+        let map = TranslationMap::from_units(&original, &translated, "zh", "en");
+
+        assert_eq!(map.entries.len(), 1);
+        assert_eq!(map.entries[0].line, 1);
+        assert_eq!(map.entries[0].column, 4);
+        assert_eq!(map.entries[0].original, "注释");
+        assert_eq!(map.entries[0].translated, "comment");
+    }
+
+    #[test]
+    fn test_revert_text() {
+        let mut map = TranslationMap::from_units(
+            &[unit("一", 1), unit("二", 3), unit("三", 5)],
+            &[unit("same", 1), unit("same", 3), unit("three", 5)],
+            "zh",
+            "en",
+        );
+        let content = "# same\nx = 1\n# same\n\n# three\n";
+
+        let (content, reverted) = map.revert_text(content, &[3], false);
+        assert_eq!(reverted, 1);
+        assert_eq!(content, "# same\nx = 1\n# 二\n\n# three\n");
+        assert_eq!(map.entries.len(), 2);
+
+        let (content, reverted) = map.revert_text(&content, &[], false);
+        assert_eq!(reverted, 2);
+        assert_eq!(content, "# 一\nx = 1\n# 二\n\n# 三\n");
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_revert_text_keeps_unmatched() {
+        let mut map = TranslationMap::from_units(&[unit("一", 1)], &[unit("one", 1)], "zh", "en");
+
+        let (content, reverted) = map.revert_text("# edited by hand\n", &[], false);
+
+        assert_eq!(reverted, 0);
+        assert_eq!(content, "# edited by hand\n");
+        assert_eq!(map.entries.len(), 1);
+    }
+
+    #[test]
+    fn test_revert_text_json_escaped() {
+        let mut map = TranslationMap::from_units(
+            &[unit("标题\n正文", 1)],
+            &[unit("Title \"quoted\"\nBody", 1)],
+            "zh",
+            "en",
+        );
+        let content = r#"{"source": ["Title \"quoted\"\nBody"]}"#;
+
+        let (content, reverted) = map.revert_text(content, &[], true);
+
+        assert_eq!(reverted, 1);
+        assert_eq!(content, r#"{"source": ["标题\n正文"]}"#);
+    }
+
+    #[test]
+    fn test_load_without_run() {
+        let map: TranslationMap = serde_json::from_str(
+            r#"{"version": 1, "source_lang": "zh", "target_lang": "en", "entries": []}"#,
+        )
+        .unwrap();
+        assert_eq!(map.run, 0);
+    }
+
+    #[test]
+    fn test_path_for() {
+        assert_eq!(
+            TranslationMap::path_for(Path::new("src/app.py")),
+            PathBuf::from("src/app.py.langlint-map.json")
+        );
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("a.py.langlint-map.json");
+        let map = TranslationMap::from_units(&[unit("你好", 2)], &[unit("hello", 2)], "zh", "en");
+
+        map.save(&path).unwrap();
+        let loaded = TranslationMap::load(&path).unwrap();
+
+        assert_eq!(loaded, map);
+        assert_eq!(loaded.version, MAP_VERSION);
+    }
+}
@@ -0,0 +1,516 @@
+//! Markdown structure handling for translatable text
+//!
+//! Docstrings and notebook markdown cells often contain lists, tables and
+//! numbered steps. Sending such a blob to a translation engine as one string
+//! tends to lose bullets, table pipes and numbering, so this module splits
+//! the text into markup (kept verbatim) and prose segments (translated one by
+//! one) and reassembles the result afterwards.
+//!
+//! The same applies to text art in comments: separator rules (`====`),
+//! box-drawing tables and ASCII diagrams. Rules are kept verbatim, and the
+//! labels inside a drawing are translated cell by cell and padded back to
+//! their original display width so the columns stay aligned.
+//!
+//! Notebook markdown cells also mix prose with math, images and HTML.
+//! `$$` display math blocks and lines holding nothing but such markup are
+//! kept verbatim; inline occurrences are masked while translating (see
+//! [`crate::Protection::with_markup`]).
+
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// A piece of a split markdown text
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece {
+    /// Markup kept verbatim (bullets, pipes, numbering, blank lines, code)
+    Markup(String),
+    /// Prose to be translated
+    Text(String),
+    /// Label inside a drawing, padded back to `width` display columns
+    Cell { text: String, width: usize },
+}
+
+/// Markdown text split into markup and translatable segments
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkdownBlocks {
+    pieces: Vec<Piece>,
+    structured: bool,
+}
+
+impl MarkdownBlocks {
+    /// Split text into markup and prose segments line by line
+    pub fn parse(text: &str) -> Self {
+        let mut pieces = Vec::new();
+        let mut structured = false;
+        let mut in_fence = false;
+        let mut in_math = false;
+
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                pieces.push(Piece::Markup("\n".to_string()));
+            }
+
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                in_fence = !in_fence;
+                pieces.push(Piece::Markup(line.to_string()));
+                continue;
+            }
+            if in_fence || trimmed.is_empty() {
+                pieces.push(Piece::Markup(line.to_string()));
+                continue;
+            }
+
+            // `$$` on a line of its own, or opening a block closed on a later line
+            let toggles_math = if in_math {
+                trimmed.trim_end().ends_with("$$")
+            } else {
+                trimmed.starts_with("$$") && !trimmed[2..].contains("$$")
+            };
+            if in_math || toggles_math {
+                in_math ^= toggles_math;
+                structured = true;
+                pieces.push(Piece::Markup(line.to_string()));
+                continue;
+            }
+
+            if is_rule_line(trimmed) || is_markup_line(trimmed) {
+                structured = true;
+                pieces.push(Piece::Markup(line.to_string()));
+            } else if is_art_line(trimmed) {
+                structured = true;
+                push_art_line(&mut pieces, line);
+            } else if let Some(caps) = list_item_re().captures(line) {
+                structured = true;
+                let prefix = caps.get(1).map_or("", |m| m.as_str());
+                pieces.push(Piece::Markup(prefix.to_string()));
+                push_text(&mut pieces, &line[prefix.len()..]);
+            } else if is_table_row(trimmed) {
+                structured = true;
+                push_table_row(&mut pieces, line);
+            } else {
+                push_text(&mut pieces, line);
+            }
+        }
+
+        Self { pieces, structured }
+    }
+
+    /// Split comment text into drawing markup and labels
+    ///
+    /// Unlike [`MarkdownBlocks::parse`], only text art is recognised:
+    /// bullets and pipe tables in comments are left to the translator.
+    pub fn parse_diagram(text: &str) -> Self {
+        let mut pieces = Vec::new();
+        let mut structured = false;
+
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                pieces.push(Piece::Markup("\n".to_string()));
+            }
+
+            let trimmed = line.trim();
+            if is_rule_line(trimmed) {
+                structured = true;
+                pieces.push(Piece::Markup(line.to_string()));
+            } else if is_art_line(trimmed) {
+                structured = true;
+                push_art_line(&mut pieces, line);
+            } else {
+                push_text(&mut pieces, line);
+            }
+        }
+
+        Self { pieces, structured }
+    }
+
+    /// Whether the text contains lists or tables that need block-wise translation
+    pub fn is_structured(&self) -> bool {
+        self.structured
+    }
+
+    /// The prose segments to translate, in order
+    pub fn texts(&self) -> Vec<String> {
+        self.pieces
+            .iter()
+            .filter_map(|p| match p {
+                Piece::Text(t) | Piece::Cell { text: t, .. } => Some(t.clone()),
+                Piece::Markup(_) => None,
+            })
+            .collect()
+    }
+
+    /// Reassemble the text, substituting translated segments in order
+    ///
+    /// Missing translations fall back to the original segment.
+    pub fn rebuild(&self, translations: &[String]) -> String {
+        let mut translations = translations.iter();
+        self.pieces
+            .iter()
+            .map(|p| match p {
+                Piece::Markup(m) => m.clone(),
+                Piece::Text(t) => translations
+                    .next()
+                    .map(|s| s.trim().to_string())
+                    .unwrap_or_else(|| t.clone()),
+                Piece::Cell { text, width } => {
+                    let cell = translations
+                        .next()
+                        .map(|s| s.trim().to_string())
+                        .unwrap_or_else(|| text.clone());
+                    let padding = width.saturating_sub(display_width(&cell));
+                    format!("{}{}", cell, " ".repeat(padding))
+                }
+            })
+            .collect()
+    }
+}
+
+/// Inline math (`$x^2$`, `$$E = mc^2$$`), images and HTML tags and comments,
+/// reStructuredText roles (`:ref:`install``), link references and
+/// substitutions (`|version|`), Jinja expressions (`{{ user.name }}`), and
+/// LaTeX commands (`\textbf`, `\cite{key}`, `\(x\)`)
+///
+/// A `$` followed or preceded by a space is not math, so `$5 and $10` is prose.
+/// HTML `<code>` elements and LaTeX commands taking keys or paths are masked
+/// whole, other LaTeX commands by name only so their text is translated.
+pub(crate) const INLINE_MARKUP_PATTERN: &str = concat!(
+    r"\{\{[^{}]*\}\}",
+    r"|\\(?:cite[A-Za-z]*|[cC]?ref|eqref|autoref|pageref|nameref|label|url|href|input|include|includegraphics)\*?(?:\[[^\]\n]*\])*\{[^{}\n]*\}",
+    r"|\\\([^\n]*?\\\)",
+    r"|\\[A-Za-z]+\*?|\\\\",
+    r"|<code\b[^<>]*>[^<]*</code>",
+    r"|<xliff:g\b[^<>]*>[^<]*</xliff:g>",
+    r"|:[A-Za-z][\w.+:-]*:`[^`\n]+`",
+    r"|`[^`\n]+`__?",
+    r"|\|[A-Za-z][A-Za-z0-9_.-]*\|_{0,2}",
+    r"|\$\$[^$]+\$\$",
+    r"|\$[^\s$](?:[^$\n]*[^\s$])?\$",
+    r"|!\[[^\]\n]*\]\([^)\n]*\)",
+    r"|<!--[\s\S]*?-->",
+    r"|</?[A-Za-z][A-Za-z0-9:-]*(?:\s[^<>]*)?/?>",
+);
+
+fn inline_markup_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(INLINE_MARKUP_PATTERN).unwrap())
+}
+
+/// Check whether a line holds markup and no prose (`$$x$$`, `<br/>`, an image)
+fn is_markup_line(trimmed: &str) -> bool {
+    inline_markup_re().is_match(trimmed)
+        && !inline_markup_re()
+            .replace_all(trimmed, "")
+            .chars()
+            .any(char::is_alphabetic)
+}
+
+/// Bullets (`-`, `*`, `+`), numbered steps (`1.`, `2)`) and headings, with their trailing space
+fn list_item_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^(\s*(?:[-*+]|\d+[.)]|#{1,6})\s+)\S").unwrap())
+}
+
+/// Check whether a line is a markdown table row (`| a | b |`)
+fn is_table_row(trimmed: &str) -> bool {
+    trimmed.starts_with('|') && trimmed.trim_end().ends_with('|') && trimmed.len() > 1
+}
+
+/// Check if a character is part of a drawing: box drawing, blocks, arrows
+/// and geometric shapes
+fn is_drawing_char(c: char) -> bool {
+    matches!(c as u32, 0x2190..=0x21FF | 0x2500..=0x25FF | 0x27F0..=0x27FF)
+}
+
+/// ASCII characters that form rules and diagram edges when repeated
+fn is_ascii_art_char(c: char) -> bool {
+    matches!(
+        c,
+        '-' | '=' | '_' | '~' | '*' | '#' | '+' | '|' | '.' | ':' | '<' | '>' | '/' | '\\'
+    )
+}
+
+/// Check whether a line is a separator rule or a drawing without labels
+/// (`=====`, `-- * --`, `└──┘`)
+pub fn is_rule_line(trimmed: &str) -> bool {
+    trimmed.chars().count() >= 3
+        && trimmed
+            .chars()
+            .all(|c| c.is_whitespace() || is_drawing_char(c) || is_ascii_art_char(c))
+        && trimmed.chars().any(|c| !c.is_whitespace())
+}
+
+/// Check whether a line is a diagram or box table with labels
+///
+/// Markdown pipe tables (`| a | b |`) are handled separately and are not
+/// text art.
+pub fn is_art_line(trimmed: &str) -> bool {
+    if trimmed.chars().any(is_drawing_char) {
+        return true;
+    }
+    // ASCII diagrams: `+-----+` boxes, `-->` arrows and `=====` runs
+    ["---", "===", "-->", "<--", "==>", "<==", "+-", "-+"]
+        .iter()
+        .any(|edge| trimmed.contains(edge))
+        && trimmed.chars().any(char::is_alphabetic)
+        && !trimmed.starts_with('|')
+}
+
+/// Terminal display width of text (East Asian wide characters take two columns)
+pub fn display_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| match c as u32 {
+            0x1100..=0x115F
+            | 0x2E80..=0x303E
+            | 0x3041..=0x33FF
+            | 0x3400..=0x4DBF
+            | 0x4E00..=0x9FFF
+            | 0xA000..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6
+            | 0x20000..=0x3FFFD => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+/// Split a drawing line into edges (markup) and labels (cells)
+///
+/// Edges are drawing characters, `|`, `+` and runs of two or more ASCII art
+/// characters. A label followed by an edge keeps its display width, so the
+/// edge stays in its column after translation.
+fn push_art_line(pieces: &mut Vec<Piece>, line: &str) {
+    let chars: Vec<(usize, char)> = line.char_indices().collect();
+    let is_edge_at = |i: usize| {
+        let c = chars[i].1;
+        is_drawing_char(c)
+            || c == '|'
+            || c == '+'
+            || (is_ascii_art_char(c)
+                && (chars.get(i + 1).is_some_and(|&(_, n)| is_ascii_art_char(n))
+                    || i > 0 && is_ascii_art_char(chars[i - 1].1)))
+    };
+
+    // Split into alternating runs of edges and labels (by byte offset)
+    let mut runs: Vec<(bool, usize, usize)> = Vec::new();
+    for (i, &(offset, c)) in chars.iter().enumerate() {
+        let edge = is_edge_at(i);
+        let end = offset + c.len_utf8();
+        match runs.last_mut() {
+            Some((is_edge, _, run_end)) if *is_edge == edge => *run_end = end,
+            _ => runs.push((edge, offset, end)),
+        }
+    }
+
+    for (index, &(is_edge, start, end)) in runs.iter().enumerate() {
+        let segment = &line[start..end];
+        if is_edge || !segment.chars().any(char::is_alphabetic) {
+            pieces.push(Piece::Markup(segment.to_string()));
+            continue;
+        }
+
+        let followed_by_edge = index + 1 < runs.len();
+        if !followed_by_edge {
+            push_text(pieces, segment);
+            continue;
+        }
+
+        // Leading whitespace stays markup; label plus trailing padding is the cell
+        let label_start = segment.len() - segment.trim_start().len();
+        if label_start > 0 {
+            pieces.push(Piece::Markup(segment[..label_start].to_string()));
+        }
+        let cell = &segment[label_start..];
+        pieces.push(Piece::Cell {
+            text: cell.trim_end().to_string(),
+            width: display_width(cell),
+        });
+    }
+}
+
+/// Push a text segment, keeping surrounding whitespace as markup
+fn push_text(pieces: &mut Vec<Piece>, segment: &str) {
+    let content = segment.trim();
+    if content.is_empty() {
+        pieces.push(Piece::Markup(segment.to_string()));
+        return;
+    }
+    let start = segment.len() - segment.trim_start().len();
+    let end = start + content.len();
+    if start > 0 {
+        pieces.push(Piece::Markup(segment[..start].to_string()));
+    }
+    pieces.push(Piece::Text(content.to_string()));
+    if end < segment.len() {
+        pieces.push(Piece::Markup(segment[end..].to_string()));
+    }
+}
+
+/// Split a table row into pipes (markup) and cells (text); separator rows stay verbatim
+fn push_table_row(pieces: &mut Vec<Piece>, line: &str) {
+    let is_separator = line
+        .chars()
+        .all(|c| matches!(c, '|' | '-' | ':' | ' ' | '\t'));
+    if is_separator {
+        pieces.push(Piece::Markup(line.to_string()));
+        return;
+    }
+
+    for (i, cell) in line.split('|').enumerate() {
+        if i > 0 {
+            pieces.push(Piece::Markup("|".to_string()));
+        }
+        let has_letters = cell.chars().any(|c| c.is_alphabetic());
+        if has_letters {
+            push_text(pieces, cell);
+        } else {
+            pieces.push(Piece::Markup(cell.to_string()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_text_is_not_structured() {
+        let blocks = MarkdownBlocks::parse("这是一个普通段落");
+        assert!(!blocks.is_structured());
+        assert_eq!(blocks.texts(), vec!["这是一个普通段落"]);
+    }
+
+    #[test]
+    fn test_list_preserves_bullets_and_numbering() {
+        let text = "步骤：\n1. 安装依赖\n2. 运行测试\n\n- 第一项\n  * 子项";
+        let blocks = MarkdownBlocks::parse(text);
+        assert!(blocks.is_structured());
+        assert_eq!(
+            blocks.texts(),
+            vec!["步骤：", "安装依赖", "运行测试", "第一项", "子项"]
+        );
+
+        let translated: Vec<String> = ["Steps:", "Install deps", "Run tests", "First", "Child"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(
+            blocks.rebuild(&translated),
+            "Steps:\n1. Install deps\n2. Run tests\n\n- First\n  * Child"
+        );
+    }
+
+    #[test]
+    fn test_table_preserves_pipes() {
+        let text = "| 名称 | 说明 |\n|------|:----:|\n| 甲 | 42 |";
+        let blocks = MarkdownBlocks::parse(text);
+        assert!(blocks.is_structured());
+        assert_eq!(blocks.texts(), vec!["名称", "说明", "甲"]);
+
+        let translated = vec![
+            "Name".to_string(),
+            "Description".to_string(),
+            "A".to_string(),
+        ];
+        assert_eq!(
+            blocks.rebuild(&translated),
+            "| Name | Description |\n|------|:----:|\n| A | 42 |"
+        );
+    }
+
+    #[test]
+    fn test_code_fence_is_kept_verbatim() {
+        let text = "- 示例\n```\n# 不要翻译\n```";
+        let blocks = MarkdownBlocks::parse(text);
+        assert_eq!(blocks.texts(), vec!["示例"]);
+        assert_eq!(
+            blocks.rebuild(&["Example".to_string()]),
+            "- Example\n```\n# 不要翻译\n```"
+        );
+    }
+
+    #[test]
+    fn test_math_and_html_lines_are_kept_verbatim() {
+        let text = "质能方程：\n$$\nE = mc^2\n$$\n$$a^2 + b^2 = c^2$$\n\
+                    ![示意图](fig.png)\n<img src=\"a.png\"/> <br>\n其中 $c$ 是光速";
+        let blocks = MarkdownBlocks::parse(text);
+        assert!(blocks.is_structured());
+        assert_eq!(blocks.texts(), vec!["质能方程：", "其中 $c$ 是光速"]);
+        assert_eq!(
+            blocks.rebuild(&[
+                "Mass-energy:".to_string(),
+                "where $c$ is the speed of light".to_string()
+            ]),
+            "Mass-energy:\n$$\nE = mc^2\n$$\n$$a^2 + b^2 = c^2$$\n\
+             ![示意图](fig.png)\n<img src=\"a.png\"/> <br>\nwhere $c$ is the speed of light"
+        );
+        // Prices are not math
+        assert!(!MarkdownBlocks::parse("售价 $5 到 $10").is_structured());
+    }
+
+    #[test]
+    fn test_rule_lines_are_kept_verbatim() {
+        let text = "==========\n配置说明\n──────────";
+        let blocks = MarkdownBlocks::parse_diagram(text);
+        assert!(blocks.is_structured());
+        assert_eq!(blocks.texts(), vec!["配置说明"]);
+        assert_eq!(
+            blocks.rebuild(&["Configuration".to_string()]),
+            "==========\nConfiguration\n──────────"
+        );
+    }
+
+    #[test]
+    fn test_box_table_keeps_columns_aligned() {
+        let text = "│ 名称   │ 说明       │";
+        let blocks = MarkdownBlocks::parse_diagram(text);
+        assert!(blocks.is_structured());
+        assert_eq!(blocks.texts(), vec!["名称", "说明"]);
+        assert_eq!(
+            blocks.rebuild(&["Name".to_string(), "Desc".to_string()]),
+            "│ Name   │ Desc       │"
+        );
+        assert_eq!(
+            display_width("│ Name   │ Desc       │"),
+            display_width(text)
+        );
+    }
+
+    #[test]
+    fn test_ascii_diagram_labels() {
+        let text = "客户端 --> 网关 --> 服务";
+        let blocks = MarkdownBlocks::parse_diagram(text);
+        assert_eq!(blocks.texts(), vec!["客户端", "网关", "服务"]);
+        assert_eq!(
+            blocks.rebuild(&[
+                "Client".to_string(),
+                "GW".to_string(),
+                "Service".to_string()
+            ]),
+            "Client --> GW   --> Service"
+        );
+    }
+
+    #[test]
+    fn test_prose_is_not_art() {
+        assert!(!is_art_line("这是普通的注释 - 没有图表"));
+        assert!(!is_rule_line("普通注释"));
+        assert!(!MarkdownBlocks::parse_diagram("计算 a + b 的和").is_structured());
+    }
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(display_width("abc"), 3);
+        assert_eq!(display_width("名称"), 4);
+        assert_eq!(display_width("ａ"), 2);
+    }
+
+    #[test]
+    fn test_rebuild_with_missing_translations_keeps_original() {
+        let blocks = MarkdownBlocks::parse("- 一\n- 二");
+        assert_eq!(blocks.rebuild(&["One".to_string()]), "- One\n- 二");
+    }
+}
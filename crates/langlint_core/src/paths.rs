@@ -0,0 +1,222 @@
+//! Cross-platform path handling
+//!
+//! Windows accepts both `\` and `/` as separators, compares file names
+//! case-insensitively, reserves device names like `CON` and `NUL`, and
+//! limits plain paths to 260 characters (failing deep output trees with
+//! "os error 3"). The helpers here normalize paths for matching and
+//! display, and turn long paths into their extended-length `\\?\` form
+//! before they reach the file system.
+
+use std::path::{Path, PathBuf};
+
+/// Whether file names compare case-insensitively on this platform
+pub const CASE_INSENSITIVE: bool = cfg!(windows);
+
+/// Length from which paths are given the extended-length prefix
+///
+/// A bit below `MAX_PATH` (260), since directory creation fails earlier.
+const LONG_PATH_THRESHOLD: usize = 240;
+
+/// Device names Windows reserves in every directory, with any extension
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Use forward slashes as separators, for matching and reports
+pub fn normalize_separators(path: &str) -> String {
+    path.replace('\\', "/")
+}
+
+/// A path as a string with forward slashes and no extended-length prefix
+pub fn to_slash(path: &Path) -> String {
+    normalize_separators(&strip_verbatim(&path.to_string_lossy()))
+}
+
+/// Remove the extended-length prefix (`\\?\C:\x`, `\\?\UNC\server\share`)
+pub fn strip_verbatim(path: &str) -> String {
+    if let Some(unc) = path.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{}", unc)
+    } else if let Some(local) = path.strip_prefix(r"\\?\") {
+        local.to_string()
+    } else {
+        path.to_string()
+    }
+}
+
+/// Compare two file names the way the platform does
+pub fn names_equal(a: &str, b: &str) -> bool {
+    if CASE_INSENSITIVE {
+        a.to_lowercase() == b.to_lowercase()
+    } else {
+        a == b
+    }
+}
+
+/// Check if a file name is reserved on Windows (`con`, `NUL.txt`, `com1.py`)
+///
+/// Windows ignores trailing dots and spaces, and everything after the
+/// first dot, when checking for device names.
+pub fn is_reserved_name(name: &str) -> bool {
+    let stem = name.trim_end_matches(['.', ' ']);
+    let stem = stem.split('.').next().unwrap_or(stem).trim_end();
+    RESERVED_NAMES.iter().any(|r| r.eq_ignore_ascii_case(stem))
+}
+
+/// First component of a path that cannot be created on Windows
+pub fn reserved_component(path: &Path) -> Option<String> {
+    path.components()
+        .filter_map(|c| match c {
+            std::path::Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .find(|name| is_reserved_name(name))
+}
+
+/// A path safe to hand to the file system
+///
+/// On Windows, absolute (or current-directory relative) paths approaching
+/// `MAX_PATH` and UNC paths get the extended-length prefix; elsewhere the
+/// path is returned as is.
+pub fn long_path(path: &Path) -> PathBuf {
+    if !cfg!(windows) {
+        return path.to_path_buf();
+    }
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        match std::env::current_dir() {
+            Ok(dir) => dir.join(path),
+            Err(_) => return path.to_path_buf(),
+        }
+    };
+    let absolute = absolute.to_string_lossy();
+    if absolute.len() < LONG_PATH_THRESHOLD && !absolute.starts_with(r"\\") {
+        return path.to_path_buf();
+    }
+    extended_length(&absolute).map_or_else(|| path.to_path_buf(), PathBuf::from)
+}
+
+/// Extended-length form of an absolute Windows path
+///
+/// The prefix turns off Windows' own normalization, so separators are
+/// converted to `\` and `.`/`..` components resolved here. Returns `None`
+/// for relative paths.
+fn extended_length(path: &str) -> Option<String> {
+    if path.starts_with(r"\\?\") {
+        return Some(path.to_string());
+    }
+    let path = path.replace('/', r"\");
+
+    let (prefix, rest) = if let Some(unc) = path.strip_prefix(r"\\") {
+        // \\server\share\rest
+        let mut parts = unc.splitn(3, '\\');
+        let server = parts.next().filter(|s| !s.is_empty())?;
+        let share = parts.next().filter(|s| !s.is_empty())?;
+        (
+            format!(r"\\?\UNC\{}\{}", server, share),
+            parts.next().unwrap_or(""),
+        )
+    } else {
+        let bytes = path.as_bytes();
+        let has_drive = bytes.len() >= 3
+            && bytes[0].is_ascii_alphabetic()
+            && bytes[1] == b':'
+            && bytes[2] == b'\\';
+        if !has_drive {
+            return None;
+        }
+        (format!(r"\\?\{}", &path[..2]), &path[3..])
+    };
+
+    let mut components: Vec<&str> = Vec::new();
+    for component in rest.split('\\') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop();
+            }
+            name => components.push(name),
+        }
+    }
+
+    Some(format!("{}\\{}", prefix, components.join("\\")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_separators() {
+        assert_eq!(normalize_separators(r"src\utils\io.py"), "src/utils/io.py");
+        assert_eq!(normalize_separators("src/mixed\\io.py"), "src/mixed/io.py");
+        assert_eq!(
+            to_slash(Path::new(r"\\?\C:\work\src\app.py")),
+            "C:/work/src/app.py"
+        );
+    }
+
+    #[test]
+    fn test_strip_verbatim() {
+        assert_eq!(strip_verbatim(r"\\?\C:\work"), r"C:\work");
+        assert_eq!(
+            strip_verbatim(r"\\?\UNC\server\share\src"),
+            r"\\server\share\src"
+        );
+        assert_eq!(strip_verbatim("/home/user"), "/home/user");
+    }
+
+    #[test]
+    fn test_reserved_names() {
+        for name in [
+            "CON",
+            "con",
+            "nul.txt",
+            "Com1.py",
+            "LPT9",
+            "aux.tar.gz",
+            "PRN. ",
+        ] {
+            assert!(is_reserved_name(name), "{}", name);
+        }
+        for name in ["console.py", "com10", "null", "lpt", "config.con", "src"] {
+            assert!(!is_reserved_name(name), "{}", name);
+        }
+        assert_eq!(
+            reserved_component(Path::new("out/aux/app.py")),
+            Some("aux".to_string())
+        );
+        assert_eq!(reserved_component(Path::new("out/src/app.py")), None);
+    }
+
+    #[test]
+    fn test_extended_length() {
+        assert_eq!(
+            extended_length(r"C:\work\src\..\out\.\app.py").unwrap(),
+            r"\\?\C:\work\out\app.py"
+        );
+        assert_eq!(
+            extended_length("D:/mixed/separators").unwrap(),
+            r"\\?\D:\mixed\separators"
+        );
+        assert_eq!(
+            extended_length(r"\\server\share\deep\file.py").unwrap(),
+            r"\\?\UNC\server\share\deep\file.py"
+        );
+        assert_eq!(
+            extended_length(r"\\?\C:\already").unwrap(),
+            r"\\?\C:\already"
+        );
+        assert_eq!(extended_length(r"relative\path"), None);
+        assert_eq!(extended_length(r"\\server"), None);
+    }
+
+    #[test]
+    fn test_long_path_is_identity_off_windows() {
+        if !cfg!(windows) {
+            let path = Path::new("a").join("b".repeat(300));
+            assert_eq!(long_path(&path), path);
+        }
+    }
+}
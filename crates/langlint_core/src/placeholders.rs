@@ -0,0 +1,537 @@
+//! Placeholder masking for translation
+//!
+//! Translators often corrupt format placeholders: `%s` becomes `% s`,
+//! `{name}` gets translated, `${var}` loses its braces. [`Masked`] replaces
+//! placeholders with opaque numbered tokens (`⟪0⟫`) before a text is sent
+//! and puts them back into the translation afterwards.
+//!
+//! Recognized syntaxes:
+//!
+//! * printf: `%s`, `%5.2f`, `%-10d`, `%(name)s`, `%%`
+//! * Python `str.format` and Rust `format!`: `{}`, `{0}`, `{name}`,
+//!   `{name!r}`, `{:?}`, `{value:>8.2f}`
+//! * JavaScript template literals: `${var}`, `${user.name}`
+//!
+//! A [`Protection`] masks more than placeholders: code spans, URLs, file
+//! paths and identifiers that must come back verbatim, plus regexes from
+//! the config. [`Protection::with_markup`] adds the math, images and HTML
+//! of markdown text.
+
+use crate::config::ProtectConfig;
+use crate::markdown::INLINE_MARKUP_PATTERN;
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::fmt;
+use std::sync::OnceLock;
+
+/// Characters delimiting a masking token
+const TOKEN_OPEN: char = '⟪';
+const TOKEN_CLOSE: char = '⟫';
+
+const PLACEHOLDER_PATTERN: &str = concat!(
+    // JavaScript template literal
+    r"\$\{[^{}\s][^{}]*\}",
+    // JSDoc/TSDoc inline tag, e.g. `{@link Config}`
+    r"|\{@[A-Za-z]+(?:\s[^{}]*)?\}",
+    // Javadoc and XML doc inline code and references, e.g. `<code>null</code>`
+    // or `<see cref="Config"/>`
+    r"|<(?:c|code|tt)>[^<>]*</(?:c|code|tt)>|<[A-Za-z]+\s[^<>]*/>",
+    // printf with a mapping key
+    r"|%\([A-Za-z_][A-Za-z0-9_]*\)[-+#0]*\d*(?:\.\d+)?[a-zA-Z]",
+    // Apple .stringsdict variable
+    r"|%#@[A-Za-z_][A-Za-z0-9_]*@",
+    // printf, positional (`%1$s`), Objective-C (`%@`) and Go (`%v`,
+    // `%[1]d`) too; no space flag, so `50% of` is left alone
+    r"|%(?:\d+\$|\[\d+\])?[-+#0]*(?:\d+|\*)?(?:\.(?:\d+|\*))?(?:hh|h|ll|l|L|q|j|z|t)?[diouxXeEfFgGcrsaAp@%vqwT]",
+    // Mustache, i18next and Angular interpolation
+    r"|\{\{[^{}]*\}\}",
+    // Ruby/Rails interpolation
+    r"|%\{[A-Za-z_][A-Za-z0-9_]*\}",
+    // ICU MessageFormat argument with a format, e.g. `{total, number, percent}`
+    r"|\{\s*[A-Za-z0-9_]+\s*,\s*(?:number|date|time|duration|ordinal|spellout)\b[^{}]*\}",
+    // Python/Rust format field, including `{:?}` and `{name!r:>8}`
+    r"|\{[A-Za-z0-9_.\[\]]*(?:![rsa])?(?::[^{}\s]*)?\}",
+);
+
+/// Backtick code spans, single or double
+const CODE_SPAN_PATTERN: &str = r"``[^\n]+?``|`[^`\n]+`";
+
+/// URLs with a scheme, ending before trailing punctuation
+const URL_PATTERN: &str =
+    r"[A-Za-z][A-Za-z0-9+.\-]*://[A-Za-z0-9\-._~:/?#\[\]@!$&'()*+,;=%]*[A-Za-z0-9\-_~/#=&%+]";
+
+const PATH_PATTERN: &str = concat!(
+    // Windows drive path
+    r"[A-Za-z]:\\[A-Za-z0-9_.\-]+(?:\\[A-Za-z0-9_.\-]+)*",
+    // Rooted path: /etc/hosts, ./build, ../lib, ~/.config
+    r"|(?:~|\.{1,2})?/[A-Za-z0-9_.\-]+(?:/[A-Za-z0-9_.\-]+)*/?",
+    // Relative path to a file: src/utils/io.py
+    r"|[A-Za-z0-9_\-]+(?:/[A-Za-z0-9_.\-]+)*/[A-Za-z0-9_\-]+\.[A-Za-z0-9]+",
+);
+
+const IDENTIFIER_PATTERN: &str = concat!(
+    // Dotted name or call: os.path.join, self.value, run()
+    r"[A-Za-z_][A-Za-z0-9_]*(?:\.[A-Za-z_][A-Za-z0-9_]*)+(?:\(\))?",
+    r"|[A-Za-z_][A-Za-z0-9_]*\(\)",
+    // Dunder: __init__
+    r"|__[A-Za-z0-9]+__",
+    // snake_case and SCREAMING_CASE
+    r"|_*[A-Za-z][A-Za-z0-9]*(?:_+[A-Za-z0-9]+)+",
+    // camelCase and PascalCase
+    r"|[a-z][a-z0-9]*(?:[A-Z][a-z0-9]*)+",
+    r"|[A-Z][a-z0-9]+(?:[A-Z][a-z0-9]*)+",
+);
+
+fn placeholder_protection() -> &'static Protection {
+    static PROTECTION: OnceLock<Protection> = OnceLock::new();
+    PROTECTION.get_or_init(|| Protection::from_patterns(&[PLACEHOLDER_PATTERN]).unwrap())
+}
+
+pub(crate) fn token_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    // Translators sometimes pad tokens with spaces
+    REGEX.get_or_init(|| Regex::new(r"⟪\s*(\d+)\s*⟫").unwrap())
+}
+
+/// A text with its placeholders replaced by tokens
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Masked {
+    /// Text to translate
+    pub text: String,
+    /// Placeholders, indexed by token number
+    pub placeholders: Vec<String>,
+}
+
+/// Error for a translation that lost placeholder tokens
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaceholderMismatch {
+    /// Placeholders missing from the translation
+    pub missing: Vec<String>,
+}
+
+impl fmt::Display for PlaceholderMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "translation lost placeholders: {}",
+            self.missing.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for PlaceholderMismatch {}
+
+/// Compiled set of regions to mask
+#[derive(Debug, Clone)]
+pub struct Protection {
+    regex: Regex,
+}
+
+impl Protection {
+    /// Protection from a config; placeholders are always included
+    ///
+    /// Fails on an invalid regex in `patterns`.
+    pub fn new(config: &ProtectConfig) -> Result<Self> {
+        let mut patterns: Vec<&str> = Vec::new();
+        for pattern in &config.patterns {
+            Regex::new(pattern).with_context(|| format!("Invalid protect pattern: {}", pattern))?;
+            patterns.push(pattern);
+        }
+        if config.code_spans {
+            patterns.push(CODE_SPAN_PATTERN);
+        }
+        if config.urls {
+            patterns.push(URL_PATTERN);
+        }
+        if config.paths {
+            patterns.push(PATH_PATTERN);
+        }
+        // Placeholders before identifiers, so `{user_id}` stays one region
+        patterns.push(PLACEHOLDER_PATTERN);
+        if config.identifiers {
+            patterns.push(IDENTIFIER_PATTERN);
+        }
+        Self::from_patterns(&patterns)
+    }
+
+    /// Earlier patterns win where matches start at the same position
+    fn from_patterns(patterns: &[&str]) -> Result<Self> {
+        let alternation: Vec<String> = patterns.iter().map(|p| format!("(?:{})", p)).collect();
+        Ok(Self {
+            regex: Regex::new(&alternation.join("|"))?,
+        })
+    }
+
+    /// This protection plus inline markdown markup: `$...$` math, images
+    /// and HTML, which win over the other regions
+    pub fn with_markup(&self) -> Self {
+        Self {
+            regex: Regex::new(&format!(
+                "(?:{})|{}",
+                INLINE_MARKUP_PATTERN,
+                self.regex.as_str()
+            ))
+            .expect("built-in patterns are valid"),
+        }
+    }
+
+    /// Protected regions of a text, as byte ranges
+    ///
+    /// A rooted path glued to a word (`and/or`) is not a path.
+    fn regions<'a>(&'a self, text: &'a str) -> impl Iterator<Item = regex::Match<'a>> + 'a {
+        self.regex.find_iter(text).filter(move |m| {
+            !(m.as_str().starts_with('/')
+                && text[..m.start()]
+                    .chars()
+                    .next_back()
+                    .is_some_and(|c| c.is_ascii_alphanumeric()))
+        })
+    }
+}
+
+impl Default for Protection {
+    /// Every heuristic, no extra patterns
+    fn default() -> Self {
+        Self::new(&ProtectConfig::default()).expect("built-in patterns are valid")
+    }
+}
+
+impl Masked {
+    /// Mask the placeholders of a text
+    ///
+    /// Texts that already contain token delimiters are left unmasked, so
+    /// restoring can never confuse them with real tokens.
+    pub fn new(text: &str) -> Self {
+        Self::with_protection(text, placeholder_protection())
+    }
+
+    /// Mask placeholders and the regions `protection` covers
+    pub fn with_protection(text: &str, protection: &Protection) -> Self {
+        if text.contains([TOKEN_OPEN, TOKEN_CLOSE]) {
+            return Self::unmasked(text);
+        }
+
+        let mut placeholders = Vec::new();
+        let mut masked = String::with_capacity(text.len());
+        let mut last = 0;
+        for region in protection.regions(text) {
+            masked.push_str(&text[last..region.start()]);
+            masked.push_str(&format!(
+                "{}{}{}",
+                TOKEN_OPEN,
+                placeholders.len(),
+                TOKEN_CLOSE
+            ));
+            placeholders.push(region.as_str().to_string());
+            last = region.end();
+        }
+        masked.push_str(&text[last..]);
+        Self {
+            text: masked,
+            placeholders,
+        }
+    }
+
+    /// Mask the matches of `regex` in the text as well, each restored as
+    /// `replacement` gives it; matches it gives nothing for stay in the text
+    pub fn mask_regions<F>(&mut self, regex: &Regex, replacement: F)
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        if self.text.contains([TOKEN_OPEN, TOKEN_CLOSE]) && !self.has_placeholders() {
+            return;
+        }
+        let mut masked = String::with_capacity(self.text.len());
+        let mut last = 0;
+        for region in regex.find_iter(&self.text) {
+            let Some(restored) = replacement(region.as_str()) else {
+                continue;
+            };
+            if region.as_str().contains([TOKEN_OPEN, TOKEN_CLOSE]) {
+                continue;
+            }
+            masked.push_str(&self.text[last..region.start()]);
+            masked.push_str(&format!(
+                "{}{}{}",
+                TOKEN_OPEN,
+                self.placeholders.len(),
+                TOKEN_CLOSE
+            ));
+            self.placeholders.push(restored);
+            last = region.end();
+        }
+        masked.push_str(&self.text[last..]);
+        self.text = masked;
+    }
+
+    fn unmasked(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            placeholders: Vec::new(),
+        }
+    }
+
+    /// Check whether any placeholder was masked
+    pub fn has_placeholders(&self) -> bool {
+        !self.placeholders.is_empty()
+    }
+
+    /// Put the placeholders back into a translation of the masked text
+    ///
+    /// Fails if a token is missing from the translation; unknown tokens
+    /// are left as they are.
+    pub fn restore(&self, translation: &str) -> Result<String, PlaceholderMismatch> {
+        if !self.has_placeholders() {
+            return Ok(translation.to_string());
+        }
+
+        let mut seen = vec![false; self.placeholders.len()];
+        let restored =
+            token_regex().replace_all(translation, |caps: &regex::Captures| {
+                match caps[1].parse::<usize>().ok().filter(|&i| i < seen.len()) {
+                    Some(i) => {
+                        seen[i] = true;
+                        self.placeholders[i].clone()
+                    }
+                    None => caps[0].to_string(),
+                }
+            });
+
+        let missing: Vec<String> = self
+            .placeholders
+            .iter()
+            .zip(&seen)
+            .filter(|(_, seen)| !**seen)
+            .map(|(placeholder, _)| placeholder.clone())
+            .collect();
+        if missing.is_empty() {
+            Ok(restored.into_owned())
+        } else {
+            Err(PlaceholderMismatch { missing })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholders(text: &str) -> Vec<String> {
+        Masked::new(text).placeholders
+    }
+
+    #[test]
+    fn test_printf() {
+        assert_eq!(
+            placeholders("读取 %s 个文件，耗时 %5.2f 秒，进度 %d%%"),
+            vec!["%s", "%5.2f", "%d", "%%"]
+        );
+        assert_eq!(placeholders("用户 %(name)s 登录"), vec!["%(name)s"]);
+        assert_eq!(placeholders("左对齐 %-10s 和 %lld"), vec!["%-10s", "%lld"]);
+        assert_eq!(placeholders("加载 %v 失败: %w"), vec!["%v", "%w"]);
+        assert_eq!(placeholders("%[2]d 个 %[1]s"), vec!["%[2]d", "%[1]s"]);
+        // Prose percentages are not placeholders
+        assert!(placeholders("50% of the time, 100 % sure").is_empty());
+    }
+
+    #[test]
+    fn test_python_format() {
+        assert_eq!(
+            placeholders("你好 {name}，第 {0} 项，共 {} 项"),
+            vec!["{name}", "{0}", "{}"]
+        );
+        assert_eq!(
+            placeholders("值 {value:>8.2f} 和 {obj!r} 和 {items[0]}"),
+            vec!["{value:>8.2f}", "{obj!r}", "{items[0]}"]
+        );
+        // Set literals and prose in braces are left alone
+        assert!(placeholders("集合 {a, b} 或 {任意 文本}").is_empty());
+    }
+
+    #[test]
+    fn test_rust_format() {
+        assert_eq!(
+            placeholders("调试输出 {:?}，宽度 {:>width$}，十六进制 {:#x}"),
+            vec!["{:?}", "{:>width$}", "{:#x}"]
+        );
+        assert_eq!(placeholders("错误：{err}"), vec!["{err}"]);
+    }
+
+    #[test]
+    fn test_js_template() {
+        assert_eq!(
+            placeholders("欢迎 ${user.name}，共 ${count} 条消息"),
+            vec!["${user.name}", "${count}"]
+        );
+        assert_eq!(
+            placeholders("参见 {@link Config.load} 和 {@inheritDoc}"),
+            vec!["{@link Config.load}", "{@inheritDoc}"]
+        );
+        assert_eq!(
+            placeholders("参见 <see cref=\"Config\"/>，不会返回 <c>null</c>"),
+            vec!["<see cref=\"Config\"/>", "<c>null</c>"]
+        );
+    }
+
+    #[test]
+    fn test_message_interpolation() {
+        assert_eq!(
+            placeholders("欢迎 {{ user.name }}，您有 %{count} 条消息"),
+            vec!["{{ user.name }}", "%{count}"]
+        );
+        assert_eq!(
+            placeholders("完成 {ratio, number, percent}，截至 {day, date, short}"),
+            vec!["{ratio, number, percent}", "{day, date, short}"]
+        );
+    }
+
+    #[test]
+    fn test_mobile_format_specifiers() {
+        assert_eq!(
+            placeholders("%1$s 分享了 %2$d 张照片给 %@，共 %#@photos@"),
+            vec!["%1$s", "%2$d", "%@", "%#@photos@"]
+        );
+    }
+
+    #[test]
+    fn test_mask_and_restore() {
+        let masked = Masked::new("读取 %s 个文件到 {path}");
+        assert_eq!(masked.text, "读取 ⟪0⟫ 个文件到 ⟪1⟫");
+
+        // Tokens may be reordered and padded by the translator
+        assert_eq!(
+            masked.restore("Read into ⟪ 1 ⟫ the ⟪0⟫ files").unwrap(),
+            "Read into {path} the %s files"
+        );
+
+        let error = masked.restore("Read ⟪0⟫ files").unwrap_err();
+        assert_eq!(error.missing, vec!["{path}"]);
+        assert_eq!(masked.restore("⟪0⟫ ⟪1⟫ ⟪7⟫").unwrap(), "%s {path} ⟪7⟫");
+    }
+
+    fn protected(text: &str) -> Vec<String> {
+        Masked::with_protection(text, &Protection::default()).placeholders
+    }
+
+    #[test]
+    fn test_protects_code_urls_and_paths() {
+        assert_eq!(
+            protected("调用 `load(path)` 或 ``a`b`` 读取"),
+            vec!["`load(path)`", "``a`b``"]
+        );
+        assert_eq!(
+            protected("详见https://example.com/docs?page=1。"),
+            vec!["https://example.com/docs?page=1"]
+        );
+        assert_eq!(
+            protected("写入 /var/log/app.log，读取 src/utils/io.py 和 ./build"),
+            vec!["/var/log/app.log", "src/utils/io.py", "./build"]
+        );
+        assert_eq!(
+            protected(r"保存到 C:\temp\out.txt"),
+            vec![r"C:\temp\out.txt"]
+        );
+        // Neither prose with a slash nor plain words
+        assert!(protected("读/写 and/or 数据").is_empty());
+    }
+
+    #[test]
+    fn test_protects_identifiers() {
+        assert_eq!(
+            protected("把 user_name 传给 getValue，再调用 os.path.join 和 run()"),
+            vec!["user_name", "getValue", "os.path.join", "run()"]
+        );
+        assert_eq!(
+            protected("重写__init__和HashMap，MAX_SIZE 常量"),
+            vec!["__init__", "HashMap", "MAX_SIZE"]
+        );
+        // A placeholder stays one region
+        assert_eq!(protected("用户 {user_id} 登录"), vec!["{user_id}"]);
+    }
+
+    #[test]
+    fn test_protects_markdown_markup() {
+        let protection = Protection::default().with_markup();
+        let masked = Masked::with_protection(
+            "当 $x_{i}$ 趋近 $$\\infty$$ 时见 ![图](a.png) 和 <b>注意</b>，售价 $5",
+            &protection,
+        );
+        assert_eq!(
+            masked.placeholders,
+            vec!["$x_{i}$", "$$\\infty$$", "![图](a.png)", "<b>", "</b>"]
+        );
+        // Without markup, only the placeholder inside the math is seen
+        assert_eq!(protected("当 $x_{i}$ 趋近"), vec!["{i}"]);
+
+        let masked = Masked::with_protection(
+            "参见 :ref:`安装 <install>` 和 `官网 <https://a.org>`_，当前版本 |version|",
+            &protection,
+        );
+        assert_eq!(
+            masked.placeholders,
+            vec![
+                ":ref:`安装 <install>`",
+                "`官网 <https://a.org>`_",
+                "|version|"
+            ]
+        );
+
+        let masked = Masked::with_protection(
+            "欢迎 {{ user.name }}，运行 <code>make all</code> 即可",
+            &protection,
+        );
+        assert_eq!(
+            masked.placeholders,
+            vec!["{{ user.name }}", "<code>make all</code>"]
+        );
+
+        let masked = Masked::with_protection(
+            r"如图~\ref{fig:a} 所示，\textbf{误差} $e$ 见 \cite[p.~3]{smith2020}\\",
+            &protection,
+        );
+        assert_eq!(
+            masked.placeholders,
+            vec![
+                r"\ref{fig:a}",
+                r"\textbf",
+                "$e$",
+                r"\cite[p.~3]{smith2020}",
+                r"\\"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_configured_protection() {
+        let config = ProtectConfig {
+            identifiers: false,
+            patterns: vec![r"JIRA-\d+".to_string()],
+            ..ProtectConfig::default()
+        };
+        let protection = Protection::new(&config).unwrap();
+        let masked = Masked::with_protection("修复 JIRA-42：user_name 为空", &protection);
+        assert_eq!(masked.placeholders, vec!["JIRA-42"]);
+        assert_eq!(
+            masked.restore("Fix ⟪0⟫: empty user_name").unwrap(),
+            "Fix JIRA-42: empty user_name"
+        );
+
+        let invalid = ProtectConfig {
+            patterns: vec!["(".to_string()],
+            ..ProtectConfig::default()
+        };
+        assert!(Protection::new(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_text_without_placeholders() {
+        let masked = Masked::new("普通注释");
+        assert!(!masked.has_placeholders());
+        assert_eq!(masked.restore("Plain comment").unwrap(), "Plain comment");
+
+        // Existing token delimiters disable masking
+        let masked = Masked::new("⟪0⟫ %s");
+        assert_eq!(masked.text, "⟪0⟫ %s");
+        assert!(!masked.has_placeholders());
+    }
+}
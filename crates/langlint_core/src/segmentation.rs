@@ -0,0 +1,292 @@
+//! Splitting unit text into the pieces sent to a translator
+//!
+//! Some backends translate a long docstring far better sentence by sentence
+//! than in one request. [`Segmentation::split`] cuts a text into sentences
+//! or paragraphs and keeps what lies between them (spaces, line breaks,
+//! blank lines), so [`Segmented::rebuild`] puts the translations back in
+//! the same layout. [`SegmentationRules`] picks the strategy per unit type.
+
+use crate::UnitType;
+use anyhow::Result;
+use std::collections::{BTreeMap, HashMap};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// How a unit's text is split before translating
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Segmentation {
+    /// The whole text in one piece
+    #[default]
+    Whole,
+    /// Paragraphs separated by blank lines
+    Paragraph,
+    /// Sentences (Unicode sentence boundaries), lines wrapped inside a
+    /// sentence staying with it
+    Sentence,
+}
+
+impl Segmentation {
+    /// Parse a strategy name: `whole`, `paragraph` or `sentence`
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "whole" | "unit" => Ok(Self::Whole),
+            "paragraph" => Ok(Self::Paragraph),
+            "sentence" => Ok(Self::Sentence),
+            _ => anyhow::bail!(
+                "Unknown segmentation '{}' (expected whole, paragraph or sentence)",
+                name
+            ),
+        }
+    }
+
+    /// Split `text` into the pieces to translate
+    pub fn split(self, text: &str) -> Segmented {
+        let bounds: Vec<usize> = match self {
+            Self::Whole => {
+                return Segmented {
+                    texts: vec![text.to_string()],
+                    separators: vec![String::new(), String::new()],
+                }
+            }
+            Self::Paragraph => paragraph_bounds(text),
+            Self::Sentence => {
+                // Single line breaks wrap a sentence rather than end it
+                let unwrapped = unwrap_lines(text);
+                unwrapped
+                    .split_sentence_bound_indices()
+                    .map(|(start, _)| start)
+                    .skip(1)
+                    .collect()
+            }
+        };
+
+        let mut texts = Vec::new();
+        let mut separators = vec![String::new()];
+        let starts = std::iter::once(0).chain(bounds.iter().copied());
+        let ends = bounds.iter().copied().chain(std::iter::once(text.len()));
+        for (start, end) in starts.zip(ends) {
+            let piece = &text[start..end];
+            let trimmed = piece.trim();
+            let separator = separators.last_mut().expect("separators start non-empty");
+            if trimmed.is_empty() {
+                separator.push_str(piece);
+                continue;
+            }
+            let leading = piece.len() - piece.trim_start().len();
+            separator.push_str(&piece[..leading]);
+            texts.push(trimmed.to_string());
+            separators.push(piece[leading + trimmed.len()..].to_string());
+        }
+        Segmented { texts, separators }
+    }
+}
+
+/// Starts of the paragraphs after the first: the first non-blank line after
+/// one or more blank lines
+fn paragraph_bounds(text: &str) -> Vec<usize> {
+    let mut bounds = Vec::new();
+    let mut offset = 0;
+    let mut after_blank = false;
+    let mut seen_text = false;
+    for line in text.split_inclusive('\n') {
+        if line.trim().is_empty() {
+            after_blank = seen_text;
+        } else {
+            if after_blank {
+                bounds.push(offset);
+                after_blank = false;
+            }
+            seen_text = true;
+        }
+        offset += line.len();
+    }
+    bounds
+}
+
+/// `text` with line breaks not next to another one turned into spaces,
+/// keeping byte offsets
+fn unwrap_lines(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let is_break = |i: usize| bytes.get(i).is_some_and(|&b| b == b'\n' || b == b'\r');
+    text.char_indices()
+        .map(|(i, c)| {
+            let blank_line = (c == '\n' && (is_break(i + 1) || i > 0 && is_break(i - 1)))
+                || (c == '\r' && is_break(i + 1) && (is_break(i + 2) || i > 0 && is_break(i - 1)));
+            match c {
+                '\n' | '\r' if !blank_line => ' ',
+                _ => c,
+            }
+        })
+        .collect()
+}
+
+/// A text split into pieces, with what lies between them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segmented {
+    texts: Vec<String>,
+    /// Text before the first piece, between pieces and after the last one
+    separators: Vec<String>,
+}
+
+impl Segmented {
+    /// The pieces to translate
+    pub fn texts(&self) -> &[String] {
+        &self.texts
+    }
+
+    /// Join translated pieces in the original layout
+    ///
+    /// Pieces without a translation keep their original text. Sentences
+    /// written without a space between them (`第一句。第二句。`) get one when
+    /// translated into a spaced script, and lose it the other way round.
+    pub fn rebuild(&self, translated: &[String]) -> String {
+        let mut result = self.separators[0].clone();
+        for (i, original) in self.texts.iter().enumerate() {
+            let text = translated.get(i).unwrap_or(original);
+            if i > 0 {
+                let separator = &self.separators[i];
+                let previous = result.chars().next_back();
+                let next = text.chars().next();
+                let unspaced = |c: Option<char>| c.is_some_and(is_unspaced_script);
+                if separator.is_empty() && !unspaced(previous) && !unspaced(next) {
+                    result.push(' ');
+                } else if !separator.contains('\n')
+                    && separator.chars().all(char::is_whitespace)
+                    && unspaced(previous)
+                    && unspaced(next)
+                {
+                    // No space between Chinese or Japanese sentences
+                } else {
+                    result.push_str(separator);
+                }
+            }
+            result.push_str(text);
+        }
+        result.push_str(&self.separators[self.texts.len()]);
+        result
+    }
+}
+
+/// Check if a character belongs to a script written without spaces between
+/// sentences (CJK ideographs, kana, fullwidth punctuation)
+fn is_unspaced_script(c: char) -> bool {
+    matches!(c,
+        '\u{3000}'..='\u{30FF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{FF00}'..='\u{FFEF}')
+}
+
+/// Segmentation per unit type, e.g. sentences for docstrings and whole
+/// units for everything else
+#[derive(Debug, Clone, Default)]
+pub struct SegmentationRules {
+    default: Segmentation,
+    by_type: HashMap<UnitType, Segmentation>,
+}
+
+impl SegmentationRules {
+    /// Rules from the config's `segmentation` table: unit type names
+    /// (`docstring`, `comment`, `text_node`, ...) or `*` for the rest
+    pub fn from_config(rules: &BTreeMap<String, String>) -> Result<Self> {
+        let mut parsed = Self::default();
+        for (key, name) in rules {
+            let segmentation = Segmentation::parse(name)?;
+            if key.trim() == "*" {
+                parsed.default = segmentation;
+                continue;
+            }
+            let unit_type: UnitType =
+                serde_json::from_value(serde_json::Value::from(key.trim().to_lowercase()))
+                    .map_err(|_| {
+                        anyhow::anyhow!(
+                            "Unknown unit type '{}' in segmentation (expected comment, docstring, \
+                             string_literal, text_node, metadata or *)",
+                            key
+                        )
+                    })?;
+            parsed.by_type.insert(unit_type, segmentation);
+        }
+        Ok(parsed)
+    }
+
+    /// Segmentation of units of `unit_type`
+    pub fn for_unit(&self, unit_type: UnitType) -> Segmentation {
+        self.by_type
+            .get(&unit_type)
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sentences() {
+        let text = "Compute the loss for a batch,\nignoring padding. Returns e.g. a float.\n\nRaises ValueError.";
+        let segmented = Segmentation::Sentence.split(text);
+        assert_eq!(
+            segmented.texts(),
+            [
+                "Compute the loss for a batch,\nignoring padding.",
+                "Returns e.g. a float.",
+                "Raises ValueError."
+            ]
+        );
+        assert_eq!(segmented.rebuild(segmented.texts()), text);
+        assert_eq!(
+            segmented.rebuild(&[
+                "计算损失。".into(),
+                "返回浮点数。".into(),
+                "抛出异常。".into()
+            ]),
+            "计算损失。返回浮点数。\n\n抛出异常。"
+        );
+
+        let chinese = Segmentation::Sentence.split("  计算损失。返回浮点数！\n");
+        assert_eq!(chinese.texts(), ["计算损失。", "返回浮点数！"]);
+        assert_eq!(
+            chinese.rebuild(&["Compute the loss.".into(), "Return a float!".into()]),
+            "  Compute the loss. Return a float!\n"
+        );
+    }
+
+    #[test]
+    fn test_paragraphs_and_whole() {
+        let text = "第一段第一行\n第一段第二行\n\n\n第二段\n";
+        let segmented = Segmentation::Paragraph.split(text);
+        assert_eq!(segmented.texts(), ["第一段第一行\n第一段第二行", "第二段"]);
+        assert_eq!(
+            segmented.rebuild(&["First.".into()]),
+            "First.\n\n\n第二段\n"
+        );
+
+        let whole = Segmentation::Whole.split(" 整个单元。 ");
+        assert_eq!(whole.texts(), [" 整个单元。 "]);
+        assert_eq!(whole.rebuild(&["Whole.".into()]), "Whole.");
+        assert!(Segmentation::Sentence.split(" \n").texts().is_empty());
+    }
+
+    #[test]
+    fn test_rules() {
+        let rules: BTreeMap<String, String> = [
+            ("docstring".to_string(), "sentence".to_string()),
+            ("*".to_string(), "paragraph".to_string()),
+        ]
+        .into();
+        let rules = SegmentationRules::from_config(&rules).unwrap();
+        assert_eq!(rules.for_unit(UnitType::Docstring), Segmentation::Sentence);
+        assert_eq!(rules.for_unit(UnitType::Comment), Segmentation::Paragraph);
+        assert_eq!(
+            SegmentationRules::default().for_unit(UnitType::TextNode),
+            Segmentation::Whole
+        );
+
+        let bad_type: BTreeMap<String, String> =
+            [("docs".to_string(), "sentence".to_string())].into();
+        assert!(SegmentationRules::from_config(&bad_type).is_err());
+        assert!(Segmentation::parse("word").is_err());
+    }
+}
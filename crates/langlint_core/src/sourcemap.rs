@@ -0,0 +1,169 @@
+//! Line maps between an original file and its translation
+//!
+//! Translations can change line counts (reflowed comments, longer
+//! docstrings), so a `<file>.langlint-sourcemap.json` next to a translated
+//! file maps every original line number to its line in the translation,
+//! letting coverage reports, linter findings and review comments be moved
+//! between the two.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use similar::{DiffOp, TextDiff};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Suffix appended to a file name to form its source map file name
+pub const SOURCE_MAP_SUFFIX: &str = ".langlint-sourcemap.json";
+
+/// Current source map format version
+const SOURCE_MAP_VERSION: u32 = 1;
+
+/// Original to translated line numbers of one file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SourceMap {
+    pub version: u32,
+    pub original_lines: u32,
+    pub translated_lines: u32,
+    /// Translated line of each original line: `lines[0]` is where line 1 went
+    pub lines: Vec<u32>,
+}
+
+impl SourceMap {
+    /// Build the map from a line diff of the two contents
+    ///
+    /// Unchanged lines map exactly. Lines of a changed region map to the
+    /// line at the same relative position in its translation, and deleted
+    /// lines to the line that follows them.
+    pub fn from_contents(original: &str, translated: &str) -> Self {
+        let original_lines = line_count(original);
+        let translated_lines = line_count(translated);
+        let mut lines = Vec::with_capacity(original_lines as usize);
+
+        let diff = TextDiff::from_lines(original, translated);
+        for op in diff.ops() {
+            let (old, new) = (op.old_range(), op.new_range());
+            match op {
+                DiffOp::Equal { .. } => lines.extend(new.map(|line| line as u32 + 1)),
+                DiffOp::Delete { .. } | DiffOp::Insert { .. } | DiffOp::Replace { .. } => {
+                    for offset in 0..old.len() {
+                        let line = if new.is_empty() {
+                            new.start
+                        } else {
+                            new.start + offset * new.len() / old.len()
+                        };
+                        lines.push((line as u32 + 1).min(translated_lines.max(1)));
+                    }
+                }
+            }
+        }
+
+        Self {
+            version: SOURCE_MAP_VERSION,
+            original_lines,
+            translated_lines,
+            lines,
+        }
+    }
+
+    /// Translated line of a 1-based original line
+    pub fn translated_line(&self, original: u32) -> Option<u32> {
+        let index = original.checked_sub(1)? as usize;
+        self.lines.get(index).copied()
+    }
+
+    /// First original line mapped to a 1-based translated line, or to the
+    /// closest line before it
+    pub fn original_line(&self, translated: u32) -> Option<u32> {
+        if translated == 0 || translated > self.translated_lines {
+            return None;
+        }
+        let index = self.lines.partition_point(|&line| line < translated);
+        match self.lines.get(index) {
+            Some(&line) if line == translated => Some(index as u32 + 1),
+            _ => Some(index.max(1) as u32),
+        }
+    }
+
+    /// Source map file path for a translated file
+    pub fn path_for(file: &Path) -> PathBuf {
+        let mut name = file.as_os_str().to_os_string();
+        name.push(SOURCE_MAP_SUFFIX);
+        PathBuf::from(name)
+    }
+
+    /// Load a source map file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read source map: {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Invalid source map: {}", path.display()))
+    }
+
+    /// Write the source map file
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let content = serde_json::to_string(self)?;
+        fs::write(path, content)
+            .with_context(|| format!("Failed to write source map: {}", path.display()))
+    }
+}
+
+/// Number of lines, counting a last line without a newline
+fn line_count(content: &str) -> u32 {
+    content.lines().count() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_identical_contents() {
+        let content = "a\nb\nc\n";
+        let map = SourceMap::from_contents(content, content);
+        assert_eq!(map.lines, vec![1, 2, 3]);
+        assert_eq!(map.original_line(2), Some(2));
+    }
+
+    #[test]
+    fn test_reflowed_comment_shifts_later_lines() {
+        let original = "# 这是一个很长的注释\ndef f():\n    pass\n";
+        let translated = "# This is a very long comment\n# that was reflowed\ndef f():\n    pass\n";
+        let map = SourceMap::from_contents(original, translated);
+
+        assert_eq!((map.original_lines, map.translated_lines), (3, 4));
+        assert_eq!(map.lines, vec![1, 3, 4]);
+        assert_eq!(map.translated_line(2), Some(3));
+        assert_eq!(map.translated_line(4), None);
+        assert_eq!(map.original_line(3), Some(2));
+        // The added line belongs to the comment
+        assert_eq!(map.original_line(2), Some(1));
+    }
+
+    #[test]
+    fn test_shortened_docstring() {
+        let original = "\"\"\"\n第一行\n第二行\n\"\"\"\nx = 1\n";
+        let translated = "\"\"\"\nFirst and second line\n\"\"\"\nx = 1\n";
+        let map = SourceMap::from_contents(original, translated);
+
+        assert_eq!(map.lines, vec![1, 2, 2, 3, 4]);
+        assert_eq!(map.original_line(4), Some(5));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("app.py");
+        let map = SourceMap::from_contents("a\nb\n", "a\nx\ny\nb\n");
+        assert_eq!(map.lines, vec![1, 4]);
+
+        let path = SourceMap::path_for(&file);
+        assert!(path
+            .to_string_lossy()
+            .ends_with("app.py.langlint-sourcemap.json"));
+        map.save(&path).unwrap();
+        assert_eq!(SourceMap::load(&path).unwrap(), map);
+    }
+}
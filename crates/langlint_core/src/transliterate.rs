@@ -0,0 +1,605 @@
+//! Rule-based transliteration
+//!
+//! [`to_ascii`] folds Latin letters with diacritics (`é` → `e`, `ß` → `ss`)
+//! and romanizes Cyrillic and Greek letter by letter. [`transliterate`]
+//! also romanizes Chinese (toneless pinyin, one reading per character),
+//! Japanese kana (Hepburn romaji) and Cyrillic by ISO 9, for names and
+//! identifiers that should be spelled out rather than translated. Scripts
+//! without a table here cannot be transliterated.
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::config::TransliterateConfig;
+use crate::placeholders::Masked;
+use crate::types::{TranslatableUnit, UnitType};
+
+/// Pinyin readings, one syllable per line followed by its characters
+const PINYIN: &str = include_str!("../data/pinyin.txt");
+
+/// Romanization system of [`transliterate`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Scheme {
+    /// Latin diacritics folded, Cyrillic and Greek romanized, as [`to_ascii`]
+    #[default]
+    Ascii,
+    /// Chinese characters as toneless pinyin (`张伟` → `Zhang Wei`)
+    Pinyin,
+    /// Japanese kana as Hepburn romaji (`テスト` → `tesuto`)
+    Romaji,
+    /// Cyrillic by ISO 9 (`Щука` → `Ŝuka`), keeping its diacritics
+    Iso9,
+}
+
+impl Scheme {
+    /// Parse a scheme name: `ascii`, `pinyin`, `romaji` or `iso9`
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.trim().to_lowercase().replace(['-', ' '], "").as_str() {
+            "ascii" => Ok(Self::Ascii),
+            "pinyin" => Ok(Self::Pinyin),
+            "romaji" | "hepburn" => Ok(Self::Romaji),
+            "iso9" => Ok(Self::Iso9),
+            other => anyhow::bail!(
+                "Unknown transliteration scheme '{}' (expected pinyin, romaji, iso9 or ascii)",
+                other
+            ),
+        }
+    }
+}
+
+/// Transliterate a text by `scheme`
+///
+/// Pinyin and romaji fold other letters like [`to_ascii`]; ISO 9 leaves
+/// Latin letters as they are. Returns `None` if the text contains
+/// characters the scheme does not cover.
+pub fn transliterate(text: &str, scheme: Scheme) -> Option<String> {
+    match scheme {
+        Scheme::Ascii => to_ascii(text),
+        Scheme::Pinyin => pinyin(text),
+        Scheme::Romaji => romaji(text),
+        Scheme::Iso9 => text
+            .chars()
+            .map(|c| match iso9(c.to_lowercase().next().unwrap_or(c)) {
+                Some(latin) if c.is_uppercase() => Some(capitalized(latin)),
+                Some(latin) => Some(latin.to_string()),
+                None if c.is_ascii() || !is_cyrillic(c) => Some(c.to_string()),
+                None => None,
+            })
+            .collect(),
+    }
+}
+
+/// Units and regions of them to transliterate instead of translating
+#[derive(Debug, Clone)]
+pub struct Transliteration {
+    scheme: Scheme,
+    patterns: Option<Regex>,
+    unit_types: Vec<UnitType>,
+}
+
+impl Transliteration {
+    /// Transliteration from the config's `transliterate` table
+    ///
+    /// Fails on an unknown scheme or unit type, or an invalid regex.
+    pub fn from_config(config: &TransliterateConfig) -> Result<Self> {
+        let scheme = Scheme::parse(&config.scheme)?;
+        let patterns = if config.patterns.is_empty() {
+            None
+        } else {
+            for pattern in &config.patterns {
+                Regex::new(pattern)
+                    .with_context(|| format!("Invalid transliterate pattern: {}", pattern))?;
+            }
+            let alternation: Vec<String> = config
+                .patterns
+                .iter()
+                .map(|p| format!("(?:{})", p))
+                .collect();
+            Some(Regex::new(&alternation.join("|"))?)
+        };
+        let unit_types = config
+            .unit_types
+            .iter()
+            .map(|name| {
+                serde_json::from_value(serde_json::Value::from(name.trim().to_lowercase())).map_err(
+                    |_| {
+                        anyhow::anyhow!(
+                            "Unknown unit type '{}' in transliterate (expected comment, \
+                             docstring, string_literal, text_node or metadata)",
+                            name
+                        )
+                    },
+                )
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            scheme,
+            patterns,
+            unit_types,
+        })
+    }
+
+    /// A whole unit transliterated, if its type is configured and every
+    /// character of it can be
+    pub fn unit(&self, unit: &TranslatableUnit) -> Option<String> {
+        if !self.unit_types.contains(&unit.unit_type) {
+            return None;
+        }
+        transliterate(&unit.content, self.scheme)
+    }
+
+    /// Mask the pattern matches of a masked text, to be restored
+    /// transliterated; matches that cannot be are left to translate
+    pub fn mask(&self, masked: &mut Masked) {
+        if let Some(patterns) = &self.patterns {
+            masked.mask_regions(patterns, |region| transliterate(region, self.scheme));
+        }
+    }
+}
+
+/// Transliterate a text to ASCII
+///
+/// Returns `None` if it contains characters no table covers.
+pub fn to_ascii(text: &str) -> Option<String> {
+    let mut output = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_ascii() {
+            output.push(c);
+            continue;
+        }
+        let lower = c.to_lowercase().next().unwrap_or(c);
+        let ascii = latin(lower)
+            .or_else(|| cyrillic(lower))
+            .or_else(|| greek(lower))?;
+        if c != lower {
+            // Keep capitals: Ж → Zh, Æ → Ae
+            let mut chars = ascii.chars();
+            if let Some(first) = chars.next() {
+                output.push(first.to_ascii_uppercase());
+                output.push_str(chars.as_str());
+            }
+        } else {
+            output.push_str(ascii);
+        }
+    }
+    Some(output)
+}
+
+/// Check if every character of a text can be transliterated
+pub fn can_transliterate(text: &str) -> bool {
+    to_ascii(text).is_some()
+}
+
+/// A romanization with its first letter capitalized
+fn capitalized(latin: &str) -> String {
+    let mut chars = latin.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn pinyin_table() -> &'static HashMap<char, &'static str> {
+    static TABLE: OnceLock<HashMap<char, &'static str>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = HashMap::new();
+        for line in PINYIN.lines().filter(|line| !line.starts_with('#')) {
+            if let Some((syllable, chars)) = line.split_once(' ') {
+                table.extend(chars.chars().map(|c| (c, syllable)));
+            }
+        }
+        table
+    })
+}
+
+/// Chinese characters as capitalized syllables, separated by spaces from
+/// each other and from neighbouring letters and digits
+fn pinyin(text: &str) -> Option<String> {
+    let table = pinyin_table();
+    let mut output = String::with_capacity(text.len() * 2);
+    let mut after_syllable = false;
+    for c in text.chars() {
+        if let Some(syllable) = table.get(&c) {
+            if output
+                .chars()
+                .next_back()
+                .is_some_and(char::is_alphanumeric)
+            {
+                output.push(' ');
+            }
+            output.push_str(&capitalized(syllable));
+            after_syllable = true;
+            continue;
+        }
+        // Name separators: 爱新觉罗·玄烨
+        let folded = match c {
+            '·' | '・' => " ".to_string(),
+            _ => to_ascii(&c.to_string())?,
+        };
+        if after_syllable && folded.starts_with(char::is_alphanumeric) {
+            output.push(' ');
+        }
+        output.push_str(&folded);
+        after_syllable = false;
+    }
+    Some(output)
+}
+
+/// Japanese kana as Hepburn romaji, long vowels doubled (`スーパー` →
+/// `suupaa`); other letters folded like [`to_ascii`]
+fn romaji(text: &str) -> Option<String> {
+    let mut syllables: Vec<String> = Vec::new();
+    // A small っ doubles the next consonant
+    let mut double = false;
+    for c in text.chars() {
+        // Katakana as hiragana
+        let kana = match c {
+            '\u{30a1}'..='\u{30f6}' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+            _ => c,
+        };
+        let syllable = match kana {
+            'っ' => {
+                double = true;
+                continue;
+            }
+            'ー' => {
+                let vowel = syllables.last().and_then(|last| last.chars().next_back())?;
+                vowel.to_string()
+            }
+            // Small ゃゅょ: きゃ → kya, しゃ → sha
+            'ゃ' | 'ゅ' | 'ょ'
+                if syllables
+                    .last()
+                    .is_some_and(|last| last.len() > 1 && last.ends_with('i')) =>
+            {
+                let last = syllables.pop()?;
+                let stem = &last[..last.len() - 1];
+                let glide = if matches!(stem, "sh" | "ch" | "j") {
+                    ""
+                } else {
+                    "y"
+                };
+                format!("{}{}{}", stem, glide, &kana_syllable(kana)?[1..])
+            }
+            // Small vowels of loanwords: ファ → fa, ティ → ti, ウィ → wi
+            'ぁ' | 'ぃ' | 'ぅ' | 'ぇ' | 'ぉ' if !syllables.is_empty() => {
+                let last = syllables.pop()?;
+                let stem = match last.as_str() {
+                    "u" => "w",
+                    _ => last.trim_end_matches(['a', 'i', 'u', 'e', 'o']),
+                };
+                format!("{}{}", stem, kana_syllable(kana)?)
+            }
+            _ => match kana_syllable(kana) {
+                Some(syllable) => syllable.to_string(),
+                None if c == '・' => " ".to_string(),
+                None => to_ascii(&c.to_string())?,
+            },
+        };
+        if std::mem::take(&mut double) {
+            match syllable.strip_prefix("ch") {
+                Some(_) => syllables.push("t".to_string()),
+                None => syllables.extend(syllable.chars().next().map(String::from)),
+            }
+        }
+        syllables.push(syllable);
+    }
+    Some(syllables.concat())
+}
+
+/// Hepburn romanization of a hiragana character
+fn kana_syllable(c: char) -> Option<&'static str> {
+    Some(match c {
+        'あ' | 'ぁ' => "a",
+        'い' | 'ぃ' | 'ゐ' => "i",
+        'う' | 'ぅ' => "u",
+        'え' | 'ぇ' | 'ゑ' => "e",
+        'お' | 'ぉ' | 'を' => "o",
+        'か' | 'ゕ' => "ka",
+        'き' => "ki",
+        'く' => "ku",
+        'け' | 'ゖ' => "ke",
+        'こ' => "ko",
+        'が' => "ga",
+        'ぎ' => "gi",
+        'ぐ' => "gu",
+        'げ' => "ge",
+        'ご' => "go",
+        'さ' => "sa",
+        'し' => "shi",
+        'す' => "su",
+        'せ' => "se",
+        'そ' => "so",
+        'ざ' => "za",
+        'じ' | 'ぢ' => "ji",
+        'ず' | 'づ' => "zu",
+        'ぜ' => "ze",
+        'ぞ' => "zo",
+        'た' => "ta",
+        'ち' => "chi",
+        'つ' => "tsu",
+        'て' => "te",
+        'と' => "to",
+        'だ' => "da",
+        'で' => "de",
+        'ど' => "do",
+        'な' => "na",
+        'に' => "ni",
+        'ぬ' => "nu",
+        'ね' => "ne",
+        'の' => "no",
+        'は' => "ha",
+        'ひ' => "hi",
+        'ふ' => "fu",
+        'へ' => "he",
+        'ほ' => "ho",
+        'ば' => "ba",
+        'び' => "bi",
+        'ぶ' => "bu",
+        'べ' => "be",
+        'ぼ' => "bo",
+        'ぱ' => "pa",
+        'ぴ' => "pi",
+        'ぷ' => "pu",
+        'ぺ' => "pe",
+        'ぽ' => "po",
+        'ま' => "ma",
+        'み' => "mi",
+        'む' => "mu",
+        'め' => "me",
+        'も' => "mo",
+        'や' | 'ゃ' => "ya",
+        'ゆ' | 'ゅ' => "yu",
+        'よ' | 'ょ' => "yo",
+        'ら' => "ra",
+        'り' => "ri",
+        'る' => "ru",
+        'れ' => "re",
+        'ろ' => "ro",
+        'わ' | 'ゎ' => "wa",
+        'ん' => "n",
+        'ゔ' => "vu",
+        'ヷ' => "va",
+        'ヸ' => "vi",
+        'ヹ' => "ve",
+        'ヺ' => "vo",
+        _ => return None,
+    })
+}
+
+fn is_cyrillic(c: char) -> bool {
+    ('\u{400}'..='\u{52f}').contains(&c)
+}
+
+/// ISO 9:1995 romanization of a lowercase Cyrillic letter
+fn iso9(c: char) -> Option<&'static str> {
+    Some(match c {
+        'а' => "a",
+        'б' => "b",
+        'в' => "v",
+        'г' => "g",
+        'ґ' => "g̀",
+        'д' => "d",
+        'ѓ' => "ǵ",
+        'ђ' => "đ",
+        'е' => "e",
+        'ё' => "ë",
+        'є' => "ê",
+        'ж' => "ž",
+        'з' => "z",
+        'ѕ' => "ẑ",
+        'и' => "i",
+        'і' => "ì",
+        'ї' => "ï",
+        'й' => "j",
+        'ј' => "ǰ",
+        'к' => "k",
+        'л' => "l",
+        'љ' => "l̂",
+        'м' => "m",
+        'н' => "n",
+        'њ' => "n̂",
+        'о' => "o",
+        'п' => "p",
+        'р' => "r",
+        'с' => "s",
+        'т' => "t",
+        'ќ' => "ḱ",
+        'ћ' => "ć",
+        'у' => "u",
+        'ў' => "ŭ",
+        'ф' => "f",
+        'х' => "h",
+        'ц' => "c",
+        'ч' => "č",
+        'џ' => "d̂",
+        'ш' => "š",
+        'щ' => "ŝ",
+        'ъ' => "ʺ",
+        'ы' => "y",
+        'ь' => "ʹ",
+        'э' => "è",
+        'ю' => "û",
+        'я' => "â",
+        _ => return None,
+    })
+}
+
+fn latin(c: char) -> Option<&'static str> {
+    Some(match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'æ' => "ae",
+        'ç' | 'ć' | 'č' | 'ĉ' => "c",
+        'ď' | 'đ' | 'ð' => "d",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ė' | 'ę' | 'ě' => "e",
+        'ğ' | 'ĝ' | 'ģ' => "g",
+        'ì' | 'í' | 'î' | 'ï' | 'ī' | 'į' | 'ı' => "i",
+        'ķ' => "k",
+        'ł' | 'ľ' | 'ĺ' | 'ļ' => "l",
+        'ñ' | 'ń' | 'ň' | 'ņ' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ő' => "o",
+        'œ' => "oe",
+        'ŕ' | 'ř' => "r",
+        'ś' | 'š' | 'ş' | 'ș' => "s",
+        'ß' => "ss",
+        'ť' | 'ţ' | 'ț' => "t",
+        'þ' => "th",
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' | 'ű' | 'ų' => "u",
+        'ý' | 'ÿ' => "y",
+        'ź' | 'ż' | 'ž' => "z",
+        _ => return None,
+    })
+}
+
+fn cyrillic(c: char) -> Option<&'static str> {
+    Some(match c {
+        'а' => "a",
+        'б' => "b",
+        'в' => "v",
+        'г' | 'ґ' => "g",
+        'д' => "d",
+        'е' => "e",
+        'ё' => "yo",
+        'є' => "ye",
+        'ж' => "zh",
+        'з' => "z",
+        'и' | 'і' => "i",
+        'ї' => "yi",
+        'й' => "y",
+        'к' => "k",
+        'л' => "l",
+        'м' => "m",
+        'н' => "n",
+        'о' => "o",
+        'п' => "p",
+        'р' => "r",
+        'с' => "s",
+        'т' => "t",
+        'у' => "u",
+        'ф' => "f",
+        'х' => "kh",
+        'ц' => "ts",
+        'ч' => "ch",
+        'ш' => "sh",
+        'щ' => "shch",
+        'ъ' | 'ь' => "",
+        'ы' => "y",
+        'э' => "e",
+        'ю' => "yu",
+        'я' => "ya",
+        _ => return None,
+    })
+}
+
+fn greek(c: char) -> Option<&'static str> {
+    Some(match c {
+        'α' | 'ά' => "a",
+        'β' => "v",
+        'γ' => "g",
+        'δ' => "d",
+        'ε' | 'έ' => "e",
+        'ζ' => "z",
+        'η' | 'ή' => "i",
+        'θ' => "th",
+        'ι' | 'ί' | 'ϊ' | 'ΐ' => "i",
+        'κ' => "k",
+        'λ' => "l",
+        'μ' => "m",
+        'ν' => "n",
+        'ξ' => "x",
+        'ο' | 'ό' => "o",
+        'π' => "p",
+        'ρ' => "r",
+        'σ' | 'ς' => "s",
+        'τ' => "t",
+        'υ' | 'ύ' | 'ϋ' | 'ΰ' => "y",
+        'φ' => "f",
+        'χ' => "ch",
+        'ψ' => "ps",
+        'ω' | 'ώ' => "o",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latin_diacritics() {
+        assert_eq!(to_ascii("Résumé_Übersicht").unwrap(), "Resume_Ubersicht");
+        assert_eq!(to_ascii("straße-æøå").unwrap(), "strasse-aeoa");
+    }
+
+    #[test]
+    fn test_cyrillic_and_greek() {
+        assert_eq!(to_ascii("Журнал_ошибок").unwrap(), "Zhurnal_oshibok");
+        assert_eq!(to_ascii("щука").unwrap(), "shchuka");
+        assert_eq!(to_ascii("Δοκιμή").unwrap(), "Dokimi");
+    }
+
+    #[test]
+    fn test_schemes() {
+        let pinyin = |text| transliterate(text, Scheme::Pinyin);
+        assert_eq!(pinyin("张伟").as_deref(), Some("Zhang Wei"));
+        assert_eq!(pinyin("北京API").as_deref(), Some("Bei Jing API"));
+        assert_eq!(
+            pinyin("爱新觉罗·玄烨").as_deref(),
+            Some("Ai Xin Jue Luo Xuan Ye")
+        );
+
+        let romaji = |text| transliterate(text, Scheme::Romaji);
+        assert_eq!(romaji("テスト").as_deref(), Some("tesuto"));
+        assert_eq!(romaji("きょうと").as_deref(), Some("kyouto"));
+        assert_eq!(romaji("しゃしん").as_deref(), Some("shashin"));
+        assert_eq!(romaji("マッチ").as_deref(), Some("matchi"));
+        assert_eq!(
+            romaji("ファイル・サーバー").as_deref(),
+            Some("fairu saabaa")
+        );
+        assert_eq!(romaji("東京"), None);
+
+        let iso9 = |text| transliterate(text, Scheme::Iso9);
+        assert_eq!(iso9("Щука и Жук").as_deref(), Some("Ŝuka i Žuk"));
+        assert_eq!(iso9("Юрий API").as_deref(), Some("Ûrij API"));
+        assert_eq!(Scheme::parse("ISO-9").unwrap(), Scheme::Iso9);
+        assert!(Scheme::parse("wade-giles").is_err());
+    }
+
+    #[test]
+    fn test_transliteration_config() {
+        let config = TransliterateConfig {
+            scheme: "pinyin".to_string(),
+            patterns: vec![r"[张李王]\p{Han}{1,2}(?:先生|女士)".to_string()],
+            unit_types: vec!["string_literal".to_string()],
+        };
+        let transliteration = Transliteration::from_config(&config).unwrap();
+        let mut masked = Masked::new("请联系张伟先生处理 %s");
+        transliteration.mask(&mut masked);
+        assert_eq!(masked.text, "请联系⟪1⟫处理 ⟪0⟫");
+        assert_eq!(
+            masked.restore("Please ask ⟪1⟫ to handle ⟪0⟫").unwrap(),
+            "Please ask Zhang Wei Xian Sheng to handle %s"
+        );
+
+        let unit = TranslatableUnit::new("王芳".to_string(), UnitType::StringLiteral, 1, 1);
+        assert_eq!(transliteration.unit(&unit).as_deref(), Some("Wang Fang"));
+        let comment = TranslatableUnit::new("王芳".to_string(), UnitType::Comment, 1, 1);
+        assert_eq!(transliteration.unit(&comment), None);
+
+        let invalid = TransliterateConfig {
+            unit_types: vec!["heading".to_string()],
+            ..config
+        };
+        assert!(Transliteration::from_config(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_unsupported_scripts() {
+        assert_eq!(to_ascii("数据处理"), None);
+        assert!(!can_transliterate("テスト"));
+        assert!(can_transliterate("plain ascii"));
+    }
+}
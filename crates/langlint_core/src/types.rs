@@ -182,6 +182,23 @@ impl ParseResult {
     pub fn len(&self) -> usize {
         self.units.len()
     }
+
+    /// Most common detected language of the units (ties: alphabetical)
+    pub fn dominant_language(&self) -> Option<String> {
+        let mut counts: std::collections::BTreeMap<&str, usize> = Default::default();
+        for unit in &self.units {
+            if let Some(language) = &unit.detected_language {
+                *counts.entry(language).or_default() += 1;
+            }
+        }
+        let mut best: Option<(&str, usize)> = None;
+        for (language, count) in counts {
+            if best.is_none_or(|(_, best_count)| count > best_count) {
+                best = Some((language, count));
+            }
+        }
+        best.map(|(language, _)| language.to_string())
+    }
 }
 
 #[cfg(test)]
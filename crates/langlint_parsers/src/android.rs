@@ -0,0 +1,534 @@
+//! Android string resource parser (`res/values/strings.xml`)
+//!
+//! Extracts `<string>` values and the items of `<string-array>` and
+//! `<plurals>`, skipping `translatable="false"` resources and references
+//! (`@string/name`). Values are unescaped for translating (`\'`, `\n`,
+//! `&amp;`) and escaped again when written back, keeping `"..."` quoting
+//! and CDATA sections; line breaks of a value laid out over several lines
+//! stay line breaks rather than becoming `\n`. Since values may hold HTML
+//! styling (`<b>`, `<xliff:g>`), units are text nodes, whose markup is
+//! kept verbatim.
+
+use anyhow::Result;
+use langlint_core::{Masked, ParseResult, Protection, TranslatableUnit, UnitType};
+use regex::Regex;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::Parser;
+
+/// Unit metadata key locating a unit's value
+pub const ANDROID_KEY: &str = "android";
+
+/// Parser for Android string resources
+pub struct AndroidStringsParser;
+
+/// A resource value: where it is written and its unescaped text
+#[derive(Debug, Clone)]
+struct Resource {
+    /// `name`, `name[0]` for array items, `name[one]` for plurals
+    name: String,
+    line: usize,
+    /// Byte range of the value, without surrounding whitespace
+    span: Range<usize>,
+    value: String,
+    quoted: bool,
+    cdata: bool,
+    /// Laid out over several lines, whose breaks are only whitespace
+    wrapped: bool,
+}
+
+impl AndroidStringsParser {
+    /// Create a new Android string resource parser
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Check if a path is an Android resource file: `strings.xml`, or XML in
+    /// a `values*` directory (`res/values/arrays.xml`)
+    pub fn is_strings_path(path: &str) -> bool {
+        let path = Path::new(path);
+        let is_xml = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("xml"));
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("");
+        let in_values = path
+            .parent()
+            .and_then(|parent| parent.file_name())
+            .and_then(|dir| dir.to_str())
+            .is_some_and(|dir| dir == "values" || dir.starts_with("values-"));
+        is_xml && (name.eq_ignore_ascii_case("strings.xml") || in_values)
+    }
+}
+
+impl Default for AndroidStringsParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn element_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r"(?s)<!--.*?-->|<(string-array|plurals|string)\b([^>]*?)(?:/>|>(.*?)</(?:string-array|plurals|string)\s*>)",
+        )
+        .unwrap()
+    })
+}
+
+fn item_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?s)<item\b([^>]*?)(?:/>|>(.*?)</item\s*>)").unwrap())
+}
+
+/// Value of an attribute in an element's attribute text
+fn attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    let pattern = format!(r#"(?:^|\s){}\s*=\s*["']([^"']*)["']"#, regex::escape(name));
+    Regex::new(&pattern)
+        .ok()?
+        .captures(attributes)
+        .and_then(|captures| captures.get(1))
+        .map(|value| value.as_str())
+}
+
+/// 1-based line of a byte offset
+fn line_of(text: &str, offset: usize) -> usize {
+    text[..offset].matches('\n').count() + 1
+}
+
+/// The translatable values of a resource file
+fn resources(content: &str) -> Vec<Resource> {
+    let mut resources = Vec::new();
+    for captures in element_regex().captures_iter(content) {
+        let (Some(kind), Some(attributes)) = (captures.get(1), captures.get(2)) else {
+            continue; // A comment
+        };
+        let Some(body) = captures.get(3) else {
+            continue; // `<string name="x"/>`
+        };
+        let attributes = attributes.as_str();
+        if attribute(attributes, "translatable") == Some("false") {
+            continue;
+        }
+        let name = attribute(attributes, "name")
+            .unwrap_or_default()
+            .to_string();
+
+        if kind.as_str() == "string" {
+            resources.extend(resource(content, body.range(), name));
+            continue;
+        }
+        for (index, item) in item_regex().captures_iter(body.as_str()).enumerate() {
+            let Some(value) = item.get(2) else {
+                continue;
+            };
+            let key = match attribute(item.get(1).map_or("", |a| a.as_str()), "quantity") {
+                Some(quantity) => format!("{}[{}]", name, quantity),
+                None => format!("{}[{}]", name, index),
+            };
+            let range = body.start() + value.start()..body.start() + value.end();
+            resources.extend(resource(content, range, key));
+        }
+    }
+    resources
+}
+
+/// The value written in `range`, unless it is empty or a reference
+fn resource(content: &str, range: Range<usize>, name: String) -> Option<Resource> {
+    let raw = &content[range.clone()];
+    let start = range.start + (raw.len() - raw.trim_start().len());
+    let span = start..start + raw.trim().len();
+    let raw = raw.trim();
+    if raw.is_empty() || raw.starts_with('@') || raw.starts_with('?') {
+        return None;
+    }
+
+    let (inner, quoted, cdata) = if let Some(inner) = raw
+        .strip_prefix("<![CDATA[")
+        .and_then(|rest| rest.strip_suffix("]]>"))
+    {
+        (inner, false, true)
+    } else if raw.len() >= 2 && raw.starts_with('"') && raw.ends_with('"') && !raw.ends_with("\\\"")
+    {
+        (&raw[1..raw.len() - 1], true, false)
+    } else {
+        (raw, false, false)
+    };
+    Some(Resource {
+        name,
+        line: line_of(content, span.start),
+        span,
+        value: unescape(inner, !cdata),
+        quoted,
+        cdata,
+        wrapped: inner.contains('\n') && !inner.contains("\\n"),
+    })
+}
+
+/// Resolve Android escapes and, outside CDATA, the XML entities of text
+/// (`&lt;` and `&gt;` stay, as they belong with the markup)
+fn unescape(raw: &str, entities: bool) -> String {
+    let mut text = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(c) = rest.chars().next() {
+        if c == '\\' {
+            let mut chars = rest[1..].chars();
+            let consumed = match chars.next() {
+                Some('n') => {
+                    text.push('\n');
+                    2
+                }
+                Some('t') => {
+                    text.push('\t');
+                    2
+                }
+                Some('u') => {
+                    let hex: String = chars.take(4).collect();
+                    match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                        Some(decoded) if hex.len() == 4 => {
+                            text.push(decoded);
+                            6
+                        }
+                        _ => {
+                            text.push('u');
+                            2
+                        }
+                    }
+                }
+                Some(escaped) => {
+                    text.push(escaped);
+                    1 + escaped.len_utf8()
+                }
+                None => {
+                    text.push('\\');
+                    1
+                }
+            };
+            rest = &rest[consumed..];
+            continue;
+        }
+        if c == '&' && entities {
+            let entity = rest.find(';').map(|end| (&rest[1..end], end + 1));
+            let decoded = entity.and_then(|(name, len)| {
+                let c = match name {
+                    "amp" => '&',
+                    "quot" => '"',
+                    "apos" => '\'',
+                    _ => {
+                        let code = name.strip_prefix('#')?;
+                        let code = match code.strip_prefix(['x', 'X']) {
+                            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                            None => code.parse().ok()?,
+                        };
+                        char::from_u32(code)?
+                    }
+                };
+                Some((c, len))
+            });
+            if let Some((decoded, len)) = decoded {
+                text.push(decoded);
+                rest = &rest[len..];
+                continue;
+            }
+        }
+        text.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    text
+}
+
+/// `text` written back in the form of `resource`; markup tags are kept as is
+fn escape(text: &str, resource: &Resource) -> String {
+    let mut escaped = String::with_capacity(text.len() + 8);
+    let mut in_tag = false;
+    for (i, c) in text.char_indices() {
+        if c == '<' && text[i + 1..].starts_with(|next: char| next.is_alphabetic() || next == '/') {
+            in_tag = true;
+        }
+        if in_tag {
+            in_tag = c != '>';
+            escaped.push(c);
+            continue;
+        }
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' if resource.wrapped => escaped.push('\n'),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '"' => escaped.push_str("\\\""),
+            '\'' if !resource.quoted => escaped.push_str("\\'"),
+            '@' | '?' if i == 0 && !resource.quoted => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '&' if !resource.cdata
+                && !text[i..].starts_with("&lt;")
+                && !text[i..].starts_with("&gt;") =>
+            {
+                escaped.push_str("&amp;")
+            }
+            _ => escaped.push(c),
+        }
+    }
+    if resource.cdata {
+        format!("<![CDATA[{}]]>", escaped)
+    } else if resource.quoted {
+        format!("\"{}\"", escaped)
+    } else {
+        escaped
+    }
+}
+
+/// Check if a value has words besides its placeholders and markup
+fn is_translatable(text: &str) -> bool {
+    static PROTECTION: OnceLock<Protection> = OnceLock::new();
+    let protection = PROTECTION.get_or_init(|| Protection::default().with_markup());
+    Masked::with_protection(text, protection)
+        .text
+        .chars()
+        .any(char::is_alphabetic)
+}
+
+impl Parser for AndroidStringsParser {
+    fn name(&self) -> &'static str {
+        "AndroidStrings"
+    }
+
+    fn supported_extensions(&self) -> &'static [&'static str] {
+        &[".xml"]
+    }
+
+    fn can_parse(&self, path: &str, _content: Option<&str>) -> bool {
+        Self::is_strings_path(path)
+    }
+
+    fn extract_units(&self, content: &str, _path: &str) -> Result<ParseResult> {
+        let units = resources(content)
+            .into_iter()
+            .filter(|resource| is_translatable(&resource.value))
+            .map(|resource| {
+                let mut unit = TranslatableUnit::new(
+                    resource.value,
+                    UnitType::TextNode,
+                    resource.line as u32,
+                    1,
+                )
+                .with_context(resource.name)
+                .with_metadata(serde_json::json!({
+                    ANDROID_KEY: { "start": resource.span.start }
+                }));
+                unit.detect_language();
+                unit
+            })
+            .collect();
+
+        Ok(
+            ParseResult::new("android_strings", "utf-8", content.lines().count() as u32)
+                .with_units(units),
+        )
+    }
+
+    fn reconstruct(
+        &self,
+        original: &str,
+        units: &[TranslatableUnit],
+        _path: &str,
+    ) -> Result<String> {
+        let start = |unit: &TranslatableUnit| {
+            unit.metadata
+                .as_ref()?
+                .get(ANDROID_KEY)?
+                .get("start")?
+                .as_u64()
+        };
+
+        let mut result = original.to_string();
+        for resource in resources(original).iter().rev() {
+            let translated = units
+                .iter()
+                .find(|unit| start(unit) == Some(resource.span.start as u64));
+            if let Some(unit) = translated.filter(|unit| unit.content != resource.value) {
+                result.replace_range(resource.span.clone(), &escape(&unit.content, resource));
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STRINGS: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<resources xmlns:xliff="urn:oasis:names:tc:xliff:document:1.2">
+    <string name="app_name" translatable="false">Langlint</string>
+    <!-- <string name="old">旧的</string> -->
+    <string name="welcome">欢迎，%1$s！</string>
+    <string name="saved">已保存到 \"下载\" &amp; 相册\n请查看</string>
+    <string name="quoted">"它的 'beta' 版"</string>
+    <string name="styled"><![CDATA[<b>粗体</b> 文本]]></string>
+    <string name="count">共 <xliff:g id="count" example="5">%d</xliff:g> 项</string>
+    <string name="alias">@string/welcome</string>
+    <string-array name="planets">
+        <item>水星</item>
+        <item>金星</item>
+    </string-array>
+    <plurals name="songs">
+        <item quantity="one">%d 首歌</item>
+        <item quantity="other">%d 首歌曲</item>
+    </plurals>
+</resources>
+"#;
+
+    #[test]
+    fn test_extract_units() {
+        let parser = AndroidStringsParser::new();
+        assert!(AndroidStringsParser::is_strings_path(
+            "app/src/main/res/values/strings.xml"
+        ));
+        assert!(AndroidStringsParser::is_strings_path(
+            "res/values-zh-rCN/arrays.xml"
+        ));
+        assert!(!AndroidStringsParser::is_strings_path(
+            "res/layout/main.xml"
+        ));
+
+        let result = parser
+            .extract_units(STRINGS, "res/values/strings.xml")
+            .unwrap();
+        let units: Vec<(&str, &str)> = result
+            .units
+            .iter()
+            .map(|unit| (unit.context.as_deref().unwrap(), unit.content.as_str()))
+            .collect();
+        assert_eq!(
+            units,
+            vec![
+                ("welcome", "欢迎，%1$s！"),
+                ("saved", "已保存到 \"下载\" & 相册\n请查看"),
+                ("quoted", "它的 'beta' 版"),
+                ("styled", "<b>粗体</b> 文本"),
+                (
+                    "count",
+                    "共 <xliff:g id=\"count\" example=\"5\">%d</xliff:g> 项"
+                ),
+                ("planets[0]", "水星"),
+                ("planets[1]", "金星"),
+                ("songs[one]", "%d 首歌"),
+                ("songs[other]", "%d 首歌曲"),
+            ]
+        );
+        assert_eq!(result.units[0].line_number, 5);
+        assert_eq!(result.units[0].unit_type, UnitType::TextNode);
+    }
+
+    #[test]
+    fn test_reconstruct() {
+        let parser = AndroidStringsParser::new();
+        let path = "res/values/strings.xml";
+        let mut units = parser.extract_units(STRINGS, path).unwrap().units;
+        let translations = [
+            "Welcome, %1$s!",
+            "Saved to \"Downloads\" & Photos\nCheck it",
+            "Its 'beta' version",
+            "<b>Bold</b> text isn't plain",
+            "<xliff:g id=\"count\" example=\"5\">%d</xliff:g> items",
+            "Mercury",
+            "Venus",
+            "%d song",
+            "%d songs",
+        ];
+        for (unit, translation) in units.iter_mut().zip(translations) {
+            unit.content = translation.to_string();
+        }
+
+        let result = parser.reconstruct(STRINGS, &units, path).unwrap();
+        for expected in [
+            r#"<string name="welcome">Welcome, %1$s!</string>"#,
+            r#"<string name="saved">Saved to \"Downloads\" &amp; Photos\nCheck it</string>"#,
+            r#"<string name="quoted">"Its 'beta' version"</string>"#,
+            r#"<string name="styled"><![CDATA[<b>Bold</b> text isn\'t plain]]></string>"#,
+            r#"<string name="count"><xliff:g id="count" example="5">%d</xliff:g> items</string>"#,
+            "<item>Mercury</item>",
+            r#"<item quantity="other">%d songs</item>"#,
+            r#"<string name="app_name" translatable="false">Langlint</string>"#,
+            "<!-- <string name=\"old\">旧的</string> -->",
+        ] {
+            assert!(result.contains(expected), "missing {}", expected);
+        }
+
+        // Untouched units give back the original
+        let units = parser.extract_units(STRINGS, path).unwrap().units;
+        assert_eq!(parser.reconstruct(STRINGS, &units, path).unwrap(), STRINGS);
+    }
+
+    #[test]
+    fn test_round_trip_plurals_and_cdata() {
+        let content = r#"<?xml version="1.0" encoding="utf-8"?>
+<resources>
+    <plurals name="messages">
+        <item quantity="zero">没有消息</item>
+        <item quantity="one"><![CDATA[<b>%d</b> 条新消息]]></item>
+        <item quantity="few">%d 条 \"重要\" 消息</item>
+        <item quantity="other">
+            %d 条消息
+        </item>
+    </plurals>
+    <plurals name="ids" translatable="false">
+        <item quantity="other">ID %d</item>
+    </plurals>
+    <string name="html"><![CDATA[
+        <p>第一段</p>
+        <p>第二段 & 更多</p>
+    ]]></string>
+</resources>
+"#;
+        let parser = AndroidStringsParser::new();
+        let path = "res/values/strings.xml";
+        let mut units = parser.extract_units(content, path).unwrap().units;
+        let contexts: Vec<&str> = units
+            .iter()
+            .map(|unit| unit.context.as_deref().unwrap())
+            .collect();
+        assert_eq!(
+            contexts,
+            [
+                "messages[zero]",
+                "messages[one]",
+                "messages[few]",
+                "messages[other]",
+                "html"
+            ]
+        );
+        assert_eq!(units[2].content, "%d 条 \"重要\" 消息");
+        assert_eq!(units[3].content, "%d 条消息");
+
+        let translations = [
+            "No messages",
+            "<b>%d</b> new message",
+            "%d \"important\" messages",
+            "%d messages",
+            "\n        <p>First</p>\n        <p>Second & more</p>\n    ",
+        ];
+        for (unit, translation) in units.iter_mut().zip(translations) {
+            unit.content = translation.to_string();
+        }
+        let rebuilt = parser.reconstruct(content, &units, path).unwrap();
+        assert_eq!(
+            rebuilt,
+            content
+                .replace("没有消息", "No messages")
+                .replace("<b>%d</b> 条新消息", "<b>%d</b> new message")
+                .replace(r#"%d 条 \"重要\" 消息"#, r#"%d \"important\" messages"#)
+                .replace("%d 条消息", "%d messages")
+                .replace("第一段", "First")
+                .replace("第二段 & 更多", "Second & more")
+        );
+    }
+}
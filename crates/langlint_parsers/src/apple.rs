@@ -0,0 +1,625 @@
+//! Apple string resource parser (`Localizable.strings`, `.stringsdict`)
+//!
+//! In `.strings` files every `"key" = "value";` value is a unit whose
+//! context is its key; comments and keys are kept, and escapes (`\n`,
+//! `\"`, `\UD83D\UDE00`) are resolved for translating. In `.stringsdict`
+//! plists the format strings (`NSStringLocalizedFormatKey`) and the plural
+//! variants (`zero`, `one`, ..., `other`) are units, and the rule
+//! dictionaries around them are kept. Format specifiers (`%@`, `%1$d`,
+//! `%#@files@`) are masked by the pipeline.
+
+use anyhow::Result;
+use langlint_core::{Masked, ParseResult, TranslatableUnit, UnitType};
+use regex::Regex;
+use std::ops::Range;
+use std::str::Chars;
+use std::sync::OnceLock;
+
+use crate::Parser;
+
+/// Unit metadata key locating a unit's value
+pub const APPLE_KEY: &str = "apple";
+
+/// `.stringsdict` keys whose string is translated
+const STRINGSDICT_KEYS: &[&str] = &[
+    "NSStringLocalizedFormatKey",
+    "zero",
+    "one",
+    "two",
+    "few",
+    "many",
+    "other",
+];
+
+/// Parser for Apple `.strings` and `.stringsdict` files
+pub struct AppleStringsParser;
+
+/// A string value: its key, where it is written and its text
+#[derive(Debug, Clone)]
+struct Entry {
+    key: String,
+    line: usize,
+    /// Byte range of the value, quotes included in `.strings` files
+    span: Range<usize>,
+    value: String,
+}
+
+impl AppleStringsParser {
+    /// Create a new Apple string resource parser
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for AppleStringsParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 1-based line of a byte offset
+fn line_of(text: &str, offset: usize) -> usize {
+    text[..offset].matches('\n').count() + 1
+}
+
+fn is_stringsdict(path: &str) -> bool {
+    path.to_lowercase().ends_with(".stringsdict")
+}
+
+/// The entries of a `.strings` file
+fn strings_entries(content: &str) -> Result<Vec<Entry>> {
+    let bytes = content.as_bytes();
+    let mut pos = 0;
+    let skip_blank = |pos: &mut usize| loop {
+        while *pos < bytes.len() && bytes[*pos].is_ascii_whitespace() {
+            *pos += 1;
+        }
+        if content[*pos..].starts_with("/*") {
+            *pos = content[*pos + 2..]
+                .find("*/")
+                .map_or(bytes.len(), |end| *pos + 2 + end + 2);
+        } else if content[*pos..].starts_with("//") {
+            *pos = content[*pos..]
+                .find('\n')
+                .map_or(bytes.len(), |end| *pos + end);
+        } else {
+            break;
+        }
+    };
+    let syntax_error = |pos: usize| {
+        anyhow::anyhow!(
+            "Invalid .strings syntax at line {}",
+            line_of(content, pos.min(content.len()))
+        )
+    };
+    // A quoted string at `pos`, as its range and unescaped text
+    let quoted = |pos: usize| -> Option<(Range<usize>, String)> {
+        let mut end = pos + 1;
+        while end < bytes.len() && bytes[end] != b'"' {
+            end += if bytes[end] == b'\\' { 2 } else { 1 };
+        }
+        (end < bytes.len()).then(|| (pos..end + 1, unescape(&content[pos + 1..end])))
+    };
+
+    let mut entries = Vec::new();
+    loop {
+        skip_blank(&mut pos);
+        if pos >= bytes.len() {
+            break;
+        }
+        let key = if bytes[pos] == b'"' {
+            let (range, key) = quoted(pos).ok_or_else(|| syntax_error(pos))?;
+            pos = range.end;
+            key
+        } else {
+            let start = pos;
+            while pos < bytes.len()
+                && !bytes[pos].is_ascii_whitespace()
+                && !matches!(bytes[pos], b'=' | b';')
+            {
+                pos += 1;
+            }
+            content[start..pos].to_string()
+        };
+
+        skip_blank(&mut pos);
+        if bytes.get(pos) == Some(&b';') {
+            // `"key";` is shorthand for a value equal to the key
+            pos += 1;
+            continue;
+        }
+        if bytes.get(pos) != Some(&b'=') {
+            return Err(syntax_error(pos));
+        }
+        pos += 1;
+        skip_blank(&mut pos);
+        if bytes.get(pos) != Some(&b'"') {
+            return Err(syntax_error(pos));
+        }
+        let (span, value) = quoted(pos).ok_or_else(|| syntax_error(pos))?;
+        pos = span.end;
+        skip_blank(&mut pos);
+        if bytes.get(pos) != Some(&b';') {
+            return Err(syntax_error(pos));
+        }
+        pos += 1;
+        entries.push(Entry {
+            key,
+            line: line_of(content, span.start),
+            span,
+            value,
+        });
+    }
+    Ok(entries)
+}
+
+fn unescape(raw: &str) -> String {
+    let mut text = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => text.push('\n'),
+            Some('t') => text.push('\t'),
+            Some('r') => text.push('\r'),
+            Some(u @ ('u' | 'U')) => match unicode_escape(&mut chars) {
+                Some(decoded) => text.push(decoded),
+                None => text.push(u),
+            },
+            Some(escaped) => text.push(escaped),
+            None => text.push('\\'),
+        }
+    }
+    text
+}
+
+/// The four hex digits `chars` starts with, as a UTF-16 code unit
+fn hex_unit(chars: &Chars) -> Option<u16> {
+    let hex: String = chars.clone().take(4).collect();
+    if hex.len() != 4 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    u16::from_str_radix(&hex, 16).ok()
+}
+
+/// The character of a `\Uxxxx` escape whose digits `chars` starts with,
+/// joining a surrogate pair written as two escapes (`\UD83D\UDE00`);
+/// `chars` is left after the escape, or untouched if it is invalid
+fn unicode_escape(chars: &mut Chars) -> Option<char> {
+    let first = hex_unit(chars)?;
+    let mut rest = chars.clone();
+    rest.nth(3);
+    let decoded = if (0xd800..0xdc00).contains(&first) {
+        if rest.next() != Some('\\') || !matches!(rest.next(), Some('u' | 'U')) {
+            return None;
+        }
+        let second = hex_unit(&rest)?;
+        rest.nth(3);
+        char::decode_utf16([first, second]).next()?.ok()?
+    } else {
+        char::from_u32(first.into())?
+    };
+    *chars = rest;
+    Some(decoded)
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + 2);
+    escaped.push('"');
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn plist_token_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"(?s)<!--.*?-->|<key>([^<]*)</key>|<string>([^<]*)</string>|<dict/>|<(/?)dict>")
+            .unwrap()
+    })
+}
+
+/// The translated strings of a `.stringsdict` plist, keyed by their path
+/// (`%d files.files.one`)
+fn stringsdict_entries(content: &str) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let mut path: Vec<String> = Vec::new();
+    let mut key: Option<String> = None;
+    for token in plist_token_regex().captures_iter(content) {
+        if let Some(name) = token.get(1) {
+            key = Some(unescape_xml(name.as_str()));
+        } else if let Some(value) = token.get(2) {
+            if let Some(name) = key
+                .take()
+                .filter(|name| STRINGSDICT_KEYS.contains(&name.as_str()))
+            {
+                entries.push(Entry {
+                    key: path
+                        .iter()
+                        .chain([&name])
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .join("."),
+                    line: line_of(content, value.start()),
+                    span: value.range(),
+                    value: unescape_xml(value.as_str()),
+                });
+            }
+        } else if let Some(close) = token.get(3) {
+            if close.as_str() == "/" {
+                path.pop();
+            } else {
+                path.push(key.take().unwrap_or_default());
+            }
+        } else {
+            key = None;
+        }
+    }
+    // The outermost dictionary has no key
+    for entry in &mut entries {
+        entry.key = entry.key.trim_start_matches('.').to_string();
+    }
+    entries
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn entries(content: &str, path: &str) -> Result<Vec<Entry>> {
+    if is_stringsdict(path) {
+        Ok(stringsdict_entries(content))
+    } else {
+        strings_entries(content)
+    }
+}
+
+impl Parser for AppleStringsParser {
+    fn name(&self) -> &'static str {
+        "AppleStrings"
+    }
+
+    fn supported_extensions(&self) -> &'static [&'static str] {
+        &[".strings", ".stringsdict"]
+    }
+
+    fn can_parse(&self, path: &str, _content: Option<&str>) -> bool {
+        let lower = path.to_lowercase();
+        self.supported_extensions()
+            .iter()
+            .any(|ext| lower.ends_with(ext))
+    }
+
+    fn extract_units(&self, content: &str, path: &str) -> Result<ParseResult> {
+        let units = entries(content, path)?
+            .into_iter()
+            .filter(|entry| {
+                Masked::new(&entry.value)
+                    .text
+                    .chars()
+                    .any(char::is_alphabetic)
+            })
+            .map(|entry| {
+                let mut unit = TranslatableUnit::new(
+                    entry.value,
+                    UnitType::StringLiteral,
+                    entry.line as u32,
+                    1,
+                )
+                .with_context(entry.key)
+                .with_metadata(serde_json::json!({
+                    APPLE_KEY: { "start": entry.span.start }
+                }));
+                unit.detect_language();
+                unit
+            })
+            .collect();
+
+        let file_type = if is_stringsdict(path) {
+            "apple_stringsdict"
+        } else {
+            "apple_strings"
+        };
+        Ok(ParseResult::new(file_type, "utf-8", content.lines().count() as u32).with_units(units))
+    }
+
+    fn reconstruct(
+        &self,
+        original: &str,
+        units: &[TranslatableUnit],
+        path: &str,
+    ) -> Result<String> {
+        let start = |unit: &TranslatableUnit| {
+            unit.metadata
+                .as_ref()?
+                .get(APPLE_KEY)?
+                .get("start")?
+                .as_u64()
+        };
+
+        let mut result = original.to_string();
+        for entry in entries(original, path)?.iter().rev() {
+            let translated = units
+                .iter()
+                .find(|unit| start(unit) == Some(entry.span.start as u64));
+            if let Some(unit) = translated.filter(|unit| unit.content != entry.value) {
+                let value = if is_stringsdict(path) {
+                    escape_xml(&unit.content)
+                } else {
+                    escape(&unit.content)
+                };
+                result.replace_range(entry.span.clone(), &value);
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STRINGS: &str = r#"/* 登录页面 */
+"login.title" = "欢迎回来";
+"login.greeting" = "你好，%@！你有 %1$d 条\"新\"消息";
+// 按钮
+OK_BUTTON = "确定";
+"version" = "1.0";
+"#;
+
+    const STRINGSDICT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+	<key>%d files</key>
+	<dict>
+		<key>NSStringLocalizedFormatKey</key>
+		<string>已选择 %#@files@</string>
+		<key>files</key>
+		<dict>
+			<key>NSStringFormatSpecTypeKey</key>
+			<string>NSStringPluralRuleType</string>
+			<key>NSStringFormatValueTypeKey</key>
+			<string>d</string>
+			<key>one</key>
+			<string>%d 个文件</string>
+			<key>other</key>
+			<string>%d 个文件 &amp; 目录</string>
+		</dict>
+	</dict>
+</dict>
+</plist>
+"#;
+
+    fn contents(units: &[TranslatableUnit]) -> Vec<(&str, &str)> {
+        units
+            .iter()
+            .map(|unit| (unit.context.as_deref().unwrap(), unit.content.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_extract_units() {
+        let parser = AppleStringsParser::new();
+        let strings = parser
+            .extract_units(STRINGS, "zh-Hans.lproj/Localizable.strings")
+            .unwrap();
+        assert_eq!(
+            contents(&strings.units),
+            vec![
+                ("login.title", "欢迎回来"),
+                ("login.greeting", "你好，%@！你有 %1$d 条\"新\"消息"),
+                ("OK_BUTTON", "确定"),
+            ]
+        );
+        assert_eq!(strings.units[2].line_number, 5);
+
+        let dict = parser
+            .extract_units(STRINGSDICT, "Localizable.stringsdict")
+            .unwrap();
+        assert_eq!(
+            contents(&dict.units),
+            vec![
+                ("%d files.NSStringLocalizedFormatKey", "已选择 %#@files@"),
+                ("%d files.files.one", "%d 个文件"),
+                ("%d files.files.other", "%d 个文件 & 目录"),
+            ]
+        );
+
+        assert!(parser
+            .extract_units("\"a\" = \"b\"\n\"c\" = \"d\";", "x.strings")
+            .is_err());
+    }
+
+    #[test]
+    fn test_reconstruct() {
+        let parser = AppleStringsParser::new();
+        let translate = |content: &str, path: &str, translations: &[&str]| {
+            let mut units = parser.extract_units(content, path).unwrap().units;
+            for (unit, translation) in units.iter_mut().zip(translations) {
+                unit.content = translation.to_string();
+            }
+            parser.reconstruct(content, &units, path).unwrap()
+        };
+
+        assert_eq!(
+            translate(
+                STRINGS,
+                "Localizable.strings",
+                &[
+                    "Welcome back",
+                    "Hi %@! You have %1$d \"new\" messages",
+                    "OK"
+                ]
+            ),
+            r#"/* 登录页面 */
+"login.title" = "Welcome back";
+"login.greeting" = "Hi %@! You have %1$d \"new\" messages";
+// 按钮
+OK_BUTTON = "OK";
+"version" = "1.0";
+"#
+        );
+
+        let dict = translate(
+            STRINGSDICT,
+            "Localizable.stringsdict",
+            &["Selected %#@files@", "%d file", "%d files & folders"],
+        );
+        assert!(dict.contains("<string>Selected %#@files@</string>"));
+        assert!(dict.contains("<string>%d files &amp; folders</string>"));
+        assert!(dict.contains("<string>NSStringPluralRuleType</string>"));
+    }
+
+    #[test]
+    fn test_round_trip_escapes_and_plural_rules() {
+        let strings = r#"/*
+ * 多行注释
+ * "comment.key" = "不是条目";
+ */
+"menu.quit" = "退出 %@；再见";
+"path" = "C:\\用户\\文档";
+"multi" = "第一行\n第二行";
+"emoji" = "\UD83D\UDE00 笑脸";
+"semicolon" = "一;二";
+
+"spaced"   =   "前后空格"  ;
+"#;
+        let parser = AppleStringsParser::new();
+        let path = "en.lproj/Localizable.strings";
+        let mut units = parser.extract_units(strings, path).unwrap().units;
+        assert_eq!(
+            contents(&units),
+            vec![
+                ("menu.quit", "退出 %@；再见"),
+                ("path", "C:\\用户\\文档"),
+                ("multi", "第一行\n第二行"),
+                ("emoji", "😀 笑脸"),
+                ("semicolon", "一;二"),
+                ("spaced", "前后空格"),
+            ]
+        );
+        let translations = [
+            "Quit %@; bye",
+            "C:\\Users\\Documents",
+            "First line\nsecond line",
+            "😀 smile",
+            "one;two",
+            "spaces around",
+        ];
+        for (unit, translation) in units.iter_mut().zip(translations) {
+            unit.content = translation.to_string();
+        }
+        assert_eq!(
+            parser.reconstruct(strings, &units, path).unwrap(),
+            r#"/*
+ * 多行注释
+ * "comment.key" = "不是条目";
+ */
+"menu.quit" = "Quit %@; bye";
+"path" = "C:\\Users\\Documents";
+"multi" = "First line\nsecond line";
+"emoji" = "😀 smile";
+"semicolon" = "one;two";
+
+"spaced"   =   "spaces around"  ;
+"#
+        );
+
+        let stringsdict = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>photos</key>
+	<dict>
+		<key>NSStringLocalizedFormatKey</key>
+		<string>%#@photos@，%#@albums@</string>
+		<key>photos</key>
+		<dict>
+			<key>NSStringFormatSpecTypeKey</key>
+			<string>NSStringPluralRuleType</string>
+			<key>NSStringFormatValueTypeKey</key>
+			<string>d</string>
+			<key>zero</key>
+			<string>没有照片</string>
+			<key>few</key>
+			<string>%d 张照片</string>
+			<key>other</key>
+			<string>%d 张照片</string>
+		</dict>
+		<key>albums</key>
+		<dict>
+			<key>NSStringFormatSpecTypeKey</key>
+			<string>NSStringPluralRuleType</string>
+			<key>NSStringFormatValueTypeKey</key>
+			<string>d</string>
+			<key>one</key>
+			<string>%d 个相册</string>
+			<key>other</key>
+			<string>%d 个相册</string>
+		</dict>
+	</dict>
+</dict>
+</plist>
+"#;
+        let path = "en.lproj/Localizable.stringsdict";
+        let mut units = parser.extract_units(stringsdict, path).unwrap().units;
+        assert_eq!(
+            contents(&units),
+            vec![
+                ("photos.photos.zero", "没有照片"),
+                ("photos.photos.few", "%d 张照片"),
+                ("photos.photos.other", "%d 张照片"),
+                ("photos.albums.one", "%d 个相册"),
+                ("photos.albums.other", "%d 个相册"),
+            ]
+        );
+        let translations = [
+            "no photos",
+            "%d photos",
+            "%d photos",
+            "%d album",
+            "%d albums",
+        ];
+        for (unit, translation) in units.iter_mut().zip(translations) {
+            unit.content = translation.to_string();
+        }
+        let mut expected = stringsdict.to_string();
+        for (original, translation) in [
+            ("<string>没有照片</string>", "<string>no photos</string>"),
+            ("<string>%d 张照片</string>", "<string>%d photos</string>"),
+            (
+                "<key>one</key>\n\t\t\t<string>%d 个相册</string>",
+                "<key>one</key>\n\t\t\t<string>%d album</string>",
+            ),
+            ("<string>%d 个相册</string>", "<string>%d albums</string>"),
+        ] {
+            expected = expected.replace(original, translation);
+        }
+        assert_eq!(
+            parser.reconstruct(stringsdict, &units, path).unwrap(),
+            expected
+        );
+    }
+}
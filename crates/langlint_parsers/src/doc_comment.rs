@@ -0,0 +1,170 @@
+//! Structured doc comments
+//!
+//! [`JsDocParser`](crate::JsDocParser), [`JavadocParser`](crate::JavadocParser)
+//! and [`XmlDocParser`](crate::XmlDocParser) split doc comments into
+//! segments: a description paragraph or the free text of a tag. Each
+//! segment is translated as one unit and written back over the lines and
+//! byte ranges it came from, so tags, types and names stay as written.
+//! Everything else in the file is handled by [`GenericCodeParser`].
+
+use anyhow::Result;
+use langlint_core::{ParseResult, Priority, TranslatableUnit, UnitType};
+use std::collections::BTreeMap;
+use std::ops::{Range, RangeInclusive};
+
+use crate::{license, paragraph, GenericCodeParser, Parser};
+
+/// Unit metadata key holding the tag a doc comment text belongs to, or
+/// `description` for the text before the first tag
+pub const DOC_TAG_KEY: &str = "doc_tag";
+
+/// Check if a unit is the text of a structured doc comment
+pub fn is_doc_text(unit: &TranslatableUnit) -> bool {
+    unit.metadata
+        .as_ref()
+        .is_some_and(|m| m.get(DOC_TAG_KEY).is_some())
+}
+
+/// Translatable text of a doc comment
+pub(crate) struct Segment {
+    pub(crate) tag: String,
+    /// Text on each line, as `(0-based line, byte range)`
+    pub(crate) lines: Vec<(usize, Range<usize>)>,
+}
+
+/// Doc comments of a file
+pub(crate) struct DocComments {
+    /// 0-based lines each doc comment spans
+    pub(crate) blocks: Vec<RangeInclusive<usize>>,
+    pub(crate) segments: Vec<Segment>,
+}
+
+/// Units of a file whose doc comments are `docs`
+///
+/// The comment units the generic parser made of the doc comments give way
+/// to one Docstring unit per segment, described by `describe(tag)`.
+pub(crate) fn extract_units(
+    generic: &GenericCodeParser,
+    content: &str,
+    path: &str,
+    parser: &str,
+    docs: Option<DocComments>,
+    describe: impl Fn(&str) -> String,
+) -> Result<ParseResult> {
+    let mut result = generic.extract_units(content, path)?;
+    let Some(docs) = docs else {
+        return Ok(result);
+    };
+    let lines: Vec<&str> = content.lines().collect();
+
+    result.units.retain(|unit| {
+        let first = (unit.line_number as usize).saturating_sub(1);
+        let last = first + paragraph::paragraph_lines(unit) - 1;
+        unit.unit_type != UnitType::Comment
+            || !docs
+                .blocks
+                .iter()
+                .any(|block| first <= *block.end() && *block.start() <= last)
+    });
+
+    let mut units = Vec::new();
+    for segment in docs.segments {
+        let text = |(line, range): &(usize, Range<usize>)| &lines[*line][range.clone()];
+        let content = segment
+            .lines
+            .iter()
+            .map(text)
+            .fold(String::new(), |content, line| {
+                if content.is_empty() {
+                    line.to_string()
+                } else {
+                    paragraph::join_wrapped(&content, line)
+                }
+            });
+        if !generic.is_translatable(&content) {
+            continue;
+        }
+        let (line, range) = &segment.lines[0];
+        let line_num = *line as u32 + 1;
+        let mut unit = TranslatableUnit::new(
+            content,
+            UnitType::Docstring,
+            line_num,
+            range.start as u32 + 1,
+        )
+        .with_context(format!("{} at line {}", describe(&segment.tag), line_num))
+        .with_priority(Priority::High)
+        .with_metadata(serde_json::json!({ DOC_TAG_KEY: segment.tag }));
+        unit.detect_language();
+        units.push(unit);
+    }
+    license::mark_license_headers(content, &mut units);
+
+    result.units.extend(units);
+    result
+        .units
+        .sort_by_key(|unit| (unit.line_number, unit.column_number));
+    if let Some(metadata) = result.metadata.as_mut().and_then(|m| m.as_object_mut()) {
+        metadata.insert("parser".to_string(), parser.into());
+    }
+    Ok(result)
+}
+
+/// Rebuild a file whose doc comments are `docs(lines)`
+///
+/// The generic parser writes back the other units first; each segment is
+/// then split over its lines, on lines the generic parser left as they were.
+pub(crate) fn reconstruct(
+    generic: &GenericCodeParser,
+    original: &str,
+    units: &[TranslatableUnit],
+    path: &str,
+    docs: impl FnOnce(&[&str]) -> Option<DocComments>,
+) -> Result<String> {
+    let (doc_units, others): (Vec<TranslatableUnit>, Vec<TranslatableUnit>) =
+        units.iter().cloned().partition(is_doc_text);
+    let rebuilt = generic.reconstruct(original, &others, path)?;
+    if doc_units.is_empty() {
+        return Ok(rebuilt);
+    }
+    let lines: Vec<&str> = original.lines().collect();
+    let Some(docs) = docs(&lines) else {
+        return Ok(rebuilt);
+    };
+
+    // Edits of each line, as `(byte range, new text)`
+    let mut edits: BTreeMap<usize, Vec<(Range<usize>, String)>> = BTreeMap::new();
+    for unit in &doc_units {
+        let line = (unit.line_number as usize).saturating_sub(1);
+        let column = (unit.column_number as usize).saturating_sub(1);
+        let Some(segment) = docs
+            .segments
+            .iter()
+            .find(|segment| segment.lines[0].0 == line && segment.lines[0].1.start == column)
+        else {
+            continue;
+        };
+        let pieces = paragraph::split_paragraph(&unit.content, segment.lines.len());
+        for ((line, range), piece) in segment.lines.iter().zip(pieces) {
+            edits.entry(*line).or_default().push((range.clone(), piece));
+        }
+    }
+
+    let mut result = String::with_capacity(rebuilt.len());
+    for (i, line) in rebuilt.split_inclusive('\n').enumerate() {
+        let body = line.trim_end_matches(['\n', '\r']);
+        match edits.get_mut(&i) {
+            Some(line_edits) if lines.get(i) == Some(&body) => {
+                line_edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+                let mut new_line = body.to_string();
+                for (range, text) in line_edits.iter() {
+                    new_line.replace_range(range.clone(), text);
+                }
+                result.push_str(&new_line);
+                result.push_str(&line[body.len()..]);
+            }
+            _ => result.push_str(line),
+        }
+    }
+    Ok(result)
+}
@@ -0,0 +1,333 @@
+//! Structure of Python docstrings
+//!
+//! Google (`Args:` sections), NumPy (underlined section headers), Sphinx
+//! (`:param x:` fields) and Epytext (`@param x:`) docstrings mix prose with
+//! headers, parameter names and types that documentation tools read.
+//! [`analyze`] splits a docstring into its sections and the description
+//! text within them; only the descriptions are translated, so headers,
+//! field names, type annotations, doctests and literal blocks stay in the
+//! form the tools expect.
+
+use regex::Regex;
+use std::ops::Range;
+use std::sync::OnceLock;
+
+/// Section headers of Google and NumPy style docstrings
+const SECTIONS: &[&str] = &[
+    "Args",
+    "Arguments",
+    "Attributes",
+    "Example",
+    "Examples",
+    "Keyword Args",
+    "Keyword Arguments",
+    "Methods",
+    "Note",
+    "Notes",
+    "Other Parameters",
+    "Parameters",
+    "Raises",
+    "Receives",
+    "References",
+    "Return",
+    "Returns",
+    "See Also",
+    "Todo",
+    "Warning",
+    "Warnings",
+    "Warns",
+    "Yield",
+    "Yields",
+];
+
+/// Sections listing names or types, each followed by its description;
+/// the others hold prose
+const FIELD_SECTIONS: &[&str] = &[
+    "Args",
+    "Arguments",
+    "Attributes",
+    "Keyword Args",
+    "Keyword Arguments",
+    "Methods",
+    "Other Parameters",
+    "Parameters",
+    "Raises",
+    "Receives",
+    "Return",
+    "Returns",
+    "See Also",
+    "Warns",
+    "Yield",
+    "Yields",
+];
+
+/// Part of a docstring: the description before the first header, or a
+/// section
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    /// Header without its colon (`Args`, `Parameters`), none for the
+    /// description
+    pub header: Option<String>,
+    pub segments: Vec<Segment>,
+}
+
+/// Description text, translated as one piece
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    /// Field the text describes, as written (`x (int):`, `:param x:`)
+    pub field: Option<String>,
+    /// Text on each line, as `(0-based line, byte range)`
+    pub lines: Vec<(usize, Range<usize>)>,
+}
+
+/// Sphinx and Epytext fields (`:param x:`, `@returns:`), bullets and
+/// numbered items
+fn item_marker() -> &'static Regex {
+    static MARKER: OnceLock<Regex> = OnceLock::new();
+    MARKER.get_or_init(|| {
+        Regex::new(r"^(?::[A-Za-z][^:`]*:|@[A-Za-z]+(?:\s+[\w.*]+)?:|[-*+]|\d+[.)])(?:\s+|$)")
+            .unwrap()
+    })
+}
+
+/// Sphinx and Epytext type fields, kept whole: `:type x: int`, `:rtype: str`
+fn type_field() -> &'static Regex {
+    static FIELD: OnceLock<Regex> = OnceLock::new();
+    FIELD.get_or_init(|| Regex::new(r"^[:@](?:type|rtype|vartype|kwtype)\b").unwrap())
+}
+
+/// Google style fields: `x:`, `x (int):`, `ValueError:`, `dict[str, int]:`
+fn google_field() -> &'static Regex {
+    static FIELD: OnceLock<Regex> = OnceLock::new();
+    FIELD.get_or_init(|| {
+        Regex::new(r"^[A-Za-z0-9_.*]+(?:\[.*?\]+)?(?:\s*\([^()]*\))?:(?:\s+|$)").unwrap()
+    })
+}
+
+fn is_underline(trimmed: &str) -> bool {
+    trimmed.len() >= 3 && trimmed.chars().all(|c| matches!(c, '-' | '=' | '~' | '^'))
+}
+
+/// The sections of a docstring, given as the lines of its body
+///
+/// Headers and their underlines, NumPy name and type lines, field names,
+/// type fields, doctests and literal blocks (after `::`) are in no
+/// segment. Lines continue a segment while they are not blank, start no
+/// new field or item, and are indented at least as deep as its first line.
+pub fn analyze(lines: &[&str]) -> Vec<Section> {
+    let mut sections = vec![Section {
+        header: None,
+        segments: Vec::new(),
+    }];
+    // Whether the next line can continue the last segment, and its indent
+    let mut open = false;
+    let mut segment_indent = 0;
+    // Indent of the current header, and whether it is Google style
+    let mut header: Option<(usize, bool)> = None;
+    let mut doctest = false;
+    let mut literal_block: Option<usize> = None;
+
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        let indent = line.len() - line.trim_start().len();
+        if trimmed.is_empty() {
+            open = false;
+            doctest = false;
+            continue;
+        }
+        match literal_block {
+            Some(intro) if indent > intro => continue,
+            _ => literal_block = None,
+        }
+        if doctest || trimmed.starts_with(">>>") {
+            doctest = true;
+            open = false;
+            continue;
+        }
+        // A Google section ends at the first line not indented under it
+        if header.is_some_and(|(at, google)| google && indent <= at) {
+            header = None;
+            sections.push(Section {
+                header: None,
+                segments: Vec::new(),
+            });
+        }
+
+        let underlined = lines
+            .get(i + 1)
+            .is_some_and(|next| is_underline(next.trim()));
+        let name = trimmed
+            .strip_suffix(':')
+            .filter(|name| !name.ends_with(':'));
+        let name = name.unwrap_or(trimmed);
+        if SECTIONS.contains(&name) && (trimmed.ends_with(':') || underlined) {
+            header = Some((indent, !underlined));
+            sections.push(Section {
+                header: Some(name.to_string()),
+                segments: Vec::new(),
+            });
+            open = false;
+            continue;
+        }
+        if is_underline(trimmed) || type_field().is_match(trimmed) {
+            open = false;
+            continue;
+        }
+
+        let section = sections.last_mut().expect("sections start with one");
+        let fields = section
+            .header
+            .as_deref()
+            .is_some_and(|name| FIELD_SECTIONS.contains(&name));
+        let rest = &line[indent..];
+        let marker = match header {
+            // NumPy names and types sit at the header's indent
+            Some((at, false)) if fields && indent <= at => {
+                open = false;
+                continue;
+            }
+            Some((_, true)) if fields => google_field().find(rest),
+            _ => None,
+        }
+        .or_else(|| item_marker().find(rest))
+        .map_or(0, |marker| marker.end());
+        let start = indent + marker;
+        let end = line.trim_end().len().max(start);
+
+        if start < end {
+            match section.segments.last_mut() {
+                Some(segment) if open && marker == 0 && indent >= segment_indent => {
+                    segment.lines.push((i, start..end));
+                }
+                _ => {
+                    // Bullets and numbers are markers, not fields
+                    let field = rest[..marker].trim();
+                    section.segments.push(Segment {
+                        field: field.ends_with(':').then(|| field.to_string()),
+                        lines: vec![(i, start..end)],
+                    });
+                    segment_indent = indent;
+                }
+            }
+            open = true;
+        } else {
+            open = false;
+        }
+        if trimmed.ends_with("::") {
+            literal_block = Some(indent);
+            open = false;
+        }
+    }
+
+    sections.retain(|section| section.header.is_some() || !section.segments.is_empty());
+    sections
+}
+
+/// The segments of all sections, in order
+pub fn segments(sections: &[Section]) -> impl Iterator<Item = (&Section, &Segment)> {
+    sections.iter().flat_map(|section| {
+        section
+            .segments
+            .iter()
+            .map(move |segment| (section, segment))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `(header, field, text)` of each segment
+    fn texts(lines: &[&str]) -> Vec<(Option<String>, Option<String>, String)> {
+        let sections = analyze(lines);
+        segments(&sections)
+            .map(|(section, segment)| {
+                let text: Vec<&str> = segment
+                    .lines
+                    .iter()
+                    .map(|(line, range)| &lines[*line][range.clone()])
+                    .collect();
+                (
+                    section.header.clone(),
+                    segment.field.clone(),
+                    text.join(" "),
+                )
+            })
+            .collect()
+    }
+
+    fn segment(
+        header: Option<&str>,
+        field: Option<&str>,
+        text: &str,
+    ) -> (Option<String>, Option<String>, String) {
+        (
+            header.map(str::to_string),
+            field.map(str::to_string),
+            text.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_google_and_sphinx_sections() {
+        let lines = [
+            "计算两个数的和。",
+            "",
+            "Args:",
+            "    a (int): 第一个数，",
+            "        不能为负",
+            "    b (dict[str, int]): 第二个数",
+            "",
+            "Returns:",
+            "    int: 两数之和",
+            "",
+            "Note:",
+            "    说明: 结果不会溢出",
+            "",
+            ":param c: 第三个数",
+            ":type c: int",
+            ":rtype: int",
+            "",
+        ];
+        assert_eq!(
+            texts(&lines),
+            vec![
+                segment(None, None, "计算两个数的和。"),
+                segment(Some("Args"), Some("a (int):"), "第一个数， 不能为负"),
+                segment(Some("Args"), Some("b (dict[str, int]):"), "第二个数"),
+                segment(Some("Returns"), Some("int:"), "两数之和"),
+                segment(Some("Note"), None, "说明: 结果不会溢出"),
+                segment(None, Some(":param c:"), "第三个数"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_numpy_sections() {
+        let lines = [
+            "计算总和",
+            "",
+            "Parameters",
+            "----------",
+            "values : list of float",
+            "    要相加的数",
+            "",
+            "Notes",
+            "-----",
+            "空列表的和为零。",
+            "",
+            "Examples",
+            "--------",
+            ">>> total([1, 2])",
+            "3",
+        ];
+        assert_eq!(
+            texts(&lines),
+            vec![
+                segment(None, None, "计算总和"),
+                segment(Some("Parameters"), None, "要相加的数"),
+                segment(Some("Notes"), None, "空列表的和为零。"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,103 @@
+//! Generated file detection
+//!
+//! Code generators mark their output near the top of the file (`@generated`,
+//! `Code generated ... DO NOT EDIT.`, protobuf compiler headers). Translating
+//! such files is wasted money and churn, since the next generator run
+//! overwrites it. Parsers flag generated files in the parse result
+//! metadata, and callers skip them unless the user opts in (see
+//! [`is_generated`]).
+
+use langlint_core::ParseResult;
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// Parse result metadata key flagging a generated file
+pub const GENERATED_KEY: &str = "generated";
+
+/// Number of leading lines searched for a generated-file marker
+pub const MARKER_LINES: usize = 10;
+
+/// Check if file content starts with a generated-file marker
+pub fn is_generated_content(content: &str) -> bool {
+    static MARKER_RE: OnceLock<Regex> = OnceLock::new();
+    let re = MARKER_RE.get_or_init(|| {
+        Regex::new(
+            r"(?i)@generated\b|\bDO NOT EDIT\b|generated by the protocol buffer compiler|\bauto-?generated (?:file|code|by)\b|this file (?:is|was|has been) (?:automatically |auto-)?generated",
+        )
+        .unwrap()
+    });
+
+    content
+        .lines()
+        .take(MARKER_LINES)
+        .any(|line| re.is_match(line))
+}
+
+/// Flag a parse result if its file is generated
+pub fn mark_generated(content: &str, result: &mut ParseResult) {
+    if !is_generated_content(content) {
+        return;
+    }
+
+    match result.metadata.as_mut().and_then(|m| m.as_object_mut()) {
+        Some(metadata) => {
+            metadata.insert(GENERATED_KEY.to_string(), true.into());
+        }
+        None => result.metadata = Some(serde_json::json!({ GENERATED_KEY: true })),
+    }
+}
+
+/// Check if a parse result comes from a generated file
+pub fn is_generated(result: &ParseResult) -> bool {
+    result
+        .metadata
+        .as_ref()
+        .and_then(|m| m.get(GENERATED_KEY))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_generated_content() {
+        assert!(is_generated_content(
+            "// Code generated by protoc-gen-go. DO NOT EDIT.\npackage pb\n"
+        ));
+        assert!(is_generated_content(
+            "# -*- coding: utf-8 -*-\n# Generated by the protocol buffer compiler.  DO NOT EDIT!\n"
+        ));
+        assert!(is_generated_content("/**\n * @generated\n */\n"));
+        assert!(is_generated_content(
+            "# This file is automatically generated by setup.py\n"
+        ));
+
+        assert!(!is_generated_content(
+            "# 计算两个数的和\ndef add(a, b): ...\n"
+        ));
+        assert!(!is_generated_content(
+            "# The generator below builds the report\nx = 1\n"
+        ));
+    }
+
+    #[test]
+    fn test_marker_must_be_in_header() {
+        let mut content = "x = 1\n".repeat(MARKER_LINES);
+        content.push_str("# DO NOT EDIT\n");
+        assert!(!is_generated_content(&content));
+    }
+
+    #[test]
+    fn test_mark_generated() {
+        let mut result = ParseResult::new("python", "utf-8", 1)
+            .with_metadata(serde_json::json!({ "parser": "PythonParser" }));
+        mark_generated("x = 1\n", &mut result);
+        assert!(!is_generated(&result));
+
+        mark_generated("# @generated\n", &mut result);
+        assert!(is_generated(&result));
+        assert_eq!(result.metadata.unwrap()["parser"], "PythonParser");
+    }
+}
@@ -1,16 +1,54 @@
 use anyhow::Result;
-use langlint_core::{ParseResult, Priority, TranslatableUnit, UnitType};
-use regex::Regex;
+use langlint_core::{MarkdownBlocks, ParseResult, Priority, TranslatableUnit, UnitType};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+use tree_sitter::{Language, Node};
 
-use crate::Parser;
+use crate::{generated, jsx, license, paragraph, pragma, sinks, todo, Parser};
 
 /// Generic code parser for various programming languages
 /// Supports: JavaScript, TypeScript, Go, Rust, Java, C/C++, R, and more
-pub struct GenericCodeParser;
+pub struct GenericCodeParser {
+    /// Language used instead of the file extension, as an extension (`.sh`)
+    language: Option<String>,
+}
 
 impl GenericCodeParser {
     pub fn new() -> Self {
-        Self
+        Self { language: None }
+    }
+
+    /// Parse every file as `language` (`groovy`, `shell`, `.lua`), whatever
+    /// its extension
+    pub fn for_language(language: &str) -> Self {
+        let language = language.trim().trim_start_matches('.').to_lowercase();
+        let extension = match language.as_str() {
+            "javascript" => "js",
+            "typescript" => "ts",
+            "rust" => "rs",
+            "shell" | "bash" | "zsh" => "sh",
+            "python" => "py",
+            "ruby" => "rb",
+            "kotlin" => "kt",
+            "csharp" | "c#" => "cs",
+            "c++" => "cpp",
+            other => other,
+        };
+        Self {
+            language: Some(format!(".{}", extension)),
+        }
+    }
+
+    /// Extension selecting the comment style of a file
+    pub(crate) fn extension(&self, path: &str) -> String {
+        if let Some(language) = &self.language {
+            return language.clone();
+        }
+        std::path::Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| format!(".{}", e))
+            .unwrap_or_else(|| ".unknown".to_string())
     }
 
     /// Get comment patterns for different languages
@@ -44,7 +82,7 @@ impl GenericCodeParser {
     }
 
     /// Check if text should be translated
-    fn is_translatable(&self, text: &str) -> bool {
+    pub(crate) fn is_translatable(&self, text: &str) -> bool {
         let text = text.trim();
 
         // Skip empty or very short text
@@ -52,6 +90,13 @@ impl GenericCodeParser {
             return false;
         }
 
+        // Judge diagrams and box tables by their labels, not the drawing
+        let diagram = MarkdownBlocks::parse_diagram(text);
+        if diagram.is_structured() {
+            let labels = diagram.texts().join(" ");
+            return !labels.is_empty() && self.is_translatable(&labels);
+        }
+
         // Skip URLs
         if text.contains("://") {
             return false;
@@ -96,67 +141,14 @@ impl GenericCodeParser {
     fn contains_non_ascii(&self, text: &str) -> bool {
         text.chars().any(|c| c as u32 > 127)
     }
-}
 
-impl Default for GenericCodeParser {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-struct CommentStyle {
-    single_line: Vec<&'static str>,
-    multi_line_start: Option<&'static str>,
-    multi_line_end: Option<&'static str>,
-}
-
-impl Parser for GenericCodeParser {
-    fn name(&self) -> &'static str {
-        "GenericCodeParser"
-    }
-
-    fn supported_extensions(&self) -> &'static [&'static str] {
-        &[
-            ".js", ".ts", ".jsx", ".tsx",  // JavaScript/TypeScript
-            ".go",   // Go
-            ".rs",   // Rust
-            ".java", // Java
-            ".c", ".cpp", ".h", ".hpp", // C/C++
-            ".cs",  // C#
-            ".php", // PHP
-            ".rb",  // Ruby
-            ".sh", ".bash", // Shell
-            ".sql",  // SQL
-            ".r", ".R",     // R
-            ".m",     // MATLAB/Objective-C
-            ".scala", // Scala
-            ".kt",    // Kotlin
-            ".swift", // Swift
-            ".dart",  // Dart
-            ".lua",   // Lua
-            ".vim",   // Vim script
-        ]
-    }
-
-    fn can_parse(&self, path: &str, _content: Option<&str>) -> bool {
-        self.supported_extensions()
-            .iter()
-            .any(|ext| path.ends_with(ext))
-    }
-
-    fn extract_units(&self, content: &str, path: &str) -> Result<ParseResult> {
+    /// Comments of a file scanned line by line
+    fn line_comment_units(
+        &self,
+        lines: &[&str],
+        comment_style: &CommentStyle,
+    ) -> Vec<TranslatableUnit> {
         let mut units = Vec::new();
-        let lines: Vec<&str> = content.lines().collect();
-
-        // Determine comment style based on file extension
-        let extension = std::path::Path::new(path)
-            .extension()
-            .and_then(|e| e.to_str())
-            .map(|e| format!(".{}", e))
-            .unwrap_or_else(|| ".unknown".to_string());
-
-        let comment_style = self.get_comment_patterns(&extension);
-
         let mut in_multi_line_comment = false;
         let mut multi_line_content = String::new();
         let mut multi_line_start = 0;
@@ -241,17 +233,32 @@ impl Parser for GenericCodeParser {
             if !in_multi_line_comment {
                 for marker in &comment_style.single_line {
                     if let Some(pos) = line.find(marker) {
-                        let comment_text = line[pos + marker.len()..].trim();
+                        let after_marker = &line[pos + marker.len()..];
+                        if pragma::is_pragma_comment(marker, after_marker) {
+                            break;
+                        }
+                        let comment_text = after_marker.trim();
+                        let (todo_marker, body) =
+                            todo::split_todo_marker(comment_text).unwrap_or(("", comment_text));
+                        let (body, trailing) = pragma::split_trailing_pragma(body, marker);
 
-                        if self.is_translatable(comment_text) {
+                        if self.is_translatable(body) {
                             let mut unit = TranslatableUnit::new(
-                                comment_text.to_string(),
+                                body.to_string(),
                                 UnitType::Comment,
                                 line_num,
                                 (pos + 1) as u32,
                             )
                             .with_context(format!("Single-line comment at line {}", line_num))
                             .with_priority(Priority::Medium);
+                            if !todo_marker.is_empty() {
+                                unit = unit.with_metadata(
+                                    serde_json::json!({ todo::TODO_MARKER_KEY: todo_marker }),
+                                );
+                            }
+                            if let Some(trailing) = trailing {
+                                pragma::set_trailing_pragma(&mut unit, trailing);
+                            }
 
                             // Detect language
                             unit.detect_language();
@@ -264,16 +271,427 @@ impl Parser for GenericCodeParser {
             }
         }
 
+        units
+    }
+
+    /// Comments found in a syntax tree
+    fn tree_comment_units(
+        &self,
+        lines: &[&str],
+        comments: &[TreeComment],
+    ) -> Vec<TranslatableUnit> {
+        let mut units = Vec::new();
+        for comment in comments {
+            let line_num = comment.line as u32 + 1;
+            let text = |(line, range): &(usize, Range<usize>)| &lines[*line][range.clone()];
+
+            if !comment.block {
+                let Some(body_line) = comment.lines.first() else {
+                    continue;
+                };
+                // `//` directives, even after `///`
+                let after_slashes = &lines[comment.line][comment.column + 2..];
+                if pragma::is_pragma_comment("//", after_slashes) {
+                    continue;
+                }
+                let comment_text = text(body_line);
+                let (todo_marker, body) =
+                    todo::split_todo_marker(comment_text).unwrap_or(("", comment_text));
+                let (body, trailing) = pragma::split_trailing_pragma(body, "//");
+                if !self.is_translatable(body) {
+                    continue;
+                }
+                let mut unit = TranslatableUnit::new(
+                    body.to_string(),
+                    UnitType::Comment,
+                    line_num,
+                    comment.column as u32 + 1,
+                )
+                .with_context(format!("Single-line comment at line {}", line_num))
+                .with_priority(Priority::Medium);
+                if !todo_marker.is_empty() {
+                    unit = unit
+                        .with_metadata(serde_json::json!({ todo::TODO_MARKER_KEY: todo_marker }));
+                }
+                if let Some(trailing) = trailing {
+                    pragma::set_trailing_pragma(&mut unit, trailing);
+                }
+                unit.detect_language();
+                units.push(unit);
+                continue;
+            }
+
+            let content = comment
+                .lines
+                .iter()
+                .map(text)
+                .fold(String::new(), |content, line| {
+                    if content.is_empty() {
+                        line.to_string()
+                    } else {
+                        paragraph::join_wrapped(&content, line)
+                    }
+                });
+            if pragma::is_pragma(&content) || !self.is_translatable(&content) {
+                continue;
+            }
+            let last_line = comment
+                .lines
+                .last()
+                .map_or(line_num, |(line, _)| *line as u32 + 1);
+            let context = if last_line > line_num {
+                format!("Multi-line comment at lines {}-{}", line_num, last_line)
+            } else {
+                format!("Multi-line comment at line {}", line_num)
+            };
+            let mut unit = TranslatableUnit::new(
+                content,
+                UnitType::Comment,
+                line_num,
+                comment.column as u32 + 1,
+            )
+            .with_context(context)
+            .with_priority(Priority::Medium);
+            unit.detect_language();
+            units.push(unit);
+        }
+        units
+    }
+}
+
+impl Default for GenericCodeParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Check if an extension is of JavaScript or TypeScript
+fn is_script(extension: &str) -> bool {
+    matches!(extension, ".js" | ".ts" | ".jsx" | ".tsx")
+}
+
+/// Languages whose strings passed to print, logging or exceptions are
+/// found by [`sinks::extract_code_sink_strings`]
+fn has_code_sinks(extension: &str) -> bool {
+    matches!(
+        extension,
+        ".go"
+            | ".rs"
+            | ".java"
+            | ".kt"
+            | ".scala"
+            | ".cs"
+            | ".swift"
+            | ".c"
+            | ".h"
+            | ".cpp"
+            | ".hpp"
+    )
+}
+
+struct CommentStyle {
+    single_line: Vec<&'static str>,
+    multi_line_start: Option<&'static str>,
+    multi_line_end: Option<&'static str>,
+}
+
+impl CommentStyle {
+    /// Indent, marker and the gap before the text of a comment-only line
+    fn comment_prefix(&self, line: &str) -> Option<String> {
+        let trimmed = line.trim_start();
+        let marker = self
+            .single_line
+            .iter()
+            .find(|marker| trimmed.starts_with(**marker))?;
+        let text = trimmed[marker.len()..].trim_start();
+        Some(line[..line.len() - text.len()].to_string())
+    }
+}
+
+/// Grammar of the languages whose comments are read from a syntax tree
+fn grammar(extension: &str) -> Option<Language> {
+    Some(match extension {
+        ".js" | ".jsx" => tree_sitter_javascript::language(),
+        ".ts" => tree_sitter_typescript::language_typescript(),
+        ".tsx" => tree_sitter_typescript::language_tsx(),
+        ".go" => tree_sitter_go::language(),
+        ".rs" => tree_sitter_rust::language(),
+        ".java" => tree_sitter_java::language(),
+        ".c" | ".h" => tree_sitter_c::language(),
+        ".cpp" | ".hpp" => tree_sitter_cpp::language(),
+        _ => return None,
+    })
+}
+
+/// A comment node of a syntax tree
+pub(crate) struct TreeComment {
+    /// 0-based line and byte column of the comment start
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+    /// `/* */` rather than `//`
+    pub(crate) block: bool,
+    /// Byte column past the marker (`//`, `///`, `/**`) on the first line
+    pub(crate) marker_end: usize,
+    /// Lines holding text, as `(0-based line, byte range of the text)`
+    pub(crate) lines: Vec<(usize, Range<usize>)>,
+}
+
+impl TreeComment {
+    fn from_node(node: Node, content: &str) -> Option<Self> {
+        let text = &content[node.byte_range()];
+        let line = node.start_position().row;
+        let column = node.start_position().column;
+        let block = text.starts_with("/*");
+        if !block && !text.starts_with("//") {
+            return None;
+        }
+
+        // Doc markers (`///`, `//!`, `/**`, `/*!`) are kept with the marker
+        let marker_len = 2 + text[2..]
+            .chars()
+            .take_while(|c| matches!(c, '/' | '!' | '*'))
+            .take(if block { usize::MAX } else { 1 })
+            .count();
+        let mut lines = Vec::new();
+        let pieces: Vec<&str> = text.split('\n').collect();
+        for (index, piece) in pieces.iter().enumerate() {
+            let base = if index == 0 { column } else { 0 };
+            let mut start = if index == 0 {
+                marker_len.min(piece.len())
+            } else {
+                0
+            };
+            let mut end = piece.trim_end_matches('\r').len();
+            if block && index == pieces.len() - 1 {
+                end = piece[..end]
+                    .strip_suffix("*/")
+                    .map_or(end, |body| body.trim_end_matches('*').len())
+                    .max(start);
+            }
+            if block && index > 0 {
+                // Leading ` * ` decoration of a block comment line
+                let indent = piece.len() - piece.trim_start().len();
+                start = indent + usize::from(piece[indent..end.max(indent)].starts_with('*'));
+            }
+            let body = &piece[start..end];
+            let text_start = start + body.len() - body.trim_start().len();
+            let text_end = start + body.trim_end().len();
+            if text_start < text_end {
+                lines.push((line + index, base + text_start..base + text_end));
+            }
+        }
+
+        Some(Self {
+            line,
+            column,
+            block,
+            marker_end: column + marker_len,
+            lines,
+        })
+    }
+}
+
+/// Comments of a file in a language with a grammar
+///
+/// Returns `None` for other languages and for files the grammar cannot
+/// parse without errors, which are scanned line by line instead.
+pub(crate) fn tree_comments(content: &str, extension: &str) -> Option<Vec<TreeComment>> {
+    let mut parser = tree_sitter::Parser::new();
+    parser.set_language(grammar(extension)?).ok()?;
+    let tree = parser.parse(content, None)?;
+    if tree.root_node().has_error() {
+        return None;
+    }
+
+    let mut comments = Vec::new();
+    let mut stack = vec![tree.root_node()];
+    while let Some(node) = stack.pop() {
+        if matches!(node.kind(), "comment" | "line_comment" | "block_comment") {
+            comments.extend(TreeComment::from_node(node, content));
+            continue;
+        }
+        let mut cursor = node.walk();
+        let children: Vec<Node> = node.children(&mut cursor).collect();
+        stack.extend(children.into_iter().rev());
+    }
+    Some(comments)
+}
+
+/// Write translated comments into the text ranges of their comment nodes
+fn rewrite_tree_comments(
+    lines: &[&str],
+    units: &[TranslatableUnit],
+    comments: &[TreeComment],
+    line_replacements: &mut HashMap<usize, String>,
+) {
+    // Edits of each line, as `(byte range, new text)`
+    let mut edits: BTreeMap<usize, Vec<(Range<usize>, String)>> = BTreeMap::new();
+    let comment_at = |line: usize, column: Option<usize>| {
+        comments.iter().find(|comment| {
+            comment.line == line && column.is_none_or(|column| comment.column == column)
+        })
+    };
+
+    for unit in units
+        .iter()
+        .filter(|unit| unit.unit_type == UnitType::Comment)
+    {
+        let line = (unit.line_number as usize).saturating_sub(1);
+        let column = (unit.column_number as usize).saturating_sub(1);
+        let Some(comment) = comment_at(line, Some(column)).or_else(|| comment_at(line, None))
+        else {
+            continue;
+        };
+
+        if comment.block {
+            let pieces = paragraph::split_paragraph(&unit.content, comment.lines.len());
+            for ((line, range), piece) in comment.lines.iter().zip(pieces) {
+                edits.entry(*line).or_default().push((range.clone(), piece));
+            }
+            continue;
+        }
+
+        // Spread a comment paragraph over its lines
+        let count = paragraph::paragraph_lines(unit);
+        if count > 1 {
+            let pieces = paragraph::split_paragraph(&unit.content, count);
+            for (offset, piece) in pieces.into_iter().enumerate() {
+                let Some(comment) = comment_at(line + offset, None).filter(|c| !c.block) else {
+                    break;
+                };
+                let end =
+                    lines[comment.line][comment.marker_end..].trim_end().len() + comment.marker_end;
+                let text = match comment.lines.first() {
+                    Some((_, range)) if !piece.is_empty() => {
+                        let gap = &lines[comment.line][comment.marker_end..range.start];
+                        format!("{}{}", gap, piece)
+                    }
+                    _ if piece.is_empty() => String::new(),
+                    _ => format!(" {}", piece),
+                };
+                edits
+                    .entry(comment.line)
+                    .or_default()
+                    .push((comment.marker_end..end, text));
+            }
+            continue;
+        }
+
+        let Some((_, range)) = comment.lines.first() else {
+            continue;
+        };
+        // Keep the original indent after the marker so diagrams stay aligned
+        let gap = &lines[comment.line][comment.marker_end..range.start];
+        let text = format!(
+            "{}{}",
+            if gap.is_empty() { " " } else { gap },
+            pragma::with_trailing_pragma(
+                unit,
+                todo::comment_text(unit),
+                &lines[comment.line][range.clone()]
+            )
+        );
+        edits
+            .entry(comment.line)
+            .or_default()
+            .push((comment.marker_end..range.end, text));
+    }
+
+    for (line, mut line_edits) in edits {
+        let Some(original) = lines.get(line) else {
+            continue;
+        };
+        line_edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+        let mut new_line = original.to_string();
+        let mut limit = new_line.len();
+        for (range, text) in line_edits {
+            // A unit given twice replaces its text once
+            if range.end > limit {
+                continue;
+            }
+            new_line.replace_range(range.clone(), &text);
+            limit = range.start;
+        }
+        line_replacements.insert(line, new_line);
+    }
+}
+
+impl Parser for GenericCodeParser {
+    fn name(&self) -> &'static str {
+        "GenericCodeParser"
+    }
+
+    fn supported_extensions(&self) -> &'static [&'static str] {
+        &[
+            ".js", ".ts", ".jsx", ".tsx",  // JavaScript/TypeScript
+            ".go",   // Go
+            ".rs",   // Rust
+            ".java", // Java
+            ".c", ".cpp", ".h", ".hpp", // C/C++
+            ".cs",  // C#
+            ".php", // PHP
+            ".rb",  // Ruby
+            ".sh", ".bash", // Shell
+            ".sql",  // SQL
+            ".r", ".R",     // R
+            ".m",     // MATLAB/Objective-C
+            ".scala", // Scala
+            ".kt",    // Kotlin
+            ".swift", // Swift
+            ".dart",  // Dart
+            ".lua",   // Lua
+            ".vim",   // Vim script
+        ]
+    }
+
+    fn can_parse(&self, path: &str, _content: Option<&str>) -> bool {
+        self.supported_extensions()
+            .iter()
+            .any(|ext| path.ends_with(ext))
+    }
+
+    fn extract_units(&self, content: &str, path: &str) -> Result<ParseResult> {
+        let lines: Vec<&str> = content.lines().collect();
+
+        // Determine comment style based on file extension
+        let extension = self.extension(path);
+
+        let comment_style = self.get_comment_patterns(&extension);
+
+        let comments = tree_comments(content, &extension);
+        let units = match &comments {
+            Some(comments) => self.tree_comment_units(&lines, comments),
+            None => self.line_comment_units(&lines, &comment_style),
+        };
+
         let line_count = lines.len() as u32;
-        let result = ParseResult::new("generic_code", "utf-8", line_count)
+        let mut units =
+            paragraph::merge_paragraphs(&lines, units, |line| comment_style.comment_prefix(line));
+        license::mark_license_headers(content, &mut units);
+        if is_script(&extension) {
+            units.extend(sinks::extract_sink_strings(content));
+            // TypeScript proper reads `<T>value` as a type assertion
+            if extension != ".ts" {
+                units.extend(jsx::extract_jsx_text(content));
+            }
+            units.sort_by_key(|unit| (unit.line_number, unit.column_number));
+        } else if has_code_sinks(&extension) {
+            units.extend(sinks::extract_code_sink_strings(content));
+            units.sort_by_key(|unit| (unit.line_number, unit.column_number));
+        }
+
+        let mut result = ParseResult::new("generic_code", "utf-8", line_count)
             .with_units(units)
             .with_metadata(serde_json::json!({
                 "parser": "GenericCodeParser",
-                "version": "0.1.0",
+                "version": "0.2.0",
                 "file_path": path,
                 "extension": extension,
+                "syntax_tree": comments.is_some(),
             }));
 
+        generated::mark_generated(content, &mut result);
+
         Ok(result)
     }
 
@@ -283,45 +701,117 @@ impl Parser for GenericCodeParser {
         units: &[TranslatableUnit],
         path: &str,
     ) -> Result<String> {
-        let mut result = original.to_string();
-
-        // Sort units by line number (reverse order for safe replacement)
-        let mut sorted_units: Vec<_> = units.iter().collect();
-        sorted_units.sort_by(|a, b| b.line_number.cmp(&a.line_number));
-
         // Determine comment style
-        let extension = std::path::Path::new(path)
-            .extension()
-            .and_then(|e| e.to_str())
-            .map(|e| format!(".{}", e))
-            .unwrap_or_else(|| ".unknown".to_string());
+        let extension = self.extension(path);
 
         let comment_style = self.get_comment_patterns(&extension);
         let lines: Vec<&str> = original.lines().collect();
+        let mut line_replacements: HashMap<usize, String> = HashMap::new();
+
+        // Comments found in a syntax tree are replaced by their spans, before
+        // strings and JSX text, which check their text is still in place
+        let comments = tree_comments(original, &extension);
+        if let Some(comments) = &comments {
+            rewrite_tree_comments(&lines, units, comments, &mut line_replacements);
+        }
 
-        for unit in sorted_units {
+        // Strings and JSX text first, right to left so earlier columns stay valid
+        let is_inline =
+            |unit: &TranslatableUnit| sinks::is_sink_string(unit) || jsx::is_jsx_text(unit);
+        let mut inline: Vec<&TranslatableUnit> =
+            units.iter().filter(|unit| is_inline(unit)).collect();
+        inline.sort_by_key(|unit| std::cmp::Reverse((unit.line_number, unit.column_number)));
+        for unit in inline {
+            let current = |idx: usize| {
+                line_replacements
+                    .get(&idx)
+                    .cloned()
+                    .or_else(|| lines.get(idx).map(|line| line.to_string()))
+            };
+            if jsx::is_jsx_text(unit) {
+                for (idx, new_line) in jsx::rewrite_lines(unit, current) {
+                    line_replacements.insert(idx, new_line);
+                }
+                continue;
+            }
+            let line_idx = (unit.line_number as usize).saturating_sub(1);
+            if let Some(new_line) =
+                current(line_idx).and_then(|line| sinks::replace_literal(&line, unit))
+            {
+                line_replacements.insert(line_idx, new_line);
+            }
+        }
+
+        let line_comments = units
+            .iter()
+            .filter(|unit| !is_inline(unit) && comments.is_none());
+        for unit in line_comments {
             let line_idx = (unit.line_number as usize).saturating_sub(1);
             if line_idx >= lines.len() {
                 continue;
             }
 
-            let line = lines[line_idx];
+            // Spread a comment paragraph over its lines
+            let count = paragraph::paragraph_lines(unit);
+            if count > 1 {
+                let pieces = paragraph::split_paragraph(&unit.content, count);
+                for (offset, piece) in pieces.iter().enumerate() {
+                    let Some(prefix) = lines
+                        .get(line_idx + offset)
+                        .and_then(|line| comment_style.comment_prefix(line))
+                    else {
+                        break;
+                    };
+                    let new_line = if piece.is_empty() {
+                        prefix.trim_end().to_string()
+                    } else {
+                        format!("{}{}", prefix, piece)
+                    };
+                    line_replacements.insert(line_idx + offset, new_line);
+                }
+                continue;
+            }
+
+            let line = line_replacements
+                .get(&line_idx)
+                .cloned()
+                .unwrap_or_else(|| lines[line_idx].to_string());
+            let line = line.as_str();
 
             // Try to find and replace comment
             for marker in &comment_style.single_line {
                 if let Some(pos) = line.find(marker) {
                     let before_comment = &line[..pos];
-                    let new_line = format!("{}{} {}", before_comment, marker, unit.content);
-
-                    let old_line_pattern = regex::escape(line);
-                    if let Ok(re) = Regex::new(&old_line_pattern) {
-                        result = re.replace(&result, new_line.as_str()).to_string();
-                    }
+                    // Keep the original indent after the marker so diagrams stay aligned
+                    let after_marker = &line[pos + marker.len()..];
+                    let gap = &after_marker[..after_marker.len() - after_marker.trim_start().len()];
+                    let new_line = format!(
+                        "{}{}{}{}",
+                        before_comment,
+                        marker,
+                        if gap.is_empty() { " " } else { gap },
+                        todo::comment_text(unit)
+                    );
+                    line_replacements
+                        .insert(line_idx, pragma::with_trailing_pragma(unit, new_line, line));
                     break;
                 }
             }
         }
 
+        // Rebuild line by line, keeping the original line endings
+        let mut result = String::with_capacity(original.len());
+        for (i, line) in original.split_inclusive('\n').enumerate() {
+            match line_replacements.get(&i) {
+                Some(new_line) => {
+                    let body = line.trim_end_matches(['\n', '\r']);
+                    result.push_str(new_line);
+                    result.push_str(&line[body.len()..]);
+                }
+                None => result.push_str(line),
+            }
+        }
+
         Ok(result)
     }
 }
@@ -356,6 +846,22 @@ function foo() {
         assert!(result.units[0].content.contains("这是一个注释"));
     }
 
+    #[test]
+    fn test_todo_comment_keeps_marker() {
+        let parser = GenericCodeParser::new();
+        let content = "// TODO: 修复\nlet x = 1;";
+
+        let result = parser.extract_units(content, "test.rs").unwrap();
+        assert_eq!(result.units.len(), 1);
+        assert_eq!(result.units[0].content, "修复");
+        assert!(todo::is_todo(&result.units[0]));
+
+        let mut units = result.units.clone();
+        units[0].content = "Fix".to_string();
+        let reconstructed = parser.reconstruct(content, &units, "test.rs").unwrap();
+        assert_eq!(reconstructed, "// TODO: Fix\nlet x = 1;");
+    }
+
     #[test]
     fn test_extract_multiline_comment() {
         let parser = GenericCodeParser::new();
@@ -439,6 +945,17 @@ int main() {
         assert_eq!(result.file_type, "generic_code");
     }
 
+    #[test]
+    fn test_marks_license_header() {
+        let parser = GenericCodeParser::new();
+        let content =
+            "// SPDX-License-Identifier: MIT\n// 版权所有 某公司\n\n// 主函数入口\nfn main() {}\n";
+        let result = parser.extract_units(content, "main.rs").unwrap();
+        assert_eq!(result.units.len(), 2);
+        assert!(crate::license::is_license_header(&result.units[0]));
+        assert!(!crate::license::is_license_header(&result.units[1]));
+    }
+
     #[test]
     fn test_line_count() {
         let parser = GenericCodeParser::new();
@@ -447,6 +964,20 @@ int main() {
         assert_eq!(result.line_count, 3);
     }
 
+    #[test]
+    fn test_for_language() {
+        let parser = GenericCodeParser::for_language("shell");
+        let content = "# 构建脚本\necho build\n";
+        let result = parser.extract_units(content, "Makefile.in").unwrap();
+        assert_eq!(result.units.len(), 1);
+        assert_eq!(result.units[0].content, "构建脚本");
+
+        let mut units = result.units.clone();
+        units[0].content = "Build script".to_string();
+        let rebuilt = parser.reconstruct(content, &units, "Makefile.in").unwrap();
+        assert_eq!(rebuilt, "# Build script\necho build\n");
+    }
+
     #[test]
     fn test_parser_name() {
         let parser = GenericCodeParser::new();
@@ -466,4 +997,136 @@ int main() {
             .unwrap();
         assert!(reconstructed.contains("新注释"));
     }
+
+    #[test]
+    fn test_sink_strings_of_scripts() {
+        let parser = GenericCodeParser::new();
+        let original = "alert('保存成功', `共有 ${n} 个项目`); // 提示用户\n";
+        let result = parser.extract_units(original, "app.tsx").unwrap();
+        let contents: Vec<&str> = result.units.iter().map(|u| u.content.as_str()).collect();
+        assert_eq!(contents, vec!["保存成功", "共有 ${n} 个项目", "提示用户"]);
+        assert_eq!(result.units[0].unit_type, UnitType::StringLiteral);
+
+        let mut units = result.units.clone();
+        units[0].content = "Saved".to_string();
+        units[1].content = "${n} items".to_string();
+        units[2].content = "Tell the user".to_string();
+        let rebuilt = parser.reconstruct(original, &units, "app.tsx").unwrap();
+        assert_eq!(rebuilt, "alert('Saved', `${n} items`); // Tell the user\n");
+
+        // Other languages have sinks of their own
+        let result = parser.extract_units(original, "app.go").unwrap();
+        assert_eq!(result.units.len(), 1);
+        let result = parser
+            .extract_units("panic(\"配置无效\") // 退出\n", "main.go")
+            .unwrap();
+        assert_eq!(result.units[0].unit_type, UnitType::StringLiteral);
+        assert_eq!(result.units[0].content, "配置无效");
+    }
+
+    #[test]
+    fn test_comment_paragraph() {
+        let parser = GenericCodeParser::new();
+        let content = "// 读取配置文件\n// 并返回解析后的字典\nfn load() {}\n// 读取配置文件\n";
+        let result = parser.extract_units(content, "config.rs").unwrap();
+
+        assert_eq!(result.units.len(), 2);
+        assert_eq!(result.units[0].content, "读取配置文件并返回解析后的字典");
+        assert_eq!(paragraph::paragraph_lines(&result.units[1]), 1);
+
+        let mut units = result.units.clone();
+        units[0].content = "Read the configuration file and return the parsed dict".to_string();
+        units[1].content = "Read the configuration file".to_string();
+        let rebuilt = parser.reconstruct(content, &units, "config.rs").unwrap();
+        assert_eq!(
+            rebuilt,
+            "// Read the configuration file\n// and return the parsed dict\nfn load() {}\n// Read the configuration file\n"
+        );
+    }
+
+    #[test]
+    fn test_pragmas_are_not_translated() {
+        let parser = GenericCodeParser::new();
+        let content = "//go:generate stringer -type=状态\n// eslint-disable-next-line 忽略检查\nrun(); // 运行任务 // @ts-ignore\n";
+        let result = parser.extract_units(content, "main.ts").unwrap();
+
+        assert_eq!(result.units.len(), 1);
+        assert_eq!(result.units[0].content, "运行任务");
+
+        let mut unit = result.units[0].clone();
+        unit.content = "Run the task".to_string();
+        let rebuilt = parser.reconstruct(content, &[unit], "main.ts").unwrap();
+        assert!(rebuilt.ends_with("run(); // Run the task // @ts-ignore\n"));
+    }
+
+    #[test]
+    fn test_aligned_trailing_pragmas_stay_aligned() {
+        let parser = GenericCodeParser::new();
+        let content = "let a = load(); // 读取配置文件     // @ts-ignore\nlet b = save(); // 保存             // @ts-ignore\n";
+        let mut units = parser.extract_units(content, "main.ts").unwrap().units;
+        assert_eq!(units.len(), 2);
+        units[0].content = "Read config".to_string();
+        units[1].content = "Save".to_string();
+
+        let rebuilt = parser.reconstruct(content, &units, "main.ts").unwrap();
+        assert_eq!(
+            rebuilt,
+            "let a = load(); // Read config      // @ts-ignore\nlet b = save(); // Save             // @ts-ignore\n"
+        );
+    }
+
+    #[test]
+    fn test_syntax_tree_comments() {
+        let parser = GenericCodeParser::new();
+        let content = "const url = \"http://example.com/首页\"; // 首页地址\n/**\n * 读取配置文件\n * 并返回结果\n */\nconst s = '// 不是注释'; /* 行内 */\n";
+        let result = parser.extract_units(content, "app.js").unwrap();
+        assert_eq!(result.metadata.as_ref().unwrap()["syntax_tree"], true);
+        let units: Vec<(u32, u32, &str)> = result
+            .units
+            .iter()
+            .map(|unit| (unit.line_number, unit.column_number, unit.content.as_str()))
+            .collect();
+        assert_eq!(
+            units,
+            vec![
+                (1, 42, "首页地址"),
+                (2, 1, "读取配置文件并返回结果"),
+                (6, 30, "行内"),
+            ]
+        );
+
+        let mut units = result.units.clone();
+        units[0].content = "Home page".to_string();
+        units[1].content = "Read the config file and return the result".to_string();
+        units[2].content = "Inline".to_string();
+        let rebuilt = parser.reconstruct(content, &units, "app.js").unwrap();
+        assert_eq!(
+            rebuilt,
+            "const url = \"http://example.com/首页\"; // Home page\n/**\n * Read the config file\n * and return the result\n */\nconst s = '// 不是注释'; /* Inline */\n"
+        );
+    }
+
+    #[test]
+    fn test_rust_doc_comments_and_fallback() {
+        let parser = GenericCodeParser::new();
+        let content = "/// 加载配置\nfn load() -> &'static str {\n    \"a//b\" // 路径\n}\n";
+        let result = parser.extract_units(content, "lib.rs").unwrap();
+        let contents: Vec<&str> = result.units.iter().map(|u| u.content.as_str()).collect();
+        assert_eq!(contents, vec!["加载配置", "路径"]);
+
+        let mut units = result.units.clone();
+        units[0].content = "Load the config".to_string();
+        units[1].content = "Path".to_string();
+        let rebuilt = parser.reconstruct(content, &units, "lib.rs").unwrap();
+        assert_eq!(
+            rebuilt,
+            "/// Load the config\nfn load() -> &'static str {\n    \"a//b\" // Path\n}\n"
+        );
+
+        // Code the grammar cannot parse is scanned line by line
+        let broken = "fn broken( {\n// 注释\n";
+        let result = parser.extract_units(broken, "lib.rs").unwrap();
+        assert_eq!(result.metadata.as_ref().unwrap()["syntax_tree"], false);
+        assert_eq!(result.units[0].content, "注释");
+    }
 }
@@ -0,0 +1,573 @@
+//! HTML and Jinja template parser (.html, .htm, .jinja files)
+//!
+//! Text between block elements becomes a `TextNode` unit, inline elements
+//! (`<a>`, `<b>`, `<code>`, ...) and Jinja expressions included: the
+//! pipeline masks them while translating, so a sentence with a link stays
+//! one unit. The `title`, `alt`, `placeholder` and `aria-label` attributes
+//! are units of their own, and HTML and Jinja comments are `Comment`
+//! units. The contents of `<script>`, `<style>`, `<pre>` and `<textarea>`
+//! and Jinja statements (`{% if %}`) are never extracted.
+//!
+//! Reconstruction replaces each unit's bytes in place, leaving every tag,
+//! attribute and whitespace outside the units as it was.
+
+use anyhow::Result;
+use langlint_core::types::detect_language;
+use langlint_core::{ParseResult, Priority, TranslatableUnit, UnitType};
+use regex::Regex;
+
+use crate::Parser;
+
+/// Unit metadata key holding where a unit's text was found
+pub const HTML_KEY: &str = "html";
+
+/// Attributes whose values are shown to users
+const TEXT_ATTRIBUTES: [&str; 4] = ["title", "alt", "placeholder", "aria-label"];
+
+/// Elements whose contents are not markup or prose
+const RAW_ELEMENTS: [&str; 4] = ["script", "style", "pre", "textarea"];
+
+/// Elements that are part of the text around them
+const INLINE_ELEMENTS: &[&str] = &[
+    "a", "abbr", "b", "bdi", "bdo", "br", "cite", "code", "data", "dfn", "em", "i", "kbd", "mark",
+    "q", "s", "samp", "small", "span", "strong", "sub", "sup", "time", "u", "var", "wbr",
+];
+
+/// Elements whose text is a heading
+const HEADING_ELEMENTS: [&str; 7] = ["title", "h1", "h2", "h3", "h4", "h5", "h6"];
+
+/// Parser for HTML pages and Jinja templates
+pub struct HtmlParser {
+    markup: Regex,
+}
+
+/// A tag read from the source
+struct Tag {
+    /// Lowercase element name
+    name: String,
+    closing: bool,
+    self_closing: bool,
+    /// Offset past the tag
+    end: usize,
+    /// Text attributes: name, value range and quote
+    attributes: Vec<(String, usize, usize, char)>,
+}
+
+/// Units found so far, with the block element their text is in
+struct Scanner<'a> {
+    content: &'a str,
+    lines: Vec<usize>,
+    units: Vec<TranslatableUnit>,
+    block: String,
+}
+
+impl HtmlParser {
+    /// Create a new HTML parser
+    pub fn new() -> Self {
+        Self {
+            markup: Regex::new(r"<[^>]*>|\{\{[^{}]*\}\}|&#?[A-Za-z0-9]+;").unwrap(),
+        }
+    }
+
+    /// Check if a text has a word in it once tags, Jinja expressions and
+    /// entities are left out
+    fn is_translatable(&self, text: &str) -> bool {
+        self.markup
+            .replace_all(text, " ")
+            .split(|c: char| !c.is_alphabetic())
+            .any(|word| word.chars().count() >= 2)
+    }
+
+    /// Text with markup left out, for language detection
+    fn prose(&self, text: &str) -> String {
+        self.markup.replace_all(text, " ").into_owned()
+    }
+
+    fn scan(&self, content: &str) -> Vec<TranslatableUnit> {
+        let mut scanner = Scanner {
+            content,
+            lines: std::iter::once(0)
+                .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+                .collect(),
+            units: Vec::new(),
+            block: String::new(),
+        };
+        let bytes = content.as_bytes();
+        // Start of the text run being read
+        let mut run: Option<usize> = None;
+        let mut i = 0;
+
+        while i < bytes.len() {
+            let rest = &content[i..];
+            if let Some((open, close)) = [("<!--", "-->"), ("{#", "#}")]
+                .into_iter()
+                .find(|(open, _)| rest.starts_with(open))
+            {
+                self.flush(&mut scanner, run.take(), i);
+                let inner_end = rest[open.len()..]
+                    .find(close)
+                    .map_or(content.len(), |p| i + open.len() + p);
+                // Conditional comments (`<!--[if IE]>`) hold markup
+                if !content[i + open.len()..inner_end]
+                    .trim_start()
+                    .starts_with('[')
+                {
+                    self.comment(&mut scanner, i + open.len(), inner_end);
+                }
+                i = (inner_end + close.len()).min(content.len());
+                continue;
+            }
+            if rest.starts_with("{%") {
+                self.flush(&mut scanner, run.take(), i);
+                i = rest.find("%}").map_or(content.len(), |p| i + p + 2);
+                continue;
+            }
+            if rest.starts_with("{{") {
+                run.get_or_insert(i);
+                i = rest.find("}}").map_or(content.len(), |p| i + p + 2);
+                continue;
+            }
+            if rest.starts_with("<!") || rest.starts_with("<?") {
+                self.flush(&mut scanner, run.take(), i);
+                i = rest.find('>').map_or(content.len(), |p| i + p + 1);
+                continue;
+            }
+            if let Some(tag) = read_tag(content, i) {
+                let inline =
+                    INLINE_ELEMENTS.contains(&tag.name.as_str()) && tag.attributes.is_empty();
+                if inline {
+                    run.get_or_insert(i);
+                    i = tag.end;
+                    continue;
+                }
+
+                self.flush(&mut scanner, run.take(), i);
+                for (name, start, end, quote) in &tag.attributes {
+                    self.attribute(&mut scanner, name, *start, *end, *quote);
+                }
+                i = tag.end;
+                if tag.closing {
+                    scanner.block.clear();
+                    continue;
+                }
+                if !INLINE_ELEMENTS.contains(&tag.name.as_str()) {
+                    scanner.block = tag.name.clone();
+                }
+                if !tag.self_closing && RAW_ELEMENTS.contains(&tag.name.as_str()) {
+                    // Up to the closing tag, which is read as usual
+                    let closing = format!("</{}", tag.name);
+                    i = content[i..]
+                        .to_ascii_lowercase()
+                        .find(&closing)
+                        .map_or(content.len(), |p| i + p);
+                }
+                continue;
+            }
+
+            if run.is_none() && !bytes[i].is_ascii_whitespace() {
+                run = Some(i);
+            }
+            i += rest.chars().next().map_or(1, char::len_utf8);
+        }
+        self.flush(&mut scanner, run, content.len());
+
+        scanner.units
+    }
+
+    /// Make a unit of the text run `start..end`
+    fn flush(&self, scanner: &mut Scanner, start: Option<usize>, end: usize) {
+        let Some(start) = start else {
+            return;
+        };
+        let raw = &scanner.content[start..end];
+        let trimmed = raw.trim_end();
+        if !self.is_translatable(trimmed) {
+            return;
+        }
+        let priority = if HEADING_ELEMENTS.contains(&scanner.block.as_str()) {
+            Priority::High
+        } else {
+            Priority::Medium
+        };
+        let context = format!("HTML text in <{}>", scanner.block);
+        let unit = self.unit(
+            scanner,
+            start,
+            start + trimmed.len(),
+            UnitType::TextNode,
+            priority,
+            context,
+            None,
+        );
+        scanner.units.push(unit);
+    }
+
+    fn comment(&self, scanner: &mut Scanner, start: usize, end: usize) {
+        let raw = &scanner.content[start..end];
+        let start = start + raw.len() - raw.trim_start().len();
+        let end = start + raw.trim().len();
+        if start >= end || !self.is_translatable(&scanner.content[start..end]) {
+            return;
+        }
+        let unit = self.unit(
+            scanner,
+            start,
+            end,
+            UnitType::Comment,
+            Priority::Low,
+            "HTML comment".to_string(),
+            None,
+        );
+        scanner.units.push(unit);
+    }
+
+    fn attribute(&self, scanner: &mut Scanner, name: &str, start: usize, end: usize, quote: char) {
+        if !self.is_translatable(&scanner.content[start..end]) {
+            return;
+        }
+        let unit = self.unit(
+            scanner,
+            start,
+            end,
+            UnitType::TextNode,
+            Priority::Medium,
+            format!("HTML {} attribute", name),
+            Some(quote),
+        );
+        scanner.units.push(unit);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn unit(
+        &self,
+        scanner: &Scanner,
+        start: usize,
+        end: usize,
+        unit_type: UnitType,
+        priority: Priority,
+        context: String,
+        quote: Option<char>,
+    ) -> TranslatableUnit {
+        let source = &scanner.content[start..end];
+        let line = scanner.lines.partition_point(|&s| s <= start);
+        let column = start - scanner.lines[line - 1] + 1;
+        let mut unit =
+            TranslatableUnit::new(normalize(source), unit_type, line as u32, column as u32)
+                .with_context(context)
+                .with_priority(priority)
+                .with_metadata(serde_json::json!({
+                    HTML_KEY: {
+                        "start": start,
+                        "end": end,
+                        "source": source,
+                        "quote": quote.map(String::from),
+                    }
+                }));
+        unit.detected_language = detect_language(&self.prose(&unit.content));
+        unit
+    }
+}
+
+impl Default for HtmlParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Text with runs of whitespace (line breaks of wrapped text) as one space
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Read the tag opening at `start`, if an element tag starts there
+fn read_tag(content: &str, start: usize) -> Option<Tag> {
+    let after = content.get(start + 1..)?;
+    let (closing, name_at) = match after.strip_prefix('/') {
+        Some(_) => (true, start + 2),
+        None => (false, start + 1),
+    };
+    let name_len = content[name_at..]
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == ':'))
+        .unwrap_or(content.len() - name_at);
+    if name_len == 0 || !content.as_bytes()[name_at].is_ascii_alphabetic() {
+        return None;
+    }
+    let name = content[name_at..name_at + name_len].to_ascii_lowercase();
+
+    let bytes = content.as_bytes();
+    let mut attributes = Vec::new();
+    let mut i = name_at + name_len;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'>' => {
+                let self_closing = content[..i].ends_with('/');
+                return Some(Tag {
+                    name,
+                    closing,
+                    self_closing,
+                    end: i + 1,
+                    attributes,
+                });
+            }
+            b'{' if content[i..].starts_with("{{") || content[i..].starts_with("{%") => {
+                let close = if content[i..].starts_with("{{") {
+                    "}}"
+                } else {
+                    "%}"
+                };
+                i = content[i..]
+                    .find(close)
+                    .map_or(content.len(), |p| i + p + 2);
+            }
+            c if c.is_ascii_alphabetic() => {
+                let name_end = content[i..]
+                    .find(|c: char| c.is_whitespace() || matches!(c, '=' | '>' | '/'))
+                    .map_or(content.len(), |p| i + p);
+                let attribute = content[i..name_end].to_ascii_lowercase();
+                i = name_end;
+                let value_at = i + content[i..].len() - content[i..].trim_start().len();
+                if !content[value_at..].starts_with('=') {
+                    continue;
+                }
+                i = value_at + 1;
+                i += content[i..].len() - content[i..].trim_start().len();
+                match bytes.get(i) {
+                    Some(&quote @ (b'"' | b'\'')) => {
+                        let len = content[i + 1..].find(quote as char)?;
+                        if TEXT_ATTRIBUTES.contains(&attribute.as_str()) {
+                            attributes.push((attribute, i + 1, i + 1 + len, quote as char));
+                        }
+                        i += len + 2;
+                    }
+                    _ => {
+                        i = content[i..]
+                            .find(|c: char| c.is_whitespace() || c == '>')
+                            .map_or(content.len(), |p| i + p);
+                    }
+                }
+            }
+            _ => i += content[i..].chars().next().map_or(1, char::len_utf8),
+        }
+    }
+    None
+}
+
+impl Parser for HtmlParser {
+    fn name(&self) -> &'static str {
+        "HTML"
+    }
+
+    fn supported_extensions(&self) -> &'static [&'static str] {
+        &[".html", ".htm", ".jinja", ".jinja2"]
+    }
+
+    fn can_parse(&self, path: &str, _content: Option<&str>) -> bool {
+        let lower = path.to_lowercase();
+        self.supported_extensions()
+            .iter()
+            .any(|ext| lower.ends_with(ext))
+    }
+
+    fn extract_units(&self, content: &str, _path: &str) -> Result<ParseResult> {
+        let units = self.scan(content);
+        Ok(ParseResult::new("html", "utf-8", content.lines().count() as u32).with_units(units))
+    }
+
+    fn reconstruct(
+        &self,
+        original: &str,
+        units: &[TranslatableUnit],
+        _path: &str,
+    ) -> Result<String> {
+        let mut edits: Vec<(usize, usize, String)> = units
+            .iter()
+            .filter_map(|unit| {
+                let html = unit.metadata.as_ref()?.get(HTML_KEY)?;
+                let start = html.get("start")?.as_u64()? as usize;
+                let end = html.get("end")?.as_u64()? as usize;
+                let source = html.get("source")?.as_str()?;
+                // Units not found where they were, or unchanged, stay as they are
+                if original.get(start..end) != Some(source) || unit.content == normalize(source) {
+                    return None;
+                }
+                let text = match html.get("quote").and_then(|q| q.as_str()) {
+                    Some("'") => unit.content.replace('\'', "&#39;"),
+                    Some(_) => unit.content.replace('"', "&quot;"),
+                    None if unit.unit_type == UnitType::Comment => {
+                        unit.content.replace("--", "- -")
+                    }
+                    None => unit.content.clone(),
+                };
+                Some((start, end, text))
+            })
+            .collect();
+        edits.sort_by_key(|(start, _, _)| std::cmp::Reverse(*start));
+
+        let mut result = original.to_string();
+        let mut limit = original.len();
+        for (start, end, text) in edits {
+            // Overlapping edits cannot both be applied
+            if end > limit {
+                continue;
+            }
+            result.replace_range(start..end, &text);
+            limit = start;
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+  <title>用户中心</title>
+  <style>.note { content: "样式"; }</style>
+</head>
+<body>
+  <!-- 页面主体 -->
+  <h1>欢迎, {{ user.name }}</h1>
+  <p>请先阅读
+     <a href="/terms">服务条款</a>，然后继续。</p>
+  <img src="logo.png" alt="公司标志">
+  <input type="text" placeholder='请输入邮箱'>
+  {% if user.admin %}<span>管理员</span>{% endif %}
+  {# 模板注释 #}
+  <script>alert("脚本");</script>
+</body>
+</html>
+"#;
+
+    #[test]
+    fn test_extract_units() {
+        let parser = HtmlParser::new();
+        assert!(parser.can_parse("templates/base.jinja", None));
+        assert!(parser.can_parse("index.HTML", None));
+        assert!(!parser.can_parse("index.js", None));
+
+        let result = parser.extract_units(PAGE, "index.html").unwrap();
+        let units: Vec<(u32, &str, &UnitType, Priority)> = result
+            .units
+            .iter()
+            .map(|u| (u.line_number, u.content.as_str(), &u.unit_type, u.priority))
+            .collect();
+        assert_eq!(
+            units,
+            vec![
+                (4, "用户中心", &UnitType::TextNode, Priority::High),
+                (8, "页面主体", &UnitType::Comment, Priority::Low),
+                (
+                    9,
+                    "欢迎, {{ user.name }}",
+                    &UnitType::TextNode,
+                    Priority::High
+                ),
+                (
+                    10,
+                    "请先阅读 <a href=\"/terms\">服务条款</a>，然后继续。",
+                    &UnitType::TextNode,
+                    Priority::Medium
+                ),
+                (12, "公司标志", &UnitType::TextNode, Priority::Medium),
+                (13, "请输入邮箱", &UnitType::TextNode, Priority::Medium),
+                (
+                    14,
+                    "<span>管理员</span>",
+                    &UnitType::TextNode,
+                    Priority::Medium
+                ),
+                (15, "模板注释", &UnitType::Comment, Priority::Low),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reconstruct() {
+        let parser = HtmlParser::new();
+        let mut units = parser.extract_units(PAGE, "index.html").unwrap().units;
+        assert_eq!(
+            parser.reconstruct(PAGE, &units, "index.html").unwrap(),
+            PAGE
+        );
+
+        units[0].content = "User Center".to_string();
+        units[3].content =
+            "Please read the <a href=\"/terms\">terms of service</a> first.".to_string();
+        units[5].content = "Your \"email\"".to_string();
+        units[7].content = "Template note".to_string();
+        let rebuilt = parser.reconstruct(PAGE, &units, "index.html").unwrap();
+        assert!(rebuilt.contains("<title>User Center</title>"));
+        assert!(rebuilt
+            .contains("<p>Please read the <a href=\"/terms\">terms of service</a> first.</p>"));
+        assert!(rebuilt.contains("placeholder='Your \"email\"'"));
+        assert!(rebuilt.contains("{# Template note #}"));
+        assert!(rebuilt.contains("<style>.note { content: \"样式\"; }</style>"));
+        assert!(rebuilt.contains("<script>alert(\"脚本\");</script>"));
+    }
+
+    #[test]
+    fn test_round_trip_jinja_blocks() {
+        let template = r#"{% extends "base.html" %}
+{% block content %}
+  <h2>{% trans %}订单列表{% endtrans %}</h2>
+  {% for order in orders %}
+    <p>订单 {{ order.id }} 已于 {{ order.date|date("Y-m-d") }} 发货。</p>
+  {% else %}
+    <p>暂无订单。</p>
+  {% endfor %}
+  {% raw %}<p>{{ 不是变量 }}</p>{% endraw %}
+  {%- if user.vip -%}
+    <div title="{{ _('会员') }}">尊享会员</div>
+  {%- endif %}
+{% endblock %}
+"#;
+        let parser = HtmlParser::new();
+        let mut units = parser
+            .extract_units(template, "orders.jinja")
+            .unwrap()
+            .units;
+        let contents: Vec<(u32, &str)> = units
+            .iter()
+            .map(|u| (u.line_number, u.content.as_str()))
+            .collect();
+        assert_eq!(
+            contents,
+            vec![
+                (3, "订单列表"),
+                (
+                    5,
+                    "订单 {{ order.id }} 已于 {{ order.date|date(\"Y-m-d\") }} 发货。"
+                ),
+                (7, "暂无订单。"),
+                (11, "尊享会员"),
+            ]
+        );
+
+        let translations = [
+            "Orders",
+            "Order {{ order.id }} shipped on {{ order.date|date(\"Y-m-d\") }}.",
+            "No orders yet.",
+            "VIP member",
+        ];
+        for (unit, translation) in units.iter_mut().zip(translations) {
+            unit.content = translation.to_string();
+        }
+        let rebuilt = parser
+            .reconstruct(template, &units, "orders.jinja")
+            .unwrap();
+        assert_eq!(
+            rebuilt,
+            template
+                .replace("订单列表", translations[0])
+                .replace(
+                    "订单 {{ order.id }} 已于 {{ order.date|date(\"Y-m-d\") }} 发货。",
+                    translations[1]
+                )
+                .replace("暂无订单。", translations[2])
+                .replace("尊享会员", translations[3])
+        );
+    }
+}
@@ -0,0 +1,490 @@
+//! i18n key extraction: replace user-facing string literals with lookup calls
+
+use langlint_core::Masked;
+use std::collections::BTreeMap;
+
+/// Placeholder for the message key inside a call template
+pub const KEY_PLACEHOLDER: &str = "{key}";
+
+/// Maximum number of words used to build a key from text
+const MAX_KEY_WORDS: usize = 6;
+
+/// Source language family, which decides comment and string syntax
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceKind {
+    /// Python: `#` comments, triple-quoted strings, string prefixes
+    Python,
+    /// JavaScript/TypeScript: `//` and `/* */` comments, template literals
+    Script,
+}
+
+impl SourceKind {
+    /// Detect the source kind from a file path
+    pub fn from_path(path: &str) -> Option<Self> {
+        let ext = path.rsplit('.').next()?.to_lowercase();
+        match ext.as_str() {
+            "py" | "pyw" => Some(Self::Python),
+            "js" | "jsx" | "ts" | "tsx" | "mjs" | "cjs" => Some(Self::Script),
+            _ => None,
+        }
+    }
+
+    /// Default lookup call template for this kind
+    pub fn default_template(&self) -> &'static str {
+        match self {
+            Self::Python => "_(\"{key}\")",
+            Self::Script => "t('{key}')",
+        }
+    }
+}
+
+/// A single-line string literal found in source code
+#[derive(Debug, Clone, PartialEq)]
+pub struct StringLiteral {
+    /// Byte offset of the opening quote
+    pub start: usize,
+    /// Byte offset just past the closing quote
+    pub end: usize,
+    /// 1-based line number
+    pub line: usize,
+    /// Unescaped literal value
+    pub value: String,
+}
+
+/// Find plain single-line string literals
+///
+/// Comments, docstrings/triple-quoted strings, template literals and
+/// prefixed Python strings (f-strings, bytes, raw) are skipped.
+pub fn find_string_literals(content: &str, kind: SourceKind) -> Vec<StringLiteral> {
+    scan_literals(content, kind, false)
+}
+
+/// Find the single-line string and template literals of a script
+///
+/// Like [`find_string_literals`], plus template literals that fit on one
+/// line. Their value is the raw body, `${}` expressions and escapes
+/// included.
+pub fn find_script_strings(content: &str) -> Vec<StringLiteral> {
+    scan_literals(content, SourceKind::Script, true)
+}
+
+fn scan_literals(content: &str, kind: SourceKind, templates: bool) -> Vec<StringLiteral> {
+    let bytes = content.as_bytes();
+    let mut literals = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let rest = &content[i..];
+        let c = bytes[i];
+
+        if c == b'\n' {
+            line += 1;
+            i += 1;
+        } else if (kind == SourceKind::Python && c == b'#')
+            || (kind == SourceKind::Script && rest.starts_with("//"))
+        {
+            i += rest.find('\n').unwrap_or(rest.len());
+        } else if kind == SourceKind::Script && rest.starts_with("/*") {
+            let len = rest.find("*/").map(|p| p + 2).unwrap_or(rest.len());
+            line += rest[..len].matches('\n').count();
+            i += len;
+        } else if kind == SourceKind::Python
+            && (rest.starts_with("\"\"\"") || rest.starts_with("'''"))
+        {
+            let len = rest[3..]
+                .find(&rest[..3])
+                .map(|p| p + 6)
+                .unwrap_or(rest.len());
+            line += rest[..len].matches('\n').count();
+            i += len;
+        } else if kind == SourceKind::Script && c == b'`' {
+            let closing = closing_quote(rest, b'`', true);
+            let len = closing.unwrap_or(rest.len());
+            let newlines = rest[..len].matches('\n').count();
+            if templates && closing.is_some() && newlines == 0 {
+                literals.push(StringLiteral {
+                    start: i,
+                    end: i + len,
+                    line,
+                    value: rest[1..len - 1].to_string(),
+                });
+            }
+            line += newlines;
+            i += len;
+        } else if c == b'"' || c == b'\'' {
+            match closing_quote(rest, c, false) {
+                Some(len) => {
+                    if !ends_with_identifier_char(&content[..i]) {
+                        literals.push(StringLiteral {
+                            start: i,
+                            end: i + len,
+                            line,
+                            value: unescape(&rest[1..len - 1]),
+                        });
+                    }
+                    i += len;
+                }
+                // Unterminated on this line: not a literal we can rewrite
+                None => i += 1,
+            }
+        } else if c.is_ascii_alphanumeric() || c == b'_' {
+            // Skip whole identifiers so a quote right after one is seen as
+            // a prefixed string
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                i += 1;
+            }
+        } else {
+            i += rest.chars().next().map(char::len_utf8).unwrap_or(1);
+        }
+    }
+
+    literals
+}
+
+/// Length of a quoted literal up to and including its closing quote
+fn closing_quote(text: &str, quote: u8, multi_line: bool) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut i = 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'\n' if !multi_line => return None,
+            b if b == quote => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Check if text ends in an identifier character
+///
+/// Before a quote this means a string prefix such as `f` or `rb`.
+fn ends_with_identifier_char(before: &str) -> bool {
+    before
+        .chars()
+        .next_back()
+        .is_some_and(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Resolve the common backslash escapes of a literal body
+fn unescape(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Check if a literal looks like text shown to users
+///
+/// Requires a word outside its placeholders plus either whitespace or
+/// non-ASCII characters, so identifiers, keys, paths, URLs and bare format
+/// strings such as `{}: {}` are left alone, as are SQL statements and
+/// regular expressions.
+pub fn is_user_facing(value: &str) -> bool {
+    let text = value.trim();
+    // Needs a real word, not just format codes like `%s` or `{name}`
+    let masked = Masked::new(text);
+    let words = masked
+        .placeholders
+        .iter()
+        .fold(text.to_string(), |words, placeholder| {
+            words.replace(placeholder.as_str(), " ")
+        });
+    let has_word = words
+        .split(|c: char| !c.is_alphabetic())
+        .any(|word| word.chars().count() >= 2);
+    if !has_word {
+        return false;
+    }
+    if text.contains("://") || text.starts_with('/') || text.contains('\\') {
+        return false;
+    }
+    if looks_like_sql(text) || looks_like_regex(text) {
+        return false;
+    }
+    text.contains(char::is_whitespace) || !text.is_ascii()
+}
+
+/// Check if text reads as a SQL statement: a statement keyword in capitals,
+/// or followed by a capitalized clause keyword (`select id FROM users`)
+fn looks_like_sql(text: &str) -> bool {
+    const STATEMENTS: &[&str] = &[
+        "SELECT", "INSERT", "UPDATE", "DELETE", "CREATE", "ALTER", "DROP", "WITH", "PRAGMA",
+    ];
+    const CLAUSES: &[&str] = &["FROM", "INTO", "SET", "TABLE", "WHERE", "VALUES", "JOIN"];
+    let mut words = text.split_whitespace();
+    let Some(first) = words.next() else {
+        return false;
+    };
+    STATEMENTS.contains(&first.to_uppercase().as_str())
+        && (STATEMENTS.contains(&first) || words.any(|word| CLAUSES.contains(&word)))
+}
+
+/// Check if text reads as a regular expression
+fn looks_like_regex(text: &str) -> bool {
+    (text.starts_with('^') || text.ends_with('$'))
+        || ["(?", "[^", ".*", ".+", "[a-z", "[A-Z", "[0-9"]
+            .iter()
+            .any(|syntax| text.contains(syntax))
+}
+
+/// Build a stable message key from text
+///
+/// ASCII words give a readable snake_case key; text without any ASCII
+/// words (e.g. Chinese) falls back to a hash-based `msg_xxxxxxxx` key.
+pub fn make_key(text: &str) -> String {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .take(MAX_KEY_WORDS)
+        .map(str::to_lowercase)
+        .collect();
+
+    if words.is_empty() {
+        format!("msg_{:08x}", fnv1a(text))
+    } else {
+        words.join("_")
+    }
+}
+
+/// 32-bit FNV-1a hash (stable across builds, unlike `DefaultHasher`)
+fn fnv1a(text: &str) -> u32 {
+    text.bytes().fold(0x811c_9dc5, |hash, b| {
+        (hash ^ b as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+/// Result of rewriting one file
+#[derive(Debug, Clone, Default)]
+pub struct Extraction {
+    /// Rewritten source
+    pub content: String,
+    /// Number of literals replaced
+    pub replaced: usize,
+}
+
+/// Replaces user-facing literals with lookup calls and collects messages
+#[derive(Default)]
+pub struct I18nExtractor {
+    template: Option<String>,
+    messages: BTreeMap<String, String>,
+}
+
+impl I18nExtractor {
+    /// Create an extractor using each language's default call template
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use a custom call template containing `{key}`, e.g. `gettext("{key}")`
+    pub fn with_template(mut self, template: &str) -> Self {
+        self.template = Some(template.to_string());
+        self
+    }
+
+    /// Start from an existing key → message resource so keys stay stable
+    pub fn with_messages(mut self, messages: BTreeMap<String, String>) -> Self {
+        self.messages = messages;
+        self
+    }
+
+    /// Collected key → source message pairs
+    pub fn messages(&self) -> &BTreeMap<String, String> {
+        &self.messages
+    }
+
+    /// Rewrite a file, replacing user-facing literals with lookup calls
+    ///
+    /// Literals already passed to the lookup function are left alone.
+    pub fn extract(&mut self, content: &str, kind: SourceKind) -> Extraction {
+        let template = self
+            .template
+            .clone()
+            .unwrap_or_else(|| kind.default_template().to_string());
+        let call_prefix = template
+            .split(KEY_PLACEHOLDER)
+            .next()
+            .unwrap_or_default()
+            .trim_end_matches(['"', '\''])
+            .to_string();
+
+        let mut result = String::with_capacity(content.len());
+        let mut last = 0;
+        let mut replaced = 0;
+
+        for literal in find_string_literals(content, kind) {
+            if !is_user_facing(&literal.value)
+                || is_lookup_argument(&content[..literal.start], &call_prefix)
+            {
+                continue;
+            }
+
+            let key = self.key_for(&literal.value);
+            result.push_str(&content[last..literal.start]);
+            result.push_str(&template.replace(KEY_PLACEHOLDER, &key));
+            last = literal.end;
+            replaced += 1;
+        }
+        result.push_str(&content[last..]);
+
+        Extraction {
+            content: result,
+            replaced,
+        }
+    }
+
+    /// Key for a message, reusing an existing key for the same text
+    fn key_for(&mut self, message: &str) -> String {
+        if let Some((key, _)) = self.messages.iter().find(|(_, m)| *m == message) {
+            return key.clone();
+        }
+
+        let base = make_key(message);
+        let mut key = base.clone();
+        let mut n = 2;
+        while self.messages.contains_key(&key) {
+            key = format!("{}_{}", base, n);
+            n += 1;
+        }
+        self.messages.insert(key.clone(), message.to_string());
+        key
+    }
+}
+
+/// Check if the code before a literal ends in a call to the lookup function
+fn is_lookup_argument(before: &str, call_prefix: &str) -> bool {
+    let before = before.trim_end();
+    if call_prefix.is_empty() || !before.ends_with(call_prefix) {
+        return false;
+    }
+    !ends_with_identifier_char(&before[..before.len() - call_prefix.len()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_python_literals() {
+        let code = r#"# "not a literal"
+def f():
+    """Docstring "quoted" """
+    name = "user_name"
+    print("Hello world", f"Hi {name}", 'It\'s ok')
+"#;
+        let values: Vec<(usize, String)> = find_string_literals(code, SourceKind::Python)
+            .into_iter()
+            .map(|l| (l.line, l.value))
+            .collect();
+
+        assert_eq!(
+            values,
+            vec![
+                (4, "user_name".to_string()),
+                (5, "Hello world".to_string()),
+                (5, "It's ok".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_script_literals() {
+        let code = "// 'comment'\n/* \"block\" */\nconst a = `tpl ${x}`;\nalert('保存成功');\n";
+        let values: Vec<String> = find_string_literals(code, SourceKind::Script)
+            .into_iter()
+            .map(|l| l.value)
+            .collect();
+
+        assert_eq!(values, vec!["保存成功".to_string()]);
+
+        let values: Vec<String> = find_script_strings(code)
+            .into_iter()
+            .map(|l| l.value)
+            .collect();
+        assert_eq!(values, vec!["tpl ${x}".to_string(), "保存成功".to_string()]);
+    }
+
+    #[test]
+    fn test_is_user_facing() {
+        assert!(is_user_facing("Hello world"));
+        assert!(is_user_facing("保存成功"));
+        assert!(!is_user_facing("user_name"));
+        assert!(!is_user_facing("https://example.com/a b"));
+        assert!(!is_user_facing("%s %d"));
+        assert!(!is_user_facing("/usr/local/bin"));
+        assert!(!is_user_facing("{name}: {value}"));
+        assert!(is_user_facing("Hello {name}"));
+        assert!(!is_user_facing("SELECT name FROM users WHERE id = ?"));
+        assert!(!is_user_facing("delete from jobs WHERE done = 1"));
+        assert!(is_user_facing("Delete from the list"));
+        assert!(!is_user_facing("^[a-z]+ 个$"));
+    }
+
+    #[test]
+    fn test_make_key() {
+        assert_eq!(make_key("Hello, world!"), "hello_world");
+        assert_eq!(
+            make_key("one two three four five six seven"),
+            "one_two_three_four_five_six"
+        );
+        assert_eq!(make_key("保存成功"), make_key("保存成功"));
+        assert!(make_key("保存成功").starts_with("msg_"));
+        assert_ne!(make_key("保存成功"), make_key("保存失败"));
+    }
+
+    #[test]
+    fn test_extract_python() {
+        let mut extractor = I18nExtractor::new();
+        let code = "print(\"Hello world\")\nlog(\"debug_key\")\nprint(_(\"Already done\"))\nmsg = 'Hello world'\n";
+
+        let extraction = extractor.extract(code, SourceKind::Python);
+
+        assert_eq!(extraction.replaced, 2);
+        assert_eq!(
+            extraction.content,
+            "print(_(\"hello_world\"))\nlog(\"debug_key\")\nprint(_(\"Already done\"))\nmsg = _(\"hello_world\")\n"
+        );
+        assert_eq!(extractor.messages().len(), 1);
+        assert_eq!(extractor.messages()["hello_world"], "Hello world");
+    }
+
+    #[test]
+    fn test_extract_key_collisions() {
+        let mut extractor = I18nExtractor::new();
+
+        let extraction = extractor.extract(
+            "a('Hello world'); b('Hello, world!'); c('Hello world')",
+            SourceKind::Script,
+        );
+
+        assert_eq!(
+            extraction.content,
+            "a(t('hello_world')); b(t('hello_world_2')); c(t('hello_world'))"
+        );
+    }
+
+    #[test]
+    fn test_extract_reuses_existing_messages() {
+        let mut existing = BTreeMap::new();
+        existing.insert("greeting".to_string(), "Hello world".to_string());
+        let mut extractor = I18nExtractor::new()
+            .with_template("i18n.get(\"{key}\")")
+            .with_messages(existing);
+
+        let extraction = extractor.extract("alert('Hello world')", SourceKind::Script);
+
+        assert_eq!(extraction.content, "alert(i18n.get(\"greeting\"))");
+    }
+}
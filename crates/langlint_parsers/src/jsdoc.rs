@@ -0,0 +1,454 @@
+//! JSDoc/TSDoc comments of JavaScript and TypeScript, and Javadoc
+//!
+//! [`JsDocParser`] and [`JavadocParser`] read each `/** */` block as a
+//! description followed by block tags. The description and the free text of
+//! tags such as `@param`, `@returns` and `@throws` are translated a
+//! paragraph at a time, while tag names, `{type}` expressions and parameter
+//! names stay as written; the bodies of `@example` and other code tags,
+//! fenced code and `<pre>` blocks are left alone. A `<p>` starts a new
+//! paragraph.
+
+use anyhow::Result;
+use langlint_core::{ParseResult, TranslatableUnit};
+
+use crate::doc_comment::{self, DocComments, Segment};
+use crate::generic::{tree_comments, TreeComment};
+use crate::{GenericCodeParser, Parser};
+
+/// Block tags of a doc comment dialect
+struct Dialect {
+    label: &'static str,
+    /// Tags followed by an optional type, a name and a description
+    named: &'static [&'static str],
+    /// Tags followed by an optional type and a description
+    described: &'static [&'static str],
+}
+
+const JSDOC: Dialect = Dialect {
+    label: "JSDoc",
+    named: &[
+        "param",
+        "arg",
+        "argument",
+        "property",
+        "prop",
+        "typeParam",
+        "template",
+    ],
+    described: &[
+        "returns",
+        "return",
+        "throws",
+        "exception",
+        "yields",
+        "yield",
+        "deprecated",
+        "description",
+        "desc",
+        "summary",
+        "remarks",
+        "classdesc",
+        "todo",
+    ],
+};
+
+/// Javadoc names the exception type of `@throws` without braces
+const JAVADOC: Dialect = Dialect {
+    label: "Javadoc",
+    named: &["param", "throws", "exception"],
+    described: &["return", "deprecated", "apiNote", "implSpec", "implNote"],
+};
+
+impl Dialect {
+    /// Doc comments of a file, `None` without a syntax tree
+    fn doc_comments(
+        &self,
+        generic: &GenericCodeParser,
+        lines: &[&str],
+        content: &str,
+        path: &str,
+    ) -> Option<DocComments> {
+        let comments = tree_comments(content, &generic.extension(path))?;
+        let comments: Vec<&TreeComment> = comments
+            .iter()
+            .filter(|comment| is_doc_block(lines, comment))
+            .collect();
+        Some(DocComments {
+            blocks: comments
+                .iter()
+                .map(|comment| {
+                    let last = comment.lines.last().map_or(comment.line, |(line, _)| *line);
+                    comment.line..=last.max(comment.line)
+                })
+                .collect(),
+            segments: comments
+                .iter()
+                .flat_map(|comment| self.segments(lines, comment))
+                .collect(),
+        })
+    }
+
+    /// Context of a segment's unit
+    fn describe(&self, tag: &str) -> String {
+        match tag {
+            "description" => format!("{} description", self.label),
+            tag => format!("{} @{}", self.label, tag),
+        }
+    }
+
+    /// Split a doc block into its description paragraphs and tag texts
+    fn segments(&self, lines: &[&str], comment: &TreeComment) -> Vec<Segment> {
+        let mut segments = Vec::new();
+        let mut current: Option<Segment> = None;
+        let mut tag = "description".to_string();
+        // In the body of a code tag such as `@example`, a fenced code block
+        // or a `<pre>` block
+        let mut code = false;
+        let mut fence = false;
+        let mut pre = false;
+        let mut previous: Option<usize> = None;
+
+        for (line, range) in &comment.lines {
+            let text = &lines[*line][range.clone()];
+            let gap = previous.is_some_and(|previous| *line > previous + 1);
+            previous = Some(*line);
+
+            if let Some(after_at) = text.strip_prefix('@') {
+                segments.extend(current.take());
+                fence = false;
+                pre = false;
+                let name_len = after_at
+                    .find(|c: char| c.is_whitespace() || c == '{')
+                    .unwrap_or(after_at.len());
+                let (name, rest) = after_at.split_at(name_len);
+                match self.tag_text_offset(name, rest) {
+                    Some(offset) => {
+                        code = false;
+                        tag = name.to_string();
+                        let start = range.start + 1 + name_len + offset;
+                        let lines = if start < range.end {
+                            vec![(*line, start..range.end)]
+                        } else {
+                            Vec::new()
+                        };
+                        current = Some(Segment {
+                            tag: tag.clone(),
+                            lines,
+                        });
+                    }
+                    None => code = true,
+                }
+                continue;
+            }
+            if code {
+                continue;
+            }
+            if text.starts_with("```") {
+                segments.extend(current.take());
+                fence = !fence;
+                continue;
+            }
+            let lower = text.to_lowercase();
+            if lower.starts_with("<pre>") || lower.starts_with("<pre ") {
+                segments.extend(current.take());
+                pre = !lower.contains("</pre>");
+                continue;
+            }
+            if fence || pre {
+                pre = pre && !lower.contains("</pre>");
+                continue;
+            }
+            let mut range = range.clone();
+            if gap || lower.starts_with("<p>") {
+                segments.extend(current.take());
+            }
+            if lower.starts_with("<p>") {
+                range.start += 3;
+                range.start += text[3..].len() - text[3..].trim_start().len();
+                if range.is_empty() {
+                    continue;
+                }
+            }
+            current
+                .get_or_insert_with(|| Segment {
+                    tag: tag.clone(),
+                    lines: Vec::new(),
+                })
+                .lines
+                .push((*line, range));
+        }
+        segments.extend(current);
+        segments.retain(|segment| !segment.lines.is_empty());
+        segments
+    }
+
+    /// Offset in `rest`, the text after a tag name, where the free text of
+    /// the tag starts; `None` for tags whose text is code or a reference
+    fn tag_text_offset(&self, name: &str, rest: &str) -> Option<usize> {
+        let named = self.named.contains(&name);
+        if !named && !self.described.contains(&name) {
+            return None;
+        }
+
+        let mut offset = skip_whitespace(rest, 0);
+        if rest[offset..].starts_with('{') {
+            offset = skip_bracketed(rest, offset, '{', '}');
+            offset = skip_whitespace(rest, offset);
+        }
+        if named {
+            offset = if rest[offset..].starts_with('[') {
+                skip_bracketed(rest, offset, '[', ']')
+            } else {
+                rest[offset..]
+                    .find(char::is_whitespace)
+                    .map_or(rest.len(), |end| offset + end)
+            };
+            offset = skip_whitespace(rest, offset);
+        }
+        // TSDoc separates the name from the description with a hyphen
+        for separator in ["- ", "— "] {
+            if let Some(after) = rest[offset..].strip_prefix(separator) {
+                offset = skip_whitespace(rest, rest.len() - after.len());
+                break;
+            }
+        }
+        Some(offset)
+    }
+}
+
+/// Check if a comment is a `/** */` block
+fn is_doc_block(lines: &[&str], comment: &TreeComment) -> bool {
+    comment.block
+        && comment.marker_end - comment.column == 3
+        && lines[comment.line][comment.column..].starts_with("/**")
+}
+
+fn skip_whitespace(text: &str, offset: usize) -> usize {
+    text.len() - text[offset..].trim_start().len()
+}
+
+/// Offset past the bracket closing the one at `offset`, or the end of the
+/// text if it is not closed
+fn skip_bracketed(text: &str, offset: usize, open: char, close: char) -> usize {
+    let mut depth = 0;
+    for (i, c) in text[offset..].char_indices() {
+        if c == open {
+            depth += 1;
+        } else if c == close {
+            depth -= 1;
+            if depth == 0 {
+                return offset + i + c.len_utf8();
+            }
+        }
+    }
+    text.len()
+}
+
+/// Parser for JavaScript/TypeScript that understands JSDoc blocks
+pub struct JsDocParser {
+    generic: GenericCodeParser,
+}
+
+impl JsDocParser {
+    pub fn new() -> Self {
+        Self {
+            generic: GenericCodeParser::new(),
+        }
+    }
+}
+
+impl Default for JsDocParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parser for JsDocParser {
+    fn name(&self) -> &'static str {
+        "JsDocParser"
+    }
+
+    fn supported_extensions(&self) -> &'static [&'static str] {
+        &[".js", ".jsx", ".ts", ".tsx"]
+    }
+
+    fn can_parse(&self, path: &str, _content: Option<&str>) -> bool {
+        self.supported_extensions()
+            .iter()
+            .any(|ext| path.ends_with(ext))
+    }
+
+    fn extract_units(&self, content: &str, path: &str) -> Result<ParseResult> {
+        let lines: Vec<&str> = content.lines().collect();
+        let docs = JSDOC.doc_comments(&self.generic, &lines, content, path);
+        doc_comment::extract_units(&self.generic, content, path, self.name(), docs, |tag| {
+            JSDOC.describe(tag)
+        })
+    }
+
+    fn reconstruct(
+        &self,
+        original: &str,
+        units: &[TranslatableUnit],
+        path: &str,
+    ) -> Result<String> {
+        doc_comment::reconstruct(&self.generic, original, units, path, |lines| {
+            JSDOC.doc_comments(&self.generic, lines, original, path)
+        })
+    }
+}
+
+/// Parser for Java that understands Javadoc blocks
+pub struct JavadocParser {
+    generic: GenericCodeParser,
+}
+
+impl JavadocParser {
+    pub fn new() -> Self {
+        Self {
+            generic: GenericCodeParser::new(),
+        }
+    }
+}
+
+impl Default for JavadocParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parser for JavadocParser {
+    fn name(&self) -> &'static str {
+        "JavadocParser"
+    }
+
+    fn supported_extensions(&self) -> &'static [&'static str] {
+        &[".java"]
+    }
+
+    fn can_parse(&self, path: &str, _content: Option<&str>) -> bool {
+        path.ends_with(".java")
+    }
+
+    fn extract_units(&self, content: &str, path: &str) -> Result<ParseResult> {
+        let lines: Vec<&str> = content.lines().collect();
+        let docs = JAVADOC.doc_comments(&self.generic, &lines, content, path);
+        doc_comment::extract_units(&self.generic, content, path, self.name(), docs, |tag| {
+            JAVADOC.describe(tag)
+        })
+    }
+
+    fn reconstruct(
+        &self,
+        original: &str,
+        units: &[TranslatableUnit],
+        path: &str,
+    ) -> Result<String> {
+        doc_comment::reconstruct(&self.generic, original, units, path, |lines| {
+            JAVADOC.doc_comments(&self.generic, lines, original, path)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::doc_comment::DOC_TAG_KEY;
+    use langlint_core::UnitType;
+
+    const SOURCE: &str = "/**\n * 计算两个数的和\n * 结果不会溢出\n *\n * @param {number} a - 第一个数\n * @param {{x: number}} [b=0] 第二个数\n *   可以省略\n * @returns {number} 两数之和\n * @example\n * add(1, 2) // 返回三\n */\nfunction add(a, b) {}\n";
+
+    #[test]
+    fn test_extract_jsdoc_segments() {
+        let parser = JsDocParser::new();
+        let result = parser.extract_units(SOURCE, "math.js").unwrap();
+        let units: Vec<(u32, u32, &str, &str)> = result
+            .units
+            .iter()
+            .map(|unit| {
+                let tag = unit.metadata.as_ref().unwrap()[DOC_TAG_KEY]
+                    .as_str()
+                    .unwrap();
+                (
+                    unit.line_number,
+                    unit.column_number,
+                    tag,
+                    unit.content.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            units,
+            vec![
+                (2, 4, "description", "计算两个数的和结果不会溢出"),
+                (5, 24, "param", "第一个数"),
+                (6, 31, "param", "第二个数可以省略"),
+                (8, 22, "returns", "两数之和"),
+            ]
+        );
+        assert!(result
+            .units
+            .iter()
+            .all(|unit| unit.unit_type == UnitType::Docstring));
+    }
+
+    #[test]
+    fn test_reconstruct_keeps_tags() {
+        let parser = JsDocParser::new();
+        let content = format!("{}// 普通注释\n", SOURCE);
+        let mut units = parser.extract_units(&content, "math.ts").unwrap().units;
+        assert_eq!(units.len(), 5);
+        let translations = [
+            "Add two numbers without overflowing",
+            "The first number",
+            "The second number, optional",
+            "The sum",
+            "Plain comment",
+        ];
+        for (unit, translation) in units.iter_mut().zip(translations) {
+            unit.content = translation.to_string();
+        }
+
+        let rebuilt = parser.reconstruct(&content, &units, "math.ts").unwrap();
+        assert_eq!(
+            rebuilt,
+            "/**\n * Add two numbers\n * without overflowing\n *\n * @param {number} a - The first number\n * @param {{x: number}} [b=0] The second\n *   number, optional\n * @returns {number} The sum\n * @example\n * add(1, 2) // 返回三\n */\nfunction add(a, b) {}\n// Plain comment\n"
+        );
+    }
+
+    #[test]
+    fn test_javadoc_round_trip() {
+        let parser = JavadocParser::new();
+        let content = "/**\n * 返回用户名\n * <p>\n * 名称不会为 {@code null}\n * <pre>\n * 示例代码\n * </pre>\n * @param <T> 类型参数\n * @param id 用户编号\n * @return 用户名\n * @throws IOException 读取失败时\n * @see Users\n */\nString name(int id);\n";
+        let mut units = parser.extract_units(content, "User.java").unwrap().units;
+        let texts: Vec<&str> = units.iter().map(|unit| unit.content.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "返回用户名",
+                "名称不会为 {@code null}",
+                "类型参数",
+                "用户编号",
+                "用户名",
+                "读取失败时",
+            ]
+        );
+
+        let translations = [
+            "Returns the user name",
+            "The name is never {@code null}",
+            "Type parameter",
+            "User id",
+            "User name",
+            "When reading fails",
+        ];
+        for (unit, translation) in units.iter_mut().zip(translations) {
+            unit.content = translation.to_string();
+        }
+        let rebuilt = parser.reconstruct(content, &units, "User.java").unwrap();
+        assert_eq!(
+            rebuilt,
+            "/**\n * Returns the user name\n * <p>\n * The name is never {@code null}\n * <pre>\n * 示例代码\n * </pre>\n * @param <T> Type parameter\n * @param id User id\n * @return User name\n * @throws IOException When reading fails\n * @see Users\n */\nString name(int id);\n"
+        );
+    }
+}
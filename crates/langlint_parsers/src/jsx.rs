@@ -0,0 +1,469 @@
+//! Text of JSX elements
+//!
+//! React components hold most of a front end's UI text, as element
+//! children (`<p>欢迎使用</p>`) and in the `title`, `aria-label` and
+//! `placeholder` attributes. [`extract_jsx_text`] finds both as
+//! [`UnitType::TextNode`] units. A text run wrapped over several lines
+//! becomes one unit, split back over the same lines like a comment
+//! paragraph. Like the strings of [`crate::sinks`], these units are
+//! extracted always and dropped unless asked for.
+
+use langlint_core::{Priority, TranslatableUnit, UnitType};
+use regex::Regex;
+use std::sync::OnceLock;
+
+use crate::paragraph;
+
+/// Unit metadata key holding where a unit's text was found
+pub const JSX_KEY: &str = "jsx";
+
+/// Attributes whose values are shown to users
+const TEXT_ATTRIBUTES: [&str; 3] = ["title", "aria-label", "placeholder"];
+
+/// What the scanner is inside of
+enum Frame {
+    /// JavaScript, with the number of open braces
+    Code { braces: usize },
+    /// Children of an element, with the number of open elements
+    Children { depth: usize },
+}
+
+/// Check if a unit was found by [`extract_jsx_text`]
+pub fn is_jsx_text(unit: &TranslatableUnit) -> bool {
+    unit.unit_type == UnitType::TextNode
+        && unit
+            .metadata
+            .as_ref()
+            .is_some_and(|m| m.get(JSX_KEY).is_some())
+}
+
+/// Find the text children and text attributes of JSX elements
+pub fn extract_jsx_text(content: &str) -> Vec<TranslatableUnit> {
+    let lines = LineIndex::new(content);
+    let bytes = content.as_bytes();
+    let mut units = Vec::new();
+    let mut frames = vec![Frame::Code { braces: 0 }];
+    // Start of the children text being read
+    let mut text_start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let rest = &content[i..];
+        let nested = frames.len() > 1;
+        match frames.last_mut() {
+            Some(Frame::Code { braces }) => match bytes[i] {
+                b'/' if rest.starts_with("//") => i += rest.find('\n').unwrap_or(rest.len()),
+                b'/' if rest.starts_with("/*") => {
+                    i += rest.find("*/").map_or(rest.len(), |p| p + 2)
+                }
+                b'\'' | b'"' | b'`' => i += quoted_len(rest),
+                b'{' => {
+                    *braces += 1;
+                    i += 1;
+                }
+                b'}' if *braces == 0 && nested => {
+                    // End of an expression container in children
+                    frames.pop();
+                    i += 1;
+                    text_start = i;
+                }
+                b'}' => {
+                    *braces = braces.saturating_sub(1);
+                    i += 1;
+                }
+                b'<' if starts_element(content, i) => {
+                    let (end, self_closing) = read_tag(content, i, &lines, &mut units);
+                    i = end;
+                    if !self_closing {
+                        frames.push(Frame::Children { depth: 1 });
+                        text_start = i;
+                    }
+                }
+                _ => i += char_len(rest),
+            },
+            Some(Frame::Children { depth }) => match bytes[i] {
+                b'{' | b'<' => {
+                    units.extend(text_units(content, text_start, i, &lines));
+                    if bytes[i] == b'{' {
+                        frames.push(Frame::Code { braces: 0 });
+                        i += 1;
+                    } else if rest.starts_with("</") {
+                        i += rest.find('>').map_or(rest.len(), |p| p + 1);
+                        *depth -= 1;
+                        if *depth == 0 {
+                            frames.pop();
+                        }
+                    } else {
+                        let (end, self_closing) = read_tag(content, i, &lines, &mut units);
+                        i = end;
+                        if !self_closing {
+                            *depth += 1;
+                        }
+                    }
+                    text_start = i;
+                }
+                _ => i += char_len(rest),
+            },
+            None => break,
+        }
+    }
+
+    units
+}
+
+/// New text of the lines a unit spans, as `(0-based line, text)`
+///
+/// `line` returns the current text of a line. Lines that no longer hold
+/// the unit's original text where it was found are left out.
+pub fn rewrite_lines<F>(unit: &TranslatableUnit, line: F) -> Vec<(usize, String)>
+where
+    F: Fn(usize) -> Option<String>,
+{
+    let Some(jsx) = unit.metadata.as_ref().and_then(|m| m.get(JSX_KEY)) else {
+        return Vec::new();
+    };
+    let segments: Vec<&str> = jsx
+        .get("segments")
+        .and_then(|s| s.as_array())
+        .map(|s| s.iter().filter_map(|v| v.as_str()).collect())
+        .unwrap_or_default();
+    let quote = jsx.get("quote").and_then(|q| q.as_str());
+    let pieces = paragraph::split_paragraph(&unit.content, segments.len());
+    let first = (unit.line_number as usize).saturating_sub(1);
+
+    let mut rewritten = Vec::new();
+    for (offset, (segment, piece)) in segments.iter().zip(&pieces).enumerate() {
+        let Some(text) = line(first + offset) else {
+            break;
+        };
+        let start = if offset == 0 {
+            (unit.column_number as usize).saturating_sub(1)
+        } else {
+            text.len() - text.trim_start().len()
+        };
+        if !text.get(start..).is_some_and(|t| t.starts_with(segment)) {
+            continue;
+        }
+        let new_text = format!(
+            "{}{}{}",
+            &text[..start],
+            escape(piece, quote),
+            &text[start + segment.len()..]
+        );
+        let new_text = if new_text.trim().is_empty() {
+            String::new()
+        } else {
+            new_text
+        };
+        rewritten.push((first + offset, new_text));
+    }
+    rewritten
+}
+
+/// Escape characters JSX would read as markup
+fn escape(text: &str, quote: Option<&str>) -> String {
+    match quote {
+        Some("'") => text.replace('\'', "&apos;"),
+        Some(_) => text.replace('"', "&quot;"),
+        None => text
+            .replace('{', "&#123;")
+            .replace('}', "&#125;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;"),
+    }
+}
+
+/// Units of the children text in `content[start..end]`
+///
+/// Runs of adjacent non-blank lines become one unit each.
+fn text_units(content: &str, start: usize, end: usize, lines: &LineIndex) -> Vec<TranslatableUnit> {
+    let mut units = Vec::new();
+    // Trimmed line texts of the run being read, with their offsets
+    let mut run: Vec<(usize, &str)> = Vec::new();
+    let mut offset = start;
+    for line in content[start..end].split_inclusive('\n') {
+        let text = line.trim();
+        if text.is_empty() {
+            units.extend(text_unit(std::mem::take(&mut run), lines));
+        } else {
+            run.push((offset + line.len() - line.trim_start().len(), text));
+        }
+        offset += line.len();
+    }
+    units.extend(text_unit(run, lines));
+    units
+}
+
+fn text_unit(run: Vec<(usize, &str)>, lines: &LineIndex) -> Option<TranslatableUnit> {
+    let (start, _) = *run.first()?;
+    let segments: Vec<&str> = run.iter().map(|(_, text)| *text).collect();
+    let text = segments.join(" ");
+    if !is_text(&text) {
+        return None;
+    }
+    let (line, column) = lines.position(start);
+    let mut unit = TranslatableUnit::new(text, UnitType::TextNode, line, column)
+        .with_context(format!("JSX text at line {}", line))
+        .with_priority(Priority::Medium)
+        .with_metadata(serde_json::json!({ JSX_KEY: { "segments": segments } }));
+    unit.detect_language();
+    Some(unit)
+}
+
+/// Check if JSX text has words in it, not just punctuation or entities
+fn is_text(text: &str) -> bool {
+    static ENTITY: OnceLock<Regex> = OnceLock::new();
+    let entity = ENTITY.get_or_init(|| Regex::new(r"&#?[A-Za-z0-9]+;").unwrap());
+    let text = entity.replace_all(text, " ");
+    text.chars().any(|c| c.is_alphabetic() && !c.is_ascii())
+        || text
+            .split(|c: char| !c.is_alphabetic())
+            .any(|word| word.len() >= 2)
+}
+
+/// Check if the `<` at `at` opens an element rather than comparing
+fn starts_element(content: &str, at: usize) -> bool {
+    let after = &content[at + 1..];
+    let name_len = after
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | ':' | '-')))
+        .unwrap_or(after.len());
+    let name_ok = match after.as_bytes().first() {
+        // Fragment
+        Some(b'>') => true,
+        Some(c) if c.is_ascii_alphabetic() => after[name_len..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_whitespace() || c == '>' || c == '/'),
+        _ => false,
+    };
+    if !name_ok {
+        return false;
+    }
+
+    // Elements start expressions, after an operator or `return`
+    let before = content[..at].trim_end();
+    match before.chars().next_back() {
+        None => true,
+        Some(c) if "(,=:?&|{}[;>".contains(c) => true,
+        Some(_) => {
+            before.ends_with("return")
+                && !before[..before.len() - "return".len()]
+                    .chars()
+                    .next_back()
+                    .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '$')
+        }
+    }
+}
+
+/// Read the tag opening at `start`, collecting its text attributes
+///
+/// Returns the offset past the tag and whether it closes itself.
+fn read_tag(
+    content: &str,
+    start: usize,
+    lines: &LineIndex,
+    units: &mut Vec<TranslatableUnit>,
+) -> (usize, bool) {
+    let bytes = content.as_bytes();
+    let name_end = |from: usize| {
+        content[from..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | ':' | '-')))
+            .map_or(content.len(), |p| from + p)
+    };
+    let mut i = name_end(start + 1);
+
+    while i < bytes.len() {
+        let rest = &content[i..];
+        match bytes[i] {
+            b'>' => return (i + 1, false),
+            b'/' if rest.starts_with("/>") => return (i + 2, true),
+            b'{' => i += braced_len(rest),
+            c if c.is_ascii_alphabetic() => {
+                let end = name_end(i);
+                let attribute = &content[i..end];
+                i = end;
+                let value_at = i + content[i..].len() - content[i..].trim_start().len();
+                if !content[value_at..].starts_with('=') {
+                    continue;
+                }
+                i = value_at + 1;
+                i += content[i..].len() - content[i..].trim_start().len();
+                match bytes.get(i) {
+                    Some(&quote @ (b'"' | b'\'')) => {
+                        let Some(len) = content[i + 1..].find(quote as char) else {
+                            return (content.len(), true);
+                        };
+                        let value = &content[i + 1..i + 1 + len];
+                        if TEXT_ATTRIBUTES.contains(&attribute)
+                            && !value.contains('\n')
+                            && is_text(value)
+                        {
+                            units.push(attribute_unit(attribute, value, i + 1, quote, lines));
+                        }
+                        i += len + 2;
+                    }
+                    Some(b'{') => i += braced_len(&content[i..]),
+                    _ => {}
+                }
+            }
+            _ => i += char_len(rest),
+        }
+    }
+    // Unterminated: nothing follows to read as children
+    (content.len(), true)
+}
+
+fn attribute_unit(
+    attribute: &str,
+    value: &str,
+    offset: usize,
+    quote: u8,
+    lines: &LineIndex,
+) -> TranslatableUnit {
+    let (line, column) = lines.position(offset);
+    let mut unit = TranslatableUnit::new(value.to_string(), UnitType::TextNode, line, column)
+        .with_context(format!("JSX {} attribute at line {}", attribute, line))
+        .with_priority(Priority::Medium)
+        .with_metadata(serde_json::json!({
+            JSX_KEY: {
+                "attribute": attribute,
+                "quote": (quote as char).to_string(),
+                "segments": [value],
+            }
+        }));
+    unit.detect_language();
+    unit
+}
+
+/// Length of the `{...}` expression at the start of `text`
+fn braced_len(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            b'\'' | b'"' | b'`' => {
+                i += quoted_len(&text[i..]);
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    text.len()
+}
+
+/// Length of the string literal at the start of `text`
+///
+/// Plain strings end at the line end if unterminated.
+fn quoted_len(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    let quote = bytes[0];
+    let mut i = 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'\n' if quote != b'`' => return i,
+            b if b == quote => return i + 1,
+            _ => i += 1,
+        }
+    }
+    text.len()
+}
+
+fn char_len(text: &str) -> usize {
+    text.chars().next().map_or(1, char::len_utf8)
+}
+
+/// Line and column of byte offsets
+struct LineIndex {
+    starts: Vec<usize>,
+}
+
+impl LineIndex {
+    fn new(content: &str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { starts }
+    }
+
+    /// 1-based line and byte column of an offset
+    fn position(&self, offset: usize) -> (u32, u32) {
+        let line = self.starts.partition_point(|&start| start <= offset);
+        let column = offset - self.starts[line - 1] + 1;
+        (line as u32, column as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_jsx_text() {
+        let code = r#"const n = items.length < 10 ? 1 : 2;
+export function Save({ items }) {
+  return (
+    <form title="保存表单" onSubmit={() => send(items)}>
+      <h1>欢迎使用</h1>
+      <input placeholder='请输入名称' value={name} />
+      <p>
+        这是一段很长的说明，
+        分成了两行
+      </p>
+      {loading && <span aria-label="加载中">…</span>}
+      <Button variant="primary">{count}&nbsp;</Button>
+    </form>
+  );
+}
+"#;
+        let units: Vec<(u32, u32, String)> = extract_jsx_text(code)
+            .into_iter()
+            .map(|unit| (unit.line_number, unit.column_number, unit.content))
+            .collect();
+        assert_eq!(
+            units,
+            vec![
+                (4, 18, "保存表单".to_string()),
+                (5, 11, "欢迎使用".to_string()),
+                (6, 27, "请输入名称".to_string()),
+                (8, 9, "这是一段很长的说明， 分成了两行".to_string()),
+                (11, 37, "加载中".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rewrite_lines() {
+        let code = "<p title=\"提示\">\n  第一行\n  第二行</p>\n";
+        let units = extract_jsx_text(code);
+        let lines: Vec<&str> = code.lines().collect();
+        let line = |i: usize| lines.get(i).map(|l| l.to_string());
+
+        let mut title = units[0].clone();
+        title.content = "A \"hint\"".to_string();
+        assert_eq!(
+            rewrite_lines(&title, line),
+            vec![(0, "<p title=\"A &quot;hint&quot;\">".to_string())]
+        );
+
+        let mut text = units[1].clone();
+        assert!(is_jsx_text(&text));
+        text.content = "First line, second {line}".to_string();
+        assert_eq!(
+            rewrite_lines(&text, line),
+            vec![
+                (1, "  First line,".to_string()),
+                (2, "  second &#123;line&#125;</p>".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,591 @@
+//! LaTeX parser (.tex files)
+//!
+//! Paragraphs, `\item`s, sectioning titles and captions of the document
+//! body become `TextNode` units, and full-line `%` comments become `Comment`
+//! units. The preamble, math (`\[ \]`, `$$`, `equation`, `align`, ...),
+//! verbatim and code listings, tables and TikZ pictures are never
+//! extracted. Inline math, labels, references, citations and command names
+//! inside the text are masked by the pipeline while translating.
+
+use anyhow::Result;
+use langlint_core::types::detect_language;
+use langlint_core::{ParseResult, Priority, TranslatableUnit, UnitType};
+use regex::Regex;
+
+use crate::Parser;
+
+/// Unit metadata key holding the markup around a unit's lines
+pub const LATEX_KEY: &str = "latex";
+
+/// Environments whose body is kept verbatim
+const SKIPPED_ENVIRONMENTS: &[&str] = &[
+    "equation",
+    "align",
+    "alignat",
+    "gather",
+    "multline",
+    "flalign",
+    "eqnarray",
+    "math",
+    "displaymath",
+    "verbatim",
+    "Verbatim",
+    "lstlisting",
+    "minted",
+    "comment",
+    "tikzpicture",
+    "tabular",
+    "tabularx",
+    "longtable",
+    "array",
+    "algorithmic",
+    "thebibliography",
+];
+
+/// Parser for LaTeX files
+pub struct LatexParser {
+    begin: Regex,
+    title: Regex,
+    item: Regex,
+    markup: Regex,
+}
+
+/// Lines of a unit with the markup around them
+struct Block {
+    start: usize,
+    /// Line after the unit's last line
+    end: usize,
+    lines: Vec<String>,
+    /// Markup before the first line (`\section{`, `\item `, `% `)
+    prefix: String,
+    /// Markup before each following line
+    continuation: String,
+    /// Markup after the first line (`}\label{sec:intro}`)
+    suffix: String,
+    unit_type: UnitType,
+    priority: Priority,
+}
+
+impl LatexParser {
+    /// Create a new LaTeX parser
+    pub fn new() -> Self {
+        Self {
+            begin: Regex::new(r"^\s*\\begin\{([A-Za-z]+)(\*?)\}").unwrap(),
+            title: Regex::new(
+                r"^\s*\\(part|chapter|section|subsection|subsubsection|paragraph|subparagraph|title|caption)\*?(?:\[[^\]]*\])?\{",
+            )
+            .unwrap(),
+            item: Regex::new(r"^(\s*\\item(?:\[[^\]]*\])?\s*)(.*)$").unwrap(),
+            markup: Regex::new(
+                r"\\[%$&#_{}]|%.*|\$[^$]*\$|\\(?:begin|end|label|[cC]?ref|eqref|autoref|pageref|cite[A-Za-z]*|url|href|input|include|includegraphics|usepackage|documentclass|bibliography[A-Za-z]*)\*?(?:\[[^\]]*\])*\{[^{}]*\}|\\[A-Za-z]+",
+            )
+            .unwrap(),
+        }
+    }
+
+    /// Check if a block's text is worth translating: a word outside math,
+    /// commands, keys and comments
+    fn is_translatable(&self, text: &str) -> bool {
+        let prose = self.markup.replace_all(text, " ");
+        prose
+            .split(|c: char| !c.is_alphabetic())
+            .any(|word| word.chars().count() >= 2)
+    }
+
+    /// Check if a line only holds commands (`\centering`, `\label{...}`)
+    fn is_command_line(&self, trimmed: &str) -> bool {
+        trimmed.starts_with('\\') && !self.is_translatable(trimmed)
+    }
+
+    /// Split a document into the blocks holding its text
+    fn blocks(&self, lines: &[&str]) -> Vec<Block> {
+        let mut blocks = Vec::new();
+        let (mut i, end) = document_body(lines);
+
+        while i < end {
+            let line = lines[i];
+            let trimmed = line.trim_start();
+            let indent = &line[..line.len() - trimmed.len()];
+
+            if trimmed.is_empty() {
+                i += 1;
+                continue;
+            }
+
+            if trimmed.starts_with('%') {
+                let start = i;
+                let marker_len = trimmed.len()
+                    - trimmed
+                        .trim_start_matches('%')
+                        .trim_start_matches([' ', '\t'])
+                        .len();
+                let prefix = format!("{}{}", indent, &trimmed[..marker_len]);
+                let mut text = Vec::new();
+                while i < end && lines[i].trim_start().starts_with('%') {
+                    let comment = lines[i].trim_start().trim_start_matches('%');
+                    text.push(comment.trim().to_string());
+                    i += 1;
+                }
+                // `% !TeX program = xelatex` directives
+                if !text[0].starts_with('!') {
+                    blocks.push(Block {
+                        start,
+                        end: i,
+                        lines: text,
+                        prefix: prefix.clone(),
+                        continuation: prefix,
+                        suffix: String::new(),
+                        unit_type: UnitType::Comment,
+                        priority: Priority::Low,
+                    });
+                }
+                continue;
+            }
+
+            if let Some(skipped_end) = self.skipped_block_end(lines, i, end) {
+                i = skipped_end;
+                continue;
+            }
+
+            if let Some(block) = self.title_block(lines, i) {
+                blocks.push(block);
+                i += 1;
+                continue;
+            }
+
+            if self.is_command_line(trimmed) && !self.item.is_match(line) {
+                i += 1;
+                continue;
+            }
+
+            let (prefix, first) = match self.item.captures(line) {
+                Some(captures) => (captures[1].to_string(), captures[2].to_string()),
+                None => (indent.to_string(), trimmed.to_string()),
+            };
+
+            // The item's or paragraph's lines, up to a blank line or another block
+            let start = i;
+            let mut text = vec![first];
+            i += 1;
+            while i < end {
+                let next = lines[i].trim_start();
+                if next.is_empty()
+                    || next.starts_with('%')
+                    || self.item.is_match(next)
+                    || self.title.is_match(next)
+                    || self.is_command_line(next)
+                    || self.skipped_block_end(lines, i, end).is_some()
+                {
+                    break;
+                }
+                text.push(next.to_string());
+                i += 1;
+            }
+            let continuation = match lines.get(start + 1) {
+                Some(next) if start + 1 < i => next[..next.len() - text[1].len()].to_string(),
+                _ => indent.to_string(),
+            };
+            blocks.push(Block {
+                start,
+                end: i,
+                lines: text,
+                prefix,
+                continuation,
+                suffix: String::new(),
+                unit_type: UnitType::TextNode,
+                priority: Priority::Medium,
+            });
+        }
+
+        blocks
+    }
+
+    /// A sectioning command, `\title` or `\caption` whose argument closes
+    /// on the same line
+    fn title_block(&self, lines: &[&str], i: usize) -> Option<Block> {
+        let line = lines[i];
+        let captures = self.title.captures(line)?;
+        let open = captures.get(0)?.end();
+        let close = open + closing_brace(&line[open..])?;
+        let priority = match &captures[1] {
+            "caption" => Priority::Medium,
+            _ => Priority::High,
+        };
+        Some(Block {
+            start: i,
+            end: i + 1,
+            lines: vec![line[open..close].to_string()],
+            prefix: line[..open].to_string(),
+            continuation: String::new(),
+            suffix: line[close..].to_string(),
+            unit_type: UnitType::TextNode,
+            priority,
+        })
+    }
+
+    /// End of a block kept verbatim starting at line `i`: display math or
+    /// one of [`SKIPPED_ENVIRONMENTS`]
+    fn skipped_block_end(&self, lines: &[&str], i: usize, end: usize) -> Option<usize> {
+        let trimmed = lines[i].trim_start();
+        let closing = |from: usize, marker: &str| {
+            (from..end)
+                .find(|&j| lines[j].contains(marker))
+                .map_or(end, |j| j + 1)
+        };
+
+        if let Some(captures) = self.begin.captures(trimmed) {
+            if !SKIPPED_ENVIRONMENTS.contains(&&captures[1]) {
+                return None;
+            }
+            let marker = format!("\\end{{{}{}}}", &captures[1], &captures[2]);
+            return Some(closing(i, &marker));
+        }
+        if let Some(rest) = trimmed.strip_prefix("\\[") {
+            if rest.contains("\\]") {
+                return Some(i + 1);
+            }
+            return Some(closing(i + 1, "\\]"));
+        }
+        if let Some(rest) = trimmed.strip_prefix("$$") {
+            if rest.contains("$$") {
+                return Some(i + 1);
+            }
+            return Some(closing(i + 1, "$$"));
+        }
+        None
+    }
+}
+
+impl Default for LatexParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Lines between `\begin{document}` and `\end{document}`, or all lines of
+/// a file without them (a chapter pulled in with `\input`)
+fn document_body(lines: &[&str]) -> (usize, usize) {
+    let find = |marker: &str| {
+        lines
+            .iter()
+            .position(|line| line.trim_start().starts_with(marker))
+    };
+    let start = find("\\begin{document}").map_or(0, |i| i + 1);
+    let end = find("\\end{document}")
+        .filter(|&end| end >= start)
+        .unwrap_or(lines.len());
+    (start, end)
+}
+
+/// Offset of the `}` closing a group whose `{` was just before `text`
+fn closing_brace(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut escaped = false;
+    for (offset, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(offset),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Markup around a unit's lines, from its metadata
+fn markup(unit: &TranslatableUnit) -> Option<(usize, &str, &str, &str)> {
+    let latex = unit.metadata.as_ref()?.get(LATEX_KEY)?;
+    let field = |key: &str| latex.get(key).and_then(|v| v.as_str()).unwrap_or("");
+    let end = latex.get("end_line")?.as_u64()? as usize;
+    Some((end, field("prefix"), field("continuation"), field("suffix")))
+}
+
+impl Parser for LatexParser {
+    fn name(&self) -> &'static str {
+        "LaTeX"
+    }
+
+    fn supported_extensions(&self) -> &'static [&'static str] {
+        &[".tex", ".ltx"]
+    }
+
+    fn can_parse(&self, path: &str, _content: Option<&str>) -> bool {
+        let lower = path.to_lowercase();
+        self.supported_extensions()
+            .iter()
+            .any(|ext| lower.ends_with(ext))
+    }
+
+    fn extract_units(&self, content: &str, _path: &str) -> Result<ParseResult> {
+        let lines: Vec<&str> = content.lines().collect();
+        let units = self
+            .blocks(&lines)
+            .into_iter()
+            .filter(|block| self.is_translatable(&block.lines.join("\n")))
+            .map(|block| {
+                let mut unit = TranslatableUnit::new(
+                    block.lines.join("\n"),
+                    block.unit_type,
+                    (block.start + 1) as u32,
+                    (block.prefix.chars().count() + 1) as u32,
+                )
+                .with_metadata(serde_json::json!({
+                    LATEX_KEY: {
+                        "end_line": block.end,
+                        "prefix": block.prefix,
+                        "continuation": block.continuation,
+                        "suffix": block.suffix,
+                    }
+                }))
+                .with_priority(block.priority);
+                // Command names and citation keys would pass for English
+                unit.detected_language =
+                    detect_language(&self.markup.replace_all(&unit.content, " "));
+                unit
+            })
+            .collect();
+
+        Ok(ParseResult::new("latex", "utf-8", lines.len() as u32).with_units(units))
+    }
+
+    fn reconstruct(
+        &self,
+        original: &str,
+        units: &[TranslatableUnit],
+        _path: &str,
+    ) -> Result<String> {
+        let newline = if original.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        let mut lines: Vec<String> = original.lines().map(str::to_string).collect();
+
+        let mut units: Vec<&TranslatableUnit> = units.iter().collect();
+        units.sort_by_key(|unit| std::cmp::Reverse(unit.line_number));
+        for unit in units {
+            let Some((end, prefix, continuation, suffix)) = markup(unit) else {
+                continue;
+            };
+            let start = (unit.line_number as usize).saturating_sub(1);
+            if start >= end || end > lines.len() {
+                continue;
+            }
+            let replacement: Vec<String> = unit
+                .content
+                .lines()
+                .enumerate()
+                .map(|(k, text)| {
+                    let line = match k {
+                        0 => format!("{}{}{}", prefix, text, suffix),
+                        _ => format!("{}{}", continuation, text),
+                    };
+                    if text.is_empty() {
+                        line.trim_end().to_string()
+                    } else {
+                        line
+                    }
+                })
+                .collect();
+            lines.splice(start..end, replacement);
+        }
+
+        let mut result = lines.join(newline);
+        if original.ends_with('\n') {
+            result.push_str(newline);
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = r"% !TeX program = xelatex
+\documentclass{ctexart}
+\usepackage{amsmath}
+\title{深度学习综述}
+
+\begin{document}
+\maketitle
+
+\section{引言}\label{sec:intro}
+% 这里需要补充引用
+近年来，深度学习在图像识别领域取得了显著进展~\cite{lecun2015}。
+  如公式~\eqref{eq:loss} 所示，损失 $L$ 随训练下降。
+
+\begin{equation}
+  L = \frac{1}{N} \sum_{i} \ell_i \label{eq:loss}
+\end{equation}
+
+\begin{itemize}
+  \item 数据增强
+  \item[2.] 正则化方法
+\end{itemize}
+
+\begin{figure}[t]
+  \centering
+  \includegraphics[width=0.8\linewidth]{figures/model.pdf}
+  \caption{模型结构 \textbf{示意}}
+  \label{fig:model}
+\end{figure}
+
+\begin{verbatim}
+不翻译的代码
+\end{verbatim}
+\[ x^2 \]
+\end{document}
+";
+
+    #[test]
+    fn test_extract_units() {
+        let parser = LatexParser::new();
+        assert!(parser.can_parse("paper/Main.TEX", None));
+        assert!(!parser.can_parse("main.bib", None));
+
+        let result = parser.extract_units(DOC, "main.tex").unwrap();
+        let units: Vec<(u32, &str, UnitType, Priority)> = result
+            .units
+            .iter()
+            .map(|unit| {
+                (
+                    unit.line_number,
+                    unit.content.as_str(),
+                    unit.unit_type,
+                    unit.priority,
+                )
+            })
+            .collect();
+        assert_eq!(
+            units,
+            vec![
+                (9, "引言", UnitType::TextNode, Priority::High),
+                (10, "这里需要补充引用", UnitType::Comment, Priority::Low),
+                (
+                    11,
+                    "近年来，深度学习在图像识别领域取得了显著进展~\\cite{lecun2015}。\n\
+                     如公式~\\eqref{eq:loss} 所示，损失 $L$ 随训练下降。",
+                    UnitType::TextNode,
+                    Priority::Medium
+                ),
+                (19, "数据增强", UnitType::TextNode, Priority::Medium),
+                (20, "正则化方法", UnitType::TextNode, Priority::Medium),
+                (
+                    26,
+                    "模型结构 \\textbf{示意}",
+                    UnitType::TextNode,
+                    Priority::Medium
+                ),
+            ]
+        );
+        // Not taken for English because of the citation and command names
+        assert_eq!(result.units[2].detected_language.as_deref(), Some("zh-CN"));
+    }
+
+    #[test]
+    fn test_reconstruct() {
+        let parser = LatexParser::new();
+        let mut units = parser.extract_units(DOC, "main.tex").unwrap().units;
+        let translations = [
+            "Introduction",
+            "Citations needed here",
+            "Deep learning has made great progress~\\cite{lecun2015}.\n\
+             As \\eqref{eq:loss} shows, the loss $L$ goes down.\nDuring training.",
+            "Data augmentation",
+            "Regularization",
+            "Model architecture \\textbf{overview}",
+        ];
+        for (unit, translation) in units.iter_mut().zip(translations) {
+            unit.content = translation.to_string();
+        }
+
+        let result = parser.reconstruct(DOC, &units, "main.tex").unwrap();
+        assert!(
+            result.contains("\\section{Introduction}\\label{sec:intro}\n% Citations needed here\n")
+        );
+        assert!(result.contains(
+            "progress~\\cite{lecun2015}.\n  As \\eqref{eq:loss} shows, the loss $L$ goes down.\n  During training.\n\n\\begin{equation}"
+        ));
+        assert!(result.contains("  \\item Data augmentation\n  \\item[2.] Regularization\n"));
+        assert!(result
+            .contains("  \\caption{Model architecture \\textbf{overview}}\n  \\label{fig:model}"));
+        assert!(result.contains("\\title{深度学习综述}") && result.contains("不翻译的代码"));
+
+        // Untouched units give back the original
+        let units = parser.extract_units(DOC, "main.tex").unwrap().units;
+        assert_eq!(parser.reconstruct(DOC, &units, "main.tex").unwrap(), DOC);
+    }
+
+    #[test]
+    fn test_round_trip_math_and_verbatim() {
+        let doc = r"\begin{document}
+设 $x \in \mathbb{R}$，则有
+$$
+  f(x) = \text{正值}
+$$
+由此可得结论。
+
+\begin{align*}
+  a &= b \\ % 对齐注释
+  c &= d
+\end{align*}
+
+\[
+  \text{不翻译} + 1
+\]
+
+\begin{verbatim}
+% 不是注释
+\section{不是标题}
+\end{verbatim}
+
+\begin{lstlisting}[language=Python]
+# 代码注释
+\end{lstlisting}
+
+\begin{tabular}{ll}
+名称 & 说明 \\
+\end{tabular}
+
+\begin{tikzpicture}
+  \node {节点};
+\end{tikzpicture}
+
+最后一段，含行内公式 \( y = 2 \)。
+\end{document}
+";
+        let parser = LatexParser::new();
+        let mut units = parser.extract_units(doc, "proof.tex").unwrap().units;
+        let contents: Vec<(u32, &str)> = units
+            .iter()
+            .map(|unit| (unit.line_number, unit.content.as_str()))
+            .collect();
+        assert_eq!(
+            contents,
+            vec![
+                (2, "设 $x \\in \\mathbb{R}$，则有"),
+                (6, "由此可得结论。"),
+                (34, "最后一段，含行内公式 \\( y = 2 \\)。"),
+            ]
+        );
+
+        let translations = [
+            "Let $x \\in \\mathbb{R}$, then",
+            "which proves the claim.",
+            "A last paragraph with inline math \\( y = 2 \\).",
+        ];
+        for (unit, translation) in units.iter_mut().zip(translations) {
+            unit.content = translation.to_string();
+        }
+        let rebuilt = parser.reconstruct(doc, &units, "proof.tex").unwrap();
+        assert_eq!(
+            rebuilt,
+            doc.replace("设 $x \\in \\mathbb{R}$，则有", translations[0])
+                .replace("由此可得结论。", translations[1])
+                .replace("最后一段，含行内公式 \\( y = 2 \\)。", translations[2])
+        );
+    }
+}
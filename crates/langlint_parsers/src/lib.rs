@@ -33,11 +33,129 @@ pub trait Parser: Send + Sync {
         -> Result<String>;
 }
 
+pub mod android;
+pub mod apple;
+pub mod doc_comment;
+pub mod docstring;
+pub mod generated;
 pub mod generic;
+pub mod html;
+pub mod i18n;
+pub mod jsdoc;
+pub mod jsx;
+pub mod latex;
+pub mod license;
+pub mod locale;
+pub mod markdown;
 pub mod notebook;
+pub mod overrides;
+pub mod paragraph;
+pub mod po;
+pub mod pragma;
 pub mod python;
+pub mod rst;
+pub mod sinks;
+pub mod todo;
+pub mod xliff;
+pub mod xmldoc;
 
 // Re-export parsers
+pub use android::AndroidStringsParser;
+pub use apple::AppleStringsParser;
 pub use generic::GenericCodeParser;
+pub use html::HtmlParser;
+pub use i18n::{I18nExtractor, SourceKind};
+pub use jsdoc::{JavadocParser, JsDocParser};
+pub use latex::LatexParser;
+pub use locale::LocaleParser;
+pub use markdown::MarkdownParser;
 pub use notebook::NotebookParser;
+pub use po::PoParser;
 pub use python::PythonParser;
+pub use rst::RstParser;
+pub use xliff::XliffParser;
+pub use xmldoc::XmlDocParser;
+
+/// Names accepted by [`parser_by_name`]
+pub const PARSER_NAMES: &[&str] = &[
+    "python",
+    "notebook",
+    "markdown",
+    "rst",
+    "html",
+    "latex",
+    "po",
+    "locale",
+    "android",
+    "apple",
+    "xliff",
+    "jsdoc",
+    "javadoc",
+    "xmldoc",
+    "generic",
+    "generic:<language>",
+];
+
+/// Parsers tried by [`parser_for`], most specific first
+const PARSERS: &[fn() -> Box<dyn Parser>] = &[
+    || Box::new(PythonParser::new()),
+    || Box::new(NotebookParser::new()),
+    || Box::new(MarkdownParser::new()),
+    || Box::new(RstParser::new()),
+    || Box::new(HtmlParser::new()),
+    || Box::new(LatexParser::new()),
+    || Box::new(PoParser::new()),
+    || Box::new(LocaleParser::new()),
+    || Box::new(AndroidStringsParser::new()),
+    || Box::new(AppleStringsParser::new()),
+    || Box::new(XliffParser::new()),
+    || Box::new(JsDocParser::new()),
+    || Box::new(JavadocParser::new()),
+    || Box::new(XmlDocParser::new()),
+    || Box::new(GenericCodeParser::new()),
+];
+
+/// Find the parser for a file from its path, and its content if known
+///
+/// Returns `None` if no parser can handle the file.
+pub fn parser_for(path: &str, content: Option<&str>) -> Option<Box<dyn Parser>> {
+    PARSERS
+        .iter()
+        .map(|parser| parser())
+        .find(|parser| parser.can_parse(path, content))
+}
+
+/// Create a parser from its config name
+///
+/// `generic:<language>` parses comments in the style of `language`
+/// (`generic:groovy`, `generic:shell`) regardless of the file extension.
+pub fn parser_by_name(name: &str) -> Result<Box<dyn Parser>> {
+    let name = name.trim();
+    match name.to_lowercase().as_str() {
+        "python" => Ok(Box::new(PythonParser::new())),
+        "notebook" | "ipynb" => Ok(Box::new(NotebookParser::new())),
+        "markdown" | "md" => Ok(Box::new(MarkdownParser::new())),
+        "rst" | "restructuredtext" => Ok(Box::new(RstParser::new())),
+        "html" | "jinja" => Ok(Box::new(HtmlParser::new())),
+        "latex" | "tex" => Ok(Box::new(LatexParser::new())),
+        "po" | "gettext" => Ok(Box::new(PoParser::new())),
+        "locale" | "i18n" => Ok(Box::new(LocaleParser::new())),
+        "android" => Ok(Box::new(AndroidStringsParser::new())),
+        "apple" | "strings" | "stringsdict" => Ok(Box::new(AppleStringsParser::new())),
+        "xliff" | "xlf" => Ok(Box::new(XliffParser::new())),
+        "jsdoc" | "tsdoc" => Ok(Box::new(JsDocParser::new())),
+        "javadoc" => Ok(Box::new(JavadocParser::new())),
+        "xmldoc" | "csharp" => Ok(Box::new(XmlDocParser::new())),
+        "generic" => Ok(Box::new(GenericCodeParser::new())),
+        lower => match lower.strip_prefix("generic:") {
+            Some(language) if !language.trim().is_empty() => {
+                Ok(Box::new(GenericCodeParser::for_language(language)))
+            }
+            _ => anyhow::bail!(
+                "Unknown parser '{}' (expected one of: {})",
+                name,
+                PARSER_NAMES.join(", ")
+            ),
+        },
+    }
+}
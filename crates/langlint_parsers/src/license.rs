@@ -0,0 +1,180 @@
+//! License header detection
+//!
+//! Legal text at the top of a file (SPDX identifiers, copyright notices,
+//! license boilerplate) should not be machine-translated. Parsers tag units
+//! inside such a header in unit metadata, and callers drop them unless the
+//! user opts in (see [`is_license_header`]).
+
+use langlint_core::TranslatableUnit;
+use regex::Regex;
+use std::ops::RangeInclusive;
+use std::sync::OnceLock;
+
+/// Unit metadata key flagging units inside a license header
+pub const LICENSE_HEADER_KEY: &str = "license_header";
+
+/// Line comment prefixes recognised in file headers
+const COMMENT_PREFIXES: [&str; 7] = ["#", "//", "--", ";", "%", "*", "REM "];
+
+/// Check if text looks like license or copyright boilerplate
+pub fn is_license_text(text: &str) -> bool {
+    static LICENSE_RE: OnceLock<Regex> = OnceLock::new();
+    let re = LICENSE_RE.get_or_init(|| {
+        Regex::new(
+            r"(?i)SPDX-License-Identifier|SPDX-FileCopyrightText|copyright\s|\(c\)\s*\d{4}|©|all rights reserved|licensed under|license(?:d)?\s+(?:under|terms|version)|apache license|GNU (?:Lesser |Affero )?General Public|MIT License|版权|许可证|許可證|著作権|ライセンス|저작권|라이선스",
+        )
+        .unwrap()
+    });
+    re.is_match(text)
+}
+
+/// Line ranges (1-based, inclusive) of license blocks in a file header
+///
+/// The header is the run of comments and module docstrings before the
+/// first line of code. It is split into blocks at blank lines, and blocks
+/// mentioning a license or copyright are returned.
+pub fn license_header_lines(content: &str) -> Vec<RangeInclusive<u32>> {
+    let mut blocks = Vec::new();
+    let mut block: Option<(u32, u32, String)> = None;
+    // Closing delimiter of a block comment or docstring we are inside of
+    let mut open: Option<&str> = None;
+
+    let mut flush = |block: &mut Option<(u32, u32, String)>| {
+        if let Some((start, end, text)) = block.take() {
+            if is_license_text(&text) {
+                blocks.push(start..=end);
+            }
+        }
+    };
+
+    for (index, line) in content.lines().enumerate() {
+        let line_num = (index + 1) as u32;
+        let trimmed = line.trim();
+
+        let in_header = if let Some(close) = open {
+            if trimmed.contains(close) {
+                open = None;
+            }
+            true
+        } else if trimmed.is_empty() {
+            flush(&mut block);
+            continue;
+        } else if index == 0 && trimmed.starts_with("#!") {
+            continue;
+        } else if let Some(delimiter) = ["/*", "<!--", "\"\"\"", "'''", "{-", "(*"]
+            .iter()
+            .find(|d| trimmed.starts_with(**d))
+        {
+            let close = match *delimiter {
+                "/*" => "*/",
+                "<!--" => "-->",
+                "{-" => "-}",
+                "(*" => "*)",
+                quote => quote,
+            };
+            if !trimmed[delimiter.len()..].contains(close) {
+                open = Some(close);
+            }
+            true
+        } else {
+            COMMENT_PREFIXES
+                .iter()
+                .any(|prefix| trimmed.starts_with(prefix))
+        };
+
+        if !in_header {
+            break;
+        }
+
+        let entry = block.get_or_insert_with(|| (line_num, line_num, String::new()));
+        entry.1 = line_num;
+        entry.2.push_str(trimmed);
+        entry.2.push('\n');
+    }
+    flush(&mut block);
+
+    blocks
+}
+
+/// Flag units that start inside a license header block
+pub fn mark_license_headers(content: &str, units: &mut [TranslatableUnit]) {
+    let blocks = license_header_lines(content);
+    if blocks.is_empty() {
+        return;
+    }
+
+    for unit in units {
+        if blocks.iter().any(|block| block.contains(&unit.line_number)) {
+            match unit.metadata.as_mut().and_then(|m| m.as_object_mut()) {
+                Some(metadata) => {
+                    metadata.insert(LICENSE_HEADER_KEY.to_string(), true.into());
+                }
+                None => unit.metadata = Some(serde_json::json!({ LICENSE_HEADER_KEY: true })),
+            }
+        }
+    }
+}
+
+/// Check if a unit is part of a license header
+pub fn is_license_header(unit: &TranslatableUnit) -> bool {
+    unit.metadata
+        .as_ref()
+        .and_then(|m| m.get(LICENSE_HEADER_KEY))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use langlint_core::UnitType;
+
+    #[test]
+    fn test_is_license_text() {
+        assert!(is_license_text("SPDX-License-Identifier: MIT"));
+        assert!(is_license_text("Copyright 2024 Acme Inc."));
+        assert!(is_license_text("版权所有 (c) 2024 某公司"));
+        assert!(is_license_text(
+            "Licensed under the Apache License, Version 2.0"
+        ));
+        assert!(!is_license_text("计算两个数的和"));
+        assert!(!is_license_text("Check the license key before use"));
+    }
+
+    #[test]
+    fn test_license_header_lines() {
+        let content = "#!/usr/bin/env python\n# 版权所有 (c) 2024 某公司\n# 保留所有权利。\n\n# 这个模块处理用户数据\nimport os\n# Copyright notice later is not a header\n";
+        assert_eq!(license_header_lines(content), vec![2..=3]);
+    }
+
+    #[test]
+    fn test_license_header_block_comment() {
+        let content = "/*\n * SPDX-License-Identifier: Apache-2.0\n * 版权所有 某公司\n */\n\n// 主函数\nfn main() {}\n";
+        assert_eq!(license_header_lines(content), vec![1..=4]);
+    }
+
+    #[test]
+    fn test_no_header() {
+        assert!(license_header_lines("import os\n# 版权所有\n").is_empty());
+        assert!(license_header_lines("# 普通注释\n\nx = 1\n").is_empty());
+    }
+
+    #[test]
+    fn test_mark_license_headers() {
+        let content = "# 版权所有 (c) 2024 某公司\n\n# 计算总和\nx = 1\n";
+        let mut units = vec![
+            TranslatableUnit::new(
+                "版权所有 (c) 2024 某公司".to_string(),
+                UnitType::Comment,
+                1,
+                1,
+            ),
+            TranslatableUnit::new("计算总和".to_string(), UnitType::Comment, 3, 1)
+                .with_metadata(serde_json::json!({ "quote_style": "#" })),
+        ];
+        mark_license_headers(content, &mut units);
+
+        assert!(is_license_header(&units[0]));
+        assert!(!is_license_header(&units[1]));
+    }
+}
@@ -0,0 +1,820 @@
+//! i18n resource file parser (`locales/en.json`, `config/locales/fr.yml`)
+//!
+//! Every string value of a JSON or YAML locale file becomes a
+//! `StringLiteral` unit whose context is its dotted key path
+//! (`home.title`, `errors[0]`). ICU MessageFormat `plural`, `select` and
+//! `selectordinal` arguments are split out, so each branch's text is a unit
+//! of its own and the syntax around it is never sent; simple arguments
+//! (`{name}`, `{{count}}`, `%{user}`) stay in the text and are masked by
+//! the pipeline. Only the translated values are rewritten, so key order,
+//! comments, quoting and indentation are kept.
+
+use anyhow::{Context, Result};
+use langlint_core::{ParseResult, TranslatableUnit, UnitType};
+use regex::Regex;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::Parser;
+
+/// Unit metadata key locating a unit's value and ICU segment
+pub const LOCALE_KEY: &str = "locale";
+
+/// Directories holding locale files
+const LOCALE_DIRS: &[&str] = &[
+    "locale",
+    "locales",
+    "i18n",
+    "l10n",
+    "lang",
+    "langs",
+    "languages",
+    "translations",
+    "messages",
+];
+
+/// ISO 639-1 language codes, for locale files named after their language
+const LANGUAGE_CODES: &str = "aa ab ae af ak am an ar as av ay az ba be bg bh bi bm bn bo br bs \
+    ca ce ch co cr cs cu cv cy da de dv dz ee el en eo es et eu fa ff fi fj fo fr fy ga gd gl gn \
+    gu gv ha he hi ho hr ht hu hy hz ia id ie ig ii ik io is it iu ja jv ka kg ki kj kk kl km kn \
+    ko kr ks ku kv kw ky la lb lg li ln lo lt lu lv mg mh mi mk ml mn mr ms mt my na nb nd ne ng \
+    nl nn no nr nv ny oc oj om or os pa pi pl ps pt qu rm rn ro ru rw sa sc sd se sg si sk sl sm \
+    sn so sq sr ss st su sv sw ta te tg th ti tk tl tn to tr ts tt tw ty ug uk ur uz ve vi vo wa \
+    wo xh yi yo za zh zu";
+
+/// Parser for JSON and YAML locale files
+pub struct LocaleParser;
+
+/// How a string value is written in the file
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Style {
+    Json,
+    Plain,
+    SingleQuoted,
+    DoubleQuoted,
+    /// `|` or `>` block scalar, its lines indented by `indent`
+    Block {
+        indent: String,
+    },
+}
+
+/// A string value: its key path, where it is written and its text
+#[derive(Debug, Clone)]
+struct Leaf {
+    key: String,
+    line: usize,
+    /// Byte range of the written value (quotes included)
+    span: Range<usize>,
+    value: String,
+    style: Style,
+}
+
+impl LocaleParser {
+    /// Create a new locale file parser
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Check if a path looks like a JSON or YAML locale file: in a
+    /// `locales`-like directory, or named after a language (`zh-CN.json`,
+    /// `messages.fr.yaml`)
+    pub fn is_locale_path(path: &str) -> bool {
+        let path = Path::new(path);
+        let is_resource = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| matches!(ext.to_lowercase().as_str(), "json" | "yml" | "yaml"));
+        if !is_resource {
+            return false;
+        }
+        let in_locale_dir = path.parent().is_some_and(|parent| {
+            parent.components().any(|component| {
+                let name = component.as_os_str().to_string_lossy().to_lowercase();
+                LOCALE_DIRS.contains(&name.as_str())
+            })
+        });
+        let language = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.rsplit('.').next())
+            .unwrap_or("");
+        in_locale_dir || is_language_tag(language)
+    }
+}
+
+impl Default for LocaleParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Check if `tag` is a language tag with an ISO 639-1 language: `en`,
+/// `zh-CN`, `pt_BR`, `zh-Hans`
+pub fn is_language_tag(tag: &str) -> bool {
+    let mut parts = tag.split(['-', '_']);
+    let language = parts.next().unwrap_or("");
+    language.len() == 2
+        && LANGUAGE_CODES
+            .split_whitespace()
+            .any(|code| code == language)
+        && parts.all(|part| {
+            (2..=4).contains(&part.len()) && part.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+}
+
+/// Append `name` to a dotted key path
+fn child_key(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", parent, name)
+    }
+}
+
+/// 1-based line of a byte offset
+fn line_of(text: &str, offset: usize) -> usize {
+    text[..offset].matches('\n').count() + 1
+}
+
+/// The string values of a JSON document
+fn json_leaves(text: &str) -> Result<Vec<Leaf>> {
+    serde_json::from_str::<serde_json::Value>(text).context("Invalid JSON")?;
+    let mut scanner = JsonScanner {
+        text,
+        pos: 0,
+        leaves: Vec::new(),
+    };
+    scanner.value("");
+    Ok(scanner.leaves)
+}
+
+/// Walks a document already known to be valid JSON
+struct JsonScanner<'a> {
+    text: &'a str,
+    pos: usize,
+    leaves: Vec<Leaf>,
+}
+
+impl JsonScanner<'_> {
+    fn peek(&self) -> u8 {
+        self.text.as_bytes().get(self.pos).copied().unwrap_or(0)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    /// Range of the string starting at the cursor, quotes included
+    fn string(&mut self) -> Range<usize> {
+        let start = self.pos;
+        self.pos += 1;
+        while self.pos < self.text.len() && self.peek() != b'"' {
+            self.pos += if self.peek() == b'\\' { 2 } else { 1 };
+        }
+        self.pos += 1;
+        start..self.pos
+    }
+
+    fn value(&mut self, key: &str) {
+        self.skip_whitespace();
+        match self.peek() {
+            b'{' => {
+                self.pos += 1;
+                loop {
+                    self.skip_whitespace();
+                    if self.peek() != b'"' {
+                        self.pos += 1;
+                        break;
+                    }
+                    let name = self.string();
+                    let name: String = serde_json::from_str(&self.text[name]).unwrap_or_default();
+                    self.skip_whitespace();
+                    self.pos += 1;
+                    self.value(&child_key(key, &name));
+                    self.skip_whitespace();
+                    if self.peek() == b',' {
+                        self.pos += 1;
+                    }
+                }
+            }
+            b'[' => {
+                self.pos += 1;
+                let mut index = 0;
+                loop {
+                    self.skip_whitespace();
+                    if self.peek() == b']' || self.pos >= self.text.len() {
+                        self.pos += 1;
+                        break;
+                    }
+                    self.value(&format!("{}[{}]", key, index));
+                    index += 1;
+                    self.skip_whitespace();
+                    if self.peek() == b',' {
+                        self.pos += 1;
+                    }
+                }
+            }
+            b'"' => {
+                let span = self.string();
+                let value = serde_json::from_str(&self.text[span.clone()]).unwrap_or_default();
+                self.leaves.push(Leaf {
+                    key: key.to_string(),
+                    line: line_of(self.text, span.start),
+                    span,
+                    value,
+                    style: Style::Json,
+                });
+            }
+            _ => {
+                while !matches!(self.peek(), b',' | b'}' | b']' | 0) {
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+}
+
+/// The string values of a YAML document written in block style
+///
+/// Flow collections, anchors, aliases, tags and plain scalars continued on
+/// the next lines are left alone.
+fn yaml_leaves(text: &str) -> Vec<Leaf> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\n', '\r']);
+        lines.push((offset, content));
+        offset += line.len();
+    }
+    let indent_of = |line: &str| line.len() - line.trim_start_matches(' ').len();
+
+    let mut leaves = Vec::new();
+    // Key paths of the enclosing mappings and list items, by indentation
+    let mut parents: Vec<(usize, String)> = Vec::new();
+    let mut item_counts: std::collections::HashMap<String, usize> = Default::default();
+    let mut i = 0;
+    while i < lines.len() {
+        let (line_start, line) = lines[i];
+        i += 1;
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed == "---" || trimmed == "..." {
+            continue;
+        }
+        let indent = indent_of(line);
+        while parents.last().is_some_and(|(level, _)| *level >= indent) {
+            parents.pop();
+        }
+        let mut parent = parents.last().map_or(String::new(), |(_, key)| key.clone());
+
+        // List items, possibly holding a mapping
+        let mut rest = trimmed;
+        let mut column = indent;
+        while rest == "-" || rest.starts_with("- ") {
+            let count = item_counts.entry(parent.clone()).or_default();
+            let item = format!("{}[{}]", parent, count);
+            *count += 1;
+            let after = rest[1..].trim_start();
+            column += rest.len() - after.len();
+            parents.push((column - (rest.len() - after.len()), item.clone()));
+            parent = item;
+            rest = after;
+        }
+        if rest.is_empty() {
+            continue;
+        }
+
+        let (key, value) = match mapping_entry(rest) {
+            Some((name, value)) => (child_key(&parent, &name), value),
+            // A list item's scalar
+            None if rest.len() < trimmed.len() => {
+                parents.pop();
+                (parent.clone(), rest)
+            }
+            None => continue,
+        };
+        let value_start = line_start + line.len() - value.len();
+        let value = value.trim_end();
+
+        if value.is_empty() || value.starts_with('#') {
+            parents.push((column, key));
+            continue;
+        }
+        if value.starts_with('|') || value.starts_with('>') {
+            // Block scalar: the following lines indented deeper than the key
+            let body_start = i;
+            while i < lines.len()
+                && (lines[i].1.trim().is_empty() || indent_of(lines[i].1) > column)
+            {
+                i += 1;
+            }
+            let Some(last) = (body_start..i)
+                .rev()
+                .find(|&j| !lines[j].1.trim().is_empty())
+            else {
+                continue;
+            };
+            let body_indent = indent_of(
+                lines[body_start..=last]
+                    .iter()
+                    .find(|(_, l)| !l.trim().is_empty())
+                    .map_or("", |(_, l)| l),
+            );
+            let value = lines[body_start..=last]
+                .iter()
+                .map(|(_, l)| l.get(body_indent..).unwrap_or("").trim_end())
+                .collect::<Vec<_>>()
+                .join("\n");
+            leaves.push(Leaf {
+                key,
+                line: body_start + 1,
+                span: lines[body_start].0..lines[last].0 + lines[last].1.len(),
+                value,
+                style: Style::Block {
+                    indent: " ".repeat(body_indent),
+                },
+            });
+            continue;
+        }
+
+        let scalar = match value.as_bytes()[0] {
+            b'"' => quoted_end(value, '"').and_then(|end| {
+                let decoded = serde_json::from_str::<String>(&value[..end]).ok()?;
+                Some((end, decoded, Style::DoubleQuoted))
+            }),
+            b'\'' => quoted_end(value, '\'').map(|end| {
+                let decoded = value[1..end - 1].replace("''", "'");
+                (end, decoded, Style::SingleQuoted)
+            }),
+            b'&' | b'*' | b'!' | b'[' | b'{' | b'@' | b'`' | b'%' => None,
+            _ => {
+                let end = value.find(" #").unwrap_or(value.len());
+                let plain = value[..end].trim_end();
+                // Continued on the next line, or not a string
+                let continued = lines.get(i).is_some_and(|(_, next)| {
+                    !next.trim().is_empty()
+                        && indent_of(next) > column
+                        && !next.trim_start().starts_with('#')
+                });
+                (!continued && !is_plain_non_string(plain))
+                    .then(|| (plain.len(), plain.to_string(), Style::Plain))
+            }
+        };
+        if let Some((end, value, style)) = scalar {
+            leaves.push(Leaf {
+                key,
+                line: i,
+                span: value_start..value_start + end,
+                value,
+                style,
+            });
+        }
+    }
+    leaves
+}
+
+/// Key and value of a `key: value` line
+fn mapping_entry(text: &str) -> Option<(String, &str)> {
+    let (name, after) = match text.as_bytes().first()? {
+        quote @ (b'"' | b'\'') => {
+            let end = quoted_end(text, *quote as char)?;
+            let name = match quote {
+                b'"' => serde_json::from_str::<String>(&text[..end]).ok()?,
+                _ => text[1..end - 1].replace("''", "'"),
+            };
+            (name, text[end..].trim_start())
+        }
+        _ => {
+            let colon = text
+                .match_indices(':')
+                .map(|(i, _)| i)
+                .find(|&i| text[i + 1..].is_empty() || text[i + 1..].starts_with([' ', '\t']))?;
+            (text[..colon].trim_end().to_string(), &text[colon..])
+        }
+    };
+    let value = after.strip_prefix(':')?;
+    (value.is_empty() || value.starts_with([' ', '\t'])).then(|| (name, value.trim_start()))
+}
+
+/// Offset after the quote closing a string starting with `quote`
+fn quoted_end(text: &str, quote: char) -> Option<usize> {
+    let mut chars = text.char_indices().skip(1).peekable();
+    while let Some((i, c)) = chars.next() {
+        if quote == '"' && c == '\\' {
+            chars.next();
+        } else if c == quote {
+            // `''` is an escaped quote in single-quoted strings
+            if quote == '\'' && chars.peek().is_some_and(|&(_, next)| next == '\'') {
+                chars.next();
+                continue;
+            }
+            return Some(i + 1);
+        }
+    }
+    None
+}
+
+/// A plain scalar read as a boolean, null or number
+fn is_plain_non_string(text: &str) -> bool {
+    matches!(
+        text.to_lowercase().as_str(),
+        "true" | "false" | "yes" | "no" | "on" | "off" | "null" | "~" | ""
+    ) || text.parse::<f64>().is_ok()
+}
+
+/// Check if `text` can be written as a plain YAML scalar
+fn is_plain_safe(text: &str) -> bool {
+    !text.is_empty()
+        && text.trim() == text
+        && !text.contains(['\n', '\t'])
+        && !text.starts_with([
+            '-', '?', ':', ',', '[', ']', '{', '}', '#', '&', '*', '!', '|', '>', '\'', '"', '%',
+            '@', '`',
+        ])
+        && !text.contains(": ")
+        && !text.contains(" #")
+        && !text.ends_with(':')
+        && !is_plain_non_string(text)
+}
+
+/// `value` written in `style`
+fn encode(value: &str, style: &Style) -> String {
+    let double_quoted = || serde_json::to_string(value).unwrap_or_default();
+    match style {
+        Style::Json | Style::DoubleQuoted => double_quoted(),
+        Style::SingleQuoted if !value.contains('\n') => format!("'{}'", value.replace('\'', "''")),
+        Style::Plain if is_plain_safe(value) => value.to_string(),
+        Style::Block { indent } => value
+            .lines()
+            .map(|line| match line.is_empty() {
+                true => String::new(),
+                false => format!("{}{}", indent, line),
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => double_quoted(),
+    }
+}
+
+/// Byte ranges of a message's text outside ICU `plural`/`select` syntax,
+/// trimmed, including the text of every branch
+fn icu_segments(message: &str) -> Vec<Range<usize>> {
+    let mut segments = Vec::new();
+    let end = icu_message(message.as_bytes(), 0, false, &mut segments);
+    if end < message.len() {
+        // Unbalanced braces: not ICU, the whole text is one segment
+        segments.clear();
+        segments.push(0..message.len());
+    }
+    segments
+        .into_iter()
+        .filter_map(|range| {
+            let text = &message[range.clone()];
+            let start = range.start + (text.len() - text.trim_start().len());
+            let end = range.start + text.trim_end().len();
+            (start < end).then_some(start..end)
+        })
+        .collect()
+}
+
+/// Parse a message from `pos` up to the `}` closing a branch (when
+/// `nested`) or the end, returning where it stopped
+fn icu_message(
+    bytes: &[u8],
+    mut pos: usize,
+    nested: bool,
+    segments: &mut Vec<Range<usize>>,
+) -> usize {
+    let mut start = pos;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'{' => match icu_argument(bytes, pos, segments) {
+                Some((end, true)) => {
+                    segments.push(start..pos);
+                    pos = end;
+                    start = end;
+                }
+                Some((end, false)) => pos = end,
+                None => return pos,
+            },
+            b'}' if nested => break,
+            b'}' => return pos,
+            _ => pos += 1,
+        }
+    }
+    segments.push(start..pos);
+    pos
+}
+
+/// Parse the argument starting at `{`, returning where it ends and whether
+/// it is a `plural`/`select` argument whose branches were parsed
+fn icu_argument(
+    bytes: &[u8],
+    open: usize,
+    segments: &mut Vec<Range<usize>>,
+) -> Option<(usize, bool)> {
+    let matching = |from: usize| {
+        let mut depth = 0;
+        for (i, &b) in bytes.iter().enumerate().skip(from) {
+            match b {
+                b'{' => depth += 1,
+                b'}' if depth == 1 => return Some(i + 1),
+                b'}' => depth -= 1,
+                _ => {}
+            }
+        }
+        None
+    };
+    let end = matching(open)?;
+    let inner = std::str::from_utf8(&bytes[open + 1..end - 1]).ok()?;
+    let mut parts = inner.splitn(3, ',');
+    let (_, kind) = (parts.next()?, parts.next().map(str::trim));
+    if !matches!(kind, Some("plural" | "select" | "selectordinal")) {
+        return Some((end, false));
+    }
+
+    // `selector {branch}` pairs after the second comma
+    let mut pos = open + 1 + inner.find(',')? + 1;
+    pos += inner[pos - open - 1..].find(',')? + 1;
+    loop {
+        while pos < end && bytes[pos] != b'{' && bytes[pos] != b'}' {
+            pos += 1;
+        }
+        if pos >= end - 1 {
+            return Some((end, true));
+        }
+        let close = icu_message(bytes, pos + 1, true, segments);
+        if bytes.get(close) != Some(&b'}') {
+            return None;
+        }
+        pos = close + 1;
+    }
+}
+
+/// Check if a segment has a word outside its placeholders
+fn is_translatable(text: &str) -> bool {
+    static PLACEHOLDER: OnceLock<Regex> = OnceLock::new();
+    let placeholder = PLACEHOLDER.get_or_init(|| Regex::new(r"%?\{\{?[^{}]*\}?\}").unwrap());
+    placeholder
+        .replace_all(text, " ")
+        .split(|c: char| !c.is_alphabetic())
+        .any(|word| word.chars().count() >= 2)
+}
+
+/// The string values of a locale file
+fn leaves(content: &str, path: &str) -> Result<Vec<Leaf>> {
+    let lower = path.to_lowercase();
+    if lower.ends_with(".json") {
+        json_leaves(content)
+    } else {
+        Ok(yaml_leaves(content))
+    }
+}
+
+impl Parser for LocaleParser {
+    fn name(&self) -> &'static str {
+        "Locale"
+    }
+
+    fn supported_extensions(&self) -> &'static [&'static str] {
+        &[".json", ".yml", ".yaml"]
+    }
+
+    fn can_parse(&self, path: &str, _content: Option<&str>) -> bool {
+        Self::is_locale_path(path)
+    }
+
+    fn extract_units(&self, content: &str, path: &str) -> Result<ParseResult> {
+        let mut units = Vec::new();
+        for leaf in leaves(content, path)? {
+            for (segment, range) in icu_segments(&leaf.value).into_iter().enumerate() {
+                let text = &leaf.value[range];
+                if !is_translatable(text) {
+                    continue;
+                }
+                let mut unit = TranslatableUnit::new(
+                    text.to_string(),
+                    UnitType::StringLiteral,
+                    leaf.line as u32,
+                    1,
+                )
+                .with_context(leaf.key.clone())
+                .with_metadata(serde_json::json!({
+                    LOCALE_KEY: { "start": leaf.span.start, "segment": segment }
+                }));
+                unit.detect_language();
+                units.push(unit);
+            }
+        }
+
+        let file_type = if path.to_lowercase().ends_with(".json") {
+            "locale_json"
+        } else {
+            "locale_yaml"
+        };
+        Ok(ParseResult::new(file_type, "utf-8", content.lines().count() as u32).with_units(units))
+    }
+
+    fn reconstruct(
+        &self,
+        original: &str,
+        units: &[TranslatableUnit],
+        path: &str,
+    ) -> Result<String> {
+        let location = |unit: &TranslatableUnit| {
+            let locale = unit.metadata.as_ref()?.get(LOCALE_KEY)?;
+            Some((
+                locale.get("start")?.as_u64()? as usize,
+                locale.get("segment")?.as_u64()? as usize,
+            ))
+        };
+
+        let mut result = original.to_string();
+        for leaf in leaves(original, path)?.iter().rev() {
+            let segments = icu_segments(&leaf.value);
+            let mut value = leaf.value.clone();
+            let mut changed = false;
+            for (segment, range) in segments.iter().enumerate().rev() {
+                let translated = units
+                    .iter()
+                    .find(|unit| location(unit) == Some((leaf.span.start, segment)))
+                    .map(|unit| unit.content.as_str());
+                if let Some(text) = translated.filter(|text| *text != &leaf.value[range.clone()]) {
+                    value.replace_range(range.clone(), text);
+                    changed = true;
+                }
+            }
+            if changed {
+                result.replace_range(leaf.span.clone(), &encode(&value, &leaf.style));
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JSON: &str = r#"{
+  "home": {
+    "title": "欢迎回来，{name}！",
+    "count": 3,
+    "files": "{count, plural, =0 {没有文件} one {# 个文件} other {# 个文件，共 {size, number} 字节}}"
+  },
+  "menu": ["打开", "保存 \"草稿\""],
+  "empty": ""
+}
+"#;
+
+    const YAML: &str = "# 中文界面
+zh-CN:
+  home:
+    title: 欢迎回来，%{name}！
+    quoted: \"第一行\\n第二行\"
+    single: '它''s 很好' # 注释
+  errors:
+    - 网络错误
+    - code: 404
+      message: 页面不存在
+  help: |
+    第一段帮助。
+
+    第二段帮助。
+  enabled: true
+";
+
+    fn contents(units: &[TranslatableUnit]) -> Vec<(&str, &str)> {
+        units
+            .iter()
+            .map(|unit| (unit.context.as_deref().unwrap_or(""), unit.content.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_locale_paths() {
+        assert!(LocaleParser::is_locale_path("src/locales/en.json"));
+        assert!(LocaleParser::is_locale_path("config/locales/models.yml"));
+        assert!(LocaleParser::is_locale_path("web/zh-CN.json"));
+        assert!(LocaleParser::is_locale_path(
+            "translations/messages.fr.yaml"
+        ));
+        assert!(LocaleParser::is_locale_path("i18n/pt_BR.yaml"));
+        assert!(!LocaleParser::is_locale_path("package.json"));
+        assert!(!LocaleParser::is_locale_path(".github/workflows/ci.yml"));
+        assert!(!LocaleParser::is_locale_path("locales/en.txt"));
+    }
+
+    #[test]
+    fn test_extract_units() {
+        let parser = LocaleParser::new();
+        let json = parser.extract_units(JSON, "locales/zh.json").unwrap();
+        assert_eq!(
+            contents(&json.units),
+            vec![
+                ("home.title", "欢迎回来，{name}！"),
+                ("home.files", "没有文件"),
+                ("home.files", "# 个文件"),
+                ("home.files", "# 个文件，共 {size, number} 字节"),
+                ("menu[0]", "打开"),
+                ("menu[1]", "保存 \"草稿\""),
+            ]
+        );
+        assert_eq!(json.units[0].line_number, 3);
+
+        let yaml = parser
+            .extract_units(YAML, "config/locales/zh-CN.yml")
+            .unwrap();
+        assert_eq!(
+            contents(&yaml.units),
+            vec![
+                ("zh-CN.home.title", "欢迎回来，%{name}！"),
+                ("zh-CN.home.quoted", "第一行\n第二行"),
+                ("zh-CN.home.single", "它's 很好"),
+                ("zh-CN.errors[0]", "网络错误"),
+                ("zh-CN.errors[1].message", "页面不存在"),
+                ("zh-CN.help", "第一段帮助。\n\n第二段帮助。"),
+            ]
+        );
+        assert!(parser.extract_units("{\"a\": ", "locales/en.json").is_err());
+    }
+
+    #[test]
+    fn test_reconstruct() {
+        let parser = LocaleParser::new();
+        let translate = |content: &str, path: &str, translations: &[&str]| {
+            let mut units = parser.extract_units(content, path).unwrap().units;
+            for (unit, translation) in units.iter_mut().zip(translations) {
+                unit.content = translation.to_string();
+            }
+            parser.reconstruct(content, &units, path).unwrap()
+        };
+
+        let json = translate(
+            JSON,
+            "locales/zh.json",
+            &[
+                "Welcome back, {name}!",
+                "No files",
+                "# file",
+                "# files, {size, number} bytes",
+                "Open",
+                "Save \"draft\"",
+            ],
+        );
+        assert_eq!(
+            json,
+            JSON.replace("欢迎回来，{name}！", "Welcome back, {name}!")
+                .replace(
+                    "=0 {没有文件} one {# 个文件} other {# 个文件，共 {size, number} 字节}",
+                    "=0 {No files} one {# file} other {# files, {size, number} bytes}"
+                )
+                .replace(
+                    "[\"打开\", \"保存 \\\"草稿\\\"\"]",
+                    "[\"Open\", \"Save \\\"draft\\\"\"]"
+                )
+        );
+        assert!(serde_json::from_str::<serde_json::Value>(&json).is_ok());
+
+        let yaml = translate(
+            YAML,
+            "config/locales/zh-CN.yml",
+            &[
+                "Welcome back, %{name}!",
+                "Line one\nline two",
+                "It's fine",
+                "Network error",
+                "Not found: the page is missing",
+                "First help paragraph.\n\nSecond help paragraph.",
+            ],
+        );
+        assert_eq!(
+            yaml,
+            "# 中文界面
+zh-CN:
+  home:
+    title: Welcome back, %{name}!
+    quoted: \"Line one\\nline two\"
+    single: 'It''s fine' # 注释
+  errors:
+    - Network error
+    - code: 404
+      message: \"Not found: the page is missing\"
+  help: |
+    First help paragraph.
+
+    Second help paragraph.
+  enabled: true
+"
+        );
+
+        // Untouched units give back the original
+        let units = parser.extract_units(YAML, "locales/zh.yml").unwrap().units;
+        assert_eq!(
+            parser.reconstruct(YAML, &units, "locales/zh.yml").unwrap(),
+            YAML
+        );
+    }
+}
@@ -0,0 +1,570 @@
+//! Markdown parser (.md files)
+//!
+//! Headings, paragraphs, list items, block quotes and tables become
+//! `TextNode` units; the markup around them (heading hashes, list markers,
+//! `>` quote prefixes and list indentation) stays in the file and is put
+//! back around the translation. Front matter, fenced and indented code,
+//! `$$` math, HTML blocks and link reference definitions are never
+//! extracted. Inline code, links and images inside the text are masked by
+//! the pipeline while translating.
+
+use anyhow::Result;
+use langlint_core::types::detect_language;
+use langlint_core::{ParseResult, Priority, TranslatableUnit, UnitType};
+use regex::Regex;
+
+use crate::Parser;
+
+/// Unit metadata key holding the markup around a unit's lines
+pub const MARKDOWN_KEY: &str = "markdown";
+
+/// Parser for Markdown files
+pub struct MarkdownParser {
+    heading: Regex,
+    list_item: Regex,
+    table_delimiter: Regex,
+    reference: Regex,
+    markup: Regex,
+}
+
+/// Lines of a unit with the markup around them
+struct Block {
+    start: usize,
+    /// Line after the unit's last line
+    end: usize,
+    lines: Vec<String>,
+    /// Markup before the first line (`## `, `- [ ] `, `> `)
+    prefix: String,
+    /// Markup before each following line
+    continuation: String,
+    /// Markup after the first line (closing `##` of a heading)
+    suffix: String,
+    priority: Priority,
+}
+
+impl MarkdownParser {
+    /// Create a new Markdown parser
+    pub fn new() -> Self {
+        Self {
+            heading: Regex::new(r"^(\s{0,3}#{1,6}\s+)(.*?)(\s+#+)?\s*$").unwrap(),
+            list_item: Regex::new(r"^(\s*(?:[-*+]|\d{1,9}[.)])\s+(?:\[[ xX]\]\s+)?)(.*)$").unwrap(),
+            table_delimiter: Regex::new(r"^\s*\|?\s*:?-+:?\s*(\|\s*:?-+:?\s*)*\|?\s*$").unwrap(),
+            reference: Regex::new(r"^\s{0,3}\[[^\]]+\]:\s*\S").unwrap(),
+            markup: Regex::new(r"`[^`]*`|!\[[^\]]*\]\([^)]*\)|\]\([^)]*\)|<[^>]*>|https?://\S+")
+                .unwrap(),
+        }
+    }
+
+    /// Check if a block's text is worth translating: prose outside code,
+    /// URLs and images, with non-ASCII characters
+    fn is_translatable(&self, text: &str) -> bool {
+        let prose = self.markup.replace_all(text, " ");
+        prose.chars().filter(|c| c.is_alphabetic()).count() >= 2 && !prose.is_ascii()
+    }
+
+    /// Split a document into the blocks holding its text
+    fn blocks(&self, lines: &[&str]) -> Vec<Block> {
+        let mut blocks = Vec::new();
+        let mut i = front_matter_end(lines);
+        // Inside a list, indented lines continue an item rather than being code
+        let mut in_list = false;
+
+        while i < lines.len() {
+            let line = lines[i];
+            let trimmed = line.trim_start();
+            let indent = indent_width(line);
+
+            if trimmed.is_empty() {
+                i += 1;
+                continue;
+            }
+            if indent >= 4 && !in_list {
+                // Indented code block
+                while i < lines.len() && (lines[i].trim().is_empty() || indent_width(lines[i]) >= 4)
+                {
+                    i += 1;
+                }
+                continue;
+            }
+            if indent == 0 && !self.list_item.is_match(line) {
+                in_list = false;
+            }
+
+            if let Some(end) = self.skipped_block_end(lines, i) {
+                i = end;
+                continue;
+            }
+
+            if let Some(captures) = self.heading.captures(line) {
+                blocks.push(Block {
+                    start: i,
+                    end: i + 1,
+                    lines: vec![captures[2].to_string()],
+                    prefix: captures[1].to_string(),
+                    continuation: String::new(),
+                    suffix: captures.get(3).map_or("", |m| m.as_str()).to_string(),
+                    priority: Priority::High,
+                });
+                i += 1;
+                continue;
+            }
+
+            if trimmed.starts_with('>') {
+                let start = i;
+                let mut text = Vec::new();
+                while i < lines.len() && lines[i].trim_start().starts_with('>') {
+                    let inner = lines[i].trim_start()[1..].strip_prefix(' ');
+                    text.push(inner.unwrap_or(&lines[i].trim_start()[1..]).to_string());
+                    i += 1;
+                }
+                let prefix = format!("{}> ", &line[..line.len() - trimmed.len()]);
+                blocks.push(Block {
+                    start,
+                    end: i,
+                    lines: text,
+                    prefix: prefix.clone(),
+                    continuation: prefix,
+                    suffix: String::new(),
+                    priority: Priority::Medium,
+                });
+                continue;
+            }
+
+            if line.contains('|')
+                && lines
+                    .get(i + 1)
+                    .is_some_and(|next| self.table_delimiter.is_match(next))
+            {
+                let start = i;
+                while i < lines.len() && lines[i].contains('|') {
+                    i += 1;
+                }
+                blocks.push(Block {
+                    start,
+                    end: i,
+                    lines: lines[start..i].iter().map(|l| l.to_string()).collect(),
+                    prefix: String::new(),
+                    continuation: String::new(),
+                    suffix: String::new(),
+                    priority: Priority::Medium,
+                });
+                continue;
+            }
+
+            let (prefix, first, continuation) = match self.list_item.captures(line) {
+                Some(captures) => {
+                    in_list = true;
+                    let marker = &captures[1];
+                    (
+                        marker.to_string(),
+                        captures[2].to_string(),
+                        " ".repeat(marker.chars().count()),
+                    )
+                }
+                None => {
+                    let indent = line[..line.len() - trimmed.len()].to_string();
+                    (indent.clone(), trimmed.to_string(), indent)
+                }
+            };
+
+            // The item's or paragraph's lines, up to a blank line or another block
+            let start = i;
+            let mut text = vec![first];
+            i += 1;
+            let mut priority = Priority::Medium;
+            while i < lines.len() {
+                let next = lines[i];
+                if is_setext_underline(next) {
+                    if prefix.trim().is_empty() {
+                        // The paragraph is a heading
+                        priority = Priority::High;
+                    }
+                    break;
+                }
+                if next.trim().is_empty()
+                    || self.list_item.is_match(next)
+                    || self.heading.is_match(next)
+                    || next.trim_start().starts_with('>')
+                    || self.skipped_block_end(lines, i).is_some()
+                {
+                    break;
+                }
+                text.push(next.trim_start().to_string());
+                i += 1;
+            }
+            let end = i;
+            if priority == Priority::High {
+                i += 1;
+            }
+            blocks.push(Block {
+                start,
+                end,
+                lines: text,
+                prefix,
+                continuation,
+                suffix: String::new(),
+                priority,
+            });
+        }
+
+        blocks
+    }
+
+    /// End of a block kept verbatim starting at line `i`: a code fence,
+    /// `$$` math, HTML, a link reference definition or a thematic break
+    fn skipped_block_end(&self, lines: &[&str], i: usize) -> Option<usize> {
+        let trimmed = lines[i].trim_start();
+        let closing = |from: usize, is_end: &dyn Fn(&str) -> bool| {
+            (from..lines.len())
+                .find(|&j| is_end(lines[j]))
+                .map_or(lines.len(), |j| j + 1)
+        };
+
+        if let Some(fence) = ["```", "~~~"].iter().find(|f| trimmed.starts_with(**f)) {
+            let marker = fence.chars().next().unwrap_or('`');
+            let width = trimmed.chars().take_while(|&c| c == marker).count();
+            return Some(closing(i + 1, &|line| {
+                let line = line.trim();
+                line.chars().take_while(|&c| c == marker).count() >= width
+                    && line.chars().all(|c| c == marker)
+            }));
+        }
+        if trimmed.starts_with("$$") {
+            if trimmed.len() > 2 && trimmed.trim_end().ends_with("$$") {
+                return Some(i + 1);
+            }
+            return Some(closing(i + 1, &|line| line.contains("$$")));
+        }
+        if trimmed.starts_with("<!--") {
+            return Some(closing(i, &|line| line.contains("-->")));
+        }
+        if trimmed.starts_with('<')
+            && trimmed[1..]
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '/')
+        {
+            return Some(closing(i, &|line| line.trim().is_empty()));
+        }
+        if self.reference.is_match(lines[i]) || is_thematic_break(trimmed) {
+            return Some(i + 1);
+        }
+        None
+    }
+}
+
+impl Default for MarkdownParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Line after a leading `---` (YAML) or `+++` (TOML) front matter block
+fn front_matter_end(lines: &[&str]) -> usize {
+    let Some(marker) = lines
+        .first()
+        .map(|line| line.trim_end())
+        .filter(|line| *line == "---" || *line == "+++")
+    else {
+        return 0;
+    };
+    lines
+        .iter()
+        .skip(1)
+        .position(|line| line.trim_end() == marker)
+        .map_or(0, |p| p + 2)
+}
+
+/// Indentation of a line in columns, tabs counting four
+fn indent_width(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+/// `***`, `---` or `___`, spaces allowed
+fn is_thematic_break(trimmed: &str) -> bool {
+    let marks: Vec<char> = trimmed.chars().filter(|c| !c.is_whitespace()).collect();
+    marks.len() >= 3 && ['*', '-', '_'].contains(&marks[0]) && marks.iter().all(|&c| c == marks[0])
+}
+
+/// Underline of a setext heading (`===` or `---`)
+fn is_setext_underline(line: &str) -> bool {
+    let trimmed = line.trim();
+    !trimmed.is_empty() && (trimmed.chars().all(|c| c == '=') || trimmed.chars().all(|c| c == '-'))
+}
+
+/// Markup around a unit's lines, from its metadata
+fn markup(unit: &TranslatableUnit) -> Option<(usize, &str, &str, &str)> {
+    let markdown = unit.metadata.as_ref()?.get(MARKDOWN_KEY)?;
+    let field = |key: &str| markdown.get(key).and_then(|v| v.as_str()).unwrap_or("");
+    let end = markdown.get("end_line")?.as_u64()? as usize;
+    Some((end, field("prefix"), field("continuation"), field("suffix")))
+}
+
+impl Parser for MarkdownParser {
+    fn name(&self) -> &'static str {
+        "Markdown"
+    }
+
+    fn supported_extensions(&self) -> &'static [&'static str] {
+        &[".md", ".markdown"]
+    }
+
+    fn can_parse(&self, path: &str, _content: Option<&str>) -> bool {
+        let lower = path.to_lowercase();
+        self.supported_extensions()
+            .iter()
+            .any(|ext| lower.ends_with(ext))
+    }
+
+    fn extract_units(&self, content: &str, _path: &str) -> Result<ParseResult> {
+        let lines: Vec<&str> = content.lines().collect();
+        let units = self
+            .blocks(&lines)
+            .into_iter()
+            .filter(|block| self.is_translatable(&block.lines.join("\n")))
+            .map(|block| {
+                let mut unit = TranslatableUnit::new(
+                    block.lines.join("\n"),
+                    UnitType::TextNode,
+                    (block.start + 1) as u32,
+                    (block.prefix.chars().count() + 1) as u32,
+                )
+                .with_metadata(serde_json::json!({
+                    MARKDOWN_KEY: {
+                        "end_line": block.end,
+                        "prefix": block.prefix,
+                        "continuation": block.continuation,
+                        "suffix": block.suffix,
+                    }
+                }))
+                .with_priority(block.priority);
+                // Markup such as links and roles would pass for English
+                unit.detected_language =
+                    detect_language(&self.markup.replace_all(&unit.content, " "));
+                unit
+            })
+            .collect();
+
+        Ok(ParseResult::new("markdown", "utf-8", lines.len() as u32).with_units(units))
+    }
+
+    fn reconstruct(
+        &self,
+        original: &str,
+        units: &[TranslatableUnit],
+        _path: &str,
+    ) -> Result<String> {
+        let newline = if original.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        let mut lines: Vec<String> = original.lines().map(str::to_string).collect();
+
+        let mut units: Vec<&TranslatableUnit> = units.iter().collect();
+        units.sort_by_key(|unit| std::cmp::Reverse(unit.line_number));
+        for unit in units {
+            let Some((end, prefix, continuation, suffix)) = markup(unit) else {
+                continue;
+            };
+            let start = (unit.line_number as usize).saturating_sub(1);
+            if start >= end || end > lines.len() {
+                continue;
+            }
+            let replacement: Vec<String> = unit
+                .content
+                .lines()
+                .enumerate()
+                .map(|(k, text)| {
+                    let line = match k {
+                        0 => format!("{}{}{}", prefix, text, suffix),
+                        _ => format!("{}{}", continuation, text),
+                    };
+                    // No trailing spaces from a prefix before an empty line
+                    if text.is_empty() {
+                        line.trim_end().to_string()
+                    } else {
+                        line
+                    }
+                })
+                .collect();
+            lines.splice(start..end, replacement);
+        }
+
+        let mut result = lines.join(newline);
+        if original.ends_with('\n') {
+            result.push_str(newline);
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = "---
+title: 文档
+---
+
+# 安装指南 #
+
+本项目需要 Rust 1.70 以上版本，
+请先安装 `cargo`。
+
+- 下载[源码](https://example.com/src.zip)
+- [ ] 运行测试
+  并检查输出
+
+```bash
+# 构建项目
+cargo build
+```
+
+> 注意：这是实验功能
+
+| 参数 | 说明 |
+| --- | --- |
+| `-v` | 详细输出 |
+
+快速开始
+--------
+
+[链接]: https://example.com
+<!-- 不翻译的注释 -->
+";
+
+    #[test]
+    fn test_extract_units() {
+        let parser = MarkdownParser::new();
+        assert!(parser.can_parse("docs/GUIDE.md", None));
+        assert!(!parser.can_parse("guide.txt", None));
+
+        let result = parser.extract_units(DOC, "README.md").unwrap();
+        let units: Vec<(u32, &str, Priority)> = result
+            .units
+            .iter()
+            .map(|unit| (unit.line_number, unit.content.as_str(), unit.priority))
+            .collect();
+        assert_eq!(
+            units,
+            vec![
+                (5, "安装指南", Priority::High),
+                (
+                    7,
+                    "本项目需要 Rust 1.70 以上版本，\n请先安装 `cargo`。",
+                    Priority::Medium
+                ),
+                (
+                    10,
+                    "下载[源码](https://example.com/src.zip)",
+                    Priority::Medium
+                ),
+                (11, "运行测试\n并检查输出", Priority::Medium),
+                (19, "注意：这是实验功能", Priority::Medium),
+                (
+                    21,
+                    "| 参数 | 说明 |\n| --- | --- |\n| `-v` | 详细输出 |",
+                    Priority::Medium
+                ),
+                (25, "快速开始", Priority::High),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reconstruct() {
+        let parser = MarkdownParser::new();
+        let mut units = parser.extract_units(DOC, "README.md").unwrap().units;
+        units[0].content = "Installation".to_string();
+        units[1].content = "Requires Rust 1.70 or later; install `cargo` first.".to_string();
+        units[3].content = "Run the tests\nand check the output".to_string();
+        units[4].content = "Note: experimental".to_string();
+        units[6].content = "Quick start".to_string();
+
+        let rebuilt = parser.reconstruct(DOC, &units, "README.md").unwrap();
+        assert!(rebuilt.starts_with("---\ntitle: 文档\n---\n\n# Installation #\n\n"));
+        assert!(rebuilt.contains("\nRequires Rust 1.70 or later; install `cargo` first.\n\n"));
+        assert!(rebuilt.contains("- [ ] Run the tests\n      and check the output\n"));
+        assert!(rebuilt.contains("```bash\n# 构建项目\ncargo build\n```"));
+        assert!(rebuilt.contains("> Note: experimental\n"));
+        assert!(rebuilt.contains("Quick start\n--------\n"));
+        assert!(rebuilt.ends_with("<!-- 不翻译的注释 -->\n"));
+
+        // Unchanged units give back the original
+        let units = parser.extract_units(DOC, "README.md").unwrap().units;
+        let same = parser.reconstruct(DOC, &units, "README.md").unwrap();
+        assert_eq!(same.replace("      并", "  并"), DOC);
+    }
+
+    #[test]
+    fn test_round_trip_nested_lists_quotes_and_code() {
+        let doc = "1. 第一步
+   1. 嵌套的子步骤
+   2. 另一个子步骤
+2. 第二步
+
+> 引用的第一行
+> 引用的第二行
+
+    # 缩进代码
+    不翻译
+
+~~~python
+# 波浪线围栏
+~~~
+
+$$
+E = mc^2 \\text{ 能量 }
+$$
+
+<div align=\"center\">
+  <p>HTML 块</p>
+</div>
+
+![示意图](img/arch.png)
+";
+        let parser = MarkdownParser::new();
+        let mut units = parser.extract_units(doc, "GUIDE.md").unwrap().units;
+        let contents: Vec<(u32, &str)> = units
+            .iter()
+            .map(|unit| (unit.line_number, unit.content.as_str()))
+            .collect();
+        assert_eq!(
+            contents,
+            vec![
+                (1, "第一步"),
+                (2, "嵌套的子步骤"),
+                (3, "另一个子步骤"),
+                (4, "第二步"),
+                (6, "引用的第一行\n引用的第二行"),
+            ]
+        );
+
+        let translations = [
+            "First step",
+            "A nested step",
+            "Another nested step",
+            "Second step",
+            "The first line of a quote\nand its second line",
+        ];
+        for (unit, translation) in units.iter_mut().zip(translations) {
+            unit.content = translation.to_string();
+        }
+        let rebuilt = parser.reconstruct(doc, &units, "GUIDE.md").unwrap();
+        assert_eq!(
+            rebuilt,
+            doc.replace("第一步", "First step")
+                .replace("嵌套的子步骤", "A nested step")
+                .replace("另一个子步骤", "Another nested step")
+                .replace("第二步", "Second step")
+                .replace(
+                    "引用的第一行\n> 引用的第二行",
+                    "The first line of a quote\n> and its second line"
+                )
+        );
+    }
+}
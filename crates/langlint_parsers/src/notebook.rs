@@ -5,7 +5,7 @@ use langlint_core::{ParseResult, Priority, TranslatableUnit, UnitType};
 use regex::Regex;
 use serde_json::Value;
 
-use crate::Parser;
+use crate::{pragma, Parser};
 
 /// Parser for Jupyter Notebook files (.ipynb)
 pub struct NotebookParser {
@@ -36,40 +36,49 @@ impl NotebookParser {
         };
 
         match cell_type {
-            "markdown" => {
-                // Extract text from markdown cells (excluding code blocks)
-                if !source.trim().is_empty() && !source.starts_with("```") {
-                    let priority = if source.starts_with('#') {
-                        Priority::High // Headers
-                    } else {
-                        Priority::Medium
-                    };
-
-                    units.push(
-                        TranslatableUnit::new(
-                            source.trim().to_string(),
-                            UnitType::TextNode,
-                            cell_index as u32,
-                            0,
-                        )
-                        .with_priority(priority),
-                    );
-                }
+            // Extract text from markdown cells (excluding code blocks)
+            "markdown" if !source.trim().is_empty() && !source.starts_with("```") => {
+                let priority = if source.starts_with('#') {
+                    Priority::High // Headers
+                } else {
+                    Priority::Medium
+                };
+
+                units.push(
+                    TranslatableUnit::new(
+                        source.trim().to_string(),
+                        UnitType::TextNode,
+                        cell_index as u32,
+                        0,
+                    )
+                    .with_metadata(serde_json::json!({"cell_index": cell_index}))
+                    .with_priority(priority),
+                );
             }
             "code" => {
                 // Extract comments from code cells
                 for (line_num, line) in source.lines().enumerate() {
+                    let after_hash = line.trim_start().strip_prefix('#').unwrap_or_default();
+                    if pragma::is_pragma_comment("#", after_hash) {
+                        continue;
+                    }
                     if let Some(captures) = self.comment_regex.captures(line) {
                         if let Some(comment) = captures.get(1) {
                             let comment_text = comment.as_str().trim();
                             if self.is_translatable(comment_text) {
+                                // 1-based column of the '#'
+                                let column = line.len() - line.trim_start().len() + 1;
                                 units.push(
                                     TranslatableUnit::new(
                                         comment_text.to_string(),
                                         UnitType::Comment,
                                         (cell_index * 1000 + line_num) as u32,
-                                        0,
+                                        column as u32,
                                     )
+                                    .with_metadata(serde_json::json!({
+                                        "cell_index": cell_index,
+                                        "cell_line": line_num,
+                                    }))
                                     .with_priority(Priority::Medium),
                                 );
                             }
@@ -138,6 +147,85 @@ impl NotebookParser {
     }
 }
 
+/// Locate a unit inside the notebook as (cell index, line within the cell)
+///
+/// Uses the `cell_index`/`cell_line` metadata written by `extract_units`,
+/// falling back to the encoded line number for units without metadata.
+fn unit_location(unit: &TranslatableUnit) -> (usize, Option<usize>) {
+    let meta = |key: &str| {
+        unit.metadata
+            .as_ref()
+            .and_then(|m| m.get(key))
+            .and_then(|v| v.as_u64())
+            .map(|v| v as usize)
+    };
+
+    match (meta("cell_index"), meta("cell_line")) {
+        (Some(cell), line) => (cell, line),
+        (None, _) if unit.unit_type == UnitType::Comment => {
+            let encoded = unit.line_number as usize;
+            (encoded / 1000, Some(encoded % 1000))
+        }
+        (None, _) => (unit.line_number as usize, None),
+    }
+}
+
+/// Get the source of a cell as a single string
+fn cell_source(cell: &Value) -> String {
+    match &cell["source"] {
+        Value::Array(lines) => lines
+            .iter()
+            .filter_map(|v| v.as_str())
+            .collect::<Vec<_>>()
+            .join(""),
+        Value::String(s) => s.clone(),
+        _ => String::new(),
+    }
+}
+
+/// Replace the source of a cell, keeping the original list-of-lines or string layout
+fn set_cell_source(cell: &mut Value, source: &str) {
+    cell["source"] = match cell["source"] {
+        Value::Array(_) => Value::Array(
+            source
+                .split_inclusive('\n')
+                .map(|line| Value::String(line.to_string()))
+                .collect(),
+        ),
+        _ => Value::String(source.to_string()),
+    };
+}
+
+/// Replace the comment text on one line of a code cell
+///
+/// `column` is the 1-based column of the comment's `#`. Without one (0),
+/// only a line that is a comment as a whole is replaced, so a `#` inside a
+/// string literal is never mistaken for the comment.
+fn replace_comment_line(source: &str, line_idx: usize, column: u32, translated: &str) -> String {
+    source
+        .split_inclusive('\n')
+        .enumerate()
+        .map(|(i, line)| {
+            if i != line_idx {
+                return line.to_string();
+            }
+            let body = line.trim_end_matches(['\n', '\r']);
+            let ending = &line[body.len()..];
+            let hash_pos = match column.checked_sub(1) {
+                Some(pos) => Some(pos as usize),
+                None => {
+                    let trimmed = body.trim_start();
+                    trimmed.starts_with('#').then(|| body.len() - trimmed.len())
+                }
+            };
+            match hash_pos.filter(|&pos| body.get(pos..).is_some_and(|c| c.starts_with('#'))) {
+                Some(pos) => format!("{}# {}{}", &body[..pos], translated, ending),
+                None => line.to_string(),
+            }
+        })
+        .collect()
+}
+
 impl Default for NotebookParser {
     fn default() -> Self {
         Self::new()
@@ -191,30 +279,33 @@ impl Parser for NotebookParser {
         // Parse original notebook
         let mut notebook: Value = serde_json::from_str(original)?;
 
-        // Create a map of original text to translated text
-        let mut translations = std::collections::HashMap::new();
-        for unit in units {
-            translations.insert(unit.content.clone(), unit.content.clone());
-        }
-
-        // Replace content in cells
         if let Some(cells) = notebook["cells"].as_array_mut() {
-            for cell in cells.iter_mut() {
-                // Get the source as string to check translations
-                let source_text = match &cell["source"] {
-                    Value::Array(lines) => lines
-                        .iter()
-                        .filter_map(|v| v.as_str())
-                        .collect::<Vec<_>>()
-                        .join(""),
-                    Value::String(s) => s.clone(),
+            for unit in units {
+                let (cell_index, cell_line) = unit_location(unit);
+                let Some(cell) = cells.get_mut(cell_index) else {
+                    continue;
+                };
+
+                let source_text = cell_source(cell);
+                let new_source = match (unit.unit_type, cell_line) {
+                    // Whole markdown cell: keep surrounding whitespace
+                    (UnitType::TextNode, _) => {
+                        let trimmed_start = source_text.trim_start();
+                        let leading = &source_text[..source_text.len() - trimmed_start.len()];
+                        let trailing = &trimmed_start[trimmed_start.trim_end().len()..];
+                        format!("{}{}{}", leading, unit.content, trailing)
+                    }
+                    // Comment inside a code cell: replace the text after '#'
+                    (_, Some(line_idx)) => replace_comment_line(
+                        &source_text,
+                        line_idx,
+                        unit.column_number,
+                        &unit.content,
+                    ),
                     _ => continue,
                 };
 
-                // Check if we have a translation for this content
-                if let Some(translated) = translations.get(source_text.trim()) {
-                    cell["source"] = Value::String(translated.clone());
-                }
+                set_cell_source(cell, &new_source);
             }
         }
 
@@ -300,6 +391,66 @@ mod tests {
         assert!(!result.units.is_empty());
     }
 
+    #[test]
+    fn test_units_carry_cell_index() {
+        let parser = NotebookParser::new();
+        let notebook_json = "{\"cells\":[{\"cell_type\":\"markdown\",\"source\":[\"\u{4F60}\u{597D}\"]},{\"cell_type\":\"code\",\"source\":[\"x = 1\\n\",\"# \u{8FD9}\u{662F}\u{6CE8}\u{91CA}\"]}]}";
+        let result = parser.extract_units(notebook_json, "test.ipynb").unwrap();
+
+        let meta = result.units[1].metadata.as_ref().unwrap();
+        assert_eq!(meta["cell_index"], 1);
+        assert_eq!(meta["cell_line"], 1);
+    }
+
+    #[test]
+    fn test_reconstruct_replaces_cells() {
+        let parser = NotebookParser::new();
+        let notebook_json = "{\"cells\":[{\"cell_type\":\"markdown\",\"source\":[\"# \u{6807}\u{9898}\u{6587}\u{672C}\\n\"]},{\"cell_type\":\"code\",\"source\":[\"x = 1\\n\",\"    # \u{8FD9}\u{662F}\u{6CE8}\u{91CA}\\n\",\"y = 2\"]}]}";
+        let mut result = parser.extract_units(notebook_json, "test.ipynb").unwrap();
+        assert_eq!(result.units.len(), 2);
+        result.units[0].content = "# Title text".to_string();
+        result.units[1].content = "This is a comment".to_string();
+
+        let reconstructed = parser
+            .reconstruct(notebook_json, &result.units, "test.ipynb")
+            .unwrap();
+        let notebook: Value = serde_json::from_str(&reconstructed).unwrap();
+
+        assert_eq!(notebook["cells"][0]["source"][0], "# Title text\n");
+        assert_eq!(notebook["cells"][1]["source"][0], "x = 1\n");
+        assert_eq!(
+            notebook["cells"][1]["source"][1],
+            "    # This is a comment\n"
+        );
+        assert_eq!(notebook["cells"][1]["source"][2], "y = 2");
+    }
+
+    #[test]
+    fn test_reconstruct_trailing_comment_after_hash_in_string() {
+        let parser = NotebookParser::new();
+        let line = "s = \"#fff\"  # \u{989C}\u{8272}"; // 颜色
+        let notebook_json = serde_json::json!({
+            "cells": [{"cell_type": "code", "source": [line]}]
+        })
+        .to_string();
+        let column = line.rfind('#').unwrap() as u32 + 1;
+        let unit = TranslatableUnit::new("Color".to_string(), UnitType::Comment, 0, column)
+            .with_metadata(serde_json::json!({"cell_index": 0, "cell_line": 0}));
+
+        let reconstructed = parser
+            .reconstruct(&notebook_json, &[unit], "test.ipynb")
+            .unwrap();
+        let notebook: Value = serde_json::from_str(&reconstructed).unwrap();
+        assert_eq!(notebook["cells"][0]["source"][0], "s = \"#fff\"  # Color");
+
+        // Without a column only whole-line comments are replaced
+        assert_eq!(replace_comment_line(line, 0, 0, "Color"), line);
+        assert_eq!(
+            replace_comment_line("  # \u{989C}\u{8272}", 0, 0, "Color"),
+            "  # Color"
+        );
+    }
+
     #[test]
     fn test_invalid_json() {
         let parser = NotebookParser::new();
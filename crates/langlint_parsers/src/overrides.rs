@@ -0,0 +1,132 @@
+//! Parser overrides from the config's `parsers` table
+//!
+//! Maps file globs to parser names (`"*.tpl" = "html"`, `"Jenkinsfile" =
+//! "generic:groovy"`), so files with nonstandard extensions are extracted
+//! with the right parser. Globs without a `/` match the file name anywhere
+//! in the tree; others match the whole path. When several globs match, the
+//! longest one wins.
+
+use crate::Parser;
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobMatcher};
+use langlint_core::paths;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Compiled glob to parser rules
+#[derive(Debug, Default)]
+pub struct ParserOverrides {
+    /// Matcher, whether it matches file names only, and the parser name
+    rules: Vec<(GlobMatcher, bool, String)>,
+}
+
+impl ParserOverrides {
+    /// Compile rules, checking every parser name
+    pub fn new(parsers: &BTreeMap<String, String>) -> Result<Self> {
+        let mut rules = Vec::new();
+        for (pattern, parser) in parsers {
+            crate::parser_by_name(parser)
+                .with_context(|| format!("Invalid parser for '{}'", pattern))?;
+            let pattern = paths::normalize_separators(pattern);
+            let matcher = GlobBuilder::new(&pattern)
+                .literal_separator(true)
+                .case_insensitive(paths::CASE_INSENSITIVE)
+                .build()
+                .with_context(|| format!("Invalid parser glob: {}", pattern))?
+                .compile_matcher();
+            rules.push((matcher, !pattern.contains('/'), parser.clone()));
+        }
+        // Longest (most specific) pattern first
+        rules.sort_by_key(|(matcher, _, _)| std::cmp::Reverse(matcher.glob().glob().len()));
+        Ok(Self { rules })
+    }
+
+    /// Name of the parser configured for a file
+    pub fn parser_name(&self, path: &Path) -> Option<&str> {
+        let path = paths::to_slash(path);
+        let path = path.trim_start_matches("./");
+        let name = path.rsplit('/').next().unwrap_or(path);
+        self.rules
+            .iter()
+            .find(|(matcher, name_only, _)| matcher.is_match(if *name_only { name } else { path }))
+            .map(|(_, _, parser)| parser.as_str())
+    }
+
+    /// Parser configured for a file
+    pub fn parser_for(&self, path: &Path) -> Option<Box<dyn Parser>> {
+        self.parser_name(path)
+            .and_then(|name| crate::parser_by_name(name).ok())
+    }
+
+    /// Parser for a file: the configured one, else the first parser that
+    /// can handle it
+    pub fn resolve(&self, path: &Path, content: Option<&str>) -> Option<Box<dyn Parser>> {
+        self.parser_for(path)
+            .or_else(|| crate::parser_for(&path.to_string_lossy(), content))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overrides(rules: &[(&str, &str)]) -> Result<ParserOverrides> {
+        ParserOverrides::new(
+            &rules
+                .iter()
+                .map(|(glob, parser)| (glob.to_string(), parser.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_parser_name() {
+        let overrides = overrides(&[
+            ("*.tpl", "generic"),
+            ("Jenkinsfile", "generic:groovy"),
+            ("scripts/*.tpl", "python"),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            overrides.parser_name(Path::new("ci/Jenkinsfile")),
+            Some("generic:groovy")
+        );
+        assert_eq!(
+            overrides.parser_name(Path::new("./web/page.tpl")),
+            Some("generic")
+        );
+        // The longer, path-based glob wins
+        assert_eq!(
+            overrides.parser_name(Path::new("scripts/setup.tpl")),
+            Some("python")
+        );
+        assert_eq!(overrides.parser_name(Path::new("src/main.rs")), None);
+
+        let parser = overrides
+            .parser_for(Path::new("Jenkinsfile"))
+            .expect("parser");
+        assert_eq!(parser.name(), "GenericCodeParser");
+    }
+
+    #[test]
+    fn test_resolve() {
+        let overrides = overrides(&[("*.tpl", "html")]).unwrap();
+
+        let resolve = |path: &str| {
+            overrides
+                .resolve(Path::new(path), None)
+                .map(|parser| parser.name())
+        };
+        assert_eq!(resolve("page.tpl"), Some("HTML"));
+        assert_eq!(resolve("main.py"), Some("PythonParser"));
+        assert_eq!(resolve("notes.txt"), None);
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        assert!(overrides(&[("*.tpl", "cobol")]).is_err());
+        assert!(overrides(&[("*.tpl", "generic:")]).is_err());
+        assert!(overrides(&[("[", "generic")]).is_err());
+    }
+}
@@ -0,0 +1,278 @@
+//! Wrapped comment paragraphs
+//!
+//! Prose comments are usually hard-wrapped over several `#` or `//` lines.
+//! Translated line by line, each fragment comes back as a sentence of its
+//! own, so parsers merge the lines of a paragraph into one unit (see
+//! [`merge_paragraphs`]) and split the translation back over the same
+//! number of lines when reconstructing (see [`split_paragraph`]).
+
+use langlint_core::{MarkdownBlocks, TranslatableUnit, UnitType};
+
+/// Unit metadata key holding the number of lines a paragraph spans
+pub const PARAGRAPH_LINES_KEY: &str = "paragraph_lines";
+
+/// Number of source lines a unit's paragraph spans (1 for single lines)
+pub fn paragraph_lines(unit: &TranslatableUnit) -> usize {
+    unit.metadata
+        .as_ref()
+        .and_then(|m| m.get(PARAGRAPH_LINES_KEY))
+        .and_then(|v| v.as_u64())
+        .map_or(1, |n| n.max(1) as usize)
+}
+
+/// Last source line of a unit, counting the lines of its paragraph
+pub fn end_line(unit: &TranslatableUnit) -> u32 {
+    let span = unit.content.lines().count().max(paragraph_lines(unit));
+    unit.line_number + span.max(1) as u32 - 1
+}
+
+/// Merge comment units on consecutive lines into paragraph units
+///
+/// `prefix` returns everything before the comment text of a line (indent,
+/// marker and the gap after it) if the line holds nothing but a comment.
+/// Adjacent comment lines join a paragraph when their prefixes are equal.
+/// TODO-style comments and lines of lists, rules or drawings stay on their
+/// own. `units` must be sorted by line.
+pub fn merge_paragraphs<F>(
+    lines: &[&str],
+    units: Vec<TranslatableUnit>,
+    prefix: F,
+) -> Vec<TranslatableUnit>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let line_prefix = |unit: &TranslatableUnit| -> Option<String> {
+        let mergeable = unit.unit_type == UnitType::Comment
+            && unit.metadata.is_none()
+            && !MarkdownBlocks::parse(&unit.content).is_structured()
+            && !MarkdownBlocks::parse_diagram(&unit.content).is_structured();
+        if !mergeable {
+            return None;
+        }
+        lines
+            .get((unit.line_number as usize).checked_sub(1)?)
+            .and_then(|line| prefix(line))
+    };
+
+    let mut merged: Vec<TranslatableUnit> = Vec::with_capacity(units.len());
+    // Units of the paragraph being built, and their shared prefix
+    let mut paragraph: Vec<TranslatableUnit> = Vec::new();
+    let mut paragraph_prefix: Option<String> = None;
+
+    for unit in units {
+        let unit_prefix = line_prefix(&unit);
+        let continues = match (paragraph.last(), &paragraph_prefix, &unit_prefix) {
+            (Some(last), Some(current), Some(next)) => {
+                current == next && unit.line_number == last.line_number + 1
+            }
+            _ => false,
+        };
+
+        if !continues {
+            merged.extend(finish(std::mem::take(&mut paragraph)));
+        }
+        paragraph.push(unit);
+        paragraph_prefix = unit_prefix;
+    }
+    merged.extend(finish(paragraph));
+
+    merged
+}
+
+/// Turn the lines of a paragraph into a single unit
+fn finish(mut paragraph: Vec<TranslatableUnit>) -> Vec<TranslatableUnit> {
+    if paragraph.len() < 2 {
+        return paragraph;
+    }
+
+    let first = paragraph.remove(0);
+    let count = paragraph.len() + 1;
+    let last_line = first.line_number + paragraph.len() as u32;
+    let content = paragraph.iter().fold(first.content.clone(), |text, unit| {
+        join_wrapped(&text, &unit.content)
+    });
+
+    let mut unit = TranslatableUnit::new(
+        content,
+        UnitType::Comment,
+        first.line_number,
+        first.column_number,
+    )
+    .with_metadata(serde_json::json!({ PARAGRAPH_LINES_KEY: count }))
+    .with_context(format!(
+        "Comment paragraph at lines {}-{}",
+        first.line_number, last_line
+    ))
+    .with_priority(first.priority);
+    unit.detect_language();
+    vec![unit]
+}
+
+/// Join two wrapped lines, without a space between CJK characters
+pub(crate) fn join_wrapped(left: &str, right: &str) -> String {
+    let tight = left.chars().last().is_some_and(is_cjk) && right.chars().next().is_some_and(is_cjk);
+    if tight {
+        format!("{}{}", left, right)
+    } else {
+        format!("{} {}", left, right)
+    }
+}
+
+/// Check if a character belongs to a script written without spaces
+fn is_cjk(c: char) -> bool {
+    ('\u{2E80}'..='\u{9FFF}').contains(&c)
+        || ('\u{AC00}'..='\u{D7AF}').contains(&c)
+        || ('\u{F900}'..='\u{FAFF}').contains(&c)
+        || ('\u{FF00}'..='\u{FFEF}').contains(&c)
+}
+
+/// Split a translated paragraph over `count` lines of similar length
+///
+/// Lines break between words, and also between characters of scripts
+/// written without spaces (Chinese, Japanese). Always returns `count`
+/// pieces; trailing ones are empty if there is too little text to go round.
+pub fn split_paragraph(text: &str, count: usize) -> Vec<String> {
+    let count = count.max(1);
+    let tokens = tokenize(text);
+    let width = |(spaced, token): &(bool, String)| token.chars().count() + usize::from(*spaced);
+
+    let mut pieces = Vec::with_capacity(count);
+    let mut remaining: usize = tokens.iter().map(width).sum();
+    let mut tokens = tokens.into_iter().peekable();
+
+    for index in 0..count {
+        let lines_left = count - index;
+        let target = remaining.div_ceil(lines_left);
+        let mut piece = String::new();
+        let mut piece_width = 0;
+
+        while let Some(token) = tokens.peek() {
+            let added = width(token);
+            // The last line takes everything; others stop near the target
+            if lines_left > 1 && !piece.is_empty() && piece_width + added / 2 >= target {
+                break;
+            }
+            let (spaced, token) = tokens.next().unwrap_or_default();
+            if spaced && !piece.is_empty() {
+                piece.push(' ');
+            }
+            piece.push_str(&token);
+            piece_width += added;
+        }
+
+        remaining = remaining.saturating_sub(piece_width);
+        pieces.push(piece);
+    }
+
+    pieces
+}
+
+/// Split text into words and single CJK characters, each flagged with
+/// whether whitespace preceded it
+fn tokenize(text: &str) -> Vec<(bool, String)> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut spaced = false;
+
+    for c in text.trim().chars() {
+        if c.is_whitespace() || is_cjk(c) {
+            if !word.is_empty() {
+                tokens.push((spaced, std::mem::take(&mut word)));
+                spaced = false;
+            }
+            if c.is_whitespace() {
+                spaced = true;
+            } else {
+                tokens.push((spaced, c.to_string()));
+                spaced = false;
+            }
+        } else {
+            word.push(c);
+        }
+    }
+    if !word.is_empty() {
+        tokens.push((spaced, word));
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comment(text: &str, line: u32) -> TranslatableUnit {
+        TranslatableUnit::new(text.to_string(), UnitType::Comment, line, 1)
+    }
+
+    fn hash_prefix(line: &str) -> Option<String> {
+        let trimmed = line.trim_start();
+        let after = trimmed.strip_prefix('#')?;
+        let gap = after.len() - after.trim_start().len();
+        Some(line[..line.len() - after.len() + gap].to_string())
+    }
+
+    #[test]
+    fn test_merge_paragraphs() {
+        let lines = vec![
+            "# 这个函数会读取配置文件",
+            "# 并返回解析后的结果",
+            "x = 1",
+            "# 第二段",
+            "    # 缩进不同的注释",
+            "# - 列表项一",
+            "# - 列表项二",
+        ];
+        let units: Vec<_> = lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| line.trim_start().starts_with('#'))
+            .map(|(i, line)| comment(line.trim_start().trim_start_matches("# "), i as u32 + 1))
+            .collect();
+
+        let merged = merge_paragraphs(&lines, units, hash_prefix);
+        assert_eq!(merged.len(), 5);
+        assert_eq!(
+            merged[0].content,
+            "这个函数会读取配置文件并返回解析后的结果"
+        );
+        assert_eq!(paragraph_lines(&merged[0]), 2);
+        assert_eq!(merged[0].line_number, 1);
+        assert_eq!(end_line(&merged[0]), 2);
+        assert_eq!(merged[1].content, "第二段");
+        assert_eq!(paragraph_lines(&merged[1]), 1);
+        assert_eq!(end_line(&merged[1]), 4);
+        assert_eq!(merged[3].content, "- 列表项一");
+    }
+
+    #[test]
+    fn test_join_wrapped() {
+        assert_eq!(join_wrapped("读取配置", "文件"), "读取配置文件");
+        assert_eq!(join_wrapped("reads the", "config"), "reads the config");
+        assert_eq!(join_wrapped("使用", "`load()`"), "使用 `load()`");
+    }
+
+    #[test]
+    fn test_split_paragraph() {
+        assert_eq!(
+            split_paragraph(
+                "Read the configuration file and return the parsed result",
+                2
+            ),
+            vec![
+                "Read the configuration file",
+                "and return the parsed result"
+            ]
+        );
+        assert_eq!(
+            split_paragraph("读取配置文件并返回结果", 2),
+            vec!["读取配置文件", "并返回结果"]
+        );
+        assert_eq!(split_paragraph("Done", 3), vec!["Done", "", ""]);
+        assert_eq!(split_paragraph("a b c", 1), vec!["a b c"]);
+        assert_eq!(
+            split_paragraph("调用 load() 读取配置文件", 2),
+            vec!["调用 load()", "读取配置文件"]
+        );
+    }
+}
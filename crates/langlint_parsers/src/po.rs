@@ -0,0 +1,445 @@
+//! gettext catalog parser (.po and .pot files)
+//!
+//! Each untranslated entry becomes a `StringLiteral` unit holding its
+//! `msgid` (plus one for `msgid_plural`), with `msgctxt` as the unit's
+//! context. On reconstruction the translations are written into `msgstr`
+//! (the singular into `msgstr[0]` and the plural into the other forms) and
+//! the entry is flagged `fuzzy`, so machine translations are reviewed
+//! before `msgfmt` compiles them. Comments, references, other flags, the
+//! header and translated, fuzzy or obsolete (`#~`) entries are left as they
+//! are.
+
+use anyhow::Result;
+use langlint_core::{ParseResult, TranslatableUnit, UnitType};
+
+use crate::Parser;
+
+/// Unit metadata key naming the field a unit was extracted from
+pub const PO_KEY: &str = "po";
+
+/// Plural forms written when neither the entry nor the header gives a count
+const DEFAULT_PLURAL_FORMS: usize = 2;
+
+/// Parser for gettext catalogs
+pub struct PoParser;
+
+/// A keyword (`msgid`, `msgstr[1]`, ...) with its string, which may
+/// continue on the following lines
+#[derive(Debug, Clone)]
+struct Field {
+    keyword: String,
+    /// Line of the keyword
+    line: usize,
+    /// Line after the field's last string line
+    end: usize,
+    value: String,
+    /// Written as `msgid ""` followed by one line per string
+    multiline: bool,
+}
+
+/// An entry of the catalog, by line ranges
+#[derive(Debug, Default)]
+struct Entry {
+    /// `#,` flags line
+    flags: Option<usize>,
+    fuzzy: bool,
+    obsolete: bool,
+    fields: Vec<Field>,
+}
+
+impl Entry {
+    fn field(&self, keyword: &str) -> Option<&Field> {
+        self.fields.iter().find(|field| field.keyword == keyword)
+    }
+
+    fn msgstrs(&self) -> impl Iterator<Item = &Field> {
+        self.fields
+            .iter()
+            .filter(|field| field.keyword.starts_with("msgstr"))
+    }
+
+    /// Check if the entry is a message still waiting for a translation
+    fn is_untranslated(&self) -> bool {
+        !self.obsolete
+            && !self.fuzzy
+            && self
+                .field("msgid")
+                .is_some_and(|msgid| !msgid.value.is_empty())
+            && self.msgstrs().all(|msgstr| msgstr.value.is_empty())
+    }
+}
+
+impl PoParser {
+    /// Create a new PO parser
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for PoParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Split a catalog into its entries
+fn entries(lines: &[&str]) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let mut entry = Entry::default();
+
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        // A comment or new message after a `msgstr` starts the next entry
+        let starts_entry = trimmed.starts_with('#')
+            || trimmed.starts_with("msgctxt")
+            || (trimmed.starts_with("msgid") && !trimmed.starts_with("msgid_plural"));
+        if trimmed.is_empty() || (starts_entry && entry.msgstrs().next().is_some()) {
+            if !entry.fields.is_empty() || entry.obsolete {
+                entries.push(std::mem::take(&mut entry));
+            } else {
+                entry = Entry::default();
+            }
+            if trimmed.is_empty() {
+                continue;
+            }
+        }
+
+        if let Some(flags) = trimmed.strip_prefix("#,") {
+            entry.flags = Some(i);
+            entry.fuzzy = flags.split(',').any(|flag| flag.trim() == "fuzzy");
+        } else if trimmed.starts_with("#~") {
+            entry.obsolete = true;
+        } else if trimmed.starts_with('#') {
+            continue;
+        } else if trimmed.starts_with('"') {
+            if let Some(field) = entry.fields.last_mut() {
+                field.value.push_str(&unquote(trimmed));
+                field.end = i + 1;
+            }
+        } else if let Some((keyword, rest)) = trimmed.split_once(char::is_whitespace) {
+            let rest = rest.trim();
+            entry.fields.push(Field {
+                keyword: keyword.to_string(),
+                line: i,
+                end: i + 1,
+                value: unquote(rest),
+                multiline: rest == "\"\"",
+            });
+        }
+    }
+    if !entry.fields.is_empty() {
+        entries.push(entry);
+    }
+    entries
+}
+
+/// The text of a quoted PO string
+fn unquote(quoted: &str) -> String {
+    let inner = quoted
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(quoted);
+    let mut text = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => text.push('\n'),
+            Some('t') => text.push('\t'),
+            Some('r') => text.push('\r'),
+            Some(other) => text.push(other),
+            None => text.push('\\'),
+        }
+    }
+    text
+}
+
+/// `text` as a quoted PO string
+fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Lines of a field holding `text`, one string per line of it if `multiline`
+fn field_lines(keyword: &str, text: &str, multiline: bool) -> Vec<String> {
+    if !multiline {
+        return vec![format!("{} {}", keyword, quote(text))];
+    }
+    let mut lines = vec![format!("{} \"\"", keyword)];
+    lines.extend(text.split_inclusive('\n').map(quote));
+    lines
+}
+
+/// `nplurals` of the header's `Plural-Forms`
+fn header_plural_forms(entries: &[Entry]) -> Option<usize> {
+    let header = entries.iter().find(|entry| {
+        entry
+            .field("msgid")
+            .is_some_and(|msgid| msgid.value.is_empty())
+    })?;
+    let value = &header.field("msgstr")?.value;
+    let forms = value
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Plural-Forms:"))?;
+    let count = forms.split(';').find_map(|part| {
+        part.trim()
+            .strip_prefix("nplurals")?
+            .trim()
+            .strip_prefix('=')
+    })?;
+    count.trim().parse().ok().filter(|&n| n > 0)
+}
+
+impl Parser for PoParser {
+    fn name(&self) -> &'static str {
+        "gettext PO"
+    }
+
+    fn supported_extensions(&self) -> &'static [&'static str] {
+        &[".po", ".pot"]
+    }
+
+    fn can_parse(&self, path: &str, _content: Option<&str>) -> bool {
+        let lower = path.to_lowercase();
+        self.supported_extensions()
+            .iter()
+            .any(|ext| lower.ends_with(ext))
+    }
+
+    fn extract_units(&self, content: &str, _path: &str) -> Result<ParseResult> {
+        let lines: Vec<&str> = content.lines().collect();
+        let mut units = Vec::new();
+
+        for entry in entries(&lines)
+            .iter()
+            .filter(|entry| entry.is_untranslated())
+        {
+            let context = entry.field("msgctxt").map(|field| field.value.clone());
+            for keyword in ["msgid", "msgid_plural"] {
+                let Some(field) = entry.field(keyword) else {
+                    continue;
+                };
+                let mut unit = TranslatableUnit::new(
+                    field.value.clone(),
+                    UnitType::StringLiteral,
+                    (field.line + 1) as u32,
+                    1,
+                )
+                .with_metadata(serde_json::json!({ PO_KEY: { "field": keyword } }));
+                if let Some(context) = &context {
+                    unit = unit.with_context(context.clone());
+                }
+                unit.detect_language();
+                units.push(unit);
+            }
+        }
+
+        Ok(ParseResult::new("po", "utf-8", lines.len() as u32).with_units(units))
+    }
+
+    fn reconstruct(
+        &self,
+        original: &str,
+        units: &[TranslatableUnit],
+        _path: &str,
+    ) -> Result<String> {
+        let newline = if original.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        let mut lines: Vec<String> = original.lines().map(str::to_string).collect();
+        let original_lines: Vec<&str> = original.lines().collect();
+        let entries = entries(&original_lines);
+        let plural_forms = header_plural_forms(&entries);
+
+        let translation = |field: &Field| {
+            units
+                .iter()
+                .find(|unit| unit.line_number as usize == field.line + 1)
+                .filter(|unit| unit.content != field.value)
+                .map(|unit| unit.content.as_str())
+        };
+
+        // Bottom-up, so earlier entries keep their line numbers
+        for entry in entries.iter().rev().filter(|entry| entry.is_untranslated()) {
+            let Some(msgid) = entry.field("msgid") else {
+                continue;
+            };
+            let plural = entry.field("msgid_plural");
+            let singular_text = translation(msgid);
+            let plural_text = plural.and_then(translation);
+            if singular_text.is_none() && plural_text.is_none() {
+                continue;
+            }
+
+            let msgstrs: Vec<&Field> = entry.msgstrs().collect();
+            let (Some(first), Some(last)) = (msgstrs.first(), msgstrs.last()) else {
+                continue;
+            };
+            let singular = singular_text.unwrap_or(&msgid.value);
+            let mut replacement = Vec::new();
+            match plural {
+                Some(plural) => {
+                    let plural_value = plural_text.unwrap_or(&plural.value);
+                    let forms = match msgstrs.len() {
+                        1 => plural_forms.unwrap_or(DEFAULT_PLURAL_FORMS),
+                        n => n,
+                    };
+                    for form in 0..forms {
+                        let text = if form == 0 { singular } else { plural_value };
+                        let keyword = format!("msgstr[{}]", form);
+                        replacement.extend(field_lines(&keyword, text, msgid.multiline));
+                    }
+                }
+                None => replacement.extend(field_lines("msgstr", singular, msgid.multiline)),
+            }
+            lines.splice(first.line..last.end, replacement);
+
+            match entry.flags {
+                Some(flags) => lines[flags].push_str(", fuzzy"),
+                None => {
+                    let first_field = entry.fields.first().map_or(msgid.line, |f| f.line);
+                    lines.insert(first_field, "#, fuzzy".to_string());
+                }
+            }
+        }
+
+        let mut result = lines.join(newline);
+        if original.ends_with('\n') {
+            result.push_str(newline);
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CATALOG: &str = r#"# French translations
+msgid ""
+msgstr ""
+"Language: fr\n"
+"Plural-Forms: nplurals=2; plural=(n > 1);\n"
+
+#. Shown on the start page
+#: src/app.py:12
+msgid "Welcome back, %(name)s!"
+msgstr ""
+
+#: src/app.py:20
+#, python-format
+msgid "%d file deleted"
+msgid_plural "%d files deleted"
+msgstr[0] ""
+msgstr[1] ""
+
+msgctxt "menu"
+msgid ""
+"Open the \"recent\" list\n"
+"and pick a file"
+msgstr ""
+
+msgid "Save"
+msgstr "Enregistrer"
+
+#, fuzzy
+msgid "Quit"
+msgstr "Quitter ?"
+
+#~ msgid "Old"
+#~ msgstr ""
+"#;
+
+    #[test]
+    fn test_extract_units() {
+        let parser = PoParser::new();
+        assert!(parser.can_parse("locale/fr/LC_MESSAGES/app.PO", None));
+        assert!(parser.can_parse("messages.pot", None));
+        assert!(!parser.can_parse("app.mo", None));
+
+        let result = parser.extract_units(CATALOG, "fr.po").unwrap();
+        let units: Vec<(u32, &str, Option<&str>)> = result
+            .units
+            .iter()
+            .map(|unit| {
+                (
+                    unit.line_number,
+                    unit.content.as_str(),
+                    unit.context.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            units,
+            vec![
+                (9, "Welcome back, %(name)s!", None),
+                (14, "%d file deleted", None),
+                (15, "%d files deleted", None),
+                (
+                    20,
+                    "Open the \"recent\" list\nand pick a file",
+                    Some("menu")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reconstruct() {
+        let parser = PoParser::new();
+        let mut units = parser.extract_units(CATALOG, "fr.po").unwrap().units;
+        let translations = [
+            "Bon retour, %(name)s !",
+            "%d fichier supprimé",
+            "%d fichiers supprimés",
+            "Ouvrir la liste « récente »\net choisir un fichier",
+        ];
+        for (unit, translation) in units.iter_mut().zip(translations) {
+            unit.content = translation.to_string();
+        }
+
+        let result = parser.reconstruct(CATALOG, &units, "fr.po").unwrap();
+        assert!(result.contains(
+            "#: src/app.py:12\n#, fuzzy\nmsgid \"Welcome back, %(name)s!\"\nmsgstr \"Bon retour, %(name)s !\"\n"
+        ));
+        assert!(result.contains(
+            "#, python-format, fuzzy\nmsgid \"%d file deleted\"\nmsgid_plural \"%d files deleted\"\n\
+             msgstr[0] \"%d fichier supprimé\"\nmsgstr[1] \"%d fichiers supprimés\"\n"
+        ));
+        assert!(result.contains(
+            "#, fuzzy\nmsgctxt \"menu\"\nmsgid \"\"\n\"Open the \\\"recent\\\" list\\n\"\n\"and pick a file\"\n\
+             msgstr \"\"\n\"Ouvrir la liste « récente »\\n\"\n\"et choisir un fichier\"\n"
+        ));
+        assert!(result.contains("msgid \"Save\"\nmsgstr \"Enregistrer\"\n"));
+        assert!(result.contains("#, fuzzy\nmsgid \"Quit\"\nmsgstr \"Quitter ?\"\n"));
+        assert!(result.ends_with("#~ msgid \"Old\"\n#~ msgstr \"\"\n"));
+
+        // Every translated entry is still a valid, single entry
+        let reparsed = parser.extract_units(&result, "fr.po").unwrap();
+        assert!(reparsed.units.is_empty());
+
+        // Untouched units give back the original
+        let units = parser.extract_units(CATALOG, "fr.po").unwrap().units;
+        assert_eq!(
+            parser.reconstruct(CATALOG, &units, "fr.po").unwrap(),
+            CATALOG
+        );
+    }
+}
@@ -0,0 +1,210 @@
+//! Pragma and decoration comment detection
+//!
+//! Some comments are instructions to tools rather than prose: shebangs,
+//! encoding cookies, Sphinx `#:` attribute comments, `# type: ignore`,
+//! `# noqa`, `# pylint:` and friends, `// eslint-disable`, `//go:generate`.
+//! Translating them breaks the tool that reads them, so parsers never turn
+//! them into units. A pragma trailing a prose comment (`# 说明  # noqa`) is
+//! split off and kept in unit metadata, then restored on reconstruction,
+//! at its original column so aligned pragmas stay aligned.
+
+use langlint_core::TranslatableUnit;
+use regex::Regex;
+use std::sync::OnceLock;
+use unicode_width::UnicodeWidthStr;
+
+/// Unit metadata key holding a trailing pragma, including its marker
+pub const PRAGMA_KEY: &str = "pragma";
+
+/// Check if comment text (after the marker and whitespace) is a tool directive
+pub fn is_pragma(text: &str) -> bool {
+    static PRAGMA_RE: OnceLock<Regex> = OnceLock::new();
+    let re = PRAGMA_RE.get_or_init(|| {
+        Regex::new(concat!(
+            r"^(?:",
+            // Python tooling
+            r"type:|noqa\b|nosec\b|pylint:|pyright:|mypy:|pragma:|fmt:\s*(?:on|off|skip)\b|",
+            r"isort:|ruff:|pyre-\w+|-\*-.*-\*-|coding[:=]|vim?:|",
+            // JavaScript/TypeScript tooling
+            r"eslint(?:-disable|-enable|-env)?\b|jshint\b|@ts-(?:ignore|expect-error|nocheck|check)\b|",
+            r"prettier-ignore\b|istanbul\s+ignore\b|c8\s+ignore\b|@flow\b|@jsx\b|webpack\w+:|",
+            // Other linters and formatters
+            r"nolint\b|NOLINT|clang-format\s+(?:on|off)\b|cppcheck-suppress\b|rubocop:|swiftlint:|",
+            r"ktlint-disable\b|shellcheck\s+\w|noinspection\b|@formatter:\s*(?:on|off)\b|",
+            r"c?spell-?checker:|cspell:|#?(?:end)?region\b",
+            r")",
+        ))
+        .unwrap()
+    });
+    re.is_match(text)
+}
+
+/// Check if a comment is a pragma or decoration that must stay untouched
+///
+/// `marker` is the comment marker (`#`, `//`, `--`) and `after_marker` the
+/// raw text following it. Besides [`is_pragma`] directives this covers
+/// shebangs (`#!`), Sphinx attribute comments (`#:`) and Go-style
+/// directives written without a space (`//go:generate`, `//nolint`).
+pub fn is_pragma_comment(marker: &str, after_marker: &str) -> bool {
+    static DIRECTIVE_RE: OnceLock<Regex> = OnceLock::new();
+    let directive_re = DIRECTIVE_RE.get_or_init(|| Regex::new(r"^[a-z]+:[a-z]").unwrap());
+
+    if marker == "#" && (after_marker.starts_with('!') || after_marker.starts_with(':')) {
+        return true;
+    }
+    if marker == "//" && directive_re.is_match(after_marker) {
+        return true;
+    }
+    is_pragma(after_marker.trim_start())
+}
+
+/// Split a trailing pragma comment off comment text
+///
+/// `"说明  # noqa: E501"` with marker `#` becomes `("说明", Some("  # noqa: E501"))`.
+pub fn split_trailing_pragma<'a>(text: &'a str, marker: &str) -> (&'a str, Option<&'a str>) {
+    for (pos, _) in text.match_indices(marker).filter(|(pos, _)| *pos > 0) {
+        if is_pragma(text[pos + marker.len()..].trim_start()) {
+            let body = text[..pos].trim_end();
+            return (body, Some(&text[body.len()..]));
+        }
+    }
+    (text, None)
+}
+
+/// Record a trailing pragma on a unit
+pub fn set_trailing_pragma(unit: &mut TranslatableUnit, pragma: &str) {
+    match unit.metadata.as_mut().and_then(|m| m.as_object_mut()) {
+        Some(metadata) => {
+            metadata.insert(PRAGMA_KEY.to_string(), pragma.into());
+        }
+        None => unit.metadata = Some(serde_json::json!({ PRAGMA_KEY: pragma })),
+    }
+}
+
+/// Trailing pragma recorded on a unit, if any
+pub fn trailing_pragma(unit: &TranslatableUnit) -> Option<&str> {
+    unit.metadata
+        .as_ref()
+        .and_then(|m| m.get(PRAGMA_KEY))
+        .and_then(|p| p.as_str())
+}
+
+/// Replacement line with the unit's trailing pragma restored
+///
+/// `line` is the replacement up to the end of the comment text and
+/// `original` the line it replaces. The pragma is padded back to the display
+/// column it started at in `original`, so end-of-line pragmas aligned in a
+/// table stay aligned; if the new text runs past that column the original
+/// gap is kept instead.
+pub fn with_trailing_pragma(unit: &TranslatableUnit, line: String, original: &str) -> String {
+    let Some(pragma) = trailing_pragma(unit) else {
+        return line;
+    };
+    let directive = pragma.trim_start();
+    let gap = &pragma[..pragma.len() - directive.len()];
+
+    let column = original
+        .trim_end()
+        .strip_suffix(directive)
+        .map(|before| before.width());
+    match column {
+        Some(column) if !gap.contains('\t') && line.width() < column => {
+            let padding = column - line.width();
+            format!("{}{}{}", line, " ".repeat(padding), directive)
+        }
+        _ => line + pragma,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use langlint_core::UnitType;
+
+    #[test]
+    fn test_is_pragma() {
+        for text in [
+            "type: ignore[attr-defined]",
+            "noqa: E501",
+            "noqa",
+            "pylint: disable=invalid-name",
+            "pragma: no cover",
+            "fmt: off",
+            "-*- coding: utf-8 -*-",
+            "eslint-disable-next-line no-console",
+            "@ts-ignore",
+            "prettier-ignore",
+            "NOLINT(readability)",
+            "region 初始化",
+        ] {
+            assert!(is_pragma(text), "{}", text);
+        }
+
+        for text in [
+            "类型：整数",
+            "format the output",
+            "注意：不要修改",
+            "type of the value",
+        ] {
+            assert!(!is_pragma(text), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_is_pragma_comment() {
+        assert!(is_pragma_comment("#", "!/usr/bin/env python"));
+        assert!(is_pragma_comment("#", ": 用户名"));
+        assert!(is_pragma_comment("#", " type: ignore"));
+        assert!(is_pragma_comment("//", "go:generate stringer -type=Pill"));
+        assert!(is_pragma_comment("//", "nolint:errcheck"));
+        assert!(!is_pragma_comment("#", " 计算总和"));
+        assert!(!is_pragma_comment("//", " 计算总和"));
+    }
+
+    #[test]
+    fn test_split_trailing_pragma() {
+        assert_eq!(
+            split_trailing_pragma("这行太长  # noqa: E501", "#"),
+            ("这行太长", Some("  # noqa: E501"))
+        );
+        assert_eq!(
+            split_trailing_pragma("忽略类型 // @ts-ignore", "//"),
+            ("忽略类型", Some(" // @ts-ignore"))
+        );
+        assert_eq!(
+            split_trailing_pragma("编号 #3 的问题", "#"),
+            ("编号 #3 的问题", None)
+        );
+    }
+
+    #[test]
+    fn test_trailing_pragma_metadata() {
+        let mut unit = TranslatableUnit::new("这行太长".to_string(), UnitType::Comment, 1, 1)
+            .with_metadata(serde_json::json!({ "todo_marker": "TODO: " }));
+        set_trailing_pragma(&mut unit, "  # noqa");
+
+        assert_eq!(trailing_pragma(&unit), Some("  # noqa"));
+        assert_eq!(unit.metadata.as_ref().unwrap()["todo_marker"], "TODO: ");
+        assert_eq!(
+            with_trailing_pragma(&unit, "# Line too long".to_string(), "# 这行太长  # noqa"),
+            "# Line too long  # noqa"
+        );
+    }
+
+    #[test]
+    fn test_trailing_pragma_keeps_column() {
+        let unit = TranslatableUnit::new("用户名".to_string(), UnitType::Comment, 1, 1)
+            .with_metadata(serde_json::json!({ PRAGMA_KEY: "    # noqa" }));
+
+        // CJK characters take two columns, so the pragma starts at column 19
+        let original = "x = 1  # 用户名    # noqa";
+        assert_eq!(
+            with_trailing_pragma(&unit, "x = 1  # Name".to_string(), original),
+            "x = 1  # Name      # noqa"
+        );
+        assert_eq!(
+            with_trailing_pragma(&unit, "x = 1  # Display name".to_string(), original),
+            "x = 1  # Display name    # noqa"
+        );
+    }
+}
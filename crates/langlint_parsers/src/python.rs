@@ -1,11 +1,16 @@
-use anyhow::Result;
-use langlint_core::{ParseResult, Priority, TranslatableUnit, UnitType};
-use regex::Regex;
-use std::sync::OnceLock;
-
-use crate::Parser;
-
-/// Python parser for extracting comments and docstrings
+use anyhow::{Context, Result};
+use langlint_core::{MarkdownBlocks, ParseResult, Priority, TranslatableUnit, UnitType};
+use std::collections::BTreeMap;
+use std::ops::Range;
+use tree_sitter::Node;
+
+use crate::{docstring, generated, i18n, license, paragraph, pragma, sinks, todo, Parser};
+
+/// Python parser for extracting comments, docstrings and user-facing strings
+///
+/// Works on the tree-sitter syntax tree, so `#` inside strings and quotes
+/// inside comments are never mistaken for one another, and translations
+/// replace the exact byte span of what they translate.
 pub struct PythonParser;
 
 impl PythonParser {
@@ -22,6 +27,13 @@ impl PythonParser {
             return false;
         }
 
+        // Judge diagrams and box tables by their labels, not the drawing
+        let diagram = MarkdownBlocks::parse_diagram(text);
+        if diagram.is_structured() {
+            let labels = diagram.texts().join(" ");
+            return !labels.is_empty() && self.is_translatable(&labels);
+        }
+
         // Skip URLs and emails
         if text.contains("://") || text.contains('@') && text.contains('.') {
             return false;
@@ -69,6 +81,13 @@ impl PythonParser {
     }
 }
 
+/// Indent, `#` and the gap before the text of a comment-only line
+fn comment_prefix(line: &str) -> Option<String> {
+    let after_hash = line.trim_start().strip_prefix('#')?;
+    let text = after_hash.trim_start();
+    Some(line[..line.len() - text.len()].to_string())
+}
+
 impl Default for PythonParser {
     fn default() -> Self {
         Self::new()
@@ -106,164 +125,151 @@ impl Parser for PythonParser {
     fn extract_units(&self, content: &str, path: &str) -> Result<ParseResult> {
         let mut units = Vec::new();
         let lines: Vec<&str> = content.lines().collect();
-
-        // Regex for single-line comments
-        static COMMENT_RE: OnceLock<Regex> = OnceLock::new();
-        let comment_re = COMMENT_RE.get_or_init(|| Regex::new(r"^\s*#\s*(.+)$").unwrap());
-
-        // Regex for docstrings (simplified)
-        static DOCSTRING_RE: OnceLock<Regex> = OnceLock::new();
-        let docstring_re =
-            DOCSTRING_RE.get_or_init(|| Regex::new(r#"^\s*["']{3}(.+?)["']{3}"#).unwrap());
-
-        // Regex for multi-line docstring start
-        static DOCSTRING_START_RE: OnceLock<Regex> = OnceLock::new();
-        let docstring_start_re =
-            DOCSTRING_START_RE.get_or_init(|| Regex::new(r#"^\s*(["']{3})\s*(.*)$"#).unwrap());
-
-        let mut i = 0;
-        while i < lines.len() {
-            let line_num = (i + 1) as u32;
-            let line = lines[i];
-
-            // Extract single-line comments
-            if let Some(caps) = comment_re.captures(line) {
-                if let Some(comment_text) = caps.get(1) {
-                    let text = comment_text.as_str().trim();
-                    if self.is_translatable(text) {
-                        let mut unit =
-                            TranslatableUnit::new(text.to_string(), UnitType::Comment, line_num, 1)
-                                .with_context(format!("Line {}: {}", line_num, line.trim()))
-                                .with_priority(Priority::Medium);
-
-                        // Detect language
-                        unit.detect_language();
-
-                        units.push(unit);
+        let line_text = |line: u32| lines.get(line as usize - 1).copied().unwrap_or_default();
+
+        for site in sites(content)? {
+            let text = &content[site.start..site.end];
+            match site.kind {
+                // Comments, leaving pragmas and shebangs alone
+                SiteKind::Comment => {
+                    let after_hash = &text[1..];
+                    if pragma::is_pragma_comment("#", after_hash) {
+                        continue;
                     }
-                }
-            }
-
-            // Extract single-line docstrings
-            if let Some(caps) = docstring_re.captures(line) {
-                if let Some(docstring_text) = caps.get(1) {
-                    let text = docstring_text.as_str().trim();
-                    if self.is_translatable(text) {
-                        // Detect quote style
-                        let quote_style = if line.trim_start().starts_with(r#"""""#) {
-                            r#"""""#
-                        } else {
-                            "'''"
-                        };
-
-                        let mut unit = TranslatableUnit::new(
-                            text.to_string(),
-                            UnitType::Docstring,
-                            line_num,
-                            1,
-                        )
-                        .with_metadata(serde_json::json!({"quote_style": quote_style}))
-                        .with_context(format!("Docstring at line {}", line_num))
-                        .with_priority(Priority::High);
-
-                        // Detect language
-                        unit.detect_language();
-
-                        units.push(unit);
+                    let text = after_hash.trim();
+                    let (marker, body) = todo::split_todo_marker(text).unwrap_or(("", text));
+                    let (body, trailing) = pragma::split_trailing_pragma(body, "#");
+                    if !self.is_translatable(body) {
+                        continue;
                     }
-                }
-                i += 1;
-                continue;
-            }
-
-            // Extract multi-line docstrings
-            if let Some(caps) = docstring_start_re.captures(line) {
-                let quote = caps.get(1).unwrap().as_str();
-                let first_line_content = caps.get(2).map(|m| m.as_str().trim()).unwrap_or("");
-
-                // Check if it's actually a single-line docstring (ends on same line)
-                if !first_line_content.is_empty() && line.trim_end().ends_with(quote) {
-                    i += 1;
-                    continue;
-                }
-
-                // Multi-line docstring
-                let start_line = line_num;
-                let mut docstring_lines = Vec::new();
-
-                if !first_line_content.is_empty() {
-                    docstring_lines.push(first_line_content.to_string());
-                }
-
-                i += 1;
-                let mut found_end = false;
-                let mut end_line = start_line;
-
-                while i < lines.len() {
-                    let current_line = lines[i];
-                    end_line = (i + 1) as u32;
-
-                    if current_line.contains(quote) {
-                        // Found closing quotes
-                        if let Some(end_pos) = current_line.find(quote) {
-                            let last_content = current_line[..end_pos].trim();
-                            if !last_content.is_empty() {
-                                docstring_lines.push(last_content.to_string());
-                            }
-                        }
-                        found_end = true;
-                        break;
-                    } else {
-                        // Middle line of docstring
-                        let content = current_line.trim();
-                        if !content.is_empty() {
-                            docstring_lines.push(content.to_string());
-                        }
+                    let mut unit = TranslatableUnit::new(
+                        body.to_string(),
+                        UnitType::Comment,
+                        site.line,
+                        site.column,
+                    )
+                    .with_context(format!(
+                        "Line {}: {}",
+                        site.line,
+                        line_text(site.line).trim()
+                    ))
+                    .with_priority(Priority::Medium);
+                    if !marker.is_empty() {
+                        unit = unit
+                            .with_metadata(serde_json::json!({ todo::TODO_MARKER_KEY: marker }));
+                    }
+                    if let Some(trailing) = trailing {
+                        pragma::set_trailing_pragma(&mut unit, trailing);
                     }
-                    i += 1;
+                    unit.detect_language();
+                    units.push(unit);
                 }
-
-                if found_end {
-                    let docstring_content = docstring_lines.join(" ");
-                    if self.is_translatable(&docstring_content) {
-                        let span = end_line - start_line + 1;
-                        let mut unit = TranslatableUnit::new(
-                            docstring_content,
+                SiteKind::Docstring => {
+                    let literal = Literal::of(text);
+                    let mut unit = if literal.body.contains('\n') {
+                        // One line of content per segment; the layout stays
+                        // in the source
+                        let body_lines: Vec<&str> = literal.body.split('\n').collect();
+                        let sections = docstring::analyze(&body_lines);
+                        let docstring = docstring::segments(&sections)
+                            .map(|(_, segment)| {
+                                segment
+                                    .lines
+                                    .iter()
+                                    .map(|(line, range)| &body_lines[*line][range.clone()])
+                                    .fold(String::new(), |text, line| {
+                                        if text.is_empty() {
+                                            line.to_string()
+                                        } else {
+                                            paragraph::join_wrapped(&text, line)
+                                        }
+                                    })
+                            })
+                            .collect::<Vec<String>>()
+                            .join("\n");
+                        if !self.is_translatable(&docstring) {
+                            continue;
+                        }
+                        let layout: Vec<serde_json::Value> = docstring::segments(&sections)
+                            .map(|(section, segment)| {
+                                let (line, range) = &segment.lines[0];
+                                serde_json::json!({
+                                    "line": site.line + *line as u32,
+                                    "lines": segment.lines.len(),
+                                    "indent": range.start,
+                                    "section": section.header,
+                                    "field": segment.field,
+                                })
+                            })
+                            .collect();
+                        TranslatableUnit::new(
+                            docstring,
                             UnitType::Docstring,
-                            start_line,
-                            1, // column
+                            site.line,
+                            site.column,
                         )
                         .with_metadata(serde_json::json!({
-                            "span": span,
-                            "end_line": end_line,
-                            "quote_style": quote
+                            "span": site.end_line - site.line + 1,
+                            "end_line": site.end_line,
+                            "quote_style": literal.quote,
+                            DOCSTRING_SEGMENTS_KEY: layout,
                         }))
                         .with_context(format!(
                             "Multi-line docstring at lines {}-{}",
-                            start_line, end_line
+                            site.line, site.end_line
                         ))
-                        .with_priority(Priority::High);
-
-                        // Detect language
-                        unit.detect_language();
-
-                        units.push(unit);
+                    } else {
+                        let docstring = literal.body.trim();
+                        if !self.is_translatable(docstring) {
+                            continue;
+                        }
+                        TranslatableUnit::new(
+                            docstring.to_string(),
+                            UnitType::Docstring,
+                            site.line,
+                            site.column,
+                        )
+                        .with_metadata(serde_json::json!({ "quote_style": literal.quote }))
+                        .with_context(format!("Docstring at line {}", site.line))
+                    }
+                    .with_priority(Priority::High);
+                    unit.detect_language();
+                    units.push(unit);
+                }
+                SiteKind::Sink(callee) => {
+                    let body = Literal::of(text).body;
+                    if !i18n::is_user_facing(body) {
+                        continue;
                     }
+                    let mut unit = TranslatableUnit::new(
+                        body.to_string(),
+                        UnitType::StringLiteral,
+                        site.line,
+                        site.column,
+                    )
+                    .with_context(format!("String passed to {} at line {}", callee, site.line))
+                    .with_priority(Priority::Medium)
+                    .with_metadata(serde_json::json!({ sinks::STRING_LITERAL_KEY: text }));
+                    unit.detect_language();
+                    units.push(unit);
                 }
             }
-
-            i += 1;
         }
 
         let line_count = lines.len() as u32;
-        let result = ParseResult::new("python", "utf-8", line_count)
+        let mut units = paragraph::merge_paragraphs(&lines, units, comment_prefix);
+        license::mark_license_headers(content, &mut units);
+
+        let mut result = ParseResult::new("python", "utf-8", line_count)
             .with_units(units)
             .with_metadata(serde_json::json!({
                 "parser": "PythonParser",
-                "version": "0.1.0",
+                "version": "0.2.0",
                 "file_path": path,
             }));
 
+        generated::mark_generated(content, &mut result);
+
         Ok(result)
     }
 
@@ -273,98 +279,395 @@ impl Parser for PythonParser {
         units: &[TranslatableUnit],
         _path: &str,
     ) -> Result<String> {
-        // Build a map of line numbers to translated content
-        let mut line_replacements: std::collections::HashMap<u32, String> =
-            std::collections::HashMap::new();
-        let mut lines_to_skip: std::collections::HashSet<u32> = std::collections::HashSet::new();
-
-        let lines: Vec<&str> = original.lines().collect();
-
+        let sites = sites(original)?;
+        let site_at = |line: u32, kind: fn(&SiteKind) -> bool| {
+            sites
+                .iter()
+                .find(|site| site.line == line && kind(&site.kind))
+        };
+        let is_comment = |kind: &SiteKind| matches!(kind, SiteKind::Comment);
+        let is_docstring = |kind: &SiteKind| matches!(kind, SiteKind::Docstring);
+
+        // Replacements of byte ranges of the original
+        let mut edits: Vec<(usize, usize, String)> = Vec::new();
         for unit in units {
-            let line_idx = (unit.line_number as usize).saturating_sub(1);
-            if line_idx >= lines.len() {
+            match unit.unit_type {
+                // Comment paragraphs, spreading the text over their lines
+                UnitType::Comment if paragraph::paragraph_lines(unit) > 1 => {
+                    let count = paragraph::paragraph_lines(unit);
+                    let pieces = paragraph::split_paragraph(&unit.content, count);
+                    for (offset, piece) in pieces.iter().enumerate() {
+                        let Some(site) = site_at(unit.line_number + offset as u32, is_comment)
+                        else {
+                            break;
+                        };
+                        let text = &original[site.start..site.end];
+                        let gap = comment_gap(text);
+                        let comment = if piece.is_empty() {
+                            "#".to_string()
+                        } else {
+                            format!("#{}{}", gap, piece)
+                        };
+                        edits.push((site.start, site.end, comment));
+                    }
+                }
+                UnitType::Comment => {
+                    let Some(site) = site_at(unit.line_number, is_comment) else {
+                        continue;
+                    };
+                    let text = &original[site.start..site.end];
+                    // Keep the original indent after `#` so diagrams stay aligned
+                    let gap = comment_gap(text);
+                    let comment = format!(
+                        "#{}{}",
+                        if gap.is_empty() { " " } else { gap },
+                        todo::comment_text(unit)
+                    );
+                    edits.push((
+                        site.start,
+                        site.end,
+                        pragma::with_trailing_pragma(unit, comment, text),
+                    ));
+                }
+                UnitType::Docstring => {
+                    let Some(site) = site_at(unit.line_number, is_docstring) else {
+                        continue;
+                    };
+                    let literal = Literal::of(&original[site.start..site.end]);
+                    let body = if literal.body.contains('\n') {
+                        relaid_docstring(&literal, &unit.content)
+                    } else {
+                        docstring_body(&unit.content, literal.quote, literal.is_raw())
+                    };
+                    edits.push((
+                        site.start,
+                        site.end,
+                        format!("{}{}{}", literal.start, body, literal.quote),
+                    ));
+                }
+                UnitType::StringLiteral if sinks::is_sink_string(unit) => {
+                    let source = unit
+                        .metadata
+                        .as_ref()
+                        .and_then(|m| m.get(sinks::STRING_LITERAL_KEY))
+                        .and_then(|s| s.as_str())
+                        .unwrap_or_default();
+                    let site = sites.iter().find(|site| {
+                        site.line == unit.line_number
+                            && site.column == unit.column_number
+                            && matches!(site.kind, SiteKind::Sink(_))
+                            && &original[site.start..site.end] == source
+                    });
+                    if let Some(site) = site {
+                        let quote = source.chars().next().unwrap_or('"');
+                        edits.push((
+                            site.start,
+                            site.end,
+                            sinks::quote_literal(&unit.content, quote),
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        edits.sort_by_key(|(start, _, _)| *start);
+        let mut result = String::with_capacity(original.len());
+        let mut pos = 0;
+        for (start, end, text) in edits {
+            // A unit given twice replaces its text once
+            if start < pos {
                 continue;
             }
+            result.push_str(&original[pos..start]);
+            result.push_str(&text);
+            pos = end;
+        }
+        result.push_str(&original[pos..]);
+        Ok(result)
+    }
+}
+
+/// What a [`Site`] holds
+#[derive(Debug, Clone, PartialEq)]
+enum SiteKind {
+    Comment,
+    /// Triple-quoted string standing alone as a statement
+    Docstring,
+    /// Plain string passed to a call that shows it to users
+    Sink(String),
+}
 
-            let line = lines[line_idx];
+/// Translatable text found in the syntax tree
+#[derive(Debug, Clone, PartialEq)]
+struct Site {
+    kind: SiteKind,
+    /// Byte span of the comment or literal, quotes included
+    start: usize,
+    end: usize,
+    line: u32,
+    end_line: u32,
+    /// Byte column, from 1
+    column: u32,
+}
+
+/// Comments, docstrings and sink strings of Python source, in order
+fn sites(content: &str) -> Result<Vec<Site>> {
+    let mut parser = tree_sitter::Parser::new();
+    parser
+        .set_language(tree_sitter_python::language())
+        .context("Failed to load the Python grammar")?;
+    let tree = parser
+        .parse(content, None)
+        .context("Failed to parse Python source")?;
+
+    let mut sites = Vec::new();
+    collect_sites(tree.root_node(), content, &mut sites);
+    Ok(sites)
+}
 
-            // Replace comments (single line)
-            if unit.unit_type == UnitType::Comment {
-                if let Some(hash_pos) = line.find('#') {
-                    let before_comment = &line[..hash_pos];
-                    let new_line = format!("{}# {}", before_comment, unit.content);
-                    line_replacements.insert(unit.line_number, new_line);
+fn collect_sites(node: Node, content: &str, sites: &mut Vec<Site>) {
+    let site = |node: Node, kind: SiteKind| Site {
+        kind,
+        start: node.start_byte(),
+        end: node.end_byte(),
+        line: node.start_position().row as u32 + 1,
+        end_line: node.end_position().row as u32 + 1,
+        column: node.start_position().column as u32 + 1,
+    };
+
+    match node.kind() {
+        "comment" => {
+            sites.push(site(node, SiteKind::Comment));
+            return;
+        }
+        "expression_statement" if node.named_child_count() == 1 => {
+            if let Some(string) = node.named_child(0).filter(|child| {
+                child.kind() == "string"
+                    && Literal::of(&content[child.byte_range()]).is_docstring()
+                    && !has_interpolation(*child)
+            }) {
+                sites.push(site(string, SiteKind::Docstring));
+                // Comments can still follow on the line
+                let mut cursor = node.walk();
+                for child in node.children(&mut cursor).skip(1) {
+                    collect_sites(child, content, sites);
                 }
+                return;
             }
-            // Replace docstrings
-            else if unit.unit_type == UnitType::Docstring {
-                // Get quote style from metadata, or detect from line
-                let quote_style = unit
-                    .metadata
-                    .as_ref()
-                    .and_then(|m| m.get("quote_style"))
-                    .and_then(|q| q.as_str())
-                    .map(|s| s.to_string())
-                    .unwrap_or_else(|| {
-                        // Fallback: detect from line
-                        if line.contains(r#"""""#) {
-                            r#"""""#.to_string()
-                        } else {
-                            "'''".to_string()
-                        }
-                    });
-
-                let indent = line
-                    .chars()
-                    .take_while(|c| c.is_whitespace())
-                    .collect::<String>();
-
-                // Check if it's multi-line (has span metadata)
-                let span = unit
-                    .metadata
-                    .as_ref()
-                    .and_then(|m| m.get("span"))
-                    .and_then(|s| s.as_u64())
-                    .unwrap_or(1) as u32;
-
-                if span == 1 {
-                    // Single-line docstring
-                    let new_line =
-                        format!("{}{}{}{}", indent, quote_style, unit.content, quote_style);
-                    line_replacements.insert(unit.line_number, new_line);
-                } else {
-                    // Multi-line docstring: collapse to single line
-                    let new_line =
-                        format!("{}{}{}{}", indent, quote_style, unit.content, quote_style);
-                    line_replacements.insert(unit.line_number, new_line);
-
-                    // Mark other lines for skipping
-                    for offset in 1..span {
-                        lines_to_skip.insert(unit.line_number + offset);
+        }
+        "call" => {
+            let callee = node
+                .child_by_field_name("function")
+                .map(|function| &content[function.byte_range()])
+                .filter(|callee| is_sink(callee));
+            let arguments = node
+                .child_by_field_name("arguments")
+                .filter(|arguments| arguments.kind() == "argument_list");
+            if let (Some(callee), Some(arguments)) = (callee, arguments) {
+                let mut cursor = arguments.walk();
+                for argument in arguments.named_children(&mut cursor) {
+                    if argument.kind() == "string"
+                        && Literal::of(&content[argument.byte_range()]).is_plain()
+                        && !has_interpolation(argument)
+                    {
+                        sites.push(site(argument, SiteKind::Sink(callee.to_string())));
                     }
                 }
             }
         }
+        _ => {}
+    }
 
-        // Reconstruct file line by line
-        let mut result_lines = Vec::new();
-        for (i, line) in lines.iter().enumerate() {
-            let line_num = (i + 1) as u32;
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_sites(child, content, sites);
+    }
+}
 
-            if lines_to_skip.contains(&line_num) {
-                // Skip this line (part of multi-line docstring)
-                continue;
-            }
+/// Check if a string node is an f-string with `{}` expressions
+fn has_interpolation(string: Node) -> bool {
+    let mut cursor = string.walk();
+    let found = string
+        .named_children(&mut cursor)
+        .any(|child| child.kind() == "interpolation");
+    found
+}
 
-            if let Some(new_line) = line_replacements.get(&line_num) {
-                result_lines.push(new_line.as_str());
-            } else {
-                result_lines.push(line);
+/// Check if a call shows its string arguments to users
+fn is_sink(callee: &str) -> bool {
+    let name = callee.rsplit('.').next().unwrap_or(callee);
+    match callee {
+        "print" | "input" => true,
+        // `logging.info`, `logger.warning`, `self.log.error`
+        _ if callee.contains('.') && callee.to_lowercase().contains("log") => matches!(
+            name,
+            "debug" | "info" | "warning" | "warn" | "error" | "critical" | "exception"
+        ),
+        // Exception constructors, as in `raise ValueError("...")`
+        _ => {
+            name.starts_with(|c: char| c.is_ascii_uppercase())
+                && (name.ends_with("Error") || name.ends_with("Exception"))
+        }
+    }
+}
+
+/// A string literal split into its parts
+struct Literal<'a> {
+    /// Prefix and opening quote (`r"""`)
+    start: &'a str,
+    quote: &'a str,
+    body: &'a str,
+}
+
+impl<'a> Literal<'a> {
+    fn of(text: &'a str) -> Self {
+        let prefix = text.find(['"', '\'']).unwrap_or(0);
+        let quote_char = &text[prefix..(prefix + 1).min(text.len())];
+        let quote = if text[prefix..].starts_with(&quote_char.repeat(3)) && text.len() >= prefix + 6
+        {
+            &text[prefix..prefix + 3]
+        } else {
+            quote_char
+        };
+        let body_end = text
+            .len()
+            .saturating_sub(quote.len())
+            .max(prefix + quote.len());
+        Self {
+            start: &text[..prefix + quote.len()],
+            quote,
+            body: &text[prefix + quote.len()..body_end],
+        }
+    }
+
+    fn prefix(&self) -> String {
+        self.start[..self.start.len() - self.quote.len()].to_lowercase()
+    }
+
+    fn is_raw(&self) -> bool {
+        self.prefix().contains('r')
+    }
+
+    /// Triple-quoted text, raw or not, but not bytes or an f-string
+    fn is_docstring(&self) -> bool {
+        self.quote.len() == 3 && matches!(self.prefix().as_str(), "" | "r" | "u")
+    }
+
+    /// A single-quoted line without prefix or escapes
+    fn is_plain(&self) -> bool {
+        self.quote.len() == 1 && self.prefix().is_empty() && !self.body.contains(['\\', '\n'])
+    }
+}
+
+/// Whitespace between `#` and the text of a comment
+fn comment_gap(comment: &str) -> &str {
+    let after_hash = &comment[1..];
+    &after_hash[..after_hash.len() - after_hash.trim_start().len()]
+}
+
+/// Docstring text that cannot end its literal early
+///
+/// The quote inside the text and a quote character right before the closing
+/// quote are escaped; raw strings cannot escape them, so the quote is
+/// broken up with a space instead.
+fn docstring_body(text: &str, quote: &str, raw: bool) -> String {
+    let quote_char = &quote[..1];
+    let last = if raw {
+        format!("{} ", quote_char)
+    } else {
+        format!("\\{}", quote_char)
+    };
+    let body = escape_quotes(text, quote, raw);
+    match body.strip_suffix(quote_char) {
+        Some(rest) if !rest.ends_with('\\') => format!("{}{}", rest, last),
+        _ => body,
+    }
+}
+
+/// Text with the docstring's quote escaped, or broken up in raw strings
+fn escape_quotes(text: &str, quote: &str, raw: bool) -> String {
+    let escaped = if raw {
+        format!("{} {}", &quote[..2], &quote[..1])
+    } else {
+        format!("\\{}", quote)
+    };
+    text.replace(quote, &escaped)
+}
+
+/// Unit metadata key holding the layout of a multi-line docstring: the
+/// first line, line count, text column, section and field of each segment
+pub const DOCSTRING_SEGMENTS_KEY: &str = "docstring_segments";
+
+/// Check if a unit is a multi-line docstring whose content holds one
+/// segment per line, each to be translated on its own
+pub fn is_segmented_docstring(unit: &TranslatableUnit) -> bool {
+    unit.unit_type == UnitType::Docstring
+        && unit
+            .metadata
+            .as_ref()
+            .is_some_and(|m| m.get(DOCSTRING_SEGMENTS_KEY).is_some())
+}
+
+/// Body of a multi-line docstring with `content`, one translated segment
+/// per line, in place of the text of its segments
+///
+/// Each segment keeps its lines, wrapping its translation over them; lines
+/// a short translation leaves empty are dropped. Indentation, headers,
+/// field names, code and the closing quote's line stay as they were. Extra
+/// lines of content join the last segment, and segments without a line
+/// keep their text.
+fn relaid_docstring(literal: &Literal, content: &str) -> String {
+    let lines: Vec<&str> = literal.body.split('\n').collect();
+    let sections = docstring::analyze(&lines);
+    let segments: Vec<&[(usize, Range<usize>)]> = docstring::segments(&sections)
+        .map(|(_, segment)| segment.lines.as_slice())
+        .collect();
+    let translations: Vec<&str> = content.split('\n').collect();
+    let raw = literal.is_raw();
+
+    let mut edits: BTreeMap<usize, (Range<usize>, String)> = BTreeMap::new();
+    for (k, segment) in segments.iter().enumerate() {
+        let text = if k + 1 == segments.len() && translations.len() > segments.len() {
+            translations[k..].join(" ")
+        } else {
+            match translations.get(k) {
+                Some(text) => text.to_string(),
+                None => continue,
             }
+        };
+        let pieces = paragraph::split_paragraph(&text, segment.len());
+        for ((line, range), piece) in segment.iter().zip(pieces) {
+            // Text right before the closing quote must not end the literal
+            let piece = if *line + 1 == lines.len() && range.end == lines[*line].len() {
+                docstring_body(&piece, literal.quote, raw)
+            } else {
+                escape_quotes(&piece, literal.quote, raw)
+            };
+            edits.insert(*line, (range.clone(), piece));
         }
+    }
 
-        Ok(result_lines.join("\n"))
+    let mut body = Vec::with_capacity(lines.len());
+    for (i, line) in lines.iter().enumerate() {
+        match edits.remove(&i) {
+            Some((range, piece)) if piece.is_empty() => {
+                if segments.iter().any(|segment| segment[0].0 == i) {
+                    body.push(line[..range.start].trim_end().to_string());
+                }
+            }
+            Some((range, piece)) => {
+                body.push(format!(
+                    "{}{}{}",
+                    &line[..range.start],
+                    piece,
+                    &line[range.end..]
+                ));
+            }
+            None => body.push(line.to_string()),
+        }
     }
+    body.join("\n")
 }
 
 #[cfg(test)]
@@ -504,6 +807,27 @@ def foo():
         assert!(result.units.is_empty());
     }
 
+    #[test]
+    fn test_todo_comment_keeps_marker() {
+        let parser = PythonParser::new();
+        let content = "# TODO: 修复这里\nx = 1\n# FIXME(bob): 处理空值";
+
+        let result = parser.extract_units(content, "test.py").unwrap();
+        assert_eq!(result.units.len(), 2);
+        assert_eq!(result.units[0].content, "修复这里");
+        assert!(todo::is_todo(&result.units[0]));
+        assert_eq!(todo::todo_marker(&result.units[1]), Some("FIXME(bob): "));
+
+        let mut units = result.units.clone();
+        units[0].content = "Fix this".to_string();
+        units[1].content = "Handle null".to_string();
+        let reconstructed = parser.reconstruct(content, &units, "test.py").unwrap();
+        assert_eq!(
+            reconstructed,
+            "# TODO: Fix this\nx = 1\n# FIXME(bob): Handle null"
+        );
+    }
+
     #[test]
     fn test_is_translatable() {
         let parser = PythonParser::new();
@@ -624,6 +948,81 @@ def foo():
         assert!(result.units[0].content.contains("缩进的注释"));
     }
 
+    #[test]
+    fn test_structured_docstring_keeps_lines() {
+        let parser = PythonParser::new();
+        let original = "def foo():\n    \"\"\"\n    处理步骤：\n    1. 读取数据\n    2. 写入结果\n    \"\"\"\n    pass";
+
+        let mut result = parser.extract_units(original, "test.py").unwrap();
+        assert_eq!(result.units.len(), 1);
+        assert_eq!(result.units[0].content, "处理步骤：\n读取数据\n写入结果");
+
+        result.units[0].content = "Steps:\nRead data\nWrite results".to_string();
+        let reconstructed = parser
+            .reconstruct(original, &result.units, "test.py")
+            .unwrap();
+        assert_eq!(
+            reconstructed,
+            "def foo():\n    \"\"\"\n    Steps:\n    1. Read data\n    2. Write results\n    \"\"\"\n    pass"
+        );
+    }
+
+    #[test]
+    fn test_docstring_keeps_layout() {
+        let parser = PythonParser::new();
+        let original = r#"def add(a, b):
+    """计算两个数的和，
+    不会溢出。
+
+    :param a: 第一个数
+    :returns: 两数之和，
+        类型与参数相同
+
+    Args:
+        b (int): 第二个数
+
+    Example::
+
+        add(1, 2)
+
+    >>> add(1, 2)
+    3
+    """
+"#;
+        let mut units = parser.extract_units(original, "calc.py").unwrap().units;
+        assert_eq!(units.len(), 1);
+        assert!(is_segmented_docstring(&units[0]));
+        assert_eq!(
+            units[0].content,
+            "计算两个数的和，不会溢出。\n第一个数\n两数之和，类型与参数相同\n第二个数\nExample::"
+        );
+
+        units[0].content = "Add two numbers; the result never overflows.\nThe first number\nThe sum, of the same type as the arguments\nThe second number\nExample::".to_string();
+        let reconstructed = parser.reconstruct(original, &units, "calc.py").unwrap();
+        assert_eq!(
+            reconstructed,
+            r#"def add(a, b):
+    """Add two numbers; the
+    result never overflows.
+
+    :param a: The first number
+    :returns: The sum, of the same
+        type as the arguments
+
+    Args:
+        b (int): The second number
+
+    Example::
+
+        add(1, 2)
+
+    >>> add(1, 2)
+    3
+    """
+"#
+        );
+    }
+
     #[test]
     fn test_parser_name() {
         let parser = PythonParser::new();
@@ -670,4 +1069,136 @@ def foo():
             assert_eq!(d.priority, Priority::High);
         }
     }
+
+    #[test]
+    fn test_box_table_comments() {
+        let parser = PythonParser::new();
+        let content = "#   ┌──────┬──────┐\n#   │ 名称 │ 说明 │\n#   └──────┴──────┘\nx = 1\n";
+        let result = parser.extract_units(content, "test.py").unwrap();
+
+        // Border rows are not prose; the labelled row is kept for cell-wise translation
+        assert_eq!(result.units.len(), 1);
+        assert_eq!(result.units[0].line_number, 2);
+
+        let mut unit = result.units[0].clone();
+        unit.content = "│ Name │ Note │".to_string();
+        let rebuilt = parser.reconstruct(content, &[unit], "test.py").unwrap();
+        assert!(rebuilt.contains("#   │ Name │ Note │\n"));
+    }
+
+    #[test]
+    fn test_comment_paragraph() {
+        let parser = PythonParser::new();
+        let content = "def load():\n    # 读取配置文件，\n    # 并返回解析后的字典\n    # TODO: 支持 YAML\n    return {}\n";
+        let result = parser.extract_units(content, "test.py").unwrap();
+
+        assert_eq!(result.units.len(), 2);
+        assert_eq!(result.units[0].content, "读取配置文件，并返回解析后的字典");
+        assert_eq!(result.units[0].line_number, 2);
+        assert_eq!(paragraph::paragraph_lines(&result.units[0]), 2);
+
+        let mut unit = result.units[0].clone();
+        unit.content = "Read the configuration file and return the parsed dict".to_string();
+        let rebuilt = parser.reconstruct(content, &[unit], "test.py").unwrap();
+        assert_eq!(
+            rebuilt,
+            "def load():\n    # Read the configuration file\n    # and return the parsed dict\n    # TODO: 支持 YAML\n    return {}\n"
+        );
+    }
+
+    #[test]
+    fn test_pragmas_are_not_translated() {
+        let parser = PythonParser::new();
+        let content = "#!/usr/bin/env python\n# -*- coding: utf-8 -*-\n#: 默认用户名\nNAME = 'x'\n# type: ignore  # 类型不匹配\n# 这一行太长了  # noqa: E501\n";
+        let result = parser.extract_units(content, "test.py").unwrap();
+
+        assert_eq!(result.units.len(), 1);
+        assert_eq!(result.units[0].content, "这一行太长了");
+        assert_eq!(
+            pragma::trailing_pragma(&result.units[0]),
+            Some("  # noqa: E501")
+        );
+
+        let mut unit = result.units[0].clone();
+        unit.content = "This line is too long".to_string();
+        let rebuilt = parser.reconstruct(content, &[unit], "test.py").unwrap();
+        assert!(rebuilt.contains("\n# This line is too long  # noqa: E501"));
+        assert!(
+            rebuilt.starts_with("#!/usr/bin/env python\n# -*- coding: utf-8 -*-\n#: 默认用户名\n")
+        );
+    }
+
+    #[test]
+    fn test_syntax_tree_boundaries() {
+        let parser = PythonParser::new();
+        let content = r#"URL = "http://x/#/首页"  # 首页地址
+TEMPLATE = """
+# 这不是注释
+"""
+
+
+def run():
+    """运行 "主" 任务
+
+    # 步骤也不是注释
+    """
+    s = '# 还是字符串'  # 真正的注释
+"#;
+        let result = parser.extract_units(content, "test.py").unwrap();
+        let units: Vec<(u32, u32, &str)> = result
+            .units
+            .iter()
+            .map(|unit| (unit.line_number, unit.column_number, unit.content.as_str()))
+            .collect();
+        assert_eq!(
+            units,
+            vec![
+                (1, 28, "首页地址"),
+                (8, 5, "运行 \"主\" 任务\n# 步骤也不是注释"),
+                (12, 30, "真正的注释"),
+            ]
+        );
+
+        let mut units = result.units.clone();
+        units[0].content = "Home page".to_string();
+        units[1].content = "Run \"main\"\n# Steps are not comments either".to_string();
+        units[2].content = "A real comment".to_string();
+        let rebuilt = parser.reconstruct(content, &units, "test.py").unwrap();
+        assert!(rebuilt.starts_with(
+            "URL = \"http://x/#/首页\"  # Home page\nTEMPLATE = \"\"\"\n# 这不是注释\n"
+        ));
+        assert!(rebuilt.contains(
+            "    \"\"\"Run \"main\"\n\n    # Steps are not comments either\n    \"\"\"\n"
+        ));
+        assert!(rebuilt.ends_with("    s = '# 还是字符串'  # A real comment\n"));
+        assert_eq!(
+            docstring_body("a \"\"\" b", "\"\"\"", false),
+            "a \\\"\"\" b"
+        );
+        assert_eq!(docstring_body("say \"hi\"", "\"\"\"", true), "say \"hi\" ");
+    }
+
+    #[test]
+    fn test_sink_strings() {
+        let parser = PythonParser::new();
+        let content = "print('开始处理', name)\nlogger.warning(\"文件不存在\")\nraise ValueError(f\"无效 {x}\")\nsave('不是输出')\n";
+        let result = parser.extract_units(content, "test.py").unwrap();
+        let strings: Vec<&TranslatableUnit> = result
+            .units
+            .iter()
+            .filter(|unit| sinks::is_sink_string(unit))
+            .collect();
+        assert_eq!(strings.len(), 2);
+        assert_eq!(strings[0].content, "开始处理");
+        assert_eq!(strings[1].content, "文件不存在");
+
+        let mut units: Vec<TranslatableUnit> = strings.into_iter().cloned().collect();
+        units[0].content = "Processing isn't done".to_string();
+        units[1].content = "File not found".to_string();
+        let rebuilt = parser.reconstruct(content, &units, "test.py").unwrap();
+        assert_eq!(
+            rebuilt,
+            "print('Processing isn\\'t done', name)\nlogger.warning(\"File not found\")\nraise ValueError(f\"无效 {x}\")\nsave('不是输出')\n"
+        );
+    }
 }
@@ -0,0 +1,735 @@
+//! reStructuredText parser (.rst files, Sphinx documentation)
+//!
+//! Section titles, paragraphs, list items and the bodies of prose
+//! directives (`note`, `warning`, `admonition`, ...) become `TextNode`
+//! units. Literal blocks (after `::`), doctests, tables, comments, link
+//! targets and the bodies of other directives (`code-block`, `toctree`,
+//! ...) are never extracted, and directive options stay as they are.
+//! Roles and link references inside the text are masked by the pipeline
+//! while translating; title adornments are resized to the translation.
+
+use anyhow::Result;
+use langlint_core::types::detect_language;
+use langlint_core::{ParseResult, Priority, TranslatableUnit, UnitType};
+use regex::Regex;
+use unicode_width::UnicodeWidthStr;
+
+use crate::Parser;
+
+/// Unit metadata key holding the markup around a unit's lines
+pub const RST_KEY: &str = "rst";
+
+/// Directives whose content is prose
+const PROSE_DIRECTIVES: &[&str] = &[
+    "admonition",
+    "attention",
+    "caution",
+    "danger",
+    "deprecated",
+    "epigraph",
+    "error",
+    "highlights",
+    "hint",
+    "important",
+    "note",
+    "pull-quote",
+    "rubric",
+    "seealso",
+    "sidebar",
+    "tip",
+    "todo",
+    "topic",
+    "versionadded",
+    "versionchanged",
+    "warning",
+];
+
+/// Prose directives whose argument is a version, not text
+const VERSION_DIRECTIVES: &[&str] = &["deprecated", "versionadded", "versionchanged"];
+
+/// Characters of section title adornments
+const ADORNMENT_CHARS: &str = "=-`:'\"~^_*+#<>";
+
+/// Parser for reStructuredText files
+pub struct RstParser {
+    list_item: Regex,
+    field: Regex,
+    directive: Regex,
+    footnote: Regex,
+    markup: Regex,
+}
+
+/// Lines of a unit with the markup around them
+struct Block {
+    start: usize,
+    /// Line after the unit's last line, adornments included
+    end: usize,
+    lines: Vec<String>,
+    /// Markup before the first line (`- `, `.. note:: `, indentation)
+    prefix: String,
+    /// Markup before each following line
+    continuation: String,
+    /// Markup after the last line (`::` introducing a literal block)
+    suffix: String,
+    /// Adornment of a section title: its character, whether it has an
+    /// overline, its length and the title's width
+    title: Option<(char, bool, usize, usize)>,
+}
+
+impl RstParser {
+    /// Create a new reStructuredText parser
+    pub fn new() -> Self {
+        Self {
+            list_item: Regex::new(
+                r"^(\s*(?:[-*+•‣⁃]|\d+[.)]|#[.)]|[a-zA-Z][.)]|\(\d+\)|\([a-zA-Z#]\))\s+)(\S.*)$",
+            )
+            .unwrap(),
+            field: Regex::new(r"^(\s*:[^:\s][^:]*:\s+)(\S.*)$").unwrap(),
+            directive: Regex::new(r"^(\s*\.\.\s+([A-Za-z][\w:+-]*)::)(\s*)(.*)$").unwrap(),
+            footnote: Regex::new(r"^(\s*\.\.\s+\[[^\]]+\]\s+)(\S.*)$").unwrap(),
+            markup: Regex::new(r"(?::[\w.+:-]+:)?`[^`]*`_{0,2}|\|[^|\s]+\||https?://\S+|<[^>]*>")
+                .unwrap(),
+        }
+    }
+
+    /// Check if a block's text is worth translating: prose outside roles,
+    /// literals and URLs, with non-ASCII characters
+    fn is_translatable(&self, text: &str) -> bool {
+        let prose = self.markup.replace_all(text, " ");
+        prose.chars().filter(|c| c.is_alphabetic()).count() >= 2 && !prose.is_ascii()
+    }
+
+    /// Split a document into the blocks holding its text
+    fn blocks(&self, lines: &[&str]) -> Vec<Block> {
+        let mut blocks = Vec::new();
+        let mut i = 0;
+        // Indentation a literal block announced by `::` must exceed
+        let mut literal_after: Option<usize> = None;
+
+        while i < lines.len() {
+            let line = lines[i];
+            let trimmed = line.trim_start();
+            let indent = indent_width(line);
+
+            if trimmed.is_empty() {
+                i += 1;
+                continue;
+            }
+            if let Some(base) = literal_after.take() {
+                if indent > base {
+                    i = block_end(lines, i, base);
+                    continue;
+                }
+            }
+
+            // Section title with an overline
+            if is_adornment(line)
+                && lines.get(i + 2).is_some_and(|under| is_adornment(under))
+                && lines.get(i + 1).is_some_and(|text| !text.trim().is_empty())
+            {
+                let text = lines[i + 1].trim();
+                blocks.push(Block {
+                    start: i + 1,
+                    end: i + 3,
+                    lines: vec![text.to_string()],
+                    prefix: String::new(),
+                    continuation: String::new(),
+                    suffix: String::new(),
+                    title: Some((
+                        line.trim().chars().next().unwrap_or('='),
+                        true,
+                        line.trim().chars().count(),
+                        lines[i + 1].trim_end().width(),
+                    )),
+                });
+                i += 3;
+                continue;
+            }
+            if is_adornment(line) {
+                // Transition
+                i += 1;
+                continue;
+            }
+            // Section title with an underline
+            if indent == 0 && lines.get(i + 1).is_some_and(|under| is_adornment(under)) {
+                let underline = lines[i + 1].trim();
+                blocks.push(Block {
+                    start: i,
+                    end: i + 2,
+                    lines: vec![trimmed.trim_end().to_string()],
+                    prefix: String::new(),
+                    continuation: String::new(),
+                    suffix: String::new(),
+                    title: Some((
+                        underline.chars().next().unwrap_or('='),
+                        false,
+                        underline.chars().count(),
+                        line.trim_end().width(),
+                    )),
+                });
+                i += 2;
+                continue;
+            }
+
+            if trimmed == ".." || trimmed.starts_with(".. ") {
+                if let Some(captures) = self.directive.captures(line) {
+                    let name = captures[2].to_lowercase();
+                    if !PROSE_DIRECTIVES.contains(&name.as_str()) {
+                        i = block_end(lines, i + 1, indent);
+                        continue;
+                    }
+                    let argument = &captures[4];
+                    let mut next = i + 1;
+                    if !argument.is_empty() && !VERSION_DIRECTIVES.contains(&name.as_str()) {
+                        let prefix = format!("{}{}", &captures[1], &captures[3]);
+                        let (block, end) = self.paragraph(lines, i, prefix, argument, indent);
+                        literal_after = block_literal(&block, indent);
+                        blocks.push(block);
+                        next = end;
+                    }
+                    // Options stay as they are; the content is parsed as usual
+                    while next < lines.len()
+                        && indent_width(lines[next]) > indent
+                        && lines[next].trim_start().starts_with(':')
+                    {
+                        next += 1;
+                    }
+                    i = next;
+                    continue;
+                }
+                if let Some(captures) = self.footnote.captures(line) {
+                    let (block, end) =
+                        self.paragraph(lines, i, captures[1].to_string(), &captures[2], indent);
+                    blocks.push(block);
+                    i = end;
+                    continue;
+                }
+                // Comment, link target or substitution definition
+                i = block_end(lines, i + 1, indent);
+                continue;
+            }
+            if trimmed.starts_with("__ ") || trimmed.starts_with(">>>") {
+                i = paragraph_end(lines, i);
+                continue;
+            }
+            if trimmed.starts_with("+-") || trimmed.starts_with("+=") {
+                // Grid table
+                i = paragraph_end(lines, i);
+                continue;
+            }
+            if is_simple_table_border(trimmed) {
+                i = simple_table_end(lines, i);
+                continue;
+            }
+
+            let (prefix, first) = match self
+                .list_item
+                .captures(line)
+                .or_else(|| self.field.captures(line))
+            {
+                Some(captures) => (captures[1].to_string(), captures[2].to_string()),
+                None => (
+                    line[..line.len() - trimmed.len()].to_string(),
+                    trimmed.to_string(),
+                ),
+            };
+            let column = prefix.chars().count();
+            let (block, end) = self.paragraph(lines, i, prefix, &first, column);
+            literal_after = block_literal(&block, column);
+            blocks.push(block);
+            i = end;
+        }
+
+        blocks
+    }
+
+    /// The paragraph starting with `first` after `prefix` on line `start`,
+    /// continued by the following lines indented to `column` (or deeper
+    /// than `column` after markup such as `.. note::`)
+    fn paragraph(
+        &self,
+        lines: &[&str],
+        start: usize,
+        prefix: String,
+        first: &str,
+        column: usize,
+    ) -> (Block, usize) {
+        let mut text = vec![first.trim_end().to_string()];
+        let explicit = prefix.trim_start().starts_with("..");
+        let mut continuation = None;
+        let mut i = start + 1;
+        while i < lines.len() {
+            let next = lines[i];
+            let indent = indent_width(next);
+            let continues = if explicit {
+                indent > column
+            } else {
+                indent == column
+            };
+            if next.trim().is_empty()
+                || !continues
+                || (explicit && next.trim_start().starts_with(':') && text.len() == 1)
+                || self.list_item.is_match(next)
+                || next.trim_start().starts_with("..")
+            {
+                break;
+            }
+            continuation
+                .get_or_insert_with(|| next[..next.len() - next.trim_start().len()].to_string());
+            text.push(next.trim().to_string());
+            i += 1;
+        }
+
+        // A trailing `::` announces a literal block; keep it out of the text
+        let mut suffix = String::new();
+        if let Some(last) = text.last_mut() {
+            if let Some(stripped) = last.strip_suffix("::") {
+                let kept = stripped.trim_end().len();
+                suffix = last[kept..].to_string();
+                last.truncate(kept);
+            }
+        }
+
+        let continuation = continuation.unwrap_or_else(|| " ".repeat(prefix.chars().count()));
+        let block = Block {
+            start,
+            end: i,
+            lines: text,
+            prefix,
+            continuation,
+            suffix,
+            title: None,
+        };
+        (block, i)
+    }
+}
+
+impl Default for RstParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Indentation of a literal block announced by a block's `::`, if any
+fn block_literal(block: &Block, column: usize) -> Option<usize> {
+    block.suffix.ends_with("::").then_some(column)
+}
+
+/// Indentation of a line in columns, tabs counting eight as in docutils
+fn indent_width(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 8 } else { 1 })
+        .sum()
+}
+
+/// Line after the block starting at `from` whose lines are blank or
+/// indented deeper than `indent`
+fn block_end(lines: &[&str], from: usize, indent: usize) -> usize {
+    let mut end = from;
+    let mut i = from;
+    while i < lines.len() && (lines[i].trim().is_empty() || indent_width(lines[i]) > indent) {
+        i += 1;
+        if !lines[i - 1].trim().is_empty() {
+            end = i;
+        }
+    }
+    end
+}
+
+/// Line after the non-blank lines starting at `from`
+fn paragraph_end(lines: &[&str], from: usize) -> usize {
+    (from..lines.len())
+        .find(|&i| lines[i].trim().is_empty())
+        .unwrap_or(lines.len())
+}
+
+/// `=====` repeated at least three times, at the start of a line
+fn is_adornment(line: &str) -> bool {
+    let trimmed = line.trim_end();
+    let Some(first) = trimmed.chars().next() else {
+        return false;
+    };
+    ADORNMENT_CHARS.contains(first)
+        && trimmed.chars().count() >= 3
+        && trimmed.chars().all(|c| c == first)
+}
+
+/// `=====  ======` border of a simple table
+fn is_simple_table_border(trimmed: &str) -> bool {
+    let trimmed = trimmed.trim_end();
+    trimmed.starts_with('=')
+        && trimmed.contains(' ')
+        && trimmed.chars().all(|c| c == '=' || c == ' ')
+}
+
+/// Line after a simple table: its last border is followed by a blank line
+fn simple_table_end(lines: &[&str], from: usize) -> usize {
+    (from + 1..lines.len())
+        .find(|&i| {
+            is_simple_table_border(lines[i].trim_start())
+                && lines.get(i + 1).is_none_or(|next| next.trim().is_empty())
+        })
+        .map_or(lines.len(), |i| i + 1)
+}
+
+/// Markup around a unit's lines, from its metadata
+struct Markup<'a> {
+    end: usize,
+    prefix: &'a str,
+    continuation: &'a str,
+    suffix: &'a str,
+    title: Option<(char, bool, usize, usize)>,
+}
+
+fn markup(unit: &TranslatableUnit) -> Option<Markup<'_>> {
+    let rst = unit.metadata.as_ref()?.get(RST_KEY)?;
+    let field = |key: &str| rst.get(key).and_then(|v| v.as_str()).unwrap_or("");
+    let number = |key: &str| rst.get(key).and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+    let title = field("adornment").chars().next().map(|c| {
+        (
+            c,
+            rst.get("overline")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            number("adornment_length"),
+            number("title_width"),
+        )
+    });
+    Some(Markup {
+        end: rst.get("end_line")?.as_u64()? as usize,
+        prefix: field("prefix"),
+        continuation: field("continuation"),
+        suffix: field("suffix"),
+        title,
+    })
+}
+
+/// Lines replacing a unit
+fn replacement(unit: &TranslatableUnit, markup: &Markup) -> Vec<String> {
+    if let Some((c, overline, length, width)) = markup.title {
+        let title = unit
+            .content
+            .lines()
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join(" ");
+        // An adornment matching the title is resized, a longer one only grows
+        let new_width = title.width();
+        let length = if length == width {
+            new_width
+        } else {
+            length.max(new_width)
+        };
+        let adornment = c.to_string().repeat(length.max(3));
+        let mut lines = Vec::new();
+        if overline {
+            lines.push(adornment.clone());
+        }
+        lines.push(title);
+        lines.push(adornment);
+        return lines;
+    }
+
+    let count = unit.content.lines().count();
+    unit.content
+        .lines()
+        .enumerate()
+        .map(|(k, text)| {
+            let mut line = match k {
+                0 => format!("{}{}", markup.prefix, text),
+                _ => format!("{}{}", markup.continuation, text),
+            };
+            if k + 1 == count {
+                line.push_str(markup.suffix);
+            }
+            // No trailing spaces from a prefix before an empty line
+            if text.is_empty() {
+                line.truncate(line.trim_end().len());
+            }
+            line
+        })
+        .collect()
+}
+
+impl Parser for RstParser {
+    fn name(&self) -> &'static str {
+        "reStructuredText"
+    }
+
+    fn supported_extensions(&self) -> &'static [&'static str] {
+        &[".rst", ".rest"]
+    }
+
+    fn can_parse(&self, path: &str, _content: Option<&str>) -> bool {
+        let lower = path.to_lowercase();
+        self.supported_extensions()
+            .iter()
+            .any(|ext| lower.ends_with(ext))
+    }
+
+    fn extract_units(&self, content: &str, _path: &str) -> Result<ParseResult> {
+        let lines: Vec<&str> = content.lines().collect();
+        let units = self
+            .blocks(&lines)
+            .into_iter()
+            .filter(|block| self.is_translatable(&block.lines.join("\n")))
+            .map(|block| {
+                let (adornment, overline, length, width) = match block.title {
+                    Some((c, overline, length, width)) => (c.to_string(), overline, length, width),
+                    None => (String::new(), false, 0, 0),
+                };
+                let mut unit = TranslatableUnit::new(
+                    block.lines.join("\n"),
+                    UnitType::TextNode,
+                    (block.start + 1) as u32,
+                    (block.prefix.chars().count() + 1) as u32,
+                )
+                .with_metadata(serde_json::json!({
+                    RST_KEY: {
+                        "end_line": block.end,
+                        "prefix": block.prefix,
+                        "continuation": block.continuation,
+                        "suffix": block.suffix,
+                        "adornment": adornment,
+                        "overline": overline,
+                        "adornment_length": length,
+                        "title_width": width,
+                    }
+                }))
+                .with_priority(if block.title.is_some() {
+                    Priority::High
+                } else {
+                    Priority::Medium
+                });
+                // Markup such as links and roles would pass for English
+                unit.detected_language =
+                    detect_language(&self.markup.replace_all(&unit.content, " "));
+                unit
+            })
+            .collect();
+
+        Ok(ParseResult::new("rst", "utf-8", lines.len() as u32).with_units(units))
+    }
+
+    fn reconstruct(
+        &self,
+        original: &str,
+        units: &[TranslatableUnit],
+        _path: &str,
+    ) -> Result<String> {
+        let newline = if original.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        let mut lines: Vec<String> = original.lines().map(str::to_string).collect();
+
+        let mut units: Vec<&TranslatableUnit> = units.iter().collect();
+        units.sort_by_key(|unit| std::cmp::Reverse(unit.line_number));
+        for unit in units {
+            let Some(markup) = markup(unit) else {
+                continue;
+            };
+            let overline = markup.title.is_some_and(|(_, overline, _, _)| overline);
+            let start = (unit.line_number as usize)
+                .saturating_sub(1)
+                .saturating_sub(overline as usize);
+            if start >= markup.end || markup.end > lines.len() {
+                continue;
+            }
+            lines.splice(start..markup.end, replacement(unit, &markup));
+        }
+
+        let mut result = lines.join(newline);
+        if original.ends_with('\n') {
+            result.push_str(newline);
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = "========
+安装指南
+========
+
+.. contents::
+   :depth: 2
+
+本项目需要 Python 3.9 以上版本，
+请参见 :ref:`快速开始 <quickstart>`。
+
+- 下载源码
+- 运行 ``pip install .`` 安装
+
+示例如下::
+
+    # 安装依赖
+    pip install langlint
+
+.. note::
+   :class: custom
+
+   这是实验功能，接口可能变化。
+
+.. code-block:: bash
+
+   # 构建文档
+   make html
+
+.. 这是一条注释
+
+快速开始
+--------
+";
+
+    #[test]
+    fn test_extract_units() {
+        let parser = RstParser::new();
+        assert!(parser.can_parse("docs/index.rst", None));
+        assert!(!parser.can_parse("docs/index.md", None));
+
+        let result = parser.extract_units(DOC, "index.rst").unwrap();
+        let units: Vec<(u32, &str, Priority)> = result
+            .units
+            .iter()
+            .map(|unit| (unit.line_number, unit.content.as_str(), unit.priority))
+            .collect();
+        assert_eq!(
+            units,
+            vec![
+                (2, "安装指南", Priority::High),
+                (
+                    8,
+                    "本项目需要 Python 3.9 以上版本，\n请参见 :ref:`快速开始 <quickstart>`。",
+                    Priority::Medium
+                ),
+                (11, "下载源码", Priority::Medium),
+                (12, "运行 ``pip install .`` 安装", Priority::Medium),
+                (14, "示例如下", Priority::Medium),
+                (22, "这是实验功能，接口可能变化。", Priority::Medium),
+                (31, "快速开始", Priority::High),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reconstruct() {
+        let parser = RstParser::new();
+        let mut units = parser.extract_units(DOC, "index.rst").unwrap().units;
+        units[0].content = "Installation Guide".to_string();
+        units[1].content =
+            "Requires Python 3.9 or later,\nsee :ref:`快速开始 <quickstart>`.".to_string();
+        units[4].content = "For example".to_string();
+        units[5].content = "This feature is experimental.".to_string();
+        units[6].content = "Quick start".to_string();
+
+        let rebuilt = parser.reconstruct(DOC, &units, "index.rst").unwrap();
+        assert!(rebuilt.starts_with(
+            "==================\nInstallation Guide\n==================\n\n.. contents::\n   :depth: 2\n"
+        ));
+        assert!(rebuilt
+            .contains("\nRequires Python 3.9 or later,\nsee :ref:`快速开始 <quickstart>`.\n"));
+        assert!(rebuilt.contains("\nFor example::\n\n    # 安装依赖\n"));
+        assert!(
+            rebuilt.contains(".. note::\n   :class: custom\n\n   This feature is experimental.\n")
+        );
+        assert!(rebuilt.contains("   # 构建文档\n"));
+        assert!(rebuilt.ends_with("Quick start\n-----------\n"));
+
+        // Unchanged units give back the original
+        let units = parser.extract_units(DOC, "index.rst").unwrap().units;
+        assert_eq!(parser.reconstruct(DOC, &units, "index.rst").unwrap(), DOC);
+    }
+
+    #[test]
+    fn test_round_trip_directives_and_literal_blocks() {
+        let doc = "用法
+====
+
+.. note:: 这是同一行的提示，
+   并在下一行继续。
+
+   提示中的示例：
+
+   .. code-block:: python
+
+      # 不要翻译
+      run()
+
+.. deprecated:: 1.2
+   请改用新接口。
+
+.. admonition:: 自定义标题
+
+   自定义提示的内容。
+
+下面是一个展开形式的字面块： ::
+
+    保留 原样
+
+>>> print(\"你好\")
+你好
+
+=====  =====
+名称   说明
+=====  =====
+a      第一项
+=====  =====
+
+.. toctree::
+   :maxdepth: 2
+
+   安装
+";
+        let parser = RstParser::new();
+        let mut units = parser.extract_units(doc, "usage.rst").unwrap().units;
+        let contents: Vec<&str> = units.iter().map(|unit| unit.content.as_str()).collect();
+        assert_eq!(
+            contents,
+            [
+                "用法",
+                "这是同一行的提示，\n并在下一行继续。",
+                "提示中的示例：",
+                "请改用新接口。",
+                "自定义标题",
+                "自定义提示的内容。",
+                "下面是一个展开形式的字面块：",
+            ]
+        );
+
+        let translations = [
+            "Usage",
+            "A hint on the directive line,\ncontinued on the next.",
+            "An example in the hint:",
+            "Use the new API instead.",
+            "Custom title",
+            "The custom hint's body.",
+            "Below is an expanded literal block:",
+        ];
+        for (unit, translation) in units.iter_mut().zip(translations) {
+            unit.content = translation.to_string();
+        }
+        let rebuilt = parser.reconstruct(doc, &units, "usage.rst").unwrap();
+        assert_eq!(
+            rebuilt,
+            doc.replace("用法\n====", "Usage\n=====")
+                .replace(
+                    "这是同一行的提示，\n   并在下一行继续。",
+                    "A hint on the directive line,\n   continued on the next."
+                )
+                .replace("提示中的示例：", "An example in the hint:")
+                .replace("请改用新接口。", "Use the new API instead.")
+                .replace("自定义标题", "Custom title")
+                .replace("自定义提示的内容。", "The custom hint's body.")
+                .replace(
+                    "下面是一个展开形式的字面块：",
+                    "Below is an expanded literal block:"
+                )
+        );
+    }
+}
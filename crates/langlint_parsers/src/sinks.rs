@@ -0,0 +1,488 @@
+//! User-facing string literals of JavaScript/TypeScript code
+//!
+//! For front-end localization audits, [`extract_sink_strings`] finds the
+//! literals passed to calls that show text to users: `console.log` and its
+//! siblings, `alert`/`confirm`/`prompt`, `Error` constructors and the
+//! fallback text of `t()` lookups (`t('key', '保存')` or
+//! `{ defaultValue: '保存' }`). Template literals are included; their
+//! `${}` expressions are masked like any other placeholder while
+//! translating. [`extract_code_sink_strings`] does the same for the
+//! double-quoted strings of Go, Rust, Java, Kotlin, C#, Swift and C/C++,
+//! passed to `fmt.Println`, `println!`, `System.out.println`,
+//! `Console.WriteLine`, `printf`, loggers, `panic` or exception
+//! constructors. Parsers always extract these units and runs drop them
+//! unless asked for (see [`is_sink_string`]).
+
+use langlint_core::{Priority, TranslatableUnit, UnitType};
+
+use crate::i18n::{self, StringLiteral};
+
+/// Unit metadata key holding the literal as written, quotes included
+pub const STRING_LITERAL_KEY: &str = "string_literal";
+
+/// An open bracket of the code before a literal
+struct Bracket {
+    open: u8,
+    /// Function called, for `(` of a call
+    callee: String,
+    /// Commas seen so far, i.e. the index of the current argument
+    commas: usize,
+}
+
+/// Check if a unit is a literal found by [`extract_sink_strings`]
+pub fn is_sink_string(unit: &TranslatableUnit) -> bool {
+    unit.unit_type == UnitType::StringLiteral
+        && unit
+            .metadata
+            .as_ref()
+            .is_some_and(|m| m.get(STRING_LITERAL_KEY).is_some())
+}
+
+/// Find user-facing literals passed to known sinks in script code
+pub fn extract_sink_strings(content: &str) -> Vec<TranslatableUnit> {
+    sink_units(content, i18n::find_script_strings(content), is_sink)
+}
+
+/// Find user-facing literals passed to known sinks in Go, Rust, Java,
+/// Kotlin, C#, Swift or C/C++ code
+pub fn extract_code_sink_strings(content: &str) -> Vec<TranslatableUnit> {
+    sink_units(content, find_code_strings(content), is_code_sink)
+}
+
+fn sink_units(
+    content: &str,
+    literals: Vec<StringLiteral>,
+    is_sink: fn(&str, usize) -> bool,
+) -> Vec<TranslatableUnit> {
+    let mut units = Vec::new();
+    let mut brackets: Vec<Bracket> = Vec::new();
+    let mut pos = 0;
+
+    for literal in literals {
+        track_brackets(content, pos, literal.start, &mut brackets);
+        pos = literal.end;
+
+        let before = content[..literal.start].trim_end();
+        let sink = match brackets.last() {
+            _ if before.ends_with("defaultValue:") => "defaultValue",
+            Some(bracket) if bracket.open == b'(' && is_sink(&bracket.callee, bracket.commas) => {
+                bracket.callee.as_str()
+            }
+            _ => continue,
+        };
+        if !i18n::is_user_facing(&literal.value) {
+            continue;
+        }
+
+        let line_start = content[..literal.start].rfind('\n').map_or(0, |p| p + 1);
+        let mut unit = TranslatableUnit::new(
+            literal.value,
+            UnitType::StringLiteral,
+            literal.line as u32,
+            (literal.start - line_start + 1) as u32,
+        )
+        .with_context(format!(
+            "String passed to {} at line {}",
+            sink, literal.line
+        ))
+        .with_priority(Priority::Medium)
+        .with_metadata(serde_json::json!({
+            STRING_LITERAL_KEY: &content[literal.start..literal.end],
+        }));
+        unit.detect_language();
+        units.push(unit);
+    }
+
+    units
+}
+
+/// Replace the literal of a unit from [`extract_sink_strings`] on its line
+///
+/// Returns `None` if the line no longer holds the literal where it was
+/// found.
+pub fn replace_literal(line: &str, unit: &TranslatableUnit) -> Option<String> {
+    let source = unit.metadata.as_ref()?.get(STRING_LITERAL_KEY)?.as_str()?;
+    let start = (unit.column_number as usize).checked_sub(1)?;
+    if !line.get(start..)?.starts_with(source) {
+        return None;
+    }
+    let quote = source.chars().next()?;
+    Some(format!(
+        "{}{}{}",
+        &line[..start],
+        quote_literal(&unit.content, quote),
+        &line[start + source.len()..]
+    ))
+}
+
+/// Write text as a literal with the given quote
+///
+/// Template bodies are kept raw, so only their bare backticks are escaped.
+pub(crate) fn quote_literal(text: &str, quote: char) -> String {
+    let mut literal = String::with_capacity(text.len() + 2);
+    literal.push(quote);
+    let mut escaped = false;
+    for c in text.chars() {
+        match c {
+            '\n' => literal.push_str("\\n"),
+            '\t' if quote != '`' => literal.push_str("\\t"),
+            '\\' if quote != '`' => literal.push_str("\\\\"),
+            c if c == quote && !escaped => {
+                literal.push('\\');
+                literal.push(c);
+            }
+            c => literal.push(c),
+        }
+        escaped = quote == '`' && c == '\\' && !escaped;
+    }
+    literal.push(quote);
+    literal
+}
+
+/// Follow the brackets of `content[from..to]`, which holds no literals
+///
+/// Comments and multi-line template literals are skipped.
+fn track_brackets(content: &str, from: usize, to: usize, brackets: &mut Vec<Bracket>) {
+    let code = &content[..to];
+    let bytes = code.as_bytes();
+    let mut i = from;
+    while i < to {
+        let rest = &code[i..];
+        match bytes[i] {
+            b'/' if rest.starts_with("//") => i += rest.find('\n').unwrap_or(rest.len()),
+            b'/' if rest.starts_with("/*") => i += rest.find("*/").map_or(rest.len(), |p| p + 2),
+            b'`' => i += rest[1..].find('`').map_or(rest.len(), |p| p + 2),
+            b'\'' => i += char_literal_len(rest).unwrap_or(1),
+            open @ (b'(' | b'[' | b'{') => {
+                brackets.push(Bracket {
+                    open,
+                    callee: callee(&code[..i]).to_string(),
+                    commas: 0,
+                });
+                i += 1;
+            }
+            b')' | b']' | b'}' => {
+                brackets.pop();
+                i += 1;
+            }
+            b',' => {
+                if let Some(bracket) = brackets.last_mut() {
+                    bracket.commas += 1;
+                }
+                i += 1;
+            }
+            _ => i += rest.chars().next().map_or(1, char::len_utf8),
+        }
+    }
+}
+
+/// Name of the function called by a `(` following `before`
+fn callee(before: &str) -> &str {
+    let before = before.trim_end();
+    let start = before
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_alphanumeric() || matches!(c, '_' | '$' | '.' | ':' | '!'))
+        .last()
+        .map_or(before.len(), |(i, _)| i);
+    let callee = before[start..].trim_start_matches('!');
+    // `cond ? a :alert(` calls `alert`, `std::puts(` calls `std::puts`
+    match callee.rsplit_once(':') {
+        Some((head, name)) if !head.ends_with(':') => name,
+        _ => callee,
+    }
+}
+
+/// Check if argument `argument` of a call to `callee` is shown to users
+fn is_sink(callee: &str, argument: usize) -> bool {
+    let callee = callee.strip_prefix("window.").unwrap_or(callee);
+    match callee {
+        "console.log" | "console.info" | "console.warn" | "console.error" | "console.debug" => true,
+        "alert" | "confirm" | "prompt" => true,
+        "Error" | "TypeError" | "RangeError" => true,
+        // The first argument of a lookup is its key
+        "t" | "i18n.t" | "i18next.t" | "$t" | "this.$t" => argument > 0,
+        _ => false,
+    }
+}
+
+/// Check if argument `argument` of a call to `callee` in Go, Rust, Java,
+/// Kotlin, C#, Swift or C/C++ code is shown to users
+fn is_code_sink(callee: &str, argument: usize) -> bool {
+    let name = callee.rsplit(['.', ':']).next().unwrap_or(callee);
+    let logger = |method: &str| {
+        matches!(
+            method.to_lowercase().as_str(),
+            "print"
+                | "printf"
+                | "println"
+                | "fatal"
+                | "fatalf"
+                | "panic"
+                | "panicf"
+                | "trace"
+                | "debug"
+                | "debugf"
+                | "info"
+                | "infof"
+                | "warn"
+                | "warnf"
+                | "warning"
+                | "error"
+                | "errorf"
+                | "severe"
+                | "critical"
+                | "logdebug"
+                | "loginformation"
+                | "logwarning"
+                | "logerror"
+                | "logcritical"
+        )
+    };
+    match callee {
+        // Go
+        "fmt.Print" | "fmt.Println" | "fmt.Printf" | "fmt.Errorf" | "errors.New" | "panic" => true,
+        "fmt.Fprint" | "fmt.Fprintln" | "fmt.Fprintf" => argument > 0,
+        // Rust
+        "println!" | "print!" | "eprintln!" | "eprint!" | "panic!" | "anyhow!" | "bail!"
+        | "unreachable!" | "unimplemented!" => true,
+        "assert!" | "ensure!" => argument > 0,
+        // Java, Kotlin, C# and Swift
+        "System.out.println"
+        | "System.out.print"
+        | "System.out.printf"
+        | "System.err.println"
+        | "System.err.print"
+        | "System.err.printf"
+        | "println"
+        | "print"
+        | "Console.WriteLine"
+        | "Console.Write"
+        | "Console.Error.WriteLine"
+        | "Debug.WriteLine"
+        | "MessageBox.Show"
+        | "fatalError" => true,
+        "JOptionPane.showMessageDialog" => argument == 1,
+        // C and C++
+        "printf" | "puts" | "perror" | "std::puts" => true,
+        "fprintf" | "std::fprintf" => argument > 0,
+        // `log::info!`, `tracing::warn!`
+        _ if name.ends_with('!') => matches!(
+            name.trim_end_matches('!'),
+            "trace" | "debug" | "info" | "warn" | "error"
+        ),
+        // `log.Printf`, `logger.warning`, `_logger.LogError`
+        _ if callee.contains('.') && callee.to_lowercase().contains("log") => logger(name),
+        // `result.expect("...")`, `.context("...")`
+        _ if callee.contains('.') && matches!(name, "expect" | "context") => true,
+        // Exception constructors: `new IllegalStateException("...")`,
+        // `std::runtime_error("...")`
+        _ => {
+            (name.starts_with(|c: char| c.is_ascii_uppercase())
+                && (name.ends_with("Error") || name.ends_with("Exception")))
+                || matches!(
+                    name,
+                    "runtime_error"
+                        | "logic_error"
+                        | "invalid_argument"
+                        | "out_of_range"
+                        | "domain_error"
+                        | "length_error"
+                        | "range_error"
+                        | "overflow_error"
+                )
+        }
+    }
+}
+
+/// Find the double-quoted strings of C-family code
+///
+/// Comments, char literals, Go raw strings and prefixed strings (Rust raw,
+/// C# verbatim or interpolated, C++ `u8"..."`) are skipped, as are strings
+/// with escapes other than `\n`, `\t`, `\"`, `\'` and `\\`, which could
+/// not be written back as they were.
+fn find_code_strings(content: &str) -> Vec<StringLiteral> {
+    let bytes = content.as_bytes();
+    let mut literals = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let rest = &content[i..];
+        let len = match bytes[i] {
+            b'/' if rest.starts_with("//") => rest.find('\n').unwrap_or(rest.len()),
+            b'/' if rest.starts_with("/*") => rest.find("*/").map_or(rest.len(), |p| p + 2),
+            b'`' => rest[1..].find('`').map_or(rest.len(), |p| p + 2),
+            b'\'' => char_literal_len(rest).unwrap_or(1),
+            b'"' if content[..i].ends_with(|c: char| c.is_alphanumeric() || "_@$#".contains(c)) => {
+                // Rust raw strings end at a quote followed by as many `#`
+                let hashes = content[..i].len() - content[..i].trim_end_matches('#').len();
+                let closing = format!("\"{}", "#".repeat(hashes));
+                let raw = content[..i].trim_end_matches('#').ends_with('r');
+                match raw.then(|| rest[1..].find(&closing)) {
+                    Some(end) => end.map_or(rest.len(), |p| p + 1 + closing.len()),
+                    None => quoted_len(rest).unwrap_or(1),
+                }
+            }
+            b'"' => match quoted_len(rest) {
+                Some(len) => {
+                    let body = &rest[1..len - 1];
+                    if let Some(value) = unescape(body) {
+                        literals.push(StringLiteral {
+                            start: i,
+                            end: i + len,
+                            line,
+                            value,
+                        });
+                    }
+                    len
+                }
+                None => 1,
+            },
+            _ => rest.chars().next().map_or(1, char::len_utf8),
+        };
+        line += rest[..len].matches('\n').count();
+        i += len;
+    }
+    literals
+}
+
+/// Length of a `"` string on one line, closing quote included
+fn quoted_len(text: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut i = 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'\n' => return None,
+            b'"' => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Length of a char literal such as `'x'` or `'\n'`, `None` for a lone
+/// quote (a Rust lifetime, say)
+fn char_literal_len(text: &str) -> Option<usize> {
+    let mut chars = text.char_indices().skip(1);
+    let (_, first) = chars.next()?;
+    if first == '\\' {
+        chars.next()?;
+    }
+    let (end, close) = chars.next()?;
+    (close == '\'' && first != '\'').then_some(end + 1)
+}
+
+/// Resolve the escapes of a string body, `None` if it has others than
+/// those [`quote_literal`] writes back
+fn unescape(body: &str) -> Option<String> {
+    let mut value = String::with_capacity(body.len());
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => value.push('\n'),
+            't' => value.push('\t'),
+            c @ ('"' | '\'' | '\\') => value.push(c),
+            _ => return None,
+        }
+    }
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_sink_strings() {
+        let code = r#"// alert('注释里的')
+const id = 'user_name';
+console.log('开始加载', id);
+label(t('save.ok', '保存成功'), notify('不是输出'));
+if (!ok) throw new Error(`加载 ${id} 失败`);
+i18n.t('greeting', { defaultValue: 'Hello there' });
+alert(
+  "请确认"
+);
+notify(标题, '不是输出');
+"#;
+        let units: Vec<(u32, String)> = extract_sink_strings(code)
+            .into_iter()
+            .map(|unit| (unit.line_number, unit.content))
+            .collect();
+        assert_eq!(
+            units,
+            vec![
+                (3, "开始加载".to_string()),
+                (4, "保存成功".to_string()),
+                (5, "加载 ${id} 失败".to_string()),
+                (6, "Hello there".to_string()),
+                (8, "请确认".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_replace_literal() {
+        let line = r#"  alert('保存成功', "其它");"#;
+        let unit = &extract_sink_strings(line)[0];
+        assert!(is_sink_string(unit));
+
+        let mut translated = unit.clone();
+        translated.content = "It's saved".to_string();
+        assert_eq!(
+            replace_literal(line, &translated).as_deref(),
+            Some(r#"  alert('It\'s saved', "其它");"#)
+        );
+        assert_eq!(replace_literal("alert(x);", &translated), None);
+
+        assert_eq!(quote_literal("a `b` ${c}", '`'), r"`a \`b\` ${c}`");
+        assert_eq!(quote_literal(r"a \`b\`", '`'), r"`a \`b\``");
+    }
+
+    #[test]
+    fn test_extract_code_sink_strings() {
+        let code = r#"// fmt.Println("注释里的")
+fmt.Println("开始加载", name)
+log.Printf("加载 %s 失败", path)
+let re = Regex::new(r"^\d+ 个$");
+println!("共有 {} 个文件", count);
+matches!(c, '"' | '(');
+throw new IllegalStateException("状态无效");
+Console.WriteLine($"你好 {name}");
+logger.LogWarning("磁盘空间不足");
+fprintf(stderr, "无法打开文件\n");
+value.expect("配置缺失");
+fn first<'a>(items: &'a [&str]) -> &'a str { notify("不是输出") }
+"#;
+        let units: Vec<(u32, String)> = extract_code_sink_strings(code)
+            .into_iter()
+            .map(|unit| (unit.line_number, unit.content))
+            .collect();
+        assert_eq!(
+            units,
+            vec![
+                (2, "开始加载".to_string()),
+                (3, "加载 %s 失败".to_string()),
+                (5, "共有 {} 个文件".to_string()),
+                (7, "状态无效".to_string()),
+                (9, "磁盘空间不足".to_string()),
+                (10, "无法打开文件\n".to_string()),
+                (11, "配置缺失".to_string()),
+            ]
+        );
+
+        let line = r#"    fmt.Println("开始加载", name)"#;
+        let mut unit = extract_code_sink_strings(line).remove(0);
+        unit.content = "Say \"hi\"\n".to_string();
+        assert_eq!(
+            replace_literal(line, &unit).as_deref(),
+            Some(r#"    fmt.Println("Say \"hi\"\n", name)"#)
+        );
+    }
+}
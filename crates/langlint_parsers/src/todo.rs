@@ -0,0 +1,81 @@
+//! TODO/FIXME marker handling for comments
+//!
+//! Parsers extract the body of marker comments such as `TODO: ...` or
+//! `FIXME(alice): ...` as the unit content and keep the marker in unit
+//! metadata, so the marker survives translation. Callers decide whether
+//! these units are translated at all (see [`is_todo`]).
+
+use langlint_core::TranslatableUnit;
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// Unit metadata key holding the marker prefix
+pub const TODO_MARKER_KEY: &str = "todo_marker";
+
+/// Split a leading marker (`TODO:`, `FIXME(name):`, `XXX`...) from comment text
+///
+/// Returns the marker including its trailing separator and the remaining
+/// body, or `None` if the text does not start with a marker.
+pub fn split_todo_marker(text: &str) -> Option<(&str, &str)> {
+    static MARKER_RE: OnceLock<Regex> = OnceLock::new();
+    let re = MARKER_RE.get_or_init(|| {
+        Regex::new(r"^(?:TODO|FIXME|XXX|HACK|BUG)(?:\([^)]*\))?(?::\s*|\s+|$)").unwrap()
+    });
+
+    re.find(text).map(|m| (&text[..m.end()], &text[m.end()..]))
+}
+
+/// Marker prefix recorded on a unit, if it came from a marker comment
+pub fn todo_marker(unit: &TranslatableUnit) -> Option<&str> {
+    unit.metadata
+        .as_ref()
+        .and_then(|m| m.get(TODO_MARKER_KEY))
+        .and_then(|m| m.as_str())
+}
+
+/// Check if a unit is the body of a TODO-style comment
+pub fn is_todo(unit: &TranslatableUnit) -> bool {
+    todo_marker(unit).is_some()
+}
+
+/// Comment text of a unit with its marker prefix restored
+pub fn comment_text(unit: &TranslatableUnit) -> String {
+    format!("{}{}", todo_marker(unit).unwrap_or_default(), unit.content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use langlint_core::UnitType;
+
+    #[test]
+    fn test_split_todo_marker() {
+        assert_eq!(
+            split_todo_marker("TODO: 修复这里"),
+            Some(("TODO: ", "修复这里"))
+        );
+        assert_eq!(
+            split_todo_marker("FIXME(alice): 处理空值"),
+            Some(("FIXME(alice): ", "处理空值"))
+        );
+        assert_eq!(
+            split_todo_marker("XXX 临时方案"),
+            Some(("XXX ", "临时方案"))
+        );
+        assert_eq!(split_todo_marker("TODO"), Some(("TODO", "")));
+        assert_eq!(split_todo_marker("TODOS 列表"), None);
+        assert_eq!(split_todo_marker("这是 TODO: 注释"), None);
+    }
+
+    #[test]
+    fn test_comment_text() {
+        let unit = TranslatableUnit::new("Fix this".to_string(), UnitType::Comment, 1, 1)
+            .with_metadata(serde_json::json!({ TODO_MARKER_KEY: "TODO: " }));
+        assert!(is_todo(&unit));
+        assert_eq!(comment_text(&unit), "TODO: Fix this");
+
+        let plain = TranslatableUnit::new("注释".to_string(), UnitType::Comment, 1, 1);
+        assert!(!is_todo(&plain));
+        assert_eq!(comment_text(&plain), "注释");
+    }
+}
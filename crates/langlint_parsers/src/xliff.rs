@@ -0,0 +1,722 @@
+//! XLIFF parser (.xlf and .xliff files, versions 1.2 and 2.0)
+//!
+//! Each segment without a target (a `<trans-unit>` in 1.2, a `<segment>`
+//! of a `<unit>` in 2.0) becomes a `TextNode` unit holding its source, with
+//! the unit's id as context. Inline elements (`<g>`, `<x/>`, `<ph>`,
+//! `<pc>`, ...) are kept as markup. On reconstruction the translation is
+//! written into the segment's `<target>`, added after `<source>` when
+//! missing; 1.2 targets are marked `state="needs-review-translation"`.
+//! Segments with a target and `translate="no"` units are left as they are.
+//!
+//! [`write_xliff`] and [`read_xliff`] exchange the units of other files
+//! with CAT tools (`langlint export` and `langlint import`).
+
+use anyhow::Result;
+use langlint_core::{Masked, ParseResult, Protection, TranslatableUnit, UnitType};
+use regex::Regex;
+use std::ops::Range;
+use std::sync::OnceLock;
+
+use crate::Parser;
+
+/// Unit metadata key locating a unit's source
+pub const XLIFF_KEY: &str = "xliff";
+
+/// Parser for XLIFF documents
+pub struct XliffParser;
+
+/// XLIFF version of a document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XliffVersion {
+    V1_2,
+    V2_0,
+}
+
+/// A segment exchanged with [`write_xliff`] and [`read_xliff`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct XliffSegment {
+    /// `original` of the enclosing `<file>`
+    pub file: String,
+    pub id: String,
+    pub source: String,
+    /// `None` when missing or empty
+    pub target: Option<String>,
+    pub note: Option<String>,
+}
+
+/// A segment of a document with the positions of its source and target
+#[derive(Debug, Clone)]
+struct Segment {
+    segment: XliffSegment,
+    version: XliffVersion,
+    line: usize,
+    /// Byte range of the raw source text
+    source: Range<usize>,
+    /// Byte range of the `<target>` element, or the empty range after
+    /// `</source>` where one is added
+    target: Range<usize>,
+    /// Attributes of the existing `<target>` element
+    target_attributes: Option<String>,
+    /// Whitespace before `<source>` when it starts a line
+    indent: Option<String>,
+    translate: bool,
+}
+
+impl XliffParser {
+    /// Create a new XLIFF parser
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for XliffParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).unwrap())
+}
+
+fn file_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    regex(&RE, r"<file\b([^>]*)>")
+}
+
+fn unit_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    regex(
+        &RE,
+        r"(?s)<!--.*?-->|<(trans-unit|unit)\b([^>]*?)(?:/>|>(.*?)</(?:trans-unit|unit)\s*>)",
+    )
+}
+
+fn segment_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    regex(&RE, r"(?s)<segment\b([^>]*?)>(.*?)</segment\s*>")
+}
+
+fn source_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    regex(&RE, r"(?s)<source\b[^>]*?(?:/>|>(.*?)</source\s*>)")
+}
+
+fn target_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    regex(&RE, r"(?s)<target\b([^>]*?)(?:/>|>(.*?)</target\s*>)")
+}
+
+fn note_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    regex(&RE, r"(?s)<note\b[^>]*>(.*?)</note\s*>")
+}
+
+/// Inline elements of both versions, kept as they are
+fn inline_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    regex(
+        &RE,
+        r"</?(?:g|x|bx|ex|bpt|ept|ph|it|sub|mrk|cp|pc|sc|ec|sm|em)\b[^<>]*>",
+    )
+}
+
+/// Value of an attribute in an element's attribute text
+fn attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    let pattern = format!(r#"(?:^|\s){}\s*=\s*["']([^"']*)["']"#, regex::escape(name));
+    Regex::new(&pattern)
+        .ok()?
+        .captures(attributes)
+        .and_then(|captures| captures.get(1))
+        .map(|value| value.as_str())
+}
+
+/// 1-based line of a byte offset
+fn line_of(text: &str, offset: usize) -> usize {
+    text[..offset].matches('\n').count() + 1
+}
+
+/// The segments of a document
+fn segments(content: &str) -> Vec<Segment> {
+    let files: Vec<(usize, String)> = file_regex()
+        .captures_iter(content)
+        .map(|captures| {
+            let attributes = captures.get(1).map_or("", |a| a.as_str());
+            let name = attribute(attributes, "original")
+                .or_else(|| attribute(attributes, "id"))
+                .unwrap_or_default();
+            (captures.get(0).unwrap().start(), unescape(name, true))
+        })
+        .collect();
+
+    let mut segments = Vec::new();
+    for captures in unit_regex().captures_iter(content) {
+        let (Some(kind), Some(attributes), Some(body)) =
+            (captures.get(1), captures.get(2), captures.get(3))
+        else {
+            continue; // A comment or an empty unit
+        };
+        let start = captures.get(0).unwrap().start();
+        let file = files
+            .iter()
+            .rev()
+            .find(|(offset, _)| *offset < start)
+            .map(|(_, name)| name.clone())
+            .unwrap_or_default();
+        let id = attribute(attributes.as_str(), "id").unwrap_or_default();
+        let translate = attribute(attributes.as_str(), "translate") != Some("no");
+        let note = note_regex()
+            .captures(body.as_str())
+            .and_then(|captures| captures.get(1))
+            .map(|note| unescape(note.as_str().trim(), true));
+
+        let base = XliffSegment {
+            file,
+            id: id.to_string(),
+            note,
+            ..XliffSegment::default()
+        };
+        if kind.as_str() == "trans-unit" {
+            segments.extend(segment(
+                content,
+                body.range(),
+                base,
+                XliffVersion::V1_2,
+                translate,
+            ));
+            continue;
+        }
+
+        let parts: Vec<_> = segment_regex().captures_iter(body.as_str()).collect();
+        for (index, part) in parts.iter().enumerate() {
+            let (attributes, inner) = (part.get(1).unwrap(), part.get(2).unwrap());
+            let id = match attribute(attributes.as_str(), "id") {
+                Some(segment_id) if parts.len() > 1 => format!("{}/{}", id, segment_id),
+                _ if parts.len() > 1 => format!("{}/{}", id, index + 1),
+                _ => id.to_string(),
+            };
+            let range = body.start() + inner.start()..body.start() + inner.end();
+            segments.extend(segment(
+                content,
+                range,
+                XliffSegment { id, ..base.clone() },
+                XliffVersion::V2_0,
+                translate,
+            ));
+        }
+    }
+    segments
+}
+
+/// The segment whose source and target are in `range` of `content`
+fn segment(
+    content: &str,
+    range: Range<usize>,
+    base: XliffSegment,
+    version: XliffVersion,
+    translate: bool,
+) -> Option<Segment> {
+    let body = &content[range.clone()];
+    let source = source_regex().captures(body)?;
+    let source_element = source.get(0).unwrap();
+    let source_text = source.get(1).map_or(
+        range.start + source_element.end()..range.start + source_element.end(),
+        |text| range.start + text.start()..range.start + text.end(),
+    );
+
+    let (target, target_attributes, target_text) = match target_regex().captures(body) {
+        Some(target) => {
+            let element = target.get(0).unwrap();
+            (
+                range.start + element.start()..range.start + element.end(),
+                Some(target.get(1).map_or("", |a| a.as_str()).to_string()),
+                target.get(2).map(|text| text.as_str()),
+            )
+        }
+        None => {
+            let end = range.start + source_element.end();
+            (end..end, None, None)
+        }
+    };
+
+    let element_start = range.start + source_element.start();
+    let line_start = content[..element_start].rfind('\n').map_or(0, |i| i + 1);
+    let before_source = &content[line_start..element_start];
+    let indent = before_source
+        .chars()
+        .all(char::is_whitespace)
+        .then(|| before_source.to_string());
+
+    Some(Segment {
+        segment: XliffSegment {
+            source: unescape(&content[source_text.clone()], true),
+            target: target_text
+                .filter(|text| !text.trim().is_empty())
+                .map(|text| unescape(text, true)),
+            ..base
+        },
+        version,
+        line: line_of(content, source_text.start),
+        source: source_text,
+        target,
+        target_attributes,
+        indent,
+        translate,
+    })
+}
+
+/// Resolve the XML entities of text outside inline elements; with
+/// `markup` false `&lt;` and `&gt;` stay, as they belong with the markup
+fn unescape(raw: &str, markup: bool) -> String {
+    let mut text = String::with_capacity(raw.len());
+    let mut last = 0;
+    for tag in inline_regex().find_iter(raw) {
+        text.push_str(&decode(&raw[last..tag.start()], markup));
+        text.push_str(tag.as_str());
+        last = tag.end();
+    }
+    text.push_str(&decode(&raw[last..], markup));
+    text
+}
+
+fn decode(raw: &str, markup: bool) -> String {
+    let mut text = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(c) = rest.chars().next() {
+        if c == '&' {
+            let entity = rest.find(';').map(|end| (&rest[1..end], end + 1));
+            let decoded = entity.and_then(|(name, len)| {
+                let c = match name {
+                    "amp" => '&',
+                    "quot" => '"',
+                    "apos" => '\'',
+                    "lt" if markup => '<',
+                    "gt" if markup => '>',
+                    _ => {
+                        let code = name.strip_prefix('#')?;
+                        let code = match code.strip_prefix(['x', 'X']) {
+                            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                            None => code.parse().ok()?,
+                        };
+                        char::from_u32(code)?
+                    }
+                };
+                Some((c, len))
+            });
+            if let Some((decoded, len)) = decoded {
+                text.push(decoded);
+                rest = &rest[len..];
+                continue;
+            }
+        }
+        text.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    text
+}
+
+/// `text` as element content; with `markup` inline elements, `&lt;` and
+/// `&gt;` are kept as they are
+fn escape(text: &str, markup: bool) -> String {
+    if !markup {
+        return text
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;");
+    }
+    let mut escaped = String::with_capacity(text.len() + 8);
+    let mut last = 0;
+    for tag in inline_regex().find_iter(text) {
+        escaped.push_str(&escape_ampersands(&text[last..tag.start()]));
+        escaped.push_str(tag.as_str());
+        last = tag.end();
+    }
+    escaped.push_str(&escape_ampersands(&text[last..]));
+    escaped
+}
+
+fn escape_ampersands(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for (i, c) in text.char_indices() {
+        if c == '&' && !text[i..].starts_with("&lt;") && !text[i..].starts_with("&gt;") {
+            escaped.push_str("&amp;");
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+fn escape_attribute(text: &str) -> String {
+    escape(text, false).replace('"', "&quot;")
+}
+
+/// Check if a source has words besides its placeholders and markup
+fn is_translatable(text: &str) -> bool {
+    static PROTECTION: OnceLock<Protection> = OnceLock::new();
+    let protection = PROTECTION.get_or_init(|| Protection::default().with_markup());
+    Masked::with_protection(text, protection)
+        .text
+        .chars()
+        .any(char::is_alphabetic)
+}
+
+/// The segments of an XLIFF document, with fully unescaped text
+pub fn read_xliff(content: &str) -> Vec<XliffSegment> {
+    segments(content)
+        .into_iter()
+        .map(|segment| segment.segment)
+        .collect()
+}
+
+/// An XLIFF document holding `segments`, one `<file>` per run of segments
+/// of the same file
+pub fn write_xliff(
+    segments: &[XliffSegment],
+    source: &str,
+    target: &str,
+    version: XliffVersion,
+) -> String {
+    let mut files: Vec<(&str, Vec<&XliffSegment>)> = Vec::new();
+    for segment in segments {
+        match files.last_mut() {
+            Some((file, list)) if *file == segment.file => list.push(segment),
+            _ => files.push((&segment.file, vec![segment])),
+        }
+    }
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    match version {
+        XliffVersion::V1_2 => {
+            out.push_str(
+                "<xliff version=\"1.2\" xmlns=\"urn:oasis:names:tc:xliff:document:1.2\">\n",
+            );
+            for (file, segments) in &files {
+                out.push_str(&format!(
+                    "  <file original=\"{}\" source-language=\"{}\" target-language=\"{}\" datatype=\"plaintext\">\n    <body>\n",
+                    escape_attribute(file),
+                    escape_attribute(source),
+                    escape_attribute(target)
+                ));
+                for segment in segments {
+                    out.push_str(&format!(
+                        "      <trans-unit id=\"{}\">\n        <source>{}</source>\n",
+                        escape_attribute(&segment.id),
+                        escape(&segment.source, false)
+                    ));
+                    if let Some(text) = &segment.target {
+                        out.push_str(&format!(
+                            "        <target>{}</target>\n",
+                            escape(text, false)
+                        ));
+                    }
+                    if let Some(note) = &segment.note {
+                        out.push_str(&format!("        <note>{}</note>\n", escape(note, false)));
+                    }
+                    out.push_str("      </trans-unit>\n");
+                }
+                out.push_str("    </body>\n  </file>\n");
+            }
+        }
+        XliffVersion::V2_0 => {
+            out.push_str(&format!(
+                "<xliff version=\"2.0\" xmlns=\"urn:oasis:names:tc:xliff:document:2.0\" srcLang=\"{}\" trgLang=\"{}\">\n",
+                escape_attribute(source),
+                escape_attribute(target)
+            ));
+            for (index, (file, segments)) in files.iter().enumerate() {
+                out.push_str(&format!(
+                    "  <file id=\"f{}\" original=\"{}\">\n",
+                    index + 1,
+                    escape_attribute(file)
+                ));
+                for segment in segments {
+                    out.push_str(&format!(
+                        "    <unit id=\"{}\">\n",
+                        escape_attribute(&segment.id)
+                    ));
+                    if let Some(note) = &segment.note {
+                        out.push_str(&format!(
+                            "      <notes>\n        <note>{}</note>\n      </notes>\n",
+                            escape(note, false)
+                        ));
+                    }
+                    out.push_str(&format!(
+                        "      <segment>\n        <source>{}</source>\n",
+                        escape(&segment.source, false)
+                    ));
+                    if let Some(text) = &segment.target {
+                        out.push_str(&format!(
+                            "        <target>{}</target>\n",
+                            escape(text, false)
+                        ));
+                    }
+                    out.push_str("      </segment>\n    </unit>\n");
+                }
+                out.push_str("  </file>\n");
+            }
+        }
+    }
+    out.push_str("</xliff>\n");
+    out
+}
+
+impl Parser for XliffParser {
+    fn name(&self) -> &'static str {
+        "Xliff"
+    }
+
+    fn supported_extensions(&self) -> &'static [&'static str] {
+        &[".xlf", ".xliff"]
+    }
+
+    fn can_parse(&self, path: &str, _content: Option<&str>) -> bool {
+        let path = path.to_lowercase();
+        self.supported_extensions()
+            .iter()
+            .any(|ext| path.ends_with(ext))
+    }
+
+    fn extract_units(&self, content: &str, _path: &str) -> Result<ParseResult> {
+        let units = segments(content)
+            .into_iter()
+            .filter(|segment| segment.translate && segment.segment.target.is_none())
+            .filter_map(|segment| {
+                let source = unescape(&content[segment.source.clone()], false);
+                if !is_translatable(&source) {
+                    return None;
+                }
+                let mut unit =
+                    TranslatableUnit::new(source, UnitType::TextNode, segment.line as u32, 1)
+                        .with_context(segment.segment.id)
+                        .with_metadata(serde_json::json!({
+                            XLIFF_KEY: { "start": segment.source.start }
+                        }));
+                unit.detect_language();
+                Some(unit)
+            })
+            .collect();
+
+        Ok(ParseResult::new("xliff", "utf-8", content.lines().count() as u32).with_units(units))
+    }
+
+    fn reconstruct(
+        &self,
+        original: &str,
+        units: &[TranslatableUnit],
+        _path: &str,
+    ) -> Result<String> {
+        let start = |unit: &TranslatableUnit| {
+            unit.metadata
+                .as_ref()?
+                .get(XLIFF_KEY)?
+                .get("start")?
+                .as_u64()
+        };
+
+        let mut result = original.to_string();
+        for segment in segments(original).iter().rev() {
+            let source = unescape(&original[segment.source.clone()], false);
+            let translated = units
+                .iter()
+                .find(|unit| start(unit) == Some(segment.source.start as u64));
+            let Some(unit) = translated.filter(|unit| unit.content != source) else {
+                continue;
+            };
+
+            let mut attributes = segment.target_attributes.clone().unwrap_or_default();
+            if segment.version == XliffVersion::V1_2 && attribute(&attributes, "state").is_none() {
+                attributes.push_str(" state=\"needs-review-translation\"");
+            }
+            let element = format!(
+                "<target{}>{}</target>",
+                attributes.trim_end(),
+                escape(&unit.content, true)
+            );
+            let element = match (&segment.target_attributes, &segment.indent) {
+                (Some(_), _) => element,
+                (None, Some(indent)) => format!("\n{}{}", indent, element),
+                (None, None) => element,
+            };
+            result.replace_range(segment.target.clone(), &element);
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XLIFF_1_2: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xliff version="1.2" xmlns="urn:oasis:names:tc:xliff:document:1.2">
+  <file original="app.py" source-language="zh" target-language="en" datatype="plaintext">
+    <body>
+      <trans-unit id="greeting">
+        <source>你好，<g id="1">世界</g> &amp; 朋友</source>
+      </trans-unit>
+      <trans-unit id="done">
+        <source>已完成</source>
+        <target>Done</target>
+      </trans-unit>
+      <trans-unit id="empty">
+        <source>保存 &lt;文件&gt;</source>
+        <target xml:lang="en"/>
+      </trans-unit>
+      <trans-unit id="code" translate="no">
+        <source>不要翻译</source>
+      </trans-unit>
+      <!-- <trans-unit id="old"><source>旧的</source></trans-unit> -->
+    </body>
+  </file>
+</xliff>
+"#;
+
+    #[test]
+    fn test_extract_and_reconstruct_1_2() {
+        let parser = XliffParser::new();
+        assert!(parser.can_parse("locale/app.zh.XLF", None));
+        assert!(!parser.can_parse("app.xml", None));
+
+        let result = parser.extract_units(XLIFF_1_2, "app.xlf").unwrap();
+        let contents: Vec<_> = result.units.iter().map(|u| u.content.as_str()).collect();
+        assert_eq!(
+            contents,
+            vec!["你好，<g id=\"1\">世界</g> & 朋友", "保存 &lt;文件&gt;"]
+        );
+        assert_eq!(result.units[0].context.as_deref(), Some("greeting"));
+        assert_eq!(result.units[0].line_number, 6);
+
+        let mut units = result.units.clone();
+        units[0].content = "Hello, <g id=\"1\">world</g> & friends".to_string();
+        units[1].content = "Save &lt;file&gt;".to_string();
+        let output = parser.reconstruct(XLIFF_1_2, &units, "app.xlf").unwrap();
+        assert!(output.contains(
+            "&amp; 朋友</source>\n        <target state=\"needs-review-translation\">Hello, <g id=\"1\">world</g> &amp; friends</target>\n      </trans-unit>"
+        ));
+        assert!(output.contains(
+            "<target xml:lang=\"en\" state=\"needs-review-translation\">Save &lt;file&gt;</target>"
+        ));
+        assert!(output.contains("<target>Done</target>"));
+        assert!(parser
+            .extract_units(&output, "app.xlf")
+            .unwrap()
+            .units
+            .is_empty());
+    }
+
+    #[test]
+    fn test_write_and_read_2_0() {
+        let segments = vec![
+            XliffSegment {
+                file: "src/a & b.py".to_string(),
+                id: "3-1".to_string(),
+                source: "如果 a < b 则返回".to_string(),
+                target: None,
+                note: Some("line 3, comment".to_string()),
+            },
+            XliffSegment {
+                file: "docs/intro.md".to_string(),
+                id: "1-1".to_string(),
+                source: "简介".to_string(),
+                target: Some("Introduction".to_string()),
+                note: None,
+            },
+        ];
+        for version in [XliffVersion::V1_2, XliffVersion::V2_0] {
+            let document = write_xliff(&segments, "zh", "en", version);
+            assert_eq!(read_xliff(&document), segments);
+        }
+
+        let document = write_xliff(&segments, "zh", "en", XliffVersion::V2_0);
+        assert!(document.contains("srcLang=\"zh\" trgLang=\"en\""));
+        let parser = XliffParser::new();
+        let mut units = parser.extract_units(&document, "x.xlf").unwrap().units;
+        assert_eq!(units.len(), 1);
+        assert_eq!(units[0].content, "如果 a &lt; b 则返回");
+        units[0].content = "If a &lt; b, return".to_string();
+        let output = parser.reconstruct(&document, &units, "x.xlf").unwrap();
+        assert_eq!(
+            read_xliff(&output)[0].target.as_deref(),
+            Some("If a < b, return")
+        );
+        assert!(output.contains("</source>\n        <target>If a &lt; b, return</target>"));
+    }
+
+    const XLIFF_2_0: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xliff version="2.0" xmlns="urn:oasis:names:tc:xliff:document:2.0" srcLang="zh" trgLang="en">
+  <file id="f1" original="app.py">
+    <unit id="welcome">
+      <segment id="1">
+        <source>欢迎，<pc id="b1" dataRefStart="d1">用户</pc>！</source>
+      </segment>
+      <ignorable>
+        <source> </source>
+      </ignorable>
+      <segment id="2">
+        <source>共 <ph id="p1" disp="{count}"/> 条消息</source>
+      </segment>
+    </unit>
+    <unit id="done">
+      <segment state="final">
+        <source>已完成</source>
+        <target>Done</target>
+      </segment>
+    </unit>
+    <unit id="code" translate="no">
+      <segment>
+        <source>不要翻译</source>
+      </segment>
+    </unit>
+  </file>
+</xliff>
+"#;
+
+    #[test]
+    fn test_round_trip_2_0_segments_and_inline_tags() {
+        let parser = XliffParser::new();
+        let result = parser.extract_units(XLIFF_2_0, "app.xlf").unwrap();
+        let units: Vec<_> = result
+            .units
+            .iter()
+            .map(|u| (u.line_number, u.context.as_deref(), u.content.as_str()))
+            .collect();
+        assert_eq!(
+            units,
+            vec![
+                (
+                    6,
+                    Some("welcome/1"),
+                    "欢迎，<pc id=\"b1\" dataRefStart=\"d1\">用户</pc>！"
+                ),
+                (
+                    12,
+                    Some("welcome/2"),
+                    "共 <ph id=\"p1\" disp=\"{count}\"/> 条消息"
+                ),
+            ]
+        );
+
+        let mut units = result.units.clone();
+        units[0].content = "Welcome, <pc id=\"b1\" dataRefStart=\"d1\">user</pc>!".to_string();
+        units[1].content = "<ph id=\"p1\" disp=\"{count}\"/> messages".to_string();
+        let output = parser.reconstruct(XLIFF_2_0, &units, "app.xlf").unwrap();
+        assert_eq!(
+            output,
+            XLIFF_2_0
+                .replace(
+                    "用户</pc>！</source>\n",
+                    "用户</pc>！</source>\n        <target>Welcome, <pc id=\"b1\" dataRefStart=\"d1\">user</pc>!</target>\n"
+                )
+                .replace(
+                    "条消息</source>\n",
+                    "条消息</source>\n        <target><ph id=\"p1\" disp=\"{count}\"/> messages</target>\n"
+                )
+        );
+        assert!(parser
+            .extract_units(&output, "app.xlf")
+            .unwrap()
+            .units
+            .is_empty());
+    }
+}
@@ -0,0 +1,299 @@
+//! XML documentation comments of C#
+//!
+//! [`XmlDocParser`] reads runs of `///` lines as one XML fragment. The text
+//! inside `<summary>`, `<param>`, `<returns>`, `<remarks>` and the other
+//! documentation elements is translated, split at `<para>`, list items and
+//! other block elements; inline elements such as `<see cref="..."/>`,
+//! `<paramref name="..."/>` and `<c>...</c>` travel with their sentence and
+//! are masked like other code. Tags, attributes and `<code>` blocks stay as
+//! written.
+
+use anyhow::Result;
+use langlint_core::{ParseResult, TranslatableUnit};
+use regex::Regex;
+use std::ops::Range;
+use std::sync::OnceLock;
+
+use crate::doc_comment::{self, DocComments, Segment};
+use crate::{GenericCodeParser, Parser};
+
+/// Elements whose text is documentation
+const TEXT_ELEMENTS: &[&str] = &[
+    "summary",
+    "remarks",
+    "param",
+    "typeparam",
+    "returns",
+    "value",
+    "exception",
+    "example",
+    "permission",
+];
+
+/// Elements that stay inside the sentence around them
+const INLINE_ELEMENTS: &[&str] = &[
+    "see",
+    "paramref",
+    "typeparamref",
+    "c",
+    "b",
+    "i",
+    "em",
+    "strong",
+    "u",
+    "a",
+];
+
+fn tag_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"<(/?)([A-Za-z][\w:.-]*)[^<>]*?(/?)>").unwrap())
+}
+
+/// Parser for C# that understands XML documentation comments
+pub struct XmlDocParser {
+    generic: GenericCodeParser,
+}
+
+impl XmlDocParser {
+    pub fn new() -> Self {
+        Self {
+            generic: GenericCodeParser::new(),
+        }
+    }
+}
+
+impl Default for XmlDocParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Doc comments of a file: each run of `///` lines
+fn doc_comments(lines: &[&str]) -> DocComments {
+    let mut docs = DocComments {
+        blocks: Vec::new(),
+        segments: Vec::new(),
+    };
+    let mut i = 0;
+    while i < lines.len() {
+        let mut bodies = Vec::new();
+        while let Some(body) = lines.get(i).and_then(|line| doc_body(line)) {
+            bodies.push((i, body));
+            i += 1;
+        }
+        match (bodies.first(), bodies.last()) {
+            (Some((first, _)), Some((last, _))) => {
+                docs.blocks.push(*first..=*last);
+                docs.segments.extend(segments(lines, &bodies));
+            }
+            _ => i += 1,
+        }
+    }
+    docs
+}
+
+/// Byte range of the text after `///` of a doc comment line
+fn doc_body(line: &str) -> Option<Range<usize>> {
+    let indent = line.len() - line.trim_start().len();
+    let rest = line[indent..].strip_prefix("///")?;
+    if rest.starts_with('/') {
+        return None;
+    }
+    let start = indent + 3 + usize::from(rest.starts_with(' '));
+    Some(start..line.trim_end().len().max(start))
+}
+
+/// Split the XML of a doc comment into the texts of its elements
+fn segments(lines: &[&str], bodies: &[(usize, Range<usize>)]) -> Vec<Segment> {
+    // The bodies joined by newlines, with the offset of each in the joint
+    let mut xml = String::new();
+    let mut offsets = Vec::new();
+    for (line, range) in bodies {
+        if !xml.is_empty() {
+            xml.push('\n');
+        }
+        offsets.push(xml.len());
+        xml.push_str(&lines[*line][range.clone()]);
+    }
+
+    // Text runs as `(element, byte range of the joint)`
+    let mut runs: Vec<(String, Range<usize>)> = Vec::new();
+    let mut current: Option<(String, Range<usize>)> = None;
+    let mut stack: Vec<String> = Vec::new();
+    let mut pos = 0;
+    let text =
+        |current: &mut Option<(String, Range<usize>)>, stack: &[String], range: Range<usize>| {
+            let run = &xml[range.clone()];
+            if !run.trim().is_empty() {
+                let start = range.start + run.len() - run.trim_start().len();
+                extend(current, stack, start..range.start + run.trim_end().len());
+            }
+        };
+    for captures in tag_pattern().captures_iter(&xml) {
+        let whole = captures.get(0).unwrap();
+        text(&mut current, &stack, pos..whole.start());
+        pos = whole.end();
+
+        let name = captures[2].to_lowercase();
+        if INLINE_ELEMENTS.contains(&name.as_str()) {
+            if current.is_some() || captures[1].is_empty() {
+                extend(&mut current, &stack, whole.range());
+            }
+            continue;
+        }
+        runs.extend(current.take());
+        if !captures[3].is_empty() {
+            continue;
+        }
+        if captures[1].is_empty() {
+            stack.push(name);
+        } else if let Some(open) = stack.iter().rposition(|element| *element == name) {
+            stack.truncate(open);
+        }
+    }
+    text(&mut current, &stack, pos..xml.len());
+    runs.extend(current);
+
+    runs.into_iter()
+        .map(|(tag, run)| {
+            let lines = bodies
+                .iter()
+                .zip(&offsets)
+                .filter_map(|((line, body), offset)| {
+                    let start = run.start.max(*offset);
+                    let end = run.end.min(offset + body.len());
+                    let piece = xml.get(start..end)?;
+                    let trimmed = piece.trim();
+                    if trimmed.is_empty() {
+                        return None;
+                    }
+                    let start =
+                        body.start + start - offset + piece.len() - piece.trim_start().len();
+                    Some((*line, start..start + trimmed.len()))
+                })
+                .collect();
+            Segment { tag, lines }
+        })
+        .filter(|segment| !segment.lines.is_empty())
+        .collect()
+}
+
+/// Extend the current run of documentation text over `range`, unless the
+/// text is outside documentation elements or in `<code>`
+fn extend(current: &mut Option<(String, Range<usize>)>, stack: &[String], range: Range<usize>) {
+    let Some(element) = stack.first().filter(|element| {
+        TEXT_ELEMENTS.contains(&element.as_str()) && !stack.iter().any(|name| name == "code")
+    }) else {
+        return;
+    };
+    match current {
+        Some((_, run)) => run.end = range.end,
+        None => *current = Some((element.clone(), range)),
+    }
+}
+
+impl Parser for XmlDocParser {
+    fn name(&self) -> &'static str {
+        "XmlDocParser"
+    }
+
+    fn supported_extensions(&self) -> &'static [&'static str] {
+        &[".cs"]
+    }
+
+    fn can_parse(&self, path: &str, _content: Option<&str>) -> bool {
+        path.ends_with(".cs")
+    }
+
+    fn extract_units(&self, content: &str, path: &str) -> Result<ParseResult> {
+        let lines: Vec<&str> = content.lines().collect();
+        let docs = doc_comments(&lines);
+        doc_comment::extract_units(
+            &self.generic,
+            content,
+            path,
+            self.name(),
+            Some(docs),
+            |tag| format!("XML doc <{}>", tag),
+        )
+    }
+
+    fn reconstruct(
+        &self,
+        original: &str,
+        units: &[TranslatableUnit],
+        path: &str,
+    ) -> Result<String> {
+        doc_comment::reconstruct(&self.generic, original, units, path, |lines| {
+            Some(doc_comments(lines))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::doc_comment::DOC_TAG_KEY;
+
+    const SOURCE: &str = "    /// <summary>\n    /// 计算两个数的和，\n    /// 参见 <see cref=\"Math\"/> 的说明。\n    /// <para>结果不会溢出</para>\n    /// </summary>\n    /// <param name=\"a\">第一个数</param>\n    /// <returns>两数之和</returns>\n    /// <example><code>Add(1, 2); // 示例</code></example>\n    // 普通注释\n    int Add(int a, int b);\n";
+
+    #[test]
+    fn test_extract_xml_doc_texts() {
+        let parser = XmlDocParser::new();
+        let units = parser.extract_units(SOURCE, "Calc.cs").unwrap().units;
+        let units: Vec<(u32, u32, &str, &str)> = units
+            .iter()
+            .map(|unit| {
+                let tag = unit
+                    .metadata
+                    .as_ref()
+                    .and_then(|m| m.get(DOC_TAG_KEY))
+                    .and_then(|tag| tag.as_str())
+                    .unwrap_or("-");
+                (
+                    unit.line_number,
+                    unit.column_number,
+                    tag,
+                    unit.content.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            units,
+            vec![
+                (
+                    2,
+                    9,
+                    "summary",
+                    "计算两个数的和，参见 <see cref=\"Math\"/> 的说明。"
+                ),
+                (4, 15, "summary", "结果不会溢出"),
+                (6, 25, "param", "第一个数"),
+                (7, 18, "returns", "两数之和"),
+                (9, 5, "-", "普通注释"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reconstruct_keeps_xml() {
+        let parser = XmlDocParser::new();
+        let mut units = parser.extract_units(SOURCE, "Calc.cs").unwrap().units;
+        let translations = [
+            "Adds two numbers, see <see cref=\"Math\"/> for details.",
+            "The result never overflows",
+            "The first number",
+            "The sum",
+            "Plain comment",
+        ];
+        for (unit, translation) in units.iter_mut().zip(translations) {
+            unit.content = translation.to_string();
+        }
+
+        let rebuilt = parser.reconstruct(SOURCE, &units, "Calc.cs").unwrap();
+        assert_eq!(
+            rebuilt,
+            "    /// <summary>\n    /// Adds two numbers, see <see\n    /// cref=\"Math\"/> for details.\n    /// <para>The result never overflows</para>\n    /// </summary>\n    /// <param name=\"a\">The first number</param>\n    /// <returns>The sum</returns>\n    /// <example><code>Add(1, 2); // 示例</code></example>\n    // Plain comment\n    int Add(int a, int b);\n"
+        );
+    }
+}
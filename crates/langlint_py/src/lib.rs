@@ -7,12 +7,14 @@ use pyo3::exceptions::PyRuntimeError;
 use pyo3::prelude::*;
 use pyo3::wrap_pyfunction;
 
-use langlint_core::ParseResult;
-use langlint_parsers::{GenericCodeParser, Parser, PythonParser};
-use langlint_translators::{GoogleTranslator, MockTranslator, Translator};
+use langlint_core::{paths, Config, ParseResult, TranslationMemory};
+use langlint_parsers::overrides::ParserOverrides;
+use langlint_parsers::{GenericCodeParser, Parser};
+use langlint_translators::{CachedTranslator, Translator, TranslatorRegistry};
 
 use std::fs;
-use std::path::Path;
+use std::path::{Component, Path};
+use std::sync::{Arc, OnceLock};
 use walkdir::WalkDir;
 
 /// Scan files and extract translatable units
@@ -53,12 +55,14 @@ async fn scan_impl(
     exclude: &[String],
 ) -> anyhow::Result<String> {
     let path_obj = Path::new(path);
+    let config = Config::load()?;
+    let parsers = ParserOverrides::new(&config.parsers)?;
 
     // Collect files to scan
     let files = if path_obj.is_file() {
         vec![path_obj.to_path_buf()]
     } else {
-        collect_files(path_obj, exclude)?
+        collect_files(path_obj, exclude, &config, &parsers)?
     };
 
     if verbose {
@@ -70,7 +74,7 @@ async fn scan_impl(
     let mut total_units = 0;
 
     for file_path in &files {
-        if let Ok(result) = scan_file(file_path).await {
+        if let Ok(result) = scan_file(file_path, &parsers).await {
             total_units += result.units.len();
             all_results.push((file_path.clone(), result));
         }
@@ -104,7 +108,7 @@ async fn scan_impl(
 ///     path: File or directory path to translate
 ///     source: Source language code (e.g., 'en', 'zh', 'ja')
 ///     target: Target language code (e.g., 'en', 'zh', 'ja')
-///     translator: Translator to use ('mock', 'google'), defaults to 'google'
+///     translator: Registered translator name ('mock', 'google', 'openai', 'ollama', 'libretranslate', 'mymemory', 'papago'), defaults to 'google'
 ///     output: Output file path (optional, defaults to in-place)
 ///     dry_run: Perform dry run without writing, defaults to False
 ///
@@ -141,6 +145,12 @@ fn translate(
     result.map_err(|e| PyRuntimeError::new_err(format!("Translation failed: {}", e)))
 }
 
+/// Translations remembered across calls in this Python process
+fn memory() -> Arc<TranslationMemory> {
+    static MEMORY: OnceLock<Arc<TranslationMemory>> = OnceLock::new();
+    Arc::clone(MEMORY.get_or_init(|| Arc::new(TranslationMemory::new())))
+}
+
 /// Implementation of translate functionality
 async fn translate_impl(
     path: &str,
@@ -151,15 +161,16 @@ async fn translate_impl(
     dry_run: bool,
 ) -> anyhow::Result<String> {
     let path_obj = Path::new(path);
+    let parsers = ParserOverrides::new(&Config::load()?.parsers)?;
 
-    // Create translator
-    let translator: Box<dyn Translator> = match translator_name {
-        "google" => Box::new(GoogleTranslator::new()?),
-        _ => Box::new(MockTranslator::default()),
-    };
+    // Create translator; texts translated by earlier calls are not sent again
+    let translator = CachedTranslator::with_memory(
+        TranslatorRegistry::global().create(translator_name)?,
+        memory(),
+    );
 
     // Scan file first
-    let parse_result = scan_file(path_obj).await?;
+    let parse_result = scan_file(path_obj, &parsers).await?;
 
     if parse_result.units.is_empty() {
         return Ok(serde_json::json!({
@@ -199,7 +210,7 @@ async fn translate_impl(
 
         // Reconstruct file
         let original_content = fs::read_to_string(path)?;
-        let parser = get_parser(path_obj);
+        let parser = get_parser(path_obj, &original_content, &parsers);
         let reconstructed = parser.reconstruct(&original_content, &translated_units, path)?;
 
         fs::write(output_path, reconstructed)?;
@@ -234,18 +245,28 @@ fn langlint_py(_py: Python, m: &PyModule) -> PyResult<()> {
 // ============================================================================
 
 /// Collect files to scan from a directory
-fn collect_files(dir: &Path, exclude: &[String]) -> anyhow::Result<Vec<std::path::PathBuf>> {
+fn collect_files(
+    dir: &Path,
+    exclude: &[String],
+    config: &Config,
+    parsers: &ParserOverrides,
+) -> anyhow::Result<Vec<std::path::PathBuf>> {
     let mut files = Vec::new();
+    let excluded_dirs = config.excluded_dirs();
 
     for entry in WalkDir::new(dir)
         .follow_links(true)
+        .sort_by_file_name()
         .into_iter()
-        .filter_entry(|e| !is_ignored(e.path(), exclude))
+        .filter_entry(|e| {
+            let relative = e.path().strip_prefix(dir).unwrap_or(e.path());
+            !is_ignored(relative, &excluded_dirs, exclude)
+        })
     {
         let entry = entry?;
         if entry.file_type().is_file() {
             let path = entry.path();
-            if should_scan(path) {
+            if should_scan(path, parsers) {
                 files.push(path.to_path_buf());
             }
         }
@@ -255,32 +276,15 @@ fn collect_files(dir: &Path, exclude: &[String]) -> anyhow::Result<Vec<std::path
 }
 
 /// Check if a path should be ignored
-fn is_ignored(path: &Path, exclude_patterns: &[String]) -> bool {
-    // Default ignored directories
-    let default_ignored_dirs = [
-        "node_modules",
-        "target",
-        "__pycache__",
-        ".git",
-        ".venv",
-        "venv",
-        "build",
-        "dist",
-        ".eggs",
-        "htmlcov",
-        "demo_files",
-        "examples",
-        "figures",
-        "submission_patterns",
-    ];
-
-    // Check default ignored directories
-    let is_default_ignored = path.components().any(|c| {
-        if let Some(s) = c.as_os_str().to_str() {
-            default_ignored_dirs.contains(&s)
-        } else {
-            false
-        }
+///
+/// `excluded_dirs` are directory names from the config (see
+/// `Config::excluded_dirs`); hidden directories are always ignored.
+fn is_ignored(path: &Path, excluded_dirs: &[String], exclude_patterns: &[String]) -> bool {
+    let is_default_ignored = path.components().any(|c| match c {
+        Component::Normal(name) => name.to_str().is_some_and(|s| {
+            s.starts_with('.') || excluded_dirs.iter().any(|d| paths::names_equal(d, s))
+        }),
+        _ => false,
     });
 
     if is_default_ignored {
@@ -289,9 +293,9 @@ fn is_ignored(path: &Path, exclude_patterns: &[String]) -> bool {
 
     // Check custom exclude patterns
     if !exclude_patterns.is_empty() {
-        let path_str = path.to_string_lossy();
+        let path_str = paths::to_slash(path);
         for pattern in exclude_patterns {
-            if path_str.contains(pattern.as_str()) {
+            if path_str.contains(&paths::normalize_separators(pattern)) {
                 return true;
             }
         }
@@ -300,62 +304,25 @@ fn is_ignored(path: &Path, exclude_patterns: &[String]) -> bool {
     false
 }
 
-/// Check if a file should be scanned
-fn should_scan(path: &Path) -> bool {
-    if let Some(ext) = path.extension() {
-        let ext_str = ext.to_string_lossy();
-        matches!(
-            ext_str.as_ref(),
-            "py" | "js"
-                | "ts"
-                | "jsx"
-                | "tsx"
-                | "rs"
-                | "go"
-                | "java"
-                | "c"
-                | "cpp"
-                | "h"
-                | "hpp"
-                | "cs"
-                | "php"
-                | "rb"
-                | "sh"
-                | "bash"
-                | "sql"
-                | "r"
-                | "R"
-                | "m"
-                | "scala"
-                | "kt"
-                | "swift"
-                | "dart"
-                | "lua"
-                | "vim"
-                | "ipynb"
-        )
-    } else {
-        false
-    }
+/// Check if a file should be scanned: some parser handles it
+fn should_scan(path: &Path, parsers: &ParserOverrides) -> bool {
+    parsers.resolve(path, None).is_some()
 }
 
 /// Scan a single file
-async fn scan_file(path: &Path) -> anyhow::Result<ParseResult> {
+async fn scan_file(path: &Path, parsers: &ParserOverrides) -> anyhow::Result<ParseResult> {
     let content = fs::read_to_string(path)?;
     let path_str = path.to_string_lossy();
 
-    let parser = get_parser(path);
+    let parser = get_parser(path, &content, parsers);
     parser.extract_units(&content, &path_str)
 }
 
-/// Get appropriate parser for a file
-fn get_parser(path: &Path) -> Box<dyn Parser> {
-    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
-
-    match ext {
-        "py" => Box::new(PythonParser),
-        _ => Box::new(GenericCodeParser),
-    }
+/// Get appropriate parser for a file, honouring the config's `parsers` table
+fn get_parser(path: &Path, content: &str, parsers: &ParserOverrides) -> Box<dyn Parser> {
+    parsers
+        .resolve(path, Some(content))
+        .unwrap_or_else(|| Box::new(GenericCodeParser::new()))
 }
 
 #[cfg(test)]
@@ -373,19 +340,36 @@ mod tests {
 
     #[test]
     fn test_is_ignored_default_dirs() {
+        let defaults = Config::default().excluded_dirs();
+        let ignored = |path: &str| is_ignored(Path::new(path), &defaults, &[]);
+
         // Test default ignored directories
-        assert!(is_ignored(Path::new("node_modules/test.js"), &[]));
-        assert!(is_ignored(Path::new("target/debug/test"), &[]));
-        assert!(is_ignored(Path::new("__pycache__/test.pyc"), &[]));
-        assert!(is_ignored(Path::new(".git/config"), &[]));
-        assert!(is_ignored(Path::new("demo_files/test.py"), &[]));
-        assert!(is_ignored(Path::new("examples/test.py"), &[]));
-        assert!(is_ignored(Path::new("figures/test.png"), &[]));
-        assert!(is_ignored(Path::new("submission_patterns/test.md"), &[]));
+        assert!(ignored("node_modules/test.js"));
+        assert!(ignored("target/debug/test"));
+        assert!(ignored("__pycache__/test.pyc"));
+        assert!(ignored(".git/config"));
+        assert!(ignored("demo_files/test.py"));
+        assert!(ignored("examples/test.py"));
+        assert!(ignored("figures/test.png"));
+        assert!(ignored("submission_patterns/test.md"));
 
         // Test non-ignored directories
-        assert!(!is_ignored(Path::new("src/main.rs"), &[]));
-        assert!(!is_ignored(Path::new("tests/test.py"), &[]));
+        assert!(!ignored("src/main.rs"));
+        assert!(!ignored("tests/test.py"));
+        assert!(!ignored("./src/main.rs"));
+    }
+
+    #[test]
+    fn test_is_ignored_with_overridden_excludes() {
+        let config = Config {
+            override_excludes: Some(vec!["node_modules".to_string()]),
+            ..Config::default()
+        };
+        let dirs = config.excluded_dirs();
+
+        assert!(!is_ignored(Path::new("examples/test.py"), &dirs, &[]));
+        assert!(is_ignored(Path::new("node_modules/test.js"), &dirs, &[]));
+        assert!(is_ignored(Path::new(".venv/lib.py"), &dirs, &[]));
     }
 
     #[test]
@@ -393,36 +377,66 @@ mod tests {
         let exclude = vec!["custom_dir".to_string(), "temp".to_string()];
 
         // Test custom patterns
-        assert!(is_ignored(Path::new("custom_dir/test.py"), &exclude));
-        assert!(is_ignored(Path::new("temp/data.txt"), &exclude));
-        assert!(is_ignored(Path::new("path/to/temp/file.rs"), &exclude));
+        assert!(is_ignored(Path::new("custom_dir/test.py"), &[], &exclude));
+        assert!(is_ignored(Path::new("temp/data.txt"), &[], &exclude));
+        assert!(is_ignored(Path::new("path/to/temp/file.rs"), &[], &exclude));
 
         // Test non-matching paths
-        assert!(!is_ignored(Path::new("src/main.rs"), &exclude));
+        assert!(!is_ignored(Path::new("src/main.rs"), &[], &exclude));
     }
 
     #[test]
     fn test_is_ignored_combined() {
         let exclude = vec!["my_tests".to_string()];
+        let defaults = Config::default().excluded_dirs();
 
         // Both default and custom should work
-        assert!(is_ignored(Path::new("node_modules/lib.js"), &exclude));
-        assert!(is_ignored(Path::new("my_tests/test.py"), &exclude));
-        assert!(is_ignored(Path::new("demo_files/example.py"), &exclude));
+        assert!(is_ignored(
+            Path::new("node_modules/lib.js"),
+            &defaults,
+            &exclude
+        ));
+        assert!(is_ignored(
+            Path::new("my_tests/test.py"),
+            &defaults,
+            &exclude
+        ));
+        assert!(is_ignored(
+            Path::new("demo_files/example.py"),
+            &defaults,
+            &exclude
+        ));
     }
 
     #[test]
     fn test_should_scan() {
+        let parsers = ParserOverrides::default();
+        let scans = |path: &str| should_scan(Path::new(path), &parsers);
+
         // Valid extensions
-        assert!(should_scan(Path::new("test.py")));
-        assert!(should_scan(Path::new("test.js")));
-        assert!(should_scan(Path::new("test.rs")));
-        assert!(should_scan(Path::new("test.ipynb")));
+        assert!(scans("test.py"));
+        assert!(scans("test.js"));
+        assert!(scans("test.rs"));
+        assert!(scans("test.ipynb"));
 
         // Invalid extensions
-        assert!(!should_scan(Path::new("test.txt")));
-        assert!(!should_scan(Path::new("test.md")));
-        assert!(!should_scan(Path::new("README")));
+        assert!(!scans("test.txt"));
+        assert!(scans("test.md"));
+        assert!(!scans("README"));
+    }
+
+    #[test]
+    fn test_get_parser_uses_overrides() {
+        let parsers = ParserOverrides::new(
+            &[("*.tpl".to_string(), "html".to_string())]
+                .into_iter()
+                .collect(),
+        )
+        .unwrap();
+
+        assert!(should_scan(Path::new("page.tpl"), &parsers));
+        let parser = get_parser(Path::new("page.tpl"), "<p>Hello</p>", &parsers);
+        assert_eq!(parser.name(), "HTML");
     }
 
     #[test]
@@ -441,12 +455,14 @@ mod tests {
         fs::write(base_path.join("demo_files/demo.py"), "# demo").unwrap();
 
         // Collect without exclude
-        let files_all = collect_files(base_path, &[]).unwrap();
+        let config = Config::default();
+        let parsers = ParserOverrides::default();
+        let files_all = collect_files(base_path, &[], &config, &parsers).unwrap();
         assert_eq!(files_all.len(), 1); // Only src/main.py (demo_files is in default ignore)
 
         // Collect with custom exclude
         let exclude = vec!["src".to_string()];
-        let files_excluded = collect_files(base_path, &exclude).unwrap();
+        let files_excluded = collect_files(base_path, &exclude, &config, &parsers).unwrap();
         assert_eq!(files_excluded.len(), 0); // Both excluded
     }
 }
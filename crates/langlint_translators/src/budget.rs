@@ -0,0 +1,317 @@
+//! Per-run cost and character budget
+//!
+//! [`BudgetTranslator`] wraps a translator and charges every request to a
+//! shared [`CostTracker`] before sending it: the characters submitted and
+//! the cost the translator estimates for them. A request that would take
+//! the run over its [`Budget`], or comes after its deadline, is not sent
+//! and fails with [`TranslationError::BudgetExceeded`].
+
+use crate::text_metrics::TextMetrics;
+use crate::{Pricing, TranslationError, TranslationRequest, TranslationResult, Translator};
+use async_trait::async_trait;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Limits on what one run may send; unset limits are not enforced
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Budget {
+    /// Estimated cost in US dollars
+    pub max_cost: Option<f64>,
+    /// Unicode code points submitted for translation
+    pub max_characters: Option<usize>,
+    /// Time after which no more requests are sent
+    pub deadline: Option<Instant>,
+}
+
+impl Budget {
+    /// Check if any limit is set
+    pub fn is_limited(&self) -> bool {
+        self.max_cost.is_some() || self.max_characters.is_some() || self.deadline.is_some()
+    }
+}
+
+/// What was sent to one translator
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Spending {
+    pub requests: u64,
+    pub characters: usize,
+    /// Estimated cost in US dollars
+    pub cost: f64,
+}
+
+impl Spending {
+    fn add(&mut self, other: &Spending) {
+        self.requests += other.requests;
+        self.characters += other.characters;
+        self.cost += other.cost;
+    }
+}
+
+impl fmt::Display for Spending {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} characters in {} requests, ${:.4} estimated",
+            self.characters, self.requests, self.cost
+        )
+    }
+}
+
+/// Thread-safe spending of a run, checked against its budget
+#[derive(Debug, Default)]
+pub struct CostTracker {
+    budget: Budget,
+    spent: Mutex<BTreeMap<String, Spending>>,
+}
+
+impl CostTracker {
+    /// Create a tracker enforcing `budget`
+    pub fn new(budget: Budget) -> Self {
+        Self {
+            budget,
+            spent: Mutex::default(),
+        }
+    }
+
+    pub fn budget(&self) -> Budget {
+        self.budget
+    }
+
+    /// Record a request of `characters` costing `cost`, unless it would
+    /// exceed the budget
+    pub fn charge(
+        &self,
+        translator: &str,
+        characters: usize,
+        cost: f64,
+    ) -> Result<(), TranslationError> {
+        if self
+            .budget
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return Err(TranslationError::BudgetExceeded(
+                "the time limit is up, no new requests are sent".to_string(),
+            ));
+        }
+
+        let mut spent = self.spent.lock().unwrap_or_else(|e| e.into_inner());
+        let mut total = Spending::default();
+        for spending in spent.values() {
+            total.add(spending);
+        }
+
+        if let Some(max) = self.budget.max_characters {
+            if total.characters + characters > max {
+                return Err(TranslationError::BudgetExceeded(format!(
+                    "sending {} more characters would exceed the limit of {} ({} sent)",
+                    characters, max, total.characters
+                )));
+            }
+        }
+        if let Some(max) = self.budget.max_cost {
+            if total.cost + cost > max {
+                return Err(TranslationError::BudgetExceeded(format!(
+                    "a request costing ${:.4} would exceed the limit of ${:.2} (${:.4} spent)",
+                    cost, max, total.cost
+                )));
+            }
+        }
+
+        spent
+            .entry(translator.to_string())
+            .or_default()
+            .add(&Spending {
+                requests: 1,
+                characters,
+                cost,
+            });
+        Ok(())
+    }
+
+    /// Spending across all translators
+    pub fn total(&self) -> Spending {
+        let spent = self.spent.lock().unwrap_or_else(|e| e.into_inner());
+        let mut total = Spending::default();
+        for spending in spent.values() {
+            total.add(spending);
+        }
+        total
+    }
+
+    /// Spending per translator name
+    pub fn by_translator(&self) -> BTreeMap<String, Spending> {
+        self.spent.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// Translator wrapper that charges every request to a [`CostTracker`]
+pub struct BudgetTranslator {
+    inner: Box<dyn Translator>,
+    tracker: Arc<CostTracker>,
+}
+
+impl BudgetTranslator {
+    /// Wrap a translator, charging a shared tracker
+    pub fn new(inner: Box<dyn Translator>, tracker: Arc<CostTracker>) -> Self {
+        Self { inner, tracker }
+    }
+
+    fn charge(&self, texts: &[&str], source: &str, target: &str) -> Result<(), TranslationError> {
+        let characters = texts
+            .iter()
+            .map(|text| TextMetrics::measure(text).chars)
+            .sum();
+        let cost = texts
+            .iter()
+            .map(|text| self.inner.estimate_cost(text, source, target))
+            .sum();
+        self.tracker.charge(self.name(), characters, cost)
+    }
+}
+
+#[async_trait]
+impl Translator for BudgetTranslator {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn cache_name(&self) -> String {
+        self.inner.cache_name()
+    }
+
+    fn supported_languages(&self) -> Vec<String> {
+        self.inner.supported_languages()
+    }
+
+    fn is_language_supported(&self, language_code: &str) -> bool {
+        self.inner.is_language_supported(language_code)
+    }
+
+    fn supports_pair(&self, source: &str, target: &str) -> bool {
+        self.inner.supports_pair(source, target)
+    }
+
+    fn normalize_language_code(&self, language_code: &str) -> String {
+        self.inner.normalize_language_code(language_code)
+    }
+
+    async fn translate(
+        &self,
+        text: &str,
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<TranslationResult, TranslationError> {
+        self.charge(&[text], source_language, target_language)?;
+        self.inner
+            .translate(text, source_language, target_language)
+            .await
+    }
+
+    async fn translate_batch(
+        &self,
+        texts: &[String],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        self.translate_requests(
+            &TranslationRequest::plain(texts),
+            source_language,
+            target_language,
+        )
+        .await
+    }
+
+    async fn translate_requests(
+        &self,
+        requests: &[TranslationRequest],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        let borrowed: Vec<&str> = requests.iter().map(|r| r.text.as_str()).collect();
+        self.charge(&borrowed, source_language, target_language)?;
+        self.inner
+            .translate_requests(requests, source_language, target_language)
+            .await
+    }
+
+    fn pricing(&self) -> Option<Pricing> {
+        self.inner.pricing()
+    }
+
+    fn estimate_cost(&self, text: &str, source: &str, target: &str) -> f64 {
+        self.inner.estimate_cost(text, source, target)
+    }
+
+    fn estimate_requests(&self, unit_count: usize) -> usize {
+        self.inner.estimate_requests(unit_count)
+    }
+
+    fn estimate_duration(&self, unit_count: usize) -> Duration {
+        self.inner.estimate_duration(unit_count)
+    }
+
+    fn get_usage_info(&self) -> HashMap<String, String> {
+        self.inner.get_usage_info()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockConfig, MockTranslator};
+
+    fn wrapped(tracker: &Arc<CostTracker>) -> BudgetTranslator {
+        let mock = MockTranslator::with_config(MockConfig {
+            delay_range: (0, 0),
+            ..MockConfig::default()
+        });
+        BudgetTranslator::new(Box::new(mock), Arc::clone(tracker))
+    }
+
+    #[tokio::test]
+    async fn test_character_budget() {
+        let tracker = Arc::new(CostTracker::new(Budget {
+            max_characters: Some(6),
+            ..Budget::default()
+        }));
+        let translator = wrapped(&tracker);
+
+        translator.translate("你好", "zh", "en").await.unwrap();
+        translator
+            .translate_batch(&["早上".to_string(), "好".to_string()], "zh", "en")
+            .await
+            .unwrap();
+        let error = translator
+            .translate("晚上好", "zh", "en")
+            .await
+            .unwrap_err();
+        assert!(matches!(error, TranslationError::BudgetExceeded(_)));
+
+        // The rejected request is not charged
+        let total = tracker.total();
+        assert_eq!((total.requests, total.characters), (2, 5));
+        assert_eq!(tracker.by_translator()["Mock"].requests, 2);
+    }
+
+    #[test]
+    fn test_cost_budget() {
+        let tracker = CostTracker::new(Budget {
+            max_cost: Some(1.0),
+            ..Budget::default()
+        });
+        tracker.charge("openai", 1000, 0.6).unwrap();
+        assert!(tracker.charge("google", 10, 0.5).is_err());
+        tracker.charge("google", 10, 0.3).unwrap();
+        assert_eq!(tracker.total().characters, 1010);
+        assert!(CostTracker::default().charge("google", 1, 100.0).is_ok());
+
+        let expired = CostTracker::new(Budget {
+            deadline: Some(Instant::now()),
+            ..Budget::default()
+        });
+        assert!(expired.charge("google", 1, 0.0).is_err());
+    }
+}
@@ -0,0 +1,476 @@
+//! Translation caching for any backend
+//!
+//! [`CachedTranslator`] remembers the successful results of the translator
+//! it wraps in a [`TranslationMemory`], keyed by text, language pair and
+//! the translator's [`Translator::cache_name`]. Remembered texts are
+//! answered without a request, and a batch sends only the texts it misses,
+//! each distinct text once. A [`SharedStore`] adds a second, remote layer:
+//! texts the memory misses are looked up there before translating, and new
+//! translations are stored there too. Hits and misses are counted in a
+//! [`TranslationMetrics`] registry when one is given.
+
+use crate::metrics::TranslationMetrics;
+use crate::{
+    Pricing, TranslationError, TranslationRequest, TranslationResult, TranslationStatus, Translator,
+};
+use async_trait::async_trait;
+use langlint_core::{MemoryEntry, TranslationMemory};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Translation memory kept outside the process, e.g. shared by CI runs
+///
+/// Entries are addressed by [`TranslationMemory::generate_key`]. A store
+/// only saves work, so it reports no errors.
+#[async_trait]
+pub trait SharedStore: Send + Sync {
+    /// Look up entries by memory key; missing keys are left out
+    async fn get_many(&self, keys: &[String]) -> HashMap<String, MemoryEntry>;
+
+    /// Store entries by memory key
+    async fn insert_many(&self, entries: HashMap<String, MemoryEntry>);
+}
+
+/// Translator wrapper answering repeated texts from a translation memory
+pub struct CachedTranslator<T: Translator> {
+    inner: T,
+    memory: Arc<TranslationMemory>,
+    shared: Option<Arc<dyn SharedStore>>,
+    metrics: Option<Arc<TranslationMetrics>>,
+}
+
+impl<T: Translator> CachedTranslator<T> {
+    /// Wrap a translator with a fresh in-memory cache
+    pub fn new(inner: T) -> Self {
+        Self::with_memory(inner, Arc::new(TranslationMemory::new()))
+    }
+
+    /// Wrap a translator with a shared memory, e.g. one kept on disk with
+    /// [`TranslationMemory::open`]
+    pub fn with_memory(inner: T, memory: Arc<TranslationMemory>) -> Self {
+        Self {
+            inner,
+            memory,
+            shared: None,
+            metrics: None,
+        }
+    }
+
+    /// Also look up and store translations in a shared store
+    pub fn with_shared(mut self, shared: Arc<dyn SharedStore>) -> Self {
+        self.shared = Some(shared);
+        self
+    }
+
+    /// Count cache hits and misses in a metrics registry
+    pub fn with_metrics(mut self, metrics: Arc<TranslationMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// The wrapped translator
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// The memory results are remembered in
+    pub fn memory(&self) -> &TranslationMemory {
+        &self.memory
+    }
+
+    fn lookup(&self, text: &str, source: &str, target: &str) -> Option<TranslationResult> {
+        let entry = self
+            .memory
+            .get(text, source, target, &self.inner.cache_name())?;
+        Some(
+            TranslationResult::success(
+                text.to_string(),
+                entry.translation,
+                source.to_string(),
+                target.to_string(),
+                entry.confidence,
+            )
+            .with_metadata("cache".to_string(), "hit".to_string()),
+        )
+    }
+
+    fn observe(&self, hits: usize, misses: usize) {
+        if let Some(metrics) = &self.metrics {
+            metrics.observe_cache(self.inner.name(), hits, misses);
+        }
+    }
+
+    /// Copy the shared store's entries for texts the memory misses
+    async fn fetch_shared(&self, texts: &[&String], source: &str, target: &str) {
+        let Some(shared) = &self.shared else {
+            return;
+        };
+        let name = self.inner.cache_name();
+        let mut missing: HashMap<String, &String> = HashMap::new();
+        for &text in texts {
+            if self.memory.get(text, source, target, &name).is_none() {
+                missing.insert(
+                    TranslationMemory::generate_key(text, source, target, &name),
+                    text,
+                );
+            }
+        }
+        if missing.is_empty() {
+            return;
+        }
+        let keys: Vec<String> = missing.keys().cloned().collect();
+        for (key, entry) in shared.get_many(&keys).await {
+            if let Some(text) = missing.get(&key) {
+                self.memory.insert(text, source, target, &name, entry);
+            }
+        }
+    }
+
+    /// Remember the successful results of `texts`, in the shared store too
+    ///
+    /// Results a fallback chain got from a translator other than its first
+    /// are not remembered, so a stand-in is not served once the preferred
+    /// translator is back.
+    async fn remember(
+        &self,
+        texts: &[&String],
+        results: &[TranslationResult],
+        source: &str,
+        target: &str,
+    ) {
+        let name = self.inner.cache_name();
+        let mut stored = HashMap::new();
+        for (text, result) in texts.iter().zip(results) {
+            let fallback = result
+                .metadata
+                .as_ref()
+                .is_some_and(|metadata| metadata.contains_key("fallback"));
+            if result.status != TranslationStatus::Success || fallback {
+                continue;
+            }
+            let entry = MemoryEntry {
+                translation: result.translated_text.clone(),
+                confidence: result.confidence,
+            };
+            if self.shared.is_some() {
+                let key = TranslationMemory::generate_key(text, source, target, &name);
+                stored.insert(key, entry.clone());
+            }
+            self.memory.insert(text, source, target, &name, entry);
+        }
+        if let Some(shared) = self.shared.as_ref().filter(|_| !stored.is_empty()) {
+            shared.insert_many(stored).await;
+        }
+    }
+}
+
+#[async_trait]
+impl<T: Translator> Translator for CachedTranslator<T> {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn cache_name(&self) -> String {
+        self.inner.cache_name()
+    }
+
+    fn supported_languages(&self) -> Vec<String> {
+        self.inner.supported_languages()
+    }
+
+    fn is_language_supported(&self, language_code: &str) -> bool {
+        self.inner.is_language_supported(language_code)
+    }
+
+    fn supports_pair(&self, source: &str, target: &str) -> bool {
+        self.inner.supports_pair(source, target)
+    }
+
+    fn normalize_language_code(&self, language_code: &str) -> String {
+        self.inner.normalize_language_code(language_code)
+    }
+
+    async fn translate(
+        &self,
+        text: &str,
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<TranslationResult, TranslationError> {
+        let text = &text.to_string();
+        self.fetch_shared(&[text], source_language, target_language)
+            .await;
+        if let Some(result) = self.lookup(text, source_language, target_language) {
+            self.observe(1, 0);
+            return Ok(result);
+        }
+        self.observe(0, 1);
+        let result = self
+            .inner
+            .translate(text, source_language, target_language)
+            .await?;
+        self.remember(
+            &[text],
+            std::slice::from_ref(&result),
+            source_language,
+            target_language,
+        )
+        .await;
+        Ok(result)
+    }
+
+    async fn translate_batch(
+        &self,
+        texts: &[String],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        self.translate_requests(
+            &TranslationRequest::plain(texts),
+            source_language,
+            target_language,
+        )
+        .await
+    }
+
+    async fn translate_requests(
+        &self,
+        requests: &[TranslationRequest],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        let name = self.inner.cache_name();
+        let texts: Vec<&String> = requests.iter().map(|request| &request.text).collect();
+        self.fetch_shared(&texts, source_language, target_language)
+            .await;
+        let mut results: Vec<Option<TranslationResult>> = Vec::with_capacity(texts.len());
+        let mut misses: Vec<TranslationRequest> = Vec::new();
+        let mut pending: HashMap<String, usize> = HashMap::new();
+        let mut waiting = Vec::new();
+        for (i, request) in requests.iter().enumerate() {
+            let text = &request.text;
+            match self.lookup(text, source_language, target_language) {
+                Some(result) => results.push(Some(result)),
+                None => {
+                    let key = TranslationMemory::generate_key(
+                        text,
+                        source_language,
+                        target_language,
+                        &name,
+                    );
+                    let miss = *pending.entry(key).or_insert_with(|| {
+                        misses.push(request.clone());
+                        misses.len() - 1
+                    });
+                    waiting.push((i, miss));
+                    results.push(None);
+                }
+            }
+        }
+
+        self.observe(texts.len() - waiting.len(), waiting.len());
+
+        if !misses.is_empty() {
+            let translated = self
+                .inner
+                .translate_requests(&misses, source_language, target_language)
+                .await?;
+            let sent: Vec<&String> = misses.iter().map(|miss| &miss.text).collect();
+            self.remember(&sent, &translated, source_language, target_language)
+                .await;
+            for (i, miss) in waiting {
+                results[i] = match translated.get(miss) {
+                    // A duplicate of an earlier text reuses its translation
+                    Some(result) if *texts[i] != misses[miss].text => Some(
+                        self.lookup(texts[i], source_language, target_language)
+                            .unwrap_or_else(|| TranslationResult {
+                                original_text: texts[i].clone(),
+                                translated_text: texts[i].clone(),
+                                ..result.clone()
+                            }),
+                    ),
+                    other => other.cloned(),
+                };
+            }
+        }
+
+        // A short reply from the translator fails the remaining texts
+        Ok(results
+            .into_iter()
+            .zip(texts)
+            .map(|(result, text)| {
+                result.unwrap_or_else(|| {
+                    TranslationResult::missing(
+                        text.clone(),
+                        source_language.to_string(),
+                        target_language.to_string(),
+                    )
+                })
+            })
+            .collect())
+    }
+
+    fn pricing(&self) -> Option<Pricing> {
+        self.inner.pricing()
+    }
+
+    fn estimate_cost(&self, text: &str, source: &str, target: &str) -> f64 {
+        self.inner.estimate_cost(text, source, target)
+    }
+
+    fn estimate_requests(&self, unit_count: usize) -> usize {
+        self.inner.estimate_requests(unit_count)
+    }
+
+    fn estimate_duration(&self, unit_count: usize) -> Duration {
+        self.inner.estimate_duration(unit_count)
+    }
+
+    fn get_usage_info(&self) -> HashMap<String, String> {
+        let mut info = self.inner.get_usage_info();
+        info.insert("cached_texts".to_string(), self.memory.len().to_string());
+        info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget::{BudgetTranslator, CostTracker};
+    use crate::mock::{MockConfig, MockFault, MockTranslator};
+
+    /// A cached mock whose requests are counted by `tracker`
+    fn counted(tracker: &Arc<CostTracker>) -> CachedTranslator<BudgetTranslator> {
+        let mock = MockTranslator::with_config(MockConfig {
+            delay_range: (0, 0),
+            ..MockConfig::default()
+        });
+        CachedTranslator::new(BudgetTranslator::new(Box::new(mock), Arc::clone(tracker)))
+    }
+
+    #[tokio::test]
+    async fn test_repeated_texts_are_not_sent() {
+        let tracker = Arc::new(CostTracker::default());
+        let translator = counted(&tracker);
+
+        let first = translator.translate("你好", "zh", "en").await.unwrap();
+        let second = translator.translate("你好", "zh", "en").await.unwrap();
+        assert_eq!(first.translated_text, second.translated_text);
+        assert_eq!(second.metadata.unwrap()["cache"], "hit");
+        // Another language pair is another entry
+        translator.translate("你好", "zh", "ja").await.unwrap();
+        assert_eq!(tracker.total().requests, 2);
+    }
+
+    #[tokio::test]
+    async fn test_batch_sends_only_misses() {
+        let tracker = Arc::new(CostTracker::default());
+        let translator = counted(&tracker);
+        translator.translate("早上好", "zh", "en").await.unwrap();
+
+        let texts: Vec<String> = ["早上好", "晚上好", "晚上好", "再见"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let results = translator
+            .translate_batch(&texts, "zh", "en")
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[1].translated_text, results[2].translated_text);
+        assert_eq!(results[2].original_text, "晚上好");
+        assert_eq!(translator.memory().len(), 3);
+
+        let total = tracker.total();
+        assert_eq!((total.requests, total.characters), (2, 3 + 3 + 2));
+    }
+
+    #[tokio::test]
+    async fn test_short_reply_fails_remaining_texts() {
+        let translator = CachedTranslator::new(MockTranslator::with_config(MockConfig {
+            delay_range: (0, 0),
+            faults: vec![MockFault::ShortReply(1)],
+            ..MockConfig::default()
+        }));
+        let texts: Vec<String> = ["早上好", "晚上好"].iter().map(|s| s.to_string()).collect();
+        let results = translator
+            .translate_batch(&texts, "zh", "en")
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].status, TranslationStatus::Success);
+        assert_eq!(results[1].status, TranslationStatus::Failed);
+        assert_eq!(results[1].translated_text, "晚上好");
+        assert_eq!(translator.memory().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_fallback_results_are_not_remembered() {
+        let translator = CachedTranslator::new(MockTranslator::with_config(MockConfig {
+            delay_range: (0, 0),
+            ..MockConfig::default()
+        }));
+        let sent = TranslationResult::success(
+            "你好".to_string(),
+            "[EN] 你好".to_string(),
+            "zh".to_string(),
+            "en".to_string(),
+            0.9,
+        )
+        .with_metadata("fallback".to_string(), "Mock".to_string());
+        let text = "你好".to_string();
+        translator.remember(&[&text], &[sent], "zh", "en").await;
+        assert!(translator.memory().is_empty());
+    }
+
+    #[derive(Default)]
+    struct Store(std::sync::Mutex<HashMap<String, MemoryEntry>>);
+
+    #[async_trait]
+    impl SharedStore for Store {
+        async fn get_many(&self, keys: &[String]) -> HashMap<String, MemoryEntry> {
+            let entries = self.0.lock().unwrap();
+            keys.iter()
+                .filter_map(|key| Some((key.clone(), entries.get(key)?.clone())))
+                .collect()
+        }
+
+        async fn insert_many(&self, entries: HashMap<String, MemoryEntry>) {
+            self.0.lock().unwrap().extend(entries);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shared_store() {
+        let tracker = Arc::new(CostTracker::default());
+        let store = Arc::new(Store::default());
+        let texts = vec!["早上好".to_string()];
+
+        let first = counted(&tracker).with_shared(store.clone());
+        first.translate_batch(&texts, "zh", "en").await.unwrap();
+        assert_eq!(store.0.lock().unwrap().len(), 1);
+
+        // Another run with an empty memory is answered by the store
+        let second = counted(&tracker).with_shared(store);
+        let results = second.translate_batch(&texts, "zh", "en").await.unwrap();
+        assert_eq!(results[0].metadata.as_ref().unwrap()["cache"], "hit");
+        assert_eq!(tracker.total().requests, 1);
+    }
+
+    #[tokio::test]
+    async fn test_counts_hits_and_misses() {
+        let tracker = Arc::new(CostTracker::default());
+        let metrics = Arc::new(TranslationMetrics::new());
+        let translator = counted(&tracker).with_metrics(Arc::clone(&metrics));
+        translator.translate("早上好", "zh", "en").await.unwrap();
+        let texts: Vec<String> = ["早上好", "晚上好"].iter().map(|s| s.to_string()).collect();
+        translator
+            .translate_batch(&texts, "zh", "en")
+            .await
+            .unwrap();
+
+        let text = metrics.render();
+        assert!(text.contains("langlint_cache_hits_total{translator=\"Mock\"} 1"));
+        assert!(text.contains("langlint_cache_misses_total{translator=\"Mock\"} 2"));
+    }
+}
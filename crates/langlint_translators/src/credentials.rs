@@ -0,0 +1,233 @@
+//! API keys of translator backends
+//!
+//! A provider's key is resolved from, in order:
+//!
+//! 1. keys set with [`set_api_key`] (`--api-key` flags, then the config file)
+//! 2. the `LANGLINT_<PROVIDER>_API_KEY` environment variable
+//! 3. the provider's own variable, such as `OPENAI_API_KEY`
+//! 4. the OS keyring, when enabled with [`use_keyring`]: the generic
+//!    password of service `langlint` and account `<provider>`, read with
+//!    `security` on macOS or `secret-tool` on Linux
+//!
+//! so every backend finds its key the same way and reports a missing one
+//! with all the places it looked.
+
+use crate::TranslationError;
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{OnceLock, RwLock};
+
+/// Keyring service the keys are stored under
+pub const KEYRING_SERVICE: &str = "langlint";
+
+/// Variables providers document for their keys
+const PROVIDER_VARIABLES: [(&str, &str); 6] = [
+    ("openai", "OPENAI_API_KEY"),
+    ("libretranslate", "LIBRETRANSLATE_API_KEY"),
+    ("mymemory", "MYMEMORY_API_KEY"),
+    ("papago", "PAPAGO_CLIENT_SECRET"),
+    ("deepl", "DEEPL_AUTH_KEY"),
+    ("azure", "AZURE_TRANSLATOR_KEY"),
+];
+
+/// Keys set explicitly, and whether to consult the OS keyring
+#[derive(Debug, Default)]
+pub struct Credentials {
+    keys: RwLock<HashMap<String, String>>,
+    keyring: AtomicBool,
+}
+
+impl Credentials {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The process-wide credentials
+    pub fn global() -> &'static Credentials {
+        static GLOBAL: OnceLock<Credentials> = OnceLock::new();
+        GLOBAL.get_or_init(Credentials::new)
+    }
+
+    /// Use `key` for `provider`, unless a key was set before
+    ///
+    /// Set keys in order of precedence: flags before the config file.
+    pub fn set_api_key(&self, provider: &str, key: &str) {
+        let key = key.trim();
+        if key.is_empty() {
+            return;
+        }
+        self.keys
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(provider.to_lowercase())
+            .or_insert_with(|| key.to_string());
+    }
+
+    /// Consult the OS keyring for keys found nowhere else
+    pub fn use_keyring(&self, enabled: bool) {
+        self.keyring.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Key of `provider`, reading variables through `lookup`
+    pub fn api_key<F>(&self, provider: &str, lookup: F) -> Option<String>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let provider = provider.to_lowercase();
+        if let Some(key) = self
+            .keys
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&provider)
+        {
+            return Some(key.clone());
+        }
+
+        let from_env = variables(&provider)
+            .iter()
+            .find_map(|name| lookup(name).filter(|key| !key.trim().is_empty()))
+            .map(|key| key.trim().to_string());
+        if from_env.is_some() {
+            return from_env;
+        }
+
+        if self.keyring.load(Ordering::Relaxed) {
+            let key = keyring_lookup(&provider)?;
+            // Remembered so the keyring is asked once per run
+            self.set_api_key(&provider, &key);
+            return Some(key);
+        }
+        None
+    }
+
+    /// Key of `provider`, or an error listing where to set it
+    pub fn require_api_key<F>(&self, provider: &str, lookup: F) -> Result<String, TranslationError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        self.api_key(provider, lookup)
+            .ok_or_else(|| missing_key_error(provider))
+    }
+}
+
+/// Use `key` for `provider` in this process (see [`Credentials::set_api_key`])
+pub fn set_api_key(provider: &str, key: &str) {
+    Credentials::global().set_api_key(provider, key);
+}
+
+/// Consult the OS keyring in this process
+pub fn use_keyring(enabled: bool) {
+    Credentials::global().use_keyring(enabled);
+}
+
+/// Key of `provider` from the process-wide credentials
+pub fn api_key<F>(provider: &str, lookup: F) -> Option<String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    Credentials::global().api_key(provider, lookup)
+}
+
+/// Key of `provider` from the process-wide credentials, or an error
+pub fn require_api_key<F>(provider: &str, lookup: F) -> Result<String, TranslationError>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    Credentials::global().require_api_key(provider, lookup)
+}
+
+/// Environment variables holding a provider's key, in lookup order
+fn variables(provider: &str) -> Vec<String> {
+    let mut names = vec![format!(
+        "LANGLINT_{}_API_KEY",
+        provider.to_uppercase().replace('-', "_")
+    )];
+    names.extend(
+        PROVIDER_VARIABLES
+            .iter()
+            .filter(|(name, _)| *name == provider)
+            .map(|(_, variable)| variable.to_string()),
+    );
+    names
+}
+
+fn missing_key_error(provider: &str) -> TranslationError {
+    TranslationError::InvalidInput(format!(
+        "No API key for {provider}: pass --api-key {provider}=KEY, set api_keys.{provider} in \
+         the config file or {}, or store it in the OS keyring (service {KEYRING_SERVICE}, \
+         account {provider}) and enable `keyring`",
+        variables(provider).join(" or "),
+    ))
+}
+
+/// Read a key from the OS keyring; `None` when absent or unsupported
+fn keyring_lookup(provider: &str) -> Option<String> {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("security");
+        command.args([
+            "find-generic-password",
+            "-s",
+            KEYRING_SERVICE,
+            "-a",
+            provider,
+            "-w",
+        ]);
+        command
+    } else if cfg!(unix) {
+        let mut command = Command::new("secret-tool");
+        command.args(["lookup", "service", KEYRING_SERVICE, "account", provider]);
+        command
+    } else {
+        return None;
+    };
+    let output = command.output().ok().filter(|o| o.status.success())?;
+    let key = String::from_utf8(output.stdout).ok()?;
+    Some(key.trim().to_string()).filter(|key| !key.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_order() {
+        let credentials = Credentials::new();
+        let env = |name: &str| match name {
+            "OPENAI_API_KEY" => Some("sk-provider".to_string()),
+            "LANGLINT_DEEPL_API_KEY" => Some("deepl-langlint".to_string()),
+            "DEEPL_AUTH_KEY" => Some("deepl-provider".to_string()),
+            "LANGLINT_AZURE_API_KEY" => Some("  ".to_string()),
+            _ => None,
+        };
+
+        assert_eq!(
+            credentials.api_key("openai", env).as_deref(),
+            Some("sk-provider")
+        );
+        assert_eq!(
+            credentials.api_key("DeepL", env).as_deref(),
+            Some("deepl-langlint")
+        );
+        assert_eq!(credentials.api_key("azure", env), None);
+
+        // Explicit keys win, and the first one set stays
+        credentials.set_api_key("openai", "sk-flag");
+        credentials.set_api_key("OpenAI", "sk-config");
+        assert_eq!(
+            credentials.api_key("openai", env).as_deref(),
+            Some("sk-flag")
+        );
+    }
+
+    #[test]
+    fn test_missing_key_error() {
+        let error = Credentials::new()
+            .require_api_key("openai", |_| None)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("--api-key openai=KEY"));
+        assert!(error.contains("LANGLINT_OPENAI_API_KEY or OPENAI_API_KEY"));
+        assert!(error.contains("account openai"));
+    }
+}
@@ -0,0 +1,488 @@
+//! Ensembles of translators
+//!
+//! [`EnsembleTranslator`] sends each text to every translator it holds and
+//! keeps the candidate the others agree with best: the one with the highest
+//! confidence-weighted similarity (by edit distance) to the rest. Candidates
+//! failing basic QA checks only count when none pass. How much the
+//! candidates agree is recorded in the metadata and scales the confidence,
+//! so disputed translations fall under `--min-confidence` review.
+
+use crate::{
+    TranslationError, TranslationRequest, TranslationResult, TranslationStatus, Translator,
+};
+use async_trait::async_trait;
+use futures::future;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Agreement below which the alternatives are recorded for review
+pub const DISAGREEMENT_THRESHOLD: f64 = 0.6;
+
+/// Translator querying several translators and keeping the consensus
+pub struct EnsembleTranslator {
+    translators: Vec<Box<dyn Translator>>,
+}
+
+/// A successful result of one member for one text
+struct Candidate<'a> {
+    member: usize,
+    result: &'a TranslationResult,
+    passes_qa: bool,
+}
+
+impl EnsembleTranslator {
+    /// Create an ensemble of two or more translators
+    pub fn new(translators: Vec<Box<dyn Translator>>) -> Result<Self, TranslationError> {
+        if translators.len() < 2 {
+            return Err(TranslationError::InvalidInput(
+                "An ensemble needs at least two translators".to_string(),
+            ));
+        }
+        Ok(Self { translators })
+    }
+
+    /// Number of translators in the ensemble
+    pub fn len(&self) -> usize {
+        self.translators.len()
+    }
+
+    /// Check whether the ensemble is empty (never true once constructed)
+    pub fn is_empty(&self) -> bool {
+        self.translators.is_empty()
+    }
+
+    /// Pick the result of one text from the results of each member
+    fn choose(&self, text: &str, results: &[Option<&TranslationResult>]) -> TranslationResult {
+        let candidates: Vec<Candidate> = results
+            .iter()
+            .enumerate()
+            .filter_map(|(member, result)| {
+                let result = (*result)?;
+                (result.status == TranslationStatus::Success).then(|| Candidate {
+                    member,
+                    result,
+                    passes_qa: passes_qa(text, &result.translated_text),
+                })
+            })
+            .collect();
+        let Some(any) = results.iter().flatten().next() else {
+            unreachable!("an ensemble always has results to choose from");
+        };
+        if candidates.is_empty() {
+            // Nothing succeeded; report the first member's outcome
+            return (*any).clone();
+        }
+
+        let pool: Vec<&Candidate> = if candidates.iter().any(|c| c.passes_qa) {
+            candidates.iter().filter(|c| c.passes_qa).collect()
+        } else {
+            candidates.iter().collect()
+        };
+        let similarities: Vec<Vec<f64>> = pool
+            .iter()
+            .map(|a| {
+                pool.iter()
+                    .map(|b| similarity(&a.result.translated_text, &b.result.translated_text))
+                    .collect()
+            })
+            .collect();
+
+        // Confidence-weighted agreement with the others, ties to the first
+        let score = |i: usize| {
+            pool[i].result.confidence
+                + (0..pool.len())
+                    .filter(|&j| j != i)
+                    .map(|j| pool[j].result.confidence * similarities[i][j])
+                    .sum::<f64>()
+        };
+        let best = (0..pool.len()).fold(0, |best, i| if score(i) > score(best) { i } else { best });
+        let agreement = if pool.len() > 1 {
+            (0..pool.len())
+                .filter(|&j| j != best)
+                .map(|j| similarities[best][j])
+                .sum::<f64>()
+                / (pool.len() - 1) as f64
+        } else {
+            // A lone candidate agrees with nobody
+            0.0
+        };
+
+        let chosen = pool[best];
+        let mut result = TranslationResult {
+            confidence: chosen.result.confidence * (0.5 + agreement / 2.0),
+            ..chosen.result.clone()
+        }
+        .with_metadata(
+            "ensemble".to_string(),
+            self.translators[chosen.member].name().to_string(),
+        )
+        .with_metadata(
+            "ensemble_agreement".to_string(),
+            format!("{:.2}", agreement),
+        )
+        .with_metadata(
+            "ensemble_candidates".to_string(),
+            format!("{}/{}", pool.len(), self.translators.len()),
+        );
+        if agreement < DISAGREEMENT_THRESHOLD {
+            let alternatives: Vec<String> = candidates
+                .iter()
+                .filter(|c| c.member != chosen.member)
+                .map(|c| {
+                    format!(
+                        "{}: {}",
+                        self.translators[c.member].name(),
+                        c.result.translated_text
+                    )
+                })
+                .collect();
+            result = result.with_metadata(
+                "ensemble_disagreement".to_string(),
+                alternatives.join(" | "),
+            );
+        }
+        result
+    }
+}
+
+/// Basic checks a usable translation passes: it is not empty, not the
+/// original text, keeps the line count and the numbers, and is of a
+/// plausible length
+fn passes_qa(original: &str, translated: &str) -> bool {
+    let translated = translated.trim();
+    if translated.is_empty() || translated == original.trim() {
+        return false;
+    }
+    if original.lines().count() != translated.lines().count() {
+        return false;
+    }
+    let numbers = |text: &str| -> Vec<String> {
+        let mut numbers: Vec<String> = text
+            .split(|c: char| !c.is_ascii_digit())
+            .filter(|n| !n.is_empty())
+            .map(str::to_string)
+            .collect();
+        numbers.sort();
+        numbers
+    };
+    if numbers(original) != numbers(translated) {
+        return false;
+    }
+    let ratio = translated.chars().count() as f64 / original.chars().count().max(1) as f64;
+    (0.2..=6.0).contains(&ratio)
+}
+
+/// Similarity of two texts, 1 minus their edit distance over the longer
+/// length
+fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    1.0 - previous[b.len()] as f64 / longest as f64
+}
+
+#[async_trait]
+impl Translator for EnsembleTranslator {
+    fn name(&self) -> &'static str {
+        "Ensemble"
+    }
+
+    fn cache_name(&self) -> String {
+        let members: Vec<String> = self.translators.iter().map(|t| t.cache_name()).collect();
+        format!("ensemble({})", members.join("+"))
+    }
+
+    fn supported_languages(&self) -> Vec<String> {
+        let mut languages: Vec<String> = self
+            .translators
+            .iter()
+            .flat_map(|t| t.supported_languages())
+            .collect();
+        languages.sort();
+        languages.dedup();
+        languages
+    }
+
+    fn supports_pair(&self, source: &str, target: &str) -> bool {
+        self.translators
+            .iter()
+            .any(|t| t.supports_pair(source, target))
+    }
+
+    fn validate_languages(&self, source: &str, target: &str) -> Result<(), TranslationError> {
+        if self
+            .translators
+            .iter()
+            .any(|t| t.validate_languages(source, target).is_ok())
+        {
+            return Ok(());
+        }
+        self.translators[0].validate_languages(source, target)
+    }
+
+    async fn translate(
+        &self,
+        text: &str,
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<TranslationResult, TranslationError> {
+        self.translate_batch(&[text.to_string()], source_language, target_language)
+            .await?
+            .pop()
+            .ok_or_else(|| TranslationError::InvalidInput("Empty translation".to_string()))
+    }
+
+    /// Send the batch to every member supporting the pair at once
+    ///
+    /// Members that fail the whole batch are left out; if all of them do,
+    /// the first error is returned.
+    async fn translate_batch(
+        &self,
+        texts: &[String],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        self.translate_requests(
+            &TranslationRequest::plain(texts),
+            source_language,
+            target_language,
+        )
+        .await
+    }
+
+    async fn translate_requests(
+        &self,
+        requests: &[TranslationRequest],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        self.validate_languages(source_language, target_language)?;
+
+        let replies = future::join_all(self.translators.iter().map(|translator| async move {
+            translator.validate_languages(source_language, target_language)?;
+            translator
+                .translate_requests(requests, source_language, target_language)
+                .await
+        }))
+        .await;
+
+        let mut first_error = None;
+        let mut members = Vec::new();
+        for reply in replies {
+            match reply {
+                Ok(results) => members.push(Some(results)),
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                    members.push(None);
+                }
+            }
+        }
+        if members.iter().all(Option::is_none) {
+            return Err(first_error.expect("an ensemble has members"));
+        }
+
+        // Texts every member's reply left out fail
+        Ok(requests
+            .iter()
+            .enumerate()
+            .map(|(i, request)| {
+                let results: Vec<Option<&TranslationResult>> = members
+                    .iter()
+                    .map(|member| member.as_ref().and_then(|results| results.get(i)))
+                    .collect();
+                if results.iter().any(Option::is_some) {
+                    self.choose(&request.text, &results)
+                } else {
+                    TranslationResult::missing(
+                        request.text.clone(),
+                        source_language.to_string(),
+                        target_language.to_string(),
+                    )
+                }
+            })
+            .collect())
+    }
+
+    fn estimate_cost(&self, text: &str, source: &str, target: &str) -> f64 {
+        self.translators
+            .iter()
+            .map(|t| t.estimate_cost(text, source, target))
+            .sum()
+    }
+
+    fn estimate_requests(&self, unit_count: usize) -> usize {
+        self.translators
+            .iter()
+            .map(|t| t.estimate_requests(unit_count))
+            .sum()
+    }
+
+    fn estimate_duration(&self, unit_count: usize) -> Duration {
+        // Members are queried at once, so the slowest one counts
+        self.translators
+            .iter()
+            .map(|t| t.estimate_duration(unit_count))
+            .max()
+            .unwrap_or_default()
+    }
+
+    fn get_usage_info(&self) -> HashMap<String, String> {
+        let members: Vec<&str> = self.translators.iter().map(|t| t.name()).collect();
+        let mut info = HashMap::new();
+        info.insert("name".to_string(), self.name().to_string());
+        info.insert("members".to_string(), members.join(", "));
+        info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockConfig, MockFault, MockTranslator};
+
+    /// Translator answering from a fixed table
+    struct Fixed {
+        name: &'static str,
+        translations: HashMap<&'static str, &'static str>,
+        confidence: f64,
+    }
+
+    fn fixed(
+        name: &'static str,
+        translations: &[(&'static str, &'static str)],
+        confidence: f64,
+    ) -> Box<dyn Translator> {
+        Box::new(Fixed {
+            name,
+            translations: translations.iter().copied().collect(),
+            confidence,
+        })
+    }
+
+    #[async_trait]
+    impl Translator for Fixed {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn supported_languages(&self) -> Vec<String> {
+            vec!["zh".to_string(), "en".to_string()]
+        }
+
+        async fn translate(
+            &self,
+            text: &str,
+            source: &str,
+            target: &str,
+        ) -> Result<TranslationResult, TranslationError> {
+            let translated = self.translations.get(text).copied().unwrap_or(text);
+            Ok(TranslationResult::success(
+                text.to_string(),
+                translated.to_string(),
+                source.to_string(),
+                target.to_string(),
+                self.confidence,
+            ))
+        }
+
+        async fn translate_batch(
+            &self,
+            texts: &[String],
+            source: &str,
+            target: &str,
+        ) -> Result<Vec<TranslationResult>, TranslationError> {
+            let mut results = Vec::new();
+            for text in texts {
+                results.push(self.translate(text, source, target).await?);
+            }
+            Ok(results)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_consensus_wins() {
+        let ensemble = EnsembleTranslator::new(vec![
+            fixed("A", &[("读取 3 个文件", "Read 3 file")], 0.95),
+            fixed("B", &[("读取 3 个文件", "Read 3 files")], 0.8),
+            fixed("C", &[("读取 3 个文件", "Reads 3 files")], 0.8),
+        ])
+        .unwrap();
+
+        let result = ensemble
+            .translate("读取 3 个文件", "zh", "en")
+            .await
+            .unwrap();
+        assert_eq!(result.translated_text, "Read 3 files");
+        let metadata = result.metadata.unwrap();
+        assert_eq!(metadata["ensemble"], "B");
+        assert_eq!(metadata["ensemble_candidates"], "3/3");
+        assert!(!metadata.contains_key("ensemble_disagreement"));
+    }
+
+    #[tokio::test]
+    async fn test_qa_failures_and_disagreement() {
+        let ensemble = EnsembleTranslator::new(vec![
+            // Drops the number
+            fixed("A", &[("等待 30 秒", "Wait a moment")], 0.99),
+            fixed("B", &[("等待 30 秒", "Wait 30 seconds")], 0.9),
+            fixed("C", &[("等待 30 秒", "Sleep for 30 s")], 0.9),
+        ])
+        .unwrap();
+
+        let texts = vec!["等待 30 秒".to_string(), "未翻译".to_string()];
+        let results = ensemble.translate_batch(&texts, "zh", "en").await.unwrap();
+        assert_eq!(results[0].translated_text, "Wait 30 seconds");
+        let metadata = results[0].metadata.as_ref().unwrap();
+        assert_eq!(metadata["ensemble_candidates"], "2/3");
+        assert!(metadata["ensemble_disagreement"].contains("C: Sleep for 30 s"));
+        assert!(results[0].confidence < 0.9);
+
+        // Unchanged by every member: none passes QA, so all of them count
+        assert_eq!(results[1].translated_text, "未翻译");
+        assert_eq!(
+            results[1].metadata.as_ref().unwrap()["ensemble_agreement"],
+            "1.00"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_short_replies_fail_remaining_texts() {
+        let short = || -> Box<dyn Translator> {
+            Box::new(MockTranslator::with_config(MockConfig {
+                delay_range: (0, 0),
+                faults: vec![MockFault::ShortReply(1)],
+                ..MockConfig::default()
+            }))
+        };
+        let ensemble = EnsembleTranslator::new(vec![short(), short()]).unwrap();
+
+        let texts = vec!["早上好".to_string(), "晚安".to_string()];
+        let results = ensemble.translate_batch(&texts, "zh", "en").await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].translated_text, "[EN] 早上好");
+        assert_eq!(results[1].status, TranslationStatus::Failed);
+        assert_eq!(results[1].translated_text, "晚安");
+    }
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(similarity("abc", "abc"), 1.0);
+        assert_eq!(similarity("", ""), 1.0);
+        assert!((similarity("kitten", "sitting") - (1.0 - 3.0 / 7.0)).abs() < 1e-9);
+        assert!(EnsembleTranslator::new(vec![fixed("A", &[], 1.0)]).is_err());
+    }
+}
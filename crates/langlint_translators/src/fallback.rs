@@ -0,0 +1,363 @@
+//! Fallback chains across translators
+//!
+//! [`FallbackTranslator`] tries an ordered list of translators. When one is
+//! rate limited, or keeps failing, requests move on to the next one for the
+//! rest of the run, so one throttled provider does not end the whole run.
+
+use crate::{Pricing, TranslationError, TranslationRequest, TranslationResult, Translator};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Attempts per translator on `TranslationFailed` before falling back
+pub const DEFAULT_MAX_ATTEMPTS: usize = 2;
+
+/// A request forwarded down the chain
+#[derive(Clone, Copy)]
+enum Request<'a> {
+    Single(&'a str),
+    Batch(&'a [TranslationRequest]),
+}
+
+impl Request<'_> {
+    async fn send(
+        self,
+        translator: &dyn Translator,
+        source: &str,
+        target: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        match self {
+            Self::Single(text) => Ok(vec![translator.translate(text, source, target).await?]),
+            Self::Batch(requests) => {
+                translator
+                    .translate_requests(requests, source, target)
+                    .await
+            }
+        }
+    }
+}
+
+/// Translator that falls back to the next of a list of translators
+pub struct FallbackTranslator {
+    translators: Vec<Box<dyn Translator>>,
+    max_attempts: usize,
+    /// Index of the translator requests start with
+    active: AtomicUsize,
+}
+
+impl FallbackTranslator {
+    /// Create a chain from translators in order of preference
+    pub fn new(translators: Vec<Box<dyn Translator>>) -> Result<Self, TranslationError> {
+        if translators.is_empty() {
+            return Err(TranslationError::InvalidInput(
+                "A fallback chain needs at least one translator".to_string(),
+            ));
+        }
+        Ok(Self {
+            translators,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            active: AtomicUsize::new(0),
+        })
+    }
+
+    /// Set how often a translator may fail in a row before falling back
+    pub fn with_max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Number of translators in the chain
+    pub fn len(&self) -> usize {
+        self.translators.len()
+    }
+
+    /// Check whether the chain is empty (never true once constructed)
+    pub fn is_empty(&self) -> bool {
+        self.translators.is_empty()
+    }
+
+    /// Name of the translator requests currently start with
+    pub fn active(&self) -> &'static str {
+        self.translators[self.active.load(Ordering::SeqCst)].name()
+    }
+
+    /// Run a request against the chain, starting at the active translator
+    ///
+    /// Translators that do not support the language pair are skipped. A
+    /// translator that is rate limited, or fails `max_attempts` times in a
+    /// row, is given up on for later requests too.
+    async fn run(
+        &self,
+        request: Request<'_>,
+        source: &str,
+        target: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        let start = self.active.load(Ordering::SeqCst);
+        let mut last_error = None;
+
+        for (index, translator) in self.translators.iter().enumerate().skip(start) {
+            if let Err(e) = translator.validate_languages(source, target) {
+                last_error = Some(e);
+                continue;
+            }
+
+            for attempt in 1..=self.max_attempts {
+                match request.send(translator.as_ref(), source, target).await {
+                    Ok(results) => return Ok(self.label(results, index)),
+                    Err(e @ TranslationError::RateLimitExceeded { .. }) => {
+                        last_error = Some(e);
+                        break;
+                    }
+                    Err(e @ TranslationError::TranslationFailed { .. }) => {
+                        last_error = Some(e);
+                        if attempt == self.max_attempts {
+                            break;
+                        }
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+
+            // Give up on this translator for the rest of the run
+            if index + 1 < self.translators.len() {
+                self.active.fetch_max(index + 1, Ordering::SeqCst);
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            TranslationError::UnsupportedLanguage(format!("{}->{}", source, target))
+        }))
+    }
+
+    /// Mark results produced by a translator other than the first
+    fn label(&self, results: Vec<TranslationResult>, index: usize) -> Vec<TranslationResult> {
+        if index == 0 {
+            return results;
+        }
+        let name = self.translators[index].name();
+        results
+            .into_iter()
+            .map(|r| r.with_metadata("fallback".to_string(), name.to_string()))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Translator for FallbackTranslator {
+    fn name(&self) -> &'static str {
+        "Fallback"
+    }
+
+    fn cache_name(&self) -> String {
+        let members: Vec<String> = self.translators.iter().map(|t| t.cache_name()).collect();
+        format!("fallback({})", members.join(">"))
+    }
+
+    fn supported_languages(&self) -> Vec<String> {
+        let mut languages: Vec<String> = self
+            .translators
+            .iter()
+            .flat_map(|t| t.supported_languages())
+            .collect();
+        languages.sort();
+        languages.dedup();
+        languages
+    }
+
+    fn supports_pair(&self, source: &str, target: &str) -> bool {
+        self.translators
+            .iter()
+            .any(|t| t.supports_pair(source, target))
+    }
+
+    fn validate_languages(&self, source: &str, target: &str) -> Result<(), TranslationError> {
+        if self
+            .translators
+            .iter()
+            .any(|t| t.validate_languages(source, target).is_ok())
+        {
+            return Ok(());
+        }
+        self.translators[0].validate_languages(source, target)
+    }
+
+    async fn translate(
+        &self,
+        text: &str,
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<TranslationResult, TranslationError> {
+        self.run(Request::Single(text), source_language, target_language)
+            .await?
+            .pop()
+            .ok_or_else(|| TranslationError::InvalidInput("Empty translation".to_string()))
+    }
+
+    async fn translate_batch(
+        &self,
+        texts: &[String],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        self.translate_requests(
+            &TranslationRequest::plain(texts),
+            source_language,
+            target_language,
+        )
+        .await
+    }
+
+    async fn translate_requests(
+        &self,
+        requests: &[TranslationRequest],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        self.run(Request::Batch(requests), source_language, target_language)
+            .await
+    }
+
+    fn pricing(&self) -> Option<Pricing> {
+        self.translators[0].pricing()
+    }
+
+    fn estimate_cost(&self, text: &str, source: &str, target: &str) -> f64 {
+        self.translators[0].estimate_cost(text, source, target)
+    }
+
+    fn estimate_requests(&self, unit_count: usize) -> usize {
+        self.translators[0].estimate_requests(unit_count)
+    }
+
+    fn estimate_duration(&self, unit_count: usize) -> Duration {
+        self.translators[0].estimate_duration(unit_count)
+    }
+
+    fn get_usage_info(&self) -> HashMap<String, String> {
+        let chain: Vec<&str> = self.translators.iter().map(|t| t.name()).collect();
+        let mut info = HashMap::new();
+        info.insert("name".to_string(), self.name().to_string());
+        info.insert("chain".to_string(), chain.join(" -> "));
+        info.insert("active".to_string(), self.active().to_string());
+        info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockConfig, MockFault, MockTranslator};
+    use regex::Regex;
+
+    fn mock(faults: Vec<MockFault>) -> Box<dyn Translator> {
+        Box::new(MockTranslator::with_config(MockConfig {
+            delay_range: (0, 0),
+            faults,
+            ..MockConfig::default()
+        }))
+    }
+
+    /// Translator that is always rate limited
+    struct Throttled;
+
+    #[async_trait]
+    impl Translator for Throttled {
+        fn name(&self) -> &'static str {
+            "Throttled"
+        }
+
+        fn supported_languages(&self) -> Vec<String> {
+            vec!["zh".to_string(), "en".to_string()]
+        }
+
+        async fn translate(
+            &self,
+            _text: &str,
+            _source: &str,
+            _target: &str,
+        ) -> Result<TranslationResult, TranslationError> {
+            Err(TranslationError::RateLimitExceeded { retry_after: None })
+        }
+
+        async fn translate_batch(
+            &self,
+            _texts: &[String],
+            _source: &str,
+            _target: &str,
+        ) -> Result<Vec<TranslationResult>, TranslationError> {
+            Err(TranslationError::RateLimitExceeded { retry_after: None })
+        }
+    }
+
+    #[test]
+    fn test_empty_chain_is_rejected() {
+        assert!(FallbackTranslator::new(Vec::new()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_when_rate_limited() {
+        let chain = FallbackTranslator::new(vec![Box::new(Throttled), mock(Vec::new())]).unwrap();
+
+        let result = chain.translate("你好", "zh", "en").await.unwrap();
+        assert_eq!(result.translated_text, "[EN] 你好");
+        assert_eq!(result.metadata.unwrap()["fallback"], "Mock");
+
+        // The throttled translator is not tried again
+        assert_eq!(chain.active(), "Mock");
+        let results = chain
+            .translate_batch(&["再见".to_string()], "zh", "en")
+            .await
+            .unwrap();
+        assert_eq!(results[0].translated_text, "[EN] 再见");
+    }
+
+    #[test]
+    fn test_cache_name_lists_chain() {
+        let chain = FallbackTranslator::new(vec![Box::new(Throttled), mock(Vec::new())]).unwrap();
+        assert_eq!(chain.cache_name(), "fallback(Throttled>Mock)");
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_after_repeated_failures() {
+        let failing = mock(vec![MockFault::FailMatching(Regex::new(".").unwrap())]);
+        let chain = FallbackTranslator::new(vec![failing, mock(Vec::new())])
+            .unwrap()
+            .with_max_attempts(3);
+
+        let result = chain.translate("你好", "zh", "en").await.unwrap();
+        assert_eq!(result.translated_text, "[EN] 你好");
+        assert_eq!(chain.active(), "Mock");
+    }
+
+    #[tokio::test]
+    async fn test_single_failure_is_retried() {
+        let flaky = mock(vec![MockFault::FailIndices(vec![0])]);
+        let chain = FallbackTranslator::new(vec![flaky, Box::new(Throttled)]).unwrap();
+
+        let result = chain.translate("你好", "zh", "en").await.unwrap();
+        assert_eq!(result.translated_text, "[EN] 你好");
+        assert!(!result.metadata.unwrap().contains_key("fallback"));
+    }
+
+    #[tokio::test]
+    async fn test_last_error_when_all_fail() {
+        let chain =
+            FallbackTranslator::new(vec![Box::new(Throttled), Box::new(Throttled)]).unwrap();
+        assert!(matches!(
+            chain.translate("你好", "zh", "en").await,
+            Err(TranslationError::RateLimitExceeded { retry_after: None })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_skips_translators_without_language_pair() {
+        let chain = FallbackTranslator::new(vec![Box::new(Throttled), mock(Vec::new())]).unwrap();
+        assert!(chain.validate_languages("fr", "de").is_ok());
+
+        let result = chain.translate("Bonjour", "fr", "de").await.unwrap();
+        assert_eq!(result.translated_text, "[Deutsch] Bonjour");
+        // Skipping for an unsupported pair does not retire the translator
+        assert_eq!(chain.active(), "Throttled");
+    }
+}
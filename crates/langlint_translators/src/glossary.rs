@@ -0,0 +1,555 @@
+//! Glossary enforcement for consistent terminology
+//!
+//! A [`Glossary`] maps source terms to required translations (`张量` ->
+//! `tensor`). [`GlossaryTranslator`] wraps any translator and applies it:
+//!
+//! * [`GlossaryMode::Protect`] (default) substitutes the required terms into
+//!   the text before it is sent, so the translator keeps them as they are.
+//! * [`GlossaryMode::Postprocess`] sends the text unchanged.
+//!
+//! In both modes the translation is then post-processed: known wrong
+//! `variants` of a term and source terms the translator left untranslated
+//! are replaced with the required translation.
+//!
+//! Glossaries are TOML:
+//!
+//! ```toml
+//! mode = "protect"      # or "postprocess"
+//! target_lang = "en"    # optional; other targets are left alone
+//!
+//! [[term]]
+//! source = "张量"
+//! target = "tensor"
+//!
+//! [[term]]
+//! source = "句柄"
+//! target = "handle"
+//! variants = ["handler"]
+//! ```
+//!
+//! or CSV with `source,target[,variant|variant...]` rows.
+
+use crate::{Pricing, TranslationError, TranslationRequest, TranslationResult, Translator};
+use anyhow::Context;
+use async_trait::async_trait;
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::time::Duration;
+
+/// How a glossary is applied
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GlossaryMode {
+    /// Substitute terms before translating, then post-process
+    #[default]
+    Protect,
+    /// Only post-process translations
+    Postprocess,
+}
+
+/// A term and its required translation
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+pub struct GlossaryTerm {
+    pub source: String,
+    pub target: String,
+    /// Wrong translations replaced by `target` in translated text
+    #[serde(default)]
+    pub variants: Vec<String>,
+}
+
+/// File layout of a TOML glossary
+#[derive(Deserialize)]
+struct GlossaryFile {
+    #[serde(default)]
+    mode: GlossaryMode,
+    #[serde(default)]
+    target_lang: Option<String>,
+    #[serde(default, rename = "term")]
+    terms: Vec<GlossaryTerm>,
+}
+
+/// Required translations of terms
+#[derive(Debug, Clone)]
+pub struct Glossary {
+    terms: Vec<GlossaryTerm>,
+    mode: GlossaryMode,
+    target_lang: Option<String>,
+    /// Matches source terms, longest first
+    sources: Option<Regex>,
+    /// Matches variants, longest first
+    variants: Option<Regex>,
+    /// Lowercased source term or variant -> required translation
+    lookup: HashMap<String, String>,
+}
+
+impl Glossary {
+    /// Create a glossary from terms
+    pub fn new(terms: Vec<GlossaryTerm>) -> Self {
+        let terms: Vec<GlossaryTerm> = terms
+            .into_iter()
+            .filter(|t| !t.source.trim().is_empty() && !t.target.trim().is_empty())
+            .collect();
+
+        let mut lookup = HashMap::new();
+        for term in &terms {
+            for variant in &term.variants {
+                lookup.insert(variant.to_lowercase(), term.target.clone());
+            }
+        }
+        for term in &terms {
+            lookup.insert(term.source.to_lowercase(), term.target.clone());
+        }
+
+        Self {
+            sources: alternation(terms.iter().map(|t| t.source.as_str())),
+            variants: alternation(
+                terms
+                    .iter()
+                    .flat_map(|t| t.variants.iter().map(String::as_str)),
+            ),
+            terms,
+            mode: GlossaryMode::default(),
+            target_lang: None,
+            lookup,
+        }
+    }
+
+    /// Set how the glossary is applied
+    pub fn with_mode(mut self, mode: GlossaryMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Only apply to translations into this language
+    pub fn with_target_lang(mut self, language: impl Into<String>) -> Self {
+        self.target_lang = Some(language.into());
+        self
+    }
+
+    /// Load a glossary from a `.toml` or `.csv` file
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read glossary: {}", path.display()))?;
+        let is_csv = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+        if is_csv {
+            Ok(Self::from_csv(&content))
+        } else {
+            Self::from_toml(&content)
+                .with_context(|| format!("Failed to parse glossary: {}", path.display()))
+        }
+    }
+
+    /// Parse a TOML glossary
+    pub fn from_toml(content: &str) -> anyhow::Result<Self> {
+        let file: GlossaryFile = toml::from_str(content)?;
+        let mut glossary = Self::new(file.terms).with_mode(file.mode);
+        glossary.target_lang = file.target_lang;
+        Ok(glossary)
+    }
+
+    /// Parse `source,target[,variant|variant...]` rows
+    ///
+    /// Blank lines, `#` comments and a `source,target` header are skipped.
+    pub fn from_csv(content: &str) -> Self {
+        let terms = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, ',').map(|f| f.trim().trim_matches('"'));
+                let source = fields.next()?;
+                let target = fields.next()?;
+                if source.eq_ignore_ascii_case("source") && target.eq_ignore_ascii_case("target") {
+                    return None;
+                }
+                let variants = fields
+                    .next()
+                    .map(|v| {
+                        v.split('|')
+                            .map(str::trim)
+                            .filter(|v| !v.is_empty())
+                            .map(str::to_string)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                Some(GlossaryTerm {
+                    source: source.to_string(),
+                    target: target.to_string(),
+                    variants,
+                })
+            })
+            .collect();
+        Self::new(terms)
+    }
+
+    pub fn terms(&self) -> &[GlossaryTerm] {
+        &self.terms
+    }
+
+    pub fn mode(&self) -> GlossaryMode {
+        self.mode
+    }
+
+    pub fn len(&self) -> usize {
+        self.terms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Check whether the glossary applies to translations into `target`
+    pub fn applies_to(&self, target: &str) -> bool {
+        self.target_lang.as_deref().is_none_or(|lang| {
+            let lang = lang.to_lowercase();
+            let target = target.to_lowercase();
+            target == lang || target.starts_with(&format!("{}-", lang))
+        })
+    }
+
+    /// Substitute required translations for source terms in a text
+    ///
+    /// Returns the text and the number of terms replaced.
+    pub fn protect(&self, text: &str) -> (String, usize) {
+        self.replace(self.sources.as_ref(), text)
+    }
+
+    /// Fix a translation: replace variants and untranslated source terms
+    ///
+    /// Returns the text and the number of terms replaced.
+    pub fn postprocess(&self, translation: &str) -> (String, usize) {
+        let (text, variants) = self.replace(self.variants.as_ref(), translation);
+        let (text, sources) = self.replace(self.sources.as_ref(), &text);
+        (text, variants + sources)
+    }
+
+    /// Stable hash of the terms and mode, to key cached translations
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.terms.hash(&mut hasher);
+        self.mode.hash(&mut hasher);
+        self.target_lang.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn replace(&self, pattern: Option<&Regex>, text: &str) -> (String, usize) {
+        let Some(pattern) = pattern else {
+            return (text.to_string(), 0);
+        };
+        let mut count = 0;
+        let replaced = pattern.replace_all(text, |caps: &regex::Captures| {
+            let found = &caps[0];
+            match self.lookup.get(&found.to_lowercase()) {
+                Some(target) => {
+                    count += 1;
+                    target.clone()
+                }
+                None => found.to_string(),
+            }
+        });
+        (replaced.into_owned(), count)
+    }
+}
+
+/// Case-insensitive regex matching any of the terms, longest first
+///
+/// Terms that start or end with an ASCII word character only match whole
+/// words, so `handle` does not match inside `handlers`.
+fn alternation<'a>(terms: impl Iterator<Item = &'a str>) -> Option<Regex> {
+    let mut terms: Vec<&str> = terms.filter(|t| !t.trim().is_empty()).collect();
+    if terms.is_empty() {
+        return None;
+    }
+    terms.sort_by(|a, b| b.chars().count().cmp(&a.chars().count()).then(a.cmp(b)));
+    terms.dedup();
+
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_ascii_alphanumeric() || c == '_');
+    let alternatives: Vec<String> = terms
+        .iter()
+        .map(|term| {
+            format!(
+                "{}{}{}",
+                if is_word(term.chars().next()) {
+                    r"\b"
+                } else {
+                    ""
+                },
+                regex::escape(term),
+                if is_word(term.chars().last()) {
+                    r"\b"
+                } else {
+                    ""
+                },
+            )
+        })
+        .collect();
+    RegexBuilder::new(&alternatives.join("|"))
+        .case_insensitive(true)
+        .build()
+        .ok()
+}
+
+/// Translator that enforces a glossary on another translator
+pub struct GlossaryTranslator {
+    inner: Box<dyn Translator>,
+    glossary: Glossary,
+}
+
+impl GlossaryTranslator {
+    pub fn new(inner: Box<dyn Translator>, glossary: Glossary) -> Self {
+        Self { inner, glossary }
+    }
+
+    pub fn glossary(&self) -> &Glossary {
+        &self.glossary
+    }
+
+    /// Text to send for `text`, and the number of terms substituted
+    fn prepare(&self, text: &str, target: &str) -> (String, usize) {
+        if self.glossary.mode == GlossaryMode::Protect && self.glossary.applies_to(target) {
+            self.glossary.protect(text)
+        } else {
+            (text.to_string(), 0)
+        }
+    }
+
+    /// Post-process a result and restore its original text
+    fn finish(
+        &self,
+        mut result: TranslationResult,
+        original: &str,
+        protected: usize,
+    ) -> TranslationResult {
+        result.original_text = original.to_string();
+        if !self.glossary.applies_to(&result.target_language) {
+            return result;
+        }
+        let (translated, fixed) = self.glossary.postprocess(&result.translated_text);
+        result.translated_text = translated;
+        if protected + fixed > 0 {
+            result = result.with_metadata("glossary".to_string(), (protected + fixed).to_string());
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl Translator for GlossaryTranslator {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn cache_name(&self) -> String {
+        format!(
+            "{}+glossary-{:016x}",
+            self.inner.cache_name(),
+            self.glossary.fingerprint()
+        )
+    }
+
+    fn supported_languages(&self) -> Vec<String> {
+        self.inner.supported_languages()
+    }
+
+    fn is_language_supported(&self, language_code: &str) -> bool {
+        self.inner.is_language_supported(language_code)
+    }
+
+    fn supports_pair(&self, source: &str, target: &str) -> bool {
+        self.inner.supports_pair(source, target)
+    }
+
+    fn normalize_language_code(&self, language_code: &str) -> String {
+        self.inner.normalize_language_code(language_code)
+    }
+
+    fn validate_languages(&self, source: &str, target: &str) -> Result<(), TranslationError> {
+        self.inner.validate_languages(source, target)
+    }
+
+    async fn translate(
+        &self,
+        text: &str,
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<TranslationResult, TranslationError> {
+        let (prepared, protected) = self.prepare(text, target_language);
+        let result = self
+            .inner
+            .translate(&prepared, source_language, target_language)
+            .await?;
+        Ok(self.finish(result, text, protected))
+    }
+
+    async fn translate_batch(
+        &self,
+        texts: &[String],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        self.translate_requests(
+            &TranslationRequest::plain(texts),
+            source_language,
+            target_language,
+        )
+        .await
+    }
+
+    async fn translate_requests(
+        &self,
+        requests: &[TranslationRequest],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        let (prepared, protected): (Vec<TranslationRequest>, Vec<usize>) = requests
+            .iter()
+            .map(|request| {
+                let (text, protected) = self.prepare(&request.text, target_language);
+                (
+                    TranslationRequest {
+                        text,
+                        ..request.clone()
+                    },
+                    protected,
+                )
+            })
+            .unzip();
+        let results = self
+            .inner
+            .translate_requests(&prepared, source_language, target_language)
+            .await?;
+        Ok(results
+            .into_iter()
+            .zip(requests.iter().zip(protected))
+            .map(|(result, (request, protected))| self.finish(result, &request.text, protected))
+            .collect())
+    }
+
+    fn pricing(&self) -> Option<Pricing> {
+        self.inner.pricing()
+    }
+
+    fn estimate_cost(&self, text: &str, source: &str, target: &str) -> f64 {
+        self.inner.estimate_cost(text, source, target)
+    }
+
+    fn estimate_requests(&self, unit_count: usize) -> usize {
+        self.inner.estimate_requests(unit_count)
+    }
+
+    fn estimate_duration(&self, unit_count: usize) -> Duration {
+        self.inner.estimate_duration(unit_count)
+    }
+
+    fn get_usage_info(&self) -> HashMap<String, String> {
+        let mut info = self.inner.get_usage_info();
+        info.insert(
+            "glossary_terms".to_string(),
+            self.glossary.len().to_string(),
+        );
+        info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockConfig, MockTranslator};
+
+    fn term(source: &str, target: &str, variants: &[&str]) -> GlossaryTerm {
+        GlossaryTerm {
+            source: source.to_string(),
+            target: target.to_string(),
+            variants: variants.iter().map(|v| v.to_string()).collect(),
+        }
+    }
+
+    fn mock() -> Box<dyn Translator> {
+        Box::new(MockTranslator::with_config(MockConfig {
+            delay_range: (0, 0),
+            ..MockConfig::default()
+        }))
+    }
+
+    #[test]
+    fn test_protect_prefers_longest_term() {
+        let glossary = Glossary::new(vec![
+            term("张量", "tensor", &[]),
+            term("稀疏张量", "sparse tensor", &[]),
+        ]);
+        assert_eq!(
+            glossary.protect("把稀疏张量转成张量"),
+            ("把sparse tensor转成tensor".to_string(), 2)
+        );
+    }
+
+    #[test]
+    fn test_postprocess_fixes_variants_on_word_boundaries() {
+        let glossary = Glossary::new(vec![term("句柄", "handle", &["handler"])]);
+        assert_eq!(
+            glossary.postprocess("Close the Handler and 句柄 (handlers stay)"),
+            ("Close the handle and handle (handlers stay)".to_string(), 2)
+        );
+    }
+
+    #[test]
+    fn test_load_toml_and_csv() {
+        let glossary = Glossary::from_toml(
+            r#"
+mode = "postprocess"
+target_lang = "en"
+
+[[term]]
+source = "张量"
+target = "tensor"
+"#,
+        )
+        .unwrap();
+        assert_eq!(glossary.mode(), GlossaryMode::Postprocess);
+        assert_eq!(glossary.terms(), &[term("张量", "tensor", &[])]);
+        assert!(glossary.applies_to("en-US"));
+        assert!(!glossary.applies_to("ja"));
+
+        let glossary = Glossary::from_csv(
+            "source,target\n# comment\n张量,tensor\n句柄,handle,handler|handel\n",
+        );
+        assert_eq!(
+            glossary.terms(),
+            &[
+                term("张量", "tensor", &[]),
+                term("句柄", "handle", &["handler", "handel"]),
+            ]
+        );
+        assert!(Glossary::from_toml("[[term]]\nsource = 1").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_glossary_translator() {
+        let glossary = Glossary::new(vec![term("张量", "tensor", &[])]);
+        let fingerprint = glossary.fingerprint();
+        let translator = GlossaryTranslator::new(mock(), glossary);
+
+        let result = translator.translate("返回张量", "zh", "en").await.unwrap();
+        assert_eq!(result.original_text, "返回张量");
+        assert_eq!(result.translated_text, "[EN] 返回tensor");
+        assert_eq!(result.metadata.unwrap()["glossary"], "1");
+
+        let results = translator
+            .translate_batch(&["张量".to_string(), "你好".to_string()], "zh", "en")
+            .await
+            .unwrap();
+        assert_eq!(results[0].translated_text, "[EN] tensor");
+        assert_eq!(results[1].original_text, "你好");
+
+        assert_eq!(
+            translator.cache_name(),
+            format!("Mock+glossary-{:016x}", fingerprint)
+        );
+    }
+}
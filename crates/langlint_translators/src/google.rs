@@ -1,33 +1,60 @@
 //! Google Translate translator using the free API
 
+use crate::http::{self, HttpSettings};
+use crate::ratelimit::{self, RateLimit, RateLimiter};
+use crate::retry::{self, RetryPolicy};
+use crate::trace;
 use crate::{TranslationError, TranslationResult, Translator};
 use async_trait::async_trait;
-use rand::Rng;
+use futures::stream::{self, StreamExt};
 use serde::Deserialize;
 use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
 use std::time::Duration;
-use tokio::time::sleep;
+
+/// Maximum number of concurrent requests issued by `translate_batch`
+const BATCH_CONCURRENCY: usize = 3;
+
+/// Default endpoint of the free API
+const DEFAULT_SERVICE_URL: &str = "https://translate.googleapis.com";
+
+/// Marker joining the texts of a batch into one request
+///
+/// A symbol on a line of its own, which Google leaves untranslated, so the
+/// reply can be split back into one translation per text.
+const BATCH_SEPARATOR: &str = "\n⁂\n";
 
 /// Configuration for Google translator
 #[derive(Debug, Clone)]
 pub struct GoogleConfig {
     /// Timeout for requests in seconds
     pub timeout: u64,
-    /// Retry count for failed requests
-    pub retry_count: u32,
-    /// Random delay range in milliseconds (to avoid rate limiting)
-    pub delay_range: (u64, u64),
-    /// Custom service URLs (if any)
+    /// Retries of failed requests
+    pub retry: RetryPolicy,
+    /// Request limits shared by all Google translators in the process
+    pub rate_limit: RateLimit,
+    /// Custom service URLs (if any); the first one is used
     pub service_urls: Option<Vec<String>>,
+    /// Maximum number of texts sent in one request by `translate_batch`
+    pub batch_size: usize,
+    /// Maximum number of characters sent in one request by `translate_batch`,
+    /// keeping the request URL within the service's limit
+    pub max_batch_chars: usize,
+    /// Proxy and trusted certificates
+    pub http: HttpSettings,
 }
 
 impl Default for GoogleConfig {
     fn default() -> Self {
         Self {
             timeout: 30,
-            retry_count: 3,
-            delay_range: (300, 600), // 300-600ms to respect rate limits
+            retry: RetryPolicy::default(),
+            rate_limit: RateLimit::requests_per_minute(300),
             service_urls: None,
+            batch_size: 50,
+            max_batch_chars: 1800,
+            http: HttpSettings::default(),
         }
     }
 }
@@ -53,6 +80,7 @@ struct Sentence {
 pub struct GoogleTranslator {
     config: GoogleConfig,
     client: reqwest::Client,
+    limiter: Arc<RateLimiter>,
     language_mapping: HashMap<String, String>,
 }
 
@@ -63,11 +91,10 @@ impl GoogleTranslator {
     }
 
     /// Create a new Google translator with custom config
-    pub fn with_config(config: GoogleConfig) -> Result<Self, TranslationError> {
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(config.timeout))
-            .build()
-            .map_err(TranslationError::NetworkError)?;
+    pub fn with_config(mut config: GoogleConfig) -> Result<Self, TranslationError> {
+        config.retry = retry::policy(config.retry);
+        config.http = http::settings(config.http);
+        let client = config.http.client(Duration::from_secs(config.timeout))?;
 
         let mut language_mapping = HashMap::new();
 
@@ -111,6 +138,7 @@ impl GoogleTranslator {
         }
 
         Ok(Self {
+            limiter: ratelimit::shared_limiter("google", config.rate_limit),
             config,
             client,
             language_mapping,
@@ -125,7 +153,13 @@ impl GoogleTranslator {
         target_lang: &str,
     ) -> Result<String, TranslationError> {
         // Use Google Translate's free endpoint
-        let url = "https://translate.googleapis.com/translate_a/single";
+        let base_url = self
+            .config
+            .service_urls
+            .as_ref()
+            .and_then(|urls| urls.first())
+            .map_or(DEFAULT_SERVICE_URL, |url| url.trim_end_matches('/'));
+        let url = format!("{}/translate_a/single", base_url);
 
         let params = [
             ("client", "gtx"),
@@ -135,14 +169,15 @@ impl GoogleTranslator {
             ("q", text),
         ];
 
-        let response = self
-            .client
-            .get(url)
-            .query(&params)
-            .send()
+        let response = trace::send("google", self.client.get(&url).query(&params))
             .await
             .map_err(TranslationError::NetworkError)?;
 
+        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(TranslationError::RateLimitExceeded {
+                retry_after: retry::retry_after(response.headers()),
+            });
+        }
         if !response.status().is_success() {
             return Err(TranslationError::TranslationFailed {
                 message: format!("HTTP error: {}", response.status()),
@@ -162,20 +197,121 @@ impl GoogleTranslator {
                     error_code: Some("PARSE_ERROR".to_string()),
                 })?;
 
-        // Extract translation from nested array structure
-        // Response format: [[[translated_text, original_text, null, null, ...]], ...]
-        let translated_text = json
-            .get(0)
-            .and_then(|arr| arr.get(0))
-            .and_then(|sentence| sentence.get(0))
-            .and_then(|text| text.as_str())
-            .ok_or_else(|| TranslationError::TranslationFailed {
+        // Extract translation from nested array structure, one entry per sentence
+        // Response format: [[[translated_text, original_text, null, null, ...], ...], ...]
+        let sentences: Option<Vec<&str>> =
+            json.get(0).and_then(|arr| arr.as_array()).map(|sentences| {
+                sentences
+                    .iter()
+                    .filter_map(|sentence| sentence.get(0).and_then(|text| text.as_str()))
+                    .collect()
+            });
+        match sentences {
+            Some(sentences) if !sentences.is_empty() => Ok(sentences.concat()),
+            _ => Err(TranslationError::TranslationFailed {
                 message: "Failed to extract translation from response".to_string(),
                 translator_name: "Google Translate".to_string(),
                 error_code: Some("EXTRACTION_ERROR".to_string()),
-            })?;
+            }),
+        }
+    }
 
-        Ok(translated_text.to_string())
+    /// Split texts into batches within the configured size and character
+    /// limits, as index ranges
+    ///
+    /// Texts containing the separator are sent on their own.
+    fn batches(&self, texts: &[String]) -> Vec<Range<usize>> {
+        let mut batches = Vec::new();
+        let mut start = 0;
+        let mut chars = 0;
+        for (i, text) in texts.iter().enumerate() {
+            let len = text.chars().count() + BATCH_SEPARATOR.chars().count();
+            let alone = text.contains(BATCH_SEPARATOR.trim());
+            let full = i - start >= self.config.batch_size.max(1)
+                || chars + len > self.config.max_batch_chars;
+            if i > start && (alone || full) {
+                batches.push(start..i);
+                start = i;
+                chars = 0;
+            }
+            chars += len;
+            if alone {
+                batches.push(start..i + 1);
+                start = i + 1;
+                chars = 0;
+            }
+        }
+        if start < texts.len() {
+            batches.push(start..texts.len());
+        }
+        batches
+    }
+
+    /// Translate a batch of texts in one request
+    ///
+    /// Falls back to one request per text if the reply does not split into
+    /// as many translations as there are texts.
+    async fn translate_joined(
+        &self,
+        texts: &[String],
+        source_lang: &str,
+        target_lang: &str,
+    ) -> Vec<Result<TranslationResult, TranslationError>> {
+        if texts.len() > 1 {
+            let joined = texts.join(BATCH_SEPARATOR);
+            let reply = self
+                .config
+                .retry
+                .run(|_| async {
+                    self.limiter.acquire(joined.chars().count()).await;
+                    self.call_google_api(&joined, source_lang, target_lang)
+                        .await
+                })
+                .await;
+            if let Ok(reply) = reply {
+                let parts = split_reply(&reply);
+                if parts.len() == texts.len() {
+                    return texts
+                        .iter()
+                        .zip(parts)
+                        .map(|(text, translated)| {
+                            Ok(
+                                Self::result(
+                                    text,
+                                    translated.to_string(),
+                                    source_lang,
+                                    target_lang,
+                                )
+                                .with_metadata("batch_size".to_string(), texts.len().to_string()),
+                            )
+                        })
+                        .collect();
+                }
+            }
+        }
+
+        let mut results = Vec::with_capacity(texts.len());
+        for text in texts {
+            results.push(self.translate(text, source_lang, target_lang).await);
+        }
+        results
+    }
+
+    /// Successful result for a text
+    fn result(
+        text: &str,
+        translated: String,
+        source_lang: &str,
+        target_lang: &str,
+    ) -> TranslationResult {
+        TranslationResult::success(
+            text.to_string(),
+            translated,
+            source_lang.to_string(),
+            target_lang.to_string(),
+            0.9, // Default confidence for Google Translate
+        )
+        .with_metadata("translator".to_string(), "Google Translate".to_string())
     }
 }
 
@@ -215,50 +351,22 @@ impl Translator for GoogleTranslator {
         let source_lang = self.normalize_language_code(source_language);
         let target_lang = self.normalize_language_code(target_language);
 
-        // Add random delay to avoid rate limiting
-        let delay_ms = {
-            let mut rng = rand::thread_rng();
-            rng.gen_range(self.config.delay_range.0..=self.config.delay_range.1)
-        };
-        sleep(Duration::from_millis(delay_ms)).await;
-
-        // Retry logic
-        let mut last_error = None;
-        for attempt in 0..self.config.retry_count {
-            match self.call_google_api(text, &source_lang, &target_lang).await {
-                Ok(translated_text) => {
-                    let mut result = TranslationResult::success(
-                        text.to_string(),
-                        translated_text,
-                        source_lang,
-                        target_lang,
-                        0.9, // Default confidence for Google Translate
-                    );
-
-                    result = result
-                        .with_metadata("translator".to_string(), "Google Translate".to_string())
-                        .with_metadata("attempt".to_string(), (attempt + 1).to_string())
-                        .with_metadata("delay_ms".to_string(), delay_ms.to_string());
-
-                    return Ok(result);
+        let (translated_text, attempt) = self
+            .config
+            .retry
+            .run(|attempt| {
+                let (source_lang, target_lang) = (&source_lang, &target_lang);
+                async move {
+                    self.limiter.acquire(text.chars().count()).await;
+                    let translated = self.call_google_api(text, source_lang, target_lang).await?;
+                    Ok((translated, attempt))
                 }
-                Err(e) => {
-                    last_error = Some(e);
-                    if attempt < self.config.retry_count - 1 {
-                        // Wait before retry
-                        sleep(Duration::from_millis(500 * (attempt as u64 + 1))).await;
-                    }
-                }
-            }
-        }
+            })
+            .await?;
 
-        // All retries failed
-        Err(
-            last_error.unwrap_or_else(|| TranslationError::TranslationFailed {
-                message: "Unknown error".to_string(),
-                translator_name: "Google Translate".to_string(),
-                error_code: None,
-            }),
+        Ok(
+            Self::result(text, translated_text, &source_lang, &target_lang)
+                .with_metadata("attempt".to_string(), attempt.to_string()),
         )
     }
 
@@ -271,48 +379,36 @@ impl Translator for GoogleTranslator {
         // Validate languages
         self.validate_languages(source_language, target_language)?;
 
-        // Translate texts concurrently with limited concurrency
-        // Use tokio semaphore to limit to 3 concurrent requests
-        use std::sync::Arc;
-        use tokio::sync::Semaphore;
-
-        let semaphore = Arc::new(Semaphore::new(3));
-        let mut tasks = Vec::new();
-
-        for (index, text) in texts.iter().enumerate() {
-            let text = text.clone();
-            let source = source_language.to_string();
-            let target = target_language.to_string();
-            let semaphore = semaphore.clone();
-            let translator = self;
-
-            let task = async move {
-                let _permit = semaphore.acquire().await.unwrap();
-
-                match translator.translate(&text, &source, &target).await {
-                    Ok(mut result) => {
-                        result = result.with_metadata("batch_index".to_string(), index.to_string());
-                        result
-                    }
-                    Err(_e) => {
+        let source = self.normalize_language_code(source_language);
+        let target = self.normalize_language_code(target_language);
+
+        // Batches are sent concurrently with limited concurrency
+        let batches: Vec<Vec<Result<TranslationResult, TranslationError>>> =
+            stream::iter(self.batches(texts))
+                .map(|range| self.translate_joined(&texts[range], &source, &target))
+                .buffered(BATCH_CONCURRENCY)
+                .collect()
+                .await;
+
+        Ok(batches
+            .into_iter()
+            .flatten()
+            .zip(texts)
+            .enumerate()
+            .map(|(index, (result, text))| {
+                result
+                    .unwrap_or_else(|e| {
                         // On error, return failed result with original text
                         TranslationResult::failed(
                             text.clone(),
-                            source.clone(),
-                            target.clone(),
-                            "Translation failed in batch".to_string(),
+                            source_language.to_string(),
+                            target_language.to_string(),
+                            e.to_string(),
                         )
-                        .with_metadata("batch_index".to_string(), index.to_string())
-                    }
-                }
-            };
-
-            tasks.push(task);
-        }
-
-        // Wait for all tasks to complete
-        let results = futures::future::join_all(tasks).await;
-        Ok(results)
+                    })
+                    .with_metadata("batch_index".to_string(), index.to_string())
+            })
+            .collect())
     }
 
     fn normalize_language_code(&self, language_code: &str) -> String {
@@ -345,6 +441,17 @@ impl Translator for GoogleTranslator {
         0.0 // Google Translate free tier
     }
 
+    fn estimate_requests(&self, unit_count: usize) -> usize {
+        unit_count.div_ceil(self.config.batch_size.max(1))
+    }
+
+    fn estimate_duration(&self, unit_count: usize) -> Duration {
+        // One request per batch, paced by the rate limit
+        self.limiter
+            .limit()
+            .min_duration(self.estimate_requests(unit_count), 0)
+    }
+
     fn get_usage_info(&self) -> HashMap<String, String> {
         let mut info = HashMap::new();
         info.insert("name".to_string(), self.name().to_string());
@@ -353,24 +460,46 @@ impl Translator for GoogleTranslator {
             self.supported_languages().len().to_string(),
         );
         info.insert("cost_per_character".to_string(), "0.0".to_string());
-        info.insert("max_batch_size".to_string(), "100".to_string());
         info.insert(
-            "rate_limit".to_string(),
-            "Limited (delays added)".to_string(),
+            "max_batch_size".to_string(),
+            self.config.batch_size.to_string(),
         );
+        info.insert("rate_limit".to_string(), self.limiter.limit().to_string());
         info.insert("timeout".to_string(), format!("{}s", self.config.timeout));
-        info.insert(
-            "retry_count".to_string(),
-            self.config.retry_count.to_string(),
-        );
+        info.insert("retry".to_string(), self.config.retry.to_string());
         info
     }
 }
 
+/// Split a joined reply into its translations
+///
+/// Only the line breaks padding each separator are removed, so the texts
+/// keep their own leading and trailing whitespace.
+fn split_reply(reply: &str) -> Vec<&str> {
+    let parts: Vec<&str> = reply.split(BATCH_SEPARATOR.trim()).collect();
+    let last = parts.len() - 1;
+    parts
+        .into_iter()
+        .enumerate()
+        .map(|(i, part)| {
+            let part = if i > 0 {
+                part.strip_prefix('\n').unwrap_or(part)
+            } else {
+                part
+            };
+            if i < last {
+                part.strip_suffix('\n').unwrap_or(part)
+            } else {
+                part
+            }
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::TranslationStatus;
+    use crate::{test_server, TranslationStatus};
 
     #[tokio::test]
     #[ignore] // Ignore by default as it requires network access
@@ -403,6 +532,105 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_estimate_duration() {
+        let translator = GoogleTranslator::new().unwrap();
+
+        assert_eq!(translator.estimate_requests(120), 3);
+        // 300 requests/min: a burst of 5, then one every 200ms
+        assert_eq!(
+            translator.estimate_duration(350),
+            Duration::from_millis(400)
+        );
+        assert_eq!(translator.estimate_duration(0), Duration::ZERO);
+    }
+
+    fn translator(url: String) -> GoogleTranslator {
+        GoogleTranslator::with_config(GoogleConfig {
+            service_urls: Some(vec![url]),
+            retry: RetryPolicy::none(),
+            ..GoogleConfig::default()
+        })
+        .unwrap()
+    }
+
+    fn reply(sentences: &[&str]) -> String {
+        let sentences: Vec<serde_json::Value> = sentences
+            .iter()
+            .map(|s| serde_json::json!([s, "", null, null]))
+            .collect();
+        serde_json::json!([sentences, null, "zh-CN"]).to_string()
+    }
+
+    #[test]
+    fn test_batches() {
+        let translator = GoogleTranslator::with_config(GoogleConfig {
+            batch_size: 2,
+            max_batch_chars: 20,
+            ..GoogleConfig::default()
+        })
+        .unwrap();
+        let texts: Vec<String> = ["a", "b", "c", "x ⁂ y", "d", "long text over the limit", "e"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+
+        assert_eq!(
+            translator.batches(&texts),
+            vec![0..2, 2..3, 3..4, 4..5, 5..6, 6..7]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batch_is_one_request() {
+        let (url, server) =
+            test_server::serve(vec![(200, reply(&["Hello\n⁂\n", "Good ", "morning"]))]).await;
+        let texts = vec!["你好".to_string(), "早上好".to_string()];
+
+        let results = translator(url)
+            .translate_batch(&texts, "zh", "en")
+            .await
+            .unwrap();
+
+        let translated: Vec<&str> = results.iter().map(|r| r.translated_text.as_str()).collect();
+        assert_eq!(translated, vec!["Hello", "Good morning"]);
+        assert_eq!(results[1].status, TranslationStatus::Success);
+        let requests = server.await.unwrap();
+        assert_eq!(requests.len(), 1);
+        // The separator travels in the query
+        assert!(requests[0].contains("%E2%81%82"));
+    }
+
+    #[test]
+    fn test_split_reply_keeps_whitespace() {
+        assert_eq!(
+            split_reply("  Hello\n\n⁂\nGood morning \n⁂\n"),
+            vec!["  Hello\n", "Good morning ", ""]
+        );
+        assert_eq!(split_reply("Hello"), vec!["Hello"]);
+    }
+
+    #[tokio::test]
+    async fn test_batch_falls_back_when_reply_does_not_split() {
+        let (url, server) = test_server::serve(vec![
+            (200, reply(&["Hello Good morning"])),
+            (200, reply(&["Hello"])),
+            (503, "{}".to_string()),
+        ])
+        .await;
+        let texts = vec!["你好".to_string(), "早上好".to_string()];
+
+        let results = translator(url)
+            .translate_batch(&texts, "zh", "en")
+            .await
+            .unwrap();
+
+        assert_eq!(results[0].translated_text, "Hello");
+        assert_eq!(results[1].status, TranslationStatus::Failed);
+        assert_eq!(results[1].translated_text, "早上好");
+        assert_eq!(server.await.unwrap().len(), 3);
+    }
+
     #[test]
     fn test_normalize_language_code() {
         let translator = GoogleTranslator::new().unwrap();
@@ -0,0 +1,150 @@
+//! HTTP client settings shared by translator backends
+//!
+//! Corporate networks often route traffic through a proxy that presents
+//! certificates of an internal root CA. Backends build their clients with
+//! [`HttpSettings::client`]; settings left unset fall back to those set
+//! with [`set_http_settings`] (e.g. from the config file), and then to
+//! reqwest's defaults: the `HTTPS_PROXY`/`HTTP_PROXY`/`NO_PROXY`
+//! environment variables and the built-in root certificates.
+
+use crate::TranslationError;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Proxy and trusted certificates of a backend's HTTP client
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HttpSettings {
+    /// Proxy for all requests, e.g. `http://proxy.corp.example:3128`
+    pub proxy: Option<String>,
+    /// Comma-separated hosts reached directly instead of through `proxy`,
+    /// e.g. `localhost,.corp.example`
+    pub no_proxy: Option<String>,
+    /// PEM file of root certificates trusted in addition to the built-in ones
+    pub ca_bundle: Option<PathBuf>,
+}
+
+impl HttpSettings {
+    /// Fill unset fields from `fallback`
+    pub fn or(self, fallback: &HttpSettings) -> Self {
+        Self {
+            proxy: self.proxy.or_else(|| fallback.proxy.clone()),
+            no_proxy: self.no_proxy.or_else(|| fallback.no_proxy.clone()),
+            ca_bundle: self.ca_bundle.or_else(|| fallback.ca_bundle.clone()),
+        }
+    }
+
+    /// Build a client with these settings and a request timeout
+    pub fn client(&self, timeout: Duration) -> Result<reqwest::Client, TranslationError> {
+        let mut builder = reqwest::Client::builder().timeout(timeout);
+
+        if let Some(proxy) = &self.proxy {
+            let proxy = reqwest::Proxy::all(proxy)
+                .map_err(|e| TranslationError::InvalidInput(format!("Invalid proxy: {}", e)))?
+                .no_proxy(
+                    self.no_proxy
+                        .as_deref()
+                        .and_then(reqwest::NoProxy::from_string),
+                );
+            builder = builder.proxy(proxy);
+        }
+
+        if let Some(path) = &self.ca_bundle {
+            let pem = std::fs::read(path).map_err(|e| {
+                TranslationError::InvalidInput(format!(
+                    "Failed to read CA bundle {}: {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+            let certificates = reqwest::Certificate::from_pem_bundle(&pem).map_err(|e| {
+                TranslationError::InvalidInput(format!(
+                    "Invalid CA bundle {}: {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        builder.build().map_err(TranslationError::NetworkError)
+    }
+}
+
+static CONFIGURED: OnceLock<HttpSettings> = OnceLock::new();
+
+/// Use `settings` where backends created afterwards set none
+pub fn set_http_settings(settings: HttpSettings) {
+    let _ = CONFIGURED.set(settings);
+}
+
+/// A backend's own settings, completed with the configured ones
+pub fn settings(own: HttpSettings) -> HttpSettings {
+    match CONFIGURED.get() {
+        Some(configured) => own.or(configured),
+        None => own,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_own_settings_win() {
+        let own = HttpSettings {
+            proxy: Some("http://proxy.team:3128".to_string()),
+            ..HttpSettings::default()
+        };
+        let configured = HttpSettings {
+            proxy: Some("http://proxy.corp:3128".to_string()),
+            no_proxy: Some("localhost".to_string()),
+            ca_bundle: None,
+        };
+        assert_eq!(
+            own.or(&configured),
+            HttpSettings {
+                proxy: Some("http://proxy.team:3128".to_string()),
+                no_proxy: Some("localhost".to_string()),
+                ca_bundle: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_client_settings() {
+        let timeout = Duration::from_secs(5);
+        let proxied = HttpSettings {
+            proxy: Some("http://proxy.corp:3128".to_string()),
+            no_proxy: Some("localhost,127.0.0.1".to_string()),
+            ca_bundle: None,
+        };
+        assert!(proxied.client(timeout).is_ok());
+
+        let bad_proxy = HttpSettings {
+            proxy: Some("not a url".to_string()),
+            ..HttpSettings::default()
+        };
+        assert!(matches!(
+            bad_proxy.client(timeout),
+            Err(TranslationError::InvalidInput(_))
+        ));
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let bundle = dir.path().join("ca.pem");
+        std::fs::write(&bundle, "not a certificate").unwrap();
+        let missing = HttpSettings {
+            ca_bundle: Some(dir.path().join("missing.pem")),
+            ..HttpSettings::default()
+        };
+        assert!(missing.client(timeout).is_err());
+        let invalid = HttpSettings {
+            ca_bundle: Some(bundle),
+            ..HttpSettings::default()
+        };
+        // A file without PEM blocks holds no certificates
+        assert!(invalid.client(timeout).is_ok());
+    }
+}
@@ -1,18 +1,62 @@
 //! Translation services for Langlint
 //!
 //! This module provides a unified interface for translation services,
-//! including mock and real translators like Google Translate.
+//! including mock and real translators like Google Translate, OpenAI,
+//! LibreTranslate, MyMemory and Papago.
 
 use async_trait::async_trait;
+use langlint_core::{Priority, TranslatableUnit, UnitType};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::Duration;
 use thiserror::Error;
 
+pub mod budget;
+pub mod cache;
+pub mod credentials;
+pub mod ensemble;
+pub mod fallback;
+pub mod glossary;
 pub mod google;
+pub mod http;
+pub mod libretranslate;
+pub mod metrics;
 pub mod mock;
+pub mod mymemory;
+pub mod ollama;
+pub mod openai;
+pub mod papago;
+pub mod pivot;
+pub mod ratelimit;
+pub mod registry;
+pub mod retry;
+pub mod routing;
+pub mod text_metrics;
+pub mod trace;
 
+#[cfg(test)]
+mod test_server;
+
+pub use budget::{Budget, BudgetTranslator, CostTracker, Spending};
+pub use cache::{CachedTranslator, SharedStore};
+pub use ensemble::EnsembleTranslator;
+pub use fallback::FallbackTranslator;
+pub use glossary::{Glossary, GlossaryMode, GlossaryTerm, GlossaryTranslator};
 pub use google::GoogleTranslator;
+pub use http::HttpSettings;
+pub use libretranslate::LibreTranslateTranslator;
+pub use metrics::{MetricsTranslator, TranslationMetrics};
 pub use mock::MockTranslator;
+pub use mymemory::MyMemoryTranslator;
+pub use ollama::OllamaTranslator;
+pub use openai::OpenAITranslator;
+pub use papago::PapagoTranslator;
+pub use pivot::PivotTranslator;
+pub use ratelimit::{RateLimit, RateLimiter};
+pub use registry::{TranslatorFactory, TranslatorRegistry};
+pub use retry::RetryPolicy;
+pub use routing::RoutingTranslator;
+pub use text_metrics::{BillingUnit, Pricing, TextMetrics};
 
 /// Translation status enum
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -84,6 +128,42 @@ impl TranslationResult {
         }
     }
 
+    /// Create a failed result for a text a short batch reply left out
+    pub fn missing(
+        original_text: String,
+        source_language: String,
+        target_language: String,
+    ) -> Self {
+        Self::failed(
+            original_text,
+            source_language,
+            target_language,
+            "the translator returned no result for this text".to_string(),
+        )
+    }
+
+    /// Create a result for text left untranslated on purpose, such as text
+    /// already in the target language
+    pub fn skipped(
+        original_text: String,
+        source_language: String,
+        target_language: String,
+        reason: String,
+    ) -> Self {
+        let mut metadata = HashMap::new();
+        metadata.insert("skipped".to_string(), reason);
+
+        Self {
+            original_text: original_text.clone(),
+            translated_text: original_text,
+            source_language,
+            target_language,
+            status: TranslationStatus::Skipped,
+            confidence: 1.0,
+            metadata: Some(metadata),
+        }
+    }
+
     /// Add metadata to the result
     pub fn with_metadata(mut self, key: String, value: String) -> Self {
         self.metadata
@@ -93,6 +173,62 @@ impl TranslationResult {
     }
 }
 
+/// A text to translate, with what is known about where it was found
+///
+/// Translators that can make use of it (an LLM prompt) read the unit type,
+/// context and path; others only see [`TranslationRequest::text`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TranslationRequest {
+    pub text: String,
+    /// Kind of unit the text comes from
+    pub unit_type: Option<UnitType>,
+    pub priority: Option<Priority>,
+    /// Where in the file the text was found, as the parser describes it
+    pub context: Option<String>,
+    /// File the text comes from
+    pub path: Option<String>,
+}
+
+impl TranslationRequest {
+    /// Request for a bare text
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Requests for bare texts
+    pub fn plain(texts: &[String]) -> Vec<Self> {
+        texts.iter().map(Self::new).collect()
+    }
+
+    /// Request for `text` taken from `unit`
+    pub fn for_unit(text: impl Into<String>, unit: &TranslatableUnit) -> Self {
+        Self {
+            text: text.into(),
+            unit_type: Some(unit.unit_type),
+            priority: Some(unit.priority),
+            context: unit.context.clone(),
+            path: None,
+        }
+    }
+
+    /// Set the file the text comes from
+    pub fn with_path(mut self, path: Option<&str>) -> Self {
+        self.path = path.map(str::to_string);
+        self
+    }
+
+    /// Texts of `requests`
+    pub fn texts(requests: &[Self]) -> Vec<String> {
+        requests
+            .iter()
+            .map(|request| request.text.clone())
+            .collect()
+    }
+}
+
 /// Translation error type
 #[derive(Error, Debug)]
 pub enum TranslationError {
@@ -113,18 +249,42 @@ pub enum TranslationError {
     InvalidInput(String),
 
     #[error("Rate limit exceeded")]
-    RateLimitExceeded,
+    RateLimitExceeded {
+        /// Delay the service asked for before retrying
+        retry_after: Option<Duration>,
+    },
+
+    #[error("Budget exceeded: {0}")]
+    BudgetExceeded(String),
 
     #[error("Other error: {0}")]
     Other(#[from] anyhow::Error),
 }
 
+impl TranslationError {
+    /// Delay a rate-limited service asked for before retrying
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::RateLimitExceeded { retry_after } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
 /// Translator trait that all translators must implement
 #[async_trait]
 pub trait Translator: Send + Sync {
     /// Return the name of this translator
     fn name(&self) -> &'static str;
 
+    /// Name that cached translations of this translator are stored under
+    ///
+    /// Wrappers that change the output (e.g. a glossary) extend it, so
+    /// translations made without them are not reused.
+    fn cache_name(&self) -> String {
+        self.name().to_string()
+    }
+
     /// Return a list of supported language codes
     fn supported_languages(&self) -> Vec<String>;
 
@@ -141,7 +301,14 @@ pub trait Translator: Send + Sync {
         language_code.to_lowercase()
     }
 
-    /// Validate that both source and target languages are supported
+    /// Check if text can be translated directly from `source` to `target`
+    ///
+    /// Providers offering only some pairs of their languages override this.
+    fn supports_pair(&self, source: &str, target: &str) -> bool {
+        self.is_language_supported(source) && self.is_language_supported(target)
+    }
+
+    /// Validate that both languages, and the pair of them, are supported
     fn validate_languages(&self, source: &str, target: &str) -> Result<(), TranslationError> {
         if !self.is_language_supported(source) {
             return Err(TranslationError::UnsupportedLanguage(source.to_string()));
@@ -149,6 +316,12 @@ pub trait Translator: Send + Sync {
         if !self.is_language_supported(target) {
             return Err(TranslationError::UnsupportedLanguage(target.to_string()));
         }
+        if !self.supports_pair(source, target) {
+            return Err(TranslationError::UnsupportedLanguage(format!(
+                "{} -> {}",
+                source, target
+            )));
+        }
         Ok(())
     }
 
@@ -168,11 +341,47 @@ pub trait Translator: Send + Sync {
         target_language: &str,
     ) -> Result<Vec<TranslationResult>, TranslationError>;
 
+    /// Translate a batch of texts along with their unit type, context and
+    /// file
+    ///
+    /// Translators that do not use the extra information translate the
+    /// bare texts.
+    async fn translate_requests(
+        &self,
+        requests: &[TranslationRequest],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        self.translate_batch(
+            &TranslationRequest::texts(requests),
+            source_language,
+            target_language,
+        )
+        .await
+    }
+
+    /// Price list of this translator, or `None` if it is free
+    fn pricing(&self) -> Option<Pricing> {
+        None
+    }
+
     /// Estimate the cost of translating the given text
     fn estimate_cost(&self, text: &str, _source: &str, _target: &str) -> f64 {
-        // Default implementation: free
-        let _ = text; // Suppress unused warning
-        0.0
+        // Default implementation: bill the text per the translator's pricing
+        self.pricing()
+            .map_or(0.0, |pricing| pricing.cost(&TextMetrics::measure(text)))
+    }
+
+    /// Estimate how many API requests a batch of `unit_count` units needs
+    fn estimate_requests(&self, unit_count: usize) -> usize {
+        unit_count
+    }
+
+    /// Estimate how long a batch of `unit_count` units takes, given this
+    /// translator's rate limiting
+    fn estimate_duration(&self, unit_count: usize) -> Duration {
+        let _ = unit_count;
+        Duration::ZERO
     }
 
     /// Get usage information for this translator
@@ -187,9 +396,95 @@ pub trait Translator: Send + Sync {
     }
 }
 
+/// Boxed translators, so wrappers generic over [`Translator`] accept them
+#[async_trait]
+impl<T: Translator + ?Sized> Translator for Box<T> {
+    fn name(&self) -> &'static str {
+        (**self).name()
+    }
+
+    fn cache_name(&self) -> String {
+        (**self).cache_name()
+    }
+
+    fn supported_languages(&self) -> Vec<String> {
+        (**self).supported_languages()
+    }
+
+    fn is_language_supported(&self, language_code: &str) -> bool {
+        (**self).is_language_supported(language_code)
+    }
+
+    fn supports_pair(&self, source: &str, target: &str) -> bool {
+        (**self).supports_pair(source, target)
+    }
+
+    fn normalize_language_code(&self, language_code: &str) -> String {
+        (**self).normalize_language_code(language_code)
+    }
+
+    fn validate_languages(&self, source: &str, target: &str) -> Result<(), TranslationError> {
+        (**self).validate_languages(source, target)
+    }
+
+    async fn translate(
+        &self,
+        text: &str,
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<TranslationResult, TranslationError> {
+        (**self)
+            .translate(text, source_language, target_language)
+            .await
+    }
+
+    async fn translate_batch(
+        &self,
+        texts: &[String],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        (**self)
+            .translate_batch(texts, source_language, target_language)
+            .await
+    }
+
+    async fn translate_requests(
+        &self,
+        requests: &[TranslationRequest],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        (**self)
+            .translate_requests(requests, source_language, target_language)
+            .await
+    }
+
+    fn pricing(&self) -> Option<Pricing> {
+        (**self).pricing()
+    }
+
+    fn estimate_cost(&self, text: &str, source: &str, target: &str) -> f64 {
+        (**self).estimate_cost(text, source, target)
+    }
+
+    fn estimate_requests(&self, unit_count: usize) -> usize {
+        (**self).estimate_requests(unit_count)
+    }
+
+    fn estimate_duration(&self, unit_count: usize) -> Duration {
+        (**self).estimate_duration(unit_count)
+    }
+
+    fn get_usage_info(&self) -> HashMap<String, String> {
+        (**self).get_usage_info()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::{Arc, Mutex};
 
     #[test]
     fn test_translation_result_success() {
@@ -238,4 +533,94 @@ mod tests {
             Some(&"Test".to_string())
         );
     }
+
+    /// Translator that records the requests it receives
+    struct Recorder(Arc<Mutex<Vec<TranslationRequest>>>);
+
+    #[async_trait]
+    impl Translator for Recorder {
+        fn name(&self) -> &'static str {
+            "Recorder"
+        }
+
+        fn supported_languages(&self) -> Vec<String> {
+            vec!["zh".to_string(), "en".to_string()]
+        }
+
+        async fn translate(
+            &self,
+            text: &str,
+            source: &str,
+            target: &str,
+        ) -> Result<TranslationResult, TranslationError> {
+            Ok(self
+                .translate_requests(&[TranslationRequest::new(text)], source, target)
+                .await?
+                .remove(0))
+        }
+
+        async fn translate_batch(
+            &self,
+            texts: &[String],
+            source: &str,
+            target: &str,
+        ) -> Result<Vec<TranslationResult>, TranslationError> {
+            self.translate_requests(&TranslationRequest::plain(texts), source, target)
+                .await
+        }
+
+        async fn translate_requests(
+            &self,
+            requests: &[TranslationRequest],
+            source: &str,
+            target: &str,
+        ) -> Result<Vec<TranslationResult>, TranslationError> {
+            self.0.lock().unwrap().extend_from_slice(requests);
+            Ok(requests
+                .iter()
+                .map(|request| {
+                    TranslationResult::success(
+                        request.text.clone(),
+                        request.text.clone(),
+                        source.to_string(),
+                        target.to_string(),
+                        1.0,
+                    )
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wrappers_pass_requests_through() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let translator = CachedTranslator::new(BudgetTranslator::new(
+            Box::new(GlossaryTranslator::new(
+                Box::new(Recorder(Arc::clone(&seen))),
+                Glossary::new(Vec::new()),
+            )),
+            Arc::new(CostTracker::default()),
+        ));
+
+        let request = TranslationRequest {
+            unit_type: Some(UnitType::Docstring),
+            priority: Some(Priority::High),
+            context: Some("Docstring at line 3".to_string()),
+            path: Some("src/app.py".to_string()),
+            ..TranslationRequest::new("计算总和")
+        };
+        let results = translator
+            .translate_requests(std::slice::from_ref(&request), "zh", "en")
+            .await
+            .unwrap();
+        assert_eq!(results[0].original_text, "计算总和");
+        assert_eq!(*seen.lock().unwrap(), vec![request]);
+
+        // Bare texts reach the translator without unit information
+        translator
+            .translate_batch(&["你好".to_string()], "zh", "en")
+            .await
+            .unwrap();
+        assert_eq!(seen.lock().unwrap()[1], TranslationRequest::new("你好"));
+    }
 }
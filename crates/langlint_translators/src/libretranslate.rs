@@ -0,0 +1,612 @@
+//! LibreTranslate translator for self-hosted instances
+//!
+//! LibreTranslate runs on-premises, so it works where public translation
+//! APIs are blocked. Public instances usually require an API key; a local
+//! one usually does not.
+
+use crate::credentials;
+use crate::http::{self, HttpSettings};
+use crate::ratelimit::{self, RateLimit, RateLimiter};
+use crate::retry::{self, RetryPolicy};
+use crate::trace;
+use crate::{TranslationError, TranslationResult, Translator};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+const TRANSLATOR_NAME: &str = "LibreTranslate";
+
+/// Languages assumed until the instance's `/languages` list is loaded
+const DEFAULT_LANGUAGES: [&str; 20] = [
+    "en", "zh", "zt", "ja", "ko", "fr", "de", "es", "it", "pt", "ru", "ar", "hi", "nl", "pl", "tr",
+    "uk", "vi", "id", "sv",
+];
+
+/// Configuration for the LibreTranslate translator
+#[derive(Debug, Clone)]
+pub struct LibreTranslateConfig {
+    /// Instance URL, e.g. `http://localhost:5000`
+    pub base_url: String,
+    /// API key, if the instance requires one
+    pub api_key: Option<String>,
+    /// Timeout for requests in seconds
+    pub timeout: u64,
+    /// Retries of failed requests
+    pub retry: RetryPolicy,
+    /// Maximum number of texts sent in one request by `translate_batch`
+    pub batch_size: usize,
+    /// Proxy and trusted certificates
+    pub http: HttpSettings,
+}
+
+impl Default for LibreTranslateConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "http://localhost:5000".to_string(),
+            api_key: None,
+            timeout: 60,
+            retry: RetryPolicy::default(),
+            batch_size: 25,
+            http: HttpSettings::default(),
+        }
+    }
+}
+
+impl LibreTranslateConfig {
+    /// Read the configuration from environment variables
+    ///
+    /// * `LIBRETRANSLATE_URL` - instance URL
+    /// * `LIBRETRANSLATE_API_KEY` - API key, if the instance requires one
+    ///   (see [`credentials`] for other places keys are read from)
+    pub fn from_env() -> Self {
+        Self::from_env_with(|key| std::env::var(key).ok())
+    }
+
+    /// Read the configuration using a custom variable lookup
+    pub fn from_env_with<F>(lookup: F) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let get = |key: &str| lookup(key).filter(|v| !v.trim().is_empty());
+
+        let mut config = Self::default();
+        if let Some(url) = get("LIBRETRANSLATE_URL") {
+            config.base_url = url.trim().trim_end_matches('/').to_string();
+        }
+        config.api_key = credentials::api_key("libretranslate", &lookup);
+        config
+    }
+}
+
+/// A language offered by a LibreTranslate instance
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct LibreLanguage {
+    pub code: String,
+    pub name: String,
+    /// Languages this one can be translated to
+    #[serde(default)]
+    pub targets: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct TranslateRequest<'a> {
+    q: Query<'a>,
+    source: &'a str,
+    target: &'a str,
+    format: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    api_key: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(untagged)]
+enum Query<'a> {
+    One(&'a str),
+    Many(&'a [String]),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TranslateResponse {
+    translated_text: OneOrMany<String>,
+    detected_language: Option<OneOrMany<DetectedLanguage>>,
+}
+
+/// A single value for a single query, a list for a batch
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<T> OneOrMany<T> {
+    fn into_vec(self) -> Vec<T> {
+        match self {
+            Self::One(value) => vec![value],
+            Self::Many(values) => values,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct DetectedLanguage {
+    language: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+/// Translator backed by a LibreTranslate instance
+pub struct LibreTranslateTranslator {
+    config: LibreTranslateConfig,
+    client: reqwest::Client,
+    /// Languages of the instance, loaded on first use
+    languages: RwLock<Option<Vec<LibreLanguage>>>,
+    limiter: Arc<RateLimiter>,
+}
+
+impl LibreTranslateTranslator {
+    /// Create a translator configured from the environment
+    pub fn from_env() -> Result<Self, TranslationError> {
+        Self::with_config(LibreTranslateConfig::from_env())
+    }
+
+    /// Create a translator with custom config
+    pub fn with_config(mut config: LibreTranslateConfig) -> Result<Self, TranslationError> {
+        config.retry = retry::policy(config.retry);
+        config.http = http::settings(config.http);
+        let client = config.http.client(Duration::from_secs(config.timeout))?;
+
+        Ok(Self {
+            config,
+            client,
+            languages: RwLock::new(None),
+            limiter: ratelimit::shared_limiter("libretranslate", RateLimit::unlimited()),
+        })
+    }
+
+    /// Languages offered by the instance, from its `/languages` endpoint
+    ///
+    /// The list is fetched once and cached.
+    pub async fn languages(&self) -> Result<Vec<LibreLanguage>, TranslationError> {
+        if let Some(languages) = self.cached_languages() {
+            return Ok(languages);
+        }
+
+        let response = trace::send(
+            "libretranslate",
+            self.client
+                .get(format!("{}/languages", self.config.base_url)),
+        )
+        .await
+        .map_err(TranslationError::NetworkError)?;
+        let languages: Vec<LibreLanguage> = check_status(response)
+            .await?
+            .json()
+            .await
+            .map_err(|e| failed(format!("Failed to parse languages: {}", e), None))?;
+
+        *self.languages.write().unwrap_or_else(|e| e.into_inner()) = Some(languages.clone());
+        Ok(languages)
+    }
+
+    fn cached_languages(&self) -> Option<Vec<LibreLanguage>> {
+        self.languages
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Load the instance's languages and check the pair is offered
+    async fn check_languages(&self, source: &str, target: &str) -> Result<(), TranslationError> {
+        // Without the list the translate request reports what is wrong
+        let _ = self.languages().await;
+        self.validate_languages(source, target)
+    }
+
+    /// Send one translate request, retrying transient failures
+    async fn call_api(
+        &self,
+        q: Query<'_>,
+        source: &str,
+        target: &str,
+    ) -> Result<TranslateResponse, TranslationError> {
+        let request = TranslateRequest {
+            q,
+            source,
+            target,
+            format: "text",
+            api_key: self.config.api_key.as_deref(),
+        };
+
+        let characters = match q {
+            Query::One(text) => text.chars().count(),
+            Query::Many(texts) => texts.iter().map(|t| t.chars().count()).sum(),
+        };
+
+        self.config
+            .retry
+            .run(|_| async {
+                self.limiter.acquire(characters).await;
+                let response = trace::send(
+                    "libretranslate",
+                    self.client
+                        .post(format!("{}/translate", self.config.base_url))
+                        .json(&request),
+                )
+                .await
+                .map_err(TranslationError::NetworkError)?;
+                check_status(response)
+                    .await?
+                    .json::<TranslateResponse>()
+                    .await
+                    .map_err(|e| failed(format!("Failed to parse response: {}", e), None))
+            })
+            .await
+    }
+
+    /// Build a result, reporting the detected language for `auto` sources
+    fn result(
+        text: &str,
+        translated: String,
+        source: &str,
+        target: &str,
+        detected: Option<DetectedLanguage>,
+    ) -> TranslationResult {
+        let source = detected
+            .as_ref()
+            .map_or(source.to_string(), |d| d.language.clone());
+        let mut result = TranslationResult::success(
+            text.to_string(),
+            translated,
+            source,
+            target.to_string(),
+            0.85,
+        )
+        .with_metadata("translator".to_string(), TRANSLATOR_NAME.to_string());
+        if let Some(detected) = detected {
+            result = result.with_metadata("detected_language".to_string(), detected.language);
+        }
+        result
+    }
+}
+
+/// Turn an error status into a translation error, using the JSON `error`
+/// message the server sends
+async fn check_status(response: reqwest::Response) -> Result<reqwest::Response, TranslationError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return Err(TranslationError::RateLimitExceeded {
+            retry_after: retry::retry_after(response.headers()),
+        });
+    }
+
+    let body = response.text().await.unwrap_or_default();
+    let message = serde_json::from_str::<ErrorResponse>(&body)
+        .map(|e| e.error)
+        .unwrap_or(body);
+    Err(failed(
+        format!("HTTP error: {} {}", status, message.trim()),
+        Some(status.as_u16().to_string()),
+    ))
+}
+
+fn failed(message: String, error_code: Option<String>) -> TranslationError {
+    TranslationError::TranslationFailed {
+        message,
+        translator_name: TRANSLATOR_NAME.to_string(),
+        error_code,
+    }
+}
+
+#[async_trait]
+impl Translator for LibreTranslateTranslator {
+    fn name(&self) -> &'static str {
+        TRANSLATOR_NAME
+    }
+
+    fn supported_languages(&self) -> Vec<String> {
+        match self.cached_languages() {
+            Some(languages) => languages.into_iter().map(|l| l.code).collect(),
+            None => DEFAULT_LANGUAGES.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    fn is_language_supported(&self, language_code: &str) -> bool {
+        language_code == "auto"
+            || self
+                .supported_languages()
+                .contains(&self.normalize_language_code(language_code))
+    }
+
+    /// Pairs the instance lists, assuming all of them until it is loaded
+    fn supports_pair(&self, source: &str, target: &str) -> bool {
+        if !self.is_language_supported(source) || !self.is_language_supported(target) {
+            return false;
+        }
+        let source = self.normalize_language_code(source);
+        let target = self.normalize_language_code(target);
+        let targets = self
+            .cached_languages()
+            .and_then(|languages| languages.into_iter().find(|l| l.code == source))
+            .map(|language| language.targets)
+            .unwrap_or_default();
+        targets.is_empty() || targets.contains(&target)
+    }
+
+    fn normalize_language_code(&self, language_code: &str) -> String {
+        match language_code.to_lowercase().as_str() {
+            "zh-cn" | "zh-hans" => "zh".to_string(),
+            // LibreTranslate's code for Traditional Chinese
+            "zh-tw" | "zh-hant" => "zt".to_string(),
+            other => other.to_string(),
+        }
+    }
+
+    async fn translate(
+        &self,
+        text: &str,
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<TranslationResult, TranslationError> {
+        if text.trim().is_empty() {
+            return Err(TranslationError::InvalidInput(
+                "Text cannot be empty".to_string(),
+            ));
+        }
+        self.check_languages(source_language, target_language)
+            .await?;
+
+        let source = self.normalize_language_code(source_language);
+        let target = self.normalize_language_code(target_language);
+        let response = self.call_api(Query::One(text), &source, &target).await?;
+
+        let translated = response
+            .translated_text
+            .into_vec()
+            .into_iter()
+            .next()
+            .ok_or_else(|| failed("Response is empty".to_string(), None))?;
+        let detected = response
+            .detected_language
+            .and_then(|d| d.into_vec().into_iter().next());
+        Ok(Self::result(text, translated, &source, &target, detected))
+    }
+
+    async fn translate_batch(
+        &self,
+        texts: &[String],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        self.check_languages(source_language, target_language)
+            .await?;
+
+        let source = self.normalize_language_code(source_language);
+        let target = self.normalize_language_code(target_language);
+
+        let mut results = Vec::with_capacity(texts.len());
+        for chunk in texts.chunks(self.config.batch_size.max(1)) {
+            let translated = match self.call_api(Query::Many(chunk), &source, &target).await {
+                Ok(response) => {
+                    let translated = response.translated_text.into_vec();
+                    if translated.len() == chunk.len() {
+                        let mut detected = response
+                            .detected_language
+                            .map(OneOrMany::into_vec)
+                            .unwrap_or_default()
+                            .into_iter();
+                        Ok(chunk
+                            .iter()
+                            .zip(translated)
+                            .map(|(text, translated)| {
+                                Self::result(text, translated, &source, &target, detected.next())
+                            })
+                            .collect::<Vec<_>>())
+                    } else {
+                        Err(format!(
+                            "Expected {} translations, got {}",
+                            chunk.len(),
+                            translated.len()
+                        ))
+                    }
+                }
+                Err(e) => Err(e.to_string()),
+            };
+
+            match translated {
+                Ok(translated) => results.extend(translated),
+                Err(message) => results.extend(chunk.iter().map(|text| {
+                    TranslationResult::failed(
+                        text.clone(),
+                        source_language.to_string(),
+                        target_language.to_string(),
+                        message.clone(),
+                    )
+                })),
+            }
+        }
+
+        Ok(results
+            .into_iter()
+            .enumerate()
+            .map(|(index, result)| {
+                result.with_metadata("batch_index".to_string(), index.to_string())
+            })
+            .collect())
+    }
+
+    fn estimate_requests(&self, unit_count: usize) -> usize {
+        unit_count.div_ceil(self.config.batch_size.max(1))
+    }
+
+    fn estimate_duration(&self, unit_count: usize) -> Duration {
+        // Roughly one second per request on a modest self-hosted instance
+        Duration::from_secs(self.estimate_requests(unit_count) as u64)
+    }
+
+    fn get_usage_info(&self) -> HashMap<String, String> {
+        let mut info = HashMap::new();
+        info.insert("name".to_string(), self.name().to_string());
+        info.insert("base_url".to_string(), self.config.base_url.clone());
+        info.insert(
+            "languages".to_string(),
+            self.supported_languages().len().to_string(),
+        );
+        info.insert("batch_size".to_string(), self.config.batch_size.to_string());
+        info.insert("timeout".to_string(), format!("{}s", self.config.timeout));
+        info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server;
+    use crate::TranslationStatus;
+
+    fn languages_json() -> String {
+        serde_json::json!([
+            {"code": "en", "name": "English", "targets": ["en", "zh", "ja"]},
+            {"code": "zh", "name": "Chinese", "targets": ["en", "zh"]},
+            {"code": "ja", "name": "Japanese", "targets": ["ja"]}
+        ])
+        .to_string()
+    }
+
+    fn translator(url: String) -> LibreTranslateTranslator {
+        LibreTranslateTranslator::with_config(LibreTranslateConfig {
+            base_url: url,
+            api_key: Some("secret".to_string()),
+            retry: RetryPolicy::none(),
+            batch_size: 2,
+            ..LibreTranslateConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_config_from_env() {
+        let config = LibreTranslateConfig::from_env_with(|key| match key {
+            "LIBRETRANSLATE_URL" => Some("https://translate.internal.example.com/".to_string()),
+            "LIBRETRANSLATE_API_KEY" => Some("secret".to_string()),
+            _ => None,
+        });
+        assert_eq!(config.base_url, "https://translate.internal.example.com");
+        assert_eq!(config.api_key.as_deref(), Some("secret"));
+
+        let config = LibreTranslateConfig::from_env_with(|_| None);
+        assert_eq!(config.base_url, "http://localhost:5000");
+        assert!(config.api_key.is_none());
+    }
+
+    #[test]
+    fn test_normalize_language_code() {
+        let translator = translator("http://localhost:5000".to_string());
+        assert_eq!(translator.normalize_language_code("zh-CN"), "zh");
+        assert_eq!(translator.normalize_language_code("zh-TW"), "zt");
+        assert_eq!(translator.normalize_language_code("FR"), "fr");
+        assert!(translator.is_language_supported("auto"));
+    }
+
+    #[tokio::test]
+    async fn test_languages() {
+        let (url, _) = test_server::serve(vec![(200, languages_json())]).await;
+        let translator = translator(url);
+
+        let languages = translator.languages().await.unwrap();
+        assert_eq!(languages.len(), 3);
+        assert_eq!(languages[1].name, "Chinese");
+        // Cached: the server only answers once
+        assert_eq!(translator.languages().await.unwrap(), languages);
+        assert_eq!(translator.supported_languages(), vec!["en", "zh", "ja"]);
+        assert!(!translator.is_language_supported("fr"));
+        assert!(translator.supports_pair("zh", "en"));
+        assert!(translator.supports_pair("auto", "ja"));
+        assert!(!translator.supports_pair("ja", "en"));
+    }
+
+    #[tokio::test]
+    async fn test_translate() {
+        let (url, requests) = test_server::serve(vec![
+            (200, languages_json()),
+            (
+                200,
+                serde_json::json!({
+                    "translatedText": "Compute the sum",
+                    "detectedLanguage": {"language": "zh", "confidence": 92.0}
+                })
+                .to_string(),
+            ),
+        ])
+        .await;
+        let translator = translator(url);
+
+        let result = translator
+            .translate("计算总和", "auto", "en")
+            .await
+            .unwrap();
+        assert_eq!(result.status, TranslationStatus::Success);
+        assert_eq!(result.translated_text, "Compute the sum");
+        assert_eq!(result.source_language, "zh");
+
+        let requests = requests.await.unwrap();
+        assert!(requests[0].starts_with("GET /languages"));
+        assert!(requests[1].starts_with("POST /translate"));
+        assert!(requests[1].contains("\"api_key\":\"secret\""));
+        assert!(requests[1].contains("\"source\":\"auto\""));
+    }
+
+    #[tokio::test]
+    async fn test_translate_batch() {
+        let (url, requests) = test_server::serve(vec![
+            (200, languages_json()),
+            (
+                200,
+                serde_json::json!({"translatedText": ["Hello", "World"]}).to_string(),
+            ),
+            (
+                400,
+                serde_json::json!({"error": "Invalid request"}).to_string(),
+            ),
+        ])
+        .await;
+        let translator = translator(url);
+
+        let texts: Vec<String> = ["你好", "世界", "再见"].map(String::from).to_vec();
+        let results = translator
+            .translate_batch(&texts, "zh-CN", "en")
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].translated_text, "Hello");
+        assert_eq!(results[1].translated_text, "World");
+        assert_eq!(results[2].status, TranslationStatus::Failed);
+        assert!(results[2].metadata.as_ref().unwrap()["error"].contains("Invalid request"));
+        assert_eq!(results[2].metadata.as_ref().unwrap()["batch_index"], "2");
+
+        let requests = requests.await.unwrap();
+        assert!(requests[1].contains("\"q\":[\"你好\",\"世界\"]"));
+        assert_eq!(translator.estimate_requests(3), 2);
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_pair() {
+        let (url, _) = test_server::serve(vec![(200, languages_json())]).await;
+        let translator = translator(url);
+
+        let error = translator
+            .translate("こんにちは", "ja", "en")
+            .await
+            .unwrap_err();
+        assert!(matches!(error, TranslationError::UnsupportedLanguage(_)));
+    }
+}
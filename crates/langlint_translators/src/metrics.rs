@@ -0,0 +1,428 @@
+//! Translation metrics in Prometheus/OpenMetrics text format
+//!
+//! [`MetricsTranslator`] wraps any translator and records request counts,
+//! translated units, submitted characters, latency and API errors into a shared
+//! [`TranslationMetrics`], which renders the text exposition format served
+//! on a `/metrics` endpoint or written for a textfile collector. A
+//! [`CachedTranslator`](crate::CachedTranslator) given the registry records
+//! its cache hits and misses too.
+
+use crate::text_metrics::TextMetrics;
+use crate::{
+    Pricing, TranslationError, TranslationRequest, TranslationResult, TranslationStatus, Translator,
+};
+use async_trait::async_trait;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Upper bounds (seconds) of the request latency histogram buckets
+const LATENCY_BUCKETS: [f64; 10] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+/// Counters and histograms for one translator label
+#[derive(Debug, Default, Clone)]
+struct TranslatorStats {
+    requests: u64,
+    units: u64,
+    failed_units: u64,
+    /// Submitted text size
+    input: TextMetrics,
+    /// Cumulative counts per bucket in `LATENCY_BUCKETS`
+    latency_buckets: [u64; LATENCY_BUCKETS.len()],
+    latency_sum: f64,
+    latency_count: u64,
+    /// API errors by kind
+    errors: BTreeMap<&'static str, u64>,
+    cache_hits: u64,
+    cache_misses: u64,
+}
+
+/// Thread-safe metrics registry shared by wrapped translators
+#[derive(Debug, Default)]
+pub struct TranslationMetrics {
+    stats: Mutex<BTreeMap<String, TranslatorStats>>,
+}
+
+impl TranslationMetrics {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record one API request
+    pub fn observe(
+        &self,
+        translator: &str,
+        input: &TextMetrics,
+        elapsed: Duration,
+        outcome: Result<&[TranslationResult], &TranslationError>,
+    ) {
+        let mut stats = self.stats.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = stats.entry(translator.to_string()).or_default();
+
+        entry.requests += 1;
+        entry.input.add(input);
+        let seconds = elapsed.as_secs_f64();
+        entry.latency_sum += seconds;
+        entry.latency_count += 1;
+        for (bucket, bound) in entry.latency_buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+
+        match outcome {
+            Ok(results) => {
+                entry.units += results.len() as u64;
+                entry.failed_units += results
+                    .iter()
+                    .filter(|r| r.status == TranslationStatus::Failed)
+                    .count() as u64;
+            }
+            Err(error) => *entry.errors.entry(error_kind(error)).or_default() += 1,
+        }
+    }
+
+    /// Record texts answered from a translation cache and texts it missed
+    pub fn observe_cache(&self, translator: &str, hits: usize, misses: usize) {
+        let mut stats = self.stats.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = stats.entry(translator.to_string()).or_default();
+        entry.cache_hits += hits as u64;
+        entry.cache_misses += misses as u64;
+    }
+
+    /// Total number of recorded requests across all translators
+    pub fn total_requests(&self) -> u64 {
+        let stats = self.stats.lock().unwrap_or_else(|e| e.into_inner());
+        stats.values().map(|s| s.requests).sum()
+    }
+
+    /// Render all metrics in the OpenMetrics text exposition format
+    pub fn render(&self) -> String {
+        let stats = self.stats.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = String::new();
+
+        let counter =
+            |out: &mut String, name: &str, help: &str, value: &dyn Fn(&TranslatorStats) -> u64| {
+                let _ = writeln!(out, "# HELP {} {}", name, help);
+                let _ = writeln!(out, "# TYPE {} counter", name);
+                for (translator, s) in stats.iter() {
+                    let _ = writeln!(
+                        out,
+                        "{}_total{{translator=\"{}\"}} {}",
+                        name,
+                        escape_label(translator),
+                        value(s)
+                    );
+                }
+            };
+
+        counter(
+            &mut out,
+            "langlint_translation_requests",
+            "Translation API requests.",
+            &|s| s.requests,
+        );
+        counter(
+            &mut out,
+            "langlint_translated_units",
+            "Units returned by the translator.",
+            &|s| s.units,
+        );
+        counter(
+            &mut out,
+            "langlint_failed_units",
+            "Units the translator reported as failed.",
+            &|s| s.failed_units,
+        );
+        counter(
+            &mut out,
+            "langlint_source_characters",
+            "Unicode code points submitted for translation.",
+            &|s| s.input.chars as u64,
+        );
+        counter(
+            &mut out,
+            "langlint_source_cjk_characters",
+            "CJK code points submitted for translation.",
+            &|s| s.input.cjk_chars as u64,
+        );
+        counter(
+            &mut out,
+            "langlint_source_bytes",
+            "UTF-8 bytes submitted for translation.",
+            &|s| s.input.bytes as u64,
+        );
+        counter(
+            &mut out,
+            "langlint_cache_hits",
+            "Texts answered from the translation cache.",
+            &|s| s.cache_hits,
+        );
+        counter(
+            &mut out,
+            "langlint_cache_misses",
+            "Texts the translation cache did not have.",
+            &|s| s.cache_misses,
+        );
+
+        let _ = writeln!(
+            out,
+            "# HELP langlint_translation_errors Translation requests that returned an error."
+        );
+        let _ = writeln!(out, "# TYPE langlint_translation_errors counter");
+        for (translator, s) in stats.iter() {
+            for (kind, count) in &s.errors {
+                let _ = writeln!(
+                    out,
+                    "langlint_translation_errors_total{{translator=\"{}\",kind=\"{}\"}} {}",
+                    escape_label(translator),
+                    kind,
+                    count
+                );
+            }
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP langlint_translation_latency_seconds Translation request latency."
+        );
+        let _ = writeln!(out, "# TYPE langlint_translation_latency_seconds histogram");
+        for (translator, s) in stats.iter() {
+            let label = escape_label(translator);
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(s.latency_buckets) {
+                let _ = writeln!(
+                    out,
+                    "langlint_translation_latency_seconds_bucket{{translator=\"{}\",le=\"{}\"}} {}",
+                    label, bound, count
+                );
+            }
+            let _ = writeln!(
+                out,
+                "langlint_translation_latency_seconds_bucket{{translator=\"{}\",le=\"+Inf\"}} {}",
+                label, s.latency_count
+            );
+            let _ = writeln!(
+                out,
+                "langlint_translation_latency_seconds_sum{{translator=\"{}\"}} {}",
+                label, s.latency_sum
+            );
+            let _ = writeln!(
+                out,
+                "langlint_translation_latency_seconds_count{{translator=\"{}\"}} {}",
+                label, s.latency_count
+            );
+        }
+
+        out.push_str("# EOF\n");
+        out
+    }
+}
+
+/// Short label value for an error variant
+fn error_kind(error: &TranslationError) -> &'static str {
+    match error {
+        TranslationError::UnsupportedLanguage(_) => "unsupported_language",
+        TranslationError::TranslationFailed { .. } => "translation_failed",
+        TranslationError::NetworkError(_) => "network",
+        TranslationError::InvalidInput(_) => "invalid_input",
+        TranslationError::RateLimitExceeded { .. } => "rate_limit",
+        TranslationError::BudgetExceeded(_) => "budget",
+        TranslationError::Other(_) => "other",
+    }
+}
+
+/// Escape a label value per the exposition format
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Translator wrapper that records every request into [`TranslationMetrics`]
+pub struct MetricsTranslator {
+    inner: Box<dyn Translator>,
+    metrics: Arc<TranslationMetrics>,
+}
+
+impl MetricsTranslator {
+    /// Wrap a translator, recording into a shared registry
+    pub fn new(inner: Box<dyn Translator>, metrics: Arc<TranslationMetrics>) -> Self {
+        Self { inner, metrics }
+    }
+}
+
+#[async_trait]
+impl Translator for MetricsTranslator {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn cache_name(&self) -> String {
+        self.inner.cache_name()
+    }
+
+    fn supported_languages(&self) -> Vec<String> {
+        self.inner.supported_languages()
+    }
+
+    fn is_language_supported(&self, language_code: &str) -> bool {
+        self.inner.is_language_supported(language_code)
+    }
+
+    fn supports_pair(&self, source: &str, target: &str) -> bool {
+        self.inner.supports_pair(source, target)
+    }
+
+    fn normalize_language_code(&self, language_code: &str) -> String {
+        self.inner.normalize_language_code(language_code)
+    }
+
+    fn validate_languages(&self, source: &str, target: &str) -> Result<(), TranslationError> {
+        self.inner.validate_languages(source, target)
+    }
+
+    async fn translate(
+        &self,
+        text: &str,
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<TranslationResult, TranslationError> {
+        let start = Instant::now();
+        let result = self
+            .inner
+            .translate(text, source_language, target_language)
+            .await;
+        self.metrics.observe(
+            self.name(),
+            &TextMetrics::measure(text),
+            start.elapsed(),
+            result.as_ref().map(std::slice::from_ref),
+        );
+        result
+    }
+
+    async fn translate_batch(
+        &self,
+        texts: &[String],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        self.translate_requests(
+            &TranslationRequest::plain(texts),
+            source_language,
+            target_language,
+        )
+        .await
+    }
+
+    async fn translate_requests(
+        &self,
+        requests: &[TranslationRequest],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        let start = Instant::now();
+        let results = self
+            .inner
+            .translate_requests(requests, source_language, target_language)
+            .await;
+        let mut input = TextMetrics::default();
+        for request in requests {
+            input.add(&TextMetrics::measure(&request.text));
+        }
+        self.metrics.observe(
+            self.name(),
+            &input,
+            start.elapsed(),
+            results.as_ref().map(Vec::as_slice),
+        );
+        results
+    }
+
+    fn pricing(&self) -> Option<Pricing> {
+        self.inner.pricing()
+    }
+
+    fn estimate_cost(&self, text: &str, source: &str, target: &str) -> f64 {
+        self.inner.estimate_cost(text, source, target)
+    }
+
+    fn estimate_requests(&self, unit_count: usize) -> usize {
+        self.inner.estimate_requests(unit_count)
+    }
+
+    fn estimate_duration(&self, unit_count: usize) -> Duration {
+        self.inner.estimate_duration(unit_count)
+    }
+
+    fn get_usage_info(&self) -> HashMap<String, String> {
+        self.inner.get_usage_info()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockConfig, MockTranslator};
+
+    fn wrapped(metrics: &Arc<TranslationMetrics>) -> MetricsTranslator {
+        let mock = MockTranslator::with_config(MockConfig {
+            delay_range: (0, 0),
+            ..MockConfig::default()
+        });
+        MetricsTranslator::new(Box::new(mock), Arc::clone(metrics))
+    }
+
+    #[tokio::test]
+    async fn test_records_requests_and_units() {
+        let metrics = Arc::new(TranslationMetrics::new());
+        let translator = wrapped(&metrics);
+
+        translator.translate("你好", "zh", "en").await.unwrap();
+        translator
+            .translate_batch(&["一".to_string(), "二".to_string()], "zh", "en")
+            .await
+            .unwrap();
+
+        assert_eq!(metrics.total_requests(), 2);
+        let text = metrics.render();
+        assert!(text.contains("langlint_translation_requests_total{translator=\"Mock\"} 2"));
+        assert!(text.contains("langlint_translated_units_total{translator=\"Mock\"} 3"));
+        assert!(text.contains("langlint_source_characters_total{translator=\"Mock\"} 4"));
+        assert!(text.contains("langlint_source_bytes_total{translator=\"Mock\"} 12"));
+        assert!(text.contains(
+            "langlint_translation_latency_seconds_bucket{translator=\"Mock\",le=\"+Inf\"} 2"
+        ));
+        assert!(text.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn test_records_errors_by_kind() {
+        let metrics = TranslationMetrics::new();
+        metrics.observe(
+            "Google",
+            &TextMetrics::measure("你好"),
+            Duration::from_millis(300),
+            Err(&TranslationError::RateLimitExceeded { retry_after: None }),
+        );
+
+        let text = metrics.render();
+        assert!(text.contains(
+            "langlint_translation_errors_total{translator=\"Google\",kind=\"rate_limit\"} 1"
+        ));
+        assert!(text.contains(
+            "langlint_translation_latency_seconds_bucket{translator=\"Google\",le=\"0.25\"} 0"
+        ));
+        assert!(text.contains(
+            "langlint_translation_latency_seconds_bucket{translator=\"Google\",le=\"0.5\"} 1"
+        ));
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label("a\"b\\c"), "a\\\"b\\\\c");
+    }
+}
@@ -1,12 +1,55 @@
 //! Mock translator for testing and development
 
-use crate::{TranslationError, TranslationResult, Translator};
+use crate::{TranslationError, TranslationResult, TranslationStatus, Translator};
 use async_trait::async_trait;
 use rand::Rng;
+use regex::Regex;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
 use tokio::time::sleep;
 
+/// Deterministic per-unit fault injected by the mock translator
+///
+/// Unit indices count every text the translator has been asked for, across
+/// single and batch calls, starting at 0.
+#[derive(Debug, Clone)]
+pub enum MockFault {
+    /// Fail the units at these indices
+    FailIndices(Vec<usize>),
+    /// Fail every Nth unit (indices N-1, 2N-1, ...)
+    FailEvery(usize),
+    /// Fail units whose text matches the pattern
+    FailMatching(Regex),
+    /// Return a `Partial` result for units whose text matches the pattern
+    PartialMatching(Regex),
+    /// Return a `Partial` result for every Nth unit
+    PartialEvery(usize),
+    /// Return only the first N results of a batch, like a truncated reply
+    ShortReply(usize),
+}
+
+/// Outcome of the faults for one unit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Injected {
+    Failed,
+    Partial,
+}
+
+impl MockFault {
+    fn outcome(&self, index: usize, text: &str) -> Option<Injected> {
+        let every = |n: usize| n > 0 && (index + 1).is_multiple_of(n);
+        match self {
+            Self::FailIndices(indices) if indices.contains(&index) => Some(Injected::Failed),
+            Self::FailEvery(n) if every(*n) => Some(Injected::Failed),
+            Self::FailMatching(re) if re.is_match(text) => Some(Injected::Failed),
+            Self::PartialMatching(re) if re.is_match(text) => Some(Injected::Partial),
+            Self::PartialEvery(n) if every(*n) => Some(Injected::Partial),
+            _ => None,
+        }
+    }
+}
+
 /// Configuration for Mock translator
 #[derive(Debug, Clone)]
 pub struct MockConfig {
@@ -16,6 +59,10 @@ pub struct MockConfig {
     pub error_rate: f64,
     /// Random confidence range (min, max)
     pub confidence_range: (f64, f64),
+    /// Per-unit faults; the first one that applies to a unit wins
+    pub faults: Vec<MockFault>,
+    /// Language pairs (source, target) not translated directly
+    pub unsupported_pairs: Vec<(String, String)>,
 }
 
 impl Default for MockConfig {
@@ -24,6 +71,8 @@ impl Default for MockConfig {
             delay_range: (100, 500), // 100-500ms
             error_rate: 0.0,         // No errors by default
             confidence_range: (0.8, 1.0),
+            faults: Vec::new(),
+            unsupported_pairs: Vec::new(),
         }
     }
 }
@@ -32,6 +81,8 @@ impl Default for MockConfig {
 pub struct MockTranslator {
     config: MockConfig,
     language_mapping: HashMap<String, String>,
+    /// Units seen so far, for index-based faults
+    units_seen: AtomicUsize,
 }
 
 impl MockTranslator {
@@ -70,9 +121,18 @@ impl MockTranslator {
         Self {
             config,
             language_mapping,
+            units_seen: AtomicUsize::new(0),
         }
     }
 
+    /// Fault injected for the unit at `index`, if any
+    fn injected(&self, index: usize, text: &str) -> Option<Injected> {
+        self.config
+            .faults
+            .iter()
+            .find_map(|fault| fault.outcome(index, text))
+    }
+
     /// Generate a mock translation
     fn generate_mock_translation(&self, text: &str, source: &str, target: &str) -> String {
         if source == target {
@@ -124,6 +184,16 @@ impl Translator for MockTranslator {
         self.language_mapping.keys().cloned().collect()
     }
 
+    fn supports_pair(&self, source: &str, target: &str) -> bool {
+        let pair = (
+            self.normalize_language_code(source),
+            self.normalize_language_code(target),
+        );
+        self.is_language_supported(source)
+            && self.is_language_supported(target)
+            && !self.config.unsupported_pairs.contains(&pair)
+    }
+
     async fn translate(
         &self,
         text: &str,
@@ -159,6 +229,16 @@ impl Translator for MockTranslator {
             });
         }
 
+        let index = self.units_seen.fetch_add(1, Ordering::SeqCst);
+        let injected = self.injected(index, text);
+        if injected == Some(Injected::Failed) {
+            return Err(TranslationError::TranslationFailed {
+                message: format!("Mock translation failed (injected at unit {})", index),
+                translator_name: "Mock".to_string(),
+                error_code: Some("MOCK_INJECTED".to_string()),
+            });
+        }
+
         // Generate mock translation
         let translated_text = self.generate_mock_translation(text, &source_lang, &target_lang);
 
@@ -169,6 +249,9 @@ impl Translator for MockTranslator {
             target_lang,
             confidence,
         );
+        if injected == Some(Injected::Partial) {
+            result = partial(result);
+        }
 
         // Add metadata
         result = result
@@ -213,8 +296,25 @@ impl Translator for MockTranslator {
         }
 
         // Generate mock translations
+        let first_index = self.units_seen.fetch_add(texts.len(), Ordering::SeqCst);
         let mut results = Vec::new();
         for (i, text) in texts.iter().enumerate() {
+            let index = first_index + i;
+            let injected = self.injected(index, text);
+            if injected == Some(Injected::Failed) {
+                results.push(
+                    TranslationResult::failed(
+                        text.to_string(),
+                        source_lang.clone(),
+                        target_lang.clone(),
+                        format!("Mock translation failed (injected at unit {})", index),
+                    )
+                    .with_metadata("batch_index".to_string(), i.to_string())
+                    .with_metadata("translator".to_string(), "Mock".to_string()),
+                );
+                continue;
+            }
+
             let translated_text = self.generate_mock_translation(text, &source_lang, &target_lang);
             let confidence = {
                 let mut rng = rand::thread_rng();
@@ -234,9 +334,17 @@ impl Translator for MockTranslator {
                 .with_metadata("delay_ms".to_string(), delay_ms.to_string())
                 .with_metadata("batch_index".to_string(), i.to_string())
                 .with_metadata("translator".to_string(), "Mock".to_string());
+            if injected == Some(Injected::Partial) {
+                result = partial(result);
+            }
 
             results.push(result);
         }
+        for fault in &self.config.faults {
+            if let MockFault::ShortReply(n) = fault {
+                results.truncate(*n);
+            }
+        }
 
         Ok(results)
     }
@@ -269,6 +377,16 @@ impl Translator for MockTranslator {
         0.0 // Mock is free
     }
 
+    fn estimate_requests(&self, unit_count: usize) -> usize {
+        // The whole batch is handled by a single simulated call
+        usize::from(unit_count > 0)
+    }
+
+    fn estimate_duration(&self, unit_count: usize) -> Duration {
+        let avg_delay_ms = (self.config.delay_range.0 + self.config.delay_range.1) / 2;
+        Duration::from_millis(avg_delay_ms * self.estimate_requests(unit_count) as u64)
+    }
+
     fn get_usage_info(&self) -> HashMap<String, String> {
         let mut info = HashMap::new();
         info.insert("name".to_string(), self.name().to_string());
@@ -287,14 +405,21 @@ impl Translator for MockTranslator {
             ),
         );
         info.insert("error_rate".to_string(), self.config.error_rate.to_string());
+        info.insert("faults".to_string(), self.config.faults.len().to_string());
         info
     }
 }
 
+/// Downgrade a result to an injected partial translation
+fn partial(mut result: TranslationResult) -> TranslationResult {
+    result.status = TranslationStatus::Partial;
+    result.confidence /= 2.0;
+    result.with_metadata("injected".to_string(), "partial".to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::TranslationStatus;
 
     #[test]
     fn test_mock_config_default() {
@@ -322,6 +447,8 @@ mod tests {
             delay_range: (10, 50),
             error_rate: 0.5,
             confidence_range: (0.5, 0.9),
+            faults: Vec::new(),
+            unsupported_pairs: Vec::new(),
         };
         let translator = MockTranslator::with_config(config);
         assert_eq!(translator.name(), "Mock");
@@ -438,6 +565,8 @@ mod tests {
             delay_range: (10, 20),
             error_rate: 1.0, // Always fail
             confidence_range: (0.8, 1.0),
+            faults: Vec::new(),
+            unsupported_pairs: Vec::new(),
         };
         let translator = MockTranslator::with_config(config);
 
@@ -458,6 +587,8 @@ mod tests {
             delay_range: (10, 20),
             error_rate: 1.0,
             confidence_range: (0.8, 1.0),
+            faults: Vec::new(),
+            unsupported_pairs: Vec::new(),
         };
         let translator = MockTranslator::with_config(config);
 
@@ -512,6 +643,19 @@ mod tests {
         assert_eq!(translator.estimate_cost("", "en", "zh"), 0.0);
     }
 
+    #[test]
+    fn test_estimate_requests_and_duration() {
+        let translator = MockTranslator::with_config(MockConfig {
+            delay_range: (100, 300),
+            ..MockConfig::default()
+        });
+
+        assert_eq!(translator.estimate_requests(0), 0);
+        assert_eq!(translator.estimate_requests(50), 1);
+        assert_eq!(translator.estimate_duration(50), Duration::from_millis(200));
+        assert_eq!(translator.estimate_duration(0), Duration::ZERO);
+    }
+
     #[test]
     fn test_get_usage_info() {
         let translator = MockTranslator::new();
@@ -588,6 +732,8 @@ mod tests {
             delay_range: (10, 20),
             error_rate: 0.0,
             confidence_range: (0.5, 0.6),
+            faults: Vec::new(),
+            unsupported_pairs: Vec::new(),
         };
         let translator = MockTranslator::with_config(config);
 
@@ -601,6 +747,8 @@ mod tests {
             delay_range: (0, 0),
             error_rate: 0.0,
             confidence_range: (0.8, 1.0),
+            faults: Vec::new(),
+            unsupported_pairs: Vec::new(),
         };
         let translator = MockTranslator::with_config(config);
 
@@ -616,4 +764,79 @@ mod tests {
         assert_eq!(result.original_text, "");
         assert!(result.translated_text.contains(""));
     }
+
+    fn faulty(faults: Vec<MockFault>) -> MockTranslator {
+        MockTranslator::with_config(MockConfig {
+            delay_range: (0, 0),
+            faults,
+            ..MockConfig::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_fail_every_nth_unit_across_calls() {
+        let translator = faulty(vec![MockFault::FailEvery(3)]);
+        let texts: Vec<String> = (0..5).map(|i| format!("text {}", i)).collect();
+
+        let results = translator
+            .translate_batch(&texts, "en", "zh")
+            .await
+            .unwrap();
+        let statuses: Vec<_> = results.iter().map(|r| r.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                TranslationStatus::Success,
+                TranslationStatus::Success,
+                TranslationStatus::Failed,
+                TranslationStatus::Success,
+                TranslationStatus::Success,
+            ]
+        );
+        assert_eq!(results[2].translated_text, "text 2");
+
+        // Counting carries on into the next call: unit 5 is the 6th
+        let error = translator
+            .translate("text 5", "en", "zh")
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("injected at unit 5"));
+    }
+
+    #[tokio::test]
+    async fn test_fail_indices_and_patterns() {
+        let translator = faulty(vec![
+            MockFault::FailIndices(vec![0]),
+            MockFault::FailMatching(Regex::new("^bad").unwrap()),
+            MockFault::PartialMatching(Regex::new("half").unwrap()),
+        ]);
+        let texts = vec![
+            "first".to_string(),
+            "bad input".to_string(),
+            "half done".to_string(),
+            "fine".to_string(),
+        ];
+
+        let results = translator
+            .translate_batch(&texts, "en", "zh")
+            .await
+            .unwrap();
+        assert_eq!(results[0].status, TranslationStatus::Failed);
+        assert_eq!(results[1].status, TranslationStatus::Failed);
+        assert_eq!(results[2].status, TranslationStatus::Partial);
+        assert_eq!(results[2].translated_text, "[中文] half done");
+        assert!(results[2].confidence <= 0.5);
+        assert_eq!(results[3].status, TranslationStatus::Success);
+    }
+
+    #[tokio::test]
+    async fn test_partial_every_nth_unit() {
+        let translator = faulty(vec![MockFault::PartialEvery(2)]);
+
+        let first = translator.translate("one", "en", "zh").await.unwrap();
+        let second = translator.translate("two", "en", "zh").await.unwrap();
+        assert_eq!(first.status, TranslationStatus::Success);
+        assert_eq!(second.status, TranslationStatus::Partial);
+        assert_eq!(second.metadata.unwrap()["injected"], "partial");
+    }
 }
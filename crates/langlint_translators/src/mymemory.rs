@@ -0,0 +1,536 @@
+//! MyMemory translator
+//!
+//! MyMemory's free API needs no key and does well on European languages.
+//! Anonymous use is limited to 5000 characters a day; an e-mail address
+//! (`MYMEMORY_EMAIL`) raises that to 50000. Queries are limited to 500
+//! bytes, so longer texts are sent line by line, or sentence by sentence.
+
+use crate::credentials;
+use crate::http::{self, HttpSettings};
+use crate::ratelimit::{self, RateLimit, RateLimiter};
+use crate::retry::{self, RetryPolicy};
+use crate::trace;
+use crate::{TranslationError, TranslationResult, Translator};
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+const TRANSLATOR_NAME: &str = "MyMemory";
+
+/// Longest query the API accepts, in UTF-8 bytes
+const MAX_QUERY_BYTES: usize = 500;
+
+/// Languages offered, in MyMemory's codes
+const LANGUAGES: [&str; 30] = [
+    "en", "zh-CN", "zh-TW", "ja", "ko", "fr", "de", "es", "it", "pt", "ru", "ar", "hi", "nl", "pl",
+    "tr", "uk", "vi", "id", "sv", "da", "fi", "no", "cs", "el", "he", "hu", "ro", "bg", "th",
+];
+
+/// Configuration for the MyMemory translator
+#[derive(Debug, Clone)]
+pub struct MyMemoryConfig {
+    /// API base URL
+    pub base_url: String,
+    /// Contact address, raising the free daily limit
+    pub email: Option<String>,
+    /// Key of a private translation memory, if any
+    pub api_key: Option<String>,
+    /// Timeout for requests in seconds
+    pub timeout: u64,
+    /// Retries of failed requests
+    pub retry: RetryPolicy,
+    /// Maximum number of concurrent requests issued by `translate_batch`
+    pub concurrency: usize,
+    /// Proxy and trusted certificates
+    pub http: HttpSettings,
+}
+
+impl Default for MyMemoryConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "https://api.mymemory.translated.net".to_string(),
+            email: None,
+            api_key: None,
+            timeout: 30,
+            retry: RetryPolicy::default(),
+            concurrency: 2,
+            http: HttpSettings::default(),
+        }
+    }
+}
+
+impl MyMemoryConfig {
+    /// Read the configuration from environment variables
+    ///
+    /// * `MYMEMORY_EMAIL` - contact address for the higher daily limit
+    /// * `MYMEMORY_API_KEY` - key of a private translation memory (see
+    ///   [`credentials`] for other places keys are read from)
+    /// * `MYMEMORY_URL` - API base URL
+    pub fn from_env() -> Self {
+        Self::from_env_with(|key| std::env::var(key).ok())
+    }
+
+    /// Read the configuration using a custom variable lookup
+    pub fn from_env_with<F>(lookup: F) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let get = |key: &str| lookup(key).filter(|v| !v.trim().is_empty());
+
+        let mut config = Self::default();
+        if let Some(url) = get("MYMEMORY_URL") {
+            config.base_url = url.trim().trim_end_matches('/').to_string();
+        }
+        config.email = get("MYMEMORY_EMAIL").map(|email| email.trim().to_string());
+        config.api_key = credentials::api_key("mymemory", &lookup);
+        config
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TranslateResponse {
+    response_data: Option<ResponseData>,
+    /// A number, or a string holding one
+    response_status: serde_json::Value,
+    #[serde(default)]
+    response_details: serde_json::Value,
+    #[serde(default)]
+    quota_finished: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ResponseData {
+    translated_text: String,
+    /// Similarity of the memory match, 0 to 1
+    #[serde(rename = "match")]
+    similarity: Option<f64>,
+}
+
+/// Translator backed by the MyMemory API
+pub struct MyMemoryTranslator {
+    config: MyMemoryConfig,
+    client: reqwest::Client,
+    limiter: Arc<RateLimiter>,
+}
+
+impl MyMemoryTranslator {
+    /// Create a translator configured from the environment
+    pub fn from_env() -> Result<Self, TranslationError> {
+        Self::with_config(MyMemoryConfig::from_env())
+    }
+
+    /// Create a translator with custom config
+    pub fn with_config(mut config: MyMemoryConfig) -> Result<Self, TranslationError> {
+        config.retry = retry::policy(config.retry);
+        config.http = http::settings(config.http);
+        let client = config.http.client(Duration::from_secs(config.timeout))?;
+
+        Ok(Self {
+            config,
+            client,
+            limiter: ratelimit::shared_limiter("mymemory", RateLimit::unlimited()),
+        })
+    }
+
+    /// Translate one query, returning the translation and match similarity
+    async fn call_api(
+        &self,
+        query: &str,
+        source: &str,
+        target: &str,
+    ) -> Result<(String, Option<f64>), TranslationError> {
+        let langpair = format!("{}|{}", source, target);
+        let mut params = vec![("q", query), ("langpair", &langpair)];
+        if let Some(email) = &self.config.email {
+            params.push(("de", email));
+        }
+        if let Some(key) = &self.config.api_key {
+            params.push(("key", key));
+        }
+
+        self.config
+            .retry
+            .run(|_| async {
+                self.limiter.acquire(query.chars().count()).await;
+                let response = trace::send(
+                    "mymemory",
+                    self.client
+                        .get(format!("{}/get", self.config.base_url))
+                        .query(&params),
+                )
+                .await
+                .map_err(TranslationError::NetworkError)?;
+
+                let status = response.status();
+                if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                    return Err(TranslationError::RateLimitExceeded {
+                        retry_after: retry::retry_after(response.headers()),
+                    });
+                }
+                if !status.is_success() {
+                    let body = response.text().await.unwrap_or_default();
+                    return Err(failed(
+                        format!("HTTP error: {} {}", status, body.trim()),
+                        Some(status.as_u16().to_string()),
+                    ));
+                }
+                let response: TranslateResponse = response
+                    .json()
+                    .await
+                    .map_err(|e| failed(format!("Failed to parse response: {}", e), None))?;
+                parse_response(response, &langpair)
+            })
+            .await
+    }
+
+    /// Translate a text of any length, query by query
+    async fn translate_text(
+        &self,
+        text: &str,
+        source: &str,
+        target: &str,
+    ) -> Result<(String, f64), TranslationError> {
+        let mut translated = String::with_capacity(text.len());
+        let mut confidence: f64 = 1.0;
+        for query in split_query(text) {
+            let trimmed = query.trim();
+            if trimmed.is_empty() {
+                translated.push_str(query);
+                continue;
+            }
+            let (translation, similarity) = self.call_api(trimmed, source, target).await?;
+            // Keep the whitespace around the query, line breaks included
+            let start = query.len() - query.trim_start().len();
+            translated.push_str(&query[..start]);
+            translated.push_str(translation.trim());
+            translated.push_str(&query[start + trimmed.len()..]);
+            confidence = confidence.min(similarity.map_or(0.8, |s| s.clamp(0.0, 1.0)));
+        }
+        Ok((translated, confidence))
+    }
+}
+
+/// Extract the translation, turning the status MyMemory reports in the
+/// body into errors
+fn parse_response(
+    response: TranslateResponse,
+    langpair: &str,
+) -> Result<(String, Option<f64>), TranslationError> {
+    let status = match &response.response_status {
+        serde_json::Value::Number(n) => n.as_u64().unwrap_or(0),
+        serde_json::Value::String(s) => s.trim().parse().unwrap_or(0),
+        _ => 0,
+    };
+    let details = match &response.response_details {
+        serde_json::Value::String(s) => s.trim().to_string(),
+        serde_json::Value::Null => String::new(),
+        other => other.to_string(),
+    };
+
+    if status == 429 || response.quota_finished == Some(true) {
+        // The free allowance is daily, so retrying soon is pointless
+        return Err(failed(
+            format!("Daily quota used up: {}", details),
+            Some("429".to_string()),
+        ));
+    }
+    if status != 200 {
+        if details.to_uppercase().contains("LANGUAGE") {
+            return Err(TranslationError::UnsupportedLanguage(
+                langpair.replace('|', " -> "),
+            ));
+        }
+        return Err(failed(details, Some(status.to_string())));
+    }
+
+    let data = response
+        .response_data
+        .ok_or_else(|| failed("Response has no translation".to_string(), None))?;
+    Ok((data.translated_text, data.similarity))
+}
+
+/// Split a text into queries within [`MAX_QUERY_BYTES`]
+///
+/// Lines are grouped while they fit; a longer line is split after its
+/// sentences, and a longer sentence at a character boundary.
+fn split_query(text: &str) -> Vec<&str> {
+    let mut queries = Vec::new();
+    let mut start = 0;
+    let mut end = 0;
+    for line in text.split_inclusive('\n') {
+        if end - start + line.len() > MAX_QUERY_BYTES && end > start {
+            queries.push(&text[start..end]);
+            start = end;
+        }
+        end += line.len();
+        if end - start > MAX_QUERY_BYTES {
+            queries.extend(split_line(&text[start..end]));
+            start = end;
+        }
+    }
+    if end > start {
+        queries.push(&text[start..end]);
+    }
+    queries
+}
+
+fn split_line(line: &str) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut last_break = 0;
+    for (i, c) in line.char_indices() {
+        let end = i + c.len_utf8();
+        if end - start > MAX_QUERY_BYTES {
+            let cut = if last_break > start { last_break } else { i };
+            pieces.push(&line[start..cut]);
+            start = cut;
+        }
+        if matches!(c, '.' | '!' | '?' | '。' | '！' | '？' | ';' | '；') {
+            last_break = end;
+        }
+    }
+    if start < line.len() {
+        pieces.push(&line[start..]);
+    }
+    pieces
+}
+
+fn failed(message: String, error_code: Option<String>) -> TranslationError {
+    TranslationError::TranslationFailed {
+        message,
+        translator_name: TRANSLATOR_NAME.to_string(),
+        error_code,
+    }
+}
+
+#[async_trait]
+impl Translator for MyMemoryTranslator {
+    fn name(&self) -> &'static str {
+        TRANSLATOR_NAME
+    }
+
+    fn supported_languages(&self) -> Vec<String> {
+        LANGUAGES.iter().map(|code| code.to_string()).collect()
+    }
+
+    fn is_language_supported(&self, language_code: &str) -> bool {
+        // MyMemory detects the source language itself with `Autodetect`
+        language_code == "auto"
+            || self
+                .supported_languages()
+                .contains(&self.normalize_language_code(language_code))
+    }
+
+    fn normalize_language_code(&self, language_code: &str) -> String {
+        match language_code.to_lowercase().as_str() {
+            "auto" => "Autodetect".to_string(),
+            "zh" | "zh-cn" | "zh-hans" => "zh-CN".to_string(),
+            "zh-tw" | "zh-hant" => "zh-TW".to_string(),
+            "nb" => "no".to_string(),
+            other => other.to_string(),
+        }
+    }
+
+    async fn translate(
+        &self,
+        text: &str,
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<TranslationResult, TranslationError> {
+        if text.trim().is_empty() {
+            return Err(TranslationError::InvalidInput(
+                "Text cannot be empty".to_string(),
+            ));
+        }
+        self.validate_languages(source_language, target_language)?;
+
+        let source = self.normalize_language_code(source_language);
+        let target = self.normalize_language_code(target_language);
+        let (translated, confidence) = self.translate_text(text, &source, &target).await?;
+
+        Ok(TranslationResult::success(
+            text.to_string(),
+            translated,
+            source_language.to_string(),
+            target,
+            confidence,
+        )
+        .with_metadata("translator".to_string(), TRANSLATOR_NAME.to_string()))
+    }
+
+    async fn translate_batch(
+        &self,
+        texts: &[String],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        self.validate_languages(source_language, target_language)?;
+
+        let tasks: Vec<_> = texts
+            .iter()
+            .enumerate()
+            .map(|(index, text)| async move {
+                match self.translate(text, source_language, target_language).await {
+                    Ok(result) => result,
+                    Err(e) => TranslationResult::failed(
+                        text.clone(),
+                        source_language.to_string(),
+                        target_language.to_string(),
+                        e.to_string(),
+                    ),
+                }
+                .with_metadata("batch_index".to_string(), index.to_string())
+            })
+            .collect();
+
+        Ok(stream::iter(tasks)
+            .buffered(self.config.concurrency.max(1))
+            .collect()
+            .await)
+    }
+
+    fn estimate_duration(&self, unit_count: usize) -> Duration {
+        // Roughly one second per request, spread over the batch concurrency
+        Duration::from_secs(unit_count.div_ceil(self.config.concurrency.max(1)) as u64)
+    }
+
+    fn get_usage_info(&self) -> HashMap<String, String> {
+        let mut info = HashMap::new();
+        info.insert("name".to_string(), self.name().to_string());
+        info.insert("base_url".to_string(), self.config.base_url.clone());
+        info.insert(
+            "daily_limit".to_string(),
+            if self.config.email.is_some() {
+                "50000 characters"
+            } else {
+                "5000 characters"
+            }
+            .to_string(),
+        );
+        info.insert("timeout".to_string(), format!("{}s", self.config.timeout));
+        info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server;
+    use crate::TranslationStatus;
+
+    fn translator(url: String) -> MyMemoryTranslator {
+        MyMemoryTranslator::with_config(MyMemoryConfig {
+            base_url: url,
+            email: Some("dev@example.com".to_string()),
+            retry: RetryPolicy::none(),
+            concurrency: 1,
+            ..MyMemoryConfig::default()
+        })
+        .unwrap()
+    }
+
+    fn reply(text: &str) -> (u16, String) {
+        (
+            200,
+            serde_json::json!({
+                "responseData": {"translatedText": text, "match": 0.85},
+                "responseStatus": 200,
+                "responseDetails": "",
+                "quotaFinished": false
+            })
+            .to_string(),
+        )
+    }
+
+    #[test]
+    fn test_config_from_env() {
+        let config = MyMemoryConfig::from_env_with(|key| match key {
+            "MYMEMORY_EMAIL" => Some(" dev@example.com ".to_string()),
+            _ => None,
+        });
+        assert_eq!(config.email.as_deref(), Some("dev@example.com"));
+        assert_eq!(config.base_url, "https://api.mymemory.translated.net");
+
+        let translator = MyMemoryTranslator::with_config(config).unwrap();
+        assert_eq!(translator.normalize_language_code("zh"), "zh-CN");
+        assert_eq!(translator.normalize_language_code("auto"), "Autodetect");
+        assert!(translator.is_language_supported("zh-TW"));
+        assert!(!translator.is_language_supported("xx"));
+    }
+
+    #[tokio::test]
+    async fn test_translate() {
+        let (url, requests) = test_server::serve(vec![reply("Guten Morgen")]).await;
+        let translator = translator(url);
+
+        let result = translator
+            .translate("Good morning", "en", "de")
+            .await
+            .unwrap();
+        assert_eq!(result.status, TranslationStatus::Success);
+        assert_eq!(result.translated_text, "Guten Morgen");
+        assert_eq!(result.confidence, 0.85);
+
+        let requests = requests.await.unwrap();
+        assert!(requests[0].starts_with("GET /get?q=Good+morning&langpair=en%7Cde&de=dev%40"));
+    }
+
+    #[tokio::test]
+    async fn test_errors_in_body() {
+        let (url, _) = test_server::serve(vec![
+            (
+                200,
+                serde_json::json!({
+                    "responseData": {"translatedText": "'XX' IS AN INVALID TARGET LANGUAGE"},
+                    "responseStatus": "403",
+                    "responseDetails": "'XX' IS AN INVALID TARGET LANGUAGE"
+                })
+                .to_string(),
+            ),
+            (
+                200,
+                serde_json::json!({
+                    "responseData": {"translatedText": "MYMEMORY WARNING"},
+                    "responseStatus": 429,
+                    "responseDetails": "YOU USED ALL AVAILABLE FREE TRANSLATIONS FOR TODAY",
+                    "quotaFinished": true
+                })
+                .to_string(),
+            ),
+        ])
+        .await;
+        let translator = translator(url);
+
+        let error = translator.translate("Hello", "en", "fr").await.unwrap_err();
+        assert!(matches!(error, TranslationError::UnsupportedLanguage(_)));
+        let error = translator.translate("Hello", "en", "fr").await.unwrap_err();
+        assert!(error.to_string().contains("Daily quota"));
+        assert!(!retry::is_retryable(&error));
+    }
+
+    #[tokio::test]
+    async fn test_long_text_is_split() {
+        let first = "Das ist ein Satz. ".repeat(40);
+        let text = format!("{}\n{}", first.trim_end(), "Zweite Zeile.");
+        let queries = split_query(&text);
+        assert!(queries.len() > 1);
+        assert!(queries.iter().all(|q| q.len() <= MAX_QUERY_BYTES));
+        assert_eq!(queries.concat(), text);
+        assert!(split_line(&"字".repeat(400))
+            .iter()
+            .all(|piece| piece.len() <= MAX_QUERY_BYTES));
+
+        let replies = queries.iter().map(|_| reply("Translated.")).collect();
+        let (url, _) = test_server::serve(replies).await;
+        let result = translator(url).translate(&text, "de", "en").await.unwrap();
+        assert_eq!(
+            result.translated_text.matches("Translated.").count(),
+            queries.len()
+        );
+    }
+}
@@ -0,0 +1,307 @@
+//! Local LLM translator for Ollama or llama.cpp servers
+//!
+//! Both servers expose an OpenAI-compatible chat completions endpoint, so
+//! this wraps [`OpenAITranslator`] with local defaults: no API key, no
+//! pricing, one request at a time and a long timeout for CPU inference.
+//! Nothing leaves the machine unless the base URL points elsewhere.
+
+use crate::http::HttpSettings;
+use crate::openai::{OpenAIConfig, DEFAULT_SYSTEM_PROMPT};
+use crate::ratelimit::{self, RateLimit};
+use crate::{
+    OpenAITranslator, Pricing, RetryPolicy, TranslationError, TranslationRequest,
+    TranslationResult, Translator,
+};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::time::Duration;
+
+const TRANSLATOR_NAME: &str = "Ollama";
+
+/// Configuration for the Ollama translator
+#[derive(Debug, Clone)]
+pub struct OllamaConfig {
+    /// Server URL, e.g. `http://localhost:11434` (Ollama) or
+    /// `http://localhost:8080` (llama.cpp)
+    pub base_url: String,
+    /// Model name as known to the server
+    pub model: String,
+    /// System prompt sent with every request
+    pub system_prompt: String,
+    /// Sampling temperature
+    pub temperature: f64,
+    /// Maximum tokens in a completion
+    pub max_tokens: u32,
+    /// Timeout for requests in seconds
+    pub timeout: u64,
+}
+
+impl Default for OllamaConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "http://localhost:11434".to_string(),
+            model: "llama3.1".to_string(),
+            system_prompt: DEFAULT_SYSTEM_PROMPT.to_string(),
+            temperature: 0.0,
+            max_tokens: 1024,
+            timeout: 300,
+        }
+    }
+}
+
+impl OllamaConfig {
+    /// Read the configuration from environment variables
+    ///
+    /// * `OLLAMA_HOST` - server address, with or without scheme
+    /// * `LANGLINT_OLLAMA_MODEL` - model name
+    pub fn from_env() -> Self {
+        Self::from_env_with(|key| std::env::var(key).ok())
+    }
+
+    /// Read the configuration using a custom variable lookup
+    pub fn from_env_with<F>(lookup: F) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let get = |key: &str| lookup(key).filter(|v| !v.trim().is_empty());
+
+        let mut config = Self::default();
+        if let Some(host) = get("OLLAMA_HOST") {
+            let host = host.trim().trim_end_matches('/');
+            config.base_url = if host.contains("://") {
+                host.to_string()
+            } else {
+                format!("http://{}", host)
+            };
+        }
+        if let Some(model) = get("LANGLINT_OLLAMA_MODEL") {
+            config.model = model.trim().to_string();
+        }
+        config
+    }
+}
+
+impl From<OllamaConfig> for OpenAIConfig {
+    fn from(config: OllamaConfig) -> Self {
+        let base_url = config.base_url.trim_end_matches('/');
+        Self {
+            api_key: String::new(),
+            base_url: if base_url.ends_with("/v1") {
+                base_url.to_string()
+            } else {
+                format!("{}/v1", base_url)
+            },
+            model: config.model,
+            system_prompt: config.system_prompt,
+            temperature: config.temperature,
+            max_tokens: config.max_tokens,
+            timeout: config.timeout,
+            retry: RetryPolicy::default().with_max_attempts(2),
+            // A local server works through requests one at a time anyway
+            concurrency: 1,
+            pricing: None,
+            http: HttpSettings::default(),
+        }
+    }
+}
+
+/// Translator backed by a local LLM server
+pub struct OllamaTranslator {
+    inner: OpenAITranslator,
+    base_url: String,
+}
+
+impl OllamaTranslator {
+    /// Create a translator configured from the environment
+    pub fn from_env() -> Result<Self, TranslationError> {
+        Self::with_config(OllamaConfig::from_env())
+    }
+
+    /// Create a translator with custom config
+    pub fn with_config(config: OllamaConfig) -> Result<Self, TranslationError> {
+        let base_url = config.base_url.clone();
+        Ok(Self {
+            inner: OpenAITranslator::with_config(config.into())?
+                .with_limiter(ratelimit::shared_limiter("ollama", RateLimit::unlimited())),
+            base_url,
+        })
+    }
+
+    /// Point connection errors at the local server
+    fn explain(&self, error: TranslationError) -> TranslationError {
+        match error {
+            TranslationError::NetworkError(e) if e.is_connect() => {
+                TranslationError::TranslationFailed {
+                    message: format!(
+                        "Cannot reach a local LLM server at {} (is `ollama serve` running?): {}",
+                        self.base_url, e
+                    ),
+                    translator_name: TRANSLATOR_NAME.to_string(),
+                    error_code: Some("CONNECT".to_string()),
+                }
+            }
+            other => other,
+        }
+    }
+}
+
+fn relabel(result: TranslationResult) -> TranslationResult {
+    result.with_metadata("translator".to_string(), TRANSLATOR_NAME.to_string())
+}
+
+#[async_trait]
+impl Translator for OllamaTranslator {
+    fn name(&self) -> &'static str {
+        TRANSLATOR_NAME
+    }
+
+    fn cache_name(&self) -> String {
+        self.inner.cache_name_as(TRANSLATOR_NAME)
+    }
+
+    fn supported_languages(&self) -> Vec<String> {
+        self.inner.supported_languages()
+    }
+
+    fn is_language_supported(&self, language_code: &str) -> bool {
+        self.inner.is_language_supported(language_code)
+    }
+
+    fn supports_pair(&self, source: &str, target: &str) -> bool {
+        self.inner.supports_pair(source, target)
+    }
+
+    async fn translate(
+        &self,
+        text: &str,
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<TranslationResult, TranslationError> {
+        self.inner
+            .translate(text, source_language, target_language)
+            .await
+            .map(relabel)
+            .map_err(|e| self.explain(e))
+    }
+
+    async fn translate_batch(
+        &self,
+        texts: &[String],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        self.translate_requests(
+            &TranslationRequest::plain(texts),
+            source_language,
+            target_language,
+        )
+        .await
+    }
+
+    async fn translate_requests(
+        &self,
+        requests: &[TranslationRequest],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        let results = self
+            .inner
+            .translate_requests(requests, source_language, target_language)
+            .await
+            .map_err(|e| self.explain(e))?;
+        Ok(results.into_iter().map(relabel).collect())
+    }
+
+    fn pricing(&self) -> Option<Pricing> {
+        None
+    }
+
+    fn estimate_duration(&self, unit_count: usize) -> Duration {
+        // Local inference: a few seconds per unit
+        Duration::from_secs(3 * unit_count as u64)
+    }
+
+    fn get_usage_info(&self) -> HashMap<String, String> {
+        let mut info = self.inner.get_usage_info();
+        info.insert("name".to_string(), self.name().to_string());
+        info.insert("base_url".to_string(), self.base_url.clone());
+        info.insert("cost_per_character".to_string(), "0.0".to_string());
+        info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server;
+    use crate::TranslationStatus;
+
+    #[test]
+    fn test_config_from_env() {
+        let config = OllamaConfig::from_env_with(|key| match key {
+            "OLLAMA_HOST" => Some("127.0.0.1:11500/".to_string()),
+            "LANGLINT_OLLAMA_MODEL" => Some("qwen2.5:7b".to_string()),
+            _ => None,
+        });
+        assert_eq!(config.base_url, "http://127.0.0.1:11500");
+        assert_eq!(config.model, "qwen2.5:7b");
+
+        let openai: OpenAIConfig = config.into();
+        assert_eq!(openai.base_url, "http://127.0.0.1:11500/v1");
+        assert!(openai.api_key.is_empty());
+        assert!(openai.pricing.is_none());
+    }
+
+    #[test]
+    fn test_cache_name_follows_model() {
+        let name = |model: &str| {
+            OllamaTranslator::with_config(OllamaConfig {
+                model: model.to_string(),
+                ..OllamaConfig::default()
+            })
+            .unwrap()
+            .cache_name()
+        };
+        assert!(name("qwen2.5:7b").starts_with("Ollama(qwen2.5:7b)-"));
+        assert_ne!(name("qwen2.5:7b"), name("llama3.1"));
+    }
+
+    #[tokio::test]
+    async fn test_translate_with_local_server() {
+        let (url, requests) = test_server::serve_json(serde_json::json!({
+            "choices": [{"message": {"content": "Compute the sum"}, "finish_reason": "stop"}]
+        }))
+        .await;
+        let translator = OllamaTranslator::with_config(OllamaConfig {
+            base_url: url,
+            model: "llama3.1".to_string(),
+            ..OllamaConfig::default()
+        })
+        .unwrap();
+
+        let result = translator.translate("计算总和", "zh", "en").await.unwrap();
+        assert_eq!(result.status, TranslationStatus::Success);
+        assert_eq!(result.translated_text, "Compute the sum");
+        assert_eq!(result.metadata.unwrap()["translator"], "Ollama");
+
+        let request = &requests.await.unwrap()[0];
+        assert!(request.starts_with("POST /v1/chat/completions"));
+        assert!(!request.to_lowercase().contains("authorization:"));
+        assert!(request.contains("\"model\":\"llama3.1\""));
+        assert!(request.contains("计算总和"));
+        assert_eq!(translator.estimate_cost("计算总和", "zh", "en"), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_server() {
+        let port = test_server::unused_port().await;
+        let translator = OllamaTranslator::with_config(OllamaConfig {
+            base_url: format!("http://127.0.0.1:{}", port),
+            ..OllamaConfig::default()
+        })
+        .unwrap();
+
+        let error = translator.translate("你好", "zh", "en").await.unwrap_err();
+        assert!(error.to_string().contains("ollama serve"));
+    }
+}
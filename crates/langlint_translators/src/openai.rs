@@ -0,0 +1,687 @@
+//! OpenAI (or any OpenAI-compatible) chat completions translator
+//!
+//! LLMs handle code-adjacent text - inline identifiers, parameter names,
+//! Markdown - much better than phrase-based services, at a per-token price.
+
+use crate::credentials;
+use crate::http::{self, HttpSettings};
+use crate::ratelimit::{self, RateLimit, RateLimiter};
+use crate::retry::{self, RetryPolicy};
+use crate::trace;
+use crate::{
+    BillingUnit, Pricing, TranslationError, TranslationRequest, TranslationResult, Translator,
+};
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use langlint_core::UnitType;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::time::Duration;
+
+const TRANSLATOR_NAME: &str = "OpenAI";
+
+/// Default system prompt
+pub const DEFAULT_SYSTEM_PROMPT: &str = "You translate comments and docstrings from source code. \
+Keep identifiers, parameter names, code spans, URLs and format placeholders exactly as they are. \
+Preserve line breaks and Markdown formatting. Reply with the translation only.";
+
+/// Language names used in prompts
+const LANGUAGES: [(&str, &str); 31] = [
+    ("en", "English"),
+    ("zh", "Simplified Chinese"),
+    ("zh-cn", "Simplified Chinese"),
+    ("zh-tw", "Traditional Chinese"),
+    ("ja", "Japanese"),
+    ("ko", "Korean"),
+    ("fr", "French"),
+    ("de", "German"),
+    ("es", "Spanish"),
+    ("it", "Italian"),
+    ("pt", "Portuguese"),
+    ("ru", "Russian"),
+    ("ar", "Arabic"),
+    ("hi", "Hindi"),
+    ("th", "Thai"),
+    ("vi", "Vietnamese"),
+    ("id", "Indonesian"),
+    ("nl", "Dutch"),
+    ("sv", "Swedish"),
+    ("da", "Danish"),
+    ("no", "Norwegian"),
+    ("fi", "Finnish"),
+    ("pl", "Polish"),
+    ("tr", "Turkish"),
+    ("cs", "Czech"),
+    ("hu", "Hungarian"),
+    ("ro", "Romanian"),
+    ("bg", "Bulgarian"),
+    ("el", "Greek"),
+    ("he", "Hebrew"),
+    ("uk", "Ukrainian"),
+];
+
+/// Configuration for the OpenAI translator
+#[derive(Debug, Clone)]
+pub struct OpenAIConfig {
+    /// API key sent as a bearer token
+    pub api_key: String,
+    /// API base URL, e.g. `https://api.openai.com/v1`
+    pub base_url: String,
+    /// Chat model name
+    pub model: String,
+    /// System prompt sent with every request
+    pub system_prompt: String,
+    /// Sampling temperature
+    pub temperature: f64,
+    /// Maximum tokens in a completion
+    pub max_tokens: u32,
+    /// Timeout for requests in seconds
+    pub timeout: u64,
+    /// Retries of failed requests
+    pub retry: RetryPolicy,
+    /// Maximum number of concurrent requests issued by `translate_batch`
+    pub concurrency: usize,
+    /// Price list of the model, used for cost estimates
+    pub pricing: Option<Pricing>,
+    /// Proxy and trusted certificates
+    pub http: HttpSettings,
+}
+
+impl Default for OpenAIConfig {
+    fn default() -> Self {
+        Self {
+            api_key: String::new(),
+            base_url: "https://api.openai.com/v1".to_string(),
+            model: "gpt-4o-mini".to_string(),
+            system_prompt: DEFAULT_SYSTEM_PROMPT.to_string(),
+            temperature: 0.0,
+            max_tokens: 1024,
+            timeout: 60,
+            retry: RetryPolicy::default(),
+            concurrency: 4,
+            // gpt-4o-mini input plus output price
+            pricing: Some(Pricing::new(BillingUnit::Tokens, 0.75)),
+            http: HttpSettings::default(),
+        }
+    }
+}
+
+impl OpenAIConfig {
+    /// Read the configuration from environment variables
+    ///
+    /// * `OPENAI_API_KEY` - required, unless the key is found through
+    ///   [`credentials`] otherwise
+    /// * `OPENAI_BASE_URL` - for OpenAI-compatible services
+    /// * `LANGLINT_OPENAI_MODEL` / `LANGLINT_OPENAI_SYSTEM_PROMPT`
+    /// * `LANGLINT_OPENAI_TEMPERATURE` / `LANGLINT_OPENAI_MAX_TOKENS`
+    pub fn from_env() -> Result<Self, TranslationError> {
+        Self::from_env_with(|key| std::env::var(key).ok())
+    }
+
+    /// Read the configuration using a custom variable lookup
+    pub fn from_env_with<F>(lookup: F) -> Result<Self, TranslationError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let get = |key: &str| lookup(key).filter(|v| !v.trim().is_empty());
+        let number = |key: &str| -> Result<Option<f64>, TranslationError> {
+            get(key)
+                .map(|v| {
+                    v.trim().parse::<f64>().map_err(|_| {
+                        TranslationError::InvalidInput(format!("{} must be a number", key))
+                    })
+                })
+                .transpose()
+        };
+
+        let mut config = Self {
+            api_key: credentials::require_api_key("openai", &lookup)?,
+            ..Self::default()
+        };
+        if let Some(base_url) = get("OPENAI_BASE_URL") {
+            config.base_url = base_url.trim().trim_end_matches('/').to_string();
+        }
+        if let Some(model) = get("LANGLINT_OPENAI_MODEL") {
+            config.model = model.trim().to_string();
+        }
+        if let Some(prompt) = get("LANGLINT_OPENAI_SYSTEM_PROMPT") {
+            config.system_prompt = prompt;
+        }
+        if let Some(temperature) = number("LANGLINT_OPENAI_TEMPERATURE")? {
+            config.temperature = temperature;
+        }
+        if let Some(max_tokens) = number("LANGLINT_OPENAI_MAX_TOKENS")? {
+            config.max_tokens = max_tokens as u32;
+        }
+        Ok(config)
+    }
+
+    /// Stable hash of the prompt and temperature, to key cached translations
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.system_prompt.hash(&mut hasher);
+        self.temperature.to_bits().hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<ChatMessage<'a>>,
+    temperature: f64,
+    max_tokens: u32,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatMessage<'a> {
+    role: &'static str,
+    content: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponse {
+    choices: Vec<Choice>,
+    usage: Option<Usage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Choice {
+    message: ResponseMessage,
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseMessage {
+    content: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Usage {
+    total_tokens: u64,
+}
+
+/// Translator backed by chat completions
+pub struct OpenAITranslator {
+    config: OpenAIConfig,
+    client: reqwest::Client,
+    limiter: Arc<RateLimiter>,
+}
+
+impl OpenAITranslator {
+    /// Create a translator configured from the environment
+    pub fn from_env() -> Result<Self, TranslationError> {
+        Self::with_config(OpenAIConfig::from_env()?)
+    }
+
+    /// Create a translator with custom config
+    pub fn with_config(mut config: OpenAIConfig) -> Result<Self, TranslationError> {
+        config.retry = retry::policy(config.retry);
+        config.http = http::settings(config.http);
+        let client = config.http.client(Duration::from_secs(config.timeout))?;
+
+        Ok(Self {
+            config,
+            client,
+            limiter: ratelimit::shared_limiter("openai", RateLimit::unlimited()),
+        })
+    }
+
+    /// Cache name for translations of this model under a backend's name
+    pub(crate) fn cache_name_as(&self, name: &str) -> String {
+        format!(
+            "{}({})-{:016x}",
+            name,
+            self.config.model,
+            self.config.fingerprint()
+        )
+    }
+
+    /// Pace requests with another backend's limiter
+    pub(crate) fn with_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
+    /// Translate one request, retrying as configured
+    async fn translate_request(
+        &self,
+        request: &TranslationRequest,
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<TranslationResult, TranslationError> {
+        if request.text.trim().is_empty() {
+            return Err(TranslationError::InvalidInput(
+                "Text cannot be empty".to_string(),
+            ));
+        }
+        self.validate_languages(source_language, target_language)?;
+
+        let source_lang = self.normalize_language_code(source_language);
+        let target_lang = self.normalize_language_code(target_language);
+
+        let ((translated_text, tokens), attempt) = self
+            .config
+            .retry
+            .run(|attempt| {
+                let (source_lang, target_lang) = (&source_lang, &target_lang);
+                async move {
+                    let reply = self.call_api(request, source_lang, target_lang).await?;
+                    Ok((reply, attempt))
+                }
+            })
+            .await?;
+
+        let mut result = TranslationResult::success(
+            request.text.clone(),
+            translated_text,
+            source_lang,
+            target_lang,
+            0.9,
+        )
+        .with_metadata("translator".to_string(), TRANSLATOR_NAME.to_string())
+        .with_metadata("model".to_string(), self.config.model.clone())
+        .with_metadata("attempt".to_string(), attempt.to_string());
+        if let Some(tokens) = tokens {
+            result = result.with_metadata("tokens".to_string(), tokens.to_string());
+        }
+        Ok(result)
+    }
+
+    /// Build the user message asking for a translation
+    ///
+    /// The file and context of the request come first, and the kind of
+    /// unit names what is being translated.
+    fn user_prompt(request: &TranslationRequest, source: &str, target: &str) -> String {
+        let mut prompt = String::new();
+        if let Some(path) = &request.path {
+            prompt.push_str(&format!("File: {}\n", path));
+        }
+        if let Some(context) = &request.context {
+            prompt.push_str(&format!("Context: {}\n", context));
+        }
+        if !prompt.is_empty() {
+            prompt.push('\n');
+        }
+
+        let noun = match request.unit_type {
+            Some(UnitType::Comment) => "code comment",
+            Some(UnitType::Docstring) => "docstring",
+            Some(UnitType::StringLiteral) => "string shown to users",
+            Some(UnitType::TextNode) => "document text",
+            Some(UnitType::Metadata) | None => "text",
+        };
+        let target = language_name(target);
+        if source == "auto" {
+            prompt.push_str(&format!("Translate the following {} to {}:", noun, target));
+        } else {
+            prompt.push_str(&format!(
+                "Translate the following {} from {} to {}:",
+                noun,
+                language_name(source),
+                target
+            ));
+        }
+        prompt.push_str("\n\n");
+        prompt.push_str(&request.text);
+        prompt
+    }
+
+    /// Send one chat completion request, returning the reply and tokens used
+    async fn call_api(
+        &self,
+        request: &TranslationRequest,
+        source: &str,
+        target: &str,
+    ) -> Result<(String, Option<u64>), TranslationError> {
+        let prompt = Self::user_prompt(request, source, target);
+        let request = ChatRequest {
+            model: &self.config.model,
+            messages: vec![
+                ChatMessage {
+                    role: "system",
+                    content: &self.config.system_prompt,
+                },
+                ChatMessage {
+                    role: "user",
+                    content: &prompt,
+                },
+            ],
+            temperature: self.config.temperature,
+            max_tokens: self.config.max_tokens,
+        };
+
+        self.limiter.acquire(prompt.chars().count()).await;
+        let mut request_builder = self
+            .client
+            .post(format!("{}/chat/completions", self.config.base_url))
+            .json(&request);
+        // Local servers usually need no key
+        if !self.config.api_key.is_empty() {
+            request_builder = request_builder.bearer_auth(&self.config.api_key);
+        }
+        let response = trace::send("openai", request_builder)
+            .await
+            .map_err(TranslationError::NetworkError)?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(TranslationError::RateLimitExceeded {
+                retry_after: retry::retry_after(response.headers()),
+            });
+        }
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(failed(
+                format!("HTTP error: {} {}", status, body.trim()),
+                Some(status.as_u16().to_string()),
+            ));
+        }
+
+        let response: ChatResponse = response
+            .json()
+            .await
+            .map_err(|e| failed(format!("Failed to parse response: {}", e), None))?;
+        parse_response(response)
+    }
+}
+
+/// Extract the translation from a chat completion
+fn parse_response(response: ChatResponse) -> Result<(String, Option<u64>), TranslationError> {
+    let tokens = response.usage.map(|usage| usage.total_tokens);
+    let choice = response
+        .choices
+        .into_iter()
+        .next()
+        .ok_or_else(|| failed("Response has no choices".to_string(), None))?;
+
+    if choice.finish_reason.as_deref() == Some("length") {
+        return Err(failed(
+            "Translation was cut off by max_tokens".to_string(),
+            Some("TRUNCATED".to_string()),
+        ));
+    }
+
+    let content = choice.message.content.unwrap_or_default();
+    let content = content.trim();
+    if content.is_empty() {
+        return Err(failed("Response is empty".to_string(), None));
+    }
+    Ok((content.to_string(), tokens))
+}
+
+fn failed(message: String, error_code: Option<String>) -> TranslationError {
+    TranslationError::TranslationFailed {
+        message,
+        translator_name: TRANSLATOR_NAME.to_string(),
+        error_code,
+    }
+}
+
+/// English name of a language code, or the code itself if unknown
+fn language_name(code: &str) -> String {
+    let code = code.to_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map_or(code.clone(), |(_, name)| name.to_string())
+}
+
+#[async_trait]
+impl Translator for OpenAITranslator {
+    fn name(&self) -> &'static str {
+        TRANSLATOR_NAME
+    }
+
+    fn cache_name(&self) -> String {
+        self.cache_name_as(TRANSLATOR_NAME)
+    }
+
+    fn supported_languages(&self) -> Vec<String> {
+        LANGUAGES.iter().map(|(code, _)| code.to_string()).collect()
+    }
+
+    fn is_language_supported(&self, language_code: &str) -> bool {
+        // The model can detect the source language itself
+        language_code == "auto"
+            || self
+                .supported_languages()
+                .contains(&self.normalize_language_code(language_code))
+    }
+
+    async fn translate(
+        &self,
+        text: &str,
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<TranslationResult, TranslationError> {
+        self.translate_request(
+            &TranslationRequest::new(text),
+            source_language,
+            target_language,
+        )
+        .await
+    }
+
+    async fn translate_batch(
+        &self,
+        texts: &[String],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        self.translate_requests(
+            &TranslationRequest::plain(texts),
+            source_language,
+            target_language,
+        )
+        .await
+    }
+
+    async fn translate_requests(
+        &self,
+        requests: &[TranslationRequest],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        self.validate_languages(source_language, target_language)?;
+
+        let tasks: Vec<_> = requests
+            .iter()
+            .enumerate()
+            .map(|(index, request)| async move {
+                match self
+                    .translate_request(request, source_language, target_language)
+                    .await
+                {
+                    Ok(result) => result,
+                    Err(e) => TranslationResult::failed(
+                        request.text.clone(),
+                        source_language.to_string(),
+                        target_language.to_string(),
+                        e.to_string(),
+                    ),
+                }
+                .with_metadata("batch_index".to_string(), index.to_string())
+            })
+            .collect();
+
+        Ok(stream::iter(tasks)
+            .buffered(self.config.concurrency.max(1))
+            .collect()
+            .await)
+    }
+
+    fn pricing(&self) -> Option<Pricing> {
+        self.config.pricing
+    }
+
+    fn estimate_duration(&self, unit_count: usize) -> Duration {
+        // Roughly one second per completion, spread over the batch concurrency
+        Duration::from_secs(unit_count.div_ceil(self.config.concurrency.max(1)) as u64)
+    }
+
+    fn get_usage_info(&self) -> HashMap<String, String> {
+        let mut info = HashMap::new();
+        info.insert("name".to_string(), self.name().to_string());
+        info.insert("model".to_string(), self.config.model.clone());
+        info.insert("base_url".to_string(), self.config.base_url.clone());
+        info.insert(
+            "languages".to_string(),
+            self.supported_languages().len().to_string(),
+        );
+        info.insert("timeout".to_string(), format!("{}s", self.config.timeout));
+        info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retry::is_retryable;
+    use crate::TranslationStatus;
+
+    fn response(json: serde_json::Value) -> ChatResponse {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_config_from_env() {
+        let env: HashMap<&str, &str> = [
+            ("OPENAI_API_KEY", "sk-test"),
+            ("OPENAI_BASE_URL", "http://localhost:8080/v1/"),
+            ("LANGLINT_OPENAI_MODEL", "llama3"),
+            ("LANGLINT_OPENAI_TEMPERATURE", "0.3"),
+            ("LANGLINT_OPENAI_MAX_TOKENS", "256"),
+        ]
+        .into_iter()
+        .collect();
+        let config =
+            OpenAIConfig::from_env_with(|key| env.get(key).map(|v| v.to_string())).unwrap();
+
+        assert_eq!(config.api_key, "sk-test");
+        assert_eq!(config.base_url, "http://localhost:8080/v1");
+        assert_eq!(config.model, "llama3");
+        assert_eq!(config.temperature, 0.3);
+        assert_eq!(config.max_tokens, 256);
+        assert_eq!(config.system_prompt, DEFAULT_SYSTEM_PROMPT);
+
+        assert!(OpenAIConfig::from_env_with(|_| None).is_err());
+    }
+
+    #[test]
+    fn test_cache_name_follows_model_and_prompt() {
+        let config = OpenAIConfig {
+            api_key: "sk-test".to_string(),
+            ..OpenAIConfig::default()
+        };
+        let name = |config: &OpenAIConfig| {
+            OpenAITranslator::with_config(config.clone())
+                .unwrap()
+                .cache_name()
+        };
+        let base = name(&config);
+        assert!(base.starts_with("OpenAI(gpt-4o-mini)-"), "{}", base);
+        assert_eq!(name(&config), base);
+
+        for changed in [
+            OpenAIConfig {
+                model: "gpt-4o".to_string(),
+                ..config.clone()
+            },
+            OpenAIConfig {
+                system_prompt: "Translate tersely.".to_string(),
+                ..config.clone()
+            },
+            OpenAIConfig {
+                temperature: 0.7,
+                ..config.clone()
+            },
+        ] {
+            assert_ne!(name(&changed), base);
+        }
+        assert!(OpenAIConfig::from_env_with(|key| match key {
+            "OPENAI_API_KEY" => Some("sk-test".to_string()),
+            "LANGLINT_OPENAI_TEMPERATURE" => Some("warm".to_string()),
+            _ => None,
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_user_prompt() {
+        assert_eq!(
+            OpenAITranslator::user_prompt(&TranslationRequest::new("你好"), "zh", "en"),
+            "Translate the following text from Simplified Chinese to English:\n\n你好"
+        );
+        assert_eq!(
+            OpenAITranslator::user_prompt(&TranslationRequest::new("你好"), "auto", "ja"),
+            "Translate the following text to Japanese:\n\n你好"
+        );
+
+        let request = TranslationRequest {
+            unit_type: Some(UnitType::Docstring),
+            context: Some("Docstring at line 3".to_string()),
+            path: Some("src/app.py".to_string()),
+            ..TranslationRequest::new("计算总和")
+        };
+        assert_eq!(
+            OpenAITranslator::user_prompt(&request, "zh", "en"),
+            "File: src/app.py\nContext: Docstring at line 3\n\n\
+             Translate the following docstring from Simplified Chinese to English:\n\n计算总和"
+        );
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let (text, tokens) = parse_response(response(serde_json::json!({
+            "choices": [{"message": {"content": " Hello\n"}, "finish_reason": "stop"}],
+            "usage": {"total_tokens": 42}
+        })))
+        .unwrap();
+        assert_eq!(text, "Hello");
+        assert_eq!(tokens, Some(42));
+
+        let truncated = parse_response(response(serde_json::json!({
+            "choices": [{"message": {"content": "Hel"}, "finish_reason": "length"}]
+        })))
+        .unwrap_err();
+        assert!(!is_retryable(&truncated));
+
+        assert!(parse_response(response(serde_json::json!({"choices": []}))).is_err());
+    }
+
+    #[test]
+    fn test_retryable_errors() {
+        assert!(is_retryable(&TranslationError::RateLimitExceeded {
+            retry_after: None
+        }));
+        assert!(is_retryable(&failed("x".into(), Some("503".into()))));
+        assert!(!is_retryable(&failed("x".into(), Some("401".into()))));
+    }
+
+    #[test]
+    fn test_languages_and_pricing() {
+        let translator = OpenAITranslator::with_config(OpenAIConfig::default()).unwrap();
+        assert!(translator.is_language_supported("auto"));
+        assert!(translator.is_language_supported("zh-CN"));
+        assert!(!translator.is_language_supported("tlh"));
+        assert_eq!(translator.pricing().unwrap().unit, BillingUnit::Tokens);
+        assert!(translator.estimate_cost("你好世界", "zh", "en") > 0.0);
+    }
+
+    #[tokio::test]
+    #[ignore] // Ignore by default as it requires network access and an API key
+    async fn test_openai_translate() {
+        let translator = OpenAITranslator::from_env().unwrap();
+        let result = translator
+            .translate("你好，世界", "zh", "en")
+            .await
+            .unwrap();
+
+        assert_eq!(result.status, TranslationStatus::Success);
+        assert!(!result.translated_text.is_empty());
+    }
+}
@@ -0,0 +1,473 @@
+//! Naver Papago translator
+//!
+//! Papago, through Naver Cloud Platform, translates Korean, Japanese and
+//! Chinese to and from English much better than the free Google endpoint.
+//! It needs an application's client ID and secret, and offers Korean with
+//! every language it knows but only a few other pairs; the rest can go
+//! through a pivot language (`--pivot`).
+
+use crate::credentials;
+use crate::http::{self, HttpSettings};
+use crate::ratelimit::{self, RateLimit, RateLimiter};
+use crate::retry::{self, RetryPolicy};
+use crate::trace;
+use crate::{BillingUnit, Pricing, TranslationError, TranslationResult, Translator};
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+const TRANSLATOR_NAME: &str = "Papago";
+
+/// Languages offered, in Papago's codes
+const LANGUAGES: [&str; 13] = [
+    "ko", "en", "ja", "zh-CN", "zh-TW", "vi", "id", "th", "de", "ru", "es", "it", "fr",
+];
+
+/// Pairs offered without Korean, in either direction
+const OTHER_PAIRS: [(&str, &str); 7] = [
+    ("en", "ja"),
+    ("en", "zh-CN"),
+    ("en", "zh-TW"),
+    ("en", "fr"),
+    ("ja", "zh-CN"),
+    ("ja", "zh-TW"),
+    ("zh-CN", "zh-TW"),
+];
+
+/// Configuration for the Papago translator
+#[derive(Debug, Clone)]
+pub struct PapagoConfig {
+    /// Client ID of the Naver Cloud application
+    pub client_id: String,
+    /// Client secret of the Naver Cloud application
+    pub client_secret: String,
+    /// API base URL
+    pub base_url: String,
+    /// Timeout for requests in seconds
+    pub timeout: u64,
+    /// Retries of failed requests
+    pub retry: RetryPolicy,
+    /// Maximum number of concurrent requests issued by `translate_batch`
+    pub concurrency: usize,
+    /// Price list, used for cost estimates
+    pub pricing: Option<Pricing>,
+    /// Proxy and trusted certificates
+    pub http: HttpSettings,
+}
+
+impl Default for PapagoConfig {
+    fn default() -> Self {
+        Self {
+            client_id: String::new(),
+            client_secret: String::new(),
+            base_url: "https://papago.apigw.ntruss.com".to_string(),
+            timeout: 30,
+            retry: RetryPolicy::default(),
+            concurrency: 4,
+            // 20,000 won per million characters
+            pricing: Some(Pricing::new(BillingUnit::CodePoints, 15.0)),
+            http: HttpSettings::default(),
+        }
+    }
+}
+
+impl PapagoConfig {
+    /// Read the configuration from environment variables
+    ///
+    /// * `PAPAGO_CLIENT_ID` - required
+    /// * `PAPAGO_CLIENT_SECRET` - required, unless the secret is found
+    ///   through [`credentials`] otherwise
+    /// * `PAPAGO_URL` - API base URL
+    pub fn from_env() -> Result<Self, TranslationError> {
+        Self::from_env_with(|key| std::env::var(key).ok())
+    }
+
+    /// Read the configuration using a custom variable lookup
+    pub fn from_env_with<F>(lookup: F) -> Result<Self, TranslationError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let get = |key: &str| lookup(key).filter(|v| !v.trim().is_empty());
+
+        let client_id = get("PAPAGO_CLIENT_ID").ok_or_else(|| {
+            TranslationError::InvalidInput(
+                "No client ID for papago: set PAPAGO_CLIENT_ID to the Naver Cloud application's"
+                    .to_string(),
+            )
+        })?;
+        let mut config = Self {
+            client_id: client_id.trim().to_string(),
+            client_secret: credentials::require_api_key("papago", &lookup)?,
+            ..Self::default()
+        };
+        if let Some(url) = get("PAPAGO_URL") {
+            config.base_url = url.trim().trim_end_matches('/').to_string();
+        }
+        Ok(config)
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct TranslateRequest<'a> {
+    source: &'a str,
+    target: &'a str,
+    text: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct TranslateResponse {
+    message: Message,
+}
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    result: MessageResult,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MessageResult {
+    src_lang_type: Option<String>,
+    translated_text: String,
+}
+
+/// Error body, nested under `error` by the API gateway
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ErrorResponse {
+    error: Option<Box<ErrorResponse>>,
+    error_code: Option<String>,
+    #[serde(alias = "errorMessage")]
+    message: Option<String>,
+}
+
+/// Translator backed by the Papago API
+pub struct PapagoTranslator {
+    config: PapagoConfig,
+    client: reqwest::Client,
+    limiter: Arc<RateLimiter>,
+}
+
+impl PapagoTranslator {
+    /// Create a translator configured from the environment
+    pub fn from_env() -> Result<Self, TranslationError> {
+        Self::with_config(PapagoConfig::from_env()?)
+    }
+
+    /// Create a translator with custom config
+    pub fn with_config(mut config: PapagoConfig) -> Result<Self, TranslationError> {
+        config.retry = retry::policy(config.retry);
+        config.http = http::settings(config.http);
+        let client = config.http.client(Duration::from_secs(config.timeout))?;
+
+        Ok(Self {
+            config,
+            client,
+            limiter: ratelimit::shared_limiter("papago", RateLimit::unlimited()),
+        })
+    }
+
+    /// Send one translate request, retrying transient failures
+    async fn call_api(
+        &self,
+        text: &str,
+        source: &str,
+        target: &str,
+    ) -> Result<MessageResult, TranslationError> {
+        let request = TranslateRequest {
+            source,
+            target,
+            text,
+        };
+
+        self.config
+            .retry
+            .run(|_| async {
+                self.limiter.acquire(text.chars().count()).await;
+                let response = trace::send(
+                    "papago",
+                    self.client
+                        .post(format!("{}/nmt/v1/translation", self.config.base_url))
+                        .header("X-NCP-APIGW-API-KEY-ID", &self.config.client_id)
+                        .header("X-NCP-APIGW-API-KEY", &self.config.client_secret)
+                        .form(&request),
+                )
+                .await
+                .map_err(TranslationError::NetworkError)?;
+
+                let status = response.status();
+                if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                    return Err(TranslationError::RateLimitExceeded {
+                        retry_after: retry::retry_after(response.headers()),
+                    });
+                }
+                if !status.is_success() {
+                    let body = response.text().await.unwrap_or_default();
+                    return Err(error_from_body(status, &body, source, target));
+                }
+                response
+                    .json::<TranslateResponse>()
+                    .await
+                    .map(|response| response.message.result)
+                    .map_err(|e| failed(format!("Failed to parse response: {}", e), None))
+            })
+            .await
+    }
+}
+
+/// Turn an error reply into a translation error, using Papago's error code
+fn error_from_body(
+    status: reqwest::StatusCode,
+    body: &str,
+    source: &str,
+    target: &str,
+) -> TranslationError {
+    let mut error = serde_json::from_str::<ErrorResponse>(body).ok();
+    while let Some(nested) = error.as_mut().and_then(|e| e.error.take()) {
+        error = Some(*nested);
+    }
+    let code = error.as_ref().and_then(|e| e.error_code.clone());
+    let message = error
+        .and_then(|e| e.message)
+        .unwrap_or_else(|| body.trim().to_string());
+
+    match code.as_deref() {
+        // Unsupported source, target, or pair
+        Some("N2MT04" | "N2MT05" | "N2MT06") => {
+            TranslationError::UnsupportedLanguage(format!("{} -> {}", source, target))
+        }
+        _ => failed(
+            format!("HTTP error: {} {}", status, message),
+            code.or_else(|| Some(status.as_u16().to_string())),
+        ),
+    }
+}
+
+fn failed(message: String, error_code: Option<String>) -> TranslationError {
+    TranslationError::TranslationFailed {
+        message,
+        translator_name: TRANSLATOR_NAME.to_string(),
+        error_code,
+    }
+}
+
+#[async_trait]
+impl Translator for PapagoTranslator {
+    fn name(&self) -> &'static str {
+        TRANSLATOR_NAME
+    }
+
+    fn supported_languages(&self) -> Vec<String> {
+        LANGUAGES.iter().map(|code| code.to_string()).collect()
+    }
+
+    fn is_language_supported(&self, language_code: &str) -> bool {
+        // Papago detects the source language itself
+        language_code == "auto"
+            || self
+                .supported_languages()
+                .contains(&self.normalize_language_code(language_code))
+    }
+
+    /// Korean with any language, and the few other pairs Papago lists
+    fn supports_pair(&self, source: &str, target: &str) -> bool {
+        if !self.is_language_supported(source) || !self.is_language_supported(target) {
+            return false;
+        }
+        let source = self.normalize_language_code(source);
+        let target = self.normalize_language_code(target);
+        source == "auto"
+            || source == "ko"
+            || target == "ko"
+            || OTHER_PAIRS
+                .iter()
+                .any(|&(a, b)| (a, b) == (&source, &target) || (b, a) == (&source, &target))
+    }
+
+    fn normalize_language_code(&self, language_code: &str) -> String {
+        match language_code.to_lowercase().as_str() {
+            "zh" | "zh-cn" | "zh-hans" => "zh-CN".to_string(),
+            "zh-tw" | "zh-hant" => "zh-TW".to_string(),
+            other => other.to_string(),
+        }
+    }
+
+    async fn translate(
+        &self,
+        text: &str,
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<TranslationResult, TranslationError> {
+        if text.trim().is_empty() {
+            return Err(TranslationError::InvalidInput(
+                "Text cannot be empty".to_string(),
+            ));
+        }
+        self.validate_languages(source_language, target_language)?;
+
+        let source = self.normalize_language_code(source_language);
+        let target = self.normalize_language_code(target_language);
+        let result = self.call_api(text, &source, &target).await?;
+
+        Ok(TranslationResult::success(
+            text.to_string(),
+            result.translated_text,
+            result.src_lang_type.unwrap_or(source),
+            target,
+            0.9,
+        )
+        .with_metadata("translator".to_string(), TRANSLATOR_NAME.to_string()))
+    }
+
+    async fn translate_batch(
+        &self,
+        texts: &[String],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        self.validate_languages(source_language, target_language)?;
+
+        let tasks: Vec<_> = texts
+            .iter()
+            .enumerate()
+            .map(|(index, text)| async move {
+                match self.translate(text, source_language, target_language).await {
+                    Ok(result) => result,
+                    Err(e) => TranslationResult::failed(
+                        text.clone(),
+                        source_language.to_string(),
+                        target_language.to_string(),
+                        e.to_string(),
+                    ),
+                }
+                .with_metadata("batch_index".to_string(), index.to_string())
+            })
+            .collect();
+
+        Ok(stream::iter(tasks)
+            .buffered(self.config.concurrency.max(1))
+            .collect()
+            .await)
+    }
+
+    fn pricing(&self) -> Option<Pricing> {
+        self.config.pricing
+    }
+
+    fn estimate_duration(&self, unit_count: usize) -> Duration {
+        // Roughly half a second per request, spread over the batch concurrency
+        Duration::from_millis(unit_count.div_ceil(self.config.concurrency.max(1)) as u64 * 500)
+    }
+
+    fn get_usage_info(&self) -> HashMap<String, String> {
+        let mut info = HashMap::new();
+        info.insert("name".to_string(), self.name().to_string());
+        info.insert("base_url".to_string(), self.config.base_url.clone());
+        info.insert(
+            "languages".to_string(),
+            self.supported_languages().len().to_string(),
+        );
+        info.insert("timeout".to_string(), format!("{}s", self.config.timeout));
+        info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server;
+    use crate::TranslationStatus;
+
+    fn translator(url: String) -> PapagoTranslator {
+        PapagoTranslator::with_config(PapagoConfig {
+            client_id: "client".to_string(),
+            client_secret: "secret".to_string(),
+            base_url: url,
+            retry: RetryPolicy::none(),
+            concurrency: 1,
+            ..PapagoConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_config_from_env() {
+        let config = PapagoConfig::from_env_with(|key| match key {
+            "PAPAGO_CLIENT_ID" => Some("client".to_string()),
+            "PAPAGO_CLIENT_SECRET" => Some("secret".to_string()),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(
+            (config.client_id.as_str(), config.client_secret.as_str()),
+            ("client", "secret")
+        );
+
+        let error = PapagoConfig::from_env_with(|_| None).unwrap_err();
+        assert!(error.to_string().contains("PAPAGO_CLIENT_ID"));
+    }
+
+    #[test]
+    fn test_supported_pairs() {
+        let translator = translator("http://localhost".to_string());
+        assert!(translator.supports_pair("ko", "th"));
+        assert!(translator.supports_pair("zh", "en"));
+        assert!(translator.supports_pair("ja", "zh-TW"));
+        assert!(translator.supports_pair("auto", "de"));
+        assert!(!translator.supports_pair("zh-CN", "th"));
+        assert!(!translator.supports_pair("en", "pt"));
+        assert!(matches!(
+            translator.validate_languages("de", "fr"),
+            Err(TranslationError::UnsupportedLanguage(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_translate() {
+        let (url, requests) = test_server::serve(vec![
+            (
+                200,
+                serde_json::json!({"message": {"result": {
+                    "srcLangType": "ko",
+                    "tarLangType": "en",
+                    "translatedText": "Initialize the settings"
+                }}})
+                .to_string(),
+            ),
+            (
+                400,
+                serde_json::json!({"error": {
+                    "errorCode": "N2MT05",
+                    "message": "target parameter is needed"
+                }})
+                .to_string(),
+            ),
+        ])
+        .await;
+        let translator = translator(url);
+
+        let result = translator
+            .translate("설정을 초기화합니다", "auto", "en")
+            .await
+            .unwrap();
+        assert_eq!(result.status, TranslationStatus::Success);
+        assert_eq!(result.translated_text, "Initialize the settings");
+        assert_eq!(result.source_language, "ko");
+
+        let error = translator
+            .translate("설정을 초기화합니다", "ko", "en")
+            .await
+            .unwrap_err();
+        assert!(matches!(error, TranslationError::UnsupportedLanguage(_)));
+
+        let requests = requests.await.unwrap();
+        assert!(requests[0].starts_with("POST /nmt/v1/translation"));
+        assert!(requests[0]
+            .to_lowercase()
+            .contains("x-ncp-apigw-api-key-id: client"));
+        assert!(requests[0].contains("source=auto&target=en&text="));
+    }
+}
@@ -0,0 +1,332 @@
+//! Translation through a pivot language
+//!
+//! Providers rarely offer every pair of the languages they know; DeepL,
+//! for one, has no direct Chinese to Thai model. [`PivotTranslator`] sends
+//! such pairs through a pivot language, usually English: the text is
+//! translated to the pivot, and that translation on to the target. Pairs
+//! the wrapped translator supports go straight through.
+
+use crate::{
+    Pricing, TranslationError, TranslationRequest, TranslationResult, TranslationStatus, Translator,
+};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Pivot language used when none is given
+pub const DEFAULT_PIVOT: &str = "en";
+
+/// Translator wrapper bridging unsupported pairs through a pivot language
+pub struct PivotTranslator {
+    inner: Box<dyn Translator>,
+    pivot: String,
+}
+
+impl PivotTranslator {
+    pub fn new(inner: Box<dyn Translator>, pivot: &str) -> Self {
+        Self {
+            inner,
+            pivot: pivot.trim().to_lowercase(),
+        }
+    }
+
+    pub fn pivot(&self) -> &str {
+        &self.pivot
+    }
+
+    /// Check if a pair can be bridged through the pivot
+    fn can_pivot(&self, source: &str, target: &str) -> bool {
+        !source.eq_ignore_ascii_case(&self.pivot)
+            && !target.eq_ignore_ascii_case(&self.pivot)
+            && self.inner.supports_pair(source, &self.pivot)
+            && self.inner.supports_pair(&self.pivot, target)
+    }
+
+    /// Check if a pair is translated through the pivot
+    pub fn needs_pivot(&self, source: &str, target: &str) -> bool {
+        !self.inner.supports_pair(source, target) && self.can_pivot(source, target)
+    }
+
+    /// Whether a direct attempt failed for want of the pair, which some
+    /// providers only find out on the first request
+    fn retry_via_pivot<T>(
+        &self,
+        direct: &Result<T, TranslationError>,
+        source: &str,
+        target: &str,
+    ) -> bool {
+        matches!(direct, Err(TranslationError::UnsupportedLanguage(_)))
+            && self.can_pivot(source, target)
+    }
+
+    async fn translate_via_pivot(
+        &self,
+        texts: &[String],
+        source: &str,
+        target: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        let first = self
+            .inner
+            .translate_batch(texts, source, &self.pivot)
+            .await?;
+        let middle: Vec<String> = first
+            .iter()
+            .filter(|result| result.status != TranslationStatus::Failed)
+            .map(|result| result.translated_text.clone())
+            .collect();
+        let second = if middle.is_empty() {
+            Vec::new()
+        } else {
+            self.inner
+                .translate_batch(&middle, &self.pivot, target)
+                .await?
+        };
+
+        let mut first = first.into_iter();
+        let mut second = second.into_iter();
+        // A short reply from either leg fails the remaining texts
+        Ok(texts
+            .iter()
+            .map(|text| {
+                let missing = || {
+                    TranslationResult::missing(text.clone(), source.to_string(), target.to_string())
+                };
+                match first.next() {
+                    Some(first) if first.status == TranslationStatus::Failed => TranslationResult {
+                        original_text: text.clone(),
+                        translated_text: text.clone(),
+                        target_language: target.to_string(),
+                        ..first
+                    },
+                    Some(first) => second
+                        .next()
+                        .map_or_else(missing, |second| self.combine(text, first, second)),
+                    None => missing(),
+                }
+            })
+            .collect())
+    }
+
+    /// Result of `text` from the results of its two legs
+    fn combine(
+        &self,
+        text: &str,
+        first: TranslationResult,
+        second: TranslationResult,
+    ) -> TranslationResult {
+        let status = match (first.status, second.status) {
+            (_, TranslationStatus::Failed) => TranslationStatus::Failed,
+            (TranslationStatus::Partial, _) => TranslationStatus::Partial,
+            (_, status) => status,
+        };
+        let translated_text = if status == TranslationStatus::Failed {
+            text.to_string()
+        } else {
+            second.translated_text
+        };
+        TranslationResult {
+            original_text: text.to_string(),
+            translated_text,
+            source_language: first.source_language,
+            status,
+            confidence: first.confidence * second.confidence,
+            ..second
+        }
+        .with_metadata("pivot".to_string(), self.pivot.clone())
+    }
+}
+
+#[async_trait]
+impl Translator for PivotTranslator {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn cache_name(&self) -> String {
+        self.inner.cache_name()
+    }
+
+    fn supported_languages(&self) -> Vec<String> {
+        self.inner.supported_languages()
+    }
+
+    fn is_language_supported(&self, language_code: &str) -> bool {
+        self.inner.is_language_supported(language_code)
+    }
+
+    fn supports_pair(&self, source: &str, target: &str) -> bool {
+        self.inner.supports_pair(source, target) || self.can_pivot(source, target)
+    }
+
+    fn normalize_language_code(&self, language_code: &str) -> String {
+        self.inner.normalize_language_code(language_code)
+    }
+
+    async fn translate(
+        &self,
+        text: &str,
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<TranslationResult, TranslationError> {
+        if !self.needs_pivot(source_language, target_language) {
+            let direct = self
+                .inner
+                .translate(text, source_language, target_language)
+                .await;
+            if !self.retry_via_pivot(&direct, source_language, target_language) {
+                return direct;
+            }
+        }
+        self.translate_via_pivot(&[text.to_string()], source_language, target_language)
+            .await?
+            .pop()
+            .ok_or_else(|| TranslationError::TranslationFailed {
+                message: "No result through the pivot language".to_string(),
+                translator_name: self.name().to_string(),
+                error_code: None,
+            })
+    }
+
+    async fn translate_batch(
+        &self,
+        texts: &[String],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        self.translate_requests(
+            &TranslationRequest::plain(texts),
+            source_language,
+            target_language,
+        )
+        .await
+    }
+
+    async fn translate_requests(
+        &self,
+        requests: &[TranslationRequest],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        if !self.needs_pivot(source_language, target_language) {
+            let direct = self
+                .inner
+                .translate_requests(requests, source_language, target_language)
+                .await;
+            if !self.retry_via_pivot(&direct, source_language, target_language) {
+                return direct;
+            }
+        }
+        self.translate_via_pivot(
+            &TranslationRequest::texts(requests),
+            source_language,
+            target_language,
+        )
+        .await
+    }
+
+    fn pricing(&self) -> Option<Pricing> {
+        self.inner.pricing()
+    }
+
+    fn estimate_cost(&self, text: &str, source: &str, target: &str) -> f64 {
+        if self.needs_pivot(source, target) {
+            // The pivot translation is billed too, at about the same length
+            self.inner.estimate_cost(text, source, &self.pivot)
+                + self.inner.estimate_cost(text, &self.pivot, target)
+        } else {
+            self.inner.estimate_cost(text, source, target)
+        }
+    }
+
+    fn estimate_requests(&self, unit_count: usize) -> usize {
+        self.inner.estimate_requests(unit_count)
+    }
+
+    fn estimate_duration(&self, unit_count: usize) -> Duration {
+        self.inner.estimate_duration(unit_count)
+    }
+
+    fn get_usage_info(&self) -> HashMap<String, String> {
+        let mut info = self.inner.get_usage_info();
+        info.insert("pivot".to_string(), self.pivot.clone());
+        info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockConfig, MockFault, MockTranslator};
+
+    fn translator() -> PivotTranslator {
+        let mock = MockTranslator::with_config(MockConfig {
+            delay_range: (0, 0),
+            unsupported_pairs: vec![("zh".to_string(), "th".to_string())],
+            ..MockConfig::default()
+        });
+        PivotTranslator::new(Box::new(mock), DEFAULT_PIVOT)
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_pair_goes_through_pivot() {
+        let translator = translator();
+        assert!(translator.needs_pivot("zh", "th"));
+        assert!(translator.supports_pair("zh", "th"));
+        assert!(translator.validate_languages("zh", "th").is_ok());
+
+        let result = translator.translate("你好", "zh", "th").await.unwrap();
+        assert_eq!(result.translated_text, "[ไทย] [EN] 你好");
+        assert_eq!(result.original_text, "你好");
+        assert_eq!(
+            (
+                result.source_language.as_str(),
+                result.target_language.as_str()
+            ),
+            ("zh", "th")
+        );
+        assert_eq!(result.metadata.unwrap()["pivot"], "en");
+
+        let texts = vec!["早上好".to_string(), "晚安".to_string()];
+        let results = translator
+            .translate_batch(&texts, "zh", "th")
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[1].translated_text, "[ไทย] [EN] 晚安");
+
+        // A short reply fails the texts it left out
+        let mock = MockTranslator::with_config(MockConfig {
+            delay_range: (0, 0),
+            unsupported_pairs: vec![("zh".to_string(), "th".to_string())],
+            faults: vec![MockFault::ShortReply(1)],
+            ..MockConfig::default()
+        });
+        let results = PivotTranslator::new(Box::new(mock), DEFAULT_PIVOT)
+            .translate_batch(&texts, "zh", "th")
+            .await
+            .unwrap();
+        assert_eq!(results[0].status, TranslationStatus::Success);
+        assert_eq!(results[1].status, TranslationStatus::Failed);
+        assert_eq!(results[1].translated_text, "晚安");
+    }
+
+    #[tokio::test]
+    async fn test_supported_pair_goes_direct() {
+        let translator = translator();
+        assert!(!translator.needs_pivot("zh", "ja"));
+        let result = translator.translate("你好", "zh", "ja").await.unwrap();
+        assert_eq!(result.translated_text, "[日本語] 你好");
+        assert!(!result.metadata.unwrap().contains_key("pivot"));
+
+        // The mock itself refuses the pair
+        let direct = MockTranslator::with_config(MockConfig {
+            delay_range: (0, 0),
+            unsupported_pairs: vec![("zh".to_string(), "th".to_string())],
+            ..MockConfig::default()
+        });
+        assert!(matches!(
+            direct.translate("你好", "zh", "th").await,
+            Err(TranslationError::UnsupportedLanguage(_))
+        ));
+    }
+}
@@ -0,0 +1,273 @@
+//! Token-bucket rate limiting shared by translator backends
+//!
+//! Each backend takes a [`RateLimiter`] from [`shared_limiter`] by name and
+//! calls [`RateLimiter::acquire`] before every API request. All instances of
+//! a backend in the process share one limiter, so concurrent batches stay
+//! within the provider's requests-per-minute and characters-per-minute
+//! limits deterministically. Limits set with [`set_rate_limit`] (e.g. from
+//! the config file) take precedence over a backend's defaults.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Request and character limits per minute; `None` means unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RateLimit {
+    pub requests_per_minute: Option<u32>,
+    pub characters_per_minute: Option<u32>,
+}
+
+impl RateLimit {
+    /// No limits at all
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Limit requests per minute only
+    pub fn requests_per_minute(requests: u32) -> Self {
+        Self {
+            requests_per_minute: Some(requests),
+            characters_per_minute: None,
+        }
+    }
+
+    /// Also limit characters per minute
+    pub fn with_characters_per_minute(mut self, characters: u32) -> Self {
+        self.characters_per_minute = Some(characters);
+        self
+    }
+
+    /// Check whether any limit is set
+    pub fn is_limited(&self) -> bool {
+        self.requests_per_minute.is_some() || self.characters_per_minute.is_some()
+    }
+
+    /// Least time `requests` requests totalling `characters` characters take
+    /// from a full bucket
+    pub fn min_duration(&self, requests: usize, characters: usize) -> Duration {
+        let wait = |limit: Option<u32>, amount: usize| {
+            limit.filter(|l| *l > 0).map_or(0.0, |limit| {
+                let bucket = Bucket::new(limit);
+                (amount as f64 - bucket.capacity).max(0.0) / bucket.per_second
+            })
+        };
+        Duration::from_secs_f64(
+            wait(self.requests_per_minute, requests)
+                .max(wait(self.characters_per_minute, characters)),
+        )
+    }
+}
+
+impl fmt::Display for RateLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.requests_per_minute, self.characters_per_minute) {
+            (None, None) => write!(f, "unlimited"),
+            (Some(r), None) => write!(f, "{} requests/min", r),
+            (None, Some(c)) => write!(f, "{} characters/min", c),
+            (Some(r), Some(c)) => write!(f, "{} requests/min, {} characters/min", r, c),
+        }
+    }
+}
+
+/// Token bucket holding one second's worth of tokens
+#[derive(Debug, Clone)]
+struct Bucket {
+    capacity: f64,
+    per_second: f64,
+    tokens: f64,
+    updated: Option<Instant>,
+}
+
+impl Bucket {
+    fn new(per_minute: u32) -> Self {
+        let per_second = f64::from(per_minute.max(1)) / 60.0;
+        let capacity = per_second.max(1.0);
+        Self {
+            capacity,
+            per_second,
+            tokens: capacity,
+            updated: None,
+        }
+    }
+
+    /// Take `amount` tokens at `now`, returning how long to wait for them
+    ///
+    /// Tokens may go negative: later callers queue up behind earlier ones
+    /// instead of racing for refills, and an amount larger than the bucket
+    /// simply waits longer.
+    fn reserve(&mut self, amount: f64, now: Instant) -> Duration {
+        if let Some(updated) = self.updated {
+            let elapsed = now.saturating_duration_since(updated).as_secs_f64();
+            self.tokens = (self.tokens + elapsed * self.per_second).min(self.capacity);
+        }
+        self.updated = Some(now);
+        self.tokens -= amount;
+
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.per_second)
+        }
+    }
+}
+
+/// Rate limiter for one translator backend
+#[derive(Debug)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    /// Request and character buckets
+    buckets: Mutex<(Option<Bucket>, Option<Bucket>)>,
+}
+
+impl RateLimiter {
+    /// Create a limiter enforcing `limit`
+    pub fn new(limit: RateLimit) -> Self {
+        let bucket = |l: Option<u32>| l.filter(|l| *l > 0).map(Bucket::new);
+        Self {
+            limit,
+            buckets: Mutex::new((
+                bucket(limit.requests_per_minute),
+                bucket(limit.characters_per_minute),
+            )),
+        }
+    }
+
+    /// The enforced limit
+    pub fn limit(&self) -> RateLimit {
+        self.limit
+    }
+
+    /// Reserve one request of `characters` characters, returning the wait
+    fn reserve(&self, characters: usize, now: Instant) -> Duration {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let (requests, chars) = &mut *buckets;
+        let request_wait = requests
+            .as_mut()
+            .map_or(Duration::ZERO, |b| b.reserve(1.0, now));
+        let char_wait = chars
+            .as_mut()
+            .map_or(Duration::ZERO, |b| b.reserve(characters as f64, now));
+        request_wait.max(char_wait)
+    }
+
+    /// Wait until a request of `characters` characters may be sent
+    pub async fn acquire(&self, characters: usize) {
+        let wait = self.reserve(characters, Instant::now());
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Configured limits and live limiters, by lowercase backend name
+#[derive(Default)]
+struct Registry {
+    limits: HashMap<String, RateLimit>,
+    limiters: HashMap<String, Arc<RateLimiter>>,
+}
+
+fn registry() -> std::sync::MutexGuard<'static, Registry> {
+    static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+    REGISTRY
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+/// Configure the limit for a backend by name
+///
+/// Takes effect for limiters created afterwards, so call it before
+/// creating translators.
+pub fn set_rate_limit(name: &str, limit: RateLimit) {
+    let mut registry = registry();
+    let name = name.to_lowercase();
+    registry.limiters.remove(&name);
+    registry.limits.insert(name, limit);
+}
+
+/// The process-wide limiter of a backend
+///
+/// `default` applies unless a limit was configured with [`set_rate_limit`].
+pub fn shared_limiter(name: &str, default: RateLimit) -> Arc<RateLimiter> {
+    let mut registry = registry();
+    let name = name.to_lowercase();
+    let limit = registry.limits.get(&name).copied().unwrap_or(default);
+    Arc::clone(
+        registry
+            .limiters
+            .entry(name)
+            .or_insert_with(|| Arc::new(RateLimiter::new(limit))),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requests_are_spaced_evenly() {
+        // 120 per minute: a burst of 2, then one every 500ms
+        let limiter = RateLimiter::new(RateLimit::requests_per_minute(120));
+        let start = Instant::now();
+
+        let waits: Vec<Duration> = (0..5).map(|_| limiter.reserve(10, start)).collect();
+        assert_eq!(
+            waits,
+            vec![
+                Duration::ZERO,
+                Duration::ZERO,
+                Duration::from_millis(500),
+                Duration::from_millis(1000),
+                Duration::from_millis(1500),
+            ]
+        );
+
+        // After the queue drains and the bucket refills there is no wait
+        let later = start + Duration::from_secs(5);
+        assert_eq!(limiter.reserve(10, later), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_character_limit() {
+        // 6000 characters per minute = 100 per second
+        let limiter = RateLimiter::new(RateLimit::unlimited().with_characters_per_minute(6000));
+        let start = Instant::now();
+
+        assert_eq!(limiter.reserve(100, start), Duration::ZERO);
+        assert_eq!(limiter.reserve(300, start), Duration::from_secs(3));
+    }
+
+    #[test]
+    fn test_unlimited_never_waits() {
+        let limiter = RateLimiter::new(RateLimit::unlimited());
+        let now = Instant::now();
+        assert!((0..100).all(|_| limiter.reserve(10_000, now).is_zero()));
+    }
+
+    #[test]
+    fn test_min_duration() {
+        let limit = RateLimit::requests_per_minute(60);
+        assert_eq!(limit.min_duration(7, 0), Duration::from_secs(6));
+        assert_eq!(limit.min_duration(0, 0), Duration::ZERO);
+        assert_eq!(
+            RateLimit::unlimited().min_duration(100, 100),
+            Duration::ZERO
+        );
+        assert_eq!(limit.to_string(), "60 requests/min");
+    }
+
+    #[test]
+    fn test_shared_limiter_uses_configured_limit() {
+        let default = RateLimit::requests_per_minute(10);
+        assert_eq!(shared_limiter("ratelimit-test-a", default).limit(), default);
+
+        let configured = RateLimit::requests_per_minute(5);
+        set_rate_limit("RateLimit-Test-B", configured);
+        let first = shared_limiter("ratelimit-test-b", default);
+        let second = shared_limiter("ratelimit-test-b", default);
+        assert_eq!(first.limit(), configured);
+        assert!(Arc::ptr_eq(&first, &second));
+    }
+}
@@ -0,0 +1,158 @@
+//! Translator registry: create translators by name
+//!
+//! Built-in backends are registered in [`TranslatorRegistry::with_builtins`].
+//! Downstream crates can add their own to the process-wide
+//! [`TranslatorRegistry::global`] registry before the CLI or bindings look
+//! a name up.
+
+use crate::{
+    GoogleTranslator, LibreTranslateTranslator, MockTranslator, MyMemoryTranslator,
+    OllamaTranslator, OpenAITranslator, PapagoTranslator, TranslationError, Translator,
+};
+use std::collections::BTreeMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+/// Constructor for a registered translator
+pub type TranslatorFactory =
+    Arc<dyn Fn() -> Result<Box<dyn Translator>, TranslationError> + Send + Sync>;
+
+/// Named translator constructors
+pub struct TranslatorRegistry {
+    factories: RwLock<BTreeMap<String, TranslatorFactory>>,
+}
+
+impl TranslatorRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self {
+            factories: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// Create a registry with the built-in translators
+    pub fn with_builtins() -> Self {
+        let registry = Self::new();
+        registry.register("mock", || Ok(Box::new(MockTranslator::new())));
+        registry.register("google", || Ok(Box::new(GoogleTranslator::new()?)));
+        registry.register("libretranslate", || {
+            Ok(Box::new(LibreTranslateTranslator::from_env()?))
+        });
+        registry.register("openai", || Ok(Box::new(OpenAITranslator::from_env()?)));
+        registry.register("ollama", || Ok(Box::new(OllamaTranslator::from_env()?)));
+        registry.register("mymemory", || Ok(Box::new(MyMemoryTranslator::from_env()?)));
+        registry.register("papago", || Ok(Box::new(PapagoTranslator::from_env()?)));
+        registry
+    }
+
+    /// The process-wide registry, initialised with the built-in translators
+    pub fn global() -> &'static TranslatorRegistry {
+        static GLOBAL: OnceLock<TranslatorRegistry> = OnceLock::new();
+        GLOBAL.get_or_init(Self::with_builtins)
+    }
+
+    /// Register a translator under a name, replacing any previous one
+    ///
+    /// Names are case-insensitive.
+    pub fn register<F>(&self, name: &str, factory: F)
+    where
+        F: Fn() -> Result<Box<dyn Translator>, TranslationError> + Send + Sync + 'static,
+    {
+        self.factories
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(name.to_lowercase(), Arc::new(factory));
+    }
+
+    /// Check if a name is registered
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains_key(&name.to_lowercase())
+    }
+
+    /// Registered names, sorted
+    pub fn names(&self) -> Vec<String> {
+        self.factories
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    /// Create the translator registered under a name
+    pub fn create(&self, name: &str) -> Result<Box<dyn Translator>, TranslationError> {
+        // Clone the factory so it runs without holding the lock
+        let factory = self
+            .factories
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&name.to_lowercase())
+            .cloned();
+
+        match factory {
+            Some(factory) => factory(),
+            None => Err(TranslationError::InvalidInput(format!(
+                "Unknown translator: {} (available: {})",
+                name,
+                self.names().join(", ")
+            ))),
+        }
+    }
+}
+
+impl Default for TranslatorRegistry {
+    fn default() -> Self {
+        Self::with_builtins()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockConfig;
+
+    #[test]
+    fn test_builtins() {
+        let registry = TranslatorRegistry::with_builtins();
+        assert_eq!(
+            registry.names(),
+            vec![
+                "google",
+                "libretranslate",
+                "mock",
+                "mymemory",
+                "ollama",
+                "openai",
+                "papago"
+            ]
+        );
+        assert_eq!(registry.create("Mock").unwrap().name(), "Mock");
+    }
+
+    #[test]
+    fn test_unknown_translator() {
+        let registry = TranslatorRegistry::with_builtins();
+        let error = registry.create("babelfish").err().unwrap();
+        assert!(error.to_string().contains("Unknown translator: babelfish"));
+        assert!(error
+            .to_string()
+            .contains("google, libretranslate, mock, mymemory, ollama, openai, papago"));
+    }
+
+    #[test]
+    fn test_register_custom() {
+        let registry = TranslatorRegistry::new();
+        assert!(!registry.contains("fast-mock"));
+
+        registry.register("fast-mock", || {
+            Ok(Box::new(MockTranslator::with_config(MockConfig {
+                delay_range: (0, 0),
+                ..MockConfig::default()
+            })))
+        });
+        assert!(registry.contains("FAST-MOCK"));
+        assert!(registry.create("fast-mock").is_ok());
+    }
+}
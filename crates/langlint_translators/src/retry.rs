@@ -0,0 +1,253 @@
+//! Retry policy shared by translator backends
+//!
+//! Failed requests that may succeed on a second try (network errors, 5xx
+//! replies, rate limiting) are retried with exponential backoff. Jitter
+//! spreads retries of concurrent requests apart, and a `Retry-After` from
+//! a rate-limited reply replaces the backoff. A policy set with
+//! [`set_retry_policy`] (e.g. from the config file) takes precedence over
+//! every backend's default, like configured rate limits.
+
+use crate::TranslationError;
+use rand::Rng;
+use std::fmt;
+use std::future::Future;
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+
+/// How often and how long to wait before retrying a failed request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts including the first; at least one is always made
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for each further one
+    pub base_delay: Duration,
+    /// Longest delay between attempts, `Retry-After` included
+    pub max_delay: Duration,
+    /// Wait a random time between half and all of each delay
+    pub jitter: bool,
+    /// Wait as long as a rate-limited reply's `Retry-After` asks
+    pub honor_retry_after: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+            honor_retry_after: true,
+        }
+    }
+}
+
+impl fmt::Display for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} attempts, {}ms-{}ms backoff",
+            self.max_attempts,
+            self.base_delay.as_millis(),
+            self.max_delay.as_millis()
+        )
+    }
+}
+
+impl RetryPolicy {
+    /// Make a single attempt
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Use `max_attempts` attempts
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Delay before retrying after failed attempt number `attempt` (from 1)
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        if let Some(retry_after) = retry_after.filter(|_| self.honor_retry_after) {
+            return retry_after.min(self.max_delay);
+        }
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self.base_delay.saturating_mul(factor).min(self.max_delay);
+        if self.jitter && !delay.is_zero() {
+            delay.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+        } else {
+            delay
+        }
+    }
+
+    /// Run `operation` until it succeeds, fails for good or runs out of
+    /// attempts
+    ///
+    /// The operation gets the attempt number, from 1. Errors that would
+    /// fail the same way again are returned at once.
+    pub async fn run<T, F, Fut>(&self, mut operation: F) -> Result<T, TranslationError>
+    where
+        F: FnMut(u32) -> Fut,
+        Fut: Future<Output = Result<T, TranslationError>>,
+    {
+        let max_attempts = self.max_attempts.max(1);
+        let mut attempt = 1;
+        loop {
+            match operation(attempt).await {
+                Ok(value) => return Ok(value),
+                Err(e) if attempt < max_attempts && is_retryable(&e) => {
+                    tokio::time::sleep(self.delay(attempt, e.retry_after())).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Check if a failed request may succeed when retried
+///
+/// Client errors (bad key, unsupported pair, unknown model) would fail the
+/// same way again.
+pub fn is_retryable(error: &TranslationError) -> bool {
+    match error {
+        TranslationError::TranslationFailed {
+            error_code: Some(code),
+            ..
+        } => code.starts_with('5'),
+        TranslationError::InvalidInput(_)
+        | TranslationError::UnsupportedLanguage(_)
+        | TranslationError::BudgetExceeded(_) => false,
+        _ => true,
+    }
+}
+
+/// Delay a reply's `Retry-After` header asks for, in seconds or as a date
+pub fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let value = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?;
+    parse_retry_after(value, SystemTime::now())
+}
+
+fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    // A date in the past means "now"
+    Some(date.duration_since(now).unwrap_or(Duration::ZERO))
+}
+
+static CONFIGURED: OnceLock<RetryPolicy> = OnceLock::new();
+
+/// Use `policy` for all backends created afterwards
+pub fn set_retry_policy(policy: RetryPolicy) {
+    let _ = CONFIGURED.set(policy);
+}
+
+/// The configured policy, or a backend's `default`
+pub fn policy(default: RetryPolicy) -> RetryPolicy {
+    CONFIGURED.get().copied().unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn test_policy() -> RetryPolicy {
+        RetryPolicy {
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(1000),
+            jitter: false,
+            ..RetryPolicy::default()
+        }
+    }
+
+    #[test]
+    fn test_exponential_backoff() {
+        let policy = test_policy();
+        let delays: Vec<u128> = (1..=5).map(|a| policy.delay(a, None).as_millis()).collect();
+        assert_eq!(delays, vec![100, 200, 400, 800, 1000]);
+
+        let jittered = RetryPolicy {
+            jitter: true,
+            ..policy
+        };
+        for _ in 0..20 {
+            let delay = jittered.delay(3, None);
+            assert!(delay >= Duration::from_millis(200) && delay <= Duration::from_millis(400));
+        }
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let policy = test_policy();
+        assert_eq!(
+            policy.delay(1, Some(Duration::from_millis(700))),
+            Duration::from_millis(700)
+        );
+        // Capped by the maximum delay, and ignored when not honored
+        assert_eq!(
+            policy.delay(1, Some(Duration::from_secs(60))),
+            Duration::from_secs(1)
+        );
+        let ignoring = RetryPolicy {
+            honor_retry_after: false,
+            ..policy
+        };
+        assert_eq!(
+            ignoring.delay(1, Some(Duration::from_secs(60))),
+            Duration::from_millis(100)
+        );
+
+        let now = httpdate::parse_http_date("Wed, 21 Oct 2015 07:28:00 GMT").unwrap();
+        assert_eq!(
+            parse_retry_after(" 120 ", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:27:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[tokio::test]
+    async fn test_run_retries_transient_errors_only() {
+        let policy = RetryPolicy {
+            base_delay: Duration::from_millis(1),
+            ..test_policy()
+        };
+        let calls = AtomicU32::new(0);
+        let result = policy
+            .run(|attempt| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    if attempt < 3 {
+                        Err(TranslationError::RateLimitExceeded { retry_after: None })
+                    } else {
+                        Ok(attempt)
+                    }
+                }
+            })
+            .await;
+        assert_eq!(result.unwrap(), 3);
+
+        calls.store(0, Ordering::SeqCst);
+        let result: Result<(), _> = policy
+            .run(|_| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                async { Err(TranslationError::InvalidInput("empty".to_string())) }
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,312 @@
+//! Per-language-pair routing between translators
+
+use crate::{TranslationError, TranslationRequest, TranslationResult, Translator};
+use async_trait::async_trait;
+use std::collections::HashMap;
+
+/// A `source->target` route pattern; `*` matches any language
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    source: Option<String>,
+    target: Option<String>,
+}
+
+impl Route {
+    /// Parse a route pattern such as `zh->en`, `*->en`, `ja->*` or `*`
+    pub fn parse(pattern: &str) -> Result<Self, TranslationError> {
+        let pattern = pattern.trim();
+        if pattern == "*" {
+            return Ok(Self {
+                source: None,
+                target: None,
+            });
+        }
+
+        let (source, target) = pattern.split_once("->").ok_or_else(|| {
+            TranslationError::InvalidInput(format!(
+                "Invalid route '{}': expected 'source->target' or '*'",
+                pattern
+            ))
+        })?;
+
+        let side = |s: &str| {
+            let s = s.trim().to_lowercase();
+            (s != "*").then_some(s)
+        };
+
+        Ok(Self {
+            source: side(source),
+            target: side(target),
+        })
+    }
+
+    /// Check whether this route applies to a language pair
+    pub fn matches(&self, source: &str, target: &str) -> bool {
+        side_matches(self.source.as_deref(), source) && side_matches(self.target.as_deref(), target)
+    }
+
+    /// The pattern in canonical form, e.g. `zh->en` or `*->en`
+    pub fn pattern(&self) -> String {
+        let side = |s: &Option<String>| s.as_deref().unwrap_or("*").to_string();
+        format!("{}->{}", side(&self.source), side(&self.target))
+    }
+
+    /// Specificity used to pick the best route: exact pairs beat wildcards
+    fn specificity(&self) -> u8 {
+        u8::from(self.source.is_some()) + u8::from(self.target.is_some())
+    }
+}
+
+/// Match a language code against a route side
+///
+/// A route side without region (`zh`) matches any regional variant (`zh-CN`).
+fn side_matches(pattern: Option<&str>, language: &str) -> bool {
+    let Some(pattern) = pattern else {
+        return true;
+    };
+    let language = language.to_lowercase();
+    if pattern.contains('-') {
+        language == pattern
+    } else {
+        language.split(['-', '_']).next() == Some(pattern)
+    }
+}
+
+/// Translator that dispatches each request to a backend chosen by language pair
+pub struct RoutingTranslator {
+    routes: Vec<(Route, Box<dyn Translator>)>,
+}
+
+impl RoutingTranslator {
+    /// Create a router with no routes
+    pub fn new() -> Self {
+        Self { routes: Vec::new() }
+    }
+
+    /// Add a route for a pattern such as `zh->en` or `*`
+    pub fn with_route(
+        mut self,
+        pattern: &str,
+        translator: Box<dyn Translator>,
+    ) -> Result<Self, TranslationError> {
+        self.routes.push((Route::parse(pattern)?, translator));
+        Ok(self)
+    }
+
+    /// Number of configured routes
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    /// Check whether any routes are configured
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    /// Select the most specific translator for a language pair
+    pub fn select(&self, source: &str, target: &str) -> Option<&dyn Translator> {
+        self.routes
+            .iter()
+            .filter(|(route, _)| route.matches(source, target))
+            .max_by_key(|(route, _)| route.specificity())
+            .map(|(_, translator)| translator.as_ref())
+    }
+
+    fn select_or_err(
+        &self,
+        source: &str,
+        target: &str,
+    ) -> Result<&dyn Translator, TranslationError> {
+        self.select(source, target).ok_or_else(|| {
+            TranslationError::UnsupportedLanguage(format!("{}->{} (no route)", source, target))
+        })
+    }
+
+    /// The wildcard route, used when no language pair is known
+    fn fallback(&self) -> Option<&dyn Translator> {
+        self.routes
+            .iter()
+            .find(|(route, _)| route.specificity() == 0)
+            .map(|(_, translator)| translator.as_ref())
+    }
+}
+
+impl Default for RoutingTranslator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Translator for RoutingTranslator {
+    fn name(&self) -> &'static str {
+        "Router"
+    }
+
+    fn cache_name(&self) -> String {
+        let mut routes: Vec<String> = self
+            .routes
+            .iter()
+            .map(|(route, t)| format!("{}={}", route.pattern(), t.cache_name()))
+            .collect();
+        routes.sort();
+        format!("router({})", routes.join(","))
+    }
+
+    fn supported_languages(&self) -> Vec<String> {
+        let mut languages: Vec<String> = self
+            .routes
+            .iter()
+            .flat_map(|(_, t)| t.supported_languages())
+            .collect();
+        languages.sort();
+        languages.dedup();
+        languages
+    }
+
+    fn supports_pair(&self, source: &str, target: &str) -> bool {
+        self.select(source, target)
+            .is_some_and(|translator| translator.supports_pair(source, target))
+    }
+
+    fn validate_languages(&self, source: &str, target: &str) -> Result<(), TranslationError> {
+        self.select_or_err(source, target)?
+            .validate_languages(source, target)
+    }
+
+    async fn translate(
+        &self,
+        text: &str,
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<TranslationResult, TranslationError> {
+        let translator = self.select_or_err(source_language, target_language)?;
+        Ok(translator
+            .translate(text, source_language, target_language)
+            .await?
+            .with_metadata("route".to_string(), translator.name().to_string()))
+    }
+
+    async fn translate_batch(
+        &self,
+        texts: &[String],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        self.translate_requests(
+            &TranslationRequest::plain(texts),
+            source_language,
+            target_language,
+        )
+        .await
+    }
+
+    async fn translate_requests(
+        &self,
+        requests: &[TranslationRequest],
+        source_language: &str,
+        target_language: &str,
+    ) -> Result<Vec<TranslationResult>, TranslationError> {
+        let translator = self.select_or_err(source_language, target_language)?;
+        Ok(translator
+            .translate_requests(requests, source_language, target_language)
+            .await?
+            .into_iter()
+            .map(|r| r.with_metadata("route".to_string(), translator.name().to_string()))
+            .collect())
+    }
+
+    fn estimate_cost(&self, text: &str, source: &str, target: &str) -> f64 {
+        self.select(source, target)
+            .map_or(0.0, |t| t.estimate_cost(text, source, target))
+    }
+
+    fn estimate_requests(&self, unit_count: usize) -> usize {
+        self.fallback()
+            .map_or(unit_count, |t| t.estimate_requests(unit_count))
+    }
+
+    fn estimate_duration(&self, unit_count: usize) -> std::time::Duration {
+        self.fallback().map_or(std::time::Duration::ZERO, |t| {
+            t.estimate_duration(unit_count)
+        })
+    }
+
+    fn get_usage_info(&self) -> HashMap<String, String> {
+        let mut info = HashMap::new();
+        info.insert("name".to_string(), self.name().to_string());
+        info.insert("routes".to_string(), self.routes.len().to_string());
+        info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockConfig, MockTranslator};
+
+    fn mock() -> Box<dyn Translator> {
+        Box::new(MockTranslator::with_config(MockConfig {
+            delay_range: (0, 0),
+            ..MockConfig::default()
+        }))
+    }
+
+    #[test]
+    fn test_route_parse() {
+        assert!(Route::parse("zh->en").unwrap().matches("zh-CN", "en"));
+        assert!(!Route::parse("zh->en").unwrap().matches("ja", "en"));
+        assert!(Route::parse("*->en").unwrap().matches("ja", "en"));
+        assert!(Route::parse("*").unwrap().matches("ko", "fr"));
+        assert!(!Route::parse("zh-tw->en").unwrap().matches("zh-CN", "en"));
+        assert!(Route::parse("zh-en").is_err());
+    }
+
+    #[test]
+    fn test_cache_name_lists_routes() {
+        let router = RoutingTranslator::new()
+            .with_route("zh->en", mock())
+            .unwrap()
+            .with_route("*", mock())
+            .unwrap();
+        assert_eq!(router.cache_name(), "router(*->*=Mock,zh->en=Mock)");
+    }
+
+    #[test]
+    fn test_select_most_specific() {
+        let router = RoutingTranslator::new()
+            .with_route("*", mock())
+            .unwrap()
+            .with_route("zh->en", mock())
+            .unwrap();
+
+        let specific = router.select("zh", "en").unwrap() as *const dyn Translator;
+        let wildcard = router.select("ja", "en").unwrap() as *const dyn Translator;
+        assert!(!std::ptr::addr_eq(specific, wildcard));
+    }
+
+    #[tokio::test]
+    async fn test_translate_without_route_fails() {
+        let router = RoutingTranslator::new()
+            .with_route("zh->en", mock())
+            .unwrap();
+
+        assert!(router.translate("你好", "zh", "en").await.is_ok());
+        assert!(matches!(
+            router.translate("hallo", "de", "en").await,
+            Err(TranslationError::UnsupportedLanguage(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_translate_batch_records_route() {
+        let router = RoutingTranslator::new().with_route("*", mock()).unwrap();
+        let results = router
+            .translate_batch(&["你好".to_string()], "zh", "en")
+            .await
+            .unwrap();
+
+        let metadata = results[0].metadata.as_ref().unwrap();
+        assert_eq!(metadata.get("route"), Some(&"Mock".to_string()));
+    }
+}
@@ -0,0 +1,188 @@
+//! Text size accounting for cost estimates and usage tracking
+//!
+//! Translation APIs do not agree on what a "character" is: most bill per
+//! Unicode code point, some per UTF-16 code unit, and LLM-based services
+//! bill per token, where a CJK character costs about one token while Latin
+//! text averages about four characters per token. [`TextMetrics`] measures
+//! a text once and [`Pricing`] turns it into the unit a given API bills.
+
+use serde::{Deserialize, Serialize};
+
+/// Average Latin-script characters per LLM token
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Size of a text in the units translation APIs bill by
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextMetrics {
+    /// Unicode code points
+    pub chars: usize,
+    /// UTF-8 bytes
+    pub bytes: usize,
+    /// UTF-16 code units (characters outside the BMP count twice)
+    pub utf16_units: usize,
+    /// Code points in CJK scripts (Han, Kana, Hangul, CJK punctuation)
+    pub cjk_chars: usize,
+    /// Whitespace code points
+    pub whitespace: usize,
+}
+
+impl TextMetrics {
+    /// Measure a text
+    pub fn measure(text: &str) -> Self {
+        let mut metrics = Self {
+            bytes: text.len(),
+            ..Self::default()
+        };
+        for c in text.chars() {
+            metrics.chars += 1;
+            metrics.utf16_units += c.len_utf16();
+            if is_cjk(c) {
+                metrics.cjk_chars += 1;
+            } else if c.is_whitespace() {
+                metrics.whitespace += 1;
+            }
+        }
+        metrics
+    }
+
+    /// Add another measurement to this one
+    pub fn add(&mut self, other: &TextMetrics) {
+        self.chars += other.chars;
+        self.bytes += other.bytes;
+        self.utf16_units += other.utf16_units;
+        self.cjk_chars += other.cjk_chars;
+        self.whitespace += other.whitespace;
+    }
+
+    /// Estimated LLM tokens: one per CJK character, one per
+    /// [`CHARS_PER_TOKEN`] other characters
+    pub fn estimated_tokens(&self) -> usize {
+        self.cjk_chars + (self.chars - self.cjk_chars).div_ceil(CHARS_PER_TOKEN)
+    }
+
+    /// Number of billable units under a billing model
+    pub fn billable(&self, unit: BillingUnit) -> usize {
+        match unit {
+            BillingUnit::CodePoints => self.chars,
+            BillingUnit::Utf8Bytes => self.bytes,
+            BillingUnit::Utf16Units => self.utf16_units,
+            BillingUnit::Tokens => self.estimated_tokens(),
+        }
+    }
+}
+
+/// What a translation API counts when billing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BillingUnit {
+    /// Unicode code points (Google Cloud, DeepL)
+    CodePoints,
+    /// UTF-8 bytes
+    Utf8Bytes,
+    /// UTF-16 code units
+    Utf16Units,
+    /// Estimated LLM tokens
+    Tokens,
+}
+
+impl std::fmt::Display for BillingUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::CodePoints => "code points",
+            Self::Utf8Bytes => "UTF-8 bytes",
+            Self::Utf16Units => "UTF-16 units",
+            Self::Tokens => "tokens",
+        })
+    }
+}
+
+/// Price list of a paid translation API
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Pricing {
+    pub unit: BillingUnit,
+    /// Price in USD per million billable units
+    pub usd_per_million: f64,
+}
+
+impl Pricing {
+    /// Create a price list
+    pub fn new(unit: BillingUnit, usd_per_million: f64) -> Self {
+        Self {
+            unit,
+            usd_per_million,
+        }
+    }
+
+    /// Cost in USD of a measured text
+    pub fn cost(&self, metrics: &TextMetrics) -> f64 {
+        metrics.billable(self.unit) as f64 * self.usd_per_million / 1_000_000.0
+    }
+}
+
+/// Check if a character belongs to a CJK script
+pub fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x1100..=0x11FF       // Hangul Jamo
+        | 0x2E80..=0x2FDF     // CJK radicals
+        | 0x3000..=0x303F     // CJK symbols and punctuation
+        | 0x3040..=0x30FF     // Hiragana, Katakana
+        | 0x3130..=0x318F     // Hangul compatibility Jamo
+        | 0x31F0..=0x31FF     // Katakana phonetic extensions
+        | 0x3400..=0x4DBF     // CJK extension A
+        | 0x4E00..=0x9FFF     // CJK unified ideographs
+        | 0xAC00..=0xD7AF     // Hangul syllables
+        | 0xF900..=0xFAFF     // CJK compatibility ideographs
+        | 0xFF00..=0xFFEF     // Half/full-width forms
+        | 0x20000..=0x3134F // CJK extensions B-G
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measure_mixed_text() {
+        let metrics = TextMetrics::measure("Hello, 世界!");
+        assert_eq!(metrics.chars, 10);
+        assert_eq!(metrics.bytes, 14);
+        assert_eq!(metrics.utf16_units, 10);
+        assert_eq!(metrics.cjk_chars, 2);
+        assert_eq!(metrics.whitespace, 1);
+    }
+
+    #[test]
+    fn test_measure_outside_bmp() {
+        // U+20BB7 is a CJK extension B ideograph: 1 code point, 4 bytes, 2 UTF-16 units
+        let metrics = TextMetrics::measure("𠮷");
+        assert_eq!(metrics.chars, 1);
+        assert_eq!(metrics.bytes, 4);
+        assert_eq!(metrics.utf16_units, 2);
+        assert_eq!(metrics.cjk_chars, 1);
+    }
+
+    #[test]
+    fn test_estimated_tokens() {
+        assert_eq!(TextMetrics::measure("你好世界").estimated_tokens(), 4);
+        assert_eq!(TextMetrics::measure("hello world").estimated_tokens(), 3);
+        assert_eq!(TextMetrics::measure("").estimated_tokens(), 0);
+    }
+
+    #[test]
+    fn test_pricing() {
+        let metrics = TextMetrics::measure("你好世界");
+        let per_char = Pricing::new(BillingUnit::CodePoints, 20.0);
+        let per_byte = Pricing::new(BillingUnit::Utf8Bytes, 20.0);
+        assert!((per_char.cost(&metrics) - 0.00008).abs() < 1e-12);
+        assert!((per_byte.cost(&metrics) - 0.00024).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_add() {
+        let mut total = TextMetrics::measure("abc");
+        total.add(&TextMetrics::measure("日本"));
+        assert_eq!(total.chars, 5);
+        assert_eq!(total.cjk_chars, 2);
+        assert_eq!(total.bytes, 9);
+    }
+}
@@ -0,0 +1,246 @@
+//! Dumps of translator API calls for debugging (`--trace-api`)
+//!
+//! Once [`set_trace_dir`] is called, backends send their requests through
+//! [`send`], which writes each request with its response (or error) to a
+//! JSON file in that directory. Headers, query parameters and JSON fields
+//! named like credentials (`Authorization`, `key`, `api_key`, ...) are
+//! redacted, as is any other occurrence of their values, so traces can be
+//! attached to bug reports.
+
+use reqwest::header::HeaderMap;
+use reqwest::{Request, RequestBuilder, Response};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Replacement of redacted values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Where traces go, and the prefix of this run's files
+static TRACE: OnceLock<(PathBuf, u64)> = OnceLock::new();
+static SEQUENCE: AtomicUsize = AtomicUsize::new(0);
+
+/// Trace all API calls made from now on into `dir`, creating it if needed
+pub fn set_trace_dir(dir: PathBuf) -> std::io::Result<()> {
+    std::fs::create_dir_all(&dir)?;
+    let run = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let _ = TRACE.set((dir, run));
+    Ok(())
+}
+
+/// Directory API calls are traced into, if any
+pub fn trace_dir() -> Option<&'static Path> {
+    TRACE.get().map(|(dir, _)| dir.as_path())
+}
+
+/// Send a backend's request, tracing it when enabled
+///
+/// The response body is read for the trace and handed back unchanged.
+/// Failing to write a trace never fails the request.
+pub(crate) async fn send(backend: &str, request: RequestBuilder) -> reqwest::Result<Response> {
+    let Some((dir, run)) = TRACE.get() else {
+        return request.send().await;
+    };
+    let (client, request) = request.build_split();
+    let request = request?;
+
+    let mut secrets = Vec::new();
+    let traced_request = describe_request(&request, &mut secrets);
+    let started = Instant::now();
+    let (result, traced_response) = match client.execute(request).await {
+        Ok(response) => {
+            let status = response.status();
+            let version = response.version();
+            let headers = response.headers().clone();
+            match response.bytes().await {
+                Ok(body) => {
+                    let traced = json!({
+                        "status": status.as_u16(),
+                        "headers": describe_headers(&headers, &mut secrets),
+                        "body": describe_body(&body, &mut secrets),
+                    });
+                    let mut rebuilt = ::http::Response::new(body);
+                    *rebuilt.status_mut() = status;
+                    *rebuilt.version_mut() = version;
+                    *rebuilt.headers_mut() = headers;
+                    (Ok(Response::from(rebuilt)), traced)
+                }
+                Err(e) => {
+                    let traced = json!({ "status": status.as_u16(), "error": e.to_string() });
+                    (Err(e), traced)
+                }
+            }
+        }
+        Err(e) => {
+            let traced = json!({ "error": e.to_string() });
+            (Err(e), traced)
+        }
+    };
+
+    let trace = json!({
+        "backend": backend,
+        "duration_ms": started.elapsed().as_millis() as u64,
+        "request": traced_request,
+        "response": traced_response,
+    });
+    let sequence = SEQUENCE.fetch_add(1, Ordering::SeqCst) + 1;
+    let path = dir.join(format!("{}-{:04}-{}.json", run, sequence, backend));
+    let _ = std::fs::write(path, redact_values(&trace, &secrets));
+
+    result
+}
+
+/// Check if a header, parameter or field name holds credentials
+fn is_secret(name: &str) -> bool {
+    let name = name.to_lowercase();
+    [
+        "key",
+        "secret",
+        "token",
+        "password",
+        "auth",
+        "cookie",
+        "signature",
+    ]
+    .iter()
+    .any(|word| name.contains(word))
+}
+
+/// Remember a redacted value so other occurrences of it are redacted too
+fn remember(value: &str, secrets: &mut Vec<String>) {
+    // `Bearer sk-...`: the token alone may show up elsewhere
+    for secret in [value, value.rsplit(' ').next().unwrap_or(value)] {
+        if secret.len() >= 4 && !secrets.iter().any(|s| s == secret) {
+            secrets.push(secret.to_string());
+        }
+    }
+}
+
+fn describe_request(request: &Request, secrets: &mut Vec<String>) -> Value {
+    let mut url = request.url().clone();
+    if url.query().is_some() {
+        let pairs: Vec<(String, String)> = url
+            .query_pairs()
+            .map(|(name, value)| {
+                if is_secret(&name) {
+                    remember(&value, secrets);
+                    (name.into_owned(), REDACTED.to_string())
+                } else {
+                    (name.into_owned(), value.into_owned())
+                }
+            })
+            .collect();
+        url.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    let body = request
+        .body()
+        .and_then(|body| body.as_bytes())
+        .map_or(Value::Null, |body| describe_body(body, secrets));
+    json!({
+        "method": request.method().as_str(),
+        "url": url.as_str(),
+        "headers": describe_headers(request.headers(), secrets),
+        "body": body,
+    })
+}
+
+fn describe_headers(headers: &HeaderMap, secrets: &mut Vec<String>) -> Value {
+    let headers = headers
+        .iter()
+        .map(|(name, value)| {
+            let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+            if is_secret(name.as_str()) {
+                remember(&value, secrets);
+                (name.to_string(), Value::from(REDACTED))
+            } else {
+                (name.to_string(), Value::from(value))
+            }
+        })
+        .collect();
+    Value::Object(headers)
+}
+
+/// A body as JSON when it is JSON, with credential fields redacted, or as text
+fn describe_body(body: &[u8], secrets: &mut Vec<String>) -> Value {
+    match serde_json::from_slice::<Value>(body) {
+        Ok(mut value) => {
+            redact_fields(&mut value, secrets);
+            value
+        }
+        Err(_) => Value::from(String::from_utf8_lossy(body).into_owned()),
+    }
+}
+
+fn redact_fields(value: &mut Value, secrets: &mut Vec<String>) {
+    match value {
+        Value::Object(fields) => {
+            for (name, field) in fields.iter_mut() {
+                if is_secret(name) && !field.is_object() && !field.is_array() {
+                    if let Some(secret) = field.as_str() {
+                        remember(secret, secrets);
+                    }
+                    *field = Value::from(REDACTED);
+                } else {
+                    redact_fields(field, secrets);
+                }
+            }
+        }
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| redact_fields(item, secrets)),
+        _ => {}
+    }
+}
+
+/// The trace as pretty JSON, without any remembered secret
+fn redact_values(trace: &Value, secrets: &[String]) -> String {
+    let mut text = serde_json::to_string_pretty(trace).unwrap_or_default();
+    for secret in secrets {
+        text = text.replace(secret.as_str(), REDACTED);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redacts_credentials() {
+        let client = reqwest::Client::new();
+        let request = client
+            .post("https://api.example.com/translate?q=hello&key=abcd1234")
+            .bearer_auth("sk-secret-token")
+            .header("X-NCP-APIGW-API-KEY-ID", "client-id")
+            .json(&json!({"q": "你好", "api_key": "lt-key-5678", "options": {"auth_key": 7}}))
+            .build()
+            .unwrap();
+
+        let mut secrets = Vec::new();
+        let traced = describe_request(&request, &mut secrets);
+        assert_eq!(
+            traced["url"],
+            "https://api.example.com/translate?q=hello&key=%5BREDACTED%5D"
+        );
+        assert_eq!(traced["headers"]["authorization"], REDACTED);
+        assert_eq!(traced["headers"]["x-ncp-apigw-api-key-id"], REDACTED);
+        assert_eq!(traced["headers"]["content-type"], "application/json");
+        assert_eq!(
+            traced["body"],
+            json!({"q": "你好", "api_key": REDACTED, "options": {"auth_key": REDACTED}})
+        );
+
+        // A key echoed back elsewhere is redacted too
+        let response =
+            json!({"request": traced, "echo": "invalid key lt-key-5678 for sk-secret-token"});
+        let text = redact_values(&response, &secrets);
+        for secret in ["abcd1234", "sk-secret-token", "client-id", "lt-key-5678"] {
+            assert!(!text.contains(secret), "{} leaked", secret);
+        }
+        assert!(text.contains("你好"));
+    }
+}